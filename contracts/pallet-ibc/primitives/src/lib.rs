@@ -6,7 +6,11 @@ use alloc::string::{String, ToString};
 use codec::{Decode, Encode};
 use frame_support::{weights::Weight, RuntimeDebug};
 use ibc::{
-	applications::transfer::{error::Error as Ics20Error, PrefixedCoin, VERSION},
+	applications::{
+		fee::{error::Error as FeeError, MODULE_ID_STR as FEE_MODULE_ID, VERSION as FEE_VERSION},
+		interchain_accounts::VERSION as ICA_VERSION,
+		transfer::{error::Error as Ics20Error, PrefixedCoin, VERSION},
+	},
 	core::{
 		ics04_channel::{
 			channel::{ChannelEnd, Order},
@@ -210,6 +214,22 @@ pub struct QueryChannelResponse {
 	pub trie_key: Vec<u8>,
 }
 
+#[derive(Clone, codec::Encode, codec::Decode, PartialEq, Eq, Ord, PartialOrd)]
+pub struct QueryChannelUpgradeResponse {
+	/// Protobuf encoded `ibc::core::ics04_channel::upgrade::Upgrade`
+	pub upgrade: Vec<u8>,
+	pub height: u64,
+	pub trie_key: Vec<u8>,
+}
+
+#[derive(Clone, codec::Encode, codec::Decode, PartialEq, Eq, Ord, PartialOrd)]
+pub struct QueryChannelUpgradeErrorResponse {
+	/// Protobuf encoded `ibc::core::ics04_channel::upgrade::ErrorReceipt`
+	pub error_receipt: Vec<u8>,
+	pub height: u64,
+	pub trie_key: Vec<u8>,
+}
+
 #[derive(Clone, codec::Encode, codec::Decode, PartialEq, Eq, Ord, PartialOrd)]
 pub struct QueryChannelsResponse {
 	pub channels: Vec<IdentifiedChannel>,
@@ -503,6 +523,51 @@ pub fn get_channel_escrow_address(
 	hex_string.parse::<Signer>().map_err(Ics20Error::signer)
 }
 
+/// Returns the account that receives the tokens of a packet forwarded by the packet-forward
+/// middleware, and sends them on to the next chain.
+pub fn get_forward_intermediate_address(
+	port_id: &PortId,
+	channel_id: ChannelId,
+	sender: &Signer,
+) -> Result<Signer, Ics20Error> {
+	let contents = format!("forward/{}/{}/{}", port_id, channel_id, sender);
+	let mut data = VERSION.as_bytes().to_vec();
+	data.extend_from_slice(&[0]);
+	data.extend_from_slice(contents.as_bytes());
+
+	let hash = sp_io::hashing::sha2_256(&data).to_vec();
+	let mut hex_string = hex::encode_upper(hash);
+	hex_string.insert_str(0, "0x");
+	hex_string.parse::<Signer>().map_err(Ics20Error::signer)
+}
+
+/// Returns the account holding the ICS29 fees escrowed for packets, until they're paid to the
+/// relayers or refunded.
+pub fn get_fee_escrow_address() -> Result<Signer, FeeError> {
+	let mut data = FEE_VERSION.as_bytes().to_vec();
+	data.extend_from_slice(&[0]);
+	data.extend_from_slice(FEE_MODULE_ID.as_bytes());
+
+	let hash = sp_io::hashing::sha2_256(&data).to_vec();
+	let mut hex_string = hex::encode_upper(hash);
+	hex_string.insert_str(0, "0x");
+	hex_string.parse::<Signer>().map_err(FeeError::signer)
+}
+
+/// Returns the address of the interchain account the ICS27 host creates for a controller port on
+/// a connection.
+pub fn get_interchain_account_address(connection_id: &ConnectionId, port_id: &PortId) -> String {
+	let contents = format!("{}/{}", connection_id, port_id);
+	let mut data = ICA_VERSION.as_bytes().to_vec();
+	data.extend_from_slice(&[0]);
+	data.extend_from_slice(contents.as_bytes());
+
+	let hash = sp_io::hashing::sha2_256(&data).to_vec();
+	let mut hex_string = hex::encode_upper(hash);
+	hex_string.insert_str(0, "0x");
+	hex_string
+}
+
 // This is needed because Ics20 traits require an implementation of TryFrom<Signer> for AccountId
 // associated type
 #[derive(Clone)]
@@ -25,6 +25,7 @@ use ibc_proto::{
 				QueryNextSequenceReceiveResponse, QueryPacketAcknowledgementResponse,
 				QueryPacketAcknowledgementsResponse, QueryPacketCommitmentResponse,
 				QueryPacketCommitmentsResponse, QueryPacketReceiptResponse,
+				QueryUpgradeErrorResponse, QueryUpgradeResponse,
 			},
 			client::v1::{
 				Height, IdentifiedClientState, QueryClientStateResponse,
@@ -252,6 +253,24 @@ where
 		port_id: String,
 	) -> Result<QueryChannelResponse>;
 
+	/// Query the upgrade proposed by this chain for a channel
+	#[method(name = "ibc_queryChannelUpgrade")]
+	fn query_channel_upgrade(
+		&self,
+		height: u32,
+		channel_id: String,
+		port_id: String,
+	) -> Result<QueryUpgradeResponse>;
+
+	/// Query the error receipt of the last aborted upgrade of a channel
+	#[method(name = "ibc_queryChannelUpgradeError")]
+	fn query_channel_upgrade_error(
+		&self,
+		height: u32,
+		channel_id: String,
+		port_id: String,
+	) -> Result<QueryUpgradeErrorResponse>;
+
 	/// Query client state for channel and port id
 	#[method(name = "ibc_queryChannelClient")]
 	fn query_channel_client(
@@ -734,13 +753,69 @@ where
 			}),
 		})
 	}
-	// TODO: Unimplemented
-	fn query_upgraded_client(&self, _height: u32) -> Result<QueryClientStateResponse> {
-		Err(runtime_error_into_rpc_error("Unimplemented"))
+	fn query_upgraded_client(&self, height: u32) -> Result<QueryClientStateResponse> {
+		let api = self.client.runtime_api();
+
+		let at = BlockId::Number(height.into());
+		let para_id = api
+			.para_id(&at)
+			.map_err(|_| runtime_error_into_rpc_error("Error getting para id"))?;
+		let result: ibc_primitives::QueryClientStateResponse = api
+			.upgraded_client_state(&at)
+			.ok()
+			.flatten()
+			.ok_or_else(|| runtime_error_into_rpc_error("No upgraded client state found"))?;
+		let client_state = AnyClientState::decode_vec(&result.client_state)
+			.map_err(|_| runtime_error_into_rpc_error("Error decoding upgraded client state"))?;
+		// The upgrade paths live in the main trie, not the ibc child trie
+		let keys = vec![result.trie_key];
+		let proof = self
+			.client
+			.read_proof(&at, &mut keys.iter().map(|key| &key[..]))
+			.map_err(runtime_error_into_rpc_error)?
+			.iter_nodes()
+			.collect::<Vec<_>>()
+			.encode();
+		Ok(QueryClientStateResponse {
+			client_state: Some(client_state.into()),
+			proof,
+			proof_height: Some(ibc_proto::ibc::core::client::v1::Height {
+				revision_number: para_id.into(),
+				revision_height: result.height,
+			}),
+		})
 	}
 
-	fn query_upgraded_cons_state(&self, _height: u32) -> Result<QueryConsensusStateResponse> {
-		Err(runtime_error_into_rpc_error("Unimplemented"))
+	fn query_upgraded_cons_state(&self, height: u32) -> Result<QueryConsensusStateResponse> {
+		let api = self.client.runtime_api();
+
+		let at = BlockId::Number(height.into());
+		let para_id = api
+			.para_id(&at)
+			.map_err(|_| runtime_error_into_rpc_error("Error getting para id"))?;
+		let result: ibc_primitives::QueryConsensusStateResponse = api
+			.upgraded_consensus_state(&at)
+			.ok()
+			.flatten()
+			.ok_or_else(|| runtime_error_into_rpc_error("No upgraded consensus state found"))?;
+		let consensus_state = AnyConsensusState::decode_vec(&result.consensus_state)
+			.map_err(|_| runtime_error_into_rpc_error("Error decoding upgraded consensus state"))?;
+		let keys = vec![result.trie_key];
+		let proof = self
+			.client
+			.read_proof(&at, &mut keys.iter().map(|key| &key[..]))
+			.map_err(runtime_error_into_rpc_error)?
+			.iter_nodes()
+			.collect::<Vec<_>>()
+			.encode();
+		Ok(QueryConsensusStateResponse {
+			consensus_state: Some(consensus_state.into()),
+			proof,
+			proof_height: Some(ibc_proto::ibc::core::client::v1::Height {
+				revision_number: para_id.into(),
+				revision_height: result.height,
+			}),
+		})
 	}
 
 	fn query_clients(&self) -> Result<Vec<IdentifiedClientState>> {
@@ -973,6 +1048,91 @@ where
 		})
 	}
 
+	fn query_channel_upgrade(
+		&self,
+		height: u32,
+		channel_id: String,
+		port_id: String,
+	) -> Result<QueryUpgradeResponse> {
+		let api = self.client.runtime_api();
+
+		let at = BlockId::Number(height.into());
+		let para_id = api
+			.para_id(&at)
+			.map_err(|_| runtime_error_into_rpc_error("Error getting para id"))?;
+		let result: ibc_primitives::QueryChannelUpgradeResponse = api
+			.channel_upgrade(&at, channel_id.as_bytes().to_vec(), port_id.as_bytes().to_vec())
+			.ok()
+			.flatten()
+			.ok_or_else(|| runtime_error_into_rpc_error("Failed to fetch channel upgrade"))?;
+		let upgrade = ibc::core::ics04_channel::upgrade::Upgrade::decode_vec(&result.upgrade)
+			.map_err(|_| runtime_error_into_rpc_error("Failed to decode channel upgrade"))?;
+		let mut keys = vec![result.trie_key];
+		let child_trie_key = api
+			.child_trie_key(&at)
+			.map_err(|_| runtime_error_into_rpc_error("Failed to get child trie key"))?;
+		let child_info = ChildInfo::new_default(&child_trie_key);
+		let proof = self
+			.client
+			.read_child_proof(&at, &child_info, &mut keys.iter_mut().map(|nodes| &nodes[..]))
+			.map_err(runtime_error_into_rpc_error)?
+			.iter_nodes()
+			.collect::<Vec<_>>()
+			.encode();
+		Ok(QueryUpgradeResponse {
+			upgrade: Some(upgrade.into()),
+			proof,
+			proof_height: Some(ibc_proto::ibc::core::client::v1::Height {
+				revision_number: para_id.into(),
+				revision_height: result.height,
+			}),
+		})
+	}
+
+	fn query_channel_upgrade_error(
+		&self,
+		height: u32,
+		channel_id: String,
+		port_id: String,
+	) -> Result<QueryUpgradeErrorResponse> {
+		let api = self.client.runtime_api();
+
+		let at = BlockId::Number(height.into());
+		let para_id = api
+			.para_id(&at)
+			.map_err(|_| runtime_error_into_rpc_error("Error getting para id"))?;
+		let result: ibc_primitives::QueryChannelUpgradeErrorResponse = api
+			.channel_upgrade_error(&at, channel_id.as_bytes().to_vec(), port_id.as_bytes().to_vec())
+			.ok()
+			.flatten()
+			.ok_or_else(|| runtime_error_into_rpc_error("Failed to fetch upgrade error receipt"))?;
+		let error_receipt =
+			ibc::core::ics04_channel::upgrade::ErrorReceipt::decode_vec(&result.error_receipt)
+				.map_err(|_| {
+					runtime_error_into_rpc_error("Failed to decode upgrade error receipt")
+				})?;
+		let mut keys = vec![result.trie_key];
+		let child_trie_key = api
+			.child_trie_key(&at)
+			.map_err(|_| runtime_error_into_rpc_error("Failed to get child trie key"))?;
+		let child_info = ChildInfo::new_default(&child_trie_key);
+		let proof = self
+			.client
+			.read_child_proof(&at, &child_info, &mut keys.iter_mut().map(|nodes| &nodes[..]))
+			.map_err(runtime_error_into_rpc_error)?
+			.iter_nodes()
+			.collect::<Vec<_>>()
+			.encode();
+		Ok(QueryUpgradeErrorResponse {
+			error_receipt: Some(error_receipt.into()),
+			proof,
+			proof_height: Some(ibc_proto::ibc::core::client::v1::Height {
+				revision_number: para_id.into(),
+				revision_height: result.height,
+			}),
+		})
+	}
+
 	fn query_channel_client(
 		&self,
 		height: u32,
@@ -31,6 +31,12 @@ sp_api::decl_runtime_apis! {
 		/// Return the consensus state for the given client at a height
 		fn client_consensus_state(client_id: Vec<u8>, revision_number: u64, revision_height: u64, latest_cs: bool) -> Option<QueryConsensusStateResponse>;
 
+		/// Returns the client state written by the last `upgrade_client` call
+		fn upgraded_client_state() -> Option<QueryClientStateResponse>;
+
+		/// Returns the consensus state written by the last `upgrade_client` call
+		fn upgraded_consensus_state() -> Option<QueryConsensusStateResponse>;
+
 		/// Returns client states for all clients on chain
 		fn clients() -> Option<Vec<(Vec<u8>, Vec<u8>)>>;
 
@@ -45,6 +51,12 @@ sp_api::decl_runtime_apis! {
 
 		fn channel(channel_id: Vec<u8>, port_id: Vec<u8>) -> Option<QueryChannelResponse>;
 
+		/// Returns the upgrade this chain proposed for the channel, if one is in progress
+		fn channel_upgrade(channel_id: Vec<u8>, port_id: Vec<u8>) -> Option<QueryChannelUpgradeResponse>;
+
+		/// Returns the error receipt of the last aborted upgrade of the channel
+		fn channel_upgrade_error(channel_id: Vec<u8>, port_id: Vec<u8>) -> Option<QueryChannelUpgradeErrorResponse>;
+
 		/// Should return the client state for the client supporting this channel
 		fn channel_client(channel_id: Vec<u8>, port_id: Vec<u8>) -> Option<IdentifiedClientState>;
 
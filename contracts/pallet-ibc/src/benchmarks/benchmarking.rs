@@ -6,10 +6,15 @@ use crate::{
 	benchmarks::{
 		grandpa_benchmark_utils::{generate_finality_proof, GRANDPA_UPDATE_TIMESTAMP},
 		tendermint_benchmark_utils::*,
+		wasm_benchmark_utils::{fuel_contract, LOOP_FUEL},
 	},
 	ics20::IbcModule,
 	ics23::client_states::ClientStates,
-	light_clients::{AnyClientState, AnyConsensusState},
+	light_clients::{
+		AnyClientState, AnyConsensusState, HostFunctionsManager, WasmClientScheduleStorage,
+		WasmCodeStorage, MAX_WASM_CODE_SIZE,
+	},
+	weight::wasm_client_schedule,
 	Any, Config,
 };
 
@@ -22,8 +27,13 @@ use sp_runtime::traits::IdentifyAccount;
 
 use crate::routing::Context;
 use ibc::{
-	applications::transfer::{
-		acknowledgement::ACK_ERR_STR, packet::PacketData, Amount, Coin, PrefixedDenom, VERSION,
+	applications::{
+		interchain_accounts::{
+			context::Ics27Keeper, controller_port_id, host_port_id, metadata::Metadata,
+		},
+		transfer::{
+			acknowledgement::ACK_ERR_STR, packet::PacketData, Amount, Coin, PrefixedDenom, VERSION,
+		},
 	},
 	core::{
 		ics02_client::{
@@ -73,8 +83,12 @@ use ibc::{
 	timestamp::Timestamp,
 };
 use ibc_primitives::get_channel_escrow_address;
+use ics08_wasm::{abi::Request, executor::Schedule, HostFunctions};
 use scale_info::prelude::string::ToString;
-use sp_core::crypto::AccountId32;
+use sp_core::{
+	crypto::{AccountId32, KeyTypeId},
+	H256,
+};
 use sp_std::vec;
 use tendermint_proto::Protobuf;
 
@@ -84,6 +98,8 @@ fn assert_last_event<T: Config>(generic_event: <T as Config>::Event) {
 
 const TIMESTAMP: u64 = 1650894363;
 const MILLIS: u128 = 1_000_000;
+/// Key type of the keys signing the messages verified by the wasm client host functions.
+const WASM_CLIENT_KEY_TYPE: KeyTypeId = KeyTypeId(*b"ibcw");
 
 benchmarks! {
 	where_clause {
@@ -862,6 +878,7 @@ benchmarks! {
 			to:  MultiAddress::Raw("bob".to_string().as_bytes().to_vec()),
 			source_channel: channel_id.sequence(),
 			timeout,
+			memo: None,
 		};
 
 		<Params<T>>::put(PalletParams {
@@ -879,6 +896,59 @@ benchmarks! {
 		), (balance - amt).into());
 	}
 
+	register_interchain_account {
+		let caller: T::AccountId = whitelisted_caller();
+		let client_id = Pallet::<T>::create_client().unwrap();
+		let connection_id = ConnectionId::new(0);
+		Pallet::<T>::create_connection(client_id, connection_id.clone()).unwrap();
+		<InterchainAccountsParams<T>>::put(InterchainAccountParams {
+			controller_enabled: true,
+			host_enabled: false,
+			allow_messages: vec![],
+		});
+	}:_(RawOrigin::Signed(caller), connection_id.as_bytes().to_vec())
+	verify {
+		assert_eq!(ChannelCounter::<T>::get(), 1);
+	}
+
+	send_interchain_account_tx {
+		let caller: T::AccountId = whitelisted_caller();
+		let client_id = Pallet::<T>::create_client().unwrap();
+		let connection_id = ConnectionId::new(0);
+		Pallet::<T>::create_connection(client_id, connection_id.clone()).unwrap();
+		<InterchainAccountsParams<T>>::put(InterchainAccountParams {
+			controller_enabled: true,
+			host_enabled: false,
+			allow_messages: vec![],
+		});
+
+		// The channel of the interchain account, as left by a completed handshake
+		let port_id = controller_port_id(&ics27::interchain_account_owner::<T>(caller.clone())).unwrap();
+		let channel_id = ChannelId::new(0);
+		let counterparty = channel::Counterparty::new(host_port_id(), Some(ChannelId::new(1)));
+		let channel_end = ChannelEnd::new(
+			channel::State::Open,
+			Order::Ordered,
+			counterparty,
+			vec![connection_id.clone()],
+			Metadata::new(&connection_id, &ConnectionId::new(1)).to_version(),
+		);
+		let mut ctx = routing::Context::<T>::new();
+		ctx.store_channel((port_id.clone(), channel_id), &channel_end).unwrap();
+		ctx.store_next_sequence_send((port_id.clone(), channel_id), 1.into()).unwrap();
+		ctx.set_active_channel(&connection_id, &port_id, &channel_id);
+
+		let params = InterchainAccountTxParams {
+			connection_id: connection_id.as_bytes().to_vec(),
+			messages: vec![Any { type_url: b"/System.remark".to_vec(), value: vec![0; 32] }],
+			memo: vec![],
+			timeout: Timeout::Offset { timestamp: Some(1690894363), height: Some(2000) },
+		};
+	}:_(RawOrigin::Signed(caller), params)
+	verify {
+		assert_eq!(ctx.get_next_sequence_send(&(port_id, channel_id)).unwrap(), 2.into());
+	}
+
 	set_params {
 		let pallet_params = PalletParams {
 			send_enabled: true,
@@ -1028,6 +1098,7 @@ benchmarks! {
 			token: coin,
 			sender: Signer::from_str("alice").unwrap(),
 			receiver: Signer::from_str(&hex_string).unwrap(),
+			memo: Default::default(),
 		};
 
 		let data = serde_json::to_vec(&packet_data).unwrap();
@@ -1107,6 +1178,7 @@ benchmarks! {
 			token: coin,
 			sender: Signer::from_str(&hex_string).unwrap(),
 			receiver: Signer::from_str("alice").unwrap(),
+			memo: Default::default(),
 		};
 
 		let data = serde_json::to_vec(&packet_data).unwrap();
@@ -1186,6 +1258,7 @@ benchmarks! {
 			token: coin,
 			sender: Signer::from_str(&hex_string).unwrap(),
 			receiver: Signer::from_str("alice").unwrap(),
+			memo: Default::default(),
 		};
 
 		let data = serde_json::to_vec(&packet_data).unwrap();
@@ -1248,4 +1321,61 @@ benchmarks! {
 		let client_state = AnyClientState::decode_vec(&*client_state).unwrap();
 		assert_eq!(client_state.latest_height(), Height::new(2000, 2));
 	}
+
+	// compiling a wasm light client contract of `c` bytes
+	wasm_client_compile {
+		let c in 100..MAX_WASM_CODE_SIZE;
+		WasmClientScheduleStorage::put(wasm_client_schedule::<T>());
+		let code = fuel_contract(0, c);
+	}: {
+		ics08_wasm::executor::validate::<HostFunctionsManager>(&code).unwrap();
+	}
+
+	// a call into a wasm light client contract consuming `f` fuel
+	wasm_client_fuel {
+		let f in 0..10_000_000;
+		let code = fuel_contract(f / LOOP_FUEL, 0);
+		let code_hash = H256(sp_io::hashing::sha2_256(&code));
+		WasmCodeStorage::insert(code_hash, code);
+		WasmClientScheduleStorage::put(Schedule { fuel_limit: u32::MAX as u64, ..Default::default() });
+		let request = Request::UpdateStateOnMisbehaviour { client_state: vec![], client_message: vec![] };
+	}: {
+		ics08_wasm::executor::execute::<HostFunctionsManager>(&code_hash.0, request).unwrap();
+	}
+
+	wasm_client_ed25519_verify {
+		let public_key = sp_io::crypto::ed25519_generate(WASM_CLIENT_KEY_TYPE, None);
+		let msg = [1u8; 32];
+		let signature = sp_io::crypto::ed25519_sign(WASM_CLIENT_KEY_TYPE, &public_key, &msg).unwrap();
+	}: {
+		assert!(<HostFunctionsManager as HostFunctions>::ed25519_verify(&signature.0, &msg, &public_key.0));
+	}
+
+	wasm_client_sr25519_verify {
+		let public_key = sp_io::crypto::sr25519_generate(WASM_CLIENT_KEY_TYPE, None);
+		let msg = [1u8; 32];
+		let signature = sp_io::crypto::sr25519_sign(WASM_CLIENT_KEY_TYPE, &public_key, &msg).unwrap();
+	}: {
+		assert!(<HostFunctionsManager as HostFunctions>::sr25519_verify(&signature.0, &msg, &public_key.0));
+	}
+
+	wasm_client_secp256k1_verify {
+		let public_key = sp_io::crypto::ecdsa_generate(WASM_CLIENT_KEY_TYPE, None);
+		let msg = [1u8; 32];
+		// signed over the blake2 rather than the sha256 hash of the message, so verification tries
+		// both recovery ids as it does for an invalid signature
+		let signature = sp_io::crypto::ecdsa_sign(WASM_CLIENT_KEY_TYPE, &public_key, &msg).unwrap();
+	}: {
+		assert!(!<HostFunctionsManager as HostFunctions>::secp256k1_verify(&signature.0[..64], &msg, &public_key.0));
+	}
+
+	// hashing `n` bytes with every hash function, so the weight covers the slowest of them
+	wasm_client_hash {
+		let n in 0..MAX_WASM_CODE_SIZE;
+		let data = vec![1u8; n as usize];
+	}: {
+		<HostFunctionsManager as HostFunctions>::sha2_256(&data);
+		<HostFunctionsManager as HostFunctions>::keccak_256(&data);
+		<HostFunctionsManager as HostFunctions>::blake2b_256(&data);
+	}
 }
@@ -0,0 +1,85 @@
+use sp_std::prelude::*;
+
+/// Fuel consumed by an iteration of the loop of [`fuel_contract`], one per instruction.
+pub const LOOP_FUEL: u32 = 8;
+
+/// Builds a wasm light client contract which loops `iterations` times and then responds with
+/// `Ok(Response::Ok)`. The `call` function is padded with `padding` nops, to benchmark the
+/// compilation of large contracts.
+pub fn fuel_contract(iterations: u32, padding: u32) -> Vec<u8> {
+	let mut module = b"\0asm\x01\0\0\0".to_vec();
+	// (i32) -> i32 for `alloc` and (i32, i32) -> i64 for `call`
+	section(
+		&mut module,
+		1,
+		vec![0x02, 0x60, 0x01, 0x7f, 0x01, 0x7f, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7e],
+	);
+	section(&mut module, 3, vec![0x02, 0x00, 0x01]);
+	// a single page of memory, the response is read from its first two zeroed bytes
+	section(&mut module, 5, vec![0x01, 0x00, 0x01]);
+	let mut exports = vec![0x03];
+	for (name, kind, index) in [("memory", 0x02, 0x00), ("alloc", 0x00, 0x00), ("call", 0x00, 0x01)]
+	{
+		exports.push(name.len() as u8);
+		exports.extend_from_slice(name.as_bytes());
+		exports.extend_from_slice(&[kind, index]);
+	}
+	section(&mut module, 7, exports);
+
+	// `alloc` places the request after the response
+	let alloc = vec![0x00, 0x41, 0x80, 0x08, 0x0b];
+	// a single i32 local counting the iterations down
+	let mut call = vec![0x01, 0x01, 0x7f];
+	call.resize(call.len() + padding as usize, 0x01);
+	call.push(0x41);
+	signed_leb128(&mut call, iterations as i32);
+	call.extend_from_slice(&[
+		0x21, 0x02, // local.set 2
+		0x02, 0x40, // block
+		0x03, 0x40, // loop
+		0x20, 0x02, 0x45, 0x0d, 0x01, // br_if 1 (i32.eqz (local.get 2))
+		0x20, 0x02, 0x41, 0x01, 0x6b, 0x21, 0x02, // local.set 2 (i32.sub (local.get 2) 1)
+		0x0c, 0x00, // br 0
+		0x0b, 0x0b, // end loop, end block
+		0x42, 0x02, // i64.const 2, the response at 0 with a length of 2
+		0x0b,
+	]);
+	let mut code = vec![0x02];
+	for body in [alloc, call] {
+		leb128(&mut code, body.len() as u32);
+		code.extend(body);
+	}
+	section(&mut module, 10, code);
+	module
+}
+
+fn section(module: &mut Vec<u8>, id: u8, contents: Vec<u8>) {
+	module.push(id);
+	leb128(module, contents.len() as u32);
+	module.extend(contents);
+}
+
+fn leb128(out: &mut Vec<u8>, mut value: u32) {
+	loop {
+		let byte = (value & 0x7f) as u8;
+		value >>= 7;
+		if value == 0 {
+			out.push(byte);
+			return
+		}
+		out.push(byte | 0x80);
+	}
+}
+
+fn signed_leb128(out: &mut Vec<u8>, value: i32) {
+	let mut value = value as i64;
+	loop {
+		let byte = (value & 0x7f) as u8;
+		value >>= 7;
+		if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+			out.push(byte);
+			return
+		}
+		out.push(byte | 0x80);
+	}
+}
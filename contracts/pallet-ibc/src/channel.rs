@@ -7,9 +7,14 @@ use sp_core::crypto::AccountId32;
 
 use crate::{
 	ics23::{
-		acknowledgements::Acknowledgements, channels::Channels, next_seq_ack::NextSequenceAck,
-		next_seq_recv::NextSequenceRecv, next_seq_send::NextSequenceSend,
-		packet_commitments::PacketCommitment, receipts::PacketReceipt,
+		acknowledgements::Acknowledgements,
+		channel_upgrades::{ChannelUpgradeErrors, ChannelUpgrades, CounterpartyUpgrades},
+		channels::Channels,
+		next_seq_ack::NextSequenceAck,
+		next_seq_recv::NextSequenceRecv,
+		next_seq_send::NextSequenceSend,
+		packet_commitments::PacketCommitment,
+		receipts::PacketReceipt,
 	},
 	impls::host_height,
 	routing::Context,
@@ -22,6 +27,7 @@ use ibc::{
 			context::{ChannelKeeper, ChannelReader},
 			error::Error as ICS04Error,
 			packet::{Receipt, Sequence},
+			upgrade::{ErrorReceipt, Upgrade},
 		},
 		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
 	},
@@ -194,6 +200,59 @@ where
 		}
 	}
 
+	fn packet_commitment_sequences(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Vec<Sequence>, ICS04Error> {
+		Ok(<PacketCommitment<T>>::sequences(port_channel_id.0.clone(), port_channel_id.1).collect())
+	}
+
+	fn channel_upgrade(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Upgrade, ICS04Error> {
+		let data = <ChannelUpgrades<T>>::get(port_channel_id.0.clone(), port_channel_id.1)
+			.ok_or_else(|| {
+				ICS04Error::upgrade_not_found(port_channel_id.0.clone(), port_channel_id.1)
+			})?;
+		Upgrade::decode_vec(&data).map_err(|e| {
+			ICS04Error::implementation_specific(format!(
+				"[channel_upgrade]: error decoding upgrade: {}",
+				e
+			))
+		})
+	}
+
+	fn channel_counterparty_upgrade(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Upgrade, ICS04Error> {
+		let data = <CounterpartyUpgrades<T>>::get(port_channel_id.0.clone(), port_channel_id.1)
+			.ok_or_else(|| {
+				ICS04Error::upgrade_not_found(port_channel_id.0.clone(), port_channel_id.1)
+			})?;
+		Upgrade::decode_vec(&data).map_err(|e| {
+			ICS04Error::implementation_specific(format!(
+				"[channel_counterparty_upgrade]: error decoding upgrade: {}",
+				e
+			))
+		})
+	}
+
+	fn channel_upgrade_error(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<ErrorReceipt, ICS04Error> {
+		let data = <ChannelUpgradeErrors<T>>::get(port_channel_id.0.clone(), port_channel_id.1)
+			.ok_or_else(ICS04Error::missing_error_receipt)?;
+		ErrorReceipt::decode_vec(&data).map_err(|e| {
+			ICS04Error::implementation_specific(format!(
+				"[channel_upgrade_error]: error decoding error receipt: {}",
+				e
+			))
+		})
+	}
+
 	/// A hashing function for packet commitments
 	fn hash(&self, value: Vec<u8>) -> Vec<u8> {
 		sp_io::hashing::sha2_256(&value).to_vec()
@@ -458,6 +517,49 @@ where
 		Ok(())
 	}
 
+	fn store_channel_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		upgrade: Upgrade,
+	) -> Result<(), ICS04Error> {
+		<ChannelUpgrades<T>>::insert(port_channel_id.0, port_channel_id.1, upgrade);
+		Ok(())
+	}
+
+	fn delete_channel_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+	) -> Result<(), ICS04Error> {
+		<ChannelUpgrades<T>>::remove(port_channel_id.0, port_channel_id.1);
+		Ok(())
+	}
+
+	fn store_channel_counterparty_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		upgrade: Upgrade,
+	) -> Result<(), ICS04Error> {
+		<CounterpartyUpgrades<T>>::insert(port_channel_id.0, port_channel_id.1, upgrade);
+		Ok(())
+	}
+
+	fn delete_channel_counterparty_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+	) -> Result<(), ICS04Error> {
+		<CounterpartyUpgrades<T>>::remove(port_channel_id.0, port_channel_id.1);
+		Ok(())
+	}
+
+	fn store_channel_upgrade_error(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		error_receipt: ErrorReceipt,
+	) -> Result<(), ICS04Error> {
+		<ChannelUpgradeErrors<T>>::insert(port_channel_id.0, port_channel_id.1, error_receipt);
+		Ok(())
+	}
+
 	/// Called upon channel identifier creation (Init or Try message processing).
 	/// Increases the counter which keeps track of how many channels have been created.
 	/// Should never fail.
@@ -8,7 +8,7 @@ use ibc::{
 		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
 		ics26_routing::{context::ModuleId, error::Error as RoutingError},
 	},
-	events::{IbcEvent as RawIbcEvent, ModuleEvent},
+	events::{IbcEvent as RawIbcEvent, IbcEventType, ModuleEvent},
 	timestamp::Timestamp,
 	Height,
 };
@@ -214,6 +214,17 @@ pub enum IbcEvent {
 	ChainError,
 	/// App module
 	AppModule { kind: Vec<u8>, module_id: Vec<u8> },
+	/// Channel upgrade handshake step, `kind` is the ibc event type of the step
+	ChannelUpgrade {
+		kind: Vec<u8>,
+		revision_height: u64,
+		revision_number: u64,
+		port_id: Vec<u8>,
+		channel_id: Vec<u8>,
+		counterparty_port_id: Vec<u8>,
+		counterparty_channel_id: Option<Vec<u8>>,
+		upgrade_sequence: u64,
+	},
 }
 
 impl From<RawIbcEvent> for IbcEvent {
@@ -433,6 +444,26 @@ impl From<RawIbcEvent> for IbcEvent {
 				kind: ev.kind.as_bytes().to_vec(),
 				module_id: ev.module_name.to_string().as_bytes().to_vec(),
 			},
+			RawIbcEvent::UpgradeInitChannel(ref ev) |
+			RawIbcEvent::UpgradeTryChannel(ref ev) |
+			RawIbcEvent::UpgradeAckChannel(ref ev) |
+			RawIbcEvent::UpgradeConfirmChannel(ref ev) |
+			RawIbcEvent::UpgradeOpenChannel(ref ev) |
+			RawIbcEvent::UpgradeTimeoutChannel(ref ev) |
+			RawIbcEvent::UpgradeCancelChannel(ref ev) |
+			RawIbcEvent::UpgradeErrorChannel(ref ev) |
+			RawIbcEvent::FlushCompleteChannel(ref ev) => IbcEvent::ChannelUpgrade {
+				kind: event.event_type().as_str().as_bytes().to_vec(),
+				revision_height: ev.height().revision_height,
+				revision_number: ev.height().revision_number,
+				port_id: ev.port_id.as_bytes().to_vec(),
+				channel_id: ev.channel_id.to_string().as_bytes().to_vec(),
+				counterparty_port_id: ev.counterparty_port_id.as_bytes().to_vec(),
+				counterparty_channel_id: ev
+					.counterparty_channel_id
+					.map(|val| val.to_string().as_bytes().to_vec()),
+				upgrade_sequence: ev.upgrade_sequence,
+			},
 		}
 	}
 }
@@ -1039,6 +1070,40 @@ impl TryFrom<IbcEvent> for RawIbcEvent {
 				.map_err(|_| ERROR_STR)?,
 				attributes: Default::default(),
 			})),
+			IbcEvent::ChannelUpgrade {
+				kind,
+				revision_height,
+				revision_number,
+				port_id,
+				channel_id,
+				counterparty_port_id,
+				counterparty_channel_id,
+				upgrade_sequence,
+			} => {
+				let kind = IbcEventType::from_str(&String::from_utf8(kind).map_err(|_| ERROR_STR)?)
+					.map_err(|_| ERROR_STR)?;
+				ChannelEvents::UpgradeAttributes {
+					height: Height::new(revision_number, revision_height),
+					port_id: PortId::from_str(&String::from_utf8(port_id).map_err(|_| ERROR_STR)?)
+						.map_err(|_| ERROR_STR)?,
+					channel_id: ChannelId::from_str(
+						&String::from_utf8(channel_id).map_err(|_| ERROR_STR)?,
+					)
+					.map_err(|_| ERROR_STR)?,
+					counterparty_port_id: PortId::from_str(
+						&String::from_utf8(counterparty_port_id).map_err(|_| ERROR_STR)?,
+					)
+					.map_err(|_| ERROR_STR)?,
+					counterparty_channel_id: counterparty_channel_id.and_then(|channel_id| {
+						String::from_utf8(channel_id)
+							.ok()
+							.and_then(|channel_id| ChannelId::from_str(&channel_id).ok())
+					}),
+					upgrade_sequence,
+				}
+				.into_ibc_event(kind)
+				.ok_or(ERROR_STR)
+			},
 		}
 	}
 }
@@ -0,0 +1,487 @@
+use super::{full_ibc_denom, IbcModule};
+use crate::{routing::Context, Config, EscrowAddresses, Event, ForwardedPackets, Pallet};
+use alloc::{format, string::ToString};
+use core::{fmt::Formatter, str::FromStr};
+use frame_support::storage::{with_transaction, TransactionOutcome};
+use ibc::{
+	applications::transfer::{
+		acknowledgement::{Acknowledgement as Ics20Acknowledgement, ACK_ERR_STR, ACK_SUCCESS_B64},
+		context::{BankKeeper, Ics20Reader},
+		error::Error as Ics20Error,
+		forward::ForwardMetadata,
+		is_receiver_chain_source, is_sender_chain_source,
+		msgs::transfer::MsgTransfer,
+		packet::PacketData,
+		relay::on_recv_packet::process_recv_packet,
+		PrefixedCoin, PrefixedDenom,
+	},
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			context::ChannelReader,
+			error::Error as Ics04Error,
+			msgs::acknowledgement::Acknowledgement,
+			packet::Packet,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleCallbackContext, ModuleOutputBuilder},
+	},
+	signer::Signer,
+	Height,
+};
+use ibc_primitives::{get_channel_escrow_address, get_forward_intermediate_address, IbcHandler};
+use sp_core::crypto::AccountId32;
+use sp_runtime::{traits::IdentifyAccount, DispatchError};
+use tendermint_proto::Protobuf;
+
+/// Returns the packet data of an ICS20 packet whose memo holds a forward instruction, along with
+/// the parsed instruction.
+pub fn forward_metadata(
+	packet: &Packet,
+) -> Option<(PacketData, Result<ForwardMetadata, Ics20Error>)> {
+	let packet_data: PacketData = serde_json::from_slice(packet.data.as_slice()).ok()?;
+	let forward = ForwardMetadata::from_memo(&packet_data.memo).transpose()?;
+	Some((packet_data, forward))
+}
+
+/// Packet-forward middleware of the transfer module.
+///
+/// The tokens of packets whose memo holds a forward instruction are received by an intermediate
+/// account and sent on to the next chain. The acknowledgement of the received packet is only
+/// written once the forwarded packet is acknowledged or times out, so that a failure anywhere
+/// along the route refunds the original sender. Other packets are passed through to the transfer
+/// module.
+#[derive(Clone, Eq, PartialEq)]
+pub struct ForwardMiddleware<T: Config> {
+	inner: IbcModule<T>,
+}
+
+impl<T: Config> core::fmt::Debug for ForwardMiddleware<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("ForwardMiddleware").field("inner", &self.inner).finish()
+	}
+}
+
+impl<T: Config> Default for ForwardMiddleware<T> {
+	fn default() -> Self {
+		Self { inner: IbcModule::default() }
+	}
+}
+
+impl<T: Config + Send + Sync> Module for ForwardMiddleware<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<<T as frame_system::Config>::AccountId>,
+{
+	fn on_chan_open_init(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_init(
+			ctx,
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+			relayer,
+		)
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		counterparty_version: &Version,
+		relayer: &Signer,
+	) -> Result<Version, Ics04Error> {
+		self.inner.on_chan_open_try(
+			ctx,
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			version,
+			counterparty_version,
+			relayer,
+		)
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner
+			.on_chan_open_ack(ctx, output, port_id, channel_id, counterparty_version, relayer)
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_confirm(ctx, output, port_id, channel_id, relayer)
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_init(ctx, output, port_id, channel_id, relayer)
+	}
+
+	fn on_chan_close_confirm(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_confirm(ctx, output, port_id, channel_id, relayer)
+	}
+
+	fn on_chan_upgrade_init(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		proposed_order: Order,
+		proposed_connection_hops: &[ConnectionId],
+		proposed_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		self.inner.on_chan_upgrade_init(
+			ctx,
+			output,
+			port_id,
+			channel_id,
+			proposed_order,
+			proposed_connection_hops,
+			proposed_version,
+		)
+	}
+
+	fn on_chan_upgrade_try(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		proposed_order: Order,
+		proposed_connection_hops: &[ConnectionId],
+		proposed_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		self.inner.on_chan_upgrade_try(
+			ctx,
+			output,
+			port_id,
+			channel_id,
+			proposed_order,
+			proposed_connection_hops,
+			proposed_version,
+		)
+	}
+
+	fn on_chan_upgrade_ack(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		self.inner
+			.on_chan_upgrade_ack(ctx, output, port_id, channel_id, counterparty_version)
+	}
+
+	fn on_chan_upgrade_open(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		version: &Version,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_upgrade_open(
+			ctx,
+			output,
+			port_id,
+			channel_id,
+			order,
+			connection_hops,
+			version,
+		)
+	}
+
+	fn on_recv_packet(
+		&self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let (packet_data, forward) = match forward_metadata(packet) {
+			Some(forward) => forward,
+			None => return self.inner.on_recv_packet(ctx, output, packet, relayer),
+		};
+		// the tokens are only received if they can be forwarded
+		let result = forward
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+			.and_then(|forward| {
+				with_transaction(|| {
+					let mut forward_output = ModuleOutputBuilder::new();
+					match forward_packet::<T>(&mut forward_output, packet, packet_data, forward) {
+						Ok(()) => TransactionOutcome::Commit(Ok(Ok(forward_output))),
+						Err(e) => TransactionOutcome::Rollback(Ok(Err(e))),
+					}
+				})
+				.map_err(|e: DispatchError| {
+					Ics04Error::implementation_specific(format!("{:?}", e))
+				})?
+			});
+		match result {
+			Ok(forward_output) => output.merge(forward_output),
+			Err(err) => {
+				log::trace!(target: "pallet_ibc", "[on_recv_packet]: forward failed {:?}", err);
+				Pallet::<T>::write_acknowledgement(
+					packet,
+					format!("{}: {:?}", ACK_ERR_STR, err).as_bytes().to_vec(),
+				)
+				.map_err(|e| {
+					Ics04Error::implementation_specific(format!("[on_recv_packet] {:#?}", e))
+				})?;
+			},
+		}
+		Ok(())
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &Acknowledgement,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner
+			.on_acknowledgement_packet(ctx, output, packet, acknowledgement, relayer)?;
+		let success = acknowledgement.as_ref() == ACK_SUCCESS_B64;
+		acknowledge_forwarded_packet::<T>(packet, success)
+	}
+
+	fn on_timeout_packet(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_timeout_packet(ctx, output, packet, relayer)?;
+		acknowledge_forwarded_packet::<T>(packet, false)
+	}
+}
+
+/// Receives the tokens of the packet in the intermediate account, and sends them on to the next
+/// chain. The packet is recorded against the sequence of the forwarded packet.
+fn forward_packet<T: Config + Send + Sync>(
+	output: &mut ModuleOutputBuilder,
+	packet: &Packet,
+	mut packet_data: PacketData,
+	forward: ForwardMetadata,
+) -> Result<(), Ics04Error>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<<T as frame_system::Config>::AccountId>,
+{
+	let mut ctx = Context::<T>::default();
+	let intermediate = get_forward_intermediate_address(
+		&packet.destination_port,
+		packet.destination_channel,
+		&packet_data.sender,
+	)
+	.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+	packet_data.receiver = intermediate.clone();
+	process_recv_packet(&mut ctx, output, packet, packet_data.clone())
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+
+	let port_id = forward
+		.port_id()
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+	let channel_id = forward
+		.channel_id()
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+	let receiver = Signer::from_str(&forward.receiver)
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+	let denom = full_ibc_denom(packet, packet_data.token.clone());
+	let token = PrefixedCoin {
+		denom: PrefixedDenom::from_str(&denom)
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?,
+		amount: packet_data.token.amount,
+	};
+	let (_, latest_timestamp) = Pallet::<T>::latest_height_and_timestamp(&port_id, &channel_id)
+		.map_err(|e| Ics04Error::implementation_specific(format!("{:?}", e)))?;
+	let timeout_timestamp = (latest_timestamp + forward.timeout())
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+
+	if is_sender_chain_source(port_id.clone(), channel_id, &token.denom) {
+		let escrow_address = get_channel_escrow_address(&port_id, channel_id)
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+		let account_id = T::AccountIdConversion::try_from(escrow_address)
+			.map_err(|_| {
+				Ics04Error::implementation_specific(
+					"Failed to derive channel escrow address".to_string(),
+				)
+			})?
+			.into_account();
+		let _ = EscrowAddresses::<T>::try_mutate::<_, (), _>(|addresses| {
+			addresses.insert(account_id);
+			Ok(())
+		});
+	}
+
+	let sequence = ctx.get_next_sequence_send(&(port_id.clone(), channel_id))?;
+	let msg = MsgTransfer {
+		source_port: port_id.clone(),
+		source_channel: channel_id,
+		token: token.clone(),
+		sender: intermediate,
+		receiver,
+		timeout_height: Height::zero(),
+		timeout_timestamp,
+		memo: forward.next_memo(),
+	};
+	Pallet::<T>::send_transfer(msg)
+		.map_err(|e| Ics04Error::implementation_specific(format!("{:?}", e)))?;
+	ForwardedPackets::<T>::insert(
+		(port_id.as_bytes().to_vec(), channel_id.to_string().as_bytes().to_vec()),
+		u64::from(sequence),
+		packet.clone().encode_vec(),
+	);
+
+	Pallet::<T>::deposit_event(Event::<T>::TokenForwarded {
+		to: forward.receiver.as_bytes().to_vec(),
+		ibc_denom: denom.as_bytes().to_vec(),
+		amount: token.amount.as_u256().as_u128().into(),
+		source_channel: packet.destination_channel.to_string().as_bytes().to_vec(),
+		destination_channel: channel_id.to_string().as_bytes().to_vec(),
+		sequence: sequence.into(),
+	});
+	Ok(())
+}
+
+/// Writes the acknowledgement of the packet forwarded by `packet`, if any. When forwarding failed
+/// the tokens that were refunded to the intermediate account are returned to where they were
+/// received from, for the counterparty to refund the original sender.
+fn acknowledge_forwarded_packet<T: Config + Send + Sync>(
+	packet: &Packet,
+	success: bool,
+) -> Result<(), Ics04Error>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<<T as frame_system::Config>::AccountId>,
+{
+	let key = (
+		packet.source_port.as_bytes().to_vec(),
+		packet.source_channel.to_string().as_bytes().to_vec(),
+	);
+	let sequence = u64::from(packet.sequence);
+	let original = match ForwardedPackets::<T>::take(key, sequence) {
+		Some(original) => Packet::decode_vec(&original)
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?,
+		None => return Ok(()),
+	};
+
+	let ack = if success {
+		Ics20Acknowledgement::success()
+	} else {
+		let packet_data: PacketData =
+			serde_json::from_slice(original.data.as_slice()).map_err(|e| {
+				Ics04Error::implementation_specific(format!("Failed to decode packet data {:?}", e))
+			})?;
+		revert_forwarded_receipt::<T>(&original, &packet_data)
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+		Ics20Acknowledgement::from_error(Ics20Error::implementation_specific(format!(
+			"forwarding packet {} on {}/{} failed",
+			packet.sequence, packet.source_port, packet.source_channel
+		)))
+	};
+	Pallet::<T>::write_acknowledgement(&original, ack.as_ref().to_vec()).map_err(|e| {
+		Ics04Error::implementation_specific(format!("[acknowledge_forwarded_packet] {:#?}", e))
+	})?;
+	Pallet::<T>::deposit_event(Event::<T>::ForwardedPacketAcknowledged {
+		port_id: packet.source_port.as_bytes().to_vec(),
+		channel_id: packet.source_channel.to_string().as_bytes().to_vec(),
+		sequence,
+		success,
+	});
+	Ok(())
+}
+
+/// Undoes the receipt of the tokens of a packet whose forwarding failed: vouchers minted for the
+/// intermediate account are burnt, and unescrowed tokens are escrowed again.
+fn revert_forwarded_receipt<T: Config + Send + Sync>(
+	packet: &Packet,
+	packet_data: &PacketData,
+) -> Result<(), Ics20Error>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<<T as frame_system::Config>::AccountId>,
+{
+	let mut ctx = Context::<T>::default();
+	let intermediate = get_forward_intermediate_address(
+		&packet.destination_port,
+		packet.destination_channel,
+		&packet_data.sender,
+	)?;
+	let account = T::AccountIdConversion::try_from(intermediate)
+		.map_err(|_| Ics20Error::parse_account_failure())?;
+	let coin = PrefixedCoin {
+		denom: PrefixedDenom::from_str(&full_ibc_denom(packet, packet_data.token.clone()))?,
+		amount: packet_data.token.amount,
+	};
+	if is_receiver_chain_source(
+		packet.source_port.clone(),
+		packet.source_channel,
+		&packet_data.token.denom,
+	) {
+		let escrow_address =
+			ctx.get_channel_escrow_address(&packet.destination_port, packet.destination_channel)?;
+		ctx.send_coins(&account, &escrow_address, &coin)
+	} else {
+		ctx.burn_coins(&account, &coin)
+	}
+}
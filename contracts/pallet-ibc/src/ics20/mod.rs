@@ -1,4 +1,5 @@
 pub mod context;
+pub mod forward;
 
 use crate::{routing::Context, ChannelIds, Config, DenomToAssetId, Event, Pallet, WeightInfo};
 use alloc::{
@@ -12,7 +13,8 @@ use ibc::{
 		acknowledgement::{Acknowledgement as Ics20Acknowledgement, ACK_ERR_STR, ACK_SUCCESS_B64},
 		context::{
 			on_chan_close_confirm, on_chan_close_init, on_chan_open_ack, on_chan_open_confirm,
-			on_chan_open_init, on_chan_open_try,
+			on_chan_open_init, on_chan_open_try, on_chan_upgrade_ack, on_chan_upgrade_init,
+			on_chan_upgrade_try,
 		},
 		is_receiver_chain_source, is_sender_chain_source,
 		packet::PacketData,
@@ -196,6 +198,65 @@ where
 			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
 	}
 
+	fn on_chan_upgrade_init(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		proposed_order: Order,
+		proposed_connection_hops: &[ConnectionId],
+		proposed_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		let mut ctx = Context::<T>::default();
+		on_chan_upgrade_init(
+			&mut ctx,
+			output,
+			port_id,
+			channel_id,
+			proposed_order,
+			proposed_connection_hops,
+			proposed_version,
+		)
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_chan_upgrade_try(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		proposed_order: Order,
+		proposed_connection_hops: &[ConnectionId],
+		proposed_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		let mut ctx = Context::<T>::default();
+		on_chan_upgrade_try(
+			&mut ctx,
+			output,
+			port_id,
+			channel_id,
+			proposed_order,
+			proposed_connection_hops,
+			proposed_version,
+		)
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_chan_upgrade_ack(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		let mut ctx = Context::<T>::default();
+		on_chan_upgrade_ack(&mut ctx, output, port_id, channel_id, counterparty_version)
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
 	fn on_recv_packet(
 		&self,
 		_ctx: &dyn ModuleCallbackContext,
@@ -377,8 +438,14 @@ impl<T: Config> CallbackWeight for WeightHandler<T> {
 		<T as Config>::WeightInfo::on_chan_close_confirm()
 	}
 
-	fn on_recv_packet(&self, _packet: &Packet) -> Weight {
-		<T as Config>::WeightInfo::on_recv_packet()
+	fn on_recv_packet(&self, packet: &Packet) -> Weight {
+		let weight = <T as Config>::WeightInfo::on_recv_packet();
+		// forwarded tokens are sent on in the same call
+		if forward::forward_metadata(packet).is_some() {
+			weight.saturating_add(<T as Config>::WeightInfo::transfer())
+		} else {
+			weight
+		}
 	}
 
 	fn on_acknowledgement_packet(
@@ -5,6 +5,7 @@
 //! just to recalculate its root hash.
 
 pub mod acknowledgements;
+pub mod channel_upgrades;
 pub mod channels;
 pub mod client_states;
 pub mod clients;
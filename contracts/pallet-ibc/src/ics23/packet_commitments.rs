@@ -52,6 +52,17 @@ impl<T: Config> PacketCommitment<T> {
 		child::exists(&ChildInfo::new_default(T::PALLET_PREFIX), &commitment_key)
 	}
 
+	/// Returns the sequences of all packets of the given channel that still have a commitment.
+	/// The cost is proportional to the number of in-flight packets on the channel.
+	pub fn sequences(port_id: PortId, channel_id: ChannelId) -> impl Iterator<Item = Sequence> {
+		let prefix = format!("commitments/ports/{}/channels/{}/sequences/", port_id, channel_id);
+		let prefix_key = apply_prefix(T::PALLET_PREFIX, vec![prefix]);
+		ChildTriePrefixIterator::with_prefix(&ChildInfo::new_default(T::PALLET_PREFIX), &prefix_key)
+			.filter_map(|(remaining_key, _)| {
+				Sequence::from_str(&String::from_utf8(remaining_key).ok()?).ok()
+			})
+	}
+
 	// WARNING: too expensive to be called from an on-chain context, only here for rpc layer.
 	pub fn iter() -> impl Iterator<Item = ((PortId, ChannelId, Sequence), Vec<u8>)> {
 		let prefix = "commitments/ports/".to_string();
@@ -0,0 +1,154 @@
+use super::super::*;
+use crate::routing::Context;
+use frame_support::{
+	dispatch::{Dispatchable, GetCallMetadata},
+	storage::{with_transaction, TransactionOutcome},
+};
+use ibc::{
+	applications::interchain_accounts::{
+		context::{Ics27Context, Ics27Keeper, Ics27Reader},
+		error::Error as Ics27Error,
+	},
+	core::ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+	signer::Signer,
+};
+use ibc_primitives::get_interchain_account_address;
+use ibc_proto::google::protobuf::Any;
+use sp_core::crypto::AccountId32;
+use sp_runtime::{traits::IdentifyAccount, DispatchError};
+
+impl<T: Config + Send + Sync> Ics27Reader for Context<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<T::AccountId>,
+{
+	fn is_host_enabled(&self) -> bool {
+		InterchainAccountsParams::<T>::get().host_enabled
+	}
+
+	fn is_controller_enabled(&self) -> bool {
+		InterchainAccountsParams::<T>::get().controller_enabled
+	}
+
+	fn is_message_allowed(&self, type_url: &str) -> bool {
+		super::is_message_allowed::<T>(type_url)
+	}
+
+	fn get_active_channel(
+		&self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+	) -> Option<ChannelId> {
+		let channel_id = ActiveChannels::<T>::get(
+			connection_id.as_bytes().to_vec(),
+			port_id.as_bytes().to_vec(),
+		)?;
+		ChannelId::from_str(&String::from_utf8(channel_id).ok()?).ok()
+	}
+
+	fn get_interchain_account(
+		&self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+	) -> Option<String> {
+		let address = InterchainAccounts::<T>::get(
+			connection_id.as_bytes().to_vec(),
+			port_id.as_bytes().to_vec(),
+		)?;
+		String::from_utf8(address).ok()
+	}
+
+	fn generate_address(
+		&self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+	) -> Result<String, Ics27Error> {
+		Ok(get_interchain_account_address(connection_id, port_id))
+	}
+}
+
+impl<T: Config + Send + Sync> Ics27Keeper for Context<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<T::AccountId>,
+{
+	fn set_active_channel(
+		&mut self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) {
+		ActiveChannels::<T>::insert(
+			connection_id.as_bytes().to_vec(),
+			port_id.as_bytes().to_vec(),
+			channel_id.to_string().as_bytes().to_vec(),
+		)
+	}
+
+	fn register_interchain_account(
+		&mut self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+		address: &str,
+	) {
+		InterchainAccounts::<T>::insert(
+			connection_id.as_bytes().to_vec(),
+			port_id.as_bytes().to_vec(),
+			address.as_bytes().to_vec(),
+		)
+	}
+
+	/// Messages are SCALE encoded runtime calls, with a `/{pallet}.{call}` type url, dispatched
+	/// with the interchain account as signed origin. The result is the SCALE encoded list of
+	/// their post dispatch info.
+	fn execute_tx(&mut self, address: &str, messages: Vec<Any>) -> Result<Vec<u8>, Ics27Error> {
+		let signer = Signer::from_str(address).map_err(|_| {
+			Ics27Error::implementation_specific(format!("Invalid interchain account {}", address))
+		})?;
+		let account = T::AccountIdConversion::try_from(signer)
+			.map_err(|_| {
+				Ics27Error::implementation_specific(format!(
+					"Invalid interchain account {}",
+					address
+				))
+			})?
+			.into_account();
+		let mut calls = vec![];
+		for message in messages {
+			let call = super::decode_call::<T>(&message.value).map_err(|e| {
+				Ics27Error::implementation_specific(format!("Failed to decode call {:?}", e))
+			})?;
+			let metadata = call.get_call_metadata();
+			let type_url = format!("/{}.{}", metadata.pallet_name, metadata.function_name);
+			if type_url != message.type_url {
+				return Err(Ics27Error::implementation_specific(format!(
+					"Call {} doesn't match type url {}",
+					type_url, message.type_url
+				)))
+			}
+			calls.push(call);
+		}
+
+		with_transaction(|| {
+			let mut results = vec![];
+			for call in calls {
+				match call.dispatch(frame_system::RawOrigin::Signed(account.clone()).into()) {
+					Ok(info) => results.push(info),
+					Err(e) => return TransactionOutcome::Rollback(Err(e.error)),
+				}
+			}
+			TransactionOutcome::Commit(Ok(results.encode()))
+		})
+		.map_err(|e: DispatchError| {
+			log::trace!(target: "pallet_ibc", "[execute_tx]: {:?}", e);
+			Ics27Error::implementation_specific(format!("Failed to execute call {:?}", e))
+		})
+	}
+}
+
+impl<T: Config + Send + Sync> Ics27Context for Context<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<T::AccountId>,
+{
+}
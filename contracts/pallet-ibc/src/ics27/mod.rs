@@ -0,0 +1,364 @@
+pub mod context;
+
+use crate::{routing::Context, Config, Event, InterchainAccountsParams, Pallet, WeightInfo};
+use alloc::{
+	format,
+	string::{String, ToString},
+};
+use codec::DecodeLimit;
+use core::fmt::Formatter;
+use frame_support::{dispatch::GetDispatchInfo, weights::Weight};
+use ibc::{
+	applications::interchain_accounts::{
+		controller, host,
+		packet::{Acknowledgement as Ics27Acknowledgement, InterchainAccountPacketData},
+	},
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::Error as Ics04Error,
+			msgs::acknowledgement::Acknowledgement,
+			packet::Packet,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleCallbackContext, ModuleOutputBuilder},
+	},
+	signer::Signer,
+};
+use ibc_primitives::{CallbackWeight, IbcHandler};
+use sp_core::crypto::AccountId32;
+use sp_std::marker::PhantomData;
+
+/// Whether the host executes messages with `type_url`, see [`InterchainAccountsParams`].
+pub(crate) fn is_message_allowed<T: Config>(type_url: &str) -> bool {
+	InterchainAccountsParams::<T>::get()
+		.allow_messages
+		.iter()
+		.any(|allowed| allowed.as_slice() == b"*" || allowed.as_slice() == type_url.as_bytes())
+}
+
+/// Decodes a runtime call from counterparty packet data. Calls can box other calls, so the
+/// nesting is bounded like it is for extrinsics.
+pub(crate) fn decode_call<T: Config>(
+	mut bytes: &[u8],
+) -> Result<<T as Config>::Call, codec::Error> {
+	<T as Config>::Call::decode_with_depth_limit(sp_api::MAX_EXTRINSIC_DEPTH, &mut bytes)
+}
+
+/// Returns the owner of the interchain accounts of a local account, from which its controller
+/// port is derived.
+pub(crate) fn interchain_account_owner<T: Config>(account: T::AccountId) -> String
+where
+	AccountId32: From<T::AccountId>,
+{
+	let account_id_32: AccountId32 = account.into();
+	format!("0x{}", hex::encode(account_id_32.as_ref()))
+}
+
+/// The ICS27 host, which executes the transactions of interchain accounts registered on this
+/// chain.
+#[derive(Clone, Eq, PartialEq)]
+pub struct HostModule<T: Config>(PhantomData<T>);
+
+impl<T: Config> core::fmt::Debug for HostModule<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		write!(f, "ica-host")
+	}
+}
+
+impl<T: Config> Default for HostModule<T> {
+	fn default() -> Self {
+		Self(PhantomData::default())
+	}
+}
+
+impl<T: Config + Send + Sync> Module for HostModule<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<<T as frame_system::Config>::AccountId>,
+{
+	fn on_chan_open_init(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		_version: &Version,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		Err(Ics04Error::implementation_specific(
+			"Interchain account channels can only be opened by the controller".to_string(),
+		))
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		_version: &Version,
+		counterparty_version: &Version,
+		_relayer: &Signer,
+	) -> Result<Version, Ics04Error> {
+		let mut ctx = Context::<T>::default();
+		host::on_chan_open_try(
+			&mut ctx,
+			order,
+			connection_hops,
+			port_id,
+			counterparty,
+			counterparty_version,
+		)
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let mut ctx = Context::<T>::default();
+		host::on_chan_open_confirm(&mut ctx, port_id, channel_id)
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		host::on_chan_close_init().map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_recv_packet(
+		&self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let mut ctx = Context::<T>::default();
+		let ack = host::on_recv_packet(&mut ctx, packet);
+		if let Ics27Acknowledgement::Error(ref err) = ack {
+			Pallet::<T>::deposit_event(Event::<T>::OnRecvPacketError {
+				msg: err.as_bytes().to_vec(),
+			});
+		}
+		Pallet::<T>::write_acknowledgement(packet, ack.to_json_bytes())
+			.map_err(|e| Ics04Error::implementation_specific(format!("[on_recv_packet] {:#?}", e)))
+	}
+}
+
+/// The ICS27 controller, which registers interchain accounts on other chains for local accounts
+/// and sends their transactions.
+#[derive(Clone, Eq, PartialEq)]
+pub struct ControllerModule<T: Config>(PhantomData<T>);
+
+impl<T: Config> core::fmt::Debug for ControllerModule<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		write!(f, "ica-controller")
+	}
+}
+
+impl<T: Config> Default for ControllerModule<T> {
+	fn default() -> Self {
+		Self(PhantomData::default())
+	}
+}
+
+impl<T: Config + Send + Sync> Module for ControllerModule<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<<T as frame_system::Config>::AccountId>,
+{
+	fn on_chan_open_init(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		_channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let mut ctx = Context::<T>::default();
+		controller::on_chan_open_init(
+			&mut ctx,
+			order,
+			connection_hops,
+			port_id,
+			counterparty,
+			version,
+		)
+		.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty: &Counterparty,
+		_version: &Version,
+		_counterparty_version: &Version,
+		_relayer: &Signer,
+	) -> Result<Version, Ics04Error> {
+		controller::on_chan_open_try()
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let mut ctx = Context::<T>::default();
+		controller::on_chan_open_ack(&mut ctx, port_id, channel_id, counterparty_version)
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		controller::on_chan_close_init()
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_recv_packet(
+		&self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_packet: &Packet,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		controller::on_recv_packet().map_err(|e| Ics04Error::implementation_specific(e.to_string()))
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &Acknowledgement,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let ack = Ics27Acknowledgement::from_json_bytes(acknowledgement.as_ref())
+			.map_err(|e| Ics04Error::implementation_specific(e.to_string()))?;
+		Pallet::<T>::deposit_event(Event::<T>::InterchainAccountTxAcknowledged {
+			port_id: packet.source_port.as_bytes().to_vec(),
+			channel_id: packet.source_channel.to_string().as_bytes().to_vec(),
+			sequence: packet.sequence.into(),
+			success: ack.is_successful(),
+		});
+		Ok(())
+	}
+
+	/// The ordered channel was closed by the timeout, the interchain account can be used again
+	/// once it's registered on a new channel.
+	fn on_timeout_packet(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		_relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		Pallet::<T>::deposit_event(Event::<T>::InterchainAccountTxTimedOut {
+			port_id: packet.source_port.as_bytes().to_vec(),
+			channel_id: packet.source_channel.to_string().as_bytes().to_vec(),
+			sequence: packet.sequence.into(),
+		});
+		Ok(())
+	}
+}
+
+pub struct WeightHandler<T: Config>(PhantomData<T>);
+
+impl<T: Config> Default for WeightHandler<T> {
+	fn default() -> Self {
+		Self(PhantomData::default())
+	}
+}
+
+impl<T: Config> CallbackWeight for WeightHandler<T> {
+	fn on_chan_open_init(&self) -> Weight {
+		<T as Config>::WeightInfo::on_chan_open_init()
+	}
+
+	fn on_chan_open_try(&self) -> Weight {
+		<T as Config>::WeightInfo::on_chan_open_try()
+	}
+
+	fn on_chan_open_ack(&self, _port_id: &PortId, _channel_id: &ChannelId) -> Weight {
+		<T as Config>::WeightInfo::on_chan_open_ack()
+	}
+
+	fn on_chan_open_confirm(&self, _port_id: &PortId, _channel_id: &ChannelId) -> Weight {
+		<T as Config>::WeightInfo::on_chan_open_confirm()
+	}
+
+	fn on_chan_close_init(&self, _port_id: &PortId, _channel_id: &ChannelId) -> Weight {
+		<T as Config>::WeightInfo::on_chan_close_init()
+	}
+
+	fn on_chan_close_confirm(&self, _port_id: &PortId, _channel_id: &ChannelId) -> Weight {
+		<T as Config>::WeightInfo::on_chan_close_confirm()
+	}
+
+	/// Includes the weight of the calls executed by the host. Messages the host doesn't allow
+	/// aren't decoded, the packet fails without executing any of them.
+	fn on_recv_packet(&self, packet: &Packet) -> Weight {
+		let calls_weight = InterchainAccountPacketData::from_json_bytes(&packet.data)
+			.and_then(|packet_data| packet_data.tx())
+			.map(|tx| {
+				tx.messages
+					.iter()
+					.filter(|message| is_message_allowed::<T>(&message.type_url))
+					.filter_map(|message| decode_call::<T>(&message.value).ok())
+					.fold(Weight::default(), |acc, call| {
+						acc.saturating_add(call.get_dispatch_info().weight)
+					})
+			})
+			.unwrap_or_default();
+		<T as Config>::WeightInfo::on_recv_packet().saturating_add(calls_weight)
+	}
+
+	fn on_acknowledgement_packet(
+		&self,
+		_packet: &Packet,
+		_acknowledgement: &Acknowledgement,
+	) -> Weight {
+		<T as Config>::WeightInfo::on_acknowledgement_packet()
+	}
+
+	fn on_timeout_packet(&self, _packet: &Packet) -> Weight {
+		<T as Config>::WeightInfo::on_timeout_packet()
+	}
+}
@@ -0,0 +1,186 @@
+use super::super::*;
+use crate::routing::Context;
+use ibc::{
+	applications::fee::{
+		context::{FeeContext, FeeKeeper, FeeReader},
+		error::Error as FeeError,
+		fee::{PacketFee, PacketFees, PacketId},
+	},
+	core::{
+		ics04_channel::packet::Sequence,
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	signer::Signer,
+};
+use ibc_primitives::get_fee_escrow_address;
+use sp_core::crypto::AccountId32;
+use tendermint_proto::Protobuf;
+
+impl<T: Config + Send + Sync> FeeReader for Context<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<T::AccountId>,
+{
+	type AccountId = T::AccountIdConversion;
+
+	fn get_fee_escrow_address(&self) -> Result<<Self as FeeReader>::AccountId, FeeError> {
+		get_fee_escrow_address()?.try_into().map_err(|_| {
+			log::trace!(target: "pallet_ibc", "Failed to get fee escrow address");
+			FeeError::parse_account_failure()
+		})
+	}
+
+	fn is_fee_enabled(&self, port_id: &PortId, channel_id: &ChannelId) -> bool {
+		FeeEnabledChannels::<T>::get(
+			port_id.as_bytes().to_vec(),
+			channel_id.to_string().as_bytes().to_vec(),
+		)
+	}
+
+	fn get_payee(&self, relayer: &Signer, channel_id: &ChannelId) -> Option<Signer> {
+		let payee = Payees::<T>::get(
+			channel_id.to_string().as_bytes().to_vec(),
+			relayer.as_ref().as_bytes().to_vec(),
+		)?;
+		String::from_utf8(payee).ok()?.parse().ok()
+	}
+
+	fn get_counterparty_payee(&self, relayer: &Signer, channel_id: &ChannelId) -> Option<String> {
+		let payee = CounterpartyPayees::<T>::get(
+			channel_id.to_string().as_bytes().to_vec(),
+			relayer.as_ref().as_bytes().to_vec(),
+		)?;
+		String::from_utf8(payee).ok()
+	}
+
+	fn get_fees_in_escrow(&self, packet_id: &PacketId) -> Vec<PacketFee> {
+		FeesInEscrow::<T>::get(
+			(
+				packet_id.port_id.as_bytes().to_vec(),
+				packet_id.channel_id.to_string().as_bytes().to_vec(),
+			),
+			u64::from(packet_id.sequence),
+		)
+		.and_then(|fees| PacketFees::decode_vec(&fees).ok())
+		.map(|fees| fees.0)
+		.unwrap_or_default()
+	}
+
+	fn get_channel_fees_in_escrow(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Vec<(PacketId, Vec<PacketFee>)> {
+		FeesInEscrow::<T>::iter_prefix((
+			port_id.as_bytes().to_vec(),
+			channel_id.to_string().as_bytes().to_vec(),
+		))
+		.filter_map(|(sequence, fees)| {
+			let fees = PacketFees::decode_vec(&fees).ok()?;
+			let packet_id = PacketId::new(port_id.clone(), *channel_id, Sequence::from(sequence));
+			Some((packet_id, fees.0))
+		})
+		.collect()
+	}
+
+	fn get_forward_relayer_address(&self, packet_id: &PacketId) -> Option<String> {
+		let address = ForwardRelayers::<T>::get((
+			packet_id.port_id.as_bytes().to_vec(),
+			packet_id.channel_id.to_string().as_bytes().to_vec(),
+			u64::from(packet_id.sequence),
+		))?;
+		String::from_utf8(address).ok()
+	}
+}
+
+impl<T: Config + Send + Sync> FeeKeeper for Context<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<T::AccountId>,
+{
+	type AccountId = T::AccountIdConversion;
+
+	fn set_fee_enabled(&mut self, port_id: &PortId, channel_id: &ChannelId) {
+		FeeEnabledChannels::<T>::insert(
+			port_id.as_bytes().to_vec(),
+			channel_id.to_string().as_bytes().to_vec(),
+			true,
+		)
+	}
+
+	fn delete_fee_enabled(&mut self, port_id: &PortId, channel_id: &ChannelId) {
+		FeeEnabledChannels::<T>::remove(
+			port_id.as_bytes().to_vec(),
+			channel_id.to_string().as_bytes().to_vec(),
+		)
+	}
+
+	fn set_payee(&mut self, relayer: &Signer, channel_id: &ChannelId, payee: &Signer) {
+		Payees::<T>::insert(
+			channel_id.to_string().as_bytes().to_vec(),
+			relayer.as_ref().as_bytes().to_vec(),
+			payee.as_ref().as_bytes().to_vec(),
+		)
+	}
+
+	fn set_counterparty_payee(
+		&mut self,
+		relayer: &Signer,
+		channel_id: &ChannelId,
+		counterparty_payee: &str,
+	) {
+		CounterpartyPayees::<T>::insert(
+			channel_id.to_string().as_bytes().to_vec(),
+			relayer.as_ref().as_bytes().to_vec(),
+			counterparty_payee.as_bytes().to_vec(),
+		)
+	}
+
+	fn store_fees_in_escrow(&mut self, packet_id: &PacketId, fees: Vec<PacketFee>) {
+		FeesInEscrow::<T>::insert(
+			(
+				packet_id.port_id.as_bytes().to_vec(),
+				packet_id.channel_id.to_string().as_bytes().to_vec(),
+			),
+			u64::from(packet_id.sequence),
+			PacketFees(fees).encode_vec(),
+		)
+	}
+
+	fn delete_fees_in_escrow(&mut self, packet_id: &PacketId) {
+		FeesInEscrow::<T>::remove(
+			(
+				packet_id.port_id.as_bytes().to_vec(),
+				packet_id.channel_id.to_string().as_bytes().to_vec(),
+			),
+			u64::from(packet_id.sequence),
+		)
+	}
+
+	fn set_forward_relayer_address(&mut self, packet_id: &PacketId, address: &str) {
+		ForwardRelayers::<T>::insert(
+			(
+				packet_id.port_id.as_bytes().to_vec(),
+				packet_id.channel_id.to_string().as_bytes().to_vec(),
+				u64::from(packet_id.sequence),
+			),
+			address.as_bytes().to_vec(),
+		)
+	}
+
+	fn delete_forward_relayer_address(&mut self, packet_id: &PacketId) {
+		ForwardRelayers::<T>::remove((
+			packet_id.port_id.as_bytes().to_vec(),
+			packet_id.channel_id.to_string().as_bytes().to_vec(),
+			u64::from(packet_id.sequence),
+		))
+	}
+}
+
+impl<T: Config + Send + Sync> FeeContext for Context<T>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<T::AccountId>,
+{
+	type AccountId = T::AccountIdConversion;
+}
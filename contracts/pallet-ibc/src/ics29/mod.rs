@@ -0,0 +1,102 @@
+pub mod context;
+
+use crate::{routing::Context, Config, Error};
+use ibc::{
+	applications::{
+		fee::{
+			msgs::{
+				pay_packet_fee::{
+					MsgPayPacketFee, MsgPayPacketFeeAsync, PAY_PACKET_FEE_ASYNC_TYPE_URL,
+					PAY_PACKET_FEE_TYPE_URL,
+				},
+				register_payee::{
+					MsgRegisterCounterpartyPayee, MsgRegisterPayee,
+					REGISTER_COUNTERPARTY_PAYEE_TYPE_URL, REGISTER_PAYEE_TYPE_URL,
+				},
+			},
+			relay::{
+				pay_packet_fee, pay_packet_fee_async, register_counterparty_payee, register_payee,
+			},
+		},
+		transfer::acknowledgement::ACK_ERR_STR,
+	},
+	signer::Signer,
+};
+use ibc_proto::google::protobuf::Any;
+use sp_core::crypto::AccountId32;
+use sp_runtime::traits::IdentifyAccount;
+
+/// Returns true if the message is handled by the fee middleware rather than the ibc handler.
+pub(crate) fn is_fee_message(type_url: &str) -> bool {
+	matches!(
+		type_url,
+		PAY_PACKET_FEE_TYPE_URL |
+			PAY_PACKET_FEE_ASYNC_TYPE_URL |
+			REGISTER_PAYEE_TYPE_URL |
+			REGISTER_COUNTERPARTY_PAYEE_TYPE_URL
+	)
+}
+
+/// Executes a fee message on behalf of `sender`, who must be the payer of the fee or the relayer
+/// registering a payee.
+pub(crate) fn execute_fee_message<T: Config + Send + Sync>(
+	ctx: &mut Context<T>,
+	sender: &T::AccountId,
+	message: Any,
+) -> Result<(), Error<T>>
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+	AccountId32: From<T::AccountId>,
+{
+	let result = match message.type_url.as_str() {
+		PAY_PACKET_FEE_TYPE_URL => {
+			let msg = MsgPayPacketFee::try_from(message).map_err(|_| Error::<T>::DecodingError)?;
+			ensure_signer::<T>(&msg.signer, sender)?;
+			pay_packet_fee(ctx, msg)
+		},
+		PAY_PACKET_FEE_ASYNC_TYPE_URL => {
+			let msg =
+				MsgPayPacketFeeAsync::try_from(message).map_err(|_| Error::<T>::DecodingError)?;
+			ensure_signer::<T>(&msg.packet_fee.refund_address, sender)?;
+			pay_packet_fee_async(ctx, msg)
+		},
+		REGISTER_PAYEE_TYPE_URL => {
+			let msg = MsgRegisterPayee::try_from(message).map_err(|_| Error::<T>::DecodingError)?;
+			ensure_signer::<T>(&msg.relayer, sender)?;
+			register_payee(ctx, msg)
+		},
+		REGISTER_COUNTERPARTY_PAYEE_TYPE_URL => {
+			let msg = MsgRegisterCounterpartyPayee::try_from(message)
+				.map_err(|_| Error::<T>::DecodingError)?;
+			ensure_signer::<T>(&msg.relayer, sender)?;
+			register_counterparty_payee(ctx, msg)
+		},
+		_ => return Err(Error::<T>::FeeMessageFailed),
+	};
+	result.map_err(|e| {
+		log::trace!(target: "pallet_ibc", "[execute_fee_message]: {:?}", e);
+		Error::<T>::FeeMessageFailed
+	})
+}
+
+fn ensure_signer<T: Config>(signer: &Signer, sender: &T::AccountId) -> Result<(), Error<T>> {
+	let account = T::AccountIdConversion::try_from(signer.clone())
+		.map_err(|_| Error::<T>::InvalidFeeSigner)?
+		.into_account();
+	if &account != sender {
+		return Err(Error::<T>::InvalidFeeSigner)
+	}
+	Ok(())
+}
+
+/// Returns false for the error acknowledgements written by the transfer module, or any other
+/// module following the ICS04 json acknowledgement format.
+pub(crate) fn is_success_acknowledgement(ack: &[u8]) -> bool {
+	if ack.starts_with(ACK_ERR_STR.as_bytes()) {
+		return false
+	}
+	!matches!(
+		serde_json::from_slice::<serde_json::Value>(ack),
+		Ok(serde_json::Value::Object(fields)) if fields.contains_key("error")
+	)
+}
@@ -5,9 +5,14 @@ use super::*;
 use crate::{
 	events::IbcEvent,
 	ics23::{
-		acknowledgements::Acknowledgements, channels::Channels, client_states::ClientStates,
-		connections::Connections, consensus_states::ConsensusStates,
-		next_seq_recv::NextSequenceRecv, packet_commitments::PacketCommitment,
+		acknowledgements::Acknowledgements,
+		channel_upgrades::{ChannelUpgradeErrors, ChannelUpgrades},
+		channels::Channels,
+		client_states::ClientStates,
+		connections::Connections,
+		consensus_states::ConsensusStates,
+		next_seq_recv::NextSequenceRecv,
+		packet_commitments::PacketCommitment,
 		receipts::PacketReceipt,
 	},
 	light_clients::AnyClientState,
@@ -16,8 +21,11 @@ use crate::{
 use codec::{Decode, Encode};
 use frame_support::traits::Currency;
 use ibc::{
-	applications::transfer::{
-		msgs::transfer::MsgTransfer, relay::send_transfer::send_transfer, PrefixedCoin,
+	applications::{
+		fee::relay::wrap_acknowledgement,
+		transfer::{
+			msgs::transfer::MsgTransfer, relay::send_transfer::send_transfer, PrefixedCoin,
+		},
 	},
 	core::{
 		ics02_client::{
@@ -34,8 +42,9 @@ use ibc::{
 		ics24_host::{
 			identifier::*,
 			path::{
-				AcksPath, ChannelEndsPath, ClientConsensusStatePath, ClientStatePath,
-				CommitmentsPath, ConnectionsPath, ReceiptsPath, SeqRecvsPath,
+				AcksPath, ChannelEndsPath, ChannelUpgradeErrorsPath, ChannelUpgradesPath,
+				ClientConsensusStatePath, ClientStatePath, CommitmentsPath, ConnectionsPath,
+				ReceiptsPath, SeqRecvsPath,
 			},
 		},
 		ics26_routing::handler::MsgReceipt,
@@ -50,7 +59,8 @@ use ibc_primitives::{
 	apply_prefix, channel_id_from_bytes, client_id_from_bytes, connection_id_from_bytes,
 	get_channel_escrow_address, port_id_from_bytes, runtime_interface, ConnectionHandshake,
 	Error as IbcHandlerError, HandlerMessage, IbcHandler, IdentifiedChannel, IdentifiedClientState,
-	IdentifiedConnection, PacketInfo, PacketState, QueryChannelResponse, QueryChannelsResponse,
+	IdentifiedConnection, PacketInfo, PacketState, QueryChannelResponse,
+	QueryChannelUpgradeErrorResponse, QueryChannelUpgradeResponse, QueryChannelsResponse,
 	QueryClientStateResponse, QueryConnectionResponse, QueryConnectionsResponse,
 	QueryConsensusStateResponse, QueryNextSequenceReceiveResponse,
 	QueryPacketAcknowledgementResponse, QueryPacketAcknowledgementsResponse,
@@ -124,6 +134,42 @@ where
 		Ok(QueryChannelResponse { channel, trie_key: key, height: host_height::<T>() })
 	}
 
+	/// Get the upgrade proposed by this chain for a channel
+	pub fn channel_upgrade(
+		channel_id: Vec<u8>,
+		port_id: Vec<u8>,
+	) -> Result<QueryChannelUpgradeResponse, Error<T>> {
+		let port_id = port_id_from_bytes(port_id).map_err(|_| Error::<T>::DecodingError)?;
+		let channel_id =
+			channel_id_from_bytes(channel_id).map_err(|_| Error::<T>::DecodingError)?;
+		let upgrade = ChannelUpgrades::<T>::get(port_id.clone(), channel_id)
+			.ok_or(Error::<T>::ChannelUpgradeNotFound)?;
+		let upgrade_path = format!("{}", ChannelUpgradesPath(port_id, channel_id));
+		let key = apply_prefix(T::PALLET_PREFIX, vec![upgrade_path]);
+
+		Ok(QueryChannelUpgradeResponse { upgrade, trie_key: key, height: host_height::<T>() })
+	}
+
+	/// Get the error receipt of the last aborted upgrade of a channel
+	pub fn channel_upgrade_error(
+		channel_id: Vec<u8>,
+		port_id: Vec<u8>,
+	) -> Result<QueryChannelUpgradeErrorResponse, Error<T>> {
+		let port_id = port_id_from_bytes(port_id).map_err(|_| Error::<T>::DecodingError)?;
+		let channel_id =
+			channel_id_from_bytes(channel_id).map_err(|_| Error::<T>::DecodingError)?;
+		let error_receipt = ChannelUpgradeErrors::<T>::get(port_id.clone(), channel_id)
+			.ok_or(Error::<T>::ChannelUpgradeNotFound)?;
+		let error_path = format!("{}", ChannelUpgradeErrorsPath(port_id, channel_id));
+		let key = apply_prefix(T::PALLET_PREFIX, vec![error_path]);
+
+		Ok(QueryChannelUpgradeErrorResponse {
+			error_receipt,
+			trie_key: key,
+			height: host_height::<T>(),
+		})
+	}
+
 	/// Get a connection state
 	pub fn connection(connection_id: Vec<u8>) -> Result<QueryConnectionResponse, Error<T>> {
 		let connection_id =
@@ -149,6 +195,26 @@ where
 		Ok(QueryClientStateResponse { client_state, trie_key: key, height: host_height::<T>() })
 	}
 
+	/// Get the client state scheduled by `upgrade_client`, if any
+	pub fn upgraded_client_state() -> Option<QueryClientStateResponse> {
+		let client_state = sp_io::storage::get(CLIENT_STATE_UPGRADE_PATH)?;
+		Some(QueryClientStateResponse {
+			client_state: client_state.to_vec(),
+			trie_key: CLIENT_STATE_UPGRADE_PATH.to_vec(),
+			height: host_height::<T>(),
+		})
+	}
+
+	/// Get the consensus state scheduled by `upgrade_client`, if any
+	pub fn upgraded_consensus_state() -> Option<QueryConsensusStateResponse> {
+		let consensus_state = sp_io::storage::get(CONSENSUS_STATE_UPGRADE_PATH)?;
+		Some(QueryConsensusStateResponse {
+			consensus_state: consensus_state.to_vec(),
+			trie_key: CONSENSUS_STATE_UPGRADE_PATH.to_vec(),
+			height: host_height::<T>(),
+		})
+	}
+
 	/// Get all client states
 	/// Returns a Vec of (client_id, client_state)
 	pub fn clients() -> Vec<(Vec<u8>, Vec<u8>)> {
@@ -808,6 +874,9 @@ where
 
 	fn write_acknowledgement(packet: &Packet, ack: Vec<u8>) -> Result<(), IbcHandlerError> {
 		let mut ctx = Context::<T>::default();
+		// acknowledgements on fee enabled channels carry the counterparty payee of the relayer
+		let success = crate::ics29::is_success_acknowledgement(&ack);
+		let ack = wrap_acknowledgement(&mut ctx, packet, ack, success);
 		Self::store_raw_acknowledgement(
 			(packet.destination_port.clone(), packet.destination_channel, packet.sequence),
 			ack.clone(),
@@ -1073,6 +1142,7 @@ where
 			receiver: to,
 			timeout_height,
 			timeout_timestamp,
+			memo: String::new(),
 		};
 		Ok(msg)
 	}
@@ -52,6 +52,8 @@ pub mod errors;
 pub mod events;
 pub mod ics20;
 mod ics23;
+pub mod ics27;
+mod ics29;
 pub mod light_clients;
 mod port;
 pub mod routing;
@@ -61,6 +63,11 @@ pub use light_client_common;
 
 pub const MODULE_ID: &str = "pallet_ibc";
 
+/// Main trie key under which the upgraded client state is written by `upgrade_client`.
+pub const CLIENT_STATE_UPGRADE_PATH: &[u8] = b"client-state-upgrade-path";
+/// Main trie key under which the upgraded consensus state is written by `upgrade_client`.
+pub const CONSENSUS_STATE_UPGRADE_PATH: &[u8] = b"consensus-state-upgrade-path";
+
 #[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
 pub struct Any {
 	pub type_url: Vec<u8>,
@@ -119,6 +126,36 @@ pub struct TransferParams<AccountId> {
 	pub source_channel: u64,
 	/// Timeout for this packet
 	pub timeout: Timeout,
+	/// Optional memo of the packet as valid utf8 string bytes
+	pub memo: Option<Vec<u8>>,
+}
+
+/// Params of the interchain accounts controller and host.
+#[derive(
+	frame_support::RuntimeDebug, PartialEq, Eq, scale_info::TypeInfo, Encode, Decode, Clone, Default,
+)]
+pub struct InterchainAccountParams {
+	/// Whether local accounts can register and use interchain accounts on other chains
+	pub controller_enabled: bool,
+	/// Whether other chains can register interchain accounts on this chain
+	pub host_enabled: bool,
+	/// Calls the host executes for interchain accounts, as `/{pallet}.{call}` type urls, or `*`
+	/// to allow all calls
+	pub allow_messages: Vec<Vec<u8>>,
+}
+
+#[derive(
+	frame_support::RuntimeDebug, PartialEq, Eq, scale_info::TypeInfo, Encode, Decode, Clone,
+)]
+pub struct InterchainAccountTxParams {
+	/// Connection to the host chain of the interchain account
+	pub connection_id: Vec<u8>,
+	/// Messages executed atomically by the interchain account on the host chain
+	pub messages: Vec<Any>,
+	/// Optional memo, as utf8 string bytes
+	pub memo: Vec<u8>,
+	/// Timeout for this packet, the channel of the interchain account is closed if it times out
+	pub timeout: Timeout,
 }
 
 pub enum LightClientProtocol {
@@ -146,17 +183,17 @@ pub mod pallet {
 	use core::time::Duration;
 
 	use frame_support::{
-		dispatch::DispatchResult,
+		dispatch::{DispatchResult, Dispatchable, GetDispatchInfo, PostDispatchInfo},
 		pallet_prelude::*,
 		traits::{
 			fungibles::{Inspect, Mutate, Transfer},
 			tokens::{AssetId, Balance},
-			ReservableCurrency, UnixTime,
+			GetCallMetadata, ReservableCurrency, UnixTime,
 		},
 	};
 	use frame_system::pallet_prelude::*;
 	pub use ibc::signer::Signer;
-	use sp_core::crypto::ByteArray;
+	use sp_core::{crypto::ByteArray, H256};
 
 	use crate::routing::{Context, ModuleRouter};
 	use ibc::{
@@ -172,7 +209,9 @@ pub mod pallet {
 		timestamp::Timestamp,
 		Height,
 	};
-	use ibc_primitives::{client_id_from_bytes, get_channel_escrow_address, IbcHandler};
+	use ibc_primitives::{
+		client_id_from_bytes, connection_id_from_bytes, get_channel_escrow_address, IbcHandler,
+	};
 	use light_clients::AnyClientState;
 	use sp_runtime::{
 		traits::{IdentifyAccount, Saturating},
@@ -233,6 +272,13 @@ pub mod pallet {
 		/// Amount to be reserved for client and connection creation
 		#[pallet::constant]
 		type SpamProtectionDeposit: Get<Self::Balance>;
+		/// Runtime calls the interchain accounts host dispatches for interchain accounts
+		type Call: Parameter
+			+ Dispatchable<
+				Origin = <Self as frame_system::Config>::Origin,
+				PostInfo = PostDispatchInfo,
+			> + GetDispatchInfo
+			+ GetCallMetadata;
 	}
 
 	#[pallet::pallet]
@@ -336,6 +382,105 @@ pub mod pallet {
 		ValueQuery,
 	>;
 
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// port_id, channel_id => whether the channel negotiated ICS29 fees
+	pub type FeeEnabledChannels<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, Vec<u8>, Blake2_128Concat, Vec<u8>, bool, ValueQuery>;
+
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// channel_id, relayer => account the fees earned by the relayer are paid to
+	pub type Payees<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		Vec<u8>,
+		Blake2_128Concat,
+		Vec<u8>,
+		Vec<u8>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// channel_id, relayer => counterparty address the receive fees earned by the relayer are
+	/// paid to
+	pub type CounterpartyPayees<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		Vec<u8>,
+		Blake2_128Concat,
+		Vec<u8>,
+		Vec<u8>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// (port_id, channel_id), sequence => protobuf encoded `PacketFees` escrowed for a sent packet
+	pub type FeesInEscrow<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(Vec<u8>, Vec<u8>),
+		Blake2_128Concat,
+		u64,
+		Vec<u8>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// (port_id, channel_id), sequence of a forwarded packet => protobuf encoded packet whose
+	/// tokens it forwards, and whose acknowledgement is written once it's acknowledged
+	pub type ForwardedPackets<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		(Vec<u8>, Vec<u8>),
+		Blake2_128Concat,
+		u64,
+		Vec<u8>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// (port_id, channel_id, sequence) => counterparty payee of the relayer of a received packet,
+	/// until its acknowledgement is written
+	pub type ForwardRelayers<T: Config> =
+		StorageMap<_, Blake2_128Concat, (Vec<u8>, Vec<u8>, u64), Vec<u8>, OptionQuery>;
+
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// Params of the interchain accounts controller and host
+	pub type InterchainAccountsParams<T: Config> =
+		StorageValue<_, InterchainAccountParams, ValueQuery>;
+
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// connection_id, controller port_id => channel_id used by the interchain account
+	pub type ActiveChannels<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		Vec<u8>,
+		Blake2_128Concat,
+		Vec<u8>,
+		Vec<u8>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[allow(clippy::disallowed_types)]
+	/// connection_id, controller port_id => address of the interchain account on the host
+	pub type InterchainAccounts<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		Vec<u8>,
+		Blake2_128Concat,
+		Vec<u8>,
+		Vec<u8>,
+		OptionQuery,
+	>;
+
 	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 	pub struct AssetConfig<AssetId> {
 		pub id: AssetId,
@@ -422,6 +567,24 @@ pub mod pallet {
 			source_channel: Vec<u8>,
 			destination_channel: Vec<u8>,
 		},
+		/// Ibc tokens have been received and sent on to the next chain, as instructed by the
+		/// packet memo
+		TokenForwarded {
+			to: Vec<u8>,
+			ibc_denom: Vec<u8>,
+			amount: T::Balance,
+			source_channel: Vec<u8>,
+			destination_channel: Vec<u8>,
+			sequence: u64,
+		},
+		/// A forwarded packet was acknowledged or timed out, and the acknowledgement of the packet
+		/// it forwards has been written
+		ForwardedPacketAcknowledged {
+			port_id: Vec<u8>,
+			channel_id: Vec<u8>,
+			sequence: u64,
+			success: bool,
+		},
 		/// On recv packet was not processed successfully processes
 		OnRecvPacketError { msg: Vec<u8> },
 		/// Client upgrade path has been set
@@ -430,6 +593,19 @@ pub mod pallet {
 		ClientFrozen { client_id: Vec<u8>, height: u64, revision_number: u64 },
 		/// Asset Admin Account Updated
 		AssetAdminUpdated { admin_account: T::AccountId },
+		/// Interchain accounts params updated
+		InterchainAccountParamsUpdated { controller_enabled: bool, host_enabled: bool },
+		/// The host acknowledged a transaction of an interchain account
+		InterchainAccountTxAcknowledged {
+			port_id: Vec<u8>,
+			channel_id: Vec<u8>,
+			sequence: u64,
+			success: bool,
+		},
+		/// A transaction of an interchain account timed out, which closed its channel
+		InterchainAccountTxTimedOut { port_id: Vec<u8>, channel_id: Vec<u8>, sequence: u64 },
+		/// The code of a wasm light client was stored
+		WasmLightClientStored { code_hash: H256 },
 	}
 
 	/// Errors inform users that something went wrong.
@@ -497,6 +673,22 @@ pub mod pallet {
 		ClientUpdateNotFound,
 		/// Error Freezing client
 		ClientFreezeFailed,
+		/// Error processing an ICS29 fee message
+		FeeMessageFailed,
+		/// The relayer or payer of a fee message isn't the sender of the extrinsic
+		InvalidFeeSigner,
+		/// The interchain account couldn't be registered
+		InterchainAccountRegistrationFailed,
+		/// The transaction of the interchain account couldn't be sent
+		InterchainAccountTxFailed,
+		/// No channel upgrade or upgrade error receipt found
+		ChannelUpgradeNotFound,
+		/// The code of the wasm light client exceeds the maximum size
+		WasmCodeTooLarge,
+		/// The code of the wasm light client is already stored
+		WasmCodeAlreadyStored,
+		/// The code isn't a valid wasm light client
+		InvalidWasmCode,
 	}
 
 	#[pallet::hooks]
@@ -509,6 +701,12 @@ pub mod pallet {
 		fn offchain_worker(_n: BlockNumberFor<T>) {
 			let _ = Pallet::<T>::packet_cleanup();
 		}
+
+		fn on_runtime_upgrade() -> Weight {
+			// the weights the schedule is derived from may have changed
+			light_clients::WasmClientScheduleStorage::put(weight::wasm_client_schedule::<T>());
+			T::DbWeight::get().writes(1)
+		}
 	}
 
 	// Dispatch able functions allows users to interact with the pallet and invoke state changes.
@@ -547,6 +745,14 @@ pub mod pallet {
 					Some(Ok(ibc_proto::google::protobuf::Any { type_url, value: message.value }))
 				})
 				.collect::<Result<Vec<ibc_proto::google::protobuf::Any>, Error<T>>>()?;
+			// fee messages aren't part of the ibc handler, they're executed by the fee
+			// middleware on behalf of the sender.
+			let (fee_messages, messages): (Vec<_>, Vec<_>) = messages
+				.into_iter()
+				.partition(|message| ics29::is_fee_message(&message.type_url));
+			for message in fee_messages {
+				ics29::execute_fee_message(&mut ctx, &sender, message)?;
+			}
 			let reserve_amt = T::SpamProtectionDeposit::get().saturating_mul(reserve_count.into());
 
 			if reserve_amt >= T::SpamProtectionDeposit::get() {
@@ -591,6 +797,12 @@ pub mod pallet {
 				MultiAddress::Raw(bytes) =>
 					String::from_utf8(bytes).map_err(|_| Error::<T>::Utf8Error)?,
 			};
+			let memo = params
+				.memo
+				.map(String::from_utf8)
+				.transpose()
+				.map_err(|_| Error::<T>::Utf8Error)?
+				.unwrap_or_default();
 			let denom = PrefixedDenom::from_str(&denom).map_err(|_| Error::<T>::InvalidIbcDenom)?;
 			let ibc_amount = Amount::from_str(&format!("{:?}", amount))
 				.map_err(|_| Error::<T>::InvalidAmount)?;
@@ -634,6 +846,7 @@ pub mod pallet {
 				receiver: Signer::from_str(&to).map_err(|_| Error::<T>::Utf8Error)?,
 				timeout_height,
 				timeout_timestamp,
+				memo,
 			};
 			let is_sender_source = is_sender_chain_source(
 				msg.source_port.clone(),
@@ -707,9 +920,6 @@ pub mod pallet {
 		#[pallet::weight(0)]
 		pub fn upgrade_client(origin: OriginFor<T>, params: UpgradeParams) -> DispatchResult {
 			<T as Config>::AdminOrigin::ensure_origin(origin)?;
-			const CLIENT_STATE_UPGRADE_PATH: &[u8] = b"client-state-upgrade-path";
-			const CONSENSUS_STATE_UPGRADE_PATH: &[u8] = b"consensus-state-upgrade-path";
-
 			sp_io::storage::set(CLIENT_STATE_UPGRADE_PATH, &params.client_state);
 			sp_io::storage::set(CONSENSUS_STATE_UPGRADE_PATH, &params.consensus_state);
 
@@ -756,6 +966,22 @@ pub mod pallet {
 							.map_err(|_| Error::<T>::ClientFreezeFailed)?,
 					)
 				},
+				AnyClientState::SoloMachine(solo) => {
+					let latest_height = solo.latest_height();
+					AnyClientState::wrap(
+						&solo
+							.with_frozen_height(Height::new(latest_height.revision_number, height))
+							.map_err(|_| Error::<T>::ClientFreezeFailed)?,
+					)
+				},
+				AnyClientState::Wasm(wasm) => {
+					let latest_height = wasm.latest_height();
+					AnyClientState::wrap(
+						&wasm
+							.with_frozen_height(Height::new(latest_height.revision_number, height))
+							.map_err(|_| Error::<T>::ClientFreezeFailed)?,
+					)
+				},
 				#[cfg(test)]
 				AnyClientState::Mock(mut ms) => {
 					ms.frozen_height =
@@ -776,6 +1002,121 @@ pub mod pallet {
 
 			Ok(())
 		}
+
+		#[pallet::weight(<T as Config>::WeightInfo::set_params())]
+		pub fn set_interchain_account_params(
+			origin: OriginFor<T>,
+			params: InterchainAccountParams,
+		) -> DispatchResult {
+			<T as Config>::AdminOrigin::ensure_origin(origin)?;
+			let InterchainAccountParams { controller_enabled, host_enabled, .. } = params;
+			<InterchainAccountsParams<T>>::put(params);
+			Self::deposit_event(Event::<T>::InterchainAccountParamsUpdated {
+				controller_enabled,
+				host_enabled,
+			});
+			Ok(())
+		}
+
+		/// Registers an interchain account for the sender on the host chain of the connection,
+		/// by opening an ordered channel from the sender's controller port. The account can be
+		/// used once the channel is open.
+		#[frame_support::transactional]
+		#[pallet::weight(<T as Config>::WeightInfo::register_interchain_account())]
+		pub fn register_interchain_account(
+			origin: OriginFor<T>,
+			connection_id: Vec<u8>,
+		) -> DispatchResult {
+			use ibc::applications::interchain_accounts::controller;
+			let owner = ensure_signed(origin)?;
+			let connection_id = connection_id_from_bytes(connection_id)
+				.map_err(|_| Error::<T>::ConnectionNotFound)?;
+			let ctx = routing::Context::<T>::default();
+			let (port_id, channel_end) = controller::register_interchain_account(
+				&ctx,
+				&connection_id,
+				&ics27::interchain_account_owner::<T>(owner),
+			)
+			.map_err(|e| {
+				log::trace!(target: "pallet_ibc", "[register_interchain_account]: {:?}", e);
+				Error::<T>::InterchainAccountRegistrationFailed
+			})?;
+			Self::open_channel(port_id, channel_end).map_err(|e| {
+				log::trace!(target: "pallet_ibc", "[register_interchain_account]: {:?}", e);
+				Error::<T>::ChannelInitError
+			})?;
+			Ok(())
+		}
+
+		/// Sends a transaction executed by the sender's interchain account on the host chain of
+		/// the connection.
+		#[frame_support::transactional]
+		#[pallet::weight(<T as Config>::WeightInfo::send_interchain_account_tx())]
+		pub fn send_interchain_account_tx(
+			origin: OriginFor<T>,
+			params: InterchainAccountTxParams,
+		) -> DispatchResult {
+			use ibc::applications::interchain_accounts::{controller, packet::CosmosTx};
+			let owner = ensure_signed(origin)?;
+			let connection_id = connection_id_from_bytes(params.connection_id)
+				.map_err(|_| Error::<T>::ConnectionNotFound)?;
+			let messages = params
+				.messages
+				.into_iter()
+				.map(|message| {
+					let type_url =
+						String::from_utf8(message.type_url).map_err(|_| Error::<T>::Utf8Error)?;
+					Ok(ibc_proto::google::protobuf::Any { type_url, value: message.value })
+				})
+				.collect::<Result<Vec<_>, Error<T>>>()?;
+			let memo = String::from_utf8(params.memo).map_err(|_| Error::<T>::Utf8Error)?;
+			let ctx = routing::Context::<T>::default();
+			let (port_id, channel_id, packet_data) = controller::send_tx(
+				&ctx,
+				&connection_id,
+				&ics27::interchain_account_owner::<T>(owner),
+				CosmosTx { messages },
+				memo,
+			)
+			.map_err(|e| {
+				log::trace!(target: "pallet_ibc", "[send_interchain_account_tx]: {:?}", e);
+				Error::<T>::InterchainAccountTxFailed
+			})?;
+			Self::send_packet(packet_data.to_json_bytes(), params.timeout, port_id, channel_id)
+				.map_err(|e| {
+					log::trace!(target: "pallet_ibc", "[send_interchain_account_tx]: {:?}", e);
+					Error::<T>::SendPacketError
+				})?;
+			Ok(())
+		}
+
+		/// Stores the code of a wasm light client, which clients of the `08-wasm` type can then
+		/// reference by its sha256 hash.
+		#[pallet::weight(0)]
+		pub fn store_wasm_light_client(origin: OriginFor<T>, code: Vec<u8>) -> DispatchResult {
+			<T as Config>::AdminOrigin::ensure_origin(origin)?;
+			ensure!(
+				code.len() <= light_clients::MAX_WASM_CODE_SIZE as usize,
+				Error::<T>::WasmCodeTooLarge
+			);
+			let code_hash = H256(sp_io::hashing::sha2_256(&code));
+			ensure!(
+				!light_clients::WasmCodeStorage::contains_key(code_hash),
+				Error::<T>::WasmCodeAlreadyStored
+			);
+			light_clients::WasmClientScheduleStorage::put(weight::wasm_client_schedule::<T>());
+			ics08_wasm::executor::validate::<light_clients::HostFunctionsManager>(&code).map_err(
+				|e| {
+					log::trace!(target: "pallet_ibc", "[store_wasm_light_client]: {:?}", e);
+					Error::<T>::InvalidWasmCode
+				},
+			)?;
+			light_clients::WasmCodeStorage::insert(code_hash, code);
+
+			Self::deposit_event(Event::<T>::WasmLightClientStored { code_hash });
+
+			Ok(())
+		}
 	}
 }
 
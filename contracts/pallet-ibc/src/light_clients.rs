@@ -1,6 +1,6 @@
 use alloc::{borrow::ToOwned, format, string::ToString, vec::Vec};
 use frame_support::{
-	pallet_prelude::{StorageValue, ValueQuery},
+	pallet_prelude::{Identity, StorageMap, StorageValue, ValueQuery},
 	traits::StorageInstance,
 };
 use ibc::core::{
@@ -10,6 +10,15 @@ use ibc::core::{
 use ibc_derive::{ClientDef, ClientMessage, ClientState, ConsensusState, Protobuf};
 use ibc_primitives::runtime_interface;
 use ibc_proto::google::protobuf::Any;
+use ics06_solomachine::{
+	client_message::SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL,
+	client_state::SOLOMACHINE_CLIENT_STATE_TYPE_URL,
+	consensus_state::SOLOMACHINE_CONSENSUS_STATE_TYPE_URL,
+};
+use ics08_wasm::{
+	client_message::WASM_CLIENT_MESSAGE_TYPE_URL, client_state::WASM_CLIENT_STATE_TYPE_URL,
+	consensus_state::WASM_CONSENSUS_STATE_TYPE_URL, executor::Schedule,
+};
 use ics10_grandpa::{
 	client_message::{RelayChainHeader, GRANDPA_CLIENT_MESSAGE_TYPE_URL},
 	client_state::GRANDPA_CLIENT_STATE_TYPE_URL,
@@ -19,7 +28,7 @@ use ics11_beefy::{
 	client_message::BEEFY_CLIENT_MESSAGE_TYPE_URL, client_state::BEEFY_CLIENT_STATE_TYPE_URL,
 	consensus_state::BEEFY_CONSENSUS_STATE_TYPE_URL,
 };
-use sp_core::{ed25519, H256};
+use sp_core::{ed25519, sr25519, H256};
 use sp_runtime::{
 	app_crypto::RuntimePublic,
 	traits::{BlakeTwo256, ConstU32, Header},
@@ -84,6 +93,100 @@ impl tendermint_light_client_verifier::host_functions::CryptoProvider for HostFu
 
 impl ics07_tendermint::HostFunctionsProvider for HostFunctionsManager {}
 
+impl ics06_solomachine::HostFunctions for HostFunctionsManager {
+	fn ed25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		<Self as tendermint_light_client_verifier::host_functions::CryptoProvider>::ed25519_verify(
+			signature, msg, public_key,
+		)
+		.is_ok()
+	}
+
+	fn secp256k1_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		if signature.len() != 64 {
+			return false
+		}
+		let message = sp_io::hashing::sha2_256(msg);
+		// cosmos-sdk signatures don't carry a recovery id, so we try both.
+		(0u8..2).any(|recovery_id| {
+			let mut signature_with_id = [0u8; 65];
+			signature_with_id[..64].copy_from_slice(signature);
+			signature_with_id[64] = recovery_id;
+			sp_io::crypto::secp256k1_ecdsa_recover_compressed(&signature_with_id, &message)
+				.map(|recovered| recovered[..] == *public_key)
+				.unwrap_or(false)
+		})
+	}
+}
+
+pub struct WasmCodeStorageInstance;
+impl StorageInstance for WasmCodeStorageInstance {
+	fn pallet_prefix() -> &'static str {
+		"ibc.lightclients.wasm"
+	}
+
+	const STORAGE_PREFIX: &'static str = "Code";
+}
+/// Code of the wasm light client contracts, by the sha256 hash of the code.
+pub type WasmCodeStorage = StorageMap<WasmCodeStorageInstance, Identity, H256, Vec<u8>>;
+
+/// Maximum size of a wasm light client contract.
+pub const MAX_WASM_CODE_SIZE: u32 = 3 * 1024 * 1024;
+
+pub struct WasmClientScheduleStorageInstance;
+impl StorageInstance for WasmClientScheduleStorageInstance {
+	fn pallet_prefix() -> &'static str {
+		"ibc.lightclients.wasm"
+	}
+
+	const STORAGE_PREFIX: &'static str = "Schedule";
+}
+/// Fuel limit and host function costs of the wasm light client contracts, derived from the
+/// benchmarked weights by [`crate::weight::wasm_client_schedule`]. It's written whenever a
+/// contract is stored and on every runtime upgrade, since the weights may have changed.
+pub type WasmClientScheduleStorage =
+	StorageValue<WasmClientScheduleStorageInstance, Schedule, ValueQuery>;
+
+impl ics08_wasm::HostFunctions for HostFunctionsManager {
+	fn code(code_hash: &[u8]) -> Option<Vec<u8>> {
+		if code_hash.len() != 32 {
+			return None
+		}
+		WasmCodeStorage::get(H256::from_slice(code_hash))
+	}
+
+	fn schedule() -> Schedule {
+		WasmClientScheduleStorage::get()
+	}
+
+	fn ed25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		<Self as ics06_solomachine::HostFunctions>::ed25519_verify(signature, msg, public_key)
+	}
+
+	fn sr25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		match (sr25519::Signature::from_slice(signature), sr25519::Public::try_from(public_key)) {
+			(Some(signature), Ok(public_key)) =>
+				sp_io::crypto::sr25519_verify(&signature, msg, &public_key),
+			_ => false,
+		}
+	}
+
+	fn secp256k1_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		<Self as ics06_solomachine::HostFunctions>::secp256k1_verify(signature, msg, public_key)
+	}
+
+	fn sha2_256(data: &[u8]) -> [u8; 32] {
+		sp_io::hashing::sha2_256(data)
+	}
+
+	fn keccak_256(data: &[u8]) -> [u8; 32] {
+		sp_io::hashing::keccak_256(data)
+	}
+
+	fn blake2b_256(data: &[u8]) -> [u8; 32] {
+		sp_io::hashing::blake2_256(data)
+	}
+}
+
 pub struct GrandpaHeaderHashesStorageInstance;
 impl StorageInstance for GrandpaHeaderHashesStorageInstance {
 	fn pallet_prefix() -> &'static str {
@@ -177,6 +280,8 @@ pub enum AnyClient {
 	Grandpa(ics10_grandpa::client_def::GrandpaClient<HostFunctionsManager>),
 	Beefy(ics11_beefy::client_def::BeefyClient<HostFunctionsManager>),
 	Tendermint(ics07_tendermint::client_def::TendermintClient<HostFunctionsManager>),
+	SoloMachine(ics06_solomachine::client_def::SoloMachineClient<HostFunctionsManager>),
+	Wasm(ics08_wasm::client_def::WasmClient<HostFunctionsManager>),
 	#[cfg(test)]
 	Mock(ibc::mock::client_def::MockClient),
 }
@@ -186,6 +291,8 @@ pub enum AnyUpgradeOptions {
 	Grandpa(ics10_grandpa::client_state::UpgradeOptions),
 	Beefy(ics11_beefy::client_state::UpgradeOptions),
 	Tendermint(ics07_tendermint::client_state::UpgradeOptions),
+	SoloMachine(()),
+	Wasm(()),
 	#[cfg(test)]
 	Mock(()),
 }
@@ -198,6 +305,10 @@ pub enum AnyClientState {
 	Beefy(ics11_beefy::client_state::ClientState<HostFunctionsManager>),
 	#[ibc(proto_url = "TENDERMINT_CLIENT_STATE_TYPE_URL")]
 	Tendermint(ics07_tendermint::client_state::ClientState<HostFunctionsManager>),
+	#[ibc(proto_url = "SOLOMACHINE_CLIENT_STATE_TYPE_URL")]
+	SoloMachine(ics06_solomachine::client_state::ClientState<HostFunctionsManager>),
+	#[ibc(proto_url = "WASM_CLIENT_STATE_TYPE_URL")]
+	Wasm(ics08_wasm::client_state::ClientState<HostFunctionsManager>),
 	#[cfg(test)]
 	#[ibc(proto_url = "MOCK_CLIENT_STATE_TYPE_URL")]
 	Mock(ibc::mock::client_state::MockClientState),
@@ -211,6 +322,10 @@ pub enum AnyConsensusState {
 	Beefy(ics11_beefy::consensus_state::ConsensusState),
 	#[ibc(proto_url = "TENDERMINT_CONSENSUS_STATE_TYPE_URL")]
 	Tendermint(ics07_tendermint::consensus_state::ConsensusState),
+	#[ibc(proto_url = "SOLOMACHINE_CONSENSUS_STATE_TYPE_URL")]
+	SoloMachine(ics06_solomachine::consensus_state::ConsensusState),
+	#[ibc(proto_url = "WASM_CONSENSUS_STATE_TYPE_URL")]
+	Wasm(ics08_wasm::consensus_state::ConsensusState),
 	#[cfg(test)]
 	#[ibc(proto_url = "MOCK_CONSENSUS_STATE_TYPE_URL")]
 	Mock(ibc::mock::client_state::MockConsensusState),
@@ -225,6 +340,10 @@ pub enum AnyClientMessage {
 	Beefy(ics11_beefy::client_message::ClientMessage),
 	#[ibc(proto_url = "TENDERMINT_CLIENT_MESSAGE_TYPE_URL")]
 	Tendermint(ics07_tendermint::client_message::ClientMessage),
+	#[ibc(proto_url = "SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL")]
+	SoloMachine(ics06_solomachine::client_message::ClientMessage),
+	#[ibc(proto_url = "WASM_CLIENT_MESSAGE_TYPE_URL")]
+	Wasm(ics08_wasm::client_message::ClientMessage),
 	#[cfg(test)]
 	#[ibc(proto_url = "MOCK_CLIENT_MESSAGE_TYPE_URL")]
 	Mock(ibc::mock::header::MockClientMessage),
@@ -249,6 +368,14 @@ impl TryFrom<Any> for AnyClientMessage {
 				ics07_tendermint::client_message::ClientMessage::decode_vec(&value.value)
 					.map_err(ics02_client::error::Error::decode_raw_header)?,
 			)),
+			SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL => Ok(Self::SoloMachine(
+				ics06_solomachine::client_message::ClientMessage::decode_vec(&value.value)
+					.map_err(ics02_client::error::Error::decode_raw_header)?,
+			)),
+			WASM_CLIENT_MESSAGE_TYPE_URL => Ok(Self::Wasm(
+				ics08_wasm::client_message::ClientMessage::decode_vec(&value.value)
+					.map_err(ics02_client::error::Error::decode_raw_header)?,
+			)),
 			_ => Err(ics02_client::error::Error::unknown_consensus_state_type(value.type_url)),
 		}
 	}
@@ -267,6 +394,12 @@ impl From<AnyClientMessage> for Any {
 				type_url: TENDERMINT_CLIENT_MESSAGE_TYPE_URL.to_string(),
 				value: msg.encode_vec(),
 			},
+			AnyClientMessage::SoloMachine(msg) => Any {
+				type_url: SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL.to_string(),
+				value: msg.encode_vec(),
+			},
+			AnyClientMessage::Wasm(msg) =>
+				Any { type_url: WASM_CLIENT_MESSAGE_TYPE_URL.to_string(), value: msg.encode_vec() },
 			#[cfg(test)]
 			AnyClientMessage::Mock(_msg) => panic!("MockHeader can't be serialized"),
 		}
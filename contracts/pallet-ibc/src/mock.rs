@@ -66,6 +66,7 @@ frame_support::construct_runtime!(
 		Assets: pallet_assets,
 		IbcPing: pallet_ibc_ping,
 		Ibc: pallet_ibc,
+		Sudo: pallet_sudo,
 	}
 );
 
@@ -111,6 +112,11 @@ impl pallet_ibc_ping::Config for Test {
 	type IbcHandler = Ibc;
 }
 
+impl pallet_sudo::Config for Test {
+	type Event = Event;
+	type Call = Call;
+}
+
 parameter_types! {
 	pub const NativeAssetId: u128 = 1;
 	pub const StringLimit: u32 = 32;
@@ -187,6 +193,7 @@ impl Config for Test {
 	type AdminOrigin = EnsureRoot<AccountId>;
 	type SentryOrigin = EnsureRoot<AccountId>;
 	type SpamProtectionDeposit = SpamProtectionDeposit;
+	type Call = Call;
 }
 
 impl pallet_timestamp::Config for Test {
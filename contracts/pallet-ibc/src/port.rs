@@ -2,8 +2,11 @@ use super::*;
 
 use crate::routing::{Context, ModuleRouter};
 use ibc::{
-	applications::transfer::{
-		MODULE_ID_STR as TRANSFER_MODULE_ID, PORT_ID_STR as TRANSFER_PORT_ID,
+	applications::{
+		interchain_accounts::{
+			is_controller_port, CONTROLLER_MODULE_ID_STR, HOST_MODULE_ID_STR, HOST_PORT_ID_STR,
+		},
+		transfer::{MODULE_ID_STR as TRANSFER_MODULE_ID, PORT_ID_STR as TRANSFER_PORT_ID},
 	},
 	core::{
 		ics05_port::{context::PortReader, error::Error as ICS05Error},
@@ -22,6 +25,10 @@ impl<T: Config + Sync + Send> PortReader for Context<T> {
 		match port_id.as_str() {
 			TRANSFER_PORT_ID => Ok(ModuleId::from_str(TRANSFER_MODULE_ID)
 				.map_err(|_| ICS05Error::module_not_found(port_id.clone()))?),
+			HOST_PORT_ID_STR => Ok(ModuleId::from_str(HOST_MODULE_ID_STR)
+				.map_err(|_| ICS05Error::module_not_found(port_id.clone()))?),
+			_ if is_controller_port(port_id) => Ok(ModuleId::from_str(CONTROLLER_MODULE_ID_STR)
+				.map_err(|_| ICS05Error::module_not_found(port_id.clone()))?),
 			_ => Err(ICS05Error::module_not_found(port_id.clone())),
 		}
 	}
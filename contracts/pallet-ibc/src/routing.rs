@@ -1,7 +1,11 @@
 use super::*;
 use core::fmt::Debug;
 use ibc::{
-	applications::transfer::MODULE_ID_STR as IBC_TRANSFER_MODULE_ID,
+	applications::{
+		fee::middleware::FeeMiddleware,
+		interchain_accounts::{CONTROLLER_MODULE_ID_STR, HOST_MODULE_ID_STR},
+		transfer::MODULE_ID_STR as IBC_TRANSFER_MODULE_ID,
+	},
 	core::{
 		ics24_host::identifier::PortId,
 		ics26_routing::context::{
@@ -32,13 +36,21 @@ impl<T: Config + Send + Sync> Context<T> {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct IbcRouter<T: Config> {
-	ibc_transfer: ics20::IbcModule<T>,
+	/// Transfer module, wrapped by the packet-forward and ICS29 fee middlewares
+	ibc_transfer: FeeMiddleware<ics20::forward::ForwardMiddleware<T>, Context<T>>,
+	ica_controller: ics27::ControllerModule<T>,
+	ica_host: ics27::HostModule<T>,
 	sub_router: T::Router,
 }
 
 impl<T: Config> Default for IbcRouter<T> {
 	fn default() -> Self {
-		Self { ibc_transfer: ics20::IbcModule::<T>::default(), sub_router: Default::default() }
+		Self {
+			ibc_transfer: FeeMiddleware::default(),
+			ica_controller: ics27::ControllerModule::<T>::default(),
+			ica_host: ics27::HostModule::<T>::default(),
+			sub_router: Default::default(),
+		}
 	}
 }
 
@@ -65,6 +77,8 @@ where
 
 		match module_id.as_ref() {
 			IBC_TRANSFER_MODULE_ID => Some(&mut self.ibc_transfer),
+			CONTROLLER_MODULE_ID_STR => Some(&mut self.ica_controller),
+			HOST_MODULE_ID_STR => Some(&mut self.ica_host),
 			&_ => None,
 		}
 	}
@@ -75,7 +89,10 @@ where
 			return true
 		}
 
-		matches!(module_id.to_string().as_str(), IBC_TRANSFER_MODULE_ID,)
+		matches!(
+			module_id.to_string().as_str(),
+			IBC_TRANSFER_MODULE_ID | CONTROLLER_MODULE_ID_STR | HOST_MODULE_ID_STR
+		)
 	}
 }
 
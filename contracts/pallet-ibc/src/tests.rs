@@ -3,19 +3,43 @@ use crate::{
 	light_clients::{AnyClientState, AnyConsensusState},
 	mock::*,
 	routing::Context,
-	Any, Config, ConsensusHeights, DenomToAssetId, MultiAddress, Pallet, PalletParams, Timeout,
-	TransferParams, MODULE_ID,
+	ActiveChannels, Any, Config, ConsensusHeights, DenomToAssetId, InterchainAccountParams,
+	InterchainAccountTxParams, InterchainAccounts, MultiAddress, Pallet, PalletParams, Timeout,
+	TransferParams, WeightInfo, MODULE_ID,
 };
+use codec::Encode;
 use core::time::Duration;
 use frame_support::{
 	assert_ok,
+	dispatch::GetDispatchInfo,
 	traits::{
 		fungibles::{Inspect, Mutate},
 		Len,
 	},
 };
 use ibc::{
-	applications::transfer::{packet::PacketData, Coin, PrefixedDenom, VERSION},
+	applications::{
+		fee::{
+			acknowledgement::IncentivizedAcknowledgement,
+			context::{FeeKeeper, FeeReader},
+			fee::Fee,
+			metadata::Metadata,
+			msgs::pay_packet_fee::MsgPayPacketFee,
+		},
+		interchain_accounts::{
+			controller_port_id, host_port_id,
+			metadata::Metadata as IcaMetadata,
+			packet::{CosmosTx, InterchainAccountPacketData},
+			CONTROLLER_MODULE_ID_STR, HOST_MODULE_ID_STR,
+		},
+		transfer::{
+			acknowledgement::{Acknowledgement as Ics20Acknowledgement, ACK_SUCCESS_B64},
+			error::Error as Ics20Error,
+			forward::DEFAULT_FORWARD_TIMEOUT,
+			packet::PacketData,
+			Coin, PrefixedDenom, MODULE_ID_STR as TRANSFER_MODULE_ID, VERSION,
+		},
+	},
 	core::{
 		ics02_client::{
 			client_state::ClientState,
@@ -31,13 +55,17 @@ use ibc::{
 		},
 		ics04_channel::{
 			channel::{ChannelEnd, Counterparty as ChanCounterParty, Order, State},
-			context::ChannelKeeper,
-			msgs::recv_packet::MsgRecvPacket,
+			context::{ChannelKeeper, ChannelReader},
+			msgs::{
+				acknowledgement::Acknowledgement as GenericAcknowledgement,
+				recv_packet::MsgRecvPacket, timeout::MsgTimeout,
+			},
 			packet::Packet,
 			Version as ChanVersion,
 		},
 		ics23_commitment::commitment::CommitmentPrefix,
 		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
+		ics26_routing::context::{Ics26Context, ModuleId, ModuleOutputBuilder, Router},
 	},
 	mock::{
 		client_state::{MockClientState, MockConsensusState},
@@ -47,7 +75,10 @@ use ibc::{
 	signer::Signer,
 	tx_msg::Msg,
 };
-use ibc_primitives::{get_channel_escrow_address, IbcHandler};
+use ibc_primitives::{
+	get_channel_escrow_address, get_forward_intermediate_address, get_interchain_account_address,
+	CallbackWeight, IbcHandler,
+};
 use sp_core::Pair;
 use sp_runtime::{offchain::storage::StorageValueRef, traits::IdentifyAccount, AccountId32};
 use std::{
@@ -230,6 +261,7 @@ fn send_transfer() {
 				to: MultiAddress::Raw(ss58_address.as_bytes().to_vec()),
 				source_channel: 0,
 				timeout,
+				memo: None,
 			},
 			asset_id,
 			balance,
@@ -306,6 +338,7 @@ fn on_deliver_ics20_recv_packet() {
 			token: coin,
 			sender: Signer::from_str("alice").unwrap(),
 			receiver: Signer::from_str(&ss58_address).unwrap(),
+			memo: String::new(),
 		};
 
 		let data = serde_json::to_vec(&packet_data).unwrap();
@@ -621,3 +654,905 @@ fn test_next_and_previous_consensus_state_for_other_client_types() {
 		assert!(ctx.next_consensus_state(&client_id, Height::new(0, 400)).unwrap().is_some());
 	})
 }
+
+const FEE_PAYER: [u8; 32] = [1; 32];
+const FORWARD_RELAYER: [u8; 32] = [2; 32];
+const REVERSE_RELAYER: [u8; 32] = [3; 32];
+const TIMEOUT_RELAYER: [u8; 32] = [4; 32];
+
+fn hex_signer(account: [u8; 32]) -> Signer {
+	Signer::from_str(&format!("0x{}", hex::encode(account))).unwrap()
+}
+
+fn pica_balance(account: [u8; 32]) -> u128 {
+	<Assets as Inspect<AccountId>>::balance(2, &AccountId32::new(account))
+}
+
+fn pica(amount: u128) -> Vec<Coin<PrefixedDenom>> {
+	vec![Coin {
+		denom: PrefixedDenom::from_str("PICA").unwrap(),
+		amount: ibc::applications::transfer::Amount::from_str(&amount.to_string()).unwrap(),
+	}]
+}
+
+/// Opens a fee enabled transfer channel and funds the fee payer.
+fn setup_fee_channel() {
+	setup_client_and_consensus_state(PortId::transfer());
+	Context::<Test>::default().set_fee_enabled(&PortId::transfer(), &ChannelId::new(0));
+	let asset_id =
+		<<Test as Config>::IbcDenomToAssetIdConversion as DenomToAssetId<Test>>::from_denom_to_asset_id(
+			&"PICA".to_string(),
+		)
+		.unwrap();
+	<<Test as Config>::Fungibles as Mutate<<Test as frame_system::Config>::AccountId>>::mint_into(
+		asset_id,
+		&AccountId32::new(FEE_PAYER),
+		1000 * MILLIS,
+	)
+	.unwrap();
+}
+
+/// Escrows a receive fee of 10, an acknowledgement fee of 20 and a timeout fee of 30 MILLIS for
+/// the next packet sent on the channel.
+fn pay_packet_fee() {
+	let msg = MsgPayPacketFee {
+		fee: Fee {
+			recv_fee: pica(10 * MILLIS),
+			ack_fee: pica(20 * MILLIS),
+			timeout_fee: pica(30 * MILLIS),
+		},
+		source_port_id: PortId::transfer(),
+		source_channel_id: ChannelId::new(0),
+		signer: hex_signer(FEE_PAYER),
+		relayers: vec![],
+	};
+	let msg = Any { type_url: msg.type_url().as_bytes().to_vec(), value: msg.encode_vec() };
+	assert_ok!(Ibc::deliver(Origin::signed(AccountId32::new(FEE_PAYER)), vec![msg]));
+}
+
+/// The packet the fee was paid for, transferring 100 MILLIS from the fee payer.
+fn fee_packet() -> Packet {
+	let packet_data = PacketData {
+		token: pica(100 * MILLIS).remove(0),
+		sender: hex_signer(FEE_PAYER),
+		receiver: Signer::from_str("alice").unwrap(),
+		memo: String::new(),
+	};
+	Packet {
+		sequence: 1u64.into(),
+		source_port: PortId::transfer(),
+		source_channel: ChannelId::new(0),
+		destination_port: PortId::transfer(),
+		destination_channel: ChannelId::new(1),
+		data: serde_json::to_vec(&packet_data).unwrap(),
+		timeout_height: Height::new(2000, 5),
+		timeout_timestamp: Default::default(),
+	}
+}
+
+/// Runs `f` with the module registered under `module_id`, along with a callback context.
+fn with_module<R>(
+	module_id: &str,
+	f: impl FnOnce(&mut dyn ibc::core::ics26_routing::context::Module, &Context<Test>) -> R,
+) -> R {
+	let mut ctx = Context::<Test>::default();
+	let callback_ctx = ctx.clone();
+	let module = ctx.router_mut().get_route_mut(&ModuleId::from_str(module_id).unwrap()).unwrap();
+	f(module, &callback_ctx)
+}
+
+/// Runs `f` with the module the transfer port is routed to, along with a callback context.
+fn with_transfer_module<R>(
+	f: impl FnOnce(&mut dyn ibc::core::ics26_routing::context::Module, &Context<Test>) -> R,
+) -> R {
+	with_module(TRANSFER_MODULE_ID, f)
+}
+
+fn escrowed_fees() -> usize {
+	Context::<Test>::default()
+		.get_channel_fees_in_escrow(&PortId::transfer(), &ChannelId::new(0))
+		.len()
+}
+
+#[test]
+fn fee_is_escrowed_for_the_next_packet() {
+	new_test_ext().execute_with(|| {
+		setup_fee_channel();
+		pay_packet_fee();
+		assert_eq!(pica_balance(FEE_PAYER), 940 * MILLIS);
+		assert_eq!(escrowed_fees(), 1);
+	})
+}
+
+#[test]
+fn acknowledgement_pays_the_forward_and_reverse_relayers() {
+	new_test_ext().execute_with(|| {
+		setup_fee_channel();
+		pay_packet_fee();
+		let ack = IncentivizedAcknowledgement {
+			app_acknowledgement: ACK_SUCCESS_B64.to_vec(),
+			forward_relayer_address: hex_signer(FORWARD_RELAYER).to_string(),
+			underlying_app_success: true,
+		};
+		with_transfer_module(|module, ctx| {
+			module.on_acknowledgement_packet(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				&fee_packet(),
+				&GenericAcknowledgement::from_bytes(ack.to_json_bytes()),
+				&hex_signer(REVERSE_RELAYER),
+			)
+		})
+		.unwrap();
+		assert_eq!(pica_balance(FORWARD_RELAYER), 10 * MILLIS);
+		assert_eq!(pica_balance(REVERSE_RELAYER), 20 * MILLIS);
+		// the timeout fee is refunded
+		assert_eq!(pica_balance(FEE_PAYER), 970 * MILLIS);
+		assert_eq!(escrowed_fees(), 0);
+	})
+}
+
+#[test]
+fn timeout_pays_the_timeout_relayer() {
+	new_test_ext().execute_with(|| {
+		setup_fee_channel();
+		pay_packet_fee();
+		// the transferred tokens are refunded from the channel escrow on timeout
+		let channel_escrow_address =
+			get_channel_escrow_address(&PortId::transfer(), ChannelId::new(0)).unwrap();
+		let channel_escrow_address =
+			<Test as Config>::AccountIdConversion::try_from(channel_escrow_address)
+				.map_err(|_| ())
+				.unwrap()
+				.into_account();
+		<<Test as Config>::Fungibles as Mutate<<Test as frame_system::Config>::AccountId>>::mint_into(
+			2,
+			&channel_escrow_address,
+			100 * MILLIS,
+		)
+		.unwrap();
+		with_transfer_module(|module, ctx| {
+			module.on_timeout_packet(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				&fee_packet(),
+				&hex_signer(TIMEOUT_RELAYER),
+			)
+		})
+		.unwrap();
+		assert_eq!(pica_balance(TIMEOUT_RELAYER), 30 * MILLIS);
+		// the receive and acknowledgement fees are refunded along with the transfer
+		assert_eq!(pica_balance(FEE_PAYER), 1070 * MILLIS);
+		assert_eq!(escrowed_fees(), 0);
+	})
+}
+
+#[test]
+fn fees_are_refunded_on_channel_closure() {
+	new_test_ext().execute_with(|| {
+		setup_fee_channel();
+		pay_packet_fee();
+		pay_packet_fee();
+		assert_eq!(pica_balance(FEE_PAYER), 880 * MILLIS);
+		with_transfer_module(|module, ctx| {
+			module.on_chan_close_init(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				&PortId::transfer(),
+				&ChannelId::new(0),
+				&hex_signer(REVERSE_RELAYER),
+			)
+		})
+		.unwrap();
+		assert_eq!(pica_balance(FEE_PAYER), 1000 * MILLIS);
+		assert_eq!(escrowed_fees(), 0);
+	})
+}
+
+#[test]
+fn fees_are_enabled_once_the_handshake_is_acknowledged() {
+	new_test_ext().execute_with(|| {
+		setup_client_and_consensus_state(PortId::transfer());
+		let port_id = PortId::transfer();
+		let channel_id = ChannelId::new(2);
+		let fee_version = Metadata::new(&ChanVersion::new(VERSION.to_string())).to_version();
+		let counterparty = ChanCounterParty::new(port_id.clone(), None);
+		let channel_end = ChannelEnd::new(
+			State::Init,
+			Order::Unordered,
+			counterparty.clone(),
+			vec![ConnectionId::new(0)],
+			fee_version.clone(),
+		);
+		Context::<Test>::default()
+			.store_channel((port_id.clone(), channel_id), &channel_end)
+			.unwrap();
+
+		with_transfer_module(|module, ctx| {
+			module.on_chan_open_init(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				Order::Unordered,
+				&[ConnectionId::new(0)],
+				&port_id,
+				&channel_id,
+				&counterparty,
+				&fee_version,
+				&hex_signer(REVERSE_RELAYER),
+			)
+		})
+		.unwrap();
+		// a handshake that never completes leaves no fee state behind
+		assert!(!Context::<Test>::default().is_fee_enabled(&port_id, &channel_id));
+
+		with_transfer_module(|module, ctx| {
+			module.on_chan_open_ack(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				&port_id,
+				&channel_id,
+				&fee_version,
+				&hex_signer(REVERSE_RELAYER),
+			)
+		})
+		.unwrap();
+		assert!(Context::<Test>::default().is_fee_enabled(&port_id, &channel_id));
+	})
+}
+
+const ICA_OWNER: [u8; 32] = [5; 32];
+const ICA_RECEIVER: [u8; 32] = [6; 32];
+
+/// The controller port of the interchain account of `ICA_OWNER`.
+fn ica_controller_port() -> PortId {
+	controller_port_id(&hex_signer(ICA_OWNER).to_string()).unwrap()
+}
+
+/// The interchain account the host creates for `ICA_OWNER` on connection-0.
+fn interchain_account() -> AccountId32 {
+	let address = get_interchain_account_address(&ConnectionId::new(0), &ica_controller_port());
+	<Test as Config>::AccountIdConversion::try_from(Signer::from_str(&address).unwrap())
+		.map_err(|_| ())
+		.unwrap()
+		.into_account()
+}
+
+/// Opens connection-0, whose counterparty is connection-1, and enables the interchain accounts
+/// controller and host.
+fn setup_interchain_accounts(allow_messages: &[&str]) {
+	frame_system::Pallet::<Test>::set_block_number(1u32);
+	setup_client_and_consensus_state(PortId::transfer());
+	assert_ok!(Ibc::set_interchain_account_params(
+		Origin::root(),
+		InterchainAccountParams {
+			controller_enabled: true,
+			host_enabled: true,
+			allow_messages: allow_messages.iter().map(|msg| msg.as_bytes().to_vec()).collect(),
+		}
+	));
+}
+
+/// Runs the host side of the handshake of the interchain account of `ICA_OWNER`, on channel-1
+/// of the host port, and funds the account with 100 MILLIS.
+fn open_host_channel() {
+	let channel_id = ChannelId::new(1);
+	let counterparty = ChanCounterParty::new(ica_controller_port(), Some(ChannelId::new(0)));
+	let version = with_module(HOST_MODULE_ID_STR, |module, ctx| {
+		module.on_chan_open_try(
+			ctx,
+			&mut ModuleOutputBuilder::new(),
+			Order::Ordered,
+			&[ConnectionId::new(0)],
+			&host_port_id(),
+			&channel_id,
+			&counterparty,
+			&ChanVersion::default(),
+			&IcaMetadata::new(&ConnectionId::new(1), &ConnectionId::new(0)).to_version(),
+			&hex_signer(REVERSE_RELAYER),
+		)
+	})
+	.unwrap();
+	let channel_end = ChannelEnd::new(
+		State::Open,
+		Order::Ordered,
+		counterparty,
+		vec![ConnectionId::new(0)],
+		version,
+	);
+	let mut ctx = Context::<Test>::default();
+	ctx.store_channel((host_port_id(), channel_id), &channel_end).unwrap();
+	ctx.store_next_sequence_recv((host_port_id(), channel_id), 1.into()).unwrap();
+	with_module(HOST_MODULE_ID_STR, |module, ctx| {
+		module.on_chan_open_confirm(
+			ctx,
+			&mut ModuleOutputBuilder::new(),
+			&host_port_id(),
+			&channel_id,
+			&hex_signer(REVERSE_RELAYER),
+		)
+	})
+	.unwrap();
+
+	let asset_id =
+		<<Test as Config>::IbcDenomToAssetIdConversion as DenomToAssetId<Test>>::from_denom_to_asset_id(
+			&"PICA".to_string(),
+		)
+		.unwrap();
+	<<Test as Config>::Fungibles as Mutate<<Test as frame_system::Config>::AccountId>>::mint_into(
+		asset_id,
+		&interchain_account(),
+		100 * MILLIS,
+	)
+	.unwrap();
+}
+
+fn transfer_call(amount: u128) -> Call {
+	Call::Assets(pallet_assets::Call::transfer {
+		id: 2,
+		target: AccountId32::new(ICA_RECEIVER),
+		amount,
+	})
+}
+
+/// The packet executing `calls` with the interchain account of `ICA_OWNER` on the host.
+fn interchain_account_tx_packet(calls: Vec<(&str, Call)>) -> Packet {
+	let messages = calls
+		.into_iter()
+		.map(|(type_url, call)| ibc_proto::google::protobuf::Any {
+			type_url: type_url.to_string(),
+			value: call.encode(),
+		})
+		.collect();
+	Packet {
+		sequence: 1u64.into(),
+		source_port: ica_controller_port(),
+		source_channel: ChannelId::new(0),
+		destination_port: host_port_id(),
+		destination_channel: ChannelId::new(1),
+		data: InterchainAccountPacketData::new(CosmosTx { messages }, String::new())
+			.to_json_bytes(),
+		timeout_height: Height::new(2000, 5),
+		timeout_timestamp: ibc::timestamp::Timestamp::from_nanoseconds(
+			1690894363u64.saturating_mul(1000000000),
+		)
+		.unwrap(),
+	}
+}
+
+/// Delivers the packet executing `calls` with the interchain account of `ICA_OWNER` on the host.
+fn recv_interchain_account_tx(calls: Vec<(&str, Call)>) {
+	let msg = MsgRecvPacket {
+		packet: interchain_account_tx_packet(calls),
+		proofs: Proofs::new(vec![0u8; 32].try_into().unwrap(), None, None, None, Height::new(0, 1))
+			.unwrap(),
+		signer: Signer::from_str(MODULE_ID).unwrap(),
+	};
+	let msg = Any { type_url: msg.type_url().as_bytes().to_vec(), value: msg.encode_vec() };
+	assert_ok!(Ibc::deliver(Origin::signed(AccountId32::new([0; 32])), vec![msg]));
+}
+
+fn recv_packet_failed() -> bool {
+	System::events()
+		.iter()
+		.any(|record| matches!(record.event, Event::Ibc(crate::Event::OnRecvPacketError { .. })))
+}
+
+#[test]
+fn host_registers_the_interchain_account_during_the_handshake() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&[]);
+		open_host_channel();
+		let connection_id = ConnectionId::new(0).as_bytes().to_vec();
+		let port_id = ica_controller_port().as_bytes().to_vec();
+		assert_eq!(
+			InterchainAccounts::<Test>::get(&connection_id, &port_id).unwrap(),
+			get_interchain_account_address(&ConnectionId::new(0), &ica_controller_port())
+				.into_bytes()
+		);
+		assert_eq!(
+			ActiveChannels::<Test>::get(&connection_id, &port_id).unwrap(),
+			ChannelId::new(1).to_string().into_bytes()
+		);
+	})
+}
+
+#[test]
+fn host_dispatches_the_calls_with_the_interchain_account() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&["/Assets.transfer"]);
+		open_host_channel();
+		recv_interchain_account_tx(vec![("/Assets.transfer", transfer_call(10 * MILLIS))]);
+		assert!(!recv_packet_failed());
+		// the transfer was signed by the interchain account
+		assert_eq!(pica_balance(ICA_RECEIVER), 10 * MILLIS);
+		assert_eq!(<Assets as Inspect<AccountId>>::balance(2, &interchain_account()), 90 * MILLIS);
+	})
+}
+
+#[test]
+fn host_only_dispatches_allowed_calls() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&["/System.remark"]);
+		open_host_channel();
+		recv_interchain_account_tx(vec![("/Assets.transfer", transfer_call(10 * MILLIS))]);
+		assert!(recv_packet_failed());
+		assert_eq!(pica_balance(ICA_RECEIVER), 0);
+	})
+}
+
+#[test]
+fn host_dispatches_any_call_allowed_by_a_wildcard() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&["*"]);
+		open_host_channel();
+		let remark = Call::System(frame_system::Call::remark { remark: vec![1, 2, 3] });
+		recv_interchain_account_tx(vec![
+			("/System.remark", remark),
+			("/Assets.transfer", transfer_call(10 * MILLIS)),
+		]);
+		assert!(!recv_packet_failed());
+		assert_eq!(pica_balance(ICA_RECEIVER), 10 * MILLIS);
+	})
+}
+
+#[test]
+fn host_rolls_back_a_transaction_with_a_failed_call() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&["*"]);
+		open_host_channel();
+		// the second transfer exceeds the balance of the interchain account
+		recv_interchain_account_tx(vec![
+			("/Assets.transfer", transfer_call(10 * MILLIS)),
+			("/Assets.transfer", transfer_call(1000 * MILLIS)),
+		]);
+		assert!(recv_packet_failed());
+		assert_eq!(pica_balance(ICA_RECEIVER), 0);
+		assert_eq!(<Assets as Inspect<AccountId>>::balance(2, &interchain_account()), 100 * MILLIS);
+	})
+}
+
+/// A remark wrapped in `depth` sudo calls.
+fn nested_call(depth: usize) -> Call {
+	(0..depth).fold(Call::System(frame_system::Call::remark { remark: vec![] }), |call, _| {
+		Call::Sudo(pallet_sudo::Call::sudo { call: Box::new(call) })
+	})
+}
+
+#[test]
+fn host_rejects_calls_nested_beyond_the_extrinsic_depth() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&["*"]);
+		open_host_channel();
+		let calls = vec![
+			("/Assets.transfer", transfer_call(10 * MILLIS)),
+			("/Sudo.sudo", nested_call(sp_api::MAX_EXTRINSIC_DEPTH as usize + 1)),
+		];
+		let packet = interchain_account_tx_packet(calls.clone());
+		// the nested call isn't decoded, only the transfer is weighed
+		assert_eq!(
+			crate::ics27::WeightHandler::<Test>::default().on_recv_packet(&packet),
+			<Test as Config>::WeightInfo::on_recv_packet()
+				.saturating_add(transfer_call(10 * MILLIS).get_dispatch_info().weight)
+		);
+		recv_interchain_account_tx(calls);
+		assert!(recv_packet_failed());
+		assert_eq!(pica_balance(ICA_RECEIVER), 0);
+	})
+}
+
+#[test]
+fn interchain_account_weight_only_includes_allowed_calls() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&["/System.remark"]);
+		let remark = Call::System(frame_system::Call::remark { remark: vec![] });
+		let packet = interchain_account_tx_packet(vec![
+			("/System.remark", remark.clone()),
+			("/Assets.transfer", transfer_call(10 * MILLIS)),
+		]);
+		assert_eq!(
+			crate::ics27::WeightHandler::<Test>::default().on_recv_packet(&packet),
+			<Test as Config>::WeightInfo::on_recv_packet()
+				.saturating_add(remark.get_dispatch_info().weight)
+		);
+	})
+}
+
+/// Registers the interchain account of `ICA_OWNER` on channel-0 of its controller port, and
+/// acknowledges the handshake with the address set by the host.
+fn register_interchain_account() {
+	assert_ok!(Ibc::register_interchain_account(
+		Origin::signed(AccountId32::new(ICA_OWNER)),
+		ConnectionId::new(0).as_bytes().to_vec()
+	));
+	let channel_end = Context::<Test>::default()
+		.channel_end(&(ica_controller_port(), ChannelId::new(0)))
+		.unwrap();
+	assert_eq!(channel_end.state, State::Init);
+	assert_eq!(channel_end.ordering, Order::Ordered);
+
+	let metadata = IcaMetadata {
+		address: get_interchain_account_address(&ConnectionId::new(1), &ica_controller_port()),
+		..IcaMetadata::new(&ConnectionId::new(0), &ConnectionId::new(1))
+	};
+	with_module(CONTROLLER_MODULE_ID_STR, |module, ctx| {
+		module.on_chan_open_ack(
+			ctx,
+			&mut ModuleOutputBuilder::new(),
+			&ica_controller_port(),
+			&ChannelId::new(0),
+			&metadata.to_version(),
+			&hex_signer(REVERSE_RELAYER),
+		)
+	})
+	.unwrap();
+}
+
+#[test]
+fn controller_registers_the_interchain_account_once_the_handshake_is_acknowledged() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&[]);
+		register_interchain_account();
+		let connection_id = ConnectionId::new(0).as_bytes().to_vec();
+		let port_id = ica_controller_port().as_bytes().to_vec();
+		assert_eq!(
+			InterchainAccounts::<Test>::get(&connection_id, &port_id).unwrap(),
+			get_interchain_account_address(&ConnectionId::new(1), &ica_controller_port())
+				.into_bytes()
+		);
+		assert_eq!(
+			ActiveChannels::<Test>::get(&connection_id, &port_id).unwrap(),
+			ChannelId::new(0).to_string().into_bytes()
+		);
+	})
+}
+
+#[test]
+fn interchain_account_channel_is_closed_by_a_timeout() {
+	new_test_ext().execute_with(|| {
+		setup_interchain_accounts(&[]);
+		register_interchain_account();
+		// the core handler opens the channel once the callback succeeds
+		let port_id = ica_controller_port();
+		let channel_id = ChannelId::new(0);
+		let mut ctx = Context::<Test>::default();
+		let mut channel_end = ctx.channel_end(&(port_id.clone(), channel_id)).unwrap();
+		channel_end.state = State::Open;
+		channel_end.remote = ChanCounterParty::new(host_port_id(), Some(ChannelId::new(1)));
+		ctx.store_channel((port_id.clone(), channel_id), &channel_end).unwrap();
+		ctx.store_next_sequence_send((port_id.clone(), channel_id), 1.into()).unwrap();
+
+		let (height, timestamp) =
+			<Ibc as IbcHandler<AccountId>>::latest_height_and_timestamp(&port_id, &channel_id)
+				.unwrap();
+		let remark = ibc_proto::google::protobuf::Any {
+			type_url: "/System.remark".to_string(),
+			value: Call::System(frame_system::Call::remark { remark: vec![] }).encode(),
+		};
+		assert_ok!(Ibc::send_interchain_account_tx(
+			Origin::signed(AccountId32::new(ICA_OWNER)),
+			InterchainAccountTxParams {
+				connection_id: ConnectionId::new(0).as_bytes().to_vec(),
+				messages: vec![Any {
+					type_url: remark.type_url.as_bytes().to_vec(),
+					value: remark.value.clone(),
+				}],
+				memo: vec![],
+				timeout: Timeout::Offset { timestamp: Some(1000), height: Some(5) },
+			}
+		));
+
+		// the host chain passed the timeout height without receiving the packet
+		let proof_height = Height::new(0, 10);
+		ctx.store_consensus_state(
+			ClientId::new(&MockClientState::client_type(), 0).unwrap(),
+			proof_height,
+			AnyConsensusState::Mock(MockConsensusState::new(MockHeader::new(proof_height))),
+		)
+		.unwrap();
+		let packet = Packet {
+			sequence: 1u64.into(),
+			source_port: port_id.clone(),
+			source_channel: channel_id,
+			destination_port: host_port_id(),
+			destination_channel: ChannelId::new(1),
+			data: InterchainAccountPacketData::new(
+				CosmosTx { messages: vec![remark] },
+				String::new(),
+			)
+			.to_json_bytes(),
+			timeout_height: height.add(5),
+			timeout_timestamp: (timestamp + Duration::from_nanos(1000)).unwrap(),
+		};
+		let msg = MsgTimeout {
+			packet,
+			next_sequence_recv: 1u64.into(),
+			proofs: Proofs::new(vec![0u8; 32].try_into().unwrap(), None, None, None, proof_height)
+				.unwrap(),
+			signer: Signer::from_str(MODULE_ID).unwrap(),
+		};
+		let msg = Any { type_url: msg.type_url().as_bytes().to_vec(), value: msg.encode_vec() };
+		assert_ok!(Ibc::deliver(Origin::signed(AccountId32::new([0; 32])), vec![msg]));
+
+		let channel_end = ctx.channel_end(&(port_id.clone(), channel_id)).unwrap();
+		assert_eq!(channel_end.state, State::Closed);
+		assert!(System::events().iter().any(|record| matches!(
+			record.event,
+			Event::Ibc(crate::Event::InterchainAccountTxTimedOut { .. })
+		)));
+		// the account can be registered again, on a new channel
+		assert_ok!(Ibc::register_interchain_account(
+			Origin::signed(AccountId32::new(ICA_OWNER)),
+			ConnectionId::new(0).as_bytes().to_vec()
+		));
+		assert_eq!(ctx.channel_end(&(port_id, ChannelId::new(1))).unwrap().state, State::Init);
+	})
+}
+
+const FORWARDED_AMOUNT: u128 = 100 * MILLIS;
+
+fn account(signer: Signer) -> AccountId32 {
+	<Test as Config>::AccountIdConversion::try_from(signer)
+		.map_err(|_| ())
+		.unwrap()
+		.into_account()
+}
+
+/// The account that receives the tokens of packets forwarded from channel-0.
+fn forward_intermediate_account() -> AccountId32 {
+	account(
+		get_forward_intermediate_address(
+			&PortId::transfer(),
+			ChannelId::new(0),
+			&Signer::from_str("alice").unwrap(),
+		)
+		.unwrap(),
+	)
+}
+
+fn channel_2_escrow_account() -> AccountId32 {
+	account(get_channel_escrow_address(&PortId::transfer(), ChannelId::new(2)).unwrap())
+}
+
+/// Opens channel-2 next to channel-0, for the tokens received on channel-0 to be forwarded on.
+fn setup_forward_channels() {
+	setup_client_and_consensus_state(PortId::transfer());
+	frame_system::Pallet::<Test>::set_block_number(1u32);
+	Ibc::set_params(Origin::root(), PalletParams { send_enabled: true, receive_enabled: true })
+		.unwrap();
+	<<Test as Config>::IbcDenomToAssetIdConversion as DenomToAssetId<Test>>::from_denom_to_asset_id(
+		&"PICA".to_string(),
+	)
+	.unwrap();
+	let channel_end = ChannelEnd::new(
+		State::Open,
+		Order::Unordered,
+		ChanCounterParty::new(PortId::transfer(), Some(ChannelId::new(3))),
+		vec![ConnectionId::new(0)],
+		ChanVersion::new(VERSION.to_string()),
+	);
+	let mut ctx = Context::<Test>::default();
+	ctx.store_channel((PortId::transfer(), ChannelId::new(2)), &channel_end)
+		.unwrap();
+	ctx.store_next_sequence_send((PortId::transfer(), ChannelId::new(2)), 1.into())
+		.unwrap();
+}
+
+/// A memo forwarding the tokens to `bob` over channel-2, with `next` as the memo of the forwarded
+/// packet.
+fn forward_memo(next: Option<serde_json::Value>) -> String {
+	let mut forward =
+		serde_json::json!({ "receiver": "bob", "port": "transfer", "channel": "channel-2" });
+	if let Some(next) = next {
+		forward["next"] = next;
+	}
+	serde_json::json!({ "forward": forward }).to_string()
+}
+
+/// Delivers a packet transferring `uatom` from the counterparty of channel-0, with `memo`.
+fn recv_transfer_with_memo(memo: String) {
+	let packet_data = PacketData {
+		token: Coin {
+			denom: PrefixedDenom::from_str("uatom").unwrap(),
+			amount: ibc::applications::transfer::Amount::from_str(&FORWARDED_AMOUNT.to_string())
+				.unwrap(),
+		},
+		sender: Signer::from_str("alice").unwrap(),
+		receiver: Signer::from_str("pallet_ibc").unwrap(),
+		memo,
+	};
+	let packet = Packet {
+		sequence: 1u64.into(),
+		source_port: PortId::transfer(),
+		source_channel: ChannelId::new(1),
+		destination_port: PortId::transfer(),
+		destination_channel: ChannelId::new(0),
+		data: serde_json::to_vec(&packet_data).unwrap(),
+		timeout_height: Height::new(2000, 5),
+		timeout_timestamp: ibc::timestamp::Timestamp::from_nanoseconds(
+			1690894363u64.saturating_mul(1000000000),
+		)
+		.unwrap(),
+	};
+	let msg = MsgRecvPacket {
+		packet,
+		proofs: Proofs::new(vec![0u8; 32].try_into().unwrap(), None, None, None, Height::new(0, 1))
+			.unwrap(),
+		signer: Signer::from_str(MODULE_ID).unwrap(),
+	};
+	let msg = Any { type_url: msg.type_url().as_bytes().to_vec(), value: msg.encode_vec() };
+	assert_ok!(Ibc::deliver(Origin::signed(AccountId32::new([0; 32])), vec![msg]));
+}
+
+/// The packet sent on channel-2 to forward the tokens received on channel-0.
+fn forwarded_packet(memo: &str) -> Packet {
+	let (_, timestamp) = <Ibc as IbcHandler<AccountId>>::latest_height_and_timestamp(
+		&PortId::transfer(),
+		&ChannelId::new(2),
+	)
+	.unwrap();
+	let packet_data = PacketData {
+		token: Coin {
+			denom: PrefixedDenom::from_str("transfer/channel-0/uatom").unwrap(),
+			amount: ibc::applications::transfer::Amount::from_str(&FORWARDED_AMOUNT.to_string())
+				.unwrap(),
+		},
+		sender: get_forward_intermediate_address(
+			&PortId::transfer(),
+			ChannelId::new(0),
+			&Signer::from_str("alice").unwrap(),
+		)
+		.unwrap(),
+		receiver: Signer::from_str("bob").unwrap(),
+		memo: memo.to_string(),
+	};
+	Packet {
+		sequence: 1u64.into(),
+		source_port: PortId::transfer(),
+		source_channel: ChannelId::new(2),
+		destination_port: PortId::transfer(),
+		destination_channel: ChannelId::new(3),
+		data: serde_json::to_vec(&packet_data).unwrap(),
+		timeout_height: Height::zero(),
+		timeout_timestamp: (timestamp + DEFAULT_FORWARD_TIMEOUT).unwrap(),
+	}
+}
+
+/// The acknowledgement commitment of the packet received on channel-0, if it was written.
+fn received_packet_ack() -> Option<Vec<u8>> {
+	Context::<Test>::default()
+		.get_packet_acknowledgement(&(PortId::transfer(), ChannelId::new(0), 1u64.into()))
+		.ok()
+		.map(|commitment| commitment.into_vec())
+}
+
+fn ack_commitment(ack: Ics20Acknowledgement) -> Vec<u8> {
+	Context::<Test>::default()
+		.ack_commitment(GenericAcknowledgement::from_bytes(ack.as_ref().to_vec()))
+		.into_vec()
+}
+
+/// The tokens that couldn't be forwarded are returned to channel-0, and an error
+/// acknowledgement is written for the counterparty to refund the sender.
+fn assert_forward_refunded() {
+	assert_eq!(
+		received_packet_ack(),
+		Some(ack_commitment(Ics20Acknowledgement::from_error(
+			Ics20Error::implementation_specific(
+				"forwarding packet 1 on transfer/channel-2 failed".to_string()
+			)
+		)))
+	);
+	assert_eq!(pica_balance(forward_intermediate_account().into()), 0);
+	assert_eq!(pica_balance(channel_2_escrow_account().into()), 0);
+	assert!(System::events().iter().any(|record| matches!(
+		record.event,
+		Event::Ibc(crate::Event::ForwardedPacketAcknowledged { success: false, .. })
+	)));
+}
+
+#[test]
+fn forwarded_packet_is_acknowledged_once_the_next_hop_acknowledges_it() {
+	new_test_ext().execute_with(|| {
+		setup_forward_channels();
+		recv_transfer_with_memo(forward_memo(None));
+		// the tokens were passed on to channel-2, the acknowledgement waits for the next hop
+		assert_eq!(pica_balance(forward_intermediate_account().into()), 0);
+		assert_eq!(pica_balance(channel_2_escrow_account().into()), FORWARDED_AMOUNT);
+		assert_eq!(received_packet_ack(), None);
+
+		with_transfer_module(|module, ctx| {
+			module.on_acknowledgement_packet(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				&forwarded_packet(""),
+				&GenericAcknowledgement::from_bytes(ACK_SUCCESS_B64.to_vec()),
+				&hex_signer(REVERSE_RELAYER),
+			)
+		})
+		.unwrap();
+		assert_eq!(received_packet_ack(), Some(ack_commitment(Ics20Acknowledgement::success())));
+		assert_eq!(pica_balance(channel_2_escrow_account().into()), FORWARDED_AMOUNT);
+	})
+}
+
+#[test]
+fn forwarded_packet_is_refunded_on_an_error_acknowledgement() {
+	new_test_ext().execute_with(|| {
+		setup_forward_channels();
+		recv_transfer_with_memo(forward_memo(None));
+		with_transfer_module(|module, ctx| {
+			module.on_acknowledgement_packet(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				&forwarded_packet(""),
+				&GenericAcknowledgement::from_bytes(
+					Ics20Acknowledgement::from_error(Ics20Error::invalid_token()).as_ref().to_vec(),
+				),
+				&hex_signer(REVERSE_RELAYER),
+			)
+		})
+		.unwrap();
+		assert_forward_refunded();
+	})
+}
+
+#[test]
+fn forwarded_packet_is_refunded_on_timeout() {
+	new_test_ext().execute_with(|| {
+		setup_forward_channels();
+		recv_transfer_with_memo(forward_memo(None));
+		with_transfer_module(|module, ctx| {
+			module.on_timeout_packet(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				&forwarded_packet(""),
+				&hex_signer(TIMEOUT_RELAYER),
+			)
+		})
+		.unwrap();
+		assert_forward_refunded();
+	})
+}
+
+#[test]
+fn multi_hop_forward_passes_the_next_instruction_on() {
+	new_test_ext().execute_with(|| {
+		setup_forward_channels();
+		let next = serde_json::json!({
+			"forward": { "receiver": "carol", "port": "transfer", "channel": "channel-7" }
+		});
+		recv_transfer_with_memo(forward_memo(Some(next.clone())));
+
+		// the forwarded packet carries the instruction for the next hop
+		let packet = forwarded_packet(&next.to_string());
+		let ctx = Context::<Test>::default();
+		assert_eq!(
+			ctx.get_packet_commitment(&(PortId::transfer(), ChannelId::new(2), 1u64.into()))
+				.unwrap(),
+			ctx.packet_commitment(
+				packet.data.clone(),
+				packet.timeout_height,
+				packet.timeout_timestamp
+			)
+		);
+
+		// a failure further along the route is reported back as an error acknowledgement
+		with_transfer_module(|module, ctx| {
+			module.on_acknowledgement_packet(
+				ctx,
+				&mut ModuleOutputBuilder::new(),
+				&packet,
+				&GenericAcknowledgement::from_bytes(
+					Ics20Acknowledgement::from_error(Ics20Error::implementation_specific(
+						"forwarding packet 1 on transfer/channel-7 failed".to_string(),
+					))
+					.as_ref()
+					.to_vec(),
+				),
+				&hex_signer(REVERSE_RELAYER),
+			)
+		})
+		.unwrap();
+		assert_forward_refunded();
+	})
+}
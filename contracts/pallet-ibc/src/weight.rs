@@ -1,18 +1,24 @@
 use super::*;
-use crate::{light_clients::AnyClientMessage, routing::Context};
+use crate::{
+	light_clients::{AnyClientMessage, AnyClientState, WasmCodeStorage},
+	routing::Context,
+};
 use core::marker::PhantomData;
-use frame_support::pallet_prelude::Weight;
+use frame_support::{pallet_prelude::Weight, traits::Get};
 use grandpa_client_primitives::justification::GrandpaJustification;
 use ibc::core::{
-	ics02_client::msgs::ClientMsg,
+	ics02_client::{context::ClientReader, msgs::ClientMsg},
 	ics03_connection::{context::ConnectionReader, msgs::ConnectionMsg},
 	ics04_channel::msgs::{ChannelMsg, PacketMsg},
-	ics24_host::identifier::ClientId,
+	ics24_host::identifier::{ChannelId, ClientId, PortId},
 	ics26_routing::msgs::Ics26Envelope,
 };
 use ibc_primitives::{client_id_from_bytes, CallbackWeight};
+use ics08_wasm::executor::Schedule;
 use ics10_grandpa::client_message::{ClientMessage, RelayChainHeader};
 use scale_info::prelude::string::ToString;
+use sp_core::H256;
+use sp_runtime::Perbill;
 
 pub trait WeightInfo {
 	fn create_client() -> Weight;
@@ -32,6 +38,8 @@ pub trait WeightInfo {
 	fn timeout_packet_tendermint(i: u32) -> Weight;
 	fn set_params() -> Weight;
 	fn transfer() -> Weight;
+	fn register_interchain_account() -> Weight;
+	fn send_interchain_account_tx() -> Weight;
 	fn on_chan_open_init() -> Weight;
 	fn on_chan_open_try() -> Weight;
 	fn on_recv_packet() -> Weight;
@@ -42,6 +50,12 @@ pub trait WeightInfo {
 	fn on_acknowledgement_packet() -> Weight;
 	fn on_timeout_packet() -> Weight;
 	fn update_grandpa_client(i: u32) -> Weight;
+	fn wasm_client_compile(c: u32) -> Weight;
+	fn wasm_client_fuel(f: u32) -> Weight;
+	fn wasm_client_ed25519_verify() -> Weight;
+	fn wasm_client_sr25519_verify() -> Weight;
+	fn wasm_client_secp256k1_verify() -> Weight;
+	fn wasm_client_hash(n: u32) -> Weight;
 }
 
 impl WeightInfo for () {
@@ -113,6 +127,14 @@ impl WeightInfo for () {
 		0
 	}
 
+	fn register_interchain_account() -> Weight {
+		0
+	}
+
+	fn send_interchain_account_tx() -> Weight {
+		0
+	}
+
 	fn on_chan_open_init() -> Weight {
 		0
 	}
@@ -152,6 +174,30 @@ impl WeightInfo for () {
 	fn update_grandpa_client(_i: u32) -> Weight {
 		0
 	}
+
+	fn wasm_client_compile(_c: u32) -> Weight {
+		0
+	}
+
+	fn wasm_client_fuel(_f: u32) -> Weight {
+		0
+	}
+
+	fn wasm_client_ed25519_verify() -> Weight {
+		0
+	}
+
+	fn wasm_client_sr25519_verify() -> Weight {
+		0
+	}
+
+	fn wasm_client_secp256k1_verify() -> Weight {
+		0
+	}
+
+	fn wasm_client_hash(_n: u32) -> Weight {
+		0
+	}
 }
 
 pub struct WeightRouter<T: Config>(PhantomData<T>);
@@ -161,6 +207,11 @@ impl<T: Config> WeightRouter<T> {
 		match port_id {
 			ibc::applications::transfer::PORT_ID_STR =>
 				Some(Box::new(ics20::WeightHandler::<T>::default())),
+			ibc::applications::interchain_accounts::HOST_PORT_ID_STR =>
+				Some(Box::new(ics27::WeightHandler::<T>::default())),
+			_ if port_id
+				.starts_with(ibc::applications::interchain_accounts::CONTROLLER_PORT_PREFIX) =>
+				Some(Box::new(ics27::WeightHandler::<T>::default())),
 			_ => None,
 		}
 	}
@@ -180,6 +231,88 @@ pub fn channel_client<T: Config>(channel_id: &[u8], port_id: &[u8]) -> Result<Cl
 	Err(Error::<T>::Other)
 }
 
+/// Fuel the `wasm_client_fuel` benchmark is sampled at to derive the weight of a unit of fuel.
+const WASM_CLIENT_FUEL_SAMPLE: u32 = 1_000_000;
+/// Bytes the `wasm_client_hash` benchmark is sampled at to derive the weight of hashing a byte.
+const WASM_CLIENT_HASH_SAMPLE: u32 = 1_024;
+/// Share of the block a single call into a wasm light client contract may take up, a client
+/// update calls the contract three times.
+const WASM_CLIENT_CALL_SHARE: Perbill = Perbill::from_percent(5);
+
+/// Derives the schedule of the wasm light client contracts from the benchmarked weights. The fuel
+/// limit keeps a call within [`WASM_CLIENT_CALL_SHARE`] of the block, and every host function is
+/// charged at least the fuel its weight is worth.
+pub fn wasm_client_schedule<T: Config>() -> Schedule {
+	let weight_per_fuel = (<T as Config>::WeightInfo::wasm_client_fuel(WASM_CLIENT_FUEL_SAMPLE)
+		.saturating_sub(<T as Config>::WeightInfo::wasm_client_fuel(0)) /
+		WASM_CLIENT_FUEL_SAMPLE as Weight)
+		.max(1);
+	let fuel = |weight: Weight| weight.saturating_add(weight_per_fuel - 1) / weight_per_fuel;
+	let hash = <T as Config>::WeightInfo::wasm_client_hash(0);
+	let hash_per_byte = <T as Config>::WeightInfo::wasm_client_hash(WASM_CLIENT_HASH_SAMPLE)
+		.saturating_sub(hash) /
+		WASM_CLIENT_HASH_SAMPLE as Weight;
+	let max_block = <T as frame_system::Config>::BlockWeights::get().max_block;
+	Schedule {
+		fuel_limit: (WASM_CLIENT_CALL_SHARE * max_block) / weight_per_fuel,
+		ed25519_verify: fuel(<T as Config>::WeightInfo::wasm_client_ed25519_verify()),
+		sr25519_verify: fuel(<T as Config>::WeightInfo::wasm_client_sr25519_verify()),
+		secp256k1_verify: fuel(<T as Config>::WeightInfo::wasm_client_secp256k1_verify()),
+		hash: fuel(hash),
+		hash_per_byte: fuel(hash_per_byte),
+	}
+}
+
+/// Weight of `calls` calls into the contract of the wasm client `client_id`, every call is
+/// charged for compiling the contract and for the most fuel it may consume.
+fn wasm_client_weight<T: Config + Send + Sync>(client_id: &ClientId, calls: u64) -> Weight
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+{
+	let ctx = routing::Context::<T>::new();
+	let code_len = match ctx.client_state(client_id) {
+		Ok(AnyClientState::Wasm(client_state)) if client_state.code_hash.len() == 32 =>
+			WasmCodeStorage::decode_len(H256::from_slice(&client_state.code_hash))
+				.unwrap_or_default(),
+		_ => return Weight::default(),
+	};
+	let fuel_limit = u32::try_from(wasm_client_schedule::<T>().fuel_limit).unwrap_or(u32::MAX);
+	<T as Config>::WeightInfo::wasm_client_compile(code_len as u32)
+		.saturating_add(<T as Config>::WeightInfo::wasm_client_fuel(fuel_limit))
+		.saturating_mul(calls)
+}
+
+/// Channel upgrade messages aren't benchmarked, they're charged like the channel handshake
+/// message that runs the same module callback and verifies proofs against the same client.
+fn channel_upgrade_weight<T: Config + Send + Sync>(
+	port_id: &PortId,
+	channel_id: &ChannelId,
+	cb_weight: impl FnOnce(&dyn CallbackWeight) -> Weight,
+) -> Weight
+where
+	u32: From<<T as frame_system::Config>::BlockNumber>,
+{
+	let cb = WeightRouter::<T>::get_weight(port_id.as_str()).unwrap_or_else(|| Box::new(()));
+	let cb_weight = cb_weight(cb.as_ref());
+	let lc_verification_weight =
+		match channel_client::<T>(channel_id.to_string().as_bytes(), port_id.as_bytes()) {
+			Ok(client_id) => {
+				let client_type = client_id
+					.as_str()
+					.rsplit_once('-')
+					.map(|(client_type_str, ..)| client_type_str);
+				match client_type {
+					Some(ty) if ty.contains("wasm") => wasm_client_weight::<T>(&client_id, 2),
+					Some(ty) if ty.contains("tendermint") =>
+						<T as Config>::WeightInfo::channel_open_ack_tendermint(),
+					_ => Weight::default(),
+				}
+			},
+			Err(_) => Weight::default(),
+		};
+	cb_weight.saturating_add(lc_verification_weight)
+}
+
 pub(crate) fn deliver<T: Config + Send + Sync>(msgs: &[Any]) -> Weight
 where
 	u32: From<<T as frame_system::Config>::BlockNumber>,
@@ -204,6 +337,8 @@ where
 							.rsplit_once('-')
 							.map(|(client_type_str, ..)| client_type_str);
 						match client_type {
+							Some(ty) if ty.contains("wasm") =>
+								wasm_client_weight::<T>(&msg.client_id, 3),
 							Some(ty) if ty.contains("tendermint") =>
 								<T as Config>::WeightInfo::update_tendermint_client(),
 							Some(ty) if ty.contains("grandpa") => match msg.client_message {
@@ -237,6 +372,8 @@ where
 							.rsplit_once('-')
 							.map(|(client_type_str, ..)| client_type_str);
 						match client_type {
+							Some(ty) if ty.contains("wasm") =>
+								wasm_client_weight::<T>(&msg.client_id, 3),
 							Some(ty) if ty.contains("tendermint") =>
 								<T as Config>::WeightInfo::conn_try_open_tendermint(),
 							_ => Weight::default(),
@@ -252,6 +389,8 @@ where
 							.rsplit_once('-')
 							.map(|(client_type_str, ..)| client_type_str);
 						match client_type {
+							Some(ty) if ty.contains("wasm") =>
+								wasm_client_weight::<T>(connection_end.client_id(), 3),
 							Some(ty) if ty.contains("tendermint") =>
 								<T as Config>::WeightInfo::conn_open_ack_tendermint(),
 							_ => Weight::default(),
@@ -267,6 +406,8 @@ where
 							.rsplit_once('-')
 							.map(|(client_type_str, ..)| client_type_str);
 						match client_type {
+							Some(ty) if ty.contains("wasm") =>
+								wasm_client_weight::<T>(connection_end.client_id(), 1),
 							Some(ty) if ty.contains("tendermint") =>
 								<T as Config>::WeightInfo::conn_open_confirm_tendermint(),
 							_ => Weight::default(),
@@ -315,6 +456,8 @@ where
 										.rsplit_once('-')
 										.map(|(client_type_str, ..)| client_type_str);
 									match client_type {
+										Some(ty) if ty.contains("wasm") =>
+											wasm_client_weight::<T>(connection_end.client_id(), 1),
 										Some(ty) if ty.contains("tendermint") =>
 											<T as Config>::WeightInfo::channel_open_try_tendermint(),
 										_ => Weight::default(),
@@ -339,6 +482,8 @@ where
 									.rsplit_once('-')
 									.map(|(client_type_str, ..)| client_type_str);
 								match client_type {
+									Some(ty) if ty.contains("wasm") =>
+										wasm_client_weight::<T>(&client_id, 1),
 									Some(ty) if ty.contains("tendermint") =>
 										<T as Config>::WeightInfo::channel_open_ack_tendermint(),
 									_ => Weight::default(),
@@ -363,6 +508,8 @@ where
 									.rsplit_once('-')
 									.map(|(client_type_str, ..)| client_type_str);
 								match client_type {
+									Some(ty) if ty.contains("wasm") =>
+										wasm_client_weight::<T>(&client_id, 1),
 									Some(ty) if ty.contains("tendermint") =>
 										<T as Config>::WeightInfo::channel_open_confirm_tendermint(),
 									_ => Weight::default(),
@@ -411,6 +558,8 @@ where
 									.rsplit_once('-')
 									.map(|(client_type_str, ..)| client_type_str);
 								match client_type {
+									Some(ty) if ty.contains("wasm") =>
+										wasm_client_weight::<T>(&client_id, 1),
 									Some(ty) if ty.contains("tendermint") =>
 										<T as Config>::WeightInfo::channel_close_confirm_tendermint(
 										),
@@ -421,6 +570,41 @@ where
 						};
 						cb_weight.saturating_add(lc_verification_weight)
 					},
+					ChannelMsg::ChannelUpgradeInit(channel_msg) => channel_upgrade_weight::<T>(
+						&channel_msg.port_id,
+						&channel_msg.channel_id,
+						|cb| cb.on_chan_open_init(),
+					),
+					ChannelMsg::ChannelUpgradeTry(channel_msg) => channel_upgrade_weight::<T>(
+						&channel_msg.port_id,
+						&channel_msg.channel_id,
+						|cb| cb.on_chan_open_try(),
+					),
+					ChannelMsg::ChannelUpgradeAck(channel_msg) => channel_upgrade_weight::<T>(
+						&channel_msg.port_id,
+						&channel_msg.channel_id,
+						|cb| cb.on_chan_open_ack(&channel_msg.port_id, &channel_msg.channel_id),
+					),
+					ChannelMsg::ChannelUpgradeConfirm(channel_msg) => channel_upgrade_weight::<T>(
+						&channel_msg.port_id,
+						&channel_msg.channel_id,
+						|cb| cb.on_chan_open_confirm(&channel_msg.port_id, &channel_msg.channel_id),
+					),
+					ChannelMsg::ChannelUpgradeOpen(channel_msg) => channel_upgrade_weight::<T>(
+						&channel_msg.port_id,
+						&channel_msg.channel_id,
+						|cb| cb.on_chan_open_confirm(&channel_msg.port_id, &channel_msg.channel_id),
+					),
+					ChannelMsg::ChannelUpgradeTimeout(channel_msg) => channel_upgrade_weight::<T>(
+						&channel_msg.port_id,
+						&channel_msg.channel_id,
+						|_| Weight::default(),
+					),
+					ChannelMsg::ChannelUpgradeCancel(channel_msg) => channel_upgrade_weight::<T>(
+						&channel_msg.port_id,
+						&channel_msg.channel_id,
+						|_| Weight::default(),
+					),
 				},
 				Ics26Envelope::Ics4PacketMsg(msgs) => match msgs {
 					PacketMsg::RecvPacket(packet_msg) => {
@@ -439,6 +623,8 @@ where
 									.rsplit_once('-')
 									.map(|(client_type_str, ..)| client_type_str);
 								match client_type {
+									Some(ty) if ty.contains("wasm") =>
+										wasm_client_weight::<T>(&client_id, 1),
 									Some(ty) if ty.contains("tendermint") =>
 										<T as Config>::WeightInfo::recv_packet_tendermint(
 											packet_msg.packet.data.len() as u32,
@@ -469,6 +655,8 @@ where
 									.rsplit_once('-')
 									.map(|(client_type_str, ..)| client_type_str);
 								match client_type {
+									Some(ty) if ty.contains("wasm") =>
+										wasm_client_weight::<T>(&client_id, 1),
 									Some(ty) if ty.contains("tendermint") =>
 										<T as Config>::WeightInfo::ack_packet_tendermint(
 											packet_msg.packet.data.len() as u32,
@@ -497,6 +685,8 @@ where
 									.rsplit_once('-')
 									.map(|(client_type_str, ..)| client_type_str);
 								match client_type {
+									Some(ty) if ty.contains("wasm") =>
+										wasm_client_weight::<T>(&client_id, 1),
 									Some(ty) if ty.contains("tendermint") =>
 										<T as Config>::WeightInfo::timeout_packet_tendermint(
 											packet_msg.packet.data.len() as u32,
@@ -524,6 +714,8 @@ where
 									.rsplit_once('-')
 									.map(|(client_type_str, ..)| client_type_str);
 								match client_type {
+									Some(ty) if ty.contains("wasm") =>
+										wasm_client_weight::<T>(&client_id, 2),
 									Some(ty) if ty.contains("tendermint") =>
 										<T as Config>::WeightInfo::timeout_packet_tendermint(
 											packet_msg.packet.data.len() as u32,
@@ -0,0 +1,202 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Monitoring of the balances of the relayer accounts on each chain.
+
+use metrics::data::BalanceMetrics;
+use primitives::{AccountBalance, Chain, LowBalanceState};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Interval at which balances are queried when none is configured.
+pub const DEFAULT_BALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Minimum balance of every relayer account on a chain, below which nothing is submitted to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceThreshold {
+	/// Name of the chain
+	pub chain: String,
+	/// Denomination of the balance, e.g `UNIT` or `stake`
+	pub denom: String,
+	/// Minimum amount, in the smallest unit of the denomination
+	pub min_amount: u128,
+}
+
+/// Returned instead of submitting messages to a chain whose relayer balance is too low.
+#[derive(Debug, thiserror::Error)]
+#[error("Relayer balance on {chain} is below the configured threshold")]
+pub struct LowBalance {
+	pub chain: String,
+}
+
+/// Balance of a relayer account that is below one of the thresholds.
+#[derive(Debug)]
+struct Shortfall<'a> {
+	account: &'a str,
+	amount: u128,
+	threshold: &'a BalanceThreshold,
+}
+
+/// Returns an error if the relayer balance on `chain` is below one of its thresholds.
+pub fn ensure_sufficient_balance(chain: &impl Chain) -> Result<(), LowBalance> {
+	if chain.low_balance().is_low() {
+		return Err(LowBalance { chain: chain.name().to_string() })
+	}
+	Ok(())
+}
+
+/// Periodically queries the balances of the relayer accounts on `chain`, exporting them to
+/// `metrics`. Submissions to the chain are refused while a balance is below one of the
+/// `thresholds` configured for it.
+pub async fn monitor_balance<C: Chain>(
+	chain: C,
+	thresholds: Vec<BalanceThreshold>,
+	metrics: Option<BalanceMetrics>,
+	interval: Duration,
+) {
+	let thresholds = thresholds
+		.into_iter()
+		.filter(|threshold| threshold.chain == chain.name())
+		.collect::<Vec<_>>();
+	let mut interval = tokio::time::interval(interval);
+	loop {
+		interval.tick().await;
+		let accounts = match chain.query_relayer_balances().await {
+			Ok(accounts) => accounts,
+			Err(err) => {
+				log::warn!("Failed to query the relayer balances on {}: {err:?}", chain.name());
+				continue
+			},
+		};
+		if let Some(metrics) = &metrics {
+			for account in &accounts {
+				for coin in &account.balances {
+					if let Ok(amount) = coin.amount.to_string().parse::<f64>() {
+						metrics
+							.relayer_balance
+							.with_label_values(&[&account.account, &coin.denom.to_string()])
+							.set(amount);
+					}
+				}
+			}
+		}
+
+		let shortfalls = shortfalls(&accounts, &thresholds);
+		if let Some(metrics) = &metrics {
+			metrics.relayer_balance_below_threshold.set(!shortfalls.is_empty() as u64);
+		}
+		update_balance_state(chain.name(), chain.low_balance(), &shortfalls);
+	}
+}
+
+/// Returns the balances of the `accounts` that are below one of the `thresholds`. Denominations
+/// an account doesn't hold count as a zero balance.
+fn shortfalls<'a>(
+	accounts: &'a [AccountBalance],
+	thresholds: &'a [BalanceThreshold],
+) -> Vec<Shortfall<'a>> {
+	let mut shortfalls = vec![];
+	for account in accounts {
+		for threshold in thresholds {
+			// balances too large for a u128 are above any threshold
+			let amount = account
+				.balances
+				.iter()
+				.find(|coin| coin.denom.to_string() == threshold.denom)
+				.map(|coin| coin.amount.to_string().parse::<u128>().unwrap_or(u128::MAX))
+				.unwrap_or_default();
+			if amount < threshold.min_amount {
+				shortfalls.push(Shortfall { account: &account.account, amount, threshold });
+			}
+		}
+	}
+	shortfalls
+}
+
+/// Pauses submissions to the chain while there are `shortfalls`, resuming them once the
+/// balances are topped up.
+fn update_balance_state(chain: &str, state: &LowBalanceState, shortfalls: &[Shortfall]) {
+	for Shortfall { account, amount, threshold } in shortfalls {
+		log::error!(
+			"Relayer balance of {account} on {chain} is {amount}{}, below the threshold of {}{}. Not submitting any messages until it's topped up",
+			threshold.denom,
+			threshold.min_amount,
+			threshold.denom
+		);
+	}
+	let low = !shortfalls.is_empty();
+	if state.set_low(low) && !low {
+		log::info!("Relayer balance on {chain} was topped up, resuming submissions");
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ibc::applications::transfer::PrefixedCoin;
+	use std::str::FromStr;
+
+	fn account(account: &str, balances: &[(&str, u128)]) -> AccountBalance {
+		AccountBalance {
+			account: account.to_owned(),
+			balances: balances
+				.iter()
+				.map(|(denom, amount)| PrefixedCoin::from_str(&format!("{amount}{denom}")).unwrap())
+				.collect(),
+		}
+	}
+
+	fn threshold(denom: &str, min_amount: u128) -> BalanceThreshold {
+		BalanceThreshold { chain: "test".to_owned(), denom: denom.to_owned(), min_amount }
+	}
+
+	#[test]
+	fn finds_balances_below_thresholds() {
+		let accounts = vec![
+			account("relayer", &[("UNIT", 100), ("stake", 5)]),
+			account("pool", &[("UNIT", 99)]),
+		];
+		let thresholds = vec![threshold("UNIT", 100), threshold("stake", 5)];
+		let shortfalls = shortfalls(&accounts, &thresholds)
+			.into_iter()
+			.map(|shortfall| {
+				(shortfall.account, shortfall.threshold.denom.as_str(), shortfall.amount)
+			})
+			.collect::<Vec<_>>();
+		// a missing denomination is a zero balance
+		assert_eq!(shortfalls, vec![("pool", "UNIT", 99), ("pool", "stake", 0)]);
+	}
+
+	#[test]
+	fn balances_without_thresholds_are_sufficient() {
+		let accounts = vec![account("relayer", &[("UNIT", 0)])];
+		assert!(shortfalls(&accounts, &[]).is_empty());
+	}
+
+	#[test]
+	fn pauses_submissions_until_topped_up() {
+		let state = LowBalanceState::default();
+		let thresholds = vec![threshold("UNIT", 100)];
+
+		let low = vec![account("relayer", &[("UNIT", 10)])];
+		update_balance_state("test", &state, &shortfalls(&low, &thresholds));
+		assert!(state.is_low());
+		// clones share the state with the chain client
+		assert!(state.clone().is_low());
+
+		let topped_up = vec![account("relayer", &[("UNIT", 100)])];
+		update_balance_state("test", &state, &shortfalls(&topped_up, &thresholds));
+		assert!(!state.is_low());
+	}
+}
@@ -34,14 +34,17 @@ use ibc::{
 };
 use ibc_proto::{
 	google::protobuf::Any,
-	ibc::core::{
-		channel::v1::{
-			QueryChannelResponse, QueryChannelsResponse, QueryNextSequenceReceiveResponse,
-			QueryPacketAcknowledgementResponse, QueryPacketCommitmentResponse,
-			QueryPacketReceiptResponse,
+	ibc::{
+		applications::transfer::v1::DenomTrace,
+		core::{
+			channel::v1::{
+				QueryChannelResponse, QueryChannelsResponse, QueryNextSequenceReceiveResponse,
+				QueryPacketAcknowledgementResponse, QueryPacketCommitmentResponse,
+				QueryPacketReceiptResponse, QueryUpgradeErrorResponse, QueryUpgradeResponse,
+			},
+			client::v1::{QueryClientStateResponse, QueryConsensusStateResponse},
+			connection::v1::{IdentifiedConnection, QueryConnectionResponse},
 		},
-		client::v1::{QueryClientStateResponse, QueryConsensusStateResponse},
-		connection::v1::{IdentifiedConnection, QueryConnectionResponse},
 	},
 };
 use pallet_ibc::light_clients::AnyClientMessage;
@@ -50,12 +53,19 @@ use pallet_ibc::Timeout;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use cosmos::CosmosClient;
 use ibc::core::ics02_client::events::UpdateClient;
 use pallet_ibc::light_clients::{AnyClientState, AnyConsensusState};
 use parachain::{config, ParachainClient};
-use primitives::{Chain, IbcProvider, KeyProvider, MisbehaviourHandler, UpdateType};
+use primitives::{
+	error::ErrorKind,
+	filter::{PacketFilter, PausedChannels},
+	misbehaviour::MisbehaviourAlert,
+	AccountBalance, Chain, IbcProvider, KeyProvider, LowBalanceState, MisbehaviourHandler,
+	SimulationResult, TxStatus, UpdateType,
+};
 use sp_runtime::generic::Era;
-use std::{pin::Pin, time::Duration};
+use std::{path::PathBuf, pin::Pin, time::Duration};
 #[cfg(feature = "dali")]
 use subxt::tx::{
 	SubstrateExtrinsicParams as ParachainExtrinsicParams,
@@ -112,26 +122,136 @@ pub struct Config {
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AnyConfig {
 	Parachain(parachain::ParachainClientConfig),
+	Cosmos(cosmos::CosmosClientConfig),
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct CoreConfig {
 	pub prometheus_endpoint: Option<String>,
+	/// Interval in seconds at which undelivered packets are cleared, disabled if not set
+	pub packet_clearing_interval: Option<u64>,
+	/// Address the admin api listens on, disabled if not set. Only loopback addresses are
+	/// allowed unless `admin_token_env` is set
+	pub admin_endpoint: Option<String>,
+	/// Environment variable holding the bearer token required by the admin api
+	pub admin_token_env: Option<String>,
+	/// Interval in seconds at which the balances of the relayer accounts are queried, defaults
+	/// to 60 seconds
+	pub balance_check_interval: Option<u64>,
+	/// Minimum balances of the relayer accounts, nothing is submitted to a chain while its
+	/// balance is below one of them
+	#[serde(default)]
+	pub min_balances: Vec<BalanceThreshold>,
+	/// Fraction of the trusting period of a counterparty client after which it's updated even
+	/// if there are no new events, defaults to 2/3
+	pub client_refresh_threshold: Option<f64>,
+	/// Trusting period in seconds assumed for counterparty clients whose state doesn't define
+	/// one, e.g. GRANDPA and BEEFY clients. Such clients aren't refreshed if not set
+	pub default_trusting_period: Option<u64>,
+}
+
+/// Config for relaying between several chain pairs from a single process.
+///
+/// Chains are declared once and referenced by name from any number of paths, e.g:
+/// ```toml
+/// [[chains]]
+/// type = "parachain"
+/// name = "picasso"
+/// # ...
+///
+/// [[paths]]
+/// chain_a = { chain = "picasso", client_id = "10-grandpa-0", connection_id = "connection-0" }
+/// chain_b = { chain = "dali", client_id = "10-grandpa-0", connection_id = "connection-0" }
+/// ```
+#[derive(Serialize, Deserialize)]
+pub struct MultiPathConfig {
+	/// Chains that can be referenced by name from a [`PathConfig`]
+	pub chains: Vec<AnyConfig>,
+	/// Chain pairs to relay between
+	pub paths: Vec<PathConfig>,
+	pub core: CoreConfig,
+}
+
+/// A pair of chains to relay between.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PathConfig {
+	pub chain_a: PathEnd,
+	pub chain_b: PathEnd,
+}
+
+impl PathConfig {
+	/// Human readable name of this path, used in logs.
+	pub fn name(&self) -> String {
+		format!(
+			"{}/{}<->{}/{}",
+			self.chain_a.chain,
+			self.chain_a.connection_id,
+			self.chain_b.chain,
+			self.chain_b.connection_id
+		)
+	}
+}
+
+/// One side of a [`PathConfig`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PathEnd {
+	/// Name of the chain, must match the name of one of the configured chains
+	pub chain: String,
+	/// Light client id for this chain on the counterparty chain
+	pub client_id: ClientId,
+	/// Connection Id on this chain
+	pub connection_id: ConnectionId,
+	/// Channels cleared for packet relay
+	#[serde(default)]
+	pub channel_whitelist: Vec<(ChannelId, PortId)>,
+	/// Filter for the packets sent from this chain over this path, overrides the one configured
+	/// for the chain
+	pub packet_filter: Option<PacketFilter>,
+}
+
+/// Config for watching every light client of a given type on a chain for misbehaviour, e.g:
+/// ```toml
+/// client_type = "10-grandpa"
+/// alert_log = "alerts.jsonl"
+///
+/// [host]
+/// type = "parachain"
+/// # ...
+///
+/// [[counterparties]]
+/// type = "parachain"
+/// # trusted rpc endpoints of a chain tracked by the watched clients
+/// ```
+#[derive(Serialize, Deserialize)]
+pub struct FishermanConfig {
+	/// Chain whose light clients are watched
+	pub host: AnyConfig,
+	/// Chains tracked by the watched clients, the client updates are checked against these
+	/// connections so they should use rpc endpoints that are independent from the relayers'
+	pub counterparties: Vec<AnyConfig>,
+	/// Type of the light clients to watch
+	pub client_type: ClientType,
+	/// Append-only file the misbehaviour alerts are recorded to, one json object per line
+	pub alert_log: PathBuf,
+	pub core: CoreConfig,
 }
 
 #[derive(Clone)]
 pub enum AnyChain {
 	Parachain(ParachainClient<DefaultConfig>),
+	Cosmos(CosmosClient),
 }
 
-#[derive(From)]
+#[derive(From, Clone)]
 pub enum AnyFinalityEvent {
 	Parachain(parachain::finality_protocol::FinalityEvent),
+	Cosmos(cosmos::provider::FinalityEvent),
 }
 
 #[derive(From)]
 pub enum AnyTransactionId {
 	Parachain(parachain::provider::TransactionId<sp_core::H256>),
+	Cosmos(cosmos::provider::TransactionId),
 }
 
 #[derive(Error, Debug)]
@@ -139,6 +259,8 @@ pub enum AnyError {
 	#[error("{0}")]
 	Parachain(#[from] parachain::error::Error),
 	#[error("{0}")]
+	Cosmos(#[from] cosmos::error::Error),
+	#[error("{0}")]
 	Other(String),
 }
 
@@ -170,6 +292,13 @@ impl IbcProvider for AnyChain {
 					chain.query_latest_ibc_events(finality_event, counterparty).await?;
 				Ok((client_msg, events, update_type))
 			},
+			AnyChain::Cosmos(chain) => {
+				let finality_event = ibc::downcast!(finality_event => AnyFinalityEvent::Cosmos)
+					.ok_or_else(|| AnyError::Other("Invalid finality event type".to_owned()))?;
+				let (client_msg, events, update_type) =
+					chain.query_latest_ibc_events(finality_event, counterparty).await?;
+				Ok((client_msg, events, update_type))
+			},
 			_ => unreachable!(),
 		}
 	}
@@ -177,6 +306,7 @@ impl IbcProvider for AnyChain {
 	async fn ibc_events(&self) -> Pin<Box<dyn Stream<Item = IbcEvent> + Send + 'static>> {
 		match self {
 			Self::Parachain(chain) => chain.ibc_events().await,
+			Self::Cosmos(chain) => chain.ibc_events().await,
 			_ => unreachable!(),
 		}
 	}
@@ -192,6 +322,10 @@ impl IbcProvider for AnyChain {
 				.query_client_consensus(at, client_id, consensus_height)
 				.await
 				.map_err(Into::into),
+			AnyChain::Cosmos(chain) => chain
+				.query_client_consensus(at, client_id, consensus_height)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -204,6 +338,42 @@ impl IbcProvider for AnyChain {
 		match self {
 			AnyChain::Parachain(chain) =>
 				chain.query_client_state(at, client_id).await.map_err(Into::into),
+			AnyChain::Cosmos(chain) =>
+				chain.query_client_state(at, client_id).await.map_err(Into::into),
+			_ => unreachable!(),
+		}
+	}
+
+	async fn query_upgraded_client_state(
+		&self,
+		at: Height,
+	) -> Result<QueryClientStateResponse, Self::Error> {
+		match self {
+			AnyChain::Parachain(chain) =>
+				chain.query_upgraded_client_state(at).await.map_err(Into::into),
+			AnyChain::Cosmos(chain) =>
+				chain.query_upgraded_client_state(at).await.map_err(Into::into),
+			_ => unreachable!(),
+		}
+	}
+
+	async fn client_upgrades(&self) -> Pin<Box<dyn Stream<Item = ()> + Send + 'static>> {
+		match self {
+			Self::Parachain(chain) => chain.client_upgrades().await,
+			Self::Cosmos(chain) => chain.client_upgrades().await,
+			_ => unreachable!(),
+		}
+	}
+
+	async fn query_upgraded_consensus_state(
+		&self,
+		at: Height,
+	) -> Result<QueryConsensusStateResponse, Self::Error> {
+		match self {
+			AnyChain::Parachain(chain) =>
+				chain.query_upgraded_consensus_state(at).await.map_err(Into::into),
+			AnyChain::Cosmos(chain) =>
+				chain.query_upgraded_consensus_state(at).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -216,6 +386,8 @@ impl IbcProvider for AnyChain {
 		match self {
 			AnyChain::Parachain(chain) =>
 				chain.query_connection_end(at, connection_id).await.map_err(Into::into),
+			AnyChain::Cosmos(chain) =>
+				chain.query_connection_end(at, connection_id).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -229,6 +401,42 @@ impl IbcProvider for AnyChain {
 		match self {
 			AnyChain::Parachain(chain) =>
 				chain.query_channel_end(at, channel_id, port_id).await.map_err(Into::into),
+			AnyChain::Cosmos(chain) =>
+				chain.query_channel_end(at, channel_id, port_id).await.map_err(Into::into),
+			_ => unreachable!(),
+		}
+	}
+
+	async fn query_channel_upgrade(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryUpgradeResponse, Self::Error> {
+		match self {
+			AnyChain::Parachain(chain) =>
+				chain.query_channel_upgrade(at, channel_id, port_id).await.map_err(Into::into),
+			AnyChain::Cosmos(chain) =>
+				chain.query_channel_upgrade(at, channel_id, port_id).await.map_err(Into::into),
+			_ => unreachable!(),
+		}
+	}
+
+	async fn query_channel_upgrade_error(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryUpgradeErrorResponse, Self::Error> {
+		match self {
+			AnyChain::Parachain(chain) => chain
+				.query_channel_upgrade_error(at, channel_id, port_id)
+				.await
+				.map_err(Into::into),
+			AnyChain::Cosmos(chain) => chain
+				.query_channel_upgrade_error(at, channel_id, port_id)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -236,6 +444,7 @@ impl IbcProvider for AnyChain {
 	async fn query_proof(&self, at: Height, keys: Vec<Vec<u8>>) -> Result<Vec<u8>, Self::Error> {
 		match self {
 			AnyChain::Parachain(chain) => chain.query_proof(at, keys).await.map_err(Into::into),
+			AnyChain::Cosmos(chain) => chain.query_proof(at, keys).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -252,6 +461,10 @@ impl IbcProvider for AnyChain {
 				.query_packet_commitment(at, port_id, channel_id, seq)
 				.await
 				.map_err(Into::into),
+			AnyChain::Cosmos(chain) => chain
+				.query_packet_commitment(at, port_id, channel_id, seq)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -268,6 +481,10 @@ impl IbcProvider for AnyChain {
 				.query_packet_acknowledgement(at, port_id, channel_id, seq)
 				.await
 				.map_err(Into::into),
+			AnyChain::Cosmos(chain) => chain
+				.query_packet_acknowledgement(at, port_id, channel_id, seq)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -283,6 +500,10 @@ impl IbcProvider for AnyChain {
 				.query_next_sequence_recv(at, port_id, channel_id)
 				.await
 				.map_err(Into::into),
+			AnyChain::Cosmos(chain) => chain
+				.query_next_sequence_recv(at, port_id, channel_id)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -299,6 +520,10 @@ impl IbcProvider for AnyChain {
 				.query_packet_receipt(at, port_id, channel_id, seq)
 				.await
 				.map_err(Into::into),
+			AnyChain::Cosmos(chain) => chain
+				.query_packet_receipt(at, port_id, channel_id, seq)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -307,6 +532,8 @@ impl IbcProvider for AnyChain {
 		match self {
 			AnyChain::Parachain(chain) =>
 				chain.latest_height_and_timestamp().await.map_err(Into::into),
+			AnyChain::Cosmos(chain) =>
+				chain.latest_height_and_timestamp().await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -322,6 +549,10 @@ impl IbcProvider for AnyChain {
 				.query_packet_commitments(at, channel_id, port_id)
 				.await
 				.map_err(Into::into),
+			Self::Cosmos(chain) => chain
+				.query_packet_commitments(at, channel_id, port_id)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -337,6 +568,10 @@ impl IbcProvider for AnyChain {
 				.query_packet_acknowledgements(at, channel_id, port_id)
 				.await
 				.map_err(Into::into),
+			Self::Cosmos(chain) => chain
+				.query_packet_acknowledgements(at, channel_id, port_id)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -353,6 +588,10 @@ impl IbcProvider for AnyChain {
 				.query_unreceived_packets(at, channel_id, port_id, seqs)
 				.await
 				.map_err(Into::into),
+			Self::Cosmos(chain) => chain
+				.query_unreceived_packets(at, channel_id, port_id, seqs)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -369,6 +608,10 @@ impl IbcProvider for AnyChain {
 				.query_unreceived_acknowledgements(at, channel_id, port_id, seqs)
 				.await
 				.map_err(Into::into),
+			Self::Cosmos(chain) => chain
+				.query_unreceived_acknowledgements(at, channel_id, port_id, seqs)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -376,6 +619,15 @@ impl IbcProvider for AnyChain {
 	fn channel_whitelist(&self) -> Vec<(ChannelId, PortId)> {
 		match self {
 			Self::Parachain(chain) => chain.channel_whitelist(),
+			Self::Cosmos(chain) => chain.channel_whitelist(),
+			_ => unreachable!(),
+		}
+	}
+
+	fn packet_filter(&self) -> PacketFilter {
+		match self {
+			Self::Parachain(chain) => chain.packet_filter(),
+			Self::Cosmos(chain) => chain.packet_filter(),
 			_ => unreachable!(),
 		}
 	}
@@ -388,6 +640,8 @@ impl IbcProvider for AnyChain {
 		match self {
 			Self::Parachain(chain) =>
 				chain.query_connection_channels(at, connection_id).await.map_err(Into::into),
+			Self::Cosmos(chain) =>
+				chain.query_connection_channels(at, connection_id).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -401,6 +655,8 @@ impl IbcProvider for AnyChain {
 		match self {
 			Self::Parachain(chain) =>
 				chain.query_send_packets(channel_id, port_id, seqs).await.map_err(Into::into),
+			Self::Cosmos(chain) =>
+				chain.query_send_packets(channel_id, port_id, seqs).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -414,6 +670,8 @@ impl IbcProvider for AnyChain {
 		match self {
 			Self::Parachain(chain) =>
 				chain.query_recv_packets(channel_id, port_id, seqs).await.map_err(Into::into),
+			Self::Cosmos(chain) =>
+				chain.query_recv_packets(channel_id, port_id, seqs).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -421,6 +679,7 @@ impl IbcProvider for AnyChain {
 	fn expected_block_time(&self) -> Duration {
 		match self {
 			Self::Parachain(chain) => chain.expected_block_time(),
+			Self::Cosmos(chain) => chain.expected_block_time(),
 			_ => unreachable!(),
 		}
 	}
@@ -435,6 +694,10 @@ impl IbcProvider for AnyChain {
 				.query_client_update_time_and_height(client_id, client_height)
 				.await
 				.map_err(Into::into),
+			Self::Cosmos(chain) => chain
+				.query_client_update_time_and_height(client_id, client_height)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -446,6 +709,8 @@ impl IbcProvider for AnyChain {
 		match self {
 			AnyChain::Parachain(chain) =>
 				chain.query_host_consensus_state_proof(height).await.map_err(Into::into),
+			AnyChain::Cosmos(chain) =>
+				chain.query_host_consensus_state_proof(height).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -453,6 +718,23 @@ impl IbcProvider for AnyChain {
 	async fn query_ibc_balance(&self) -> Result<Vec<PrefixedCoin>, Self::Error> {
 		match self {
 			Self::Parachain(chain) => chain.query_ibc_balance().await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.query_ibc_balance().await.map_err(Into::into),
+			_ => unreachable!(),
+		}
+	}
+
+	async fn query_relayer_balances(&self) -> Result<Vec<AccountBalance>, Self::Error> {
+		match self {
+			Self::Parachain(chain) => chain.query_relayer_balances().await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.query_relayer_balances().await.map_err(Into::into),
+			_ => unreachable!(),
+		}
+	}
+
+	async fn query_denom_traces(&self) -> Result<Vec<DenomTrace>, Self::Error> {
+		match self {
+			Self::Parachain(chain) => chain.query_denom_traces().await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.query_denom_traces().await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -460,6 +742,7 @@ impl IbcProvider for AnyChain {
 	fn connection_prefix(&self) -> CommitmentPrefix {
 		match self {
 			AnyChain::Parachain(chain) => chain.connection_prefix(),
+			AnyChain::Cosmos(chain) => chain.connection_prefix(),
 			_ => unreachable!(),
 		}
 	}
@@ -467,6 +750,7 @@ impl IbcProvider for AnyChain {
 	fn client_id(&self) -> ClientId {
 		match self {
 			AnyChain::Parachain(chain) => chain.client_id(),
+			AnyChain::Cosmos(chain) => chain.client_id(),
 			_ => unreachable!(),
 		}
 	}
@@ -474,6 +758,7 @@ impl IbcProvider for AnyChain {
 	fn connection_id(&self) -> ConnectionId {
 		match self {
 			AnyChain::Parachain(chain) => chain.connection_id(),
+			AnyChain::Cosmos(chain) => chain.connection_id(),
 			_ => unreachable!(),
 		}
 	}
@@ -481,6 +766,7 @@ impl IbcProvider for AnyChain {
 	fn client_type(&self) -> ClientType {
 		match self {
 			AnyChain::Parachain(chain) => chain.client_type(),
+			AnyChain::Cosmos(chain) => chain.client_type(),
 			_ => unreachable!(),
 		}
 	}
@@ -489,6 +775,7 @@ impl IbcProvider for AnyChain {
 		match self {
 			Self::Parachain(chain) =>
 				chain.query_timestamp_at(block_number).await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.query_timestamp_at(block_number).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -496,6 +783,7 @@ impl IbcProvider for AnyChain {
 	async fn query_clients(&self) -> Result<Vec<ClientId>, Self::Error> {
 		match self {
 			Self::Parachain(chain) => chain.query_clients().await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.query_clients().await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -503,6 +791,7 @@ impl IbcProvider for AnyChain {
 	async fn query_channels(&self) -> Result<Vec<(ChannelId, PortId)>, Self::Error> {
 		match self {
 			Self::Parachain(chain) => chain.query_channels().await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.query_channels().await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -515,6 +804,8 @@ impl IbcProvider for AnyChain {
 		match self {
 			Self::Parachain(chain) =>
 				chain.query_connection_using_client(height, client_id).await.map_err(Into::into),
+			Self::Cosmos(chain) =>
+				chain.query_connection_using_client(height, client_id).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -527,6 +818,8 @@ impl IbcProvider for AnyChain {
 		match self {
 			Self::Parachain(chain) =>
 				chain.is_update_required(latest_height, latest_client_height_on_counterparty),
+			Self::Cosmos(chain) =>
+				chain.is_update_required(latest_height, latest_client_height_on_counterparty),
 			_ => unreachable!(),
 		}
 	}
@@ -535,6 +828,7 @@ impl IbcProvider for AnyChain {
 	) -> Result<(AnyClientState, AnyConsensusState), Self::Error> {
 		match self {
 			Self::Parachain(chain) => chain.initialize_client_state().await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.initialize_client_state().await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -551,6 +845,13 @@ impl IbcProvider for AnyChain {
 				)
 				.await
 				.map_err(Into::into),
+			Self::Cosmos(chain) => chain
+				.query_client_id_from_tx_hash(
+					downcast!(tx_id => AnyTransactionId::Cosmos)
+						.expect("Should be cosmos transaction id"),
+				)
+				.await
+				.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -562,10 +863,12 @@ impl MisbehaviourHandler for AnyChain {
 		&self,
 		counterparty: &C,
 		client_message: AnyClientMessage,
-	) -> Result<(), anyhow::Error> {
+	) -> Result<Option<MisbehaviourAlert>, anyhow::Error> {
 		match self {
 			AnyChain::Parachain(parachain) =>
 				parachain.check_for_misbehaviour(counterparty, client_message).await,
+			AnyChain::Cosmos(chain) =>
+				chain.check_for_misbehaviour(counterparty, client_message).await,
 			_ => unreachable!(),
 		}
 	}
@@ -575,6 +878,7 @@ impl KeyProvider for AnyChain {
 	fn account_id(&self) -> Signer {
 		match self {
 			AnyChain::Parachain(parachain) => parachain.account_id(),
+			AnyChain::Cosmos(chain) => chain.account_id(),
 			_ => unreachable!(),
 		}
 	}
@@ -585,6 +889,7 @@ impl Chain for AnyChain {
 	fn name(&self) -> &str {
 		match self {
 			Self::Parachain(chain) => chain.name(),
+			Self::Cosmos(chain) => chain.name(),
 			_ => unreachable!(),
 		}
 	}
@@ -592,6 +897,7 @@ impl Chain for AnyChain {
 	fn block_max_weight(&self) -> u64 {
 		match self {
 			Self::Parachain(chain) => chain.block_max_weight(),
+			Self::Cosmos(chain) => chain.block_max_weight(),
 			_ => unreachable!(),
 		}
 	}
@@ -599,6 +905,15 @@ impl Chain for AnyChain {
 	async fn estimate_weight(&self, msg: Vec<Any>) -> Result<u64, Self::Error> {
 		match self {
 			Self::Parachain(chain) => chain.estimate_weight(msg).await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.estimate_weight(msg).await.map_err(Into::into),
+			_ => unreachable!(),
+		}
+	}
+
+	async fn simulate(&self, messages: Vec<Any>) -> Result<SimulationResult, Self::Error> {
+		match self {
+			Self::Parachain(chain) => chain.simulate(messages).await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.simulate(messages).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -611,6 +926,10 @@ impl Chain for AnyChain {
 				use futures::StreamExt;
 				Box::pin(chain.finality_notifications().await.map(|x| x.into()))
 			},
+			Self::Cosmos(chain) => {
+				use futures::StreamExt;
+				Box::pin(chain.finality_notifications().await.map(|x| x.into()))
+			},
 			_ => unreachable!(),
 		}
 	}
@@ -622,6 +941,29 @@ impl Chain for AnyChain {
 				.await
 				.map_err(Into::into)
 				.map(|id| AnyTransactionId::Parachain(id)),
+			Self::Cosmos(chain) => chain
+				.submit(messages)
+				.await
+				.map_err(Into::into)
+				.map(|id| AnyTransactionId::Cosmos(id)),
+			_ => unreachable!(),
+		}
+	}
+
+	fn low_balance(&self) -> &LowBalanceState {
+		match self {
+			Self::Parachain(chain) => chain.low_balance(),
+			Self::Cosmos(chain) => chain.low_balance(),
+			_ => unreachable!(),
+		}
+	}
+
+	async fn query_tx_status(&self, tx_id: &Self::TransactionId) -> Result<TxStatus, Self::Error> {
+		match (self, tx_id) {
+			(Self::Parachain(chain), AnyTransactionId::Parachain(tx_id)) =>
+				chain.query_tx_status(tx_id).await.map_err(Into::into),
+			(Self::Cosmos(chain), AnyTransactionId::Cosmos(tx_id)) =>
+				chain.query_tx_status(tx_id).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -632,6 +974,15 @@ impl Chain for AnyChain {
 	) -> Result<AnyClientMessage, Self::Error> {
 		match self {
 			Self::Parachain(chain) => chain.query_client_message(update).await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.query_client_message(update).await.map_err(Into::into),
+			_ => unreachable!(),
+		}
+	}
+
+	fn error_kind(&self, error: &anyhow::Error) -> Option<ErrorKind> {
+		match self {
+			Self::Parachain(chain) => chain.error_kind(error),
+			Self::Cosmos(chain) => chain.error_kind(error),
 			_ => unreachable!(),
 		}
 	}
@@ -643,6 +994,7 @@ impl primitives::TestProvider for AnyChain {
 	async fn send_transfer(&self, params: MsgTransfer<PrefixedCoin>) -> Result<(), Self::Error> {
 		match self {
 			Self::Parachain(chain) => chain.send_transfer(params).await.map_err(Into::into),
+			Self::Cosmos(chain) => chain.send_transfer(params).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -655,6 +1007,8 @@ impl primitives::TestProvider for AnyChain {
 		match self {
 			Self::Parachain(chain) =>
 				chain.send_ordered_packet(channel_id, timeout).await.map_err(Into::into),
+			Self::Cosmos(chain) =>
+				chain.send_ordered_packet(channel_id, timeout).await.map_err(Into::into),
 			_ => unreachable!(),
 		}
 	}
@@ -662,6 +1016,7 @@ impl primitives::TestProvider for AnyChain {
 	async fn subscribe_blocks(&self) -> Pin<Box<dyn Stream<Item = u64> + Send + Sync>> {
 		match self {
 			Self::Parachain(chain) => chain.subscribe_blocks().await,
+			Self::Cosmos(chain) => chain.subscribe_blocks().await,
 			_ => unreachable!(),
 		}
 	}
@@ -669,16 +1024,84 @@ impl primitives::TestProvider for AnyChain {
 	fn set_channel_whitelist(&mut self, channel_whitelist: Vec<(ChannelId, PortId)>) {
 		match self {
 			Self::Parachain(chain) => chain.set_channel_whitelist(channel_whitelist),
+			Self::Cosmos(chain) => chain.set_channel_whitelist(channel_whitelist),
 			_ => unreachable!(),
 		}
 	}
 }
 
+impl AnyChain {
+	pub fn set_client_id(&mut self, client_id: ClientId) {
+		match self {
+			Self::Parachain(chain) => {
+				chain.client_id.replace(client_id);
+			},
+			Self::Cosmos(chain) => {
+				chain.client_id.replace(client_id);
+			},
+		}
+	}
+
+	pub fn set_connection_id(&mut self, connection_id: ConnectionId) {
+		match self {
+			Self::Parachain(chain) => {
+				chain.connection_id.replace(connection_id);
+			},
+			Self::Cosmos(chain) => {
+				chain.connection_id.replace(connection_id);
+			},
+		}
+	}
+
+	pub fn set_channel_whitelist(&mut self, channel_whitelist: Vec<(ChannelId, PortId)>) {
+		match self {
+			Self::Parachain(chain) => chain.channel_whitelist = channel_whitelist,
+			Self::Cosmos(chain) => chain.channel_whitelist = channel_whitelist,
+		}
+	}
+
+	pub fn set_packet_filter(&mut self, packet_filter: PacketFilter) {
+		match self {
+			Self::Parachain(chain) => chain.packet_filter = packet_filter,
+			Self::Cosmos(chain) => chain.packet_filter = packet_filter,
+		}
+	}
+
+	/// Attaches the set of channels paused through the admin api to the packet filter.
+	pub fn set_paused_channels(&mut self, paused: PausedChannels) {
+		match self {
+			Self::Parachain(chain) => chain.packet_filter.paused = paused,
+			Self::Cosmos(chain) => chain.packet_filter.paused = paused,
+		}
+	}
+
+	/// Returns true if the given light client state tracks this chain.
+	pub fn is_tracked_by(&self, client_state: &AnyClientState) -> bool {
+		match (self, client_state) {
+			(Self::Parachain(chain), AnyClientState::Grandpa(client_state)) =>
+				client_state.para_id == chain.para_id,
+			(Self::Parachain(chain), AnyClientState::Beefy(client_state)) =>
+				client_state.para_id == chain.para_id,
+			(Self::Cosmos(chain), AnyClientState::Tendermint(client_state)) =>
+				client_state.chain_id == chain.chain_id,
+			_ => false,
+		}
+	}
+}
+
 impl AnyConfig {
+	pub fn name(&self) -> &str {
+		match self {
+			Self::Parachain(config) => &config.name,
+			Self::Cosmos(config) => &config.name,
+		}
+	}
+
 	pub async fn into_client(self) -> anyhow::Result<AnyChain> {
 		Ok(match self {
 			AnyConfig::Parachain(config) =>
 				AnyChain::Parachain(ParachainClient::new(config).await?),
+			AnyConfig::Cosmos(config) => AnyChain::Cosmos(CosmosClient::new(config).await?),
 		})
 	}
 
@@ -687,6 +1110,9 @@ impl AnyConfig {
 			Self::Parachain(chain) => {
 				chain.client_id.replace(client_id);
 			},
+			Self::Cosmos(chain) => {
+				chain.client_id.replace(client_id);
+			},
 		}
 	}
 
@@ -695,6 +1121,9 @@ impl AnyConfig {
 			Self::Parachain(chain) => {
 				chain.connection_id.replace(connection_id);
 			},
+			Self::Cosmos(chain) => {
+				chain.connection_id.replace(connection_id);
+			},
 		}
 	}
 
@@ -703,6 +1132,9 @@ impl AnyConfig {
 			Self::Parachain(chain) => {
 				chain.channel_whitelist.push((channel_id, port_id));
 			},
+			Self::Cosmos(chain) => {
+				chain.channel_whitelist.push((channel_id, port_id));
+			},
 		}
 	}
 }
@@ -14,15 +14,41 @@
 
 use anyhow::Result;
 use clap::Parser;
-use primitives::Chain;
+use primitives::{filter::PausedChannels, Chain};
 use prometheus::Registry;
 use std::{path::PathBuf, str::FromStr, time::Duration};
 
-use crate::{chain::Config, fish, relay, Mode};
-use ibc::core::{ics04_channel::channel::Order, ics24_host::identifier::PortId};
-use metrics::{data::Metrics, handler::MetricsHandler, init_prometheus};
+use crate::{
+	admin::{self, AdminPath},
+	balance::{monitor_balance, DEFAULT_BALANCE_CHECK_INTERVAL},
+	chain::{Config, FishermanConfig, MultiPathConfig},
+	fee::register_counterparty_payees,
+	finality::SharedFinality,
+	fish,
+	fisherman::Fisherman,
+	handshake::{complete_channel, complete_connection},
+	packets::clearing::{clear_packets, clear_packets_periodically},
+	query::QueryCmd,
+	refresh::{refresh_client_periodically, RefreshPolicy},
+	relay, relay_with_finality,
+	supervisor::Supervisor,
+	upgrade::{upgrade_client, upgrade_clients_on_schedule},
+	Mode,
+};
+use ibc::core::{
+	ics04_channel::channel::Order,
+	ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+};
+use metrics::{
+	data::{BalanceMetrics, ClientMetrics, Metrics},
+	handler::MetricsHandler,
+	init_prometheus,
+};
 use primitives::{
-	utils::{create_channel, create_clients, create_connection},
+	utils::{
+		channel_open_init_message, connection_open_init_message, create_channel,
+		create_client_messages, create_clients, create_connection, dry_run,
+	},
 	IbcProvider,
 };
 
@@ -35,19 +61,50 @@ pub struct Cli {
 /// Possible subcommands of the main binary.
 #[derive(Debug, Parser)]
 pub enum Subcommand {
-	#[clap(name = "relay", about = "Start relaying messages between two chains")]
+	#[clap(
+		name = "relay",
+		about = "Start relaying messages between two chains, or over all the paths of a multi-path config"
+	)]
 	Relay(Cmd),
 	#[clap(
 		name = "fish",
-		about = "Start the relayer in fishing mode (catching malicious transactions)"
+		about = "Start the relayer in fishing mode (catching malicious transactions), or watch every client of a given type on a chain"
 	)]
 	Fish(Cmd),
+	#[clap(
+		name = "clear-packets",
+		about = "Relays all undelivered packets, acknowledgements and timeouts between both chains"
+	)]
+	ClearPackets(Cmd),
 	#[clap(name = "create-clients", about = "Creates light clients on both chains")]
 	CreateClients(Cmd),
 	#[clap(name = "create-connection", about = "Creates a connection between both chains")]
 	CreateConnection(Cmd),
 	#[clap(name = "create-channel", about = "Creates a channel on the specified port")]
 	CreateChannel(Cmd),
+	#[clap(
+		name = "complete-connection",
+		about = "Submits the missing handshake steps of a connection until it's open on both chains"
+	)]
+	CompleteConnection(Cmd),
+	#[clap(
+		name = "complete-channel",
+		about = "Submits the missing handshake steps of a channel until it's open on both chains"
+	)]
+	CompleteChannel(Cmd),
+	#[clap(
+		name = "upgrade-clients",
+		about = "Upgrades the clients on both chains whose counterparty scheduled a client upgrade"
+	)]
+	UpgradeClients(Cmd),
+	#[clap(
+		name = "register-payees",
+		about = "Registers the relayer accounts as counterparty payees on the fee enabled channels"
+	)]
+	RegisterPayees(Cmd),
+	#[clap(name = "query", about = "Queries the IBC state of one of the configured chains")]
+	#[clap(subcommand)]
+	Query(QueryCmd),
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -58,6 +115,13 @@ pub struct Cmd {
 	/// Port id for channel creation
 	#[clap(long)]
 	port_id: Option<String>,
+	/// Id of the connection on chain a whose handshake should be completed, defaults to the
+	/// configured connection
+	#[clap(long)]
+	connection_id: Option<String>,
+	/// Id of the channel on chain a whose handshake should be completed
+	#[clap(long)]
+	channel_id: Option<String>,
 	/// Connection delay period in seconds
 	#[clap(long)]
 	#[clap(long)]
@@ -71,6 +135,10 @@ pub struct Cmd {
 	/// New config path to avoid overriding existing configuration
 	#[clap(long)]
 	pub new_config: Option<String>,
+	/// Log the messages that would be submitted along with their simulated execution, without
+	/// submitting them
+	#[clap(long)]
+	pub dry_run: bool,
 }
 
 impl Cmd {
@@ -79,9 +147,18 @@ impl Cmd {
 	pub async fn run(&self) -> Result<()> {
 		let path: PathBuf = self.config.parse()?;
 		let file_content = tokio::fs::read_to_string(path).await?;
-		let config: Config = toml::from_str(&file_content)?;
-		let any_chain_a = config.chain_a.into_client().await?;
-		let any_chain_b = config.chain_b.into_client().await?;
+		let value: toml::Value = toml::from_str(&file_content)?;
+		if value.get("paths").is_some() {
+			return self.run_paths(value).await
+		}
+		let config: Config = value.clone().try_into()?;
+		let refresh_policy = RefreshPolicy::new(&config.core)?;
+		let mut any_chain_a = config.chain_a.into_client().await?;
+		let mut any_chain_b = config.chain_b.into_client().await?;
+		let paused = PausedChannels::default();
+		any_chain_a.set_paused_channels(paused.clone());
+		any_chain_b.set_paused_channels(paused.clone());
+		let mode = self.dry_run.then(|| Mode::DryRun);
 
 		let registry =
 			Registry::new_custom(None, None).expect("this can only fail if the prefix is empty");
@@ -91,19 +168,138 @@ impl Cmd {
 		let mut metrics_handler_b = MetricsHandler::new(registry.clone(), metrics_b);
 		metrics_handler_a.link_with_counterparty(&mut metrics_handler_b);
 
-		if let Some(addr) = config.core.prometheus_endpoint.map(|s| s.parse().ok()).flatten() {
+		let balance_check_interval = config
+			.core
+			.balance_check_interval
+			.map(Duration::from_secs)
+			.unwrap_or(DEFAULT_BALANCE_CHECK_INTERVAL);
+		for chain in [&any_chain_a, &any_chain_b] {
+			tokio::spawn(monitor_balance(
+				chain.clone(),
+				config.core.min_balances.clone(),
+				Some(BalanceMetrics::register(chain.name(), &registry)?),
+				balance_check_interval,
+			));
+		}
+
+		if let Some(addr) = config.core.prometheus_endpoint.as_ref().and_then(|s| s.parse().ok()) {
+			tokio::spawn(init_prometheus(addr, registry.clone()));
+		}
+
+		// a single finality subscription per chain, shared by the relay loop and the tasks that
+		// update clients outside of it.
+		let finality_a = SharedFinality::new(any_chain_a.clone());
+		let finality_b = SharedFinality::new(any_chain_b.clone());
+
+		if let Some(addr) = config.core.admin_endpoint.as_ref().and_then(|s| s.parse().ok()) {
+			let path = AdminPath {
+				name: format!("{}<->{}", any_chain_a.name(), any_chain_b.name()),
+				chain_a: any_chain_a.clone(),
+				chain_b: any_chain_b.clone(),
+				finality_a: finality_a.clone(),
+				finality_b: finality_b.clone(),
+			};
+			let token_env = config.core.admin_token_env.clone();
+			tokio::spawn(admin::serve(addr, token_env, vec![path], paused, value, mode)?);
+		}
+
+		// packet clearing submits its messages directly, so it's disabled in dry-run mode.
+		if let Some(interval) = config.core.packet_clearing_interval.filter(|_| !self.dry_run) {
+			tokio::spawn(clear_packets_periodically(
+				any_chain_a.clone(),
+				any_chain_b.clone(),
+				finality_a.clone(),
+				finality_b.clone(),
+				Duration::from_secs(interval),
+			));
+		}
+
+		// client refreshes and upgrades submit their messages directly, so they're disabled in
+		// dry-run mode.
+		if !self.dry_run {
+			for (source, sink) in [(&any_chain_a, &any_chain_b), (&any_chain_b, &any_chain_a)] {
+				let prefix = format!("{}_{}", sink.name(), source.name());
+				tokio::spawn(refresh_client_periodically(
+					source.clone(),
+					sink.clone(),
+					refresh_policy,
+					Some(ClientMetrics::register(&prefix, &registry)?),
+				));
+				tokio::spawn(upgrade_clients_on_schedule(source.clone(), sink.clone()));
+			}
+		}
+
+		relay_with_finality(
+			any_chain_a,
+			any_chain_b,
+			finality_a.subscribe(),
+			finality_b.subscribe(),
+			&mut Some(metrics_handler_a),
+			&mut Some(metrics_handler_b),
+			mode,
+		)
+		.await
+	}
+
+	/// Relay over all the paths of a multi-path config
+	async fn run_paths(&self, value: toml::Value) -> Result<()> {
+		let config: MultiPathConfig = value.clone().try_into()?;
+		let registry =
+			Registry::new_custom(None, None).expect("this can only fail if the prefix is empty");
+		if let Some(addr) = config.core.prometheus_endpoint.as_ref().and_then(|s| s.parse().ok()) {
 			tokio::spawn(init_prometheus(addr, registry.clone()));
 		}
+		let admin_addr = config.core.admin_endpoint.as_ref().and_then(|s| s.parse().ok());
+		let admin_token_env = config.core.admin_token_env.clone();
 
-		relay(any_chain_a, any_chain_b, Some(metrics_handler_a), Some(metrics_handler_b), None)
-			.await
+		let mode = self.dry_run.then(|| Mode::DryRun);
+		let supervisor = Supervisor::new(config, registry, mode).await?;
+		if let Some(addr) = admin_addr {
+			tokio::spawn(admin::serve(
+				addr,
+				admin_token_env,
+				supervisor.admin_paths(),
+				supervisor.paused_channels(),
+				value,
+				mode,
+			)?);
+		}
+		supervisor.run().await
+	}
+
+	/// Watch all the clients of a fisherman config for misbehaviour
+	async fn watch_clients(&self, value: toml::Value) -> Result<()> {
+		let config: FishermanConfig = value.try_into()?;
+		let registry =
+			Registry::new_custom(None, None).expect("this can only fail if the prefix is empty");
+		if let Some(addr) = config.core.prometheus_endpoint.as_ref().and_then(|s| s.parse().ok()) {
+			tokio::spawn(init_prometheus(addr, registry.clone()));
+		}
+		Fisherman::new(config, Some(&registry)).await?.run().await
+	}
+
+	/// Clear undelivered packets
+	pub async fn clear_packets(&self) -> Result<()> {
+		let path: PathBuf = self.config.parse()?;
+		let file_content = tokio::fs::read_to_string(path).await?;
+		let config: Config = toml::from_str(&file_content)?;
+		let mut any_chain_a = config.chain_a.into_client().await?;
+		let mut any_chain_b = config.chain_b.into_client().await?;
+		let finality_a = SharedFinality::new(any_chain_a.clone());
+		let finality_b = SharedFinality::new(any_chain_b.clone());
+
+		clear_packets(&mut any_chain_a, &mut any_chain_b, &finality_a, &finality_b).await
 	}
 
 	/// Run fisherman
 	pub async fn fish(&self) -> Result<()> {
 		let path: PathBuf = self.config.parse()?;
 		let file_content = tokio::fs::read_to_string(path).await?;
-		let config: Config = toml::from_str(&file_content)?;
+		let value: toml::Value = toml::from_str(&file_content)?;
+		if value.get("counterparties").is_some() {
+			return self.watch_clients(value).await
+		}
+		let config: Config = value.try_into()?;
 		let any_chain_a = config.chain_a.into_client().await?;
 		let any_chain_b = config.chain_b.into_client().await?;
 
@@ -118,6 +314,13 @@ impl Cmd {
 		let any_chain_a = config.chain_a.clone().into_client().await?;
 		let any_chain_b = config.chain_b.clone().into_client().await?;
 
+		if self.dry_run {
+			let (msg_a, msg_b) = create_client_messages(&any_chain_a, &any_chain_b).await?;
+			dry_run(&any_chain_a, vec![msg_a]).await?;
+			dry_run(&any_chain_b, vec![msg_b]).await?;
+			return Ok(config)
+		}
+
 		let (client_id_a_on_b, client_id_b_on_a) =
 			create_clients(&any_chain_a, &any_chain_b).await?;
 		log::info!(
@@ -149,6 +352,16 @@ impl Cmd {
 		let any_chain_a = config.chain_a.clone().into_client().await?;
 		let any_chain_b = config.chain_b.clone().into_client().await?;
 
+		if self.dry_run {
+			let msg = connection_open_init_message(&any_chain_a, &any_chain_b, delay);
+			dry_run(&any_chain_a, vec![msg]).await?;
+			log::info!(
+				"[dry-run] The remaining handshake messages depend on the connection being initialized on {}",
+				any_chain_a.name()
+			);
+			return Ok(config)
+		}
+
 		let any_chain_a_clone = any_chain_a.clone();
 		let any_chain_b_clone = any_chain_b.clone();
 		let handle = tokio::task::spawn(async move {
@@ -189,6 +402,24 @@ impl Cmd {
 		let any_chain_a = config.chain_a.clone().into_client().await?;
 		let any_chain_b = config.chain_b.clone().into_client().await?;
 
+		let order = Order::from_str(order).expect("Expected one of 'ordered' or 'unordered'");
+
+		if self.dry_run {
+			let msg = channel_open_init_message(
+				&any_chain_a,
+				any_chain_a.connection_id(),
+				port_id,
+				version,
+				order,
+			);
+			dry_run(&any_chain_a, vec![msg]).await?;
+			log::info!(
+				"[dry-run] The remaining handshake messages depend on the channel being initialized on {}",
+				any_chain_a.name()
+			);
+			return Ok(config)
+		}
+
 		let any_chain_a_clone = any_chain_a.clone();
 		let any_chain_b_clone = any_chain_b.clone();
 		let handle = tokio::task::spawn(async move {
@@ -197,7 +428,6 @@ impl Cmd {
 				.unwrap();
 		});
 
-		let order = Order::from_str(order).expect("Expected one of 'ordered' or 'unordered'");
 		let (channel_id_a, channel_id_b) = create_channel(
 			&any_chain_a,
 			&any_chain_b,
@@ -216,4 +446,133 @@ impl Cmd {
 
 		Ok(config)
 	}
+
+	pub async fn complete_connection(&self) -> Result<Config> {
+		let path: PathBuf = self.config.parse()?;
+		let file_content = tokio::fs::read_to_string(path).await?;
+		let mut config: Config = toml::from_str(&file_content)?;
+		let mut any_chain_a = config.chain_a.clone().into_client().await?;
+		let mut any_chain_b = config.chain_b.clone().into_client().await?;
+
+		let connection_id = match &self.connection_id {
+			Some(connection_id) =>
+				ConnectionId::from_str(connection_id).expect("Connection id was invalid"),
+			None => any_chain_a.connection_id(),
+		};
+		let connection_id_b = complete_connection(
+			&mut any_chain_a,
+			&mut any_chain_b,
+			connection_id.clone(),
+			self.dry_run,
+		)
+		.await?;
+		if let Some(connection_id_b) = connection_id_b {
+			log::info!("ConnectionId on Chain {}: {}", any_chain_a.name(), connection_id);
+			log::info!("ConnectionId on Chain {}: {}", any_chain_b.name(), connection_id_b);
+			config.chain_a.set_connection_id(connection_id);
+			config.chain_b.set_connection_id(connection_id_b);
+		}
+
+		Ok(config)
+	}
+
+	pub async fn complete_channel(&self) -> Result<Config> {
+		let port_id = PortId::from_str(
+			self.port_id
+				.as_ref()
+				.expect("port_id must be specified when completing a channel")
+				.as_str(),
+		)
+		.expect("Port id was invalid");
+		let channel_id = ChannelId::from_str(
+			self.channel_id
+				.as_ref()
+				.expect("channel_id must be specified when completing a channel")
+				.as_str(),
+		)
+		.expect("Channel id was invalid");
+		let path: PathBuf = self.config.parse()?;
+		let file_content = tokio::fs::read_to_string(path).await?;
+		let mut config: Config = toml::from_str(&file_content)?;
+		let mut any_chain_a = config.chain_a.clone().into_client().await?;
+		let mut any_chain_b = config.chain_b.clone().into_client().await?;
+
+		let channel_b = complete_channel(
+			&mut any_chain_a,
+			&mut any_chain_b,
+			port_id.clone(),
+			channel_id,
+			self.dry_run,
+		)
+		.await?;
+		if let Some((channel_id_b, port_id_b)) = channel_b {
+			log::info!("ChannelId on Chain {}: {}", any_chain_a.name(), channel_id);
+			log::info!("ChannelId on Chain {}: {}", any_chain_b.name(), channel_id_b);
+			config.chain_a.set_channel_whitelist(channel_id, port_id);
+			config.chain_b.set_channel_whitelist(channel_id_b, port_id_b);
+		}
+
+		Ok(config)
+	}
+
+	/// Upgrades the client of each chain on its counterparty, if the chain scheduled an upgrade.
+	pub async fn upgrade_clients(&self) -> Result<()> {
+		let path: PathBuf = self.config.parse()?;
+		let file_content = tokio::fs::read_to_string(path).await?;
+		let config: Config = toml::from_str(&file_content)?;
+		let any_chain_a = config.chain_a.into_client().await?;
+		let any_chain_b = config.chain_b.into_client().await?;
+
+		for (mut source, sink) in
+			[(any_chain_a.clone(), any_chain_b.clone()), (any_chain_b, any_chain_a)]
+		{
+			match upgrade_client(&mut source, &sink, self.dry_run).await {
+				Ok(Some(height)) => log::info!(
+					"Client of {} on {} {} to {height}",
+					source.name(),
+					sink.name(),
+					if self.dry_run { "can be upgraded" } else { "upgraded" }
+				),
+				Ok(None) => {},
+				Err(e) => log::warn!(
+					"Skipping upgrade of the client of {} on {}: {e:?}",
+					source.name(),
+					sink.name()
+				),
+			}
+		}
+		Ok(())
+	}
+
+	pub async fn register_payees(&self) -> Result<()> {
+		let path: PathBuf = self.config.parse()?;
+		let file_content = tokio::fs::read_to_string(path).await?;
+		let config: Config = toml::from_str(&file_content)?;
+		let any_chain_a = config.chain_a.into_client().await?;
+		let any_chain_b = config.chain_b.into_client().await?;
+
+		for (chain, counterparty) in [(&any_chain_a, &any_chain_b), (&any_chain_b, &any_chain_a)] {
+			let channels = register_counterparty_payees(chain, counterparty, self.dry_run).await?;
+			for (channel_id, port_id) in channels {
+				log::info!(
+					"{} {} as the counterparty payee of {} on {channel_id}/{port_id}",
+					if self.dry_run { "Can register" } else { "Registered" },
+					counterparty.account_id(),
+					chain.account_id(),
+				);
+			}
+		}
+		Ok(())
+	}
+
+	/// Writes the updated config to the new config path if given, or over the existing config.
+	/// Nothing is written in dry-run mode, since the config is left unchanged.
+	pub async fn save_config(&self, new_config: &Config) -> Result<()> {
+		if self.dry_run {
+			return Ok(())
+		}
+		let path = self.new_config.as_ref().unwrap_or(&self.config);
+		tokio::fs::write(path.parse::<PathBuf>()?, toml::to_string(new_config)?).await?;
+		Ok(())
+	}
 }
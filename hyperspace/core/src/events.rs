@@ -14,7 +14,10 @@
 
 #[cfg(feature = "testing")]
 use crate::send_packet_relay::packet_relay_status;
-use crate::{packets::query_ready_and_timed_out_packets, Mode};
+use crate::{
+	packets::{query_ready_and_timed_out_packets, query_timed_out_channel_upgrades, should_relay},
+	Mode,
+};
 use codec::Encode;
 use ibc::{
 	core::{
@@ -28,12 +31,18 @@ use ibc::{
 			},
 		},
 		ics04_channel::{
-			channel::{ChannelEnd, Counterparty as ChannelCounterparty},
+			channel::{ChannelEnd, Counterparty as ChannelCounterparty, State},
+			events::UpgradeAttributes,
 			msgs::{
 				acknowledgement::MsgAcknowledgement, chan_close_confirm::MsgChannelCloseConfirm,
 				chan_open_ack::MsgChannelOpenAck, chan_open_confirm::MsgChannelOpenConfirm,
-				chan_open_try::MsgChannelOpenTry, recv_packet::MsgRecvPacket,
+				chan_open_try::MsgChannelOpenTry, chan_upgrade_ack::MsgChannelUpgradeAck,
+				chan_upgrade_cancel::MsgChannelUpgradeCancel,
+				chan_upgrade_confirm::MsgChannelUpgradeConfirm,
+				chan_upgrade_open::MsgChannelUpgradeOpen, chan_upgrade_try::MsgChannelUpgradeTry,
+				recv_packet::MsgRecvPacket,
 			},
+			upgrade::{ErrorReceipt, Upgrade},
 		},
 		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes},
 	},
@@ -421,11 +430,217 @@ pub async fn parse_events(
 				let msg = Any { value, type_url: msg.type_url() };
 				messages.push(msg)
 			},
+			IbcEvent::UpgradeInitChannel(upgrade_init) => {
+				let (channel_end, upgrade, proofs) =
+					query_channel_upgrade_proofs(source, &upgrade_init).await?;
+				let counterparty = channel_end.counterparty();
+				// The upgrade proposes connection hops of the source, the sink has to propose the
+				// other end of the same connection.
+				let connection_id =
+					upgrade.fields.connection_hops.get(0).cloned().ok_or_else(|| {
+						Error::Custom("Channel upgrade missing connection id".to_string())
+					})?;
+				let connection_response = source
+					.query_connection_end(upgrade_init.height(), connection_id.clone())
+					.await?;
+				let connection_end =
+					ConnectionEnd::try_from(connection_response.connection.ok_or_else(|| {
+						Error::Custom(format!("ConnectionEnd not found for {:?}", connection_id))
+					})?)?;
+				let proposed_connection_id =
+					connection_end.counterparty().connection_id().cloned().ok_or_else(|| {
+						Error::Custom(format!(
+							"Counterparty connection id not set for {:?}",
+							connection_id
+						))
+					})?;
+
+				let msg = MsgChannelUpgradeTry {
+					port_id: counterparty.port_id.clone(),
+					channel_id: counterparty.channel_id.expect("Expect channel id to be set"),
+					proposed_upgrade_connection_hops: vec![proposed_connection_id],
+					counterparty_upgrade_fields: upgrade.fields,
+					counterparty_upgrade_sequence: channel_end.upgrade_sequence(),
+					proofs,
+					signer: sink.account_id(),
+				};
+
+				let value = msg.encode_vec();
+				let msg = Any { value, type_url: msg.type_url() };
+				messages.push(msg)
+			},
+			IbcEvent::UpgradeTryChannel(upgrade_try) => {
+				let (channel_end, upgrade, proofs) =
+					query_channel_upgrade_proofs(source, &upgrade_try).await?;
+				let counterparty = channel_end.counterparty();
+
+				let msg = MsgChannelUpgradeAck {
+					port_id: counterparty.port_id.clone(),
+					channel_id: counterparty.channel_id.expect("Expect channel id to be set"),
+					counterparty_upgrade: upgrade,
+					proofs,
+					signer: sink.account_id(),
+				};
+
+				let value = msg.encode_vec();
+				let msg = Any { value, type_url: msg.type_url() };
+				messages.push(msg)
+			},
+			IbcEvent::UpgradeAckChannel(upgrade_ack) => {
+				let (channel_end, upgrade, proofs) =
+					query_channel_upgrade_proofs(source, &upgrade_ack).await?;
+				let counterparty = channel_end.counterparty();
+
+				let msg = MsgChannelUpgradeConfirm {
+					port_id: counterparty.port_id.clone(),
+					channel_id: counterparty.channel_id.expect("Expect channel id to be set"),
+					counterparty_channel_state: channel_end.state,
+					counterparty_upgrade: upgrade,
+					proofs,
+					signer: sink.account_id(),
+				};
+
+				let value = msg.encode_vec();
+				let msg = Any { value, type_url: msg.type_url() };
+				messages.push(msg)
+			},
+			// Either end may be the last one to finish flushing, so every step that can leave the
+			// source flushed or open is a chance to open the sink.
+			IbcEvent::UpgradeConfirmChannel(attributes) |
+			IbcEvent::UpgradeOpenChannel(attributes) |
+			IbcEvent::FlushCompleteChannel(attributes) => {
+				let channel_response = source
+					.query_channel_end(
+						attributes.height(),
+						attributes.channel_id,
+						attributes.port_id.clone(),
+					)
+					.await?;
+				let channel_end =
+					ChannelEnd::try_from(channel_response.channel.ok_or_else(|| {
+						Error::Custom(format!(
+							"[get_messages_for_events - upgrade_open] ChannelEnd not found for {:?}/{:?}",
+							attributes.channel_id, attributes.port_id
+						))
+					})?)?;
+				if !matches!(channel_end.state, State::FlushComplete | State::Open) {
+					continue
+				}
+				let counterparty = channel_end.counterparty();
+				let counterparty_channel_id =
+					counterparty.channel_id.expect("Expect channel id to be set");
+				let (sink_height, _) = sink.latest_height_and_timestamp().await?;
+				let sink_channel_response = sink
+					.query_channel_end(
+						sink_height,
+						counterparty_channel_id,
+						counterparty.port_id.clone(),
+					)
+					.await?;
+				let sink_channel_end =
+					ChannelEnd::try_from(sink_channel_response.channel.ok_or_else(|| {
+						Error::Custom(format!(
+							"[get_messages_for_events - upgrade_open] ChannelEnd not found for {:?}/{:?}",
+							counterparty_channel_id, counterparty.port_id
+						))
+					})?)?;
+				if sink_channel_end.state != State::FlushComplete {
+					continue
+				}
+				let channel_proof = CommitmentProofBytes::try_from(channel_response.proof)?;
+
+				let proof_height =
+					channel_response.proof_height.expect("Proof height should be present");
+				let proof_height =
+					Height::new(proof_height.revision_number, proof_height.revision_height);
+
+				let msg = MsgChannelUpgradeOpen {
+					port_id: counterparty.port_id.clone(),
+					channel_id: counterparty_channel_id,
+					counterparty_channel_state: channel_end.state,
+					counterparty_upgrade_sequence: channel_end.upgrade_sequence(),
+					proofs: Proofs::new(channel_proof, None, None, None, proof_height)?,
+					signer: sink.account_id(),
+				};
+
+				let value = msg.encode_vec();
+				let msg = Any { value, type_url: msg.type_url() };
+				messages.push(msg)
+			},
+			IbcEvent::UpgradeErrorChannel(upgrade_error) => {
+				let channel_response = source
+					.query_channel_end(
+						upgrade_error.height(),
+						upgrade_error.channel_id,
+						upgrade_error.port_id.clone(),
+					)
+					.await?;
+				let channel_end =
+					ChannelEnd::try_from(channel_response.channel.ok_or_else(|| {
+						Error::Custom(format!(
+							"[get_messages_for_events - upgrade_error] ChannelEnd not found for {:?}/{:?}",
+							upgrade_error.channel_id, upgrade_error.port_id
+						))
+					})?)?;
+				let counterparty = channel_end.counterparty();
+				let counterparty_channel_id =
+					counterparty.channel_id.expect("Expect channel id to be set");
+				// nothing to cancel if the sink never took part in this upgrade
+				let (sink_height, _) = sink.latest_height_and_timestamp().await?;
+				let sink_upgrade = sink
+					.query_channel_upgrade(
+						sink_height,
+						counterparty_channel_id,
+						counterparty.port_id.clone(),
+					)
+					.await
+					.ok()
+					.and_then(|response| response.upgrade);
+				if sink_upgrade.is_none() {
+					continue
+				}
+				let error_response = source
+					.query_channel_upgrade_error(
+						upgrade_error.height(),
+						upgrade_error.channel_id,
+						upgrade_error.port_id.clone(),
+					)
+					.await?;
+				let error_receipt =
+					ErrorReceipt::try_from(error_response.error_receipt.ok_or_else(|| {
+						Error::Custom(format!(
+							"[get_messages_for_events - upgrade_error] Error receipt not found for {:?}/{:?}",
+							upgrade_error.channel_id, upgrade_error.port_id
+						))
+					})?)?;
+				let error_proof = CommitmentProofBytes::try_from(error_response.proof)?;
+
+				let proof_height =
+					error_response.proof_height.expect("Proof height should be present");
+				let proof_height =
+					Height::new(proof_height.revision_number, proof_height.revision_height);
+
+				let msg = MsgChannelUpgradeCancel {
+					port_id: counterparty.port_id.clone(),
+					channel_id: counterparty_channel_id,
+					error_receipt,
+					proofs: Proofs::new(error_proof, None, None, None, proof_height)?,
+					signer: sink.account_id(),
+				};
+
+				let value = msg.encode_vec();
+				let msg = Any { value, type_url: msg.type_url() };
+				messages.push(msg)
+			},
 			IbcEvent::SendPacket(send_packet) => {
 				#[cfg(feature = "testing")]
 				if !packet_relay_status() {
 					continue
 				}
+				// packets are filtered by the policy of the chain that sent them
+				if !should_relay(source, &send_packet.packet) {
+					continue
+				}
 				// can we send this packet?
 				// 1. query the connection and get the connection delay.
 				// 2. if none, send message immediately
@@ -480,6 +695,10 @@ pub async fn parse_events(
 				messages.push(msg);
 			},
 			IbcEvent::WriteAcknowledgement(write_ack) => {
+				// the acknowledged packet was sent from the sink
+				if !should_relay(sink, &write_ack.packet) {
+					continue
+				}
 				let port_id = &write_ack.packet.source_port.clone();
 				let channel_id = &write_ack.packet.source_channel.clone();
 				let channel_response = source
@@ -542,13 +761,55 @@ pub async fn parse_events(
 	}
 
 	// 2. query packets that can now be sent, at this sink height because of connection delay.
-	let (ready_packets, timed_out_packets) =
+	let (ready_packets, mut timed_out_packets) =
 		query_ready_and_timed_out_packets(source, sink).await?;
 	messages.extend(ready_packets);
 
+	// 3. upgrades the sink failed to complete in time are aborted on the source, which lets the
+	// sink cancel them once the error receipt is relayed back.
+	timed_out_packets.extend(query_timed_out_channel_upgrades(source, sink).await?);
+
 	Ok((messages, timed_out_packets))
 }
 
+/// Fetch a channel end that is being upgraded along with its upgrade, the proofs of both are
+/// taken at the height of the upgrade event.
+async fn query_channel_upgrade_proofs(
+	source: &impl Chain,
+	attributes: &UpgradeAttributes,
+) -> Result<(ChannelEnd, Upgrade, Proofs), anyhow::Error> {
+	let channel_response = source
+		.query_channel_end(attributes.height(), attributes.channel_id, attributes.port_id.clone())
+		.await?;
+	let channel_end = ChannelEnd::try_from(channel_response.channel.ok_or_else(|| {
+		Error::Custom(format!(
+			"[get_messages_for_events - channel_upgrade] ChannelEnd not found for {:?}/{:?}",
+			attributes.channel_id, attributes.port_id
+		))
+	})?)?;
+	let upgrade_response = source
+		.query_channel_upgrade(
+			attributes.height(),
+			attributes.channel_id,
+			attributes.port_id.clone(),
+		)
+		.await?;
+	let upgrade = Upgrade::try_from(upgrade_response.upgrade.ok_or_else(|| {
+		Error::Custom(format!(
+			"[get_messages_for_events - channel_upgrade] Upgrade not found for {:?}/{:?}",
+			attributes.channel_id, attributes.port_id
+		))
+	})?)?;
+	let channel_proof = CommitmentProofBytes::try_from(channel_response.proof)?;
+	let upgrade_proof = CommitmentProofBytes::try_from(upgrade_response.proof)?;
+
+	let proof_height = channel_response.proof_height.expect("Proof height should be present");
+	let proof_height = Height::new(proof_height.revision_number, proof_height.revision_height);
+
+	let proofs = Proofs::new(channel_proof, None, None, Some(upgrade_proof), proof_height)?;
+	Ok((channel_end, upgrade, proofs))
+}
+
 /// Fetch the connection proof for the sink chain.
 async fn query_consensus_proof(
 	sink: &impl Chain,
@@ -0,0 +1,77 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registration of the relayer's payees with the ICS29 fee middleware.
+
+use crate::{handshake::wait_for_finality, queue};
+use ibc::{
+	applications::fee::{metadata::Metadata, msgs::register_payee::MsgRegisterCounterpartyPayee},
+	core::{
+		ics04_channel::Version,
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	tx_msg::Msg,
+};
+use ibc_proto::google::protobuf::Any;
+use primitives::Chain;
+use tendermint_proto::Protobuf;
+
+/// Registers the relayer account on `counterparty` as the counterparty payee of the relayer
+/// account on `chain`, for every whitelisted channel of `chain` that negotiated fees. This is
+/// where the receive fees of the packets `chain` receives will be paid.
+///
+/// Returns the channels the payee was registered on. In dry-run mode the messages are only
+/// simulated.
+pub async fn register_counterparty_payees<A, B>(
+	chain: &A,
+	counterparty: &B,
+	dry_run: bool,
+) -> Result<Vec<(ChannelId, PortId)>, anyhow::Error>
+where
+	A: Chain,
+	B: Chain,
+{
+	let (height, ..) = chain.latest_height_and_timestamp().await?;
+	let mut channels = vec![];
+	let mut messages = vec![];
+	for (channel_id, port_id) in chain.channel_whitelist() {
+		let channel = chain.query_channel_end(height, channel_id, port_id.clone()).await?;
+		let fee_enabled = channel
+			.channel
+			.map(|channel| Metadata::from_version(&Version::new(channel.version)).is_some())
+			.unwrap_or_default();
+		if !fee_enabled {
+			continue
+		}
+		let msg = MsgRegisterCounterpartyPayee {
+			port_id: port_id.clone(),
+			channel_id,
+			relayer: chain.account_id(),
+			counterparty_payee: counterparty.account_id().to_string(),
+		};
+		messages.push(Any { type_url: msg.type_url(), value: msg.encode_vec() });
+		channels.push((channel_id, port_id));
+	}
+	if messages.is_empty() {
+		return Ok(channels)
+	}
+
+	if dry_run {
+		queue::simulate_message_batch(messages, chain).await?;
+		return Ok(channels)
+	}
+	let transactions = queue::flush_message_batch(messages, None, chain, counterparty).await?;
+	wait_for_finality(chain, transactions).await?;
+	Ok(channels)
+}
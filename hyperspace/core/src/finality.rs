@@ -0,0 +1,93 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sharing of a single finality subscription between all the tasks following a chain.
+
+use futures::{stream, Stream, StreamExt};
+use primitives::Chain;
+use std::{pin::Pin, time::Duration};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Number of finality events buffered for each subscriber before it starts lagging behind.
+const FINALITY_CHANNEL_CAPACITY: usize = 64;
+/// Delay before an ended finality subscription is restarted.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(10);
+
+/// A single subscription to the finality notifications of a chain, whose events are broadcast to
+/// the relay loop, packet clearing and client refreshes alike.
+pub struct SharedFinality<E> {
+	name: String,
+	sender: broadcast::Sender<E>,
+}
+
+impl<E> Clone for SharedFinality<E> {
+	fn clone(&self) -> Self {
+		Self { name: self.name.clone(), sender: self.sender.clone() }
+	}
+}
+
+impl<E: Clone + Send + 'static> SharedFinality<E> {
+	/// Subscribes to the finality notifications of `chain`, re-subscribing whenever they end.
+	pub fn new<C>(chain: C) -> Self
+	where
+		C: Chain + Clone + 'static,
+		C: primitives::IbcProvider<FinalityEvent = E>,
+	{
+		let (sender, _) = broadcast::channel(FINALITY_CHANNEL_CAPACITY);
+		let name = chain.name().to_owned();
+		tokio::spawn(forward_finality_notifications(chain, sender.clone()));
+		Self { name, sender }
+	}
+
+	/// Returns a stream of the finality notifications observed from now on.
+	pub fn subscribe(&self) -> Pin<Box<dyn Stream<Item = E> + Send>> {
+		let name = self.name.clone();
+		Box::pin(stream::unfold(self.sender.subscribe(), move |mut receiver| {
+			let name = name.clone();
+			async move {
+				loop {
+					match receiver.recv().await {
+						Ok(event) => return Some((event, receiver)),
+						// skipped finality events are covered by the next one, since events are
+						// always queried from the latest height known to the counterparty.
+						Err(RecvError::Lagged(skipped)) => log::warn!(
+							"Subscriber lagged behind finality notifications from {name}, skipped {skipped}"
+						),
+						Err(RecvError::Closed) => return None,
+					}
+				}
+			}
+		}))
+	}
+}
+
+/// Forwards the finality notifications of `chain` to all its subscribers, re-subscribing
+/// whenever the underlying stream ends.
+async fn forward_finality_notifications<C: Chain>(
+	chain: C,
+	sender: broadcast::Sender<C::FinalityEvent>,
+) {
+	loop {
+		let mut notifications = chain.finality_notifications().await;
+		while let Some(event) = notifications.next().await {
+			// this only fails when nothing is currently subscribed.
+			let _ = sender.send(event);
+		}
+		log::error!(
+			"Finality notifications from {} ended, re-subscribing in {RESUBSCRIBE_DELAY:?}",
+			chain.name()
+		);
+		tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+	}
+}
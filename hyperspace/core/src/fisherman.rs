@@ -0,0 +1,284 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fisherman that watches every light client of a given type on a chain, checking the client
+//! updates it receives against trusted connections to the chains they track.
+
+use crate::chain::{AnyChain, FishermanConfig};
+use anyhow::anyhow;
+use futures::{future::ready, StreamExt};
+use ibc::{
+	core::{
+		ics02_client::{
+			client_state::{ClientState, ClientType},
+			events::UpdateClient,
+		},
+		ics24_host::identifier::ClientId,
+	},
+	events::IbcEvent,
+};
+use metrics::data::FishermanMetrics;
+use pallet_ibc::light_clients::AnyClientState;
+use primitives::{
+	misbehaviour::{MisbehaviourAlert, UnreportedMisbehaviour},
+	Chain, IbcProvider, MisbehaviourHandler,
+};
+use prometheus::Registry;
+use serde::Serialize;
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+/// Delay before the ibc events subscription is restarted.
+const RESTART_DELAY: Duration = Duration::from_secs(10);
+
+/// An alert as it's recorded to the alert log.
+#[derive(Serialize)]
+struct AlertRecord<'a> {
+	/// Unix timestamp in seconds at which the misbehaviour was found
+	timestamp: u64,
+	#[serde(flatten)]
+	alert: &'a MisbehaviourAlert,
+}
+
+/// Watches the light clients on a chain for misbehaviour until it's stopped, errors are logged and
+/// counted without interrupting the watch.
+pub struct Fisherman {
+	host: AnyChain,
+	counterparties: Vec<AnyChain>,
+	client_type: ClientType,
+	alert_log: PathBuf,
+	metrics: Option<FishermanMetrics>,
+	/// Trusted connection to the chain tracked by each watched client, `None` if it isn't one of
+	/// the configured counterparties.
+	clients: HashMap<ClientId, Option<AnyChain>>,
+}
+
+impl Fisherman {
+	/// Connects to the host and all the counterparties.
+	pub async fn new(config: FishermanConfig, registry: Option<&Registry>) -> anyhow::Result<Self> {
+		let host = config.host.into_client().await?;
+		let mut counterparties = vec![];
+		for counterparty in config.counterparties {
+			counterparties.push(counterparty.into_client().await?);
+		}
+		let metrics = registry.and_then(|registry| {
+			FishermanMetrics::register(host.name(), registry)
+				.map_err(|err| log::warn!("Failed to register fisherman metrics: {err:?}"))
+				.ok()
+		});
+		Ok(Self {
+			host,
+			counterparties,
+			client_type: config.client_type,
+			alert_log: config.alert_log,
+			metrics,
+			clients: HashMap::new(),
+		})
+	}
+
+	/// Checks every update of the watched clients for misbehaviour, re-subscribing to the ibc
+	/// events of the host whenever the subscription ends.
+	pub async fn run(mut self) -> anyhow::Result<()> {
+		if let Err(err) = self.refresh_clients().await {
+			log::error!("Failed to query the clients on {}: {err:?}", self.host.name());
+		}
+		loop {
+			let client_type = self.client_type.clone();
+			let mut updates = self.host.ibc_events().await.filter_map(move |event| {
+				ready(match event {
+					IbcEvent::UpdateClient(update) if update.client_type() == client_type =>
+						Some(update),
+					_ => None,
+				})
+			});
+			while let Some(update) = updates.next().await {
+				let client_id = update.client_id().clone();
+				if let Err(err) = self.check_update(update).await {
+					log::error!("Failed to check the update of client {client_id} for misbehaviour: {err:?}");
+					if let Some(metrics) = self.metrics.as_ref() {
+						metrics.number_of_failed_checks.inc();
+					}
+				}
+			}
+			log::error!(
+				"Ibc events from {} ended, re-subscribing in {RESTART_DELAY:?}",
+				self.host.name()
+			);
+			tokio::time::sleep(RESTART_DELAY).await;
+		}
+	}
+
+	/// Checks a single client update against the counterparty tracked by the client, recording
+	/// the alert if misbehaviour is found, even if it couldn't be reported to the host.
+	async fn check_update(&mut self, update: UpdateClient) -> anyhow::Result<()> {
+		let client_id = update.client_id().clone();
+		// the client may have been created after the last refresh
+		if !self.clients.contains_key(&client_id) {
+			self.refresh_clients().await?;
+		}
+		let counterparty = match self.clients.get(&client_id) {
+			Some(Some(counterparty)) => counterparty,
+			_ => return Ok(()),
+		};
+
+		let message = self.host.query_client_message(update).await?;
+		let outcome = counterparty.check_for_misbehaviour(&self.host, message).await;
+		record_outcome(&self.alert_log, self.metrics.as_ref(), outcome).await
+	}
+
+	/// Matches the clients on the host that aren't watched yet with the counterparty they track.
+	async fn refresh_clients(&mut self) -> anyhow::Result<()> {
+		let (height, _) = self.host.latest_height_and_timestamp().await?;
+		for client_id in self.host.query_clients().await? {
+			if self.clients.contains_key(&client_id) {
+				continue
+			}
+			let response = self.host.query_client_state(height, client_id.clone()).await?;
+			let client_state = response
+				.client_state
+				.ok_or_else(|| anyhow!("Client state for {client_id} not found"))?;
+			let client_state = match AnyClientState::try_from(client_state) {
+				Ok(client_state) => client_state,
+				Err(err) => {
+					log::debug!("Skipping client {client_id} with unknown client state: {err:?}");
+					continue
+				},
+			};
+			if client_state.client_type() != self.client_type {
+				continue
+			}
+
+			let counterparty = self
+				.counterparties
+				.iter()
+				.find(|counterparty| counterparty.is_tracked_by(&client_state))
+				.map(|counterparty| {
+					let mut counterparty = counterparty.clone();
+					counterparty.set_client_id(client_id.clone());
+					counterparty
+				});
+			match &counterparty {
+				Some(counterparty) => log::info!(
+					"Watching client {client_id} on {} tracking {}",
+					self.host.name(),
+					counterparty.name()
+				),
+				None => log::warn!(
+					"No trusted counterparty configured for client {client_id} on {}, its updates won't be checked",
+					self.host.name()
+				),
+			}
+			self.clients.insert(client_id, counterparty);
+		}
+		Ok(())
+	}
+}
+
+/// Records the outcome of checking a client update for misbehaviour. The alert is appended to the
+/// alert log whether or not the misbehaviour could be reported, failing to report it is returned
+/// afterwards.
+async fn record_outcome(
+	alert_log: &Path,
+	metrics: Option<&FishermanMetrics>,
+	outcome: anyhow::Result<Option<MisbehaviourAlert>>,
+) -> anyhow::Result<()> {
+	let (alert, error) = match outcome {
+		Ok(alert) => (alert, None),
+		Err(err) => match err.downcast::<UnreportedMisbehaviour>() {
+			Ok(UnreportedMisbehaviour { alert, error }) => (Some(alert), Some(error)),
+			Err(err) => return Err(err),
+		},
+	};
+	if let Some(metrics) = metrics {
+		metrics.number_of_checked_client_updates.inc();
+	}
+	if let Some(alert) = alert {
+		if let Some(metrics) = metrics {
+			metrics.number_of_misbehaviours.inc();
+		}
+		record(alert_log, &alert).await?;
+	}
+	match error {
+		Some(error) => Err(error.context("Failed to submit misbehaviour report")),
+		None => Ok(()),
+	}
+}
+
+/// Appends the alert to the alert log.
+async fn record(alert_log: &Path, alert: &MisbehaviourAlert) -> anyhow::Result<()> {
+	let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+	let mut line = serde_json::to_vec(&AlertRecord { timestamp, alert })?;
+	line.push(b'\n');
+	let mut file = OpenOptions::new().create(true).append(true).open(alert_log).await?;
+	file.write_all(&line).await?;
+	file.sync_data().await?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn alert(client_id: &str) -> MisbehaviourAlert {
+		MisbehaviourAlert {
+			counterparty: "picasso".to_owned(),
+			client_id: client_id.to_owned(),
+			height: 10,
+			submitted_hash: "0x01".to_owned(),
+			canonical_hash: "0x02".to_owned(),
+			equivocators: vec![],
+		}
+	}
+
+	fn alert_log(name: &str) -> PathBuf {
+		let path = std::env::temp_dir().join(format!("{name}-{}.jsonl", std::process::id()));
+		let _ = std::fs::remove_file(&path);
+		path
+	}
+
+	#[tokio::test]
+	async fn alerts_are_recorded_when_the_report_fails() {
+		let path = alert_log("unreported-misbehaviour");
+		// the host rejected the misbehaviour report
+		let outcome = Err(UnreportedMisbehaviour {
+			alert: alert("10-grandpa-0"),
+			error: anyhow::Error::msg("connection refused"),
+		}
+		.into());
+		let error = record_outcome(&path, None, outcome).await.unwrap_err();
+		assert!(format!("{error:?}").contains("connection refused"));
+
+		let log = std::fs::read_to_string(&path).unwrap();
+		assert_eq!(log.lines().count(), 1);
+		assert!(log.contains("\"client_id\":\"10-grandpa-0\""));
+		std::fs::remove_file(path).unwrap();
+	}
+
+	#[tokio::test]
+	async fn only_misbehaviour_is_recorded() {
+		let path = alert_log("checked-updates");
+		record_outcome(&path, None, Ok(None)).await.unwrap();
+		assert!(record_outcome(&path, None, Err(anyhow::Error::msg("rpc error"))).await.is_err());
+		assert!(!path.exists());
+
+		record_outcome(&path, None, Ok(Some(alert("10-grandpa-1")))).await.unwrap();
+		let log = std::fs::read_to_string(&path).unwrap();
+		assert!(log.contains("10-grandpa-1"));
+		std::fs::remove_file(path).unwrap();
+	}
+}
@@ -0,0 +1,532 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Completion of connection and channel handshakes. Rather than waiting for the events of each
+//! handshake step, the state of both ends is read from the chains and the next missing step is
+//! submitted, so a handshake that was interrupted half-way can always be resumed.
+
+use crate::{
+	events::parse_events, packets::clearing::update_counterparty_client, queue,
+	tracker::SubmittedTx, Mode,
+};
+use anyhow::anyhow;
+use ibc::{
+	core::{
+		ics02_client::client_state::ClientState as ClientStateT,
+		ics03_connection::{
+			connection::{ConnectionEnd, IdentifiedConnectionEnd, State as ConnectionState},
+			events::{self as connection_events, Attributes as ConnectionAttributes},
+		},
+		ics04_channel::{
+			channel::{ChannelEnd, IdentifiedChannelEnd, State as ChannelState},
+			events as channel_events,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+	},
+	events::IbcEvent,
+	Height,
+};
+use pallet_ibc::light_clients::AnyClientState;
+use primitives::{Chain, TxStatus};
+use std::time::{Duration, Instant};
+
+/// Time the transactions of a handshake step have to be finalized.
+const STEP_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// A handshake step, named after the event on the source chain that the next message is proving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+	OpenInit,
+	OpenTry,
+	OpenAck,
+}
+
+/// The state of a handshake end, reduced to the states connections and channels have in common.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EndState {
+	Init,
+	TryOpen,
+	Open,
+	/// Any state the handshake can't be resumed from, e.g. a closed channel.
+	Other,
+}
+
+impl From<ConnectionState> for EndState {
+	fn from(state: ConnectionState) -> Self {
+		match state {
+			ConnectionState::Init => EndState::Init,
+			ConnectionState::TryOpen => EndState::TryOpen,
+			ConnectionState::Open => EndState::Open,
+			ConnectionState::Uninitialized => EndState::Other,
+		}
+	}
+}
+
+impl From<ChannelState> for EndState {
+	fn from(state: ChannelState) -> Self {
+		match state {
+			ChannelState::Init => EndState::Init,
+			ChannelState::TryOpen => EndState::TryOpen,
+			ChannelState::Open => EndState::Open,
+			_ => EndState::Other,
+		}
+	}
+}
+
+/// What remains to be done to complete a handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NextStep {
+	/// Both ends are open.
+	Done,
+	/// The step must be relayed from chain a to chain b.
+	TowardsB(Step),
+	/// The step must be relayed from chain b to chain a.
+	TowardsA(Step),
+	/// The handshake can't be completed from the current states.
+	Stuck,
+}
+
+/// Returns the next step of a handshake given the state of its end on chain a, and of the end on
+/// chain b if there's one yet.
+fn next_step(state_a: EndState, state_b: Option<EndState>) -> NextStep {
+	match (state_a, state_b) {
+		(EndState::Open, Some(EndState::Open)) => NextStep::Done,
+		(EndState::Init, None) => NextStep::TowardsB(Step::OpenInit),
+		(EndState::Init, Some(EndState::TryOpen)) => NextStep::TowardsA(Step::OpenTry),
+		(EndState::TryOpen, Some(EndState::Init)) => NextStep::TowardsB(Step::OpenTry),
+		(EndState::Open, Some(EndState::TryOpen)) => NextStep::TowardsB(Step::OpenAck),
+		(EndState::TryOpen, Some(EndState::Open)) => NextStep::TowardsA(Step::OpenAck),
+		_ => NextStep::Stuck,
+	}
+}
+
+/// Submits the missing steps of the handshake of `connection_id` on `chain_a` until both ends are
+/// open, and returns the id of the connection on `chain_b`. In dry-run mode only the next step is
+/// simulated and `None` is returned.
+pub async fn complete_connection<A, B>(
+	chain_a: &mut A,
+	chain_b: &mut B,
+	connection_id: ConnectionId,
+	dry_run: bool,
+) -> Result<Option<ConnectionId>, anyhow::Error>
+where
+	A: Chain,
+	B: Chain,
+{
+	loop {
+		let end_a = query_connection(chain_a, connection_id.clone()).await?;
+		let counterparty = find_counterparty_connection(chain_b, &connection_id, &end_a).await?;
+		let state_b = counterparty.as_ref().map(|end| *end.connection_end.state());
+		let (towards_a, step) = match next_step((*end_a.state()).into(), state_b.map(Into::into)) {
+			NextStep::Done => {
+				let connection_id_b = counterparty.map(|end| end.connection_id);
+				log::info!(
+					"Connection {connection_id} on {} is open with {connection_id_b:?} on {}",
+					chain_a.name(),
+					chain_b.name()
+				);
+				return Ok(connection_id_b)
+			},
+			NextStep::TowardsB(step) => (false, step),
+			NextStep::TowardsA(step) => (true, step),
+			NextStep::Stuck => Err(anyhow!(
+				"Connection {connection_id} can't be completed, it's {:?} on {} and {state_b:?} on {}",
+				end_a.state(),
+				chain_a.name(),
+				chain_b.name()
+			))?,
+		};
+
+		if towards_a {
+			let counterparty = counterparty.expect("counterparty connection exists; qed");
+			log::info!(
+				"Relaying connection {step:?} from {} to {}",
+				chain_b.name(),
+				chain_a.name()
+			);
+			let event = |height| {
+				connection_event(
+					step,
+					height,
+					counterparty.connection_id,
+					&counterparty.connection_end,
+				)
+			};
+			submit_step(chain_b, chain_a, event, dry_run).await?;
+		} else {
+			log::info!(
+				"Relaying connection {step:?} from {} to {}",
+				chain_a.name(),
+				chain_b.name()
+			);
+			let id = connection_id.clone();
+			let event = |height| connection_event(step, height, id, &end_a);
+			submit_step(chain_a, chain_b, event, dry_run).await?;
+		}
+		if dry_run {
+			return Ok(None)
+		}
+	}
+}
+
+/// Submits the missing steps of the handshake of `channel_id` on `chain_a` until both ends are
+/// open, and returns the id and port of the channel on `chain_b`. In dry-run mode only the next
+/// step is simulated and `None` is returned.
+pub async fn complete_channel<A, B>(
+	chain_a: &mut A,
+	chain_b: &mut B,
+	port_id: PortId,
+	channel_id: ChannelId,
+	dry_run: bool,
+) -> Result<Option<(ChannelId, PortId)>, anyhow::Error>
+where
+	A: Chain,
+	B: Chain,
+{
+	loop {
+		let end_a = query_channel(chain_a, port_id.clone(), channel_id).await?;
+		let counterparty =
+			find_counterparty_channel(chain_a, chain_b, &port_id, channel_id, &end_a).await?;
+		let state_b = counterparty.as_ref().map(|end| end.channel_end.state);
+		let (towards_a, step) = match next_step(end_a.state.into(), state_b.map(Into::into)) {
+			NextStep::Done => {
+				let channel_b = counterparty.map(|end| (end.channel_id, end.port_id));
+				log::info!(
+					"Channel {channel_id}/{port_id} on {} is open with {channel_b:?} on {}",
+					chain_a.name(),
+					chain_b.name()
+				);
+				return Ok(channel_b)
+			},
+			NextStep::TowardsB(step) => (false, step),
+			NextStep::TowardsA(step) => (true, step),
+			NextStep::Stuck => Err(anyhow!(
+				"Channel {channel_id}/{port_id} can't be completed, it's {:?} on {} and {state_b:?} on {}",
+				end_a.state,
+				chain_a.name(),
+				chain_b.name()
+			))?,
+		};
+
+		if towards_a {
+			let counterparty = counterparty.expect("counterparty channel exists; qed");
+			log::info!("Relaying channel {step:?} from {} to {}", chain_b.name(), chain_a.name());
+			let event = |height| {
+				channel_event(
+					step,
+					height,
+					counterparty.port_id.clone(),
+					counterparty.channel_id,
+					&counterparty.channel_end,
+				)
+			};
+			submit_step(chain_b, chain_a, event, dry_run).await?;
+		} else {
+			log::info!("Relaying channel {step:?} from {} to {}", chain_a.name(), chain_b.name());
+			let event = |height| channel_event(step, height, port_id.clone(), channel_id, &end_a);
+			submit_step(chain_a, chain_b, event, dry_run).await?;
+		}
+		if dry_run {
+			return Ok(None)
+		}
+	}
+}
+
+/// Builds the message proving `event` on `source` and submits it to `sink`, once the client of
+/// `source` on `sink` has been updated. The event is built at the height the client was updated
+/// to, so that the message proofs can be verified against it.
+async fn submit_step<S, T>(
+	source: &mut S,
+	sink: &mut T,
+	event: impl FnOnce(Height) -> Result<IbcEvent, anyhow::Error>,
+	dry_run: bool,
+) -> Result<(), anyhow::Error>
+where
+	S: Chain,
+	T: Chain,
+{
+	// in dry-run mode the message is proven at the height the client is currently at.
+	if !dry_run {
+		let mut finality = source.finality_notifications().await;
+		let transactions = update_counterparty_client(source, &*sink, &mut finality, None).await?;
+		wait_for_finality(sink, transactions).await?;
+	}
+	let height = latest_client_height(sink).await?;
+	let (messages, _) = parse_events(source, sink, vec![event(height)?], Some(Mode::Light)).await?;
+	if dry_run {
+		return queue::simulate_message_batch(messages, sink).await
+	}
+	let transactions = queue::flush_message_batch(messages, None, sink, source).await?;
+	wait_for_finality(sink, transactions).await
+}
+
+/// Returns the latest height of the counterparty client on `chain`.
+pub(crate) async fn latest_client_height(chain: &impl Chain) -> Result<Height, anyhow::Error> {
+	let (height, _) = chain.latest_height_and_timestamp().await?;
+	let response = chain.query_client_state(height, chain.client_id()).await?;
+	let client_state = response
+		.client_state
+		.map(AnyClientState::try_from)
+		.ok_or_else(|| anyhow!("Client {} not found on {}", chain.client_id(), chain.name()))??;
+	Ok(client_state.latest_height())
+}
+
+/// Waits for `transactions` to be finalized on `chain`, failing if any of their messages failed,
+/// so that the next step is only decided once the chain state reflects the previous one.
+pub(crate) async fn wait_for_finality<C: Chain>(
+	chain: &C,
+	transactions: Vec<SubmittedTx<C::TransactionId>>,
+) -> Result<(), anyhow::Error> {
+	let started_at = Instant::now();
+	for tx in transactions {
+		loop {
+			match chain.query_tx_status(&tx.tx_id).await? {
+				TxStatus::Finalized { failed_messages } if failed_messages.is_empty() => break,
+				TxStatus::Finalized { failed_messages } => Err(anyhow!(
+					"Handshake messages failed on {}: {failed_messages:?}",
+					chain.name()
+				))?,
+				TxStatus::Failed(error) =>
+					Err(anyhow!("Handshake transaction failed on {}: {error}", chain.name()))?,
+				TxStatus::Pending | TxStatus::Included => {},
+			}
+			if started_at.elapsed() > STEP_TIMEOUT {
+				Err(anyhow!("Handshake transaction wasn't finalized on {} in time", chain.name()))?
+			}
+			tokio::time::sleep(chain.expected_block_time()).await;
+		}
+	}
+	Ok(())
+}
+
+async fn query_connection(
+	chain: &impl Chain,
+	connection_id: ConnectionId,
+) -> Result<ConnectionEnd, anyhow::Error> {
+	let (height, _) = chain.latest_height_and_timestamp().await?;
+	let response = chain.query_connection_end(height, connection_id.clone()).await?;
+	let connection = response
+		.connection
+		.ok_or_else(|| anyhow!("Connection {connection_id} not found on {}", chain.name()))?;
+	Ok(ConnectionEnd::try_from(connection)?)
+}
+
+/// Returns the connection on `chain` that is the counterparty of `connection_end`, looking it up
+/// among the connections of the counterparty client if its id isn't known yet.
+async fn find_counterparty_connection(
+	chain: &impl Chain,
+	connection_id: &ConnectionId,
+	connection_end: &ConnectionEnd,
+) -> Result<Option<IdentifiedConnectionEnd>, anyhow::Error> {
+	let counterparty = connection_end.counterparty();
+	if let Some(counterparty_id) = counterparty.connection_id() {
+		let end = query_connection(chain, counterparty_id.clone()).await?;
+		return Ok(Some(IdentifiedConnectionEnd::new(counterparty_id.clone(), end)))
+	}
+	let (height, _) = chain.latest_height_and_timestamp().await?;
+	let connections = chain
+		.query_connection_using_client(
+			height.revision_height as u32,
+			counterparty.client_id().to_string(),
+		)
+		.await?;
+	for connection in connections {
+		let connection = IdentifiedConnectionEnd::try_from(connection)?;
+		if connection.connection_end.counterparty().connection_id() == Some(connection_id) {
+			return Ok(Some(connection))
+		}
+	}
+	Ok(None)
+}
+
+async fn query_channel(
+	chain: &impl Chain,
+	port_id: PortId,
+	channel_id: ChannelId,
+) -> Result<ChannelEnd, anyhow::Error> {
+	let (height, _) = chain.latest_height_and_timestamp().await?;
+	let response = chain.query_channel_end(height, channel_id, port_id.clone()).await?;
+	let channel = response
+		.channel
+		.ok_or_else(|| anyhow!("Channel {channel_id}/{port_id} not found on {}", chain.name()))?;
+	Ok(ChannelEnd::try_from(channel)?)
+}
+
+/// Returns the channel on `chain_b` that is the counterparty of `channel_end`, looking it up
+/// among the channels of the counterparty connection if its id isn't known yet.
+async fn find_counterparty_channel(
+	chain_a: &impl Chain,
+	chain_b: &impl Chain,
+	port_id: &PortId,
+	channel_id: ChannelId,
+	channel_end: &ChannelEnd,
+) -> Result<Option<IdentifiedChannelEnd>, anyhow::Error> {
+	let counterparty = channel_end.counterparty();
+	if let Some(counterparty_id) = counterparty.channel_id() {
+		let end = query_channel(chain_b, counterparty.port_id().clone(), *counterparty_id).await?;
+		return Ok(Some(IdentifiedChannelEnd::new(
+			counterparty.port_id().clone(),
+			*counterparty_id,
+			end,
+		)))
+	}
+	let connection_id = connection_hop(channel_end)?;
+	let connection_end = query_connection(chain_a, connection_id.clone()).await?;
+	let counterparty_connection_id = connection_end
+		.counterparty()
+		.connection_id()
+		.ok_or_else(|| anyhow!("Connection {connection_id} on {} isn't open", chain_a.name()))?;
+	let (height, _) = chain_b.latest_height_and_timestamp().await?;
+	let channels = chain_b
+		.query_connection_channels(height, counterparty_connection_id)
+		.await?
+		.channels;
+	for channel in channels {
+		let channel = IdentifiedChannelEnd::try_from(channel)?;
+		let channel_counterparty = channel.channel_end.counterparty();
+		if &channel.port_id == counterparty.port_id() &&
+			channel_counterparty.port_id() == port_id &&
+			channel_counterparty.channel_id() == Some(&channel_id)
+		{
+			return Ok(Some(channel))
+		}
+	}
+	Ok(None)
+}
+
+fn connection_hop(channel_end: &ChannelEnd) -> Result<ConnectionId, anyhow::Error> {
+	channel_end
+		.connection_hops()
+		.get(0)
+		.cloned()
+		.ok_or_else(|| anyhow!("Channel end missing connection id"))
+}
+
+/// Builds the event that `step` of the handshake of `connection_id` emitted on its chain.
+fn connection_event(
+	step: Step,
+	height: Height,
+	connection_id: ConnectionId,
+	connection_end: &ConnectionEnd,
+) -> Result<IbcEvent, anyhow::Error> {
+	let attributes = ConnectionAttributes {
+		height,
+		connection_id: Some(connection_id),
+		client_id: connection_end.client_id().clone(),
+		counterparty_connection_id: connection_end.counterparty().connection_id().cloned(),
+		counterparty_client_id: connection_end.counterparty().client_id().clone(),
+	};
+	Ok(match step {
+		Step::OpenInit =>
+			IbcEvent::OpenInitConnection(connection_events::OpenInit::from(attributes)),
+		Step::OpenTry => IbcEvent::OpenTryConnection(connection_events::OpenTry::from(attributes)),
+		Step::OpenAck => IbcEvent::OpenAckConnection(connection_events::OpenAck::from(attributes)),
+	})
+}
+
+/// Builds the event that `step` of the handshake of `channel_id` emitted on its chain.
+fn channel_event(
+	step: Step,
+	height: Height,
+	port_id: PortId,
+	channel_id: ChannelId,
+	channel_end: &ChannelEnd,
+) -> Result<IbcEvent, anyhow::Error> {
+	let connection_id = connection_hop(channel_end)?;
+	let counterparty = channel_end.counterparty();
+	let counterparty_port_id = counterparty.port_id().clone();
+	let counterparty_channel_id = counterparty.channel_id().cloned();
+	Ok(match step {
+		Step::OpenInit => IbcEvent::OpenInitChannel(channel_events::OpenInit {
+			height,
+			port_id,
+			channel_id: Some(channel_id),
+			connection_id,
+			counterparty_port_id,
+			counterparty_channel_id,
+		}),
+		Step::OpenTry => IbcEvent::OpenTryChannel(channel_events::OpenTry {
+			height,
+			port_id,
+			channel_id: Some(channel_id),
+			connection_id,
+			counterparty_port_id,
+			counterparty_channel_id,
+		}),
+		Step::OpenAck => IbcEvent::OpenAckChannel(channel_events::OpenAck {
+			height,
+			port_id,
+			channel_id: Some(channel_id),
+			counterparty_channel_id,
+			connection_id,
+			counterparty_port_id,
+		}),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn open_ends_complete_the_handshake() {
+		assert_eq!(next_step(EndState::Open, Some(EndState::Open)), NextStep::Done);
+	}
+
+	#[test]
+	fn steps_are_relayed_towards_the_end_behind() {
+		assert_eq!(next_step(EndState::Init, None), NextStep::TowardsB(Step::OpenInit));
+		assert_eq!(
+			next_step(EndState::Init, Some(EndState::TryOpen)),
+			NextStep::TowardsA(Step::OpenTry)
+		);
+		assert_eq!(
+			next_step(EndState::TryOpen, Some(EndState::Init)),
+			NextStep::TowardsB(Step::OpenTry)
+		);
+		assert_eq!(
+			next_step(EndState::Open, Some(EndState::TryOpen)),
+			NextStep::TowardsB(Step::OpenAck)
+		);
+		assert_eq!(
+			next_step(EndState::TryOpen, Some(EndState::Open)),
+			NextStep::TowardsA(Step::OpenAck)
+		);
+	}
+
+	#[test]
+	fn inconsistent_states_are_stuck() {
+		for (state_a, state_b) in [
+			(EndState::TryOpen, None),
+			(EndState::Open, None),
+			(EndState::Init, Some(EndState::Init)),
+			(EndState::Init, Some(EndState::Open)),
+			(EndState::Open, Some(EndState::Init)),
+			(EndState::TryOpen, Some(EndState::TryOpen)),
+			(EndState::Other, Some(EndState::Open)),
+			(EndState::Open, Some(EndState::Other)),
+		] {
+			assert_eq!(next_step(state_a, state_b), NextStep::Stuck, "{state_a:?} {state_b:?}");
+		}
+	}
+
+	#[test]
+	fn closed_and_uninitialized_ends_are_not_resumed() {
+		assert_eq!(EndState::from(ChannelState::Closed), EndState::Other);
+		assert_eq!(EndState::from(ChannelState::Flushing), EndState::Other);
+		assert_eq!(EndState::from(ConnectionState::Uninitialized), EndState::Other);
+		assert_eq!(EndState::from(ChannelState::TryOpen), EndState::TryOpen);
+		assert_eq!(EndState::from(ConnectionState::Open), EndState::Open);
+	}
+}
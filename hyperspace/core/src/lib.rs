@@ -14,32 +14,47 @@
 
 #![warn(unused_variables)]
 
-use futures::{future::ready, StreamExt};
+use futures::{future::ready, Stream, StreamExt};
 use primitives::Chain;
 
+pub mod admin;
+pub mod balance;
 pub mod chain;
 pub mod command;
 pub mod events;
+pub mod fee;
+pub mod finality;
+pub mod fisherman;
+pub mod handshake;
 pub mod logging;
 mod macros;
 pub mod packets;
+pub mod query;
 pub mod queue;
+pub mod refresh;
+pub mod retry;
+pub mod supervisor;
+pub mod tracker;
+pub mod upgrade;
 
 use events::{has_packet_events, parse_events};
 use ibc::events::IbcEvent;
 use metrics::handler::MetricsHandler;
+use tracker::TxTracker;
 
 #[derive(Copy, Debug, Clone)]
 pub enum Mode {
 	/// Run without trying to relay packets or query channel state
 	Light,
+	/// Build and simulate the messages that would be relayed without submitting them
+	DryRun,
 }
 
 /// Core relayer loop, waits for new finality events and forwards any new [`ibc::IbcEvents`]
 /// to the counter party chain.
 pub async fn relay<A, B>(
-	mut chain_a: A,
-	mut chain_b: B,
+	chain_a: A,
+	chain_b: B,
 	mut chain_a_metrics: Option<MetricsHandler>,
 	mut chain_b_metrics: Option<MetricsHandler>,
 	mode: Option<Mode>,
@@ -48,18 +63,52 @@ where
 	A: Chain,
 	B: Chain,
 {
-	let (mut chain_a_finality, mut chain_b_finality) =
+	let (chain_a_finality, chain_b_finality) =
 		(chain_a.finality_notifications().await, chain_b.finality_notifications().await);
+	relay_with_finality(
+		chain_a,
+		chain_b,
+		chain_a_finality,
+		chain_b_finality,
+		&mut chain_a_metrics,
+		&mut chain_b_metrics,
+		mode,
+	)
+	.await
+}
+
+/// Same as [`relay`], but driven by the given finality streams rather than subscribing to
+/// finality notifications on both chains. This lets several relay loops share a single
+/// subscription per chain.
+pub async fn relay_with_finality<A, B, FA, FB>(
+	mut chain_a: A,
+	mut chain_b: B,
+	mut chain_a_finality: FA,
+	mut chain_b_finality: FB,
+	chain_a_metrics: &mut Option<MetricsHandler>,
+	chain_b_metrics: &mut Option<MetricsHandler>,
+	mode: Option<Mode>,
+) -> Result<(), anyhow::Error>
+where
+	A: Chain,
+	B: Chain,
+	FA: Stream<Item = A::FinalityEvent> + Unpin,
+	FB: Stream<Item = B::FinalityEvent> + Unpin,
+{
+	// batches that failed to be submitted to either chain, retried on the next finality event.
+	let (mut chain_a_pending, mut chain_b_pending) = (vec![], vec![]);
+	// transactions submitted to either chain that aren't finalized yet.
+	let (mut chain_a_tracker, mut chain_b_tracker) = (TxTracker::default(), TxTracker::default());
 	// loop forever
 	loop {
 		tokio::select! {
 			// new finality event from chain A
 			result = chain_a_finality.next() => {
-				process_finality_event!(chain_a, chain_b, chain_a_metrics, mode, result)
+				process_finality_event!(chain_a, chain_b, chain_a_metrics, mode, result, chain_a_pending, chain_b_pending, chain_a_tracker, chain_b_tracker)
 			}
 			// new finality event from chain B
 			result = chain_b_finality.next() => {
-				process_finality_event!(chain_b, chain_a, chain_b_metrics, mode, result)
+				process_finality_event!(chain_b, chain_a, chain_b_metrics, mode, result, chain_b_pending, chain_a_pending, chain_b_tracker, chain_a_tracker)
 			}
 		}
 	}
@@ -14,7 +14,17 @@
 
 #[macro_export]
 macro_rules! process_finality_event {
-	($source:ident, $sink:ident, $metrics:expr, $mode:ident, $result:ident) => {
+	(
+		$source:ident,
+		$sink:ident,
+		$metrics:expr,
+		$mode:ident,
+		$result:ident,
+		$source_pending:ident,
+		$sink_pending:ident,
+		$source_tracker:ident,
+		$sink_tracker:ident
+	) => {
 		match $result {
 			// stream closed
 			None => break,
@@ -25,11 +35,12 @@ macro_rules! process_finality_event {
 					match $source.query_latest_ibc_events(finality_event, &$sink).await {
 						Ok(resp) => resp,
 						Err(err) => {
-							log::error!(
-								"Failed to fetch IBC events for finality event for {} {:?}",
+							retry::handle_finality_event_error(
+								err,
+								"fetch IBC events for finality event",
 								$source.name(),
-								err
-							);
+								&|err: &anyhow::Error| retry::classify_error(err, &$source, &$sink),
+							)?;
 							continue
 						},
 					};
@@ -39,8 +50,68 @@ macro_rules! process_finality_event {
 					}
 				}
 				let event_types = events.iter().map(|ev| ev.event_type()).collect::<Vec<_>>();
-				let (mut messages, timeouts) =
-					parse_events(&mut $source, &mut $sink, events, $mode).await?;
+				let (messages, timeouts) =
+					match parse_events(&mut $source, &mut $sink, events, $mode).await {
+						Ok(messages) => messages,
+						Err(err) => {
+							retry::handle_finality_event_error(
+								err,
+								"parse events",
+								$source.name(),
+								&|err: &anyhow::Error| retry::classify_error(err, &$source, &$sink),
+							)?;
+							continue
+						},
+					};
+				let (source, sink) = (&$source, &$sink);
+				let (msg_update_client, mut messages, timeouts) = match queue::simulate_in_dry_run(
+					$mode,
+					msg_update_client,
+					messages,
+					timeouts,
+					|timeouts| async move {
+						if let Err(err) = queue::simulate_message_batch(timeouts, source).await {
+							log::error!(
+								"Failed to simulate timeouts on {}: {err:?}",
+								source.name()
+							);
+						}
+					},
+					|messages| async move {
+						if let Err(err) = queue::simulate_message_batch(messages, sink).await {
+							log::error!("Failed to simulate messages on {}: {err:?}", sink.name());
+						}
+					},
+				)
+				.await
+				{
+					Some(unsent) => unsent,
+					// dry runs submit nothing
+					None => continue,
+				};
+				// requeue the messages of transactions that were dropped or failed on chain.
+				$source_tracker.poll(&$source, $metrics.as_ref(), &mut $source_pending).await;
+				$sink_tracker.poll(&$sink, $metrics.as_ref(), &mut $sink_pending).await;
+				// resubmit batches that failed on previous finality events first, they may carry
+				// client updates that newer messages depend on. Batches whose client update went
+				// stale are submitted along with the update of this finality event.
+				let carried = retry::take_awaiting_update(&mut $sink_pending);
+				retry::retry_pending(
+					&mut $source_pending,
+					&mut $source_tracker,
+					$metrics.as_ref(),
+					&$source,
+					&$sink,
+				)
+				.await?;
+				retry::retry_pending(
+					&mut $sink_pending,
+					&mut $sink_tracker,
+					$metrics.as_ref(),
+					&$sink,
+					&$source,
+				)
+				.await?;
 				if !timeouts.is_empty() {
 					if let Some(metrics) = $metrics.as_ref() {
 						metrics.handle_timeouts(timeouts.as_slice()).await;
@@ -48,14 +119,23 @@ macro_rules! process_finality_event {
 					let type_urls =
 						timeouts.iter().map(|msg| msg.type_url.as_str()).collect::<Vec<_>>();
 					log::info!("Submitting timeout messages to {}: {type_urls:#?}", $source.name());
-					queue::flush_message_batch(timeouts, $metrics.as_ref(), &$source).await?;
+					retry::submit_or_carry(
+						timeouts,
+						None,
+						$metrics.as_ref(),
+						&$source,
+						&$sink,
+						&mut $source_pending,
+						&mut $source_tracker,
+					)
+					.await?;
 				}
 				// We want to send client update if packet messages exist but where not sent due to
 				// a connection delay even if client update message is optional
 				match (
 					update_type.is_optional(),
 					has_packet_events(&event_types),
-					messages.is_empty(),
+					messages.is_empty() && carried.is_none(),
 				) {
 					(true, false, true) => {
 						// skip sending ibc messages if no new events
@@ -80,7 +160,16 @@ macro_rules! process_finality_event {
 				let type_urls =
 					messages.iter().map(|msg| msg.type_url.as_str()).collect::<Vec<_>>();
 				log::info!("Submitting messages to {}: {type_urls:#?}", $sink.name());
-				queue::flush_message_batch(messages, $metrics.as_ref(), &$sink).await?;
+				retry::submit_or_carry(
+					messages,
+					carried,
+					$metrics.as_ref(),
+					&$sink,
+					&$source,
+					&mut $sink_pending,
+					&mut $sink_tracker,
+				)
+				.await?;
 			},
 		}
 	};
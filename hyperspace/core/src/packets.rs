@@ -23,8 +23,17 @@ use ibc::{
 	core::{
 		ics02_client::client_state::ClientState as ClientStateT,
 		ics03_connection::connection::ConnectionEnd,
-		ics04_channel::channel::{ChannelEnd, State},
+		ics04_channel::{
+			channel::{ChannelEnd, State},
+			msgs::chan_upgrade_timeout::MsgChannelUpgradeTimeout,
+			packet::Packet,
+			upgrade::Upgrade,
+		},
+		ics23_commitment::commitment::CommitmentProofBytes,
 	},
+	proofs::Proofs,
+	timestamp::Timestamp,
+	tx_msg::Msg,
 	Height,
 };
 use ibc_proto::google::protobuf::Any;
@@ -33,10 +42,22 @@ use primitives::{
 	error::Error, find_suitable_proof_height_for_client, packet_info_to_packet,
 	query_undelivered_acks, query_undelivered_sequences, Chain,
 };
+use tendermint_proto::Protobuf;
 
+pub mod clearing;
 pub mod connection_delay;
 pub mod utils;
 
+/// Returns true if the packet sent from `sender` passes its packet filter, and relaying over its
+/// channel hasn't been paused.
+pub fn should_relay(sender: &impl Chain, packet: &Packet) -> bool {
+	let filter = sender.packet_filter();
+	filter.allows(packet) &&
+		!filter
+			.paused
+			.contains(sender.name(), &packet.source_port, &packet.source_channel)
+}
+
 /// Returns a tuple of messages, with the first item being packets that are ready to be sent to the
 /// sink chain. And the second item being packet timeouts that should be sent to the source.
 pub async fn query_ready_and_timed_out_packets(
@@ -158,7 +179,8 @@ pub async fn query_ready_and_timed_out_packets(
 		let send_packets = source.query_send_packets(channel_id, port_id.clone(), seqs).await?;
 		for send_packet in send_packets {
 			let packet = packet_info_to_packet(&send_packet);
-			// Check if packet has timed out
+			// Check if packet has timed out, filtered packets are still timed out so that their
+			// funds are refunded on the source.
 			if packet.timed_out(&sink_timestamp, sink_height) {
 				// so we know this packet has timed out on the sink, we need to find the maximum
 				// consensus state height at which we can generate a non-membership proof of the
@@ -211,6 +233,11 @@ pub async fn query_ready_and_timed_out_packets(
 				continue
 			}
 
+			if !should_relay(source, &packet) {
+				log::trace!(target: "hyperspace", "Packet {:?} skipped by the packet filter", packet);
+				continue
+			}
+
 			// If packet has not timed out but channel is closed on sink we skip
 			// Since we have no reference point for when this channel was closed so we can't
 			// calculate connection delays yet
@@ -285,6 +312,10 @@ pub async fn query_ready_and_timed_out_packets(
 		let acknowledgements = source.query_recv_packets(channel_id, port_id, acks).await?;
 		for acknowledgement in acknowledgements {
 			let packet = packet_info_to_packet(&acknowledgement);
+			if !should_relay(sink, &packet) {
+				log::trace!(target: "hyperspace", "Packet {:?} skipped by the packet filter", packet);
+				continue
+			}
 			let ack = if let Some(ack) = acknowledgement.ack {
 				ack
 			} else {
@@ -340,3 +371,102 @@ pub async fn query_ready_and_timed_out_packets(
 
 	Ok((messages, timeout_messages))
 }
+
+/// Returns `MsgChannelUpgradeTimeout`s for the channels of the source that are flushing an upgrade
+/// the sink did not complete before its upgrade timeout. They should be sent to the source, which
+/// then aborts the upgrade and lets the sink cancel its side.
+pub async fn query_timed_out_channel_upgrades(
+	source: &impl Chain,
+	sink: &impl Chain,
+) -> Result<Vec<Any>, anyhow::Error> {
+	let mut messages = vec![];
+	let (source_height, _) = source.latest_height_and_timestamp().await?;
+	let (sink_height, _) = sink.latest_height_and_timestamp().await?;
+
+	for (channel_id, port_id) in source.channel_whitelist() {
+		let source_channel_response =
+			source.query_channel_end(source_height, channel_id, port_id.clone()).await?;
+		let source_channel_end =
+			ChannelEnd::try_from(source_channel_response.channel.ok_or_else(|| {
+				Error::Custom(format!("ChannelEnd not found for {:?}/{:?}", channel_id, port_id))
+			})?)?;
+		if !matches!(source_channel_end.state, State::Flushing | State::FlushComplete) {
+			continue
+		}
+		let sink_channel_id = source_channel_end.counterparty().channel_id.ok_or_else(|| {
+			Error::Custom("An upgrading channel should have a counterparty channel id".to_string())
+		})?;
+		let sink_port_id = source_channel_end.counterparty().port_id.clone();
+
+		// the timeout is part of the upgrade the sink proposed, it's also what the source stored
+		// as the counterparty upgrade.
+		let sink_upgrade = match sink
+			.query_channel_upgrade(sink_height, sink_channel_id, sink_port_id.clone())
+			.await
+			.ok()
+			.and_then(|response| response.upgrade)
+		{
+			Some(upgrade) => Upgrade::try_from(upgrade)?,
+			None => continue,
+		};
+
+		// the sink channel is proven at the latest height the source knows about, the timeout has
+		// to be reached there.
+		let sink_client_state_on_source =
+			source.query_client_state(source_height, sink.client_id()).await?;
+		let sink_client_state_on_source = AnyClientState::try_from(
+			sink_client_state_on_source.client_state.ok_or_else(|| {
+				Error::Custom(format!(
+					"Client state for {} should exist on {}",
+					sink.name(),
+					source.name()
+				))
+			})?,
+		)
+		.map_err(|_| {
+			Error::Custom(format!(
+				"Invalid Client state for {} should found on {}",
+				sink.name(),
+				source.name()
+			))
+		})?;
+		let proof_height = sink_client_state_on_source.latest_height();
+		let proof_timestamp = Timestamp::from_nanoseconds(
+			sink.query_timestamp_at(proof_height.revision_height).await?,
+		)?;
+		if !sink_upgrade.timeout.has_passed(proof_height, proof_timestamp) {
+			continue
+		}
+
+		let sink_channel_response = sink
+			.query_channel_end(proof_height, sink_channel_id, sink_port_id.clone())
+			.await?;
+		let sink_channel_end =
+			ChannelEnd::try_from(sink_channel_response.channel.ok_or_else(|| {
+				Error::Custom(format!(
+					"ChannelEnd not found for {:?}/{:?}",
+					sink_channel_id, sink_port_id
+				))
+			})?)?;
+		// a sink that is done flushing, or already opened the upgrade, can't be timed out.
+		if sink_channel_end.state == State::FlushComplete ||
+			(sink_channel_end.state == State::Open &&
+				sink_channel_end.upgrade_sequence() == source_channel_end.upgrade_sequence())
+		{
+			continue
+		}
+
+		let channel_proof = CommitmentProofBytes::try_from(sink_channel_response.proof)?;
+		let msg = MsgChannelUpgradeTimeout {
+			port_id: port_id.clone(),
+			channel_id,
+			counterparty_channel: sink_channel_end,
+			proofs: Proofs::new(channel_proof, None, None, None, proof_height)?,
+			signer: source.account_id(),
+		};
+		let value = msg.encode_vec();
+		messages.push(Any { value, type_url: msg.type_url() })
+	}
+
+	Ok(messages)
+}
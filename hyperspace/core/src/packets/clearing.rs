@@ -0,0 +1,378 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Clearing of packets that were left undelivered, e.g. because they were sent while the relayer
+//! was offline.
+
+use crate::{
+	finality::SharedFinality,
+	handshake::{latest_client_height, wait_for_finality},
+	packets::{query_ready_and_timed_out_packets, should_relay},
+	queue,
+	tracker::SubmittedTx,
+};
+use anyhow::anyhow;
+use futures::{Stream, StreamExt};
+use ibc::{core::ics04_channel::packet::Packet, timestamp::Timestamp, Height};
+use primitives::{
+	packet_info_to_packet, query_undelivered_acks, query_undelivered_sequences, Chain,
+};
+use std::time::Duration;
+
+/// Relays all the undelivered packets, acknowledgements and timeouts on the whitelisted channels
+/// of both chains, independently of any new ibc events.
+pub async fn clear_packets<A, B>(
+	chain_a: &mut A,
+	chain_b: &mut B,
+	finality_a: &SharedFinality<A::FinalityEvent>,
+	finality_b: &SharedFinality<B::FinalityEvent>,
+) -> Result<(), anyhow::Error>
+where
+	A: Chain,
+	B: Chain,
+	A::FinalityEvent: Clone + Send + 'static,
+	B::FinalityEvent: Clone + Send + 'static,
+{
+	let heights_a = required_client_heights(chain_a, chain_b).await?;
+	let heights_b = required_client_heights(chain_b, chain_a).await?;
+	for step in clearing_steps(heights_a, heights_b) {
+		match step {
+			ClearingStep::UpdateClientOfA(height) => {
+				let transactions = update_counterparty_client(
+					chain_a,
+					chain_b,
+					&mut finality_a.subscribe(),
+					Some(height),
+				)
+				.await?;
+				wait_for_finality(chain_b, transactions).await?;
+			},
+			ClearingStep::UpdateClientOfB(height) => {
+				let transactions = update_counterparty_client(
+					chain_b,
+					chain_a,
+					&mut finality_b.subscribe(),
+					Some(height),
+				)
+				.await?;
+				wait_for_finality(chain_a, transactions).await?;
+			},
+			ClearingStep::RelayFromA => relay_undelivered_packets(chain_a, chain_b).await?,
+			ClearingStep::RelayFromB => relay_undelivered_packets(chain_b, chain_a).await?,
+		}
+	}
+
+	Ok(())
+}
+
+/// A step of [`clear_packets`] between chains `A` and `B`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClearingStep {
+	/// Update the client of `A` on `B` to at least the given height
+	UpdateClientOfA(u64),
+	/// Update the client of `B` on `A` to at least the given height
+	UpdateClientOfB(u64),
+	/// Relay the undelivered packets and acknowledgements of `A` to `B`, and time out the
+	/// expired ones
+	RelayFromA,
+	/// Relay the undelivered packets and acknowledgements of `B` to `A`, and time out the
+	/// expired ones
+	RelayFromB,
+}
+
+/// Returns the steps clearing the packets of both chains, given the [`RequiredHeights`] of the
+/// packets sent from each of them. Undelivered packets can only be proven once the counterparty
+/// clients have been updated past the heights they were sent at, and timeouts once they've been
+/// updated past the timeout, so the client updates come first.
+fn clearing_steps(heights_a: RequiredHeights, heights_b: RequiredHeights) -> Vec<ClearingStep> {
+	let mut steps = vec![];
+	if let Some(height) = heights_a.packets.max(heights_b.timeouts) {
+		steps.push(ClearingStep::UpdateClientOfA(height));
+	}
+	if let Some(height) = heights_b.packets.max(heights_a.timeouts) {
+		steps.push(ClearingStep::UpdateClientOfB(height));
+	}
+	steps.extend([ClearingStep::RelayFromA, ClearingStep::RelayFromB]);
+	steps
+}
+
+/// Heights the clients of a `source` and `sink` chain must reach to clear the packets `source`
+/// left undelivered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct RequiredHeights {
+	/// Height the client of `source` on `sink` must reach to prove the packets and
+	/// acknowledgements
+	packets: Option<u64>,
+	/// Height the client of `sink` on `source` must reach to prove the timeouts
+	timeouts: Option<u64>,
+}
+
+impl RequiredHeights {
+	/// Accounts for a packet sent at `height` that `sink` hasn't received, only relayed if it
+	/// passes `should_relay`. Timed out packets require the latest height of `sink` instead.
+	fn add_packet(
+		&mut self,
+		packet: &Packet,
+		height: u64,
+		sink_height: Height,
+		sink_timestamp: &Timestamp,
+		should_relay: impl Fn(&Packet) -> bool,
+	) {
+		if packet.timed_out(sink_timestamp, sink_height) {
+			self.timeouts = Some(sink_height.revision_height);
+		} else if should_relay(packet) {
+			self.packets = self.packets.max(Some(height));
+		}
+	}
+
+	/// Accounts for an acknowledgement written at `height` that `sink` hasn't received, only
+	/// relayed if its packet passes `should_relay`.
+	fn add_acknowledgement(
+		&mut self,
+		packet: &Packet,
+		height: u64,
+		should_relay: impl Fn(&Packet) -> bool,
+	) {
+		if should_relay(packet) {
+			self.packets = self.packets.max(Some(height));
+		}
+	}
+}
+
+/// Periodically clears undelivered packets between both chains, errors are logged and the
+/// packets are retried on the next sweep.
+pub async fn clear_packets_periodically<A, B>(
+	mut chain_a: A,
+	mut chain_b: B,
+	finality_a: SharedFinality<A::FinalityEvent>,
+	finality_b: SharedFinality<B::FinalityEvent>,
+	interval: Duration,
+) where
+	A: Chain,
+	B: Chain,
+	A::FinalityEvent: Clone + Send + 'static,
+	B::FinalityEvent: Clone + Send + 'static,
+{
+	let mut interval = tokio::time::interval(interval);
+	// the first tick completes immediately, packets left over from a previous run are cleared
+	// right away.
+	loop {
+		interval.tick().await;
+		log::info!(
+			"Clearing undelivered packets between {} and {}",
+			chain_a.name(),
+			chain_b.name()
+		);
+		if let Err(err) = clear_packets(&mut chain_a, &mut chain_b, &finality_a, &finality_b).await
+		{
+			log::error!(
+				"Failed to clear packets between {} and {}: {err:?}",
+				chain_a.name(),
+				chain_b.name()
+			);
+		}
+	}
+}
+
+/// Updates the light client of `source` on `sink` with the next event of `finality`, unless the
+/// client is already at `height` or above. A `height` of `None` always updates the client.
+///
+/// Returns the submitted transactions, which are empty if the client didn't need an update.
+pub(crate) async fn update_counterparty_client<S, T>(
+	source: &mut S,
+	sink: &T,
+	finality: &mut (impl Stream<Item = S::FinalityEvent> + Unpin),
+	height: Option<u64>,
+) -> Result<Vec<SubmittedTx<T::TransactionId>>, anyhow::Error>
+where
+	S: Chain,
+	T: Chain,
+{
+	if let Some(height) = height {
+		let client_height = latest_client_height(sink).await?;
+		if client_height.revision_height >= height {
+			log::debug!(
+				"The client of {} on {} is at {client_height}, no update needed for {height}",
+				source.name(),
+				sink.name()
+			);
+			return Ok(vec![])
+		}
+	}
+	let finality_event = finality
+		.next()
+		.await
+		.ok_or_else(|| anyhow!("Finality notifications from {} ended", source.name()))?;
+	let (msg_update_client, _, _) = source.query_latest_ibc_events(finality_event, sink).await?;
+	log::info!("Updating the client of {} on {}", source.name(), sink.name());
+	queue::flush_message_batch(vec![msg_update_client], None, sink, &*source).await
+}
+
+/// Returns the [`RequiredHeights`] of the packets and acknowledgements `source` left undelivered.
+async fn required_client_heights(
+	source: &impl Chain,
+	sink: &impl Chain,
+) -> Result<RequiredHeights, anyhow::Error> {
+	let (source_height, _) = source.latest_height_and_timestamp().await?;
+	let (sink_height, sink_timestamp) = sink.latest_height_and_timestamp().await?;
+	let mut heights = RequiredHeights::default();
+	for (channel_id, port_id) in source.channel_whitelist() {
+		let seqs = query_undelivered_sequences(
+			source_height,
+			sink_height,
+			channel_id,
+			port_id.clone(),
+			source,
+			sink,
+		)
+		.await?;
+		for send_packet in source.query_send_packets(channel_id, port_id.clone(), seqs).await? {
+			heights.add_packet(
+				&packet_info_to_packet(&send_packet),
+				send_packet.height,
+				sink_height,
+				&sink_timestamp,
+				|packet| should_relay(source, packet),
+			);
+		}
+
+		let acks = query_undelivered_acks(
+			source_height,
+			sink_height,
+			channel_id,
+			port_id.clone(),
+			source,
+			sink,
+		)
+		.await?;
+		for acknowledgement in source.query_recv_packets(channel_id, port_id, acks).await? {
+			heights.add_acknowledgement(
+				&packet_info_to_packet(&acknowledgement),
+				acknowledgement.height,
+				|packet| should_relay(sink, packet),
+			);
+		}
+	}
+	Ok(heights)
+}
+
+/// Relays the packets and acknowledgements from `source` that `sink` hasn't received yet, and
+/// times out the ones that have expired.
+async fn relay_undelivered_packets(
+	source: &impl Chain,
+	sink: &impl Chain,
+) -> Result<(), anyhow::Error> {
+	let (messages, timeouts) = query_ready_and_timed_out_packets(source, sink).await?;
+	if !messages.is_empty() {
+		log::info!(
+			"Clearing {} packet messages from {} to {}",
+			messages.len(),
+			source.name(),
+			sink.name()
+		);
+		queue::flush_message_batch(messages, None, sink, source).await?;
+	}
+	if !timeouts.is_empty() {
+		log::info!("Clearing {} timed out packets on {}", timeouts.len(), source.name());
+		queue::flush_message_batch(timeouts, None, source, sink).await?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ibc::core::ics24_host::identifier::ChannelId;
+
+	fn packet(channel: u64, timeout_height: u64) -> Packet {
+		Packet {
+			source_channel: ChannelId::new(channel),
+			timeout_height: Height::new(1, timeout_height),
+			..Default::default()
+		}
+	}
+
+	fn heights(packets: Option<u64>, timeouts: Option<u64>) -> RequiredHeights {
+		RequiredHeights { packets, timeouts }
+	}
+
+	#[test]
+	fn required_heights_cover_packets_acknowledgements_and_timeouts() {
+		let sink_height = Height::new(1, 100);
+		let sink_timestamp = Timestamp::from_nanoseconds(1_000).unwrap();
+		let relay_all = |_: &Packet| true;
+		let mut required = RequiredHeights::default();
+
+		required.add_packet(&packet(0, 200), 10, sink_height, &sink_timestamp, relay_all);
+		required.add_packet(&packet(0, 200), 30, sink_height, &sink_timestamp, relay_all);
+		required.add_acknowledgement(&packet(0, 200), 20, relay_all);
+		assert_eq!(required, heights(Some(30), None));
+
+		// timed out packets need the client of the sink at its latest height, whatever the
+		// height they were sent at
+		required.add_packet(&packet(0, 50), 40, sink_height, &sink_timestamp, relay_all);
+		assert_eq!(required, heights(Some(30), Some(100)));
+
+		let expired = Packet {
+			timeout_timestamp: Timestamp::from_nanoseconds(500).unwrap(),
+			..packet(0, 200)
+		};
+		let mut required = RequiredHeights::default();
+		required.add_packet(&expired, 40, sink_height, &sink_timestamp, relay_all);
+		assert_eq!(required, heights(None, Some(100)));
+	}
+
+	#[test]
+	fn filtered_packets_require_no_client_update() {
+		let sink_height = Height::new(1, 100);
+		let sink_timestamp = Timestamp::from_nanoseconds(1_000).unwrap();
+		let only_channel_0 = |packet: &Packet| packet.source_channel == ChannelId::new(0);
+		let mut required = RequiredHeights::default();
+
+		required.add_packet(&packet(1, 200), 50, sink_height, &sink_timestamp, only_channel_0);
+		required.add_acknowledgement(&packet(1, 200), 60, only_channel_0);
+		assert_eq!(required, RequiredHeights::default());
+
+		required.add_packet(&packet(0, 200), 10, sink_height, &sink_timestamp, only_channel_0);
+		assert_eq!(required, heights(Some(10), None));
+	}
+
+	#[test]
+	fn clients_are_updated_before_packets_are_relayed() {
+		use ClearingStep::*;
+
+		// nothing to prove, nothing to update
+		assert_eq!(
+			clearing_steps(RequiredHeights::default(), RequiredHeights::default()),
+			vec![RelayFromA, RelayFromB]
+		);
+
+		// the packets of a are proven with the client of a on b, their timeouts with the client
+		// of b on a
+		assert_eq!(
+			clearing_steps(heights(Some(10), Some(20)), RequiredHeights::default()),
+			vec![UpdateClientOfA(10), UpdateClientOfB(20), RelayFromA, RelayFromB]
+		);
+		assert_eq!(
+			clearing_steps(heights(None, Some(20)), heights(Some(5), None)),
+			vec![UpdateClientOfB(20), RelayFromA, RelayFromB]
+		);
+
+		// the client of a on b must reach the highest of the packets of a and timeouts of b
+		assert_eq!(
+			clearing_steps(heights(Some(10), None), heights(Some(7), Some(30))),
+			vec![UpdateClientOfA(30), UpdateClientOfB(7), RelayFromA, RelayFromB]
+		);
+	}
+}
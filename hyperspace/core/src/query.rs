@@ -0,0 +1,453 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Queries of the IBC state of the configured chains, to inspect it from the cli.
+
+use crate::chain::{AnyChain, AnyConfig, Config};
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use ibc::{
+	core::{
+		ics02_client::{
+			client_consensus::ConsensusState as ConsensusStateT,
+			client_state::ClientState as ClientStateT,
+		},
+		ics03_connection::connection::{ConnectionEnd, IdentifiedConnectionEnd},
+		ics04_channel::channel::{ChannelEnd, IdentifiedChannelEnd},
+		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
+	},
+	Height,
+};
+use pallet_ibc::light_clients::{AnyClientState, AnyConsensusState};
+use primitives::{query_undelivered_acks, query_undelivered_sequences, Chain};
+use serde::Serialize;
+use std::{fmt::Debug, path::PathBuf};
+
+/// Possible subcommands of `query`.
+#[derive(Debug, Parser)]
+pub enum QueryCmd {
+	#[clap(name = "client-state", about = "Queries the state of a light client")]
+	ClientState {
+		#[clap(flatten)]
+		args: QueryArgs,
+		/// Client id, defaults to the configured client id
+		#[clap(long)]
+		client_id: Option<ClientId>,
+	},
+	#[clap(name = "consensus-state", about = "Queries a consensus state of a light client")]
+	ConsensusState {
+		#[clap(flatten)]
+		args: QueryArgs,
+		/// Client id, defaults to the configured client id
+		#[clap(long)]
+		client_id: Option<ClientId>,
+		/// Height of the consensus state as `revision-height`, defaults to the latest height of
+		/// the client
+		#[clap(long)]
+		consensus_height: Option<Height>,
+	},
+	#[clap(name = "connection", about = "Queries a connection end")]
+	Connection {
+		#[clap(flatten)]
+		args: QueryArgs,
+		/// Connection id, defaults to the configured connection id
+		#[clap(long)]
+		connection_id: Option<ConnectionId>,
+	},
+	#[clap(name = "channel", about = "Queries a channel end")]
+	Channel {
+		#[clap(flatten)]
+		args: QueryArgs,
+		#[clap(flatten)]
+		channel: ChannelArgs,
+	},
+	#[clap(
+		name = "packet-commitments",
+		about = "Queries the sequences of the packets sent on a channel that haven't been acknowledged or timed out"
+	)]
+	PacketCommitments {
+		#[clap(flatten)]
+		args: QueryArgs,
+		#[clap(flatten)]
+		channel: ChannelArgs,
+	},
+	#[clap(
+		name = "unreceived-packets",
+		about = "Queries the sequences of the packets sent on a channel that the counterparty hasn't received"
+	)]
+	UnreceivedPackets {
+		#[clap(flatten)]
+		args: QueryArgs,
+		#[clap(flatten)]
+		channel: ChannelArgs,
+	},
+	#[clap(
+		name = "unreceived-acks",
+		about = "Queries the sequences of the acknowledgements written on a channel that the counterparty hasn't received"
+	)]
+	UnreceivedAcks {
+		#[clap(flatten)]
+		args: QueryArgs,
+		#[clap(flatten)]
+		channel: ChannelArgs,
+	},
+	#[clap(
+		name = "denom-traces",
+		about = "Queries the traces of the ibc denoms known to the chain"
+	)]
+	DenomTraces {
+		#[clap(flatten)]
+		args: QueryArgs,
+	},
+}
+
+/// Arguments shared by all the queries.
+#[derive(Debug, Clone, Parser)]
+pub struct QueryArgs {
+	/// Relayer config path.
+	#[clap(long)]
+	pub config: String,
+	/// Name of the chain to query, defaults to chain a of the config
+	#[clap(long)]
+	chain: Option<String>,
+	/// Block height to query the state at, defaults to the latest height
+	#[clap(long)]
+	height: Option<u64>,
+	/// Print the result as json
+	#[clap(long)]
+	json: bool,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ChannelArgs {
+	/// Channel id
+	#[clap(long)]
+	channel_id: ChannelId,
+	/// Port id
+	#[clap(long)]
+	port_id: PortId,
+}
+
+#[derive(Debug, Serialize)]
+struct ClientStateInfo {
+	client_id: ClientId,
+	client_type: String,
+	chain_id: String,
+	latest_height: Height,
+	frozen_height: Option<Height>,
+}
+
+#[derive(Debug, Serialize)]
+struct ConsensusStateInfo {
+	client_id: ClientId,
+	height: Height,
+	/// Timestamp of the consensus state, in nanoseconds since the unix epoch.
+	timestamp: u64,
+	/// Hex encoded commitment root.
+	root: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SequencesInfo {
+	channel_id: ChannelId,
+	port_id: PortId,
+	sequences: Vec<u64>,
+}
+
+impl QueryCmd {
+	/// Run the query and print its result
+	pub async fn run(&self) -> Result<()> {
+		match self {
+			Self::ClientState { args, client_id } => {
+				let chain = args.chain().await?;
+				let height = args.query_height(&chain).await?;
+				let client_id = client_id.clone().unwrap_or_else(|| chain.client_id());
+				let client_state = query_client_state(&chain, height, client_id.clone()).await?;
+				args.print(&ClientStateInfo {
+					client_id,
+					client_type: client_state.client_type(),
+					chain_id: client_state.chain_id().to_string(),
+					latest_height: client_state.latest_height(),
+					frozen_height: client_state.frozen_height(),
+				})
+			},
+			Self::ConsensusState { args, client_id, consensus_height } => {
+				let chain = args.chain().await?;
+				let height = args.query_height(&chain).await?;
+				let client_id = client_id.clone().unwrap_or_else(|| chain.client_id());
+				let consensus_height = match consensus_height {
+					Some(consensus_height) => *consensus_height,
+					None =>
+						query_client_state(&chain, height, client_id.clone()).await?.latest_height(),
+				};
+				let response = chain
+					.query_client_consensus(height, client_id.clone(), consensus_height)
+					.await?;
+				let consensus_state = response
+					.consensus_state
+					.map(AnyConsensusState::try_from)
+					.ok_or_else(|| {
+					anyhow!("Consensus state of {client_id} at {consensus_height} not found")
+				})??;
+				args.print(&ConsensusStateInfo {
+					client_id,
+					height: consensus_height,
+					timestamp: consensus_state.timestamp().nanoseconds(),
+					root: hex::encode(consensus_state.root().as_bytes()),
+				})
+			},
+			Self::Connection { args, connection_id } => {
+				let chain = args.chain().await?;
+				let height = args.query_height(&chain).await?;
+				let connection_id = connection_id.clone().unwrap_or_else(|| chain.connection_id());
+				let response = chain.query_connection_end(height, connection_id.clone()).await?;
+				let connection_end = response
+					.connection
+					.ok_or_else(|| anyhow!("Connection {connection_id} not found"))?;
+				args.print(&IdentifiedConnectionEnd::new(
+					connection_id,
+					ConnectionEnd::try_from(connection_end)?,
+				))
+			},
+			Self::Channel { args, channel } => {
+				let chain = args.chain().await?;
+				let height = args.query_height(&chain).await?;
+				let response = chain
+					.query_channel_end(height, channel.channel_id, channel.port_id.clone())
+					.await?;
+				let channel_end = response.channel.ok_or_else(|| {
+					anyhow!("Channel {}/{} not found", channel.channel_id, channel.port_id)
+				})?;
+				args.print(&IdentifiedChannelEnd::new(
+					channel.port_id.clone(),
+					channel.channel_id,
+					ChannelEnd::try_from(channel_end)?,
+				))
+			},
+			Self::PacketCommitments { args, channel } => {
+				let chain = args.chain().await?;
+				let height = args.query_height(&chain).await?;
+				let sequences = chain
+					.query_packet_commitments(height, channel.channel_id, channel.port_id.clone())
+					.await?;
+				args.print(&channel.sequences(sequences))
+			},
+			Self::UnreceivedPackets { args, channel } => {
+				let (chain, counterparty) = args.chain_and_counterparty().await?;
+				let height = args.query_height(&chain).await?;
+				let (counterparty_height, _) = counterparty.latest_height_and_timestamp().await?;
+				let sequences = query_undelivered_sequences(
+					height,
+					counterparty_height,
+					channel.channel_id,
+					channel.port_id.clone(),
+					&chain,
+					&counterparty,
+				)
+				.await?;
+				args.print(&channel.sequences(sequences))
+			},
+			Self::UnreceivedAcks { args, channel } => {
+				let (chain, counterparty) = args.chain_and_counterparty().await?;
+				let height = args.query_height(&chain).await?;
+				let (counterparty_height, _) = counterparty.latest_height_and_timestamp().await?;
+				let sequences = query_undelivered_acks(
+					height,
+					counterparty_height,
+					channel.channel_id,
+					channel.port_id.clone(),
+					&chain,
+					&counterparty,
+				)
+				.await?;
+				args.print(&channel.sequences(sequences))
+			},
+			Self::DenomTraces { args } => {
+				let chain = args.chain().await?;
+				args.print(&chain.query_denom_traces().await?)
+			},
+		}
+	}
+}
+
+impl QueryArgs {
+	/// Returns the configs of the queried chain and of its counterparty.
+	async fn configs(&self) -> Result<(AnyConfig, AnyConfig)> {
+		let path: PathBuf = self.config.parse()?;
+		let file_content = tokio::fs::read_to_string(path).await?;
+		let config: Config = toml::from_str(&file_content)?;
+		match &self.chain {
+			None => Ok((config.chain_a, config.chain_b)),
+			Some(name) if config.chain_a.name() == name => Ok((config.chain_a, config.chain_b)),
+			Some(name) if config.chain_b.name() == name => Ok((config.chain_b, config.chain_a)),
+			Some(name) => Err(anyhow!("Chain {name} is not part of the config")),
+		}
+	}
+
+	async fn chain(&self) -> Result<AnyChain> {
+		let (config, _) = self.configs().await?;
+		config.into_client().await
+	}
+
+	async fn chain_and_counterparty(&self) -> Result<(AnyChain, AnyChain)> {
+		let (config, counterparty_config) = self.configs().await?;
+		Ok((config.into_client().await?, counterparty_config.into_client().await?))
+	}
+
+	/// Returns the height to query `chain` at, in the revision of its latest height.
+	async fn query_height(&self, chain: &AnyChain) -> Result<Height> {
+		let (latest_height, _) = chain.latest_height_and_timestamp().await?;
+		Ok(match self.height {
+			Some(height) => Height::new(latest_height.revision_number, height),
+			None => latest_height,
+		})
+	}
+
+	fn print<T: Serialize + Debug>(&self, value: &T) -> Result<()> {
+		println!("{}", self.format(value)?);
+		Ok(())
+	}
+
+	/// Formats a query result as pretty printed json if requested, in a human readable form
+	/// otherwise.
+	fn format<T: Serialize + Debug>(&self, value: &T) -> Result<String> {
+		Ok(if self.json { serde_json::to_string_pretty(value)? } else { format!("{value:#?}") })
+	}
+}
+
+impl ChannelArgs {
+	fn sequences(&self, sequences: Vec<u64>) -> SequencesInfo {
+		SequencesInfo { channel_id: self.channel_id, port_id: self.port_id.clone(), sequences }
+	}
+}
+
+async fn query_client_state(
+	chain: &AnyChain,
+	height: Height,
+	client_id: ClientId,
+) -> Result<AnyClientState> {
+	let response = chain.query_client_state(height, client_id.clone()).await?;
+	Ok(response
+		.client_state
+		.map(AnyClientState::try_from)
+		.ok_or_else(|| anyhow!("Client {client_id} not found"))??)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	fn args(json: bool) -> QueryArgs {
+		QueryArgs { config: "config.toml".to_owned(), chain: None, height: None, json }
+	}
+
+	fn client_state() -> ClientStateInfo {
+		ClientStateInfo {
+			client_id: ClientId::new("07-tendermint", 0).unwrap(),
+			client_type: "07-tendermint".to_owned(),
+			chain_id: "cosmos-1".to_owned(),
+			latest_height: Height::new(1, 100),
+			frozen_height: None,
+		}
+	}
+
+	fn sequences() -> SequencesInfo {
+		ChannelArgs { channel_id: ChannelId::new(0), port_id: PortId::transfer() }
+			.sequences(vec![1, 2])
+	}
+
+	#[test]
+	fn results_are_printed_as_json() {
+		let output = args(true).format(&client_state()).unwrap();
+		assert_eq!(
+			serde_json::from_str::<serde_json::Value>(&output).unwrap(),
+			json!({
+				"client_id": "07-tendermint-0",
+				"client_type": "07-tendermint",
+				"chain_id": "cosmos-1",
+				"latest_height": { "revision_number": 1, "revision_height": 100 },
+				"frozen_height": null,
+			})
+		);
+
+		let consensus_state = ConsensusStateInfo {
+			client_id: ClientId::new("07-tendermint", 0).unwrap(),
+			height: Height::new(1, 90),
+			timestamp: 1_000,
+			root: "abcd".to_owned(),
+		};
+		let output = args(true).format(&consensus_state).unwrap();
+		assert_eq!(
+			serde_json::from_str::<serde_json::Value>(&output).unwrap(),
+			json!({
+				"client_id": "07-tendermint-0",
+				"height": { "revision_number": 1, "revision_height": 90 },
+				"timestamp": 1_000,
+				"root": "abcd",
+			})
+		);
+
+		// identifiers are printed as strings
+		let output = args(true).format(&sequences()).unwrap();
+		assert_eq!(
+			output,
+			r#"{
+  "channel_id": "channel-0",
+  "port_id": "transfer",
+  "sequences": [
+    1,
+    2
+  ]
+}"#
+		);
+	}
+
+	#[test]
+	fn results_are_printed_in_a_human_readable_form() {
+		let output = args(false).format(&sequences()).unwrap();
+		assert_eq!(
+			output,
+			r#"SequencesInfo {
+    channel_id: ChannelId(
+        "channel-0",
+    ),
+    port_id: PortId(
+        "transfer",
+    ),
+    sequences: [
+        1,
+        2,
+    ],
+}"#
+		);
+
+		let output = args(false).format(&client_state()).unwrap();
+		assert_eq!(
+			output,
+			r#"ClientStateInfo {
+    client_id: ClientId(
+        "07-tendermint-0",
+    ),
+    client_type: "07-tendermint",
+    chain_id: "cosmos-1",
+    latest_height: Height {
+        revision: 1,
+        height: 100,
+    },
+    frozen_height: None,
+}"#
+		);
+	}
+}
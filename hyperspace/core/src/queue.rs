@@ -12,16 +12,179 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::{balance, retry, tracker::SubmittedTx, Mode};
+use ibc::core::ics02_client::msgs::update_client;
 use ibc_proto::google::protobuf::Any;
 use metrics::handler::MetricsHandler;
-use primitives::Chain;
+use primitives::{error::ErrorKind, utils, Chain};
+use std::future::Future;
+
+/// Maximum encoded length of the messages packed into a single transaction.
+const MAX_BATCH_SIZE: usize = 512 * 1024;
 
 /// This sends messages to the sink chain in a gas-aware manner.
-pub async fn flush_message_batch(
+///
+/// Messages that don't fit in a single transaction are packed greedily into chunks bounded by
+/// the block max weight and [`MAX_BATCH_SIZE`], with the client update leading every chunk
+/// until it has been submitted. A chunk rejected by the sink is bisected to isolate the
+/// messages that are rejected on their own, which are dropped. Any other error stops the
+/// submission, the messages that weren't submitted are returned in [`Submission::unsent`].
+pub async fn submit_message_batch<C: Chain>(
 	msgs: Vec<Any>,
 	metrics: Option<&MetricsHandler>,
+	sink: &C,
+	source: &impl Chain,
+) -> Submission<C::TransactionId> {
+	if msgs.is_empty() {
+		return Submission { transactions: vec![], unsent: vec![], error: None }
+	}
+	let (update, chunks) = match pack_batch(msgs.clone(), metrics, sink).await {
+		Ok(packed) => packed,
+		Err(error) => return Submission { transactions: vec![], unsent: msgs, error: Some(error) },
+	};
+	let mut report = SubmissionReport {
+		update,
+		transactions: vec![],
+		rejected: vec![],
+		unsent: None,
+		error: None,
+	};
+	let submit = move |batch: Vec<Any>| async move {
+		let result = sink.submit(batch).await;
+		if let Some(metrics) = metrics {
+			metrics.handle_bundle_result(result.is_ok()).await;
+		}
+		result.map_err(anyhow::Error::from)
+	};
+	let classify = |error: &anyhow::Error| retry::classify_error(error, source, sink);
+	for chunk in chunks {
+		match report.unsent.as_mut() {
+			// the submission was stopped, the remaining chunks aren't attempted
+			Some(unsent) => unsent.extend(chunk),
+			None => submit_bisecting(chunk, &mut report, &submit, &classify).await,
+		}
+	}
+
+	if !report.rejected.is_empty() {
+		log::error!(
+			"Dropping {} messages rejected by {}: {:?}",
+			report.rejected.len(),
+			sink.name(),
+			report.error
+		);
+		if let Some(metrics) = metrics {
+			metrics.handle_failed_messages(&report.rejected).await;
+		}
+	}
+	report.into_submission()
+}
+
+/// Submits `msgs` to the sink chain like [`submit_message_batch`], failing if any message
+/// couldn't be submitted for reasons other than being rejected, or if nothing was submitted.
+pub async fn flush_message_batch<C: Chain>(
+	msgs: Vec<Any>,
+	metrics: Option<&MetricsHandler>,
+	sink: &C,
+	source: &impl Chain,
+) -> Result<Vec<SubmittedTx<C::TransactionId>>, anyhow::Error> {
+	let Submission { transactions, unsent, error } =
+		submit_message_batch(msgs, metrics, sink, source).await;
+	match error {
+		Some(error) if !unsent.is_empty() || transactions.is_empty() => Err(error),
+		_ => Ok(transactions),
+	}
+}
+
+/// Logs the messages that would be sent to the sink chain along with the result of simulating
+/// them, without submitting anything.
+pub async fn simulate_message_batch(
+	msgs: Vec<Any>,
 	sink: &impl Chain,
 ) -> Result<(), anyhow::Error> {
+	let result = utils::dry_run(sink, msgs).await?;
+	if result.weight > sink.block_max_weight() {
+		log::warn!(
+			target: "hyperspace",
+			"[dry-run] Messages for {} exceed the block max weight and would be split into chunks",
+			sink.name()
+		);
+	}
+	Ok(())
+}
+
+/// In dry-run mode, simulates the messages built for a finality event rather than submitting
+/// them: the timeouts with `simulate_source`, and the client update leading the other messages
+/// with `simulate_sink`. In any other mode the messages are handed back to be submitted, they're
+/// never handed back in dry-run mode.
+pub async fn simulate_in_dry_run<S, T, FutS, FutT>(
+	mode: Option<Mode>,
+	msg_update_client: Any,
+	mut messages: Vec<Any>,
+	timeouts: Vec<Any>,
+	simulate_source: S,
+	simulate_sink: T,
+) -> Option<(Any, Vec<Any>, Vec<Any>)>
+where
+	S: FnOnce(Vec<Any>) -> FutS,
+	FutS: Future<Output = ()>,
+	T: FnOnce(Vec<Any>) -> FutT,
+	FutT: Future<Output = ()>,
+{
+	if !matches!(mode, Some(Mode::DryRun)) {
+		return Some((msg_update_client, messages, timeouts))
+	}
+	if !timeouts.is_empty() {
+		simulate_source(timeouts).await;
+	}
+	messages.insert(0, msg_update_client);
+	simulate_sink(messages).await;
+	None
+}
+
+/// Outcome of submitting a batch of messages.
+pub struct Submission<Id> {
+	/// Transactions of the chunks that were submitted.
+	pub transactions: Vec<SubmittedTx<Id>>,
+	/// Messages that weren't submitted because of [`Submission::error`], led by the client
+	/// update if it wasn't submitted either. These should be retried.
+	pub unsent: Vec<Any>,
+	/// The error that stopped the submission, or the last rejection if the submission went
+	/// through.
+	pub error: Option<anyhow::Error>,
+}
+
+/// Outcome of submitting the chunks of a batch.
+struct SubmissionReport<Id> {
+	/// The client update, until a chunk including it has been submitted.
+	update: Option<Any>,
+	/// Transactions of the chunks that were submitted successfully.
+	transactions: Vec<SubmittedTx<Id>>,
+	/// Messages that were rejected on their own.
+	rejected: Vec<Any>,
+	/// Messages left once the submission was stopped by an error other than a rejection.
+	unsent: Option<Vec<Any>>,
+	/// The last submission error.
+	error: Option<anyhow::Error>,
+}
+
+impl<Id> SubmissionReport<Id> {
+	fn into_submission(self) -> Submission<Id> {
+		let unsent = match self.unsent {
+			Some(unsent) => self.update.into_iter().chain(unsent).collect(),
+			None => vec![],
+		};
+		Submission { transactions: self.transactions, unsent, error: self.error }
+	}
+}
+
+/// Estimates the weight of the batch and splits it into chunks that fit in a transaction,
+/// separating the client update that leads them.
+async fn pack_batch<C: Chain>(
+	msgs: Vec<Any>,
+	metrics: Option<&MetricsHandler>,
+	sink: &C,
+) -> Result<(Option<Any>, Vec<Vec<Any>>), anyhow::Error> {
+	balance::ensure_sufficient_balance(sink)?;
 	let block_max_weight = sink.block_max_weight();
 	let batch_weight = sink.estimate_weight(msgs.clone()).await?;
 
@@ -29,30 +192,363 @@ pub async fn flush_message_batch(
 		metrics.handle_transaction_costs(batch_weight, &msgs).await;
 	}
 
-	let ratio = (batch_weight / block_max_weight) as usize;
-	if ratio == 0 {
-		sink.submit(msgs).await?;
-		return Ok(())
+	let (update, msgs) = split_client_update(msgs);
+	if batch_weight <= block_max_weight && batch_size(update.iter().chain(&msgs)) <= MAX_BATCH_SIZE
+	{
+		return Ok((update, vec![msgs]))
 	}
+	let chunks = pack_messages(update.as_ref(), msgs, batch_weight, sink).await;
+	log::info!(
+		"Outgoing messages weight: {} exceeds the block max weight: {}. Packed them into {} chunks",
+		batch_weight,
+		block_max_weight,
+		chunks.len(),
+	);
+	Ok((update, chunks))
+}
 
-	// whelp our batch exceeds the block max weight.
-	let chunk = if ratio == 1 {
-		// split the batch into ratio * 2
-		ratio * 2
+/// Separates the client update from the rest of the batch, it's always inserted first.
+fn split_client_update(mut msgs: Vec<Any>) -> (Option<Any>, Vec<Any>) {
+	if msgs[0].type_url == update_client::TYPE_URL {
+		let update = msgs.remove(0);
+		(Some(update), msgs)
 	} else {
-		// split the batch into ratio + 2
-		ratio + 2
+		(None, msgs)
+	}
+}
+
+fn batch_size<'a>(msgs: impl IntoIterator<Item = &'a Any>) -> usize {
+	msgs.into_iter().map(|msg| msg.type_url.len() + msg.value.len()).sum()
+}
+
+/// Packs messages greedily into chunks whose estimated weight and encoded length fit in a
+/// single transaction, leaving room for the client update in each of them.
+async fn pack_messages(
+	update: Option<&Any>,
+	msgs: Vec<Any>,
+	batch_weight: u64,
+	sink: &impl Chain,
+) -> Vec<Vec<Any>> {
+	let block_max_weight = sink.block_max_weight();
+	let average_weight = batch_weight / (msgs.len() as u64 + update.is_some() as u64);
+	// messages are estimated alongside the client update, since their proofs can only be
+	// verified against the updated client.
+	let update_weight = match update {
+		Some(update) => sink.estimate_weight(vec![update.clone()]).await.unwrap_or(average_weight),
+		None => 0,
 	};
+	let update_size = batch_size(update);
 
-	log::info!(
-		"Outgoing messages weight: {} exceeds the block max weight: {}. Chunking {} messages into {} chunks",
-        batch_weight, block_max_weight, msgs.len(), chunk,
-	);
-	// TODO: return number of failed messages and record it to metrics
-	for batch in msgs.chunks(chunk) {
-		// send out batches.
-		sink.submit(batch.to_vec()).await?;
+	let mut chunks = vec![];
+	let mut chunk = vec![];
+	let (mut chunk_weight, mut chunk_size) = (update_weight, update_size);
+	for msg in msgs {
+		let estimate =
+			sink.estimate_weight(update.into_iter().cloned().chain([msg.clone()]).collect());
+		let weight = match estimate.await {
+			Ok(weight) => weight.saturating_sub(update_weight),
+			Err(err) => {
+				log::debug!(
+					"Failed to estimate the weight of {} on {}: {err:?}, assuming the average weight",
+					msg.type_url,
+					sink.name()
+				);
+				average_weight
+			},
+		};
+		let size = batch_size([&msg]);
+		if !chunk.is_empty() &&
+			(chunk_weight + weight > block_max_weight || chunk_size + size > MAX_BATCH_SIZE)
+		{
+			chunks.push(std::mem::take(&mut chunk));
+			chunk_weight = update_weight;
+			chunk_size = update_size;
+		}
+		chunk_weight += weight;
+		chunk_size += size;
+		chunk.push(msg);
+	}
+	// a lone client update still gets its own chunk
+	if !chunk.is_empty() || chunks.is_empty() {
+		chunks.push(chunk);
 	}
+	chunks
+}
 
-	Ok(())
+/// Submits a chunk of messages, bisecting it when it's rejected until the messages that are
+/// rejected on their own are isolated. A rejected chunk led by the client update first has the
+/// update submitted alone, if the update itself is rejected none of the messages can be
+/// submitted. That and any other error stops the submission, leaving the messages that weren't
+/// submitted in [`SubmissionReport::unsent`].
+async fn submit_bisecting<Id, F, Fut>(
+	chunk: Vec<Any>,
+	report: &mut SubmissionReport<Id>,
+	submit: &F,
+	classify: &impl Fn(&anyhow::Error) -> ErrorKind,
+) where
+	F: Fn(Vec<Any>) -> Fut,
+	Fut: Future<Output = Result<Id, anyhow::Error>>,
+{
+	let mut pending = vec![chunk];
+	while let Some(mut msgs) = pending.pop() {
+		let batch = report.update.iter().cloned().chain(msgs.iter().cloned()).collect::<Vec<_>>();
+		let error = match submit(batch.clone()).await {
+			Ok(tx_id) => {
+				report.transactions.push(SubmittedTx { tx_id, messages: batch });
+				// the client has been updated, the remaining chunks don't need to include it.
+				report.update = None;
+				continue
+			},
+			Err(error) => error,
+		};
+		match classify(&error) {
+			ErrorKind::Rejected if report.update.is_some() && !msgs.is_empty() => {
+				log::warn!(
+					"Messages were rejected with the client update: {error:?}, checking the update"
+				);
+				let update = report.update.iter().cloned().collect::<Vec<_>>();
+				match submit(update.clone()).await {
+					Ok(tx_id) => {
+						report.transactions.push(SubmittedTx { tx_id, messages: update });
+						report.update = None;
+						pending.push(msgs);
+					},
+					Err(error) => {
+						// the messages can't be verified without the client update, they're left
+						// to be retried with a fresh one
+						let unsent =
+							msgs.into_iter().chain(pending.into_iter().rev().flatten()).collect();
+						report.unsent = Some(unsent);
+						report.error = Some(error);
+						return
+					},
+				}
+			},
+			ErrorKind::Rejected if msgs.len() > 1 => {
+				log::warn!("{} messages were rejected: {error:?}, bisecting them", msgs.len());
+				let second_half = msgs.split_off(msgs.len() / 2);
+				pending.push(second_half);
+				pending.push(msgs);
+			},
+			ErrorKind::Rejected => {
+				if msgs.is_empty() {
+					// only the client update was submitted
+					report.rejected.extend(report.update.clone());
+				}
+				report.rejected.extend(msgs);
+				report.error = Some(error);
+			},
+			_ => {
+				// the messages themselves weren't at fault, they're left to be retried
+				let unsent = msgs.into_iter().chain(pending.into_iter().rev().flatten()).collect();
+				report.unsent = Some(unsent);
+				report.error = Some(error);
+				return
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::RefCell;
+
+	fn msg(id: u8) -> Any {
+		Any { type_url: "/test.Msg".to_owned(), value: vec![id] }
+	}
+
+	fn report(update: Option<Any>) -> SubmissionReport<usize> {
+		SubmissionReport {
+			update,
+			transactions: vec![],
+			rejected: vec![],
+			unsent: None,
+			error: None,
+		}
+	}
+
+	fn classify(error: &anyhow::Error) -> ErrorKind {
+		if error.to_string() == "rejected" {
+			ErrorKind::Rejected
+		} else {
+			ErrorKind::Transient
+		}
+	}
+
+	/// Submits batches through `outcome`, recording every attempted batch.
+	async fn submit_chunk(
+		chunk: Vec<Any>,
+		report: &mut SubmissionReport<usize>,
+		outcome: impl Fn(&[Any]) -> Result<(), &'static str>,
+	) -> Vec<Vec<Any>> {
+		let attempts = RefCell::new(vec![]);
+		let submit = |batch: Vec<Any>| {
+			let result =
+				outcome(&batch).map(|_| attempts.borrow().len()).map_err(anyhow::Error::msg);
+			attempts.borrow_mut().push(batch);
+			async move { result }
+		};
+		submit_bisecting(chunk, report, &submit, &classify).await;
+		attempts.into_inner()
+	}
+
+	#[tokio::test]
+	async fn rejected_messages_are_isolated() {
+		let mut report = report(None);
+		let outcome =
+			|batch: &[Any]| if batch.contains(&msg(3)) { Err("rejected") } else { Ok(()) };
+		let attempts = submit_chunk((1..=4).map(msg).collect(), &mut report, outcome).await;
+
+		assert_eq!(attempts.len(), 5);
+		let submitted =
+			report.transactions.iter().map(|tx| tx.messages.clone()).collect::<Vec<_>>();
+		assert_eq!(submitted, vec![vec![msg(1), msg(2)], vec![msg(4)]]);
+		assert_eq!(report.rejected, vec![msg(3)]);
+		assert!(report.unsent.is_none());
+	}
+
+	#[tokio::test]
+	async fn transient_errors_are_not_bisected() {
+		let mut report = report(None);
+		let attempts =
+			submit_chunk((1..=4).map(msg).collect(), &mut report, |_| Err("connection reset"))
+				.await;
+
+		assert_eq!(attempts.len(), 1);
+		assert!(report.transactions.is_empty());
+		assert!(report.rejected.is_empty());
+		assert_eq!(report.unsent, Some((1..=4).map(msg).collect()));
+		assert!(report.error.is_some());
+	}
+
+	#[tokio::test]
+	async fn transient_errors_stop_bisection() {
+		let mut report = report(None);
+		let outcome = |batch: &[Any]| {
+			if batch.contains(&msg(1)) {
+				Err("rejected")
+			} else if batch.contains(&msg(4)) {
+				Err("connection reset")
+			} else {
+				Ok(())
+			}
+		};
+		submit_chunk((1..=6).map(msg).collect(), &mut report, outcome).await;
+
+		let submitted =
+			report.transactions.iter().map(|tx| tx.messages.clone()).collect::<Vec<_>>();
+		assert_eq!(submitted, vec![vec![msg(2), msg(3)]]);
+		assert_eq!(report.rejected, vec![msg(1)]);
+		// the messages left after the transient error are kept in order
+		assert_eq!(report.unsent, Some(vec![msg(4), msg(5), msg(6)]));
+	}
+
+	#[tokio::test]
+	async fn unsent_messages_keep_the_client_update() {
+		let update = Any { type_url: update_client::TYPE_URL.to_owned(), value: vec![] };
+		let mut report = report(Some(update.clone()));
+		let attempts =
+			submit_chunk(vec![msg(1), msg(2)], &mut report, |_| Err("connection reset")).await;
+
+		assert_eq!(attempts, vec![vec![update.clone(), msg(1), msg(2)]]);
+		let submission = report.into_submission();
+		assert_eq!(submission.unsent, vec![update, msg(1), msg(2)]);
+	}
+
+	#[tokio::test]
+	async fn client_update_is_only_submitted_once() {
+		let update = Any { type_url: update_client::TYPE_URL.to_owned(), value: vec![] };
+		let mut report = report(Some(update.clone()));
+		let outcome =
+			|batch: &[Any]| if batch.contains(&msg(2)) { Err("rejected") } else { Ok(()) };
+		let attempts = submit_chunk(vec![msg(1), msg(2)], &mut report, outcome).await;
+
+		assert_eq!(
+			attempts,
+			vec![
+				vec![update.clone(), msg(1), msg(2)],
+				vec![update],
+				vec![msg(1), msg(2)],
+				vec![msg(1)],
+				vec![msg(2)]
+			]
+		);
+		assert_eq!(report.rejected, vec![msg(2)]);
+		assert!(report.into_submission().unsent.is_empty());
+	}
+
+	#[tokio::test]
+	async fn rejected_client_update_leaves_the_messages_unsent() {
+		let update = Any { type_url: update_client::TYPE_URL.to_owned(), value: vec![] };
+		let mut report = report(Some(update.clone()));
+		let outcome =
+			|batch: &[Any]| if batch.contains(&update) { Err("rejected") } else { Ok(()) };
+		let attempts = submit_chunk((1..=4).map(msg).collect(), &mut report, outcome).await;
+
+		// the messages aren't bisected once the client update alone is rejected
+		assert_eq!(
+			attempts,
+			vec![vec![update.clone(), msg(1), msg(2), msg(3), msg(4)], vec![update.clone()]]
+		);
+		assert!(report.transactions.is_empty());
+		assert!(report.rejected.is_empty());
+		let submission = report.into_submission();
+		assert_eq!(submission.unsent, vec![update, msg(1), msg(2), msg(3), msg(4)]);
+		assert!(submission.error.is_some());
+	}
+
+	#[tokio::test]
+	async fn dry_runs_submit_nothing() {
+		let simulated = RefCell::new(vec![]);
+		let simulate = |chain: &'static str| {
+			let simulated = &simulated;
+			move |msgs: Vec<Any>| {
+				simulated.borrow_mut().push((chain, msgs));
+				async {}
+			}
+		};
+
+		let unsent = simulate_in_dry_run(
+			Some(Mode::DryRun),
+			msg(0),
+			vec![msg(1), msg(2)],
+			vec![msg(3)],
+			simulate("source"),
+			simulate("sink"),
+		)
+		.await;
+		assert!(unsent.is_none());
+		assert_eq!(
+			simulated.take(),
+			vec![("source", vec![msg(3)]), ("sink", vec![msg(0), msg(1), msg(2)])]
+		);
+
+		// the client update is simulated even without any other message
+		let unsent = simulate_in_dry_run(
+			Some(Mode::DryRun),
+			msg(0),
+			vec![],
+			vec![],
+			simulate("source"),
+			simulate("sink"),
+		)
+		.await;
+		assert!(unsent.is_none());
+		assert_eq!(simulated.take(), vec![("sink", vec![msg(0)])]);
+
+		for mode in [None, Some(Mode::Light)] {
+			let unsent = simulate_in_dry_run(
+				mode,
+				msg(0),
+				vec![msg(1)],
+				vec![msg(2)],
+				simulate("source"),
+				simulate("sink"),
+			)
+			.await;
+			assert_eq!(unsent, Some((msg(0), vec![msg(1)], vec![msg(2)])));
+			assert!(simulated.borrow().is_empty());
+		}
+	}
 }
@@ -0,0 +1,205 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Refreshing of idle light clients, so that they don't expire while there's nothing to relay.
+
+use crate::{
+	chain::CoreConfig, handshake::wait_for_finality, packets::clearing::update_counterparty_client,
+};
+use anyhow::anyhow;
+use ibc::core::ics02_client::{
+	client_consensus::ConsensusState as ConsensusStateT, client_state::ClientState as ClientStateT,
+};
+use metrics::data::ClientMetrics;
+use pallet_ibc::light_clients::{AnyClientState, AnyConsensusState};
+use primitives::Chain;
+use std::time::Duration;
+
+/// Interval at which the counterparty clients are checked.
+const CLIENT_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+/// Fraction of the trusting period after which a client is refreshed when none is configured.
+pub const DEFAULT_REFRESH_THRESHOLD: f64 = 2.0 / 3.0;
+
+/// When the counterparty clients should be refreshed.
+#[derive(Debug, Clone, Copy)]
+pub struct RefreshPolicy {
+	/// Fraction of the trusting period after which a client is refreshed.
+	pub threshold: f64,
+	/// Trusting period of the clients whose state doesn't define one.
+	pub default_trusting_period: Option<Duration>,
+}
+
+impl RefreshPolicy {
+	/// Reads the policy from `config`, failing if the threshold isn't a fraction strictly between
+	/// 0 and 1.
+	pub fn new(config: &CoreConfig) -> Result<Self, anyhow::Error> {
+		let threshold = config.client_refresh_threshold.unwrap_or(DEFAULT_REFRESH_THRESHOLD);
+		if !(threshold > 0.0 && threshold < 1.0) {
+			Err(anyhow!("client_refresh_threshold must be between 0 and 1, got {threshold}"))?
+		}
+		Ok(Self {
+			threshold,
+			default_trusting_period: config.default_trusting_period.map(Duration::from_secs),
+		})
+	}
+
+	/// Returns how close to expiry a client last updated `elapsed` ago is.
+	fn client_age(&self, elapsed: Duration, trusting_period: Duration) -> ClientAge {
+		if elapsed >= trusting_period {
+			ClientAge::Expired
+		} else if elapsed >= trusting_period.mul_f64(self.threshold) {
+			ClientAge::Stale
+		} else {
+			ClientAge::Fresh
+		}
+	}
+}
+
+/// How close to expiry a client is, see [`RefreshPolicy::client_age`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClientAge {
+	/// The client doesn't need to be refreshed yet.
+	Fresh,
+	/// The client must be refreshed.
+	Stale,
+	/// The client can't be refreshed anymore.
+	Expired,
+}
+
+/// Periodically checks when the client of `source` on `sink` was last updated, and updates it
+/// once the fraction of its trusting period given by `policy` has elapsed. The time left before
+/// the client expires is exported to `metrics`.
+pub async fn refresh_client_periodically<S, T>(
+	mut source: S,
+	sink: T,
+	policy: RefreshPolicy,
+	metrics: Option<ClientMetrics>,
+) where
+	S: Chain,
+	T: Chain,
+{
+	let mut interval = tokio::time::interval(CLIENT_CHECK_INTERVAL);
+	loop {
+		interval.tick().await;
+		if let Err(err) = refresh_client(&mut source, &sink, policy, metrics.as_ref()).await {
+			log::error!(
+				"Failed to refresh the client of {} on {}: {err:?}",
+				source.name(),
+				sink.name()
+			);
+		}
+	}
+}
+
+async fn refresh_client(
+	source: &mut impl Chain,
+	sink: &impl Chain,
+	policy: RefreshPolicy,
+	metrics: Option<&ClientMetrics>,
+) -> Result<(), anyhow::Error> {
+	let (height, timestamp) = sink.latest_height_and_timestamp().await?;
+	let client_id = sink.client_id();
+	let response = sink.query_client_state(height, client_id.clone()).await?;
+	let client_state = response
+		.client_state
+		.map(AnyClientState::try_from)
+		.ok_or_else(|| anyhow!("Client {client_id} not found on {}", sink.name()))??;
+	let trusting_period = match &client_state {
+		AnyClientState::Tendermint(client_state) => client_state.trusting_period,
+		_ => match policy.default_trusting_period {
+			Some(trusting_period) => trusting_period,
+			None => return Ok(()),
+		},
+	};
+
+	let consensus_height = client_state.latest_height();
+	let response = sink.query_client_consensus(height, client_id.clone(), consensus_height).await?;
+	let consensus_state =
+		response.consensus_state.map(AnyConsensusState::try_from).ok_or_else(|| {
+			anyhow!(
+				"Consensus state of {client_id} at {consensus_height} not found on {}",
+				sink.name()
+			)
+		})??;
+	// the trusting period is measured against the time of the chain the client lives on.
+	let elapsed = Duration::from_nanos(
+		timestamp
+			.nanoseconds()
+			.saturating_sub(consensus_state.timestamp().nanoseconds()),
+	);
+	if let Some(metrics) = metrics {
+		metrics
+			.time_until_client_expiry
+			.set(trusting_period.as_secs_f64() - elapsed.as_secs_f64());
+	}
+	match policy.client_age(elapsed, trusting_period) {
+		ClientAge::Fresh => return Ok(()),
+		ClientAge::Stale => {},
+		ClientAge::Expired => Err(anyhow!(
+			"Client {client_id} on {} expired, it was last updated {elapsed:?} ago",
+			sink.name()
+		))?,
+	}
+
+	log::info!(
+		"Refreshing the client of {} on {}, it was last updated {elapsed:?} ago",
+		source.name(),
+		sink.name()
+	);
+	// only the client is updated, the events covered by the update are left to the relay loop
+	// and packet clearing, which own the submission of packet messages.
+	let mut finality = source.finality_notifications().await;
+	let transactions = update_counterparty_client(source, sink, &mut finality, None).await?;
+	wait_for_finality(sink, transactions).await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn policy(threshold: Option<f64>) -> Result<RefreshPolicy, anyhow::Error> {
+		RefreshPolicy::new(&CoreConfig {
+			prometheus_endpoint: None,
+			packet_clearing_interval: None,
+			admin_endpoint: None,
+			admin_token_env: None,
+			balance_check_interval: None,
+			min_balances: vec![],
+			client_refresh_threshold: threshold,
+			default_trusting_period: None,
+		})
+	}
+
+	#[test]
+	fn threshold_must_be_a_fraction() {
+		assert_eq!(policy(None).unwrap().threshold, DEFAULT_REFRESH_THRESHOLD);
+		assert_eq!(policy(Some(0.5)).unwrap().threshold, 0.5);
+		for threshold in [0.0, 1.0, -0.5, 1.5, f64::NAN] {
+			assert!(policy(Some(threshold)).is_err(), "{threshold}");
+		}
+	}
+
+	#[test]
+	fn clients_are_refreshed_past_the_threshold() {
+		let policy = policy(Some(0.5)).unwrap();
+		let trusting_period = Duration::from_secs(100);
+		let age = |secs| policy.client_age(Duration::from_secs(secs), trusting_period);
+		assert_eq!(age(0), ClientAge::Fresh);
+		assert_eq!(age(49), ClientAge::Fresh);
+		assert_eq!(age(50), ClientAge::Stale);
+		assert_eq!(age(99), ClientAge::Stale);
+		assert_eq!(age(100), ClientAge::Expired);
+		assert_eq!(age(1000), ClientAge::Expired);
+	}
+}
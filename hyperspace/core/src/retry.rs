@@ -0,0 +1,314 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recovery from the errors encountered in the relay loop.
+
+use crate::{balance::LowBalance, queue, tracker::TxTracker};
+use ibc::core::ics02_client::msgs::update_client;
+use ibc_proto::google::protobuf::Any;
+use metrics::handler::MetricsHandler;
+use primitives::{
+	error::{ErrorKind, OutdatedFinalityEvent},
+	Chain,
+};
+use std::time::Duration;
+
+/// Number of times a batch is resubmitted before it's carried over to the next finality event.
+const MAX_SUBMIT_RETRIES: u32 = 3;
+/// Number of finality events a failed batch is carried over to before it's dropped.
+const MAX_CARRIED_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Exponential backoff for retrying transient errors.
+pub struct Backoff {
+	current: Duration,
+}
+
+impl Default for Backoff {
+	fn default() -> Self {
+		Self { current: INITIAL_BACKOFF }
+	}
+}
+
+impl Backoff {
+	/// Returns the delay to wait before the next retry, doubling it for the one after.
+	pub fn next_delay(&mut self) -> Duration {
+		let delay = self.current;
+		self.current = (self.current * 2).min(MAX_BACKOFF);
+		delay
+	}
+
+	/// Resets the delay after a successful attempt.
+	pub fn reset(&mut self) {
+		self.current = INITIAL_BACKOFF;
+	}
+}
+
+/// A batch of messages that failed to be submitted, carried over to the next finality event.
+pub struct PendingBatch {
+	messages: Vec<Any>,
+	/// Whether the batch carried a client update, which is stale by the time it's resubmitted
+	needs_client_update: bool,
+	attempts: u32,
+}
+
+impl PendingBatch {
+	/// A batch of messages to be resubmitted on a later finality event. Its client updates are
+	/// dropped, the batch is resubmitted along with the client update of that event instead.
+	pub fn new(messages: Vec<Any>) -> Self {
+		let (updates, messages): (Vec<_>, Vec<_>) =
+			messages.into_iter().partition(|msg| msg.type_url == update_client::TYPE_URL);
+		Self { messages, needs_client_update: !updates.is_empty(), attempts: 0 }
+	}
+
+	/// Messages of the batch, without its client updates.
+	pub fn messages(&self) -> &[Any] {
+		&self.messages
+	}
+}
+
+/// Takes the batches that wait for a fresh client update out of `pending`, merged into a single
+/// batch to be submitted after the client update of the current finality event.
+pub fn take_awaiting_update(pending: &mut Vec<PendingBatch>) -> Option<PendingBatch> {
+	let (awaiting, rest): (Vec<_>, Vec<_>) =
+		std::mem::take(pending).into_iter().partition(|batch| batch.needs_client_update);
+	*pending = rest;
+	awaiting.into_iter().reduce(|mut merged, batch| {
+		merged.messages.extend(batch.messages);
+		merged.attempts = merged.attempts.max(batch.attempts);
+		merged
+	})
+}
+
+/// Classifies an error encountered while relaying between `source` and `sink`, errors that
+/// neither chain recognizes are assumed to be transient.
+pub fn classify_error(error: &anyhow::Error, source: &impl Chain, sink: &impl Chain) -> ErrorKind {
+	source
+		.error_kind(error)
+		.or_else(|| sink.error_kind(error))
+		.unwrap_or(ErrorKind::Transient)
+}
+
+/// Handles an error encountered while processing a finality event from the `source` chain, only
+/// fatal errors are returned. The finality event is dropped without waiting, the relay loop is
+/// shared by both chains and the ibc events of the next finality event include those of this one.
+pub fn handle_finality_event_error(
+	error: anyhow::Error,
+	action: &str,
+	source: &str,
+	classify: &impl Fn(&anyhow::Error) -> ErrorKind,
+) -> Result<(), anyhow::Error> {
+	// routine when finality events arrive faster than they're relayed, not an error
+	if error.is::<OutdatedFinalityEvent>() {
+		log::debug!("Finality event from {source} already relayed: {error}");
+		return Ok(())
+	}
+	match classify(&error) {
+		ErrorKind::Fatal => Err(error),
+		kind => {
+			log::error!("Failed to {action} from {source} ({kind:?}): {error:?}");
+			Ok(())
+		},
+	}
+}
+
+/// Submits `messages` to `sink`, followed by the messages of a `carried` batch that waited for
+/// the client update leading `messages`. Transient and nonce errors are retried, batches that
+/// still fail are pushed to `pending` to be retried on the next finality event, only fatal errors
+/// are returned. The submitted transactions are handed over to `tracker`.
+pub async fn submit_or_carry<S: Chain>(
+	mut messages: Vec<Any>,
+	carried: Option<PendingBatch>,
+	metrics: Option<&MetricsHandler>,
+	sink: &S,
+	source: &impl Chain,
+	pending: &mut Vec<PendingBatch>,
+	tracker: &mut TxTracker<S::TransactionId>,
+) -> Result<(), anyhow::Error> {
+	if let Some(carried) = &carried {
+		log::info!(
+			"Resubmitting {} messages to {} with a fresh client update, attempt {}",
+			carried.messages.len(),
+			sink.name(),
+			carried.attempts + 1
+		);
+	}
+	let attempts = carried.as_ref().map(|batch| batch.attempts).unwrap_or(0);
+	messages.extend(carried.into_iter().flat_map(|batch| batch.messages));
+	let batch = PendingBatch { messages, needs_client_update: false, attempts };
+	submit_batch(batch, metrics, sink, source, pending, tracker).await
+}
+
+/// Resubmits the batches that previously failed to be submitted to `sink`. Batches that wait
+/// for a client update are left in `pending`, see [`take_awaiting_update`].
+pub async fn retry_pending<S: Chain>(
+	pending: &mut Vec<PendingBatch>,
+	tracker: &mut TxTracker<S::TransactionId>,
+	metrics: Option<&MetricsHandler>,
+	sink: &S,
+	source: &impl Chain,
+) -> Result<(), anyhow::Error> {
+	for batch in std::mem::take(pending) {
+		if batch.needs_client_update {
+			pending.push(batch);
+			continue
+		}
+		log::info!(
+			"Resubmitting {} messages to {}, attempt {}",
+			batch.messages.len(),
+			sink.name(),
+			batch.attempts + 1
+		);
+		submit_batch(batch, metrics, sink, source, pending, tracker).await?;
+	}
+	Ok(())
+}
+
+async fn submit_batch<S: Chain>(
+	mut batch: PendingBatch,
+	metrics: Option<&MetricsHandler>,
+	sink: &S,
+	source: &impl Chain,
+	pending: &mut Vec<PendingBatch>,
+	tracker: &mut TxTracker<S::TransactionId>,
+) -> Result<(), anyhow::Error> {
+	let mut backoff = Backoff::default();
+	let mut retries = 0;
+	loop {
+		let submission =
+			queue::submit_message_batch(batch.messages.clone(), metrics, sink, source).await;
+		tracker.track(submission.transactions);
+		// rejected messages were already dropped, only the unsent ones are retried
+		let error = match submission.error {
+			Some(error) if !submission.unsent.is_empty() => error,
+			_ => return Ok(()),
+		};
+		batch.messages = submission.unsent;
+		let kind = classify_error(&error, sink, source);
+		let delay = match kind {
+			ErrorKind::Fatal => return Err(error),
+			// the balance is checked periodically, retrying right away won't help. The batch is
+			// kept until the balance is topped up, without counting it as a failed attempt.
+			_ if error.is::<LowBalance>() => {
+				log::error!(
+					"Not submitting {} messages to {}: {error}",
+					batch.messages.len(),
+					sink.name()
+				);
+				let batch =
+					PendingBatch { attempts: batch.attempts, ..PendingBatch::new(batch.messages) };
+				if !batch.messages.is_empty() {
+					pending.push(batch);
+				}
+				return Ok(())
+			},
+			ErrorKind::Transient if retries < MAX_SUBMIT_RETRIES => backoff.next_delay(),
+			// give the pending transactions a block to be included
+			ErrorKind::Nonce if retries < MAX_SUBMIT_RETRIES => sink.expected_block_time(),
+			_ => {
+				log::error!("Failed to submit messages to {} ({kind:?}): {error:?}", sink.name());
+				break
+			},
+		};
+		retries += 1;
+		log::warn!(
+			"Failed to submit messages to {} ({kind:?}): {error:?}, retrying in {delay:?}",
+			sink.name()
+		);
+		tokio::time::sleep(delay).await;
+	}
+
+	let batch = PendingBatch { attempts: batch.attempts + 1, ..PendingBatch::new(batch.messages) };
+	if batch.messages.is_empty() {
+		// only a client update is left, the next finality event brings a fresh one
+		return Ok(())
+	}
+	if batch.attempts >= MAX_CARRIED_ATTEMPTS {
+		log::error!(
+			"Dropping {} messages for {} after {} failed attempts",
+			batch.messages.len(),
+			sink.name(),
+			batch.attempts
+		);
+	} else {
+		log::warn!(
+			"Failed to submit {} messages to {}, carrying them over to the next finality event",
+			batch.messages.len(),
+			sink.name()
+		);
+		pending.push(batch);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn msg(id: u8) -> Any {
+		Any { type_url: "/test.Msg".to_owned(), value: vec![id] }
+	}
+
+	fn update() -> Any {
+		Any { type_url: update_client::TYPE_URL.to_owned(), value: vec![] }
+	}
+
+	#[test]
+	fn merges_batches_awaiting_a_client_update() {
+		let mut pending = vec![
+			PendingBatch { attempts: 2, ..PendingBatch::new(vec![update(), msg(0)]) },
+			PendingBatch::new(vec![msg(1)]),
+			PendingBatch { attempts: 1, ..PendingBatch::new(vec![update(), msg(2), msg(3)]) },
+		];
+		let merged = take_awaiting_update(&mut pending).unwrap();
+		assert_eq!(merged.messages(), &[msg(0), msg(2), msg(3)]);
+		assert_eq!(merged.attempts, 2);
+		// batches without a client update are resubmitted as they are
+		assert_eq!(pending.len(), 1);
+		assert_eq!(pending[0].messages(), &[msg(1)]);
+		assert!(take_awaiting_update(&mut pending).is_none());
+	}
+
+	fn classify(error: &anyhow::Error) -> ErrorKind {
+		match error.to_string().as_str() {
+			"fatal" => ErrorKind::Fatal,
+			"rejected" => ErrorKind::Rejected,
+			_ => ErrorKind::Transient,
+		}
+	}
+
+	fn handle(error: anyhow::Error) -> Result<(), anyhow::Error> {
+		handle_finality_event_error(error, "parse events", "test", &classify)
+	}
+
+	#[test]
+	fn only_fatal_finality_event_errors_stop_the_relay_loop() {
+		let error = handle(anyhow::Error::msg("fatal")).unwrap_err();
+		assert_eq!(error.to_string(), "fatal");
+		assert!(handle(anyhow::Error::msg("rejected")).is_ok());
+		assert!(handle(anyhow::Error::msg("connection refused")).is_ok());
+	}
+
+	#[test]
+	fn outdated_finality_events_are_skipped() {
+		let outdated = || OutdatedFinalityEvent { height: 10, latest_height: 12 };
+		let classify = |_: &anyhow::Error| -> ErrorKind { panic!("outdated events aren't errors") };
+		assert!(handle_finality_event_error(outdated().into(), "parse events", "test", &classify)
+			.is_ok());
+		// the event is still recognized when context was added to the error
+		let error = anyhow::Error::from(outdated()).context("querying the latest ibc events");
+		assert!(handle_finality_event_error(error, "parse events", "test", &classify).is_ok());
+	}
+}
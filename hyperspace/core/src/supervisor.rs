@@ -0,0 +1,525 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Supervisor for relaying over several paths from a single process.
+
+use crate::{
+	admin::AdminPath,
+	balance::{monitor_balance, DEFAULT_BALANCE_CHECK_INTERVAL},
+	chain::{AnyChain, AnyFinalityEvent, MultiPathConfig, PathConfig, PathEnd},
+	finality::SharedFinality,
+	packets::clearing::clear_packets_periodically,
+	refresh::{refresh_client_periodically, RefreshPolicy},
+	relay_with_finality,
+	upgrade::upgrade_clients_on_schedule,
+	Mode,
+};
+use anyhow::anyhow;
+use futures::{stream::FuturesUnordered, Future, StreamExt};
+use ibc::core::ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId};
+use metrics::{
+	data::{BalanceMetrics, ClientMetrics, Metrics},
+	handler::MetricsHandler,
+};
+use primitives::{
+	filter::{PacketFilter, PausedChannels},
+	Chain, IbcProvider,
+};
+use prometheus::Registry;
+use std::{collections::HashMap, time::Duration};
+
+/// Delay before a failed path is restarted.
+const RESTART_DELAY: Duration = Duration::from_secs(10);
+
+/// A chain client shared by all the paths it appears on, along with a single subscription to
+/// its finality notifications.
+#[derive(Clone)]
+struct SharedChain {
+	chain: AnyChain,
+	finality: SharedFinality<AnyFinalityEvent>,
+}
+
+impl SharedChain {
+	fn new(chain: AnyChain) -> Self {
+		let finality = SharedFinality::new(chain.clone());
+		Self { chain, finality }
+	}
+}
+
+/// Runs one relay loop per configured path, sharing chain clients and finality subscriptions
+/// between the paths. A failing path is restarted without affecting the others. The relayer
+/// balance on every chain is monitored from the moment it's connected.
+pub struct Supervisor {
+	chains: HashMap<String, SharedChain>,
+	paths: Vec<PathConfig>,
+	/// Channels paused through the admin api, attached to the packet filters of every path
+	paused: PausedChannels,
+	registry: Registry,
+	packet_clearing_interval: Option<Duration>,
+	refresh_policy: Option<RefreshPolicy>,
+	mode: Option<Mode>,
+}
+
+impl Supervisor {
+	/// Connects to all the configured chains and checks that every path refers to one of them.
+	pub async fn new(
+		config: MultiPathConfig,
+		registry: Registry,
+		mode: Option<Mode>,
+	) -> anyhow::Result<Self> {
+		let refresh_policy = RefreshPolicy::new(&config.core)?;
+		let balance_check_interval = config
+			.core
+			.balance_check_interval
+			.map(Duration::from_secs)
+			.unwrap_or(DEFAULT_BALANCE_CHECK_INTERVAL);
+		let mut chains = HashMap::new();
+		for chain_config in config.chains {
+			let name = chain_config.name().to_owned();
+			if chains.contains_key(&name) {
+				Err(anyhow!("Chain {name} is configured more than once"))?
+			}
+			let chain = chain_config.into_client().await?;
+			tokio::spawn(monitor_balance(
+				chain.clone(),
+				config.core.min_balances.clone(),
+				Some(BalanceMetrics::register(&name, &registry)?),
+				balance_check_interval,
+			));
+			chains.insert(name, SharedChain::new(chain));
+		}
+
+		for path in &config.paths {
+			for end in [&path.chain_a, &path.chain_b] {
+				if !chains.contains_key(&end.chain) {
+					Err(anyhow!("Path {} refers to unknown chain {}", path.name(), end.chain))?
+				}
+			}
+		}
+
+		// packet clearing submits its messages directly, so it's disabled in dry-run mode.
+		let packet_clearing_interval = config
+			.core
+			.packet_clearing_interval
+			.filter(|_| !matches!(mode, Some(Mode::DryRun)))
+			.map(Duration::from_secs);
+		// and so do client refreshes and upgrades.
+		let refresh_policy = Some(refresh_policy).filter(|_| !matches!(mode, Some(Mode::DryRun)));
+		Ok(Self {
+			chains,
+			paths: config.paths,
+			paused: PausedChannels::default(),
+			registry,
+			packet_clearing_interval,
+			refresh_policy,
+			mode,
+		})
+	}
+
+	/// Runs all the paths, only returns once every path has stopped.
+	pub async fn run(self) -> anyhow::Result<()> {
+		let mut paths = self
+			.paths
+			.into_iter()
+			.map(|path| {
+				let name = path.name();
+				let chain_a = self.chains[&path.chain_a.chain].clone();
+				let chain_b = self.chains[&path.chain_b.chain].clone();
+				let client_a = configure_client(&chain_a.chain, path.chain_a, &self.paused);
+				let client_b = configure_client(&chain_b.chain, path.chain_b, &self.paused);
+				let handle = tokio::spawn(run_path(
+					name.clone(),
+					client_a,
+					client_b,
+					chain_a,
+					chain_b,
+					self.registry.clone(),
+					self.packet_clearing_interval,
+					self.refresh_policy,
+					self.mode,
+				));
+				async move { (name, handle.await) }
+			})
+			.collect::<FuturesUnordered<_>>();
+
+		while let Some((name, result)) = paths.next().await {
+			if let Err(err) = result {
+				log::error!("Relay path {name} stopped: {err:?}");
+			}
+		}
+
+		Err(anyhow!("All relay paths have stopped"))
+	}
+
+	/// Returns the clients of every path, configured for the path, to be exposed through the
+	/// admin api.
+	pub fn admin_paths(&self) -> Vec<AdminPath> {
+		self.paths
+			.iter()
+			.map(|path| AdminPath {
+				name: path.name(),
+				chain_a: configure_client(
+					&self.chains[&path.chain_a.chain].chain,
+					path.chain_a.clone(),
+					&self.paused,
+				),
+				chain_b: configure_client(
+					&self.chains[&path.chain_b.chain].chain,
+					path.chain_b.clone(),
+					&self.paused,
+				),
+				finality_a: self.chains[&path.chain_a.chain].finality.clone(),
+				finality_b: self.chains[&path.chain_b.chain].finality.clone(),
+			})
+			.collect()
+	}
+
+	/// Returns the channels paused through the admin api, shared by every path.
+	pub fn paused_channels(&self) -> PausedChannels {
+		self.paused.clone()
+	}
+}
+
+/// The settings of a chain client that differ between the paths sharing it.
+trait PathClient: Clone {
+	fn set_client_id(&mut self, client_id: ClientId);
+	fn set_connection_id(&mut self, connection_id: ConnectionId);
+	fn set_channel_whitelist(&mut self, channel_whitelist: Vec<(ChannelId, PortId)>);
+	fn set_packet_filter(&mut self, packet_filter: PacketFilter);
+	fn set_paused_channels(&mut self, paused: PausedChannels);
+}
+
+impl PathClient for AnyChain {
+	fn set_client_id(&mut self, client_id: ClientId) {
+		AnyChain::set_client_id(self, client_id)
+	}
+
+	fn set_connection_id(&mut self, connection_id: ConnectionId) {
+		AnyChain::set_connection_id(self, connection_id)
+	}
+
+	fn set_channel_whitelist(&mut self, channel_whitelist: Vec<(ChannelId, PortId)>) {
+		AnyChain::set_channel_whitelist(self, channel_whitelist)
+	}
+
+	fn set_packet_filter(&mut self, packet_filter: PacketFilter) {
+		AnyChain::set_packet_filter(self, packet_filter)
+	}
+
+	fn set_paused_channels(&mut self, paused: PausedChannels) {
+		AnyChain::set_paused_channels(self, paused)
+	}
+}
+
+/// Returns a copy of the chain client configured for one end of a path.
+fn configure_client<C: PathClient>(chain: &C, end: PathEnd, paused: &PausedChannels) -> C {
+	let mut client = chain.clone();
+	client.set_client_id(end.client_id);
+	client.set_connection_id(end.connection_id);
+	client.set_channel_whitelist(end.channel_whitelist);
+	if let Some(packet_filter) = end.packet_filter {
+		client.set_packet_filter(packet_filter);
+	}
+	client.set_paused_channels(paused.clone());
+	client
+}
+
+/// Relays over a single path forever, restarting the relay loop whenever it fails.
+async fn run_path(
+	name: String,
+	client_a: AnyChain,
+	client_b: AnyChain,
+	chain_a: SharedChain,
+	chain_b: SharedChain,
+	registry: Registry,
+	packet_clearing_interval: Option<Duration>,
+	refresh_policy: Option<RefreshPolicy>,
+	mode: Option<Mode>,
+) {
+	if let Some(interval) = packet_clearing_interval {
+		tokio::spawn(clear_packets_periodically(
+			client_a.clone(),
+			client_b.clone(),
+			chain_a.finality.clone(),
+			chain_b.finality.clone(),
+			interval,
+		));
+	}
+	if let Some(policy) = refresh_policy {
+		for (source, sink) in [(&client_a, &client_b), (&client_b, &client_a)] {
+			let prefix = metrics_prefix(
+				sink.name(),
+				source.name(),
+				&source.client_id(),
+				&sink.connection_id(),
+			);
+			let metrics = ClientMetrics::register(&prefix, &registry)
+				.map_err(|err| {
+					log::warn!("Failed to register client metrics for relay path {name}: {err:?}")
+				})
+				.ok();
+			tokio::spawn(refresh_client_periodically(
+				source.clone(),
+				sink.clone(),
+				policy,
+				metrics,
+			));
+			tokio::spawn(upgrade_clients_on_schedule(source.clone(), sink.clone()));
+		}
+	}
+
+	let metrics = match register_metrics(&client_a, &client_b, &registry) {
+		Ok((metrics_a, metrics_b)) => (Some(metrics_a), Some(metrics_b)),
+		Err(err) => {
+			log::warn!("Failed to register metrics for relay path {name}: {err:?}");
+			(None, None)
+		},
+	};
+
+	restart_on_failure(&name, metrics, |(mut metrics_a, mut metrics_b)| {
+		let (client_a, client_b) = (client_a.clone(), client_b.clone());
+		let (finality_a, finality_b) = (chain_a.finality.subscribe(), chain_b.finality.subscribe());
+		async move {
+			let result = relay_with_finality(
+				client_a,
+				client_b,
+				finality_a,
+				finality_b,
+				&mut metrics_a,
+				&mut metrics_b,
+				mode,
+			)
+			.await;
+			((metrics_a, metrics_b), result)
+		}
+	})
+	.await
+}
+
+/// Runs the relay loop of a path forever, restarting it [`RESTART_DELAY`] after it stops. The
+/// `state` returned by one run is handed to the next.
+async fn restart_on_failure<S, F, Fut>(name: &str, mut state: S, mut relay: F)
+where
+	F: FnMut(S) -> Fut,
+	Fut: Future<Output = (S, anyhow::Result<()>)>,
+{
+	loop {
+		let (next_state, result) = relay(state).await;
+		state = next_state;
+		match result {
+			Ok(()) => log::error!(
+				"Finality notifications for relay path {name} ended, restarting in {RESTART_DELAY:?}"
+			),
+			Err(err) =>
+				log::error!("Relay path {name} failed: {err:?}, restarting in {RESTART_DELAY:?}"),
+		}
+		tokio::time::sleep(RESTART_DELAY).await;
+	}
+}
+
+/// Returns the prefix of the metrics of one end of a path: the chain and counterparty names,
+/// along with the client of the counterparty and the connection on the chain, since the same
+/// chains may be connected by several paths.
+fn metrics_prefix(
+	chain: &str,
+	counterparty: &str,
+	client_id: &ClientId,
+	connection_id: &ConnectionId,
+) -> String {
+	format!("{chain}_{counterparty}_{client_id}_{connection_id}")
+		.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+		.collect()
+}
+
+/// Registers the metrics of both ends of a path, see [`metrics_prefix`].
+fn register_metrics(
+	chain_a: &AnyChain,
+	chain_b: &AnyChain,
+	registry: &Registry,
+) -> anyhow::Result<(MetricsHandler, MetricsHandler)> {
+	let prefix_a = metrics_prefix(
+		chain_a.name(),
+		chain_b.name(),
+		&chain_b.client_id(),
+		&chain_a.connection_id(),
+	);
+	let prefix_b = metrics_prefix(
+		chain_b.name(),
+		chain_a.name(),
+		&chain_a.client_id(),
+		&chain_b.connection_id(),
+	);
+	let metrics_a = Metrics::register(&prefix_a, registry)?;
+	let metrics_b = Metrics::register(&prefix_b, registry)?;
+	let mut metrics_handler_a = MetricsHandler::new(registry.clone(), metrics_a);
+	let mut metrics_handler_b = MetricsHandler::new(registry.clone(), metrics_b);
+	metrics_handler_a.link_with_counterparty(&mut metrics_handler_b);
+	Ok((metrics_handler_a, metrics_handler_b))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::filter::{ChannelPattern, PausedChannel};
+	use std::sync::{Arc, Mutex};
+	use tokio::time::Instant;
+
+	#[derive(Clone, Default)]
+	struct TestClient {
+		client_id: Option<ClientId>,
+		connection_id: Option<ConnectionId>,
+		channel_whitelist: Vec<(ChannelId, PortId)>,
+		packet_filter: PacketFilter,
+	}
+
+	impl PathClient for TestClient {
+		fn set_client_id(&mut self, client_id: ClientId) {
+			self.client_id = Some(client_id);
+		}
+
+		fn set_connection_id(&mut self, connection_id: ConnectionId) {
+			self.connection_id = Some(connection_id);
+		}
+
+		fn set_channel_whitelist(&mut self, channel_whitelist: Vec<(ChannelId, PortId)>) {
+			self.channel_whitelist = channel_whitelist;
+		}
+
+		fn set_packet_filter(&mut self, packet_filter: PacketFilter) {
+			self.packet_filter = packet_filter;
+		}
+
+		fn set_paused_channels(&mut self, paused: PausedChannels) {
+			self.packet_filter.paused = paused;
+		}
+	}
+
+	fn deny(channel_id: &str) -> PacketFilter {
+		PacketFilter {
+			deny: vec![ChannelPattern {
+				port_id: "*".to_owned(),
+				channel_id: channel_id.to_owned(),
+			}],
+			..Default::default()
+		}
+	}
+
+	fn path_end(counter: u64, packet_filter: Option<PacketFilter>) -> PathEnd {
+		PathEnd {
+			chain: "a".to_owned(),
+			client_id: ClientId::new("07-tendermint", counter).unwrap(),
+			connection_id: ConnectionId::new(counter),
+			channel_whitelist: vec![(ChannelId::new(counter), PortId::transfer())],
+			packet_filter,
+		}
+	}
+
+	#[test]
+	fn clients_are_configured_per_path() {
+		let chain = TestClient { packet_filter: deny("channel-9"), ..Default::default() };
+		let paused = PausedChannels::default();
+
+		let client_0 = configure_client(&chain, path_end(0, None), &paused);
+		let client_1 = configure_client(&chain, path_end(1, Some(deny("channel-0"))), &paused);
+
+		assert_eq!(client_0.client_id, Some(ClientId::new("07-tendermint", 0).unwrap()));
+		assert_eq!(client_0.connection_id, Some(ConnectionId::new(0)));
+		assert_eq!(client_0.channel_whitelist, vec![(ChannelId::new(0), PortId::transfer())]);
+		// paths without a packet filter keep the one of the chain
+		assert_eq!(client_0.packet_filter.deny, deny("channel-9").deny);
+
+		assert_eq!(client_1.client_id, Some(ClientId::new("07-tendermint", 1).unwrap()));
+		assert_eq!(client_1.connection_id, Some(ConnectionId::new(1)));
+		assert_eq!(client_1.channel_whitelist, vec![(ChannelId::new(1), PortId::transfer())]);
+		assert_eq!(client_1.packet_filter.deny, deny("channel-0").deny);
+
+		// the shared chain client is left as is
+		assert_eq!(chain.client_id, None);
+		assert_eq!(chain.connection_id, None);
+		assert!(chain.channel_whitelist.is_empty());
+	}
+
+	#[test]
+	fn paused_channels_are_shared_by_every_path() {
+		let chain = TestClient::default();
+		let paused = PausedChannels::default();
+		let clients = [
+			configure_client(&chain, path_end(0, None), &paused),
+			configure_client(&chain, path_end(1, Some(deny("channel-0"))), &paused),
+		];
+
+		let channel = PausedChannel {
+			chain: "a".to_owned(),
+			port_id: PortId::transfer(),
+			channel_id: ChannelId::new(1),
+		};
+		assert!(paused.pause(channel.clone()));
+		for client in &clients {
+			assert!(client.packet_filter.paused.contains(
+				"a",
+				&PortId::transfer(),
+				&ChannelId::new(1)
+			));
+		}
+
+		assert!(paused.resume(&channel));
+		for client in &clients {
+			assert!(!client.packet_filter.paused.contains(
+				"a",
+				&PortId::transfer(),
+				&ChannelId::new(1)
+			));
+		}
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn paths_are_restarted_after_the_restart_delay() {
+		let runs = Arc::new(Mutex::new(Vec::new()));
+		let restart = restart_on_failure("a<->b", 0, |run: u32| {
+			let runs = runs.clone();
+			async move {
+				runs.lock().unwrap().push((run, Instant::now()));
+				// alternate between failures and finality notifications ending
+				let result = if run % 2 == 0 { Err(anyhow!("relay failed")) } else { Ok(()) };
+				(run + 1, result)
+			}
+		});
+		let start = Instant::now();
+		assert!(tokio::time::timeout(RESTART_DELAY * 3 + RESTART_DELAY / 2, restart)
+			.await
+			.is_err());
+
+		let runs = runs.lock().unwrap();
+		let expected = (0..4).map(|run| (run, start + RESTART_DELAY * run)).collect::<Vec<_>>();
+		assert_eq!(*runs, expected);
+	}
+
+	#[test]
+	fn paths_between_the_same_chains_have_distinct_metrics() {
+		let registry = Registry::new();
+		let prefix = |counter| {
+			metrics_prefix(
+				"chain-a",
+				"chain-b",
+				&ClientId::new("07-tendermint", counter).unwrap(),
+				&ConnectionId::new(counter),
+			)
+		};
+		assert_eq!(prefix(0), "chain_a_chain_b_07_tendermint_0_connection_0");
+		Metrics::register(&prefix(0), &registry).unwrap();
+		Metrics::register(&prefix(1), &registry).unwrap();
+		assert!(Metrics::register(&prefix(0), &registry).is_err());
+	}
+}
@@ -0,0 +1,261 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracking of the transactions submitted by the relay loop until they're finalized.
+
+use crate::retry::PendingBatch;
+use anyhow::anyhow;
+use ibc_proto::google::protobuf::Any;
+use metrics::handler::MetricsHandler;
+use primitives::{error::ErrorKind, Chain, TxStatus};
+use std::time::{Duration, Instant};
+
+/// Time a submitted transaction has to be included in a canonical block, before its messages
+/// are resubmitted.
+const INCLUSION_TIMEOUT: Duration = Duration::from_secs(120);
+/// Time a submitted transaction has to be finalized, before it stops being tracked.
+const FINALITY_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// A transaction submitted to a chain, along with the messages it carries.
+pub struct SubmittedTx<Id> {
+	pub tx_id: Id,
+	pub messages: Vec<Any>,
+}
+
+struct TrackedTx<Id> {
+	tx: SubmittedTx<Id>,
+	submitted_at: Instant,
+}
+
+/// Tracks the transactions submitted to a chain until they're finalized, reporting the
+/// messages that failed on chain and requeueing the transactions that were dropped or failed
+/// transiently.
+pub struct TxTracker<Id> {
+	transactions: Vec<TrackedTx<Id>>,
+}
+
+impl<Id> Default for TxTracker<Id> {
+	fn default() -> Self {
+		Self { transactions: vec![] }
+	}
+}
+
+impl<Id> TxTracker<Id> {
+	/// Starts tracking the given transactions.
+	pub fn track(&mut self, transactions: Vec<SubmittedTx<Id>>) {
+		let submitted_at = Instant::now();
+		self.transactions
+			.extend(transactions.into_iter().map(|tx| TrackedTx { tx, submitted_at }));
+	}
+
+	/// Checks the status of the tracked transactions on `chain`. The messages of transactions
+	/// that weren't included in time or failed transiently are pushed to `pending` to be
+	/// resubmitted.
+	pub async fn poll<C>(
+		&mut self,
+		chain: &C,
+		metrics: Option<&MetricsHandler>,
+		pending: &mut Vec<PendingBatch>,
+	) where
+		C: Chain<TransactionId = Id>,
+	{
+		let mut statuses = vec![];
+		for tracked in std::mem::take(&mut self.transactions) {
+			let status =
+				chain.query_tx_status(&tracked.tx.tx_id).await.map_err(anyhow::Error::from);
+			statuses.push((tracked, status));
+		}
+		let classify =
+			|error: &anyhow::Error| chain.error_kind(error).unwrap_or(ErrorKind::Transient);
+		self.handle_statuses(statuses, chain.name(), &classify, metrics, pending).await
+	}
+
+	async fn handle_statuses(
+		&mut self,
+		statuses: Vec<(TrackedTx<Id>, Result<TxStatus, anyhow::Error>)>,
+		chain_name: &str,
+		classify: &impl Fn(&anyhow::Error) -> ErrorKind,
+		metrics: Option<&MetricsHandler>,
+		pending: &mut Vec<PendingBatch>,
+	) {
+		for (tracked, status) in statuses {
+			let status = match status {
+				Ok(status) => status,
+				Err(err) => {
+					log::warn!(
+						"Failed to query the status of a transaction on {chain_name}: {err:?}"
+					);
+					self.transactions.push(tracked);
+					continue
+				},
+			};
+			let elapsed = tracked.submitted_at.elapsed();
+			match status {
+				TxStatus::Pending if elapsed > INCLUSION_TIMEOUT => {
+					log::warn!(
+						"Transaction with {} messages wasn't included on {chain_name} after {elapsed:?}, resubmitting them",
+						tracked.tx.messages.len(),
+					);
+					if let Some(metrics) = metrics {
+						metrics.handle_timed_out_bundle().await;
+					}
+					pending.push(PendingBatch::new(tracked.tx.messages));
+				},
+				TxStatus::Included if elapsed > FINALITY_TIMEOUT => {
+					log::error!(
+						"Transaction with {} messages wasn't finalized on {chain_name} after {elapsed:?}, no longer tracking it",
+						tracked.tx.messages.len(),
+					);
+					if let Some(metrics) = metrics {
+						metrics.handle_timed_out_bundle().await;
+					}
+				},
+				TxStatus::Pending | TxStatus::Included => self.transactions.push(tracked),
+				TxStatus::Finalized { failed_messages } => {
+					for error in &failed_messages {
+						log::error!("Message delivered to {chain_name} failed: {error}");
+					}
+					if let Some(metrics) = metrics {
+						metrics.handle_finalized_bundle(failed_messages.len()).await;
+					}
+				},
+				TxStatus::Failed(error) => {
+					if let Some(metrics) = metrics {
+						metrics.handle_reverted_bundle().await;
+					}
+					let error = anyhow!(error);
+					match classify(&error) {
+						kind @ (ErrorKind::Transient | ErrorKind::Nonce) => {
+							log::warn!(
+								"Transaction with {} messages failed on {chain_name} ({kind:?}): {error:?}, resubmitting them",
+								tracked.tx.messages.len(),
+							);
+							pending.push(PendingBatch::new(tracked.tx.messages));
+						},
+						kind => log::error!(
+							"Transaction with {} messages failed on {chain_name} ({kind:?}): {error:?}",
+							tracked.tx.messages.len(),
+						),
+					}
+				},
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ibc::core::ics02_client::msgs::update_client;
+
+	fn msg(id: u8) -> Any {
+		Any { type_url: "/test.Msg".to_owned(), value: vec![id] }
+	}
+
+	fn update() -> Any {
+		Any { type_url: update_client::TYPE_URL.to_owned(), value: vec![] }
+	}
+
+	fn classify(error: &anyhow::Error) -> ErrorKind {
+		match error.to_string().as_str() {
+			"nonce" => ErrorKind::Nonce,
+			"rejected" => ErrorKind::Rejected,
+			_ => ErrorKind::Transient,
+		}
+	}
+
+	fn tracked(tx_id: usize, messages: Vec<Any>, age: Duration) -> TrackedTx<usize> {
+		TrackedTx { tx: SubmittedTx { tx_id, messages }, submitted_at: Instant::now() - age }
+	}
+
+	/// Handles the statuses of transactions `0..statuses.len()`, each carrying a client update and
+	/// a message with the id of the transaction. Returns the transactions still tracked and the
+	/// messages requeued.
+	async fn handle(
+		statuses: Vec<(Result<TxStatus, &'static str>, Duration)>,
+	) -> (Vec<usize>, Vec<Vec<Any>>) {
+		let mut tracker = TxTracker::default();
+		let statuses = statuses
+			.into_iter()
+			.enumerate()
+			.map(|(id, (status, age))| {
+				(
+					tracked(id, vec![update(), msg(id as u8)], age),
+					status.map_err(anyhow::Error::msg),
+				)
+			})
+			.collect();
+		let mut pending = vec![];
+		tracker.handle_statuses(statuses, "test", &classify, None, &mut pending).await;
+		let tracked = tracker.transactions.iter().map(|tracked| tracked.tx.tx_id).collect();
+		let requeued = pending.iter().map(|batch| batch.messages().to_vec()).collect();
+		(tracked, requeued)
+	}
+
+	#[tokio::test]
+	async fn keeps_tracking_transactions_in_time() {
+		let fresh = Duration::from_secs(1);
+		let (tracked, requeued) =
+			handle(vec![(Ok(TxStatus::Pending), fresh), (Ok(TxStatus::Included), fresh)]).await;
+		assert_eq!(tracked, vec![0, 1]);
+		assert!(requeued.is_empty());
+	}
+
+	#[tokio::test]
+	async fn keeps_tracking_transactions_whose_status_is_unknown() {
+		let late = INCLUSION_TIMEOUT * 2;
+		let (tracked, requeued) = handle(vec![(Err("connection refused"), late)]).await;
+		// a failed query doesn't mean the transaction was dropped
+		assert_eq!(tracked, vec![0]);
+		assert!(requeued.is_empty());
+	}
+
+	#[tokio::test]
+	async fn requeues_transactions_not_included_in_time() {
+		let late = INCLUSION_TIMEOUT + Duration::from_secs(1);
+		let (tracked, requeued) = handle(vec![(Ok(TxStatus::Pending), late)]).await;
+		assert!(tracked.is_empty());
+		// the stale client update is dropped, a fresh one is submitted with the messages
+		assert_eq!(requeued, vec![vec![msg(0)]]);
+	}
+
+	#[tokio::test]
+	async fn stops_tracking_transactions_not_finalized_in_time() {
+		let late = FINALITY_TIMEOUT + Duration::from_secs(1);
+		let (tracked, requeued) = handle(vec![(Ok(TxStatus::Included), late)]).await;
+		assert!(tracked.is_empty());
+		assert!(requeued.is_empty());
+	}
+
+	#[tokio::test]
+	async fn stops_tracking_finalized_transactions() {
+		let status = TxStatus::Finalized { failed_messages: vec!["failed".to_owned()] };
+		let (tracked, requeued) = handle(vec![(Ok(status), Duration::from_secs(1))]).await;
+		assert!(tracked.is_empty());
+		assert!(requeued.is_empty());
+	}
+
+	#[tokio::test]
+	async fn requeues_only_transactions_that_failed_transiently() {
+		let age = Duration::from_secs(1);
+		let (tracked, requeued) = handle(vec![
+			(Ok(TxStatus::Failed("nonce".to_owned())), age),
+			(Ok(TxStatus::Failed("rejected".to_owned())), age),
+			(Ok(TxStatus::Failed("out of gas".to_owned())), age),
+		])
+		.await;
+		assert!(tracked.is_empty());
+		assert_eq!(requeued, vec![vec![msg(0)], vec![msg(2)]]);
+	}
+}
@@ -0,0 +1,132 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Relaying of the client upgrades scheduled with `pallet-ibc`'s `upgrade_client` extrinsic.
+
+use crate::{
+	handshake::{latest_client_height, wait_for_finality},
+	packets::clearing::update_counterparty_client,
+	queue,
+};
+use anyhow::anyhow;
+use futures::StreamExt;
+use ibc::{
+	core::ics02_client::{
+		client_state::ClientState as ClientStateT, msgs::upgrade_client::MsgUpgradeAnyClient,
+	},
+	tx_msg::Msg,
+	Height,
+};
+use ibc_proto::google::protobuf::Any;
+use pallet_ibc::light_clients::{AnyClientState, AnyConsensusState};
+use primitives::{mock::LocalClientTypes, Chain};
+use std::time::Duration;
+use tendermint_proto::Protobuf;
+
+/// How many times a scheduled upgrade is attempted before waiting for the next one.
+const UPGRADE_ATTEMPTS: usize = 10;
+/// Delay between the attempts, the block that scheduled the upgrade may not be final yet.
+const UPGRADE_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Upgrades the client of `source` on `sink` whenever `source` schedules a client upgrade.
+pub async fn upgrade_clients_on_schedule<S, T>(mut source: S, sink: T)
+where
+	S: Chain,
+	T: Chain,
+{
+	let mut upgrades = source.client_upgrades().await;
+	while upgrades.next().await.is_some() {
+		log::info!("{} scheduled a client upgrade", source.name());
+		for attempt in 1..=UPGRADE_ATTEMPTS {
+			match upgrade_client(&mut source, &sink, false).await {
+				Ok(Some(_)) => break,
+				Ok(None) => log::debug!(
+					"The upgrade scheduled by {} isn't final yet (attempt {attempt})",
+					source.name()
+				),
+				Err(err) => log::error!(
+					"Failed to upgrade the client of {} on {} (attempt {attempt}): {err:?}",
+					source.name(),
+					sink.name()
+				),
+			}
+			tokio::time::sleep(UPGRADE_RETRY_DELAY).await;
+		}
+	}
+	log::error!("The client upgrade subscription of {} ended", source.name());
+}
+
+/// Upgrades the client of `source` on `sink` to the client and consensus states `source`
+/// scheduled for its upgrade. The states are proven at the latest height of the client, so it's
+/// updated first to make sure that height includes the scheduled upgrade.
+///
+/// Returns the latest height of the upgraded client, or `None` if the client had already been
+/// upgraded. In dry-run mode the message is only simulated, against the current client height.
+pub async fn upgrade_client<S, T>(
+	source: &mut S,
+	sink: &T,
+	dry_run: bool,
+) -> Result<Option<Height>, anyhow::Error>
+where
+	S: Chain,
+	T: Chain,
+{
+	if !dry_run {
+		let mut finality = source.finality_notifications().await;
+		let transactions = update_counterparty_client(source, sink, &mut finality, None).await?;
+		wait_for_finality(sink, transactions).await?;
+	}
+	let client_height = latest_client_height(sink).await?;
+	let client_response = source.query_upgraded_client_state(client_height).await?;
+	let client_state = client_response
+		.client_state
+		.map(AnyClientState::try_from)
+		.ok_or_else(|| anyhow!("No client upgrade is scheduled on {}", source.name()))??;
+	let upgraded_height = client_state.latest_height();
+	if upgraded_height <= client_height {
+		log::info!(
+			"The client of {} on {} is already at {client_height}, past the upgraded height {upgraded_height}",
+			source.name(),
+			sink.name()
+		);
+		return Ok(None)
+	}
+
+	let consensus_response = source.query_upgraded_consensus_state(client_height).await?;
+	let consensus_state = consensus_response
+		.consensus_state
+		.map(AnyConsensusState::try_from)
+		.ok_or_else(|| {
+			anyhow!("No upgraded consensus state is scheduled on {}", source.name())
+		})??;
+
+	let msg = MsgUpgradeAnyClient::<LocalClientTypes> {
+		client_id: sink.client_id(),
+		client_state,
+		consensus_state,
+		proof_upgrade_client: client_response.proof,
+		proof_upgrade_consensus_state: consensus_response.proof,
+		signer: sink.account_id(),
+	};
+	let msg = Any { type_url: msg.type_url(), value: msg.encode_vec() };
+
+	if dry_run {
+		queue::simulate_message_batch(vec![msg], sink).await?;
+		return Ok(Some(upgraded_height))
+	}
+	log::info!("Upgrading the client of {} on {} to {upgraded_height}", source.name(), sink.name());
+	let transactions = queue::flush_message_batch(vec![msg], None, sink, source).await?;
+	wait_for_finality(sink, transactions).await?;
+	Ok(Some(upgraded_height))
+}
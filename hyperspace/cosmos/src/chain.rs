@@ -0,0 +1,308 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{pin::Pin, str::FromStr};
+
+use anyhow::anyhow;
+use futures::{Stream, StreamExt, TryFutureExt};
+use ibc::{
+	core::ics02_client::{events::UpdateClient, msgs::update_client::MsgUpdateAnyClient},
+	tx_msg::Msg,
+};
+use ibc_proto::{
+	cosmos::tx::v1beta1::{service_client::ServiceClient, SimulateRequest},
+	google::protobuf::Any,
+};
+use ics07_tendermint::client_message::{ClientMessage, Misbehaviour};
+use pallet_ibc::light_clients::AnyClientMessage;
+use primitives::{
+	error::ErrorKind,
+	misbehaviour::{MisbehaviourAlert, UnreportedMisbehaviour},
+	mock::LocalClientTypes,
+	Chain, IbcProvider, LowBalanceState, MisbehaviourHandler, SimulationResult, TxStatus,
+};
+use prost::Message;
+use tendermint::{abci::transaction::Hash as TxHash, block::Height as TmHeight};
+use tendermint_rpc::{
+	error::ErrorDetail,
+	event::EventData,
+	query::{EventType, Query},
+	Client, Order,
+};
+use tonic::Code;
+
+use super::{
+	error::Error,
+	provider::{FinalityEvent, TransactionId},
+	CosmosClient,
+};
+
+#[async_trait::async_trait]
+impl Chain for CosmosClient {
+	fn name(&self) -> &str {
+		&*self.name
+	}
+
+	fn block_max_weight(&self) -> u64 {
+		// weights are expressed in gas, which is bounded by the gas limit of a single transaction
+		self.gas_limit
+	}
+
+	async fn estimate_weight(&self, messages: Vec<Any>) -> Result<u64, Self::Error> {
+		let account = self.query_account().await?;
+		let tx = self.sign_tx(messages, &account)?;
+		let mut client = ServiceClient::new(self.grpc_channel.clone());
+		#[allow(deprecated)]
+		let response = client
+			.simulate(SimulateRequest { tx: None, tx_bytes: tx.encode_to_vec() })
+			.await?
+			.into_inner();
+		let gas_info = response
+			.gas_info
+			.ok_or_else(|| Error::Custom("Simulation returned no gas info".to_string()))?;
+		Ok(gas_info.gas_used)
+	}
+
+	async fn simulate(&self, messages: Vec<Any>) -> Result<SimulationResult, Self::Error> {
+		let account = self.query_account().await?;
+		let tx = self.sign_tx(messages, &account)?;
+		let mut client = ServiceClient::new(self.grpc_channel.clone());
+		#[allow(deprecated)]
+		let result = client
+			.simulate(SimulateRequest { tx: None, tx_bytes: tx.encode_to_vec() })
+			.await;
+		// the transaction fee is fixed by the config rather than derived from the gas used.
+		let fee = self.fee_amount.parse().ok();
+		match result {
+			Ok(response) => {
+				let gas_info = response
+					.into_inner()
+					.gas_info
+					.ok_or_else(|| Error::Custom("Simulation returned no gas info".to_string()))?;
+				Ok(SimulationResult { weight: gas_info.gas_used, fee, error: None })
+			},
+			// failed message execution is reported as an error status by the simulation endpoint
+			Err(status) if matches!(status.code(), Code::Unknown | Code::InvalidArgument) =>
+				Ok(SimulationResult { weight: 0, fee, error: Some(status.message().to_string()) }),
+			Err(status) => Err(status.into()),
+		}
+	}
+
+	async fn finality_notifications(
+		&self,
+	) -> Pin<Box<dyn Stream<Item = <Self as IbcProvider>::FinalityEvent> + Send + Sync>> {
+		let subscription = self
+			.subscribe(EventType::NewBlock.into())
+			.await
+			.expect("Failed to subscribe to new blocks");
+
+		let stream = subscription.filter_map(|event| {
+			let header = match event.data {
+				EventData::NewBlock { block: Some(block), .. } => block.header,
+				_ => return futures::future::ready(None),
+			};
+			futures::future::ready(Some(FinalityEvent::Tendermint(header)))
+		});
+
+		Box::pin(stream)
+	}
+
+	async fn submit(&self, messages: Vec<Any>) -> Result<Self::TransactionId, Error> {
+		let account = self.query_account().await?;
+		let tx = self.sign_tx(messages, &account)?;
+		let response = self.broadcast_tx(tx).await?;
+		let result = self.wait_for_tx(&response.txhash).await?;
+		log::debug!(
+			target: "hyperspace",
+			"Transaction {} included in block {} on {}",
+			response.txhash,
+			result.height,
+			self.name
+		);
+
+		Ok(TransactionId { hash: response.txhash, height: result.height.value() })
+	}
+
+	fn low_balance(&self) -> &LowBalanceState {
+		&self.low_balance
+	}
+
+	async fn query_tx_status(&self, tx_id: &Self::TransactionId) -> Result<TxStatus, Error> {
+		let result = match self.rpc_client.tx(TxHash::from_str(&tx_id.hash)?, false).await {
+			Ok(result) => result,
+			Err(e) if is_tx_not_found(&e) => {
+				log::trace!(target: "hyperspace", "Transaction {} not found: {e}", tx_id.hash);
+				return Ok(TxStatus::Pending)
+			},
+			Err(e) => return Err(e.into()),
+		};
+		if result.tx_result.code.is_err() {
+			return Ok(TxStatus::Failed(result.tx_result.log.to_string()))
+		}
+		// tendermint has instant finality and the messages of a transaction are executed
+		// atomically, so an included transaction that didn't fail is final.
+		Ok(TxStatus::Finalized { failed_messages: vec![] })
+	}
+
+	async fn query_client_message(
+		&self,
+		update: UpdateClient,
+	) -> Result<AnyClientMessage, Self::Error> {
+		let header = match update.header {
+			Some(header) => header,
+			None => {
+				// the header wasn't included in the event, find the transaction that emitted it.
+				let query = Query::eq("update_client.client_id", update.client_id().to_string())
+					.and_eq(
+						"update_client.consensus_height",
+						update.consensus_height().to_string(),
+					);
+				let tx = self
+					.rpc_client
+					.tx_search(query, false, 1, 1, Order::Ascending)
+					.await?
+					.txs
+					.into_iter()
+					.next()
+					.ok_or_else(|| {
+						Error::Custom(format!("No transaction found for update {:?}", update))
+					})?;
+				let height = self.height(tx.height.value());
+				tx.tx_result
+					.events
+					.iter()
+					.filter_map(|event| super::events::ibc_event_try_from_abci_event(event, height))
+					.find_map(|event| match event {
+						ibc::events::IbcEvent::UpdateClient(update) => update.header,
+						_ => None,
+					})
+					.ok_or_else(|| {
+						Error::Custom(format!("Update client event has no header: {:?}", update))
+					})?
+			},
+		};
+
+		let any = Any::decode(header.as_slice())?;
+		AnyClientMessage::try_from(any)
+			.map_err(|e| Error::Custom(format!("Failed to decode client message: {e:?}")))
+	}
+
+	fn error_kind(&self, error: &anyhow::Error) -> Option<ErrorKind> {
+		error.chain().find_map(|err| err.downcast_ref::<Error>()).map(Error::kind)
+	}
+}
+
+#[async_trait::async_trait]
+impl MisbehaviourHandler for CosmosClient {
+	async fn check_for_misbehaviour<C: Chain>(
+		&self,
+		counterparty: &C,
+		client_message: AnyClientMessage,
+	) -> Result<Option<MisbehaviourAlert>, anyhow::Error> {
+		match client_message {
+			AnyClientMessage::Tendermint(ClientMessage::Header(header)) => {
+				let height = header.signed_header.header.height.value();
+				let trusted_header =
+					self.rpc_client.commit(TmHeight::try_from(height)?).await?.signed_header.header;
+
+				let header_hash = header.signed_header.header.hash();
+				let trusted_header_hash = trusted_header.hash();
+				if header_hash == trusted_header_hash {
+					return Ok(None)
+				}
+				let client_id = self
+					.client_id
+					.as_ref()
+					.map(|x| x.as_str().to_owned())
+					.unwrap_or_else(|| "{unknown}".to_owned());
+				log::warn!(
+					"Found misbehaviour on client {}: {:?} != {:?}",
+					client_id,
+					header_hash,
+					trusted_header_hash
+				);
+				let alert = MisbehaviourAlert {
+					counterparty: counterparty.name().to_owned(),
+					client_id,
+					height,
+					submitted_hash: header_hash.to_string(),
+					canonical_hash: trusted_header_hash.to_string(),
+					equivocators: vec![],
+				};
+				alert.emit();
+
+				let report = async {
+					let trusted_tendermint_header =
+						self.construct_tendermint_header(height, header.trusted_height).await?;
+					let misbehaviour = ClientMessage::Misbehaviour(Misbehaviour {
+						client_id: self.client_id(),
+						header1: header,
+						header2: trusted_tendermint_header,
+					});
+
+					counterparty
+						.submit(vec![MsgUpdateAnyClient::<LocalClientTypes>::new(
+							self.client_id(),
+							AnyClientMessage::Tendermint(misbehaviour),
+							counterparty.account_id(),
+						)
+						.to_any()])
+						.map_err(|e| anyhow!("{:?}", e))
+						.await?;
+					Ok::<_, anyhow::Error>(())
+				};
+				report
+					.await
+					.map_err(|error| UnreportedMisbehaviour { alert: alert.clone(), error })?;
+				Ok(Some(alert))
+			},
+			_ => Ok(None),
+		}
+	}
+}
+
+/// Returns true if the error is the response of a node that hasn't indexed the transaction,
+/// either because it hasn't been included yet or because it was dropped.
+fn is_tx_not_found(error: &tendermint_rpc::Error) -> bool {
+	match error.detail() {
+		ErrorDetail::Response(response) =>
+			response.source.data().map(|data| data.contains("not found")).unwrap_or(false),
+		_ => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tendermint_rpc::response_error::{Code as RpcCode, ResponseError};
+
+	#[test]
+	fn only_missing_transactions_are_pending() {
+		let not_found =
+			ResponseError::new(RpcCode::InternalError, Some("tx (2A1E8B4C) not found".to_owned()));
+		assert!(is_tx_not_found(&tendermint_rpc::Error::response(not_found)));
+
+		let other = ResponseError::new(
+			RpcCode::InternalError,
+			Some(
+				"height 10 must be less than or equal to the current blockchain height 5"
+					.to_owned(),
+			),
+		);
+		assert!(!is_tx_not_found(&tendermint_rpc::Error::response(other)));
+		assert!(!is_tx_not_found(&tendermint_rpc::Error::client_internal(
+			"connection refused".to_owned()
+		)));
+	}
+}
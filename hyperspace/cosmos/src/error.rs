@@ -0,0 +1,119 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ibc::{core::ics02_client, timestamp::ParseTimestampError};
+use primitives::error::ErrorKind;
+use thiserror::Error;
+
+/// Error definition for the cosmos client
+#[derive(Error, Debug)]
+pub enum Error {
+	/// An error from the tendermint rpc interface
+	#[error("Tendermint rpc error: {0}")]
+	Rpc(#[from] tendermint_rpc::Error),
+	/// An error from the cosmos grpc interface
+	#[error("Grpc error: {0}")]
+	Grpc(#[from] tonic::Status),
+	/// Failed to establish a grpc connection
+	#[error("Grpc transport error: {0}")]
+	Transport(#[from] tonic::transport::Error),
+	/// Protobuf decoding error
+	#[error("Protobuf decoding error: {0}")]
+	Decode(#[from] prost::DecodeError),
+	/// Errors originating from tendermint types
+	#[error("Tendermint error: {0}")]
+	Tendermint(#[from] tendermint::Error),
+	/// hex error
+	#[error("Error decoding hex: {0:?}")]
+	Hex(#[from] hex::FromHexError),
+	/// Custom error
+	#[error("{0}")]
+	Custom(String),
+	/// The chain rejected a transaction, `code` is only meaningful within its `codespace`
+	#[error("Transaction {hash} failed with code {code} in codespace {codespace}: {log}")]
+	TxFailed { hash: String, codespace: String, code: u32, log: String },
+	/// Failed to rehydrate client state
+	#[error("Error decoding some value: {0}")]
+	ClientStateRehydration(String),
+	/// Failed to construct a client update header
+	#[error("Error constructing a client update header: {0}")]
+	HeaderConstruction(String),
+	/// Errors associated with ics-02 client
+	#[error("Ibc client error: {0}")]
+	IbcClient(#[from] ics02_client::error::Error),
+	/// Ics-20 errors
+	#[error("Ics-20 error: {0}")]
+	Ics20Error(#[from] ibc::applications::transfer::error::Error),
+	/// Error occured parsing timestamp
+	#[error("Timestamp error: {0}")]
+	ParseTimestamp(#[from] ParseTimestampError),
+}
+
+impl From<String> for Error {
+	fn from(error: String) -> Self {
+		Self::Custom(error)
+	}
+}
+
+/// Codespace of the errors raised by the cosmos-sdk itself, rather than by one of its modules
+const SDK_CODESPACE: &str = "sdk";
+/// Cosmos-sdk error code for an incorrect account sequence
+const ERR_WRONG_SEQUENCE: u32 = 32;
+/// Cosmos-sdk error code for a transaction that ran out of gas
+const ERR_OUT_OF_GAS: u32 = 11;
+
+impl Error {
+	/// Classifies this error for the relay loop.
+	pub fn kind(&self) -> ErrorKind {
+		match self {
+			Error::TxFailed { codespace, code, .. } => match (codespace == SDK_CODESPACE, *code) {
+				(true, ERR_WRONG_SEQUENCE) => ErrorKind::Nonce,
+				(true, ERR_OUT_OF_GAS) => ErrorKind::OutOfGas,
+				_ => ErrorKind::Rejected,
+			},
+			// simulation failures are only reported through the status message
+			Error::Grpc(status) if status.message().contains("account sequence mismatch") =>
+				ErrorKind::Nonce,
+			Error::Grpc(status) if status.message().contains("out of gas") => ErrorKind::OutOfGas,
+			Error::Grpc(status) if status.message().contains("failed to execute message") =>
+				ErrorKind::Rejected,
+			_ => ErrorKind::Transient,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn tx_failed(codespace: &str, code: u32) -> Error {
+		Error::TxFailed {
+			hash: "hash".to_string(),
+			codespace: codespace.to_string(),
+			code,
+			log: "log".to_string(),
+		}
+	}
+
+	#[test]
+	fn tx_failures_are_classified_by_codespace_and_code() {
+		assert_eq!(tx_failed("sdk", 32).kind(), ErrorKind::Nonce);
+		assert_eq!(tx_failed("sdk", 11).kind(), ErrorKind::OutOfGas);
+		assert_eq!(tx_failed("sdk", 4).kind(), ErrorKind::Rejected);
+		// codes of other codespaces have different meanings
+		assert_eq!(tx_failed("ibc", 32).kind(), ErrorKind::Rejected);
+		assert_eq!(tx_failed("ibc", 11).kind(), ErrorKind::Rejected);
+		assert_eq!(tx_failed("", 32).kind(), ErrorKind::Rejected);
+	}
+}
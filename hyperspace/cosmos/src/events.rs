@@ -0,0 +1,183 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of tendermint abci events into [`IbcEvent`]s.
+
+use ibc::{
+	core::{
+		ics02_client::events::{
+			Attributes as ClientAttributes, ClientMisbehaviour, CreateClient, UpdateClient,
+			UpgradeClient,
+		},
+		ics03_connection, ics04_channel,
+	},
+	events::{IbcEvent, IbcEventType},
+	Height,
+};
+use ibc_rpc::PacketInfo;
+use tendermint::abci::Event as AbciEvent;
+
+const CLIENT_ID_ATTRIBUTE_KEY: &str = "client_id";
+const CLIENT_TYPE_ATTRIBUTE_KEY: &str = "client_type";
+const CONSENSUS_HEIGHT_ATTRIBUTE_KEY: &str = "consensus_height";
+const HEADER_ATTRIBUTE_KEY: &str = "header";
+
+const PKT_SEQ_ATTRIBUTE_KEY: &str = "packet_sequence";
+const PKT_SRC_PORT_ATTRIBUTE_KEY: &str = "packet_src_port";
+const PKT_SRC_CHANNEL_ATTRIBUTE_KEY: &str = "packet_src_channel";
+const PKT_DST_PORT_ATTRIBUTE_KEY: &str = "packet_dst_port";
+const PKT_DST_CHANNEL_ATTRIBUTE_KEY: &str = "packet_dst_channel";
+const PKT_CHANNEL_ORDERING_ATTRIBUTE_KEY: &str = "packet_channel_ordering";
+const PKT_DATA_HEX_ATTRIBUTE_KEY: &str = "packet_data_hex";
+const PKT_TIMEOUT_HEIGHT_ATTRIBUTE_KEY: &str = "packet_timeout_height";
+const PKT_TIMEOUT_TIMESTAMP_ATTRIBUTE_KEY: &str = "packet_timeout_timestamp";
+const PKT_ACK_HEX_ATTRIBUTE_KEY: &str = "packet_ack_hex";
+
+/// Attempts to convert an abci event emitted at `height` into an [`IbcEvent`], returns `None`
+/// for events that are not emitted by the ibc module.
+pub fn ibc_event_try_from_abci_event(event: &AbciEvent, height: Height) -> Option<IbcEvent> {
+	let mut ibc_event = client_event_try_from_abci_event(event)
+		.or_else(|| ics03_connection::events::try_from_tx(event))
+		.or_else(|| ics04_channel::events::try_from_tx(event))?;
+	ibc_event.set_height(height);
+	Some(ibc_event)
+}
+
+/// Client events are not covered by the `try_from_tx` helpers in the ibc crate, so they're
+/// parsed here.
+fn client_event_try_from_abci_event(event: &AbciEvent) -> Option<IbcEvent> {
+	let event_type = event.kind.parse::<IbcEventType>().ok()?;
+	match event_type {
+		IbcEventType::CreateClient =>
+			Some(IbcEvent::CreateClient(CreateClient(client_attributes(event)?))),
+		IbcEventType::UpdateClient => {
+			let header = event
+				.attributes
+				.iter()
+				.find(|attr| attr.key == HEADER_ATTRIBUTE_KEY)
+				.and_then(|attr| hex::decode(&attr.value).ok());
+			Some(IbcEvent::UpdateClient(UpdateClient { common: client_attributes(event)?, header }))
+		},
+		IbcEventType::UpgradeClient =>
+			Some(IbcEvent::UpgradeClient(UpgradeClient(client_attributes(event)?))),
+		IbcEventType::ClientMisbehaviour =>
+			Some(IbcEvent::ClientMisbehaviour(ClientMisbehaviour(client_attributes(event)?))),
+		_ => None,
+	}
+}
+
+fn client_attributes(event: &AbciEvent) -> Option<ClientAttributes> {
+	let mut client_id = None;
+	let mut client_type = None;
+	let mut consensus_height = None;
+	for attr in &event.attributes {
+		match attr.key.as_str() {
+			CLIENT_ID_ATTRIBUTE_KEY => client_id = attr.value.parse().ok(),
+			CLIENT_TYPE_ATTRIBUTE_KEY => client_type = Some(attr.value.clone()),
+			CONSENSUS_HEIGHT_ATTRIBUTE_KEY => consensus_height = attr.value.parse().ok(),
+			_ => {},
+		}
+	}
+
+	Some(ClientAttributes {
+		height: Height::default(),
+		client_id: client_id?,
+		client_type: client_type?,
+		consensus_height: consensus_height.unwrap_or_default(),
+	})
+}
+
+/// Extracts the packet carried by a `send_packet` or `write_acknowledgement` abci event.
+/// `height` should be the height at which the packet commitment or acknowledgement can be
+/// proven.
+pub fn packet_info_from_abci_event(event: &AbciEvent, height: u64) -> Option<PacketInfo> {
+	let mut packet_info = PacketInfo {
+		height,
+		sequence: 0,
+		source_port: String::new(),
+		source_channel: String::new(),
+		destination_port: String::new(),
+		destination_channel: String::new(),
+		channel_order: String::new(),
+		data: vec![],
+		timeout_height: Default::default(),
+		timeout_timestamp: 0,
+		ack: None,
+	};
+	for attr in &event.attributes {
+		let value = attr.value.as_str();
+		match attr.key.as_str() {
+			PKT_SEQ_ATTRIBUTE_KEY => packet_info.sequence = value.parse().ok()?,
+			PKT_SRC_PORT_ATTRIBUTE_KEY => packet_info.source_port = value.to_string(),
+			PKT_SRC_CHANNEL_ATTRIBUTE_KEY => packet_info.source_channel = value.to_string(),
+			PKT_DST_PORT_ATTRIBUTE_KEY => packet_info.destination_port = value.to_string(),
+			PKT_DST_CHANNEL_ATTRIBUTE_KEY => packet_info.destination_channel = value.to_string(),
+			PKT_CHANNEL_ORDERING_ATTRIBUTE_KEY => packet_info.channel_order = value.to_string(),
+			PKT_DATA_HEX_ATTRIBUTE_KEY => packet_info.data = hex::decode(value).ok()?,
+			PKT_TIMEOUT_HEIGHT_ATTRIBUTE_KEY => {
+				let timeout_height = value.parse::<Height>().ok()?;
+				packet_info.timeout_height = timeout_height.into();
+			},
+			PKT_TIMEOUT_TIMESTAMP_ATTRIBUTE_KEY =>
+				packet_info.timeout_timestamp = value.parse().ok()?,
+			PKT_ACK_HEX_ATTRIBUTE_KEY => packet_info.ack = Some(hex::decode(value).ok()?),
+			_ => {},
+		}
+	}
+
+	Some(packet_info)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tendermint::abci::EventAttribute;
+
+	fn attribute(key: &str, value: &str) -> EventAttribute {
+		EventAttribute { key: key.to_string(), value: value.to_string(), index: true }
+	}
+
+	#[test]
+	fn parses_update_client_event() {
+		let event = AbciEvent {
+			kind: "update_client".to_string(),
+			attributes: vec![
+				attribute("client_id", "10-grandpa-0"),
+				attribute("client_type", "10-grandpa"),
+				attribute("consensus_height", "2000-45"),
+				attribute("header", "0a0b0c"),
+			],
+		};
+		let height = Height::new(1, 100);
+		let ibc_event = ibc_event_try_from_abci_event(&event, height).unwrap();
+		match ibc_event {
+			IbcEvent::UpdateClient(update) => {
+				assert_eq!(update.client_id().as_str(), "10-grandpa-0");
+				assert_eq!(update.consensus_height(), Height::new(2000, 45));
+				assert_eq!(update.height(), height);
+				assert_eq!(update.header, Some(vec![0x0a, 0x0b, 0x0c]));
+			},
+			ev => panic!("Expected update client event, found {:?}", ev),
+		}
+	}
+
+	#[test]
+	fn ignores_non_ibc_events() {
+		let event = AbciEvent {
+			kind: "transfer".to_string(),
+			attributes: vec![attribute("recipient", "cosmos1qqqq")],
+		};
+		assert!(ibc_event_try_from_abci_event(&event, Height::new(1, 1)).is_none());
+	}
+}
@@ -0,0 +1,65 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use bech32::ToBase32;
+use k256::ecdsa::{signature::Signer as _, Signature, SigningKey};
+use primitives::KeyProvider;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use super::{error::Error, CosmosClient};
+
+/// A secp256k1 key used for signing cosmos transactions.
+#[derive(Clone)]
+pub struct KeyEntry {
+	/// The signing key
+	pub signing_key: SigningKey,
+	/// Compressed public key bytes
+	pub public_key: Vec<u8>,
+	/// Bech32 encoded account address
+	pub account: String,
+}
+
+impl KeyEntry {
+	/// Constructs a [`KeyEntry`] from a hex encoded secp256k1 private key.
+	pub fn from_hex(private_key: &str, account_prefix: &str) -> Result<Self, Error> {
+		let bytes = hex::decode(private_key.trim_start_matches("0x"))?;
+		let signing_key = SigningKey::from_bytes(&bytes)
+			.map_err(|e| Error::Custom(format!("Invalid secp256k1 private key: {e}")))?;
+		let public_key = signing_key.verifying_key().to_bytes().to_vec();
+		let account = account_address(&public_key, account_prefix)?;
+		Ok(Self { signing_key, public_key, account })
+	}
+
+	/// Signs the given bytes, returning the 64 byte compact signature expected by the sdk.
+	pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+		let signature: Signature = self.signing_key.sign(message);
+		signature.as_ref().to_vec()
+	}
+}
+
+/// Derives the bech32 account address for a compressed secp256k1 public key.
+pub fn account_address(public_key: &[u8], account_prefix: &str) -> Result<String, Error> {
+	let hash = Ripemd160::digest(Sha256::digest(public_key));
+	bech32::encode(account_prefix, hash.to_base32(), bech32::Variant::Bech32)
+		.map_err(|e| Error::Custom(format!("Failed to encode account address: {e}")))
+}
+
+impl KeyProvider for CosmosClient {
+	fn account_id(&self) -> ibc::signer::Signer {
+		ibc::signer::Signer::from_str(&self.keybase.account).expect("Account Id should be valid")
+	}
+}
@@ -11,3 +11,314 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+
+#![allow(clippy::all)]
+
+use std::{str::FromStr, time::Duration};
+
+pub mod chain;
+pub mod error;
+pub mod events;
+pub mod key_provider;
+pub mod provider;
+#[cfg(any(test, feature = "testing"))]
+pub mod test_provider;
+#[cfg(test)]
+mod tests;
+pub mod tx;
+
+use error::Error;
+use ibc::{
+	core::{
+		ics02_client::trust_threshold::TrustThreshold,
+		ics23_commitment::{commitment::CommitmentRoot, specs::ProofSpecs},
+		ics24_host::identifier::{ChainId, ChannelId, ClientId, ConnectionId, PortId},
+	},
+	events::IbcEvent,
+	Height,
+};
+use ibc_proto::{
+	cosmos::staking::v1beta1::{
+		query_client::QueryClient as StakingQueryClient, QueryParamsRequest,
+	},
+	ibc::core::commitment::v1::MerkleProof as RawMerkleProof,
+};
+use ics07_tendermint::{
+	client_message::Header, client_state::ClientState as TendermintClientState,
+	consensus_state::ConsensusState as TendermintConsensusState,
+};
+use key_provider::KeyEntry;
+use pallet_ibc::light_clients::{AnyClientState, AnyConsensusState, HostFunctionsManager};
+use primitives::{filter::PacketFilter, LowBalanceState};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use tendermint::{block::Height as TmHeight, merkle::proof::Proof as TmProof, validator};
+use tendermint_rpc::{endpoint::abci_query::AbciQuery, Client, HttpClient, Paging, Url};
+use tonic::transport::{Channel, Endpoint};
+
+/// The abci query path for proven ibc store queries.
+const IBC_QUERY_PATH: &str = "store/ibc/key";
+
+/// Implements the [`crate::Chain`] trait for cosmos-sdk based chains.
+/// This is responsible for:
+/// 1. Tracking a tendermint light client on a counter-party chain, advancing this light
+/// client state as new blocks are committed.
+/// 2. Submiting new IBC messages to this chain.
+#[derive(Clone)]
+pub struct CosmosClient {
+	/// Chain name
+	pub name: String,
+	/// Tendermint rpc client
+	pub rpc_client: HttpClient,
+	/// Lazily connected grpc channel to the cosmos-sdk query and tx services
+	pub grpc_channel: Channel,
+	/// Tendermint websocket url, used for block subscriptions
+	pub websocket_url: Url,
+	/// Chain Id
+	pub chain_id: ChainId,
+	/// Light client id on counterparty chain
+	pub client_id: Option<ClientId>,
+	/// Connection Id
+	pub connection_id: Option<ConnectionId>,
+	/// ICS-23 provable store commitment prefix
+	pub commitment_prefix: Vec<u8>,
+	/// Bech32 prefix for account addresses
+	pub account_prefix: String,
+	/// Denomination used to pay transaction fees
+	pub fee_denom: String,
+	/// Fee amount paid per transaction
+	pub fee_amount: String,
+	/// Gas limit for a single transaction
+	pub gas_limit: u64,
+	/// Relayer key
+	pub keybase: KeyEntry,
+	/// Channels cleared for packet relay
+	pub channel_whitelist: Vec<(ChannelId, PortId)>,
+	/// Filter for the packets sent from this chain
+	pub packet_filter: PacketFilter,
+	/// Set while the relayer balance is below one of the configured thresholds
+	pub low_balance: LowBalanceState,
+}
+
+/// config options for [`CosmosClient`]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CosmosClientConfig {
+	/// Chain name
+	pub name: String,
+	/// Tendermint rpc url
+	pub rpc_url: String,
+	/// Cosmos-sdk grpc url
+	pub grpc_url: String,
+	/// Tendermint websocket url
+	pub websocket_url: String,
+	/// Chain Id
+	pub chain_id: String,
+	/// Light client id on counterparty chain
+	pub client_id: Option<ClientId>,
+	/// Connection Id
+	pub connection_id: Option<ConnectionId>,
+	/// Bech32 prefix for account addresses
+	pub account_prefix: String,
+	/// Denomination used to pay transaction fees
+	pub fee_denom: String,
+	/// Fee amount paid per transaction
+	pub fee_amount: String,
+	/// Gas limit for a single transaction
+	pub gas_limit: u64,
+	/// Store prefix, usually "ibc"
+	pub store_prefix: String,
+	/// Hex encoded secp256k1 private key for signing transactions
+	pub private_key: String,
+	/// Channels cleared for packet relay
+	pub channel_whitelist: Vec<(ChannelId, PortId)>,
+	/// Filter for the packets sent from this chain
+	#[serde(default)]
+	pub packet_filter: PacketFilter,
+}
+
+impl CosmosClient {
+	/// Initializes a [`CosmosClient`] given a [`CosmosClientConfig`]
+	pub async fn new(config: CosmosClientConfig) -> Result<Self, Error> {
+		let rpc_url = Url::from_str(&config.rpc_url)?;
+		let rpc_client = HttpClient::new(rpc_url)?;
+		let websocket_url = Url::from_str(&config.websocket_url)?;
+		let grpc_channel = Endpoint::from_shared(config.grpc_url.clone())
+			.map_err(|e| Error::Custom(format!("Invalid grpc url: {e}")))?
+			.connect_lazy();
+		let keybase = KeyEntry::from_hex(&config.private_key, &config.account_prefix)?;
+
+		Ok(Self {
+			name: config.name,
+			rpc_client,
+			grpc_channel,
+			websocket_url,
+			chain_id: ChainId::from_string(&config.chain_id),
+			client_id: config.client_id,
+			connection_id: config.connection_id,
+			commitment_prefix: config.store_prefix.into_bytes(),
+			account_prefix: config.account_prefix,
+			fee_denom: config.fee_denom,
+			fee_amount: config.fee_amount,
+			gas_limit: config.gas_limit,
+			keybase,
+			channel_whitelist: config.channel_whitelist,
+			packet_filter: config.packet_filter,
+			low_balance: LowBalanceState::default(),
+		})
+	}
+
+	pub fn client_id(&self) -> ClientId {
+		self.client_id.as_ref().expect("Client Id should be defined").clone()
+	}
+
+	pub fn set_client_id(&mut self, client_id: ClientId) {
+		self.client_id = Some(client_id)
+	}
+
+	/// Returns the [`Height`] of this chain for the given tendermint block number.
+	pub fn height(&self, block_number: u64) -> Height {
+		Height::new(self.chain_id.version(), block_number)
+	}
+
+	/// Performs an abci query for `key` in the ibc store.
+	///
+	/// Proofs returned by the query at height `h` are verified against the app hash in the
+	/// header at `h + 1`, so the store is queried at `at - 1` which makes the returned proof
+	/// valid at `at`.
+	pub async fn query_ibc_store(
+		&self,
+		at: Height,
+		key: Vec<u8>,
+		prove: bool,
+	) -> Result<(Vec<u8>, Vec<u8>), Error> {
+		let query_height = TmHeight::try_from(at.revision_height.saturating_sub(1))?;
+		let path = tendermint::abci::Path::from_str(IBC_QUERY_PATH)?;
+		let response: AbciQuery =
+			self.rpc_client.abci_query(Some(path), key, Some(query_height), prove).await?;
+
+		if response.code.is_err() {
+			Err(Error::Custom(format!(
+				"Abci query failed with code {}: {}",
+				response.code.value(),
+				response.log
+			)))?
+		}
+
+		let proof = if prove {
+			let proof = response
+				.proof
+				.ok_or_else(|| Error::Custom("Abci query returned an empty proof".to_string()))?;
+			convert_tm_to_raw_merkle_proof(&proof)?.encode_to_vec()
+		} else {
+			vec![]
+		};
+
+		Ok((response.value, proof))
+	}
+
+	/// Fetches the validator set at the given height.
+	pub async fn validator_set(&self, height: u64) -> Result<validator::Set, Error> {
+		let response = self.rpc_client.validators(TmHeight::try_from(height)?, Paging::All).await?;
+		Ok(validator::Set::new(response.validators, None))
+	}
+
+	/// Constructs a tendermint [`Header`] for the block at `height` that can be verified by a
+	/// client whose latest trusted height is `trusted_height`.
+	pub async fn construct_tendermint_header(
+		&self,
+		height: u64,
+		trusted_height: Height,
+	) -> Result<Header, Error> {
+		let signed_header =
+			self.rpc_client.commit(TmHeight::try_from(height)?).await?.signed_header;
+		let validator_set = self.validator_set(height).await?;
+		// the client stores the next validators hash of the trusted header
+		let trusted_validator_set = self.validator_set(trusted_height.revision_height + 1).await?;
+
+		Ok(Header { signed_header, validator_set, trusted_height, trusted_validator_set })
+	}
+
+	/// Returns all ibc events emitted in the block at the given height.
+	pub async fn query_block_ibc_events(&self, block_number: u64) -> Result<Vec<IbcEvent>, Error> {
+		let response = self.rpc_client.block_results(TmHeight::try_from(block_number)?).await?;
+		// state changes made in block `n` are committed in the app hash of block `n + 1`, so
+		// that's the height at which these events can be proven.
+		let height = self.height(block_number + 1);
+		let begin_block_events = response.begin_block_events.unwrap_or_default();
+		let end_block_events = response.end_block_events.unwrap_or_default();
+		let tx_events = response
+			.txs_results
+			.unwrap_or_default()
+			.into_iter()
+			.flat_map(|tx_result| tx_result.events);
+
+		Ok(begin_block_events
+			.into_iter()
+			.chain(tx_events)
+			.chain(end_block_events)
+			.filter_map(|event| events::ibc_event_try_from_abci_event(&event, height))
+			.collect())
+	}
+
+	/// Queries the unbonding period from the staking module.
+	pub async fn query_unbonding_period(&self) -> Result<Duration, Error> {
+		let mut client = StakingQueryClient::new(self.grpc_channel.clone());
+		let params = client
+			.params(QueryParamsRequest {})
+			.await?
+			.into_inner()
+			.params
+			.ok_or_else(|| Error::Custom("Staking params are missing".to_string()))?;
+		let unbonding_time = params
+			.unbonding_time
+			.ok_or_else(|| Error::Custom("Unbonding time is missing".to_string()))?;
+		Duration::try_from(unbonding_time)
+			.map_err(|_| Error::Custom("Received a negative unbonding time".to_string()))
+	}
+
+	/// Construct a tendermint client state to be submitted to the counterparty chain
+	pub async fn construct_tendermint_client_state(
+		&self,
+	) -> Result<(AnyClientState, AnyConsensusState), Error> {
+		let latest_block = self.rpc_client.status().await?.sync_info.latest_block_height.value();
+		let unbonding_period = self.query_unbonding_period().await?;
+		// the trusting period should be shorter than the unbonding period, the sdk recommends
+		// two thirds of it.
+		let trusting_period = unbonding_period * 2 / 3;
+		let client_state = TendermintClientState::<HostFunctionsManager>::new(
+			self.chain_id.clone(),
+			TrustThreshold::ONE_THIRD,
+			trusting_period,
+			unbonding_period,
+			Duration::from_secs(10),
+			self.height(latest_block),
+			ProofSpecs::default(),
+			vec!["upgrade".to_string(), "upgradedIBCState".to_string()],
+		)
+		.map_err(|e| Error::ClientStateRehydration(e.to_string()))?;
+
+		let signed_header =
+			self.rpc_client.commit(TmHeight::try_from(latest_block)?).await?.signed_header;
+		let consensus_state = TendermintConsensusState::new(
+			CommitmentRoot::from_bytes(signed_header.header.app_hash.as_ref()),
+			signed_header.header.time,
+			signed_header.header.next_validators_hash,
+		);
+
+		Ok((
+			AnyClientState::Tendermint(client_state),
+			AnyConsensusState::Tendermint(consensus_state),
+		))
+	}
+}
+
+/// Converts the tendermint proof ops returned by an abci query into an ics23 merkle proof.
+pub fn convert_tm_to_raw_merkle_proof(tm_proof: &TmProof) -> Result<RawMerkleProof, Error> {
+	let proofs = tm_proof
+		.ops
+		.iter()
+		.map(|op| ibc_proto::ics23::CommitmentProof::decode(op.data.as_slice()))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(RawMerkleProof { proofs })
+}
@@ -0,0 +1,864 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{error::Error, events, CosmosClient};
+use futures::{Stream, StreamExt};
+use ibc::{
+	applications::transfer::{Amount, PrefixedCoin, PrefixedDenom},
+	core::{
+		ics02_client::{client_state::ClientType, msgs::update_client::MsgUpdateAnyClient},
+		ics23_commitment::commitment::CommitmentPrefix,
+		ics24_host::{
+			identifier::{ChannelId, ClientId, ConnectionId, PortId},
+			path::{
+				AcksPath, ChannelEndsPath, ChannelUpgradeErrorsPath, ChannelUpgradesPath,
+				ClientConsensusStatePath, ClientStatePath, CommitmentsPath, ConnectionsPath,
+				ReceiptsPath, SeqRecvsPath,
+			},
+		},
+	},
+	events::IbcEvent,
+	timestamp::Timestamp,
+	tx_msg::Msg,
+	Height,
+};
+use ibc_proto::{
+	cosmos::{
+		bank::v1beta1::{query_client::QueryClient as BankQueryClient, QueryAllBalancesRequest},
+		base::query::v1beta1::PageRequest,
+	},
+	google::protobuf::Any,
+	ibc::{
+		applications::transfer::v1::{
+			query_client::QueryClient as TransferQueryClient, DenomTrace, QueryDenomTracesRequest,
+		},
+		core::{
+			channel::v1::{
+				query_client::QueryClient as ChannelQueryClient, Channel, ErrorReceipt,
+				QueryChannelResponse, QueryChannelsRequest, QueryChannelsResponse,
+				QueryConnectionChannelsRequest, QueryNextSequenceReceiveResponse,
+				QueryPacketAcknowledgementResponse, QueryPacketAcknowledgementsRequest,
+				QueryPacketCommitmentResponse, QueryPacketCommitmentsRequest,
+				QueryPacketReceiptResponse, QueryUnreceivedAcksRequest,
+				QueryUnreceivedPacketsRequest, QueryUpgradeErrorResponse, QueryUpgradeResponse,
+				Upgrade,
+			},
+			client::v1::{
+				query_client::QueryClient as ClientQueryClient, QueryClientStateResponse,
+				QueryClientStatesRequest, QueryConsensusStateResponse,
+			},
+			connection::v1::{
+				query_client::QueryClient as ConnectionQueryClient, ConnectionEnd,
+				IdentifiedConnection, QueryClientConnectionsRequest, QueryConnectionRequest,
+				QueryConnectionResponse,
+			},
+		},
+	},
+};
+use ibc_rpc::PacketInfo;
+use ics07_tendermint::{
+	client_message::ClientMessage, client_state::ClientState as TendermintClientState,
+};
+use pallet_ibc::light_clients::{
+	AnyClientMessage, AnyClientState, AnyConsensusState, HostFunctionsManager,
+};
+use primitives::{
+	error::OutdatedFinalityEvent, filter::PacketFilter, mock::LocalClientTypes,
+	query_maximum_height_for_timeout_proofs, AccountBalance, Chain, IbcProvider, UpdateType,
+};
+use prost::Message;
+use std::{pin::Pin, str::FromStr, time::Duration};
+use tendermint::{abci::transaction::Hash as TxHash, block::Height as TmHeight};
+use tendermint_proto::Protobuf;
+use tendermint_rpc::{
+	query::{EventType, Query},
+	Client, Order, SubscriptionClient, WebSocketClient,
+};
+
+/// Finality notifications for cosmos chains, every committed block is final.
+#[derive(Clone)]
+pub enum FinalityEvent {
+	Tendermint(tendermint::block::Header),
+}
+
+pub struct TransactionId {
+	/// Hex encoded transaction hash
+	pub hash: String,
+	/// Height of the block the transaction was included in
+	pub height: u64,
+}
+
+/// Builds a grpc request that is executed against the state at the given height.
+fn request_at<T>(message: T, at: Height) -> tonic::Request<T> {
+	let mut request = tonic::Request::new(message);
+	request.metadata_mut().insert(
+		"x-cosmos-block-height",
+		at.revision_height
+			.to_string()
+			.parse()
+			.expect("Block height is valid ascii; qed"),
+	);
+	request
+}
+
+/// Requests every item of a paginated grpc query in a single page.
+fn all_pages() -> Option<PageRequest> {
+	Some(PageRequest { limit: u32::MAX as u64, ..Default::default() })
+}
+
+impl CosmosClient {
+	/// Subscribes to the given query over the tendermint websocket.
+	pub(crate) async fn subscribe(
+		&self,
+		query: Query,
+	) -> Result<impl Stream<Item = tendermint_rpc::event::Event> + Send + Sync, Error> {
+		let (client, driver) = WebSocketClient::new(self.websocket_url.clone()).await?;
+		tokio::spawn(async move {
+			if let Err(e) = driver.run().await {
+				log::error!(target: "hyperspace", "Tendermint websocket driver exited: {e}");
+			}
+		});
+		let subscription = client.subscribe(query).await?;
+
+		Ok(subscription.filter_map(move |result| {
+			// the subscription is closed once the client is dropped, so keep it alive for as
+			// long as the stream is.
+			let _client = &client;
+			futures::future::ready(match result {
+				Ok(event) => Some(event),
+				Err(err) => {
+					log::error!(target: "hyperspace", "Error in tendermint subscription: {err:?}");
+					None
+				},
+			})
+		}))
+	}
+
+	/// Queries the packets carried by the events matching `query`.
+	async fn query_packets_by_event(
+		&self,
+		query: Query,
+		event_type: &str,
+	) -> Result<Vec<PacketInfo>, Error> {
+		const PER_PAGE: u8 = 100;
+		let mut packets = vec![];
+		let mut fetched = 0;
+		for page in 1.. {
+			let response = self
+				.rpc_client
+				.tx_search(query.clone(), false, page, PER_PAGE, Order::Ascending)
+				.await?;
+			let txs = response.txs.len();
+			fetched += txs;
+			packets.extend(response.txs.into_iter().flat_map(|tx| {
+				// the packet can be proven at the block after the one it was included in
+				let height = tx.height.value() + 1;
+				tx.tx_result
+					.events
+					.into_iter()
+					.filter(|event| event.kind == event_type)
+					.filter_map(|event| events::packet_info_from_abci_event(&event, height))
+					.collect::<Vec<_>>()
+			}));
+			if txs < PER_PAGE as usize || fetched >= response.total_count as usize {
+				break
+			}
+		}
+		Ok(packets)
+	}
+}
+
+#[async_trait::async_trait]
+impl IbcProvider for CosmosClient {
+	type FinalityEvent = FinalityEvent;
+	type TransactionId = TransactionId;
+	type Error = Error;
+
+	async fn query_latest_ibc_events<C>(
+		&mut self,
+		finality_event: Self::FinalityEvent,
+		counterparty: &C,
+	) -> Result<(Any, Vec<IbcEvent>, UpdateType), anyhow::Error>
+	where
+		C: Chain,
+	{
+		let FinalityEvent::Tendermint(header) = finality_event;
+		let client_id = self.client_id();
+		let latest_height = counterparty.latest_height_and_timestamp().await?.0;
+		let response = counterparty.query_client_state(latest_height, client_id.clone()).await?;
+		let client_state = response.client_state.ok_or_else(|| {
+			Error::Custom("Received an empty client state from counterparty".to_string())
+		})?;
+
+		let client_state = AnyClientState::try_from(client_state)
+			.map_err(|_| Error::Custom("Failed to decode client state".to_string()))?;
+
+		let client_state = match client_state {
+			AnyClientState::Tendermint(client_state) => client_state,
+			c => Err(Error::ClientStateRehydration(format!(
+				"Expected AnyClientState::Tendermint found: {:?}",
+				c
+			)))?,
+		};
+
+		let block_number = header.height.value();
+		let latest_client_height = client_state.latest_height.revision_height;
+		if block_number <= latest_client_height {
+			Err(OutdatedFinalityEvent {
+				height: block_number,
+				latest_height: latest_client_height,
+			})?
+		}
+
+		// events emitted in block `h` are committed to by the app hash in block `h + 1`, so the
+		// new header makes all events up to `block_number - 1` provable.
+		log::info!(
+			"Fetching events from {} for blocks {}..{}",
+			self.name,
+			latest_client_height,
+			block_number - 1,
+		);
+		let mut events = vec![];
+		for height in latest_client_height..block_number {
+			events.extend(self.query_block_ibc_events(height).await?);
+		}
+
+		let max_height_for_timeouts =
+			query_maximum_height_for_timeout_proofs(counterparty, self).await;
+		let timeout_update_required = max_height_for_timeouts
+			.map(|max_height| max_height > latest_client_height && max_height <= block_number)
+			.unwrap_or_default();
+		let is_update_required = self.is_update_required(block_number, latest_client_height);
+		let validator_set_changed = header.validators_hash != header.next_validators_hash;
+		let update_type =
+			match validator_set_changed || timeout_update_required || is_update_required {
+				true => UpdateType::Mandatory,
+				false => UpdateType::Optional,
+			};
+
+		let tendermint_header = self
+			.construct_tendermint_header(block_number, client_state.latest_height)
+			.await?;
+		let update_header = {
+			let msg = MsgUpdateAnyClient::<LocalClientTypes> {
+				client_id,
+				client_message: AnyClientMessage::Tendermint(ClientMessage::Header(
+					tendermint_header,
+				)),
+				signer: counterparty.account_id(),
+			};
+			let value = msg.encode_vec();
+			Any { value, type_url: msg.type_url() }
+		};
+
+		Ok((update_header, events, update_type))
+	}
+
+	async fn ibc_events(&self) -> Pin<Box<dyn Stream<Item = IbcEvent> + Send + 'static>> {
+		use futures::stream;
+		use tendermint_rpc::event::EventData;
+
+		let chain_id = self.chain_id.clone();
+		let stream = self
+			.subscribe(EventType::Tx.into())
+			.await
+			.expect("Failed to subscribe to events")
+			.filter_map(move |event| {
+				let tx_result = match event.data {
+					EventData::Tx { tx_result } => tx_result,
+					_ => return futures::future::ready(None),
+				};
+				let height = Height::new(chain_id.version(), tx_result.height as u64 + 1);
+				let events = tx_result
+					.result
+					.events
+					.iter()
+					.filter_map(|event| events::ibc_event_try_from_abci_event(event, height))
+					.collect::<Vec<_>>();
+				futures::future::ready(Some(stream::iter(events)))
+			})
+			.flatten();
+		Box::pin(stream)
+	}
+
+	async fn query_client_consensus(
+		&self,
+		at: Height,
+		client_id: ClientId,
+		consensus_height: Height,
+	) -> Result<QueryConsensusStateResponse, Self::Error> {
+		let path = ClientConsensusStatePath {
+			client_id,
+			epoch: consensus_height.revision_number,
+			height: consensus_height.revision_height,
+		};
+		let (value, proof) = self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+		if value.is_empty() {
+			Err(Error::Custom(format!("Consensus state not found for {path}")))?
+		}
+
+		Ok(QueryConsensusStateResponse {
+			consensus_state: Some(Any::decode(value.as_slice())?),
+			proof,
+			proof_height: Some(at.into()),
+		})
+	}
+
+	async fn query_client_state(
+		&self,
+		at: Height,
+		client_id: ClientId,
+	) -> Result<QueryClientStateResponse, Self::Error> {
+		let path = ClientStatePath(client_id);
+		let (value, proof) = self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+		if value.is_empty() {
+			Err(Error::Custom(format!("Client state not found for {path}")))?
+		}
+
+		Ok(QueryClientStateResponse {
+			client_state: Some(Any::decode(value.as_slice())?),
+			proof,
+			proof_height: Some(at.into()),
+		})
+	}
+
+	async fn query_upgraded_client_state(
+		&self,
+		_at: Height,
+	) -> Result<QueryClientStateResponse, Self::Error> {
+		// The tendermint light client can't verify upgrade proofs yet, so there is no
+		// counterparty that could accept them.
+		Err(Error::Custom("Client upgrades are not supported for cosmos chains".to_string()))
+	}
+
+	async fn query_upgraded_consensus_state(
+		&self,
+		_at: Height,
+	) -> Result<QueryConsensusStateResponse, Self::Error> {
+		Err(Error::Custom("Client upgrades are not supported for cosmos chains".to_string()))
+	}
+
+	async fn client_upgrades(&self) -> Pin<Box<dyn Stream<Item = ()> + Send + 'static>> {
+		// no cosmos client upgrade can be relayed (see above), so there's nothing to watch for.
+		Box::pin(futures::stream::pending())
+	}
+
+	async fn query_connection_end(
+		&self,
+		at: Height,
+		connection_id: ConnectionId,
+	) -> Result<QueryConnectionResponse, Self::Error> {
+		let path = ConnectionsPath(connection_id);
+		let (value, proof) = self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+		let connection =
+			if value.is_empty() { None } else { Some(ConnectionEnd::decode(value.as_slice())?) };
+
+		Ok(QueryConnectionResponse { connection, proof, proof_height: Some(at.into()) })
+	}
+
+	async fn query_channel_end(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryChannelResponse, Self::Error> {
+		let path = ChannelEndsPath(port_id, channel_id);
+		let (value, proof) = self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+		let channel =
+			if value.is_empty() { None } else { Some(Channel::decode(value.as_slice())?) };
+
+		Ok(QueryChannelResponse { channel, proof, proof_height: Some(at.into()) })
+	}
+
+	async fn query_channel_upgrade(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryUpgradeResponse, Self::Error> {
+		let path = ChannelUpgradesPath(port_id, channel_id);
+		let (value, proof) = self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+		let upgrade =
+			if value.is_empty() { None } else { Some(Upgrade::decode(value.as_slice())?) };
+
+		Ok(QueryUpgradeResponse { upgrade, proof, proof_height: Some(at.into()) })
+	}
+
+	async fn query_channel_upgrade_error(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryUpgradeErrorResponse, Self::Error> {
+		let path = ChannelUpgradeErrorsPath(port_id, channel_id);
+		let (value, proof) = self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+		let error_receipt =
+			if value.is_empty() { None } else { Some(ErrorReceipt::decode(value.as_slice())?) };
+
+		Ok(QueryUpgradeErrorResponse { error_receipt, proof, proof_height: Some(at.into()) })
+	}
+
+	async fn query_proof(&self, at: Height, keys: Vec<Vec<u8>>) -> Result<Vec<u8>, Self::Error> {
+		let key = keys
+			.into_iter()
+			.next()
+			.ok_or_else(|| Error::Custom("No key provided for proof query".to_string()))?;
+		// keys are prefixed with the commitment prefix which is already implied by the store
+		// we're querying.
+		let key = key.strip_prefix(self.commitment_prefix.as_slice()).unwrap_or(&key).to_vec();
+		let (_, proof) = self.query_ibc_store(at, key, true).await?;
+		Ok(proof)
+	}
+
+	async fn query_packet_commitment(
+		&self,
+		at: Height,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		seq: u64,
+	) -> Result<QueryPacketCommitmentResponse, Self::Error> {
+		let path = CommitmentsPath {
+			port_id: port_id.clone(),
+			channel_id: *channel_id,
+			sequence: seq.into(),
+		};
+		let (commitment, proof) =
+			self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+
+		Ok(QueryPacketCommitmentResponse { commitment, proof, proof_height: Some(at.into()) })
+	}
+
+	async fn query_packet_acknowledgement(
+		&self,
+		at: Height,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		seq: u64,
+	) -> Result<QueryPacketAcknowledgementResponse, Self::Error> {
+		let path =
+			AcksPath { port_id: port_id.clone(), channel_id: *channel_id, sequence: seq.into() };
+		let (acknowledgement, proof) =
+			self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+
+		Ok(QueryPacketAcknowledgementResponse {
+			acknowledgement,
+			proof,
+			proof_height: Some(at.into()),
+		})
+	}
+
+	async fn query_next_sequence_recv(
+		&self,
+		at: Height,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Result<QueryNextSequenceReceiveResponse, Self::Error> {
+		let path = SeqRecvsPath(port_id.clone(), *channel_id);
+		let (value, proof) = self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+		// sequences are stored as big endian encoded u64s
+		let bytes: [u8; 8] = value.as_slice().try_into().map_err(|_| {
+			Error::Custom(format!("Invalid next sequence receive value for {path}"))
+		})?;
+
+		Ok(QueryNextSequenceReceiveResponse {
+			next_sequence_receive: u64::from_be_bytes(bytes),
+			proof,
+			proof_height: Some(at.into()),
+		})
+	}
+
+	async fn query_packet_receipt(
+		&self,
+		at: Height,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		seq: u64,
+	) -> Result<QueryPacketReceiptResponse, Self::Error> {
+		let path = ReceiptsPath {
+			port_id: port_id.clone(),
+			channel_id: *channel_id,
+			sequence: seq.into(),
+		};
+		let (value, proof) = self.query_ibc_store(at, path.to_string().into_bytes(), true).await?;
+
+		Ok(QueryPacketReceiptResponse {
+			received: !value.is_empty(),
+			proof,
+			proof_height: Some(at.into()),
+		})
+	}
+
+	async fn latest_height_and_timestamp(&self) -> Result<(Height, Timestamp), Self::Error> {
+		let sync_info = self.rpc_client.status().await?.sync_info;
+		let height = self.height(sync_info.latest_block_height.value());
+		Ok((height, sync_info.latest_block_time.into()))
+	}
+
+	async fn query_packet_commitments(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<Vec<u64>, Self::Error> {
+		let mut client = ChannelQueryClient::new(self.grpc_channel.clone());
+		let request = QueryPacketCommitmentsRequest {
+			port_id: port_id.to_string(),
+			channel_id: channel_id.to_string(),
+			pagination: all_pages(),
+		};
+		let response = client.packet_commitments(request_at(request, at)).await?.into_inner();
+		Ok(response
+			.commitments
+			.into_iter()
+			.map(|packet_state| packet_state.sequence)
+			.collect())
+	}
+
+	async fn query_packet_acknowledgements(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<Vec<u64>, Self::Error> {
+		let mut client = ChannelQueryClient::new(self.grpc_channel.clone());
+		let request = QueryPacketAcknowledgementsRequest {
+			port_id: port_id.to_string(),
+			channel_id: channel_id.to_string(),
+			pagination: all_pages(),
+			packet_commitment_sequences: vec![],
+		};
+		let response = client.packet_acknowledgements(request_at(request, at)).await?.into_inner();
+		Ok(response
+			.acknowledgements
+			.into_iter()
+			.map(|packet_state| packet_state.sequence)
+			.collect())
+	}
+
+	async fn query_unreceived_packets(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+		seqs: Vec<u64>,
+	) -> Result<Vec<u64>, Self::Error> {
+		let mut client = ChannelQueryClient::new(self.grpc_channel.clone());
+		let request = QueryUnreceivedPacketsRequest {
+			port_id: port_id.to_string(),
+			channel_id: channel_id.to_string(),
+			packet_commitment_sequences: seqs,
+		};
+		let response = client.unreceived_packets(request_at(request, at)).await?.into_inner();
+		Ok(response.sequences)
+	}
+
+	async fn query_unreceived_acknowledgements(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+		seqs: Vec<u64>,
+	) -> Result<Vec<u64>, Self::Error> {
+		let mut client = ChannelQueryClient::new(self.grpc_channel.clone());
+		let request = QueryUnreceivedAcksRequest {
+			port_id: port_id.to_string(),
+			channel_id: channel_id.to_string(),
+			packet_ack_sequences: seqs,
+		};
+		let response = client.unreceived_acks(request_at(request, at)).await?.into_inner();
+		Ok(response.sequences)
+	}
+
+	fn channel_whitelist(&self) -> Vec<(ChannelId, PortId)> {
+		self.channel_whitelist.clone()
+	}
+
+	fn packet_filter(&self) -> PacketFilter {
+		self.packet_filter.clone()
+	}
+
+	async fn query_connection_channels(
+		&self,
+		at: Height,
+		connection_id: &ConnectionId,
+	) -> Result<QueryChannelsResponse, Self::Error> {
+		let mut client = ChannelQueryClient::new(self.grpc_channel.clone());
+		let request = QueryConnectionChannelsRequest {
+			connection: connection_id.to_string(),
+			pagination: all_pages(),
+		};
+		let response = client.connection_channels(request_at(request, at)).await?.into_inner();
+		Ok(QueryChannelsResponse {
+			channels: response.channels,
+			pagination: response.pagination,
+			height: response.height,
+		})
+	}
+
+	async fn query_send_packets(
+		&self,
+		channel_id: ChannelId,
+		port_id: PortId,
+		seqs: Vec<u64>,
+	) -> Result<Vec<PacketInfo>, Self::Error> {
+		let mut packets = vec![];
+		for seq in seqs {
+			let query = Query::eq("send_packet.packet_src_channel", channel_id.to_string())
+				.and_eq("send_packet.packet_src_port", port_id.to_string())
+				.and_eq("send_packet.packet_sequence", seq.to_string());
+			packets.extend(
+				self.query_packets_by_event(query, "send_packet")
+					.await?
+					.into_iter()
+					.filter(|packet| packet.sequence == seq),
+			);
+		}
+		Ok(packets)
+	}
+
+	async fn query_recv_packets(
+		&self,
+		channel_id: ChannelId,
+		port_id: PortId,
+		seqs: Vec<u64>,
+	) -> Result<Vec<PacketInfo>, Self::Error> {
+		let mut packets = vec![];
+		for seq in seqs {
+			let query =
+				Query::eq("write_acknowledgement.packet_dst_channel", channel_id.to_string())
+					.and_eq("write_acknowledgement.packet_dst_port", port_id.to_string())
+					.and_eq("write_acknowledgement.packet_sequence", seq.to_string());
+			packets.extend(
+				self.query_packets_by_event(query, "write_acknowledgement")
+					.await?
+					.into_iter()
+					.filter(|packet| packet.sequence == seq),
+			);
+		}
+		Ok(packets)
+	}
+
+	fn expected_block_time(&self) -> Duration {
+		// Cosmos chains have an expected block time of 6 seconds
+		Duration::from_secs(6)
+	}
+
+	async fn query_client_update_time_and_height(
+		&self,
+		client_id: ClientId,
+		client_height: Height,
+	) -> Result<(Height, Timestamp), Self::Error> {
+		let mut txs = vec![];
+		for event_type in ["update_client", "create_client"] {
+			let query = Query::eq(format!("{event_type}.client_id"), client_id.to_string())
+				.and_eq(format!("{event_type}.consensus_height"), client_height.to_string());
+			txs = self.rpc_client.tx_search(query, false, 1, 1, Order::Ascending).await?.txs;
+			if !txs.is_empty() {
+				break
+			}
+		}
+		let tx = txs.into_iter().next().ok_or_else(|| {
+			Error::Custom(format!(
+				"Could not find update for client {client_id} at height {client_height}"
+			))
+		})?;
+		let block_number = tx.height.value();
+		let timestamp = self.query_timestamp_at(block_number).await?;
+
+		Ok((self.height(block_number), Timestamp::from_nanoseconds(timestamp)?))
+	}
+
+	async fn query_host_consensus_state_proof(
+		&self,
+		_height: Height,
+	) -> Result<Option<Vec<u8>>, Self::Error> {
+		// tendermint light clients can verify the host consensus state without a proof
+		Ok(None)
+	}
+
+	async fn query_ibc_balance(&self) -> Result<Vec<PrefixedCoin>, Self::Error> {
+		let mut client = BankQueryClient::new(self.grpc_channel.clone());
+		let response = client
+			.all_balances(QueryAllBalancesRequest {
+				address: self.keybase.account.clone(),
+				pagination: all_pages(),
+			})
+			.await?
+			.into_inner();
+
+		response
+			.balances
+			.into_iter()
+			.map(|coin| {
+				Ok(PrefixedCoin {
+					denom: PrefixedDenom::from_str(&coin.denom)?,
+					amount: Amount::from_str(&coin.amount)?,
+				})
+			})
+			.collect()
+	}
+
+	async fn query_relayer_balances(&self) -> Result<Vec<AccountBalance>, Self::Error> {
+		// the bank balances include the native denoms along with the ibc ones
+		let balances = self.query_ibc_balance().await?;
+		Ok(vec![AccountBalance { account: self.keybase.account.clone(), balances }])
+	}
+
+	async fn query_denom_traces(&self) -> Result<Vec<DenomTrace>, Self::Error> {
+		let mut client = TransferQueryClient::new(self.grpc_channel.clone());
+		let response = client
+			.denom_traces(QueryDenomTracesRequest { pagination: all_pages() })
+			.await?
+			.into_inner();
+		Ok(response.denom_traces)
+	}
+
+	fn connection_prefix(&self) -> CommitmentPrefix {
+		CommitmentPrefix::try_from(self.commitment_prefix.clone()).expect("Should not fail")
+	}
+
+	fn client_id(&self) -> ClientId {
+		self.client_id()
+	}
+
+	fn connection_id(&self) -> ConnectionId {
+		self.connection_id.as_ref().expect("Connection id should be defined").clone()
+	}
+
+	fn client_type(&self) -> ClientType {
+		TendermintClientState::<HostFunctionsManager>::client_type()
+	}
+
+	async fn query_timestamp_at(&self, block_number: u64) -> Result<u64, Self::Error> {
+		let header = self.rpc_client.commit(TmHeight::try_from(block_number)?).await?;
+		let timestamp: Timestamp = header.signed_header.header.time.into();
+		Ok(timestamp.nanoseconds())
+	}
+
+	async fn query_clients(&self) -> Result<Vec<ClientId>, Self::Error> {
+		let mut client = ClientQueryClient::new(self.grpc_channel.clone());
+		let response = client
+			.client_states(QueryClientStatesRequest { pagination: all_pages() })
+			.await?
+			.into_inner();
+		response
+			.client_states
+			.into_iter()
+			.map(|client| {
+				ClientId::from_str(&client.client_id)
+					.map_err(|_| Error::Custom("Invalid client id ".to_string()))
+			})
+			.collect()
+	}
+
+	async fn query_channels(&self) -> Result<Vec<(ChannelId, PortId)>, Self::Error> {
+		let mut client = ChannelQueryClient::new(self.grpc_channel.clone());
+		let response = client
+			.channels(QueryChannelsRequest { pagination: all_pages() })
+			.await?
+			.into_inner();
+		response
+			.channels
+			.into_iter()
+			.map(|identified_chan| {
+				Ok((
+					ChannelId::from_str(&identified_chan.channel_id).map_err(|e| {
+						Error::Custom(format!(
+							"Invalid channel id {}: {e}",
+							identified_chan.channel_id
+						))
+					})?,
+					PortId::from_str(&identified_chan.port_id).map_err(|e| {
+						Error::Custom(format!("Invalid port id {}: {e}", identified_chan.port_id))
+					})?,
+				))
+			})
+			.collect::<Result<Vec<_>, _>>()
+	}
+
+	async fn query_connection_using_client(
+		&self,
+		height: u32,
+		client_id: String,
+	) -> Result<Vec<IdentifiedConnection>, Self::Error> {
+		let at = self.height(height as u64);
+		let mut client = ConnectionQueryClient::new(self.grpc_channel.clone());
+		let connection_ids = client
+			.client_connections(request_at(QueryClientConnectionsRequest { client_id }, at))
+			.await?
+			.into_inner()
+			.connection_paths;
+
+		let mut connections = vec![];
+		for connection_id in connection_ids {
+			let connection = client
+				.connection(request_at(
+					QueryConnectionRequest { connection_id: connection_id.clone() },
+					at,
+				))
+				.await?
+				.into_inner()
+				.connection;
+			if let Some(connection) = connection {
+				connections.push(IdentifiedConnection {
+					id: connection_id,
+					client_id: connection.client_id,
+					versions: connection.versions,
+					state: connection.state,
+					counterparty: connection.counterparty,
+					delay_period: connection.delay_period,
+				});
+			}
+		}
+
+		Ok(connections)
+	}
+
+	fn is_update_required(
+		&self,
+		latest_height: u64,
+		latest_client_height_on_counterparty: u64,
+	) -> bool {
+		let refresh_period: u64 = if cfg!(feature = "testing") { 15 } else { 50 };
+		latest_height - latest_client_height_on_counterparty >= refresh_period
+	}
+
+	async fn initialize_client_state(
+		&self,
+	) -> Result<(AnyClientState, AnyConsensusState), Self::Error> {
+		self.construct_tendermint_client_state().await
+	}
+
+	async fn query_client_id_from_tx_hash(
+		&self,
+		tx_id: Self::TransactionId,
+	) -> Result<ClientId, Self::Error> {
+		let response = self.rpc_client.tx(TxHash::from_str(&tx_id.hash)?, false).await?;
+		let height = self.height(tx_id.height);
+		let client_id = response
+			.tx_result
+			.events
+			.iter()
+			.filter_map(|event| events::ibc_event_try_from_abci_event(event, height))
+			.find_map(|event| match event {
+				IbcEvent::CreateClient(create_client) => Some(create_client.client_id().clone()),
+				_ => None,
+			})
+			.ok_or_else(|| {
+				Error::Custom(format!("No client was created in transaction {}", tx_id.hash))
+			})?;
+
+		Ok(client_id)
+	}
+}
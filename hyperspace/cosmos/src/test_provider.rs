@@ -0,0 +1,81 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{error::Error, CosmosClient};
+use futures::{Stream, StreamExt};
+use ibc::{
+	applications::transfer::{msgs::transfer::MsgTransfer, PrefixedCoin},
+	core::ics24_host::identifier::{ChannelId, PortId},
+};
+use ibc_proto::{
+	cosmos::base::v1beta1::Coin, google::protobuf::Any,
+	ibc::applications::transfer::v1::MsgTransfer as RawMsgTransfer,
+};
+use pallet_ibc::Timeout;
+use primitives::{Chain, TestProvider};
+use prost::Message;
+use std::pin::Pin;
+use tendermint_rpc::{event::EventData, query::EventType};
+
+const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+#[async_trait::async_trait]
+impl TestProvider for CosmosClient {
+	async fn send_transfer(&self, transfer: MsgTransfer<PrefixedCoin>) -> Result<(), Self::Error> {
+		let msg = RawMsgTransfer {
+			source_port: transfer.source_port.to_string(),
+			source_channel: transfer.source_channel.to_string(),
+			token: Some(Coin {
+				denom: transfer.token.denom.to_string(),
+				amount: transfer.token.amount.to_string(),
+			}),
+			sender: transfer.sender.to_string(),
+			receiver: transfer.receiver.to_string(),
+			timeout_height: Some(transfer.timeout_height.into()),
+			timeout_timestamp: transfer.timeout_timestamp.nanoseconds(),
+			memo: transfer.memo,
+		};
+		let any = Any { type_url: MSG_TRANSFER_TYPE_URL.to_string(), value: msg.encode_to_vec() };
+		self.submit(vec![any]).await?;
+		Ok(())
+	}
+
+	async fn send_ordered_packet(
+		&self,
+		_channel_id: ChannelId,
+		_timeout: Timeout,
+	) -> Result<(), Self::Error> {
+		Err(Error::Custom("Ordered packets are not supported on cosmos chains".to_string()))
+	}
+
+	async fn subscribe_blocks(&self) -> Pin<Box<dyn Stream<Item = u64> + Send + Sync>> {
+		let stream = self
+			.subscribe(EventType::NewBlock.into())
+			.await
+			.expect("Failed to subscribe to new blocks")
+			.filter_map(|event| {
+				futures::future::ready(match event.data {
+					EventData::NewBlock { block: Some(block), .. } =>
+						Some(block.header.height.value()),
+					_ => None,
+				})
+			});
+
+		Box::pin(stream)
+	}
+
+	fn set_channel_whitelist(&mut self, channel_whitelist: Vec<(ChannelId, PortId)>) {
+		self.channel_whitelist = channel_whitelist;
+	}
+}
@@ -0,0 +1,174 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for [`CosmosClient`] against a mock tendermint rpc server.
+
+use crate::{CosmosClient, CosmosClientConfig};
+use hyper::{
+	service::{make_service_fn, service_fn},
+	Body, Request, Response, Server,
+};
+use ibc::{
+	core::ics24_host::identifier::{ChannelId, PortId},
+	Height,
+};
+use ibc_proto::ibc::core::channel::v1::{Channel, Counterparty};
+use primitives::IbcProvider;
+use prost::Message;
+use serde_json::{json, Value};
+use std::{convert::Infallible, net::SocketAddr};
+
+const LATEST_HEIGHT: u64 = 42;
+const LATEST_BLOCK_TIME_SECS: u64 = 1664582400;
+
+fn channel_end() -> Channel {
+	Channel {
+		state: 3,
+		ordering: 1,
+		counterparty: Some(Counterparty {
+			port_id: "transfer".to_string(),
+			channel_id: "channel-0".to_string(),
+		}),
+		connection_hops: vec!["connection-0".to_string()],
+		version: "ics20-1".to_string(),
+	}
+}
+
+fn to_base64(bytes: &[u8]) -> String {
+	base64::encode(bytes)
+}
+
+fn status() -> Value {
+	json!({
+		"node_info": {
+			"protocol_version": { "p2p": "8", "block": "11", "app": "0" },
+			"id": "a".repeat(40),
+			"listen_addr": "tcp://0.0.0.0:26656",
+			"network": "test-1",
+			"version": "0.34.21",
+			"channels": "40202122233038606100",
+			"moniker": "mock",
+			"other": { "tx_index": "on", "rpc_address": "tcp://0.0.0.0:26657" }
+		},
+		"sync_info": {
+			"latest_block_hash": "A".repeat(64),
+			"latest_app_hash": "B".repeat(64),
+			"latest_block_height": LATEST_HEIGHT.to_string(),
+			"latest_block_time": "2022-10-01T00:00:00Z",
+			"earliest_block_hash": "C".repeat(64),
+			"earliest_app_hash": "D".repeat(64),
+			"earliest_block_height": "1",
+			"earliest_block_time": "2022-09-01T00:00:00Z",
+			"catching_up": false
+		},
+		"validator_info": {
+			"address": "E".repeat(40),
+			"pub_key": {
+				"type": "tendermint/PubKeyEd25519",
+				"value": to_base64(&[1u8; 32])
+			},
+			"voting_power": "10"
+		}
+	})
+}
+
+fn abci_query(params: &Value) -> Value {
+	// proofs for height `h` are queried from the state at `h - 1`, only serve the channel at the
+	// expected query height.
+	let value = if params["height"].as_str() == Some(&(LATEST_HEIGHT - 1).to_string()) {
+		channel_end().encode_to_vec()
+	} else {
+		vec![]
+	};
+	let commitment_proof = ibc_proto::ics23::CommitmentProof { proof: None }.encode_to_vec();
+
+	json!({
+		"response": {
+			"code": 0,
+			"log": "",
+			"info": "",
+			"index": "0",
+			"key": to_base64(&hex::decode(params["data"].as_str().unwrap()).unwrap()),
+			"value": to_base64(&value),
+			"proofOps": {
+				"ops": [{ "type": "ics23:iavl", "key": "", "data": to_base64(&commitment_proof) }]
+			},
+			"height": (LATEST_HEIGHT - 1).to_string(),
+			"codespace": ""
+		}
+	})
+}
+
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+	let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+	let request: Value = serde_json::from_slice(&body).unwrap();
+	let result = match request["method"].as_str().unwrap() {
+		"status" => status(),
+		"abci_query" => abci_query(&request["params"]),
+		method => panic!("Unexpected rpc method: {method}"),
+	};
+	let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+	Ok(Response::new(Body::from(response.to_string())))
+}
+
+fn spawn_mock_rpc() -> SocketAddr {
+	let service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+	let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(service);
+	let addr = server.local_addr();
+	tokio::spawn(server);
+	addr
+}
+
+async fn mock_client() -> CosmosClient {
+	let addr = spawn_mock_rpc();
+	let config = CosmosClientConfig {
+		name: "mock".to_string(),
+		rpc_url: format!("http://{addr}"),
+		grpc_url: "http://127.0.0.1:9090".to_string(),
+		websocket_url: format!("ws://{addr}/websocket"),
+		chain_id: "test-1".to_string(),
+		client_id: None,
+		connection_id: None,
+		account_prefix: "cosmos".to_string(),
+		fee_denom: "stake".to_string(),
+		fee_amount: "4000".to_string(),
+		gas_limit: 400_000,
+		store_prefix: "ibc".to_string(),
+		private_key: "01".repeat(32),
+		channel_whitelist: vec![],
+		packet_filter: Default::default(),
+	};
+	CosmosClient::new(config).await.unwrap()
+}
+
+#[tokio::test]
+async fn queries_latest_height_and_timestamp() {
+	let client = mock_client().await;
+	let (height, timestamp) = client.latest_height_and_timestamp().await.unwrap();
+	assert_eq!(height, Height::new(1, LATEST_HEIGHT));
+	assert_eq!(timestamp.nanoseconds(), LATEST_BLOCK_TIME_SECS * 1_000_000_000);
+}
+
+#[tokio::test]
+async fn queries_channel_end_with_proof() {
+	let client = mock_client().await;
+	let at = Height::new(1, LATEST_HEIGHT);
+	let response = client
+		.query_channel_end(at, ChannelId::new(0), PortId::transfer())
+		.await
+		.unwrap();
+	assert_eq!(response.channel, Some(channel_end()));
+	assert_eq!(response.proof_height, Some(at.into()));
+	assert!(!response.proof.is_empty());
+}
@@ -0,0 +1,162 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Construction, signing and broadcast of `cosmos.tx.v1beta1` transactions.
+
+use std::{str::FromStr, time::Duration};
+
+use ibc_proto::{
+	cosmos::{
+		auth::v1beta1::{
+			query_client::QueryClient as AuthQueryClient, BaseAccount, QueryAccountRequest,
+		},
+		base::{abci::v1beta1::TxResponse, v1beta1::Coin},
+		tx::{
+			signing::v1beta1::SignMode,
+			v1beta1::{
+				mode_info::{Single, Sum},
+				service_client::ServiceClient,
+				AuthInfo, BroadcastMode, BroadcastTxRequest, Fee, ModeInfo, SignDoc, SignerInfo,
+				TxBody, TxRaw,
+			},
+		},
+	},
+	google::protobuf::Any,
+};
+use prost::Message;
+use tendermint::abci::transaction::Hash as TxHash;
+use tendermint_rpc::{endpoint::tx::Response as TxResult, Client};
+
+use super::{error::Error, CosmosClient};
+
+const SECP256K1_PUB_KEY_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+
+/// How many times we poll for a broadcast transaction before giving up.
+const TX_INCLUSION_ATTEMPTS: u32 = 60;
+
+/// Protobuf representation of a secp256k1 public key, `cosmos.crypto.secp256k1.PubKey`.
+#[derive(Clone, PartialEq, Message)]
+pub struct Secp256k1PubKey {
+	#[prost(bytes = "vec", tag = "1")]
+	pub key: Vec<u8>,
+}
+
+impl CosmosClient {
+	/// Queries the account number and sequence of the relayer account.
+	pub async fn query_account(&self) -> Result<BaseAccount, Error> {
+		let mut client = AuthQueryClient::new(self.grpc_channel.clone());
+		let account = client
+			.account(QueryAccountRequest { address: self.keybase.account.clone() })
+			.await?
+			.into_inner()
+			.account
+			.ok_or_else(|| {
+				Error::Custom(format!("Account {} does not exist", self.keybase.account))
+			})?;
+
+		Ok(BaseAccount::decode(account.value.as_slice())?)
+	}
+
+	/// Builds and signs a transaction containing the given messages.
+	pub fn sign_tx(&self, messages: Vec<Any>, account: &BaseAccount) -> Result<TxRaw, Error> {
+		let body = TxBody {
+			messages,
+			memo: String::new(),
+			timeout_height: 0,
+			extension_options: vec![],
+			non_critical_extension_options: vec![],
+		};
+		let public_key = Any {
+			type_url: SECP256K1_PUB_KEY_TYPE_URL.to_string(),
+			value: Secp256k1PubKey { key: self.keybase.public_key.clone() }.encode_to_vec(),
+		};
+		let signer_info = SignerInfo {
+			public_key: Some(public_key),
+			mode_info: Some(ModeInfo {
+				sum: Some(Sum::Single(Single { mode: SignMode::Direct as i32 })),
+			}),
+			sequence: account.sequence,
+		};
+		let fee = Fee {
+			amount: vec![Coin { denom: self.fee_denom.clone(), amount: self.fee_amount.clone() }],
+			gas_limit: self.gas_limit,
+			payer: String::new(),
+			granter: String::new(),
+		};
+		let auth_info = AuthInfo { signer_infos: vec![signer_info], fee: Some(fee) };
+
+		let body_bytes = body.encode_to_vec();
+		let auth_info_bytes = auth_info.encode_to_vec();
+		let sign_doc = SignDoc {
+			body_bytes: body_bytes.clone(),
+			auth_info_bytes: auth_info_bytes.clone(),
+			chain_id: self.chain_id.to_string(),
+			account_number: account.account_number,
+		};
+		let signature = self.keybase.sign(&sign_doc.encode_to_vec());
+
+		Ok(TxRaw { body_bytes, auth_info_bytes, signatures: vec![signature] })
+	}
+
+	/// Broadcasts a signed transaction, returning once it has passed `CheckTx`.
+	pub async fn broadcast_tx(&self, tx: TxRaw) -> Result<TxResponse, Error> {
+		let mut client = ServiceClient::new(self.grpc_channel.clone());
+		let response = client
+			.broadcast_tx(BroadcastTxRequest {
+				tx_bytes: tx.encode_to_vec(),
+				mode: BroadcastMode::Sync as i32,
+			})
+			.await?
+			.into_inner()
+			.tx_response
+			.ok_or_else(|| Error::Custom("Broadcast returned an empty response".to_string()))?;
+
+		if response.code != 0 {
+			Err(Error::TxFailed {
+				hash: response.txhash.clone(),
+				codespace: response.codespace.clone(),
+				code: response.code,
+				log: response.raw_log.clone(),
+			})?
+		}
+
+		Ok(response)
+	}
+
+	/// Polls the node until the transaction with the given hash is included in a block.
+	pub async fn wait_for_tx(&self, hash: &str) -> Result<TxResult, Error> {
+		let tx_hash = TxHash::from_str(hash)?;
+		for _ in 0..TX_INCLUSION_ATTEMPTS {
+			match self.rpc_client.tx(tx_hash, false).await {
+				Ok(result) => {
+					if result.tx_result.code.is_err() {
+						Err(Error::TxFailed {
+							hash: hash.to_string(),
+							codespace: result.tx_result.codespace.to_string(),
+							code: result.tx_result.code.value(),
+							log: result.tx_result.log.to_string(),
+						})?
+					}
+					return Ok(result)
+				},
+				Err(e) => {
+					log::trace!(target: "hyperspace", "Transaction {hash} not yet included: {e}");
+					tokio::time::sleep(Duration::from_secs(1)).await;
+				},
+			}
+		}
+
+		Err(Error::Custom(format!("Timed out waiting for transaction {hash} to be included")))
+	}
+}
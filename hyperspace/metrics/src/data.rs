@@ -88,6 +88,95 @@ impl LightClientMetrics {
 	}
 }
 
+#[derive(Clone)]
+pub struct FishermanMetrics {
+	/// Total number of client updates checked for misbehaviour.
+	pub number_of_checked_client_updates: Counter<U64>,
+	/// Total number of misbehaviours found.
+	pub number_of_misbehaviours: Counter<U64>,
+	/// Total number of client updates that couldn't be checked.
+	pub number_of_failed_checks: Counter<U64>,
+}
+
+impl FishermanMetrics {
+	pub fn register(prefix: &str, registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			number_of_checked_client_updates: register(
+				Counter::new(
+					format!("hyperspace_{}_number_of_checked_client_updates", prefix),
+					"Total number of client updates checked for misbehaviour",
+				)?,
+				registry,
+			)?,
+			number_of_misbehaviours: register(
+				Counter::new(
+					format!("hyperspace_{}_number_of_misbehaviours", prefix),
+					"Total number of misbehaviours found",
+				)?,
+				registry,
+			)?,
+			number_of_failed_checks: register(
+				Counter::new(
+					format!("hyperspace_{}_number_of_failed_misbehaviour_checks", prefix),
+					"Total number of client updates that couldn't be checked for misbehaviour",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+#[derive(Clone)]
+pub struct BalanceMetrics {
+	/// Balance of the relayer accounts, per account and denomination.
+	pub relayer_balance: GaugeVec<F64>,
+	/// Whether the balance of a relayer account is below one of the configured thresholds.
+	pub relayer_balance_below_threshold: Gauge<U64>,
+}
+
+impl BalanceMetrics {
+	pub fn register(prefix: &str, registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			relayer_balance: register(
+				GaugeVec::new(
+					Opts::new(
+						format!("hyperspace_{}_relayer_balance", prefix),
+						"Balance of the relayer accounts, per account and denomination",
+					),
+					&["account", "denom"],
+				)?,
+				registry,
+			)?,
+			relayer_balance_below_threshold: register(
+				Gauge::new(
+					format!("hyperspace_{}_relayer_balance_below_threshold", prefix),
+					"Whether the balance of a relayer account is below one of the configured thresholds",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+pub struct ClientMetrics {
+	/// Time left before the light client of the counterparty expires, in seconds.
+	pub time_until_client_expiry: Gauge<F64>,
+}
+
+impl ClientMetrics {
+	pub fn register(prefix: &str, registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			time_until_client_expiry: register(
+				Gauge::new(
+					format!("hyperspace_{}_time_until_client_expiry", prefix),
+					"Time left before the light client of the counterparty expires, in seconds",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
 #[derive(Clone)]
 pub struct Metrics {
 	/// Total number of "send packet" events received.
@@ -119,6 +208,20 @@ pub struct Metrics {
 	pub gas_cost_for_sent_tx_bundle: Histogram,
 	/// Transaction length (in bytes) for every sent tx bundle.
 	pub transaction_length_for_sent_tx_bundle: Histogram,
+	/// Total number of tx bundles that were successfully submitted.
+	pub number_of_successful_tx_bundles: Counter<U64>,
+	/// Total number of tx bundles that failed to be submitted.
+	pub number_of_failed_tx_bundles: Counter<U64>,
+	/// Total number of messages that were dropped after failing on their own.
+	pub number_of_failed_messages: Counter<U64>,
+	/// Total number of submitted tx bundles that were finalized.
+	pub number_of_finalized_tx_bundles: Counter<U64>,
+	/// Total number of submitted tx bundles that failed on chain as a whole.
+	pub number_of_reverted_tx_bundles: Counter<U64>,
+	/// Total number of submitted tx bundles that weren't included or finalized in time.
+	pub number_of_timed_out_tx_bundles: Counter<U64>,
+	/// Total number of messages of finalized tx bundles that failed to be executed.
+	pub number_of_failed_delivered_messages: Counter<U64>,
 
 	/// Light client height.
 	pub light_client_height: HashMap<ClientId, LightClientMetrics>,
@@ -230,6 +333,55 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			number_of_successful_tx_bundles: register(
+				Counter::new(
+					&format!("hyperspace_{}_number_of_successful_tx_bundles", prefix),
+					"Total number of tx bundles that were successfully submitted",
+				)?,
+				registry,
+			)?,
+			number_of_failed_tx_bundles: register(
+				Counter::new(
+					&format!("hyperspace_{}_number_of_failed_tx_bundles", prefix),
+					"Total number of tx bundles that failed to be submitted",
+				)?,
+				registry,
+			)?,
+			number_of_failed_messages: register(
+				Counter::new(
+					&format!("hyperspace_{}_number_of_failed_messages", prefix),
+					"Total number of messages that were dropped after failing on their own",
+				)?,
+				registry,
+			)?,
+			number_of_finalized_tx_bundles: register(
+				Counter::new(
+					&format!("hyperspace_{}_number_of_finalized_tx_bundles", prefix),
+					"Total number of submitted tx bundles that were finalized",
+				)?,
+				registry,
+			)?,
+			number_of_reverted_tx_bundles: register(
+				Counter::new(
+					&format!("hyperspace_{}_number_of_reverted_tx_bundles", prefix),
+					"Total number of submitted tx bundles that failed on chain as a whole",
+				)?,
+				registry,
+			)?,
+			number_of_timed_out_tx_bundles: register(
+				Counter::new(
+					&format!("hyperspace_{}_number_of_timed_out_tx_bundles", prefix),
+					"Total number of submitted tx bundles that weren't included or finalized in time",
+				)?,
+				registry,
+			)?,
+			number_of_failed_delivered_messages: register(
+				Counter::new(
+					&format!("hyperspace_{}_number_of_failed_delivered_messages", prefix),
+					"Total number of messages of finalized tx bundles that failed to be executed",
+				)?,
+				registry,
+			)?,
 			light_client_height: HashMap::new(),
 			send_packet_event_time: register(
 				Histogram::with_opts(
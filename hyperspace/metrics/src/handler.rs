@@ -191,6 +191,31 @@ impl MetricsHandler {
 		self.metrics.transaction_length_for_sent_tx_bundle.observe(batch_size as f64);
 	}
 
+	pub async fn handle_bundle_result(&self, succeeded: bool) {
+		if succeeded {
+			self.metrics.number_of_successful_tx_bundles.inc();
+		} else {
+			self.metrics.number_of_failed_tx_bundles.inc();
+		}
+	}
+
+	pub async fn handle_failed_messages(&self, messages: &[Any]) {
+		self.metrics.number_of_failed_messages.inc_by(messages.len() as u64);
+	}
+
+	pub async fn handle_finalized_bundle(&self, failed_messages: usize) {
+		self.metrics.number_of_finalized_tx_bundles.inc();
+		self.metrics.number_of_failed_delivered_messages.inc_by(failed_messages as u64);
+	}
+
+	pub async fn handle_reverted_bundle(&self) {
+		self.metrics.number_of_reverted_tx_bundles.inc();
+	}
+
+	pub async fn handle_timed_out_bundle(&self) {
+		self.metrics.number_of_timed_out_tx_bundles.inc();
+	}
+
 	pub fn observe_last_packet_time(
 		&self,
 		packet: &Packet,
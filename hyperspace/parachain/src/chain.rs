@@ -16,7 +16,6 @@ use anyhow::anyhow;
 use codec::{Decode, Encode};
 use std::{collections::BTreeMap, fmt::Display, pin::Pin, time::Duration};
 
-use beefy_gadget_rpc::BeefyApiClient;
 use finality_grandpa::BlockNumberOps;
 use futures::{Stream, StreamExt, TryFutureExt};
 use grandpa_light_client_primitives::{FinalityProof, ParachainHeaderProofs};
@@ -24,7 +23,7 @@ use ibc_proto::google::protobuf::Any;
 use sp_runtime::{
 	generic::Era,
 	traits::{Header as HeaderT, IdentifyAccount, One, Verify},
-	MultiSignature, MultiSigner,
+	ApplyExtrinsicResult, MultiSignature, MultiSigner,
 };
 #[cfg(feature = "dali")]
 use subxt::tx::{
@@ -37,20 +36,35 @@ use subxt::tx::{
 	PlainTip as Tip, PolkadotExtrinsicParamsBuilder as ParachainExtrinsicsParamsBuilder,
 };
 
+use frame_rpc_system::SystemApiClient;
 use transaction_payment_rpc::TransactionPaymentApiClient;
 use transaction_payment_runtime_api::RuntimeDispatchInfo;
 
-use primitives::{Chain, IbcProvider, MisbehaviourHandler};
+use primitives::{
+	error::ErrorKind,
+	misbehaviour::{MisbehaviourAlert, UnreportedMisbehaviour},
+	Chain, IbcProvider, LowBalanceState, MisbehaviourHandler, SimulationResult, TxStatus,
+};
 
-use super::{error::Error, signer::ExtrinsicSigner, ParachainClient};
+use super::{
+	error::Error,
+	misbehaviour::{find_equivocations, query_grandpa_set_id},
+	signer::ExtrinsicSigner,
+	ParachainClient,
+};
 use crate::{
 	config,
 	parachain::{
 		api,
-		api::runtime_types::{frame_system::Phase, pallet_ibc::Any as RawAny},
+		api::runtime_types::{
+			frame_system::Phase,
+			pallet_ibc::{errors::IbcError, Any as RawAny},
+		},
 		UncheckedExtrinsic,
 	},
 	provider::TransactionId,
+	reconnect,
+	sampling::{self, FinalitySampler, FinalitySampling},
 	utils::MetadataIbcEventWrapper,
 	FinalityProtocol,
 };
@@ -69,19 +83,20 @@ use ibc::{
 use ics10_grandpa::client_message::{ClientMessage, Misbehaviour, RelayChainHeader};
 use pallet_ibc::light_clients::AnyClientMessage;
 use primitives::mock::LocalClientTypes;
-use sp_core::{twox_128, H256};
+use sp_core::{crypto::AccountId32, twox_128, H256};
 use tokio::time::sleep;
 
-type GrandpaJustification = grandpa_light_client_primitives::justification::GrandpaJustification<
-	polkadot_core_primitives::Header,
->;
+pub(crate) type GrandpaJustification =
+	grandpa_light_client_primitives::justification::GrandpaJustification<
+		polkadot_core_primitives::Header,
+	>;
 
-type BeefyJustification =
+pub(crate) type BeefyJustification =
 	beefy_primitives::SignedCommitment<u32, beefy_primitives::crypto::Signature>;
 
 /// An encoded justification proving that the given header has been finalized
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
-struct JustificationNotification(sp_core::Bytes);
+pub(crate) struct JustificationNotification(pub sp_core::Bytes);
 
 #[async_trait::async_trait]
 impl<T: config::Config + Send + Sync> Chain for ParachainClient<T>
@@ -114,11 +129,7 @@ where
 	async fn estimate_weight(&self, messages: Vec<Any>) -> Result<u64, Self::Error> {
 		let extrinsic = {
 			// todo: put this in utils
-			let signer = ExtrinsicSigner::<T, Self>::new(
-				self.key_store.clone(),
-				self.key_type_id.clone(),
-				self.public_key.clone(),
-			);
+			let signer = ExtrinsicSigner::<T, Self>::new(self.key_source.clone());
 
 			let messages = messages
 				.into_iter()
@@ -127,13 +138,16 @@ where
 
 			let tx_params = ParachainExtrinsicsParamsBuilder::new()
 				.tip(Tip::new(100_000))
-				.era(Era::Immortal, self.para_client.genesis_hash());
+				.era(Era::Immortal, self.para_client().genesis_hash());
 			let call = api::tx().ibc().deliver(messages);
-			self.para_client.tx().create_signed(&call, &signer, tx_params.into()).await?
+			let extrinsic =
+				self.para_client().tx().create_signed(&call, &signer, tx_params.into()).await?;
+			signer.check()?;
+			extrinsic
 		};
 		let dispatch_info =
 			TransactionPaymentApiClient::<sp_core::H256, RuntimeDispatchInfo<u128>>::query_info(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				extrinsic.encoded().to_vec().into(),
 				None,
 			)
@@ -142,30 +156,66 @@ where
 		Ok(dispatch_info.weight)
 	}
 
+	async fn simulate(&self, messages: Vec<Any>) -> Result<SimulationResult, Self::Error> {
+		let extrinsic = {
+			let signer = ExtrinsicSigner::<T, Self>::new(self.key_source.clone());
+
+			let messages = messages
+				.into_iter()
+				.map(|msg| RawAny { type_url: msg.type_url.as_bytes().to_vec(), value: msg.value })
+				.collect::<Vec<_>>();
+
+			// use the same params as an actual submission, so that the nonce is checked as well.
+			let other_params = T::custom_extrinsic_params(&self.para_client()).await?;
+			let call = api::tx().ibc().deliver(messages);
+			let extrinsic =
+				self.para_client().tx().create_signed(&call, &signer, other_params).await?;
+			signer.check()?;
+			extrinsic
+		};
+		let encoded = extrinsic.encoded().to_vec();
+		let dispatch_info =
+			TransactionPaymentApiClient::<sp_core::H256, RuntimeDispatchInfo<u128>>::query_info(
+				&*self.para_ws_client(),
+				encoded.clone().into(),
+				None,
+			)
+			.await
+			.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		let outcome = SystemApiClient::<sp_core::H256, AccountId32, u32>::dry_run(
+			&*self.para_ws_client(),
+			encoded.into(),
+			None,
+		)
+		.await
+		.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		let error = match ApplyExtrinsicResult::decode(&mut &*outcome.0)? {
+			Ok(Ok(())) => None,
+			Ok(Err(dispatch_error)) => Some(format!("{dispatch_error:?}")),
+			Err(validity_error) => Some(format!("{validity_error:?}")),
+		};
+
+		Ok(SimulationResult {
+			weight: dispatch_info.weight,
+			fee: Some(dispatch_info.partial_fee),
+			error,
+		})
+	}
+
 	async fn finality_notifications(
 		&self,
 	) -> Pin<Box<dyn Stream<Item = <Self as IbcProvider>::FinalityEvent> + Send + Sync>> {
-		match self.finality_protocol {
-			FinalityProtocol::Grandpa => {
-				let subscription =
-					GrandpaApiClient::<JustificationNotification, sp_core::H256, u32>::subscribe_justifications(
-						&*self.relay_ws_client,
-					)
-						.await
-						.expect("Failed to subscribe to grandpa justifications")
-						.chunks(6)
-						.map(|mut notifs| notifs.remove(notifs.len() - 1)); // skip every 4 finality notifications
-
-				let stream = subscription.filter_map(|justification_notif| {
-					let encoded_justification = match justification_notif {
-						Ok(JustificationNotification(sp_core::Bytes(justification))) =>
-							justification,
-						Err(err) => {
-							log::error!("Failed to fetch Justification: {}", err);
-							return futures::future::ready(None)
-						},
-					};
-
+		let justifications = reconnect::justifications(
+			self.relay_ws_client(),
+			self.relay_chain_rpc_urls.clone(),
+			self.reconnect.clone(),
+			self.finality_protocol.clone(),
+		);
+		let events: Pin<Box<dyn Stream<Item = Self::FinalityEvent> + Send>> = match self
+			.finality_protocol
+		{
+			FinalityProtocol::Grandpa =>
+				Box::pin(justifications.filter_map(|encoded_justification| {
 					let justification =
 						match GrandpaJustification::decode(&mut &*encoded_justification) {
 							Ok(j) => j,
@@ -175,41 +225,31 @@ where
 							},
 						};
 					futures::future::ready(Some(Self::FinalityEvent::Grandpa(justification)))
-				});
-
-				Box::pin(Box::new(stream))
-			},
-			FinalityProtocol::Beefy => {
-				let subscription =
-					BeefyApiClient::<JustificationNotification, sp_core::H256>::subscribe_justifications(
-						&*self.relay_ws_client,
-					)
-						.await
-						.expect("Failed to subscribe to beefy justifications");
-
-				let stream = subscription.filter_map(|commitment_notification| {
-					let encoded_commitment = match commitment_notification {
-						Ok(JustificationNotification(sp_core::Bytes(commitment))) => commitment,
-						Err(err) => {
-							log::error!("Failed to fetch Commitment: {}", err);
-							return futures::future::ready(None)
-						},
-					};
-
-					let signed_commitment =
-						match BeefyJustification::decode(&mut &*encoded_commitment) {
-							Ok(c) => c,
-							Err(err) => {
-								log::error!("SignedCommitment scale decode error: {}", err);
-								return futures::future::ready(None)
-							},
-						};
-					futures::future::ready(Some(Self::FinalityEvent::Beefy(signed_commitment)))
-				});
+				})),
+			FinalityProtocol::Beefy => Box::pin(justifications.filter_map(|encoded_commitment| {
+				let signed_commitment = match BeefyJustification::decode(&mut &*encoded_commitment)
+				{
+					Ok(c) => c,
+					Err(err) => {
+						log::error!("SignedCommitment scale decode error: {}", err);
+						return futures::future::ready(None)
+					},
+				};
+				futures::future::ready(Some(Self::FinalityEvent::Beefy(signed_commitment)))
+			})),
+		};
 
-				Box::pin(Box::new(stream))
-			},
-		}
+		let policy = self
+			.finality_sampling
+			.clone()
+			.unwrap_or_else(|| FinalitySampling::default_for(&self.finality_protocol));
+		let sampler = FinalitySampler::new(
+			policy,
+			self.relay_rpc.clone(),
+			self.para_rpc.clone(),
+			self.para_id,
+		);
+		Box::pin(sampling::sample(events, sampler))
 	}
 
 	async fn submit(&self, messages: Vec<Any>) -> Result<Self::TransactionId, Error> {
@@ -224,6 +264,88 @@ where
 		Ok(TransactionId { ext_hash, block_hash })
 	}
 
+	fn low_balance(&self) -> &LowBalanceState {
+		&self.low_balance
+	}
+
+	async fn query_tx_status(&self, tx_id: &Self::TransactionId) -> Result<TxStatus, Error> {
+		use api::runtime_types::{
+			frame_system::{pallet::Event as SystemEvent, EventRecord},
+			pallet_ibc::pallet::Event as PalletEvent,
+		};
+
+		#[cfg(feature = "dali")]
+		use api::runtime_types::dali_runtime::Event;
+		#[cfg(not(feature = "dali"))]
+		use api::runtime_types::parachain_runtime::Event;
+
+		let header = match self.para_client().rpc().header(Some(tx_id.block_hash)).await? {
+			Some(header) => header,
+			None => return Ok(TxStatus::Pending),
+		};
+		let finalized_hash = self.para_client().rpc().finalized_head().await?;
+		let finalized_header = self
+			.para_client()
+			.rpc()
+			.header(Some(finalized_hash))
+			.await?
+			.ok_or_else(|| Error::from("Finalized header not found".to_owned()))?;
+		let number = u32::from(*header.number());
+		if number > u32::from(*finalized_header.number()) {
+			return Ok(TxStatus::Included)
+		}
+		// the block is final, unless it was on a fork that has been retracted
+		let canonical_hash =
+			self.para_client().rpc().block_hash(Some(u64::from(number).into())).await?;
+		if canonical_hash != Some(tx_id.block_hash) {
+			return Ok(TxStatus::Pending)
+		}
+
+		let block =
+			self.para_client().rpc().block(Some(tx_id.block_hash)).await?.ok_or_else(|| {
+				Error::from(format!("Block not found for hash {:?}", tx_id.block_hash))
+			})?;
+		let ext_hash = H256::from(tx_id.ext_hash);
+		let extrinsic_index = block
+			.block
+			.extrinsics
+			.iter()
+			.position(|extrinsic| H256(sp_core::blake2_256(&extrinsic.encode())) == ext_hash)
+			.ok_or_else(|| {
+				Error::from(format!("Extrinsic {:?} not found in its block", ext_hash))
+			})? as u32;
+
+		let mut storage_key = twox_128(b"System").to_vec();
+		storage_key.extend(twox_128(b"Events").to_vec());
+		let event_bytes = self
+			.para_client()
+			.rpc()
+			.storage(&*storage_key, Some(tx_id.block_hash))
+			.await?
+			.map(|e| e.0)
+			.ok_or_else(|| Error::from("No events found".to_owned()))?;
+		let events: Vec<EventRecord<Event, H256>> = Decode::decode(&mut &*event_bytes)
+			.map_err(|e| Error::from(format!("Failed to decode events: {:?}", e)))?;
+
+		let mut failed_messages = vec![];
+		for record in events {
+			if !matches!(record.phase, Phase::ApplyExtrinsic(i) if i == extrinsic_index) {
+				continue
+			}
+			match record.event {
+				Event::System(SystemEvent::ExtrinsicFailed { dispatch_error, .. }) =>
+					return Ok(TxStatus::Failed(format!("{:?}", dispatch_error))),
+				// pallet-ibc reports the messages that failed in its events rather than failing
+				// the extrinsic.
+				Event::Ibc(PalletEvent::Events { events }) => failed_messages.extend(
+					events.into_iter().filter_map(|event| event.err()).map(ibc_error_message),
+				),
+				_ => {},
+			}
+		}
+		Ok(TxStatus::Finalized { failed_messages })
+	}
+
 	async fn query_client_message(&self, update: UpdateClient) -> Result<AnyClientMessage, Error> {
 		use api::runtime_types::{
 			frame_system::EventRecord,
@@ -241,7 +363,7 @@ where
 		let now = std::time::Instant::now();
 		let block_hash = loop {
 			let maybe_hash = self
-				.para_client
+				.para_client()
 				.rpc()
 				.block_hash(Some(host_height.revision_height.into()))
 				.await?;
@@ -260,7 +382,7 @@ where
 		storage_key.extend(twox_128(b"Events").to_vec());
 
 		let event_bytes = self
-			.para_client
+			.para_client()
 			.rpc()
 			.storage(&*storage_key, Some(block_hash))
 			.await?
@@ -299,7 +421,7 @@ where
 			.ok_or_else(|| Error::from("No update client event found".to_owned()))?;
 
 		let block = self
-			.para_client
+			.para_client()
 			.rpc()
 			.block(Some(block_hash.into()))
 			.await?
@@ -332,6 +454,10 @@ where
 
 		Err(Error::from("No client message found".to_owned()))
 	}
+
+	fn error_kind(&self, error: &anyhow::Error) -> Option<ErrorKind> {
+		error.chain().find_map(|err| err.downcast_ref::<Error>()).map(Error::kind)
+	}
 }
 
 #[async_trait::async_trait]
@@ -358,7 +484,7 @@ where
 		&self,
 		counterparty: &C,
 		client_message: AnyClientMessage,
-	) -> Result<(), anyhow::Error> {
+	) -> Result<Option<MisbehaviourAlert>, anyhow::Error> {
 		match client_message {
 			AnyClientMessage::Grandpa(ClientMessage::Header(header)) => {
 				let base_header = header
@@ -368,8 +494,49 @@ where
 					.min_by_key(|h| h.number)
 					.expect("unknown_headers always contain at least one header; qed");
 
+				// cross-check every header the counterparty received against our own view of the
+				// relay chain, headers we haven't seen yet can't be proven to conflict.
+				let mut unknown_headers =
+					header.finality_proof.unknown_headers.iter().collect::<Vec<_>>();
+				unknown_headers.sort_by_key(|h| h.number);
+				let mut conflict = None;
+				for unknown_header in unknown_headers {
+					let canonical_hash = self
+						.relay_client()
+						.rpc()
+						.block_hash(Some(unknown_header.number.into()))
+						.await?;
+					match canonical_hash.map(H256::from) {
+						Some(canonical_hash) if canonical_hash != unknown_header.hash() => {
+							conflict = Some((
+								unknown_header.number,
+								unknown_header.hash(),
+								canonical_hash,
+							));
+							break
+						},
+						_ => {},
+					}
+				}
+				let (relay_block_number, submitted_hash, canonical_hash) = match conflict {
+					Some(conflict) => conflict,
+					None => return Ok(None),
+				};
+				let client_id = self
+					.client_id
+					.as_ref()
+					.map(|x| x.as_str().to_owned())
+					.unwrap_or_else(|| "{unknown}".to_owned());
+				log::warn!(
+					"Found misbehaviour on client {}: {:?} != {:?} at relay chain block {}",
+					client_id,
+					submitted_hash,
+					canonical_hash,
+					relay_block_number
+				);
+
 				let common_ancestor_header = self
-					.relay_client
+					.relay_client()
 					.rpc()
 					.header(Some(base_header.parent_hash.into()))
 					.await?
@@ -380,7 +547,7 @@ where
 				let common_ancestor_block_number = u32::from(*common_ancestor_header.number());
 				let encoded =
 					GrandpaApiClient::<JustificationNotification, H256, u32>::prove_finality(
-						&*self.relay_ws_client,
+						&*self.relay_ws_client(),
 						common_ancestor_block_number,
 					)
 					.await?
@@ -399,65 +566,81 @@ where
 				let to_block = trusted_justification.commit.target_number;
 				let from_block = (common_ancestor_block_number + 1).min(to_block);
 
-				let trusted_base_header_hash = self
-					.relay_client
-					.rpc()
-					.block_hash(Some(from_block.into()))
-					.await?
-					.ok_or_else(|| anyhow!("No hash found for block: {:?}", from_block))?;
-
-				let base_header_hash = base_header.hash();
-				if base_header_hash != trusted_base_header_hash.into() {
-					log::warn!(
-						"Found misbehaviour on client {}: {:?} != {:?}",
-						self.client_id
-							.as_ref()
-							.map(|x| x.as_str().to_owned())
-							.unwrap_or_else(|| "{unknown}".to_owned()),
-						base_header_hash,
-						trusted_base_header_hash
-					);
-
-					trusted_finality_proof.unknown_headers.clear();
-					for i in from_block..=to_block {
-						let unknown_header_hash =
-							self.relay_client.rpc().block_hash(Some(i.into())).await?.ok_or_else(
-								|| {
-									anyhow!(
-										"No block hash found for block number: {:?}",
-										common_ancestor_block_number
-									)
-								},
-							)?;
-						let unknown_header = self
-							.relay_client
-							.rpc()
-							.header(Some(unknown_header_hash))
-							.await?
-							.ok_or_else(|| {
-								anyhow!("No header found for hash: {:?}", unknown_header_hash)
-							})?;
-						trusted_finality_proof.unknown_headers.push(unknown_header.into());
-					}
+				trusted_finality_proof.unknown_headers.clear();
+				for i in from_block..=to_block {
+					let unknown_header_hash =
+						self.relay_client().rpc().block_hash(Some(i.into())).await?.ok_or_else(
+							|| anyhow!("No block hash found for block number: {:?}", i),
+						)?;
+					let unknown_header = self
+						.relay_client()
+						.rpc()
+						.header(Some(unknown_header_hash))
+						.await?
+						.ok_or_else(|| {
+							anyhow!("No header found for hash: {:?}", unknown_header_hash)
+						})?;
+					trusted_finality_proof.unknown_headers.push(unknown_header.into());
+				}
 
-					let misbehaviour = ClientMessage::Misbehaviour(Misbehaviour {
-						first_finality_proof: header.finality_proof,
-						second_finality_proof: trusted_finality_proof,
-					});
+				// the authorities that signed both conflicting justifications are the ones at fault
+				let submitted_justification =
+					GrandpaJustification::decode(&mut &*header.finality_proof.justification)?;
+				let equivocations = match query_grandpa_set_id(counterparty, self.client_id()).await
+				{
+					Ok(set_id) =>
+						find_equivocations(set_id, &submitted_justification, &trusted_justification),
+					Err(err) => {
+						log::warn!("Failed to query the authority set id of {client_id}: {err:?}");
+						vec![]
+					},
+				};
+				let alert = MisbehaviourAlert {
+					counterparty: counterparty.name().to_owned(),
+					client_id,
+					height: relay_block_number.into(),
+					submitted_hash: format!("{submitted_hash:?}"),
+					canonical_hash: format!("{canonical_hash:?}"),
+					equivocators: equivocations
+						.iter()
+						.map(|equivocation| format!("{:?}", equivocation.offender()))
+						.collect(),
+				};
+				alert.emit();
 
-					counterparty
-						.submit(vec![MsgUpdateAnyClient::<LocalClientTypes>::new(
-							self.client_id(),
-							AnyClientMessage::Grandpa(misbehaviour.clone()),
-							counterparty.account_id(),
-						)
-						.to_any()])
-						.map_err(|e| anyhow!("Failed to submit misbehaviour report: {:?}", e))
-						.await?;
-				}
+				let misbehaviour = ClientMessage::Misbehaviour(Misbehaviour {
+					first_finality_proof: header.finality_proof,
+					second_finality_proof: trusted_finality_proof,
+				});
+
+				counterparty
+					.submit(vec![MsgUpdateAnyClient::<LocalClientTypes>::new(
+						self.client_id(),
+						AnyClientMessage::Grandpa(misbehaviour.clone()),
+						counterparty.account_id(),
+					)
+					.to_any()])
+					.map_err(|e| UnreportedMisbehaviour {
+						alert: alert.clone(),
+						error: anyhow!("{:?}", e),
+					})
+					.await?;
+				Ok(Some(alert))
 			},
-			_ => {},
+			_ => Ok(None),
 		}
-		Ok(())
 	}
 }
+
+/// Formats an error that pallet-ibc reported for one of the messages of an extrinsic.
+fn ibc_error_message(error: IbcError) -> String {
+	let (kind, message) = match error {
+		IbcError::Ics02Client { message } => ("Ics02Client", message),
+		IbcError::Ics03Connection { message } => ("Ics03Connection", message),
+		IbcError::Ics04Channel { message } => ("Ics04Channel", message),
+		IbcError::Ics20FungibleTokenTransfer { message } => ("Ics20FungibleTokenTransfer", message),
+		IbcError::UnknownMessageTypeUrl { message } => ("UnknownMessageTypeUrl", message),
+		IbcError::MalformedMessageBytes { message } => ("MalformedMessageBytes", message),
+	};
+	format!("{}: {}", kind, String::from_utf8_lossy(&message))
+}
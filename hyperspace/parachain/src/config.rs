@@ -23,7 +23,7 @@ pub type CustomExtrinsicParams<T> = <<T as subxt::Config>::ExtrinsicParams as Ex
 /// This allows end users of this crate return the correct extrinsic metadata required by their
 /// runtimes into the transactions signed by this crate.
 #[async_trait]
-pub trait Config: subxt::Config + Sized {
+pub trait Config: subxt::Config + Sized + Send + Sync {
 	/// Asset Id type used by the parachain runtime
 	type AssetId: codec::Codec + serde::Serialize + Send + Sync + 'static;
 	/// use the subxt client to fetch any neccessary data needed for the extrinsic metadata.
@@ -13,10 +13,12 @@
 // limitations under the License.
 
 use ibc::{core::ics02_client, timestamp::ParseTimestampError};
-use sp_runtime::traits::BlakeTwo256;
+use jsonrpsee::types::error::CallError;
+use primitives::error::ErrorKind;
+use sp_runtime::{traits::BlakeTwo256, transaction_validity::InvalidTransaction};
 use sp_trie::TrieError;
 use std::num::ParseIntError;
-use subxt::error::MetadataError;
+use subxt::error::{DispatchError, MetadataError, RpcError};
 use thiserror::Error;
 
 /// Error definition for the parachain client
@@ -87,3 +89,103 @@ impl From<String> for Error {
 		Self::Custom(error)
 	}
 }
+
+/// Error code of the `author` rpc for a transaction the pool found invalid, the reason is given
+/// in the error data
+const POOL_INVALID_TX: i32 = 1010;
+/// Error code of the `author` rpc for a transaction replacing one with the same nonce in the pool
+/// without a higher priority
+const POOL_TOO_LOW_PRIORITY: i32 = 1014;
+
+impl Error {
+	/// Classifies this error for the relay loop.
+	pub fn kind(&self) -> ErrorKind {
+		match self {
+			Error::PalletNotFound(_) | Error::CallNotFound(_) | Error::MetadataError(_) =>
+				ErrorKind::Fatal,
+			Error::Subxt(subxt::Error::Metadata(_)) => ErrorKind::Fatal,
+			// dispatch errors raised by the ibc pallet while handling the messages
+			Error::Subxt(subxt::Error::Runtime(DispatchError::Module(_))) => ErrorKind::Rejected,
+			Error::Subxt(subxt::Error::Rpc(err)) | Error::SubxtRRpc(err) => rpc_error_kind(err),
+			Error::JosnrpseeError(err) => jsonrpsee_error_kind(err),
+			_ => ErrorKind::Transient,
+		}
+	}
+}
+
+fn rpc_error_kind(err: &RpcError) -> ErrorKind {
+	match err {
+		RpcError::ClientError(err) => match err.downcast_ref::<jsonrpsee::core::Error>() {
+			Some(err) => jsonrpsee_error_kind(err),
+			None => ErrorKind::Transient,
+		},
+		_ => ErrorKind::Transient,
+	}
+}
+
+/// Classifies the transaction pool errors returned when submitting an extrinsic.
+fn jsonrpsee_error_kind(err: &jsonrpsee::core::Error) -> ErrorKind {
+	let error = match err {
+		jsonrpsee::core::Error::Call(CallError::Custom(error)) => error,
+		_ => return ErrorKind::Transient,
+	};
+	match error.code() {
+		POOL_TOO_LOW_PRIORITY => ErrorKind::Nonce,
+		POOL_INVALID_TX => {
+			let reason =
+				error.data().and_then(|data| serde_json::from_str::<String>(data.get()).ok());
+			match reason.as_deref() {
+				Some(reason) if reason == <&str>::from(InvalidTransaction::Stale) =>
+					ErrorKind::Nonce,
+				Some(reason) if reason == <&str>::from(InvalidTransaction::ExhaustsResources) =>
+					ErrorKind::OutOfGas,
+				_ => ErrorKind::Transient,
+			}
+		},
+		_ => ErrorKind::Transient,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpsee::types::ErrorObject;
+
+	fn pool_error(code: i32, reason: Option<&str>) -> jsonrpsee::core::Error {
+		CallError::Custom(ErrorObject::owned(code, "Invalid Transaction", reason)).into()
+	}
+
+	#[test]
+	fn pool_errors_are_classified_by_code_and_reason() {
+		let kind =
+			|err| Error::Subxt(subxt::Error::Rpc(RpcError::ClientError(Box::new(err)))).kind();
+		assert_eq!(kind(pool_error(POOL_TOO_LOW_PRIORITY, None)), ErrorKind::Nonce);
+		assert_eq!(
+			kind(pool_error(POOL_INVALID_TX, Some("Transaction is outdated"))),
+			ErrorKind::Nonce
+		);
+		assert_eq!(
+			kind(pool_error(POOL_INVALID_TX, Some("Transaction would exhaust the block limits"))),
+			ErrorKind::OutOfGas
+		);
+		assert_eq!(
+			kind(pool_error(
+				POOL_INVALID_TX,
+				Some("Inability to pay some fees (e.g. account balance too low)")
+			)),
+			ErrorKind::Transient
+		);
+		// the reason only matters for invalid transactions
+		assert_eq!(kind(pool_error(1012, Some("Transaction is outdated"))), ErrorKind::Transient);
+
+		let err = Error::JosnrpseeError(pool_error(POOL_TOO_LOW_PRIORITY, None));
+		assert_eq!(err.kind(), ErrorKind::Nonce);
+	}
+
+	#[test]
+	fn error_messages_are_not_classified() {
+		let err = Error::Custom("Priority is too low: Module(ModuleError".to_string());
+		assert_eq!(err.kind(), ErrorKind::Transient);
+		assert_eq!(Error::PalletNotFound("Ibc").kind(), ErrorKind::Fatal);
+	}
+}
@@ -15,7 +15,6 @@
 //! Light client protocols for parachains.
 
 use crate::{config, error::Error, ParachainClient};
-use anyhow::anyhow;
 use beefy_light_client_primitives::{ClientState as BeefyPrimitivesClientState, NodesUtils};
 use codec::{Decode, Encode};
 use finality_grandpa::BlockNumberOps;
@@ -36,8 +35,8 @@ use ics11_beefy::client_message::{
 };
 use pallet_ibc::light_clients::{AnyClientMessage, AnyClientState};
 use primitives::{
-	mock::LocalClientTypes, query_maximum_height_for_timeout_proofs, Chain, IbcProvider,
-	KeyProvider, UpdateType,
+	error::OutdatedFinalityEvent, mock::LocalClientTypes, query_maximum_height_for_timeout_proofs,
+	Chain, IbcProvider, KeyProvider, UpdateType,
 };
 use serde::{Deserialize, Serialize};
 use sp_core::H256;
@@ -64,7 +63,7 @@ pub enum FinalityProtocol {
 }
 
 /// Finality event for parachains
-#[derive(Decode, Encode)]
+#[derive(Clone, Decode, Encode)]
 pub enum FinalityEvent {
 	Grandpa(
 		grandpa_light_client_primitives::justification::GrandpaJustification<
@@ -241,7 +240,7 @@ where
 	// block_number => events
 	let events: HashMap<String, Vec<IbcEvent>> =
 		IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_events(
-			&*source.para_ws_client,
+			&*source.para_ws_client(),
 			finalized_block_numbers,
 		)
 		.await?;
@@ -360,11 +359,10 @@ where
 	};
 
 	if justification.commit.target_number <= client_state.latest_relay_height {
-		Err(anyhow!(
-			"skipping outdated commit: {}, with latest relay height: {}",
-			justification.commit.target_number,
-			client_state.latest_relay_height
-		))?
+		Err(OutdatedFinalityEvent {
+			height: justification.commit.target_number.into(),
+			latest_height: client_state.latest_relay_height.into(),
+		})?
 	}
 
 	let prover = source.grandpa_prover();
@@ -413,7 +411,7 @@ where
 	// block_number => events
 	let events: HashMap<String, Vec<IbcEvent>> =
 		IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_events(
-			&*source.para_ws_client,
+			&*source.para_ws_client(),
 			finalized_block_numbers,
 		)
 		.await?;
@@ -460,17 +458,14 @@ where
 		)
 		.await?;
 
-	let target =
-		source
-			.relay_client
-			.rpc()
-			.header(Some(finality_proof.block))
-			.await?
-			.ok_or_else(|| {
-				Error::from(
-					"Could not find relay chain header for justification target".to_string(),
-				)
-			})?;
+	let target = source
+		.relay_client()
+		.rpc()
+		.header(Some(finality_proof.block))
+		.await?
+		.ok_or_else(|| {
+			Error::from("Could not find relay chain header for justification target".to_string())
+		})?;
 
 	let authority_set_changed_scheduled = find_scheduled_change(&target).is_some();
 	// if validator set has changed this is a mandatory update
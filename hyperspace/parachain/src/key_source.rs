@@ -0,0 +1,442 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sources of the key the relayer signs extrinsics with.
+
+use crate::error::Error;
+use codec::{Decode, Encode};
+use hex_literal::hex;
+#[cfg(feature = "testing")]
+use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
+use jsonrpsee::{
+	core::RpcResult,
+	http_client::{HttpClient, HttpClientBuilder},
+	proc_macros::rpc,
+};
+use serde::{Deserialize, Serialize};
+use sp_core::{ecdsa, ed25519, sr25519, Bytes, Pair};
+use sp_runtime::{MultiSignature, MultiSigner};
+use std::{
+	path::{Path, PathBuf},
+	str::FromStr,
+	sync::Arc,
+};
+use xsalsa20poly1305::{
+	aead::{Aead, NewAead},
+	Key, Nonce, XSalsa20Poly1305,
+};
+
+/// Prefix of the pkcs8 encoded keys in polkadot-js keystores.
+const PKCS8_HEADER: [u8; 16] = hex!("3053020101300506032b657004220420");
+/// Separator between the secret and public key of pkcs8 encoded keys.
+const PKCS8_DIVIDER: [u8; 5] = hex!("a123032100");
+/// Length of the scrypt salt and parameters that prefix scrypt encrypted keystores.
+const SCRYPT_PARAMS_LENGTH: usize = 32 + 3 * 4;
+/// Length of the xsalsa20-poly1305 nonce.
+const NONCE_LENGTH: usize = 24;
+
+/// Digital signature scheme of the relayer key.
+#[derive(Debug, Clone, Copy)]
+pub enum KeyType {
+	Sr25519,
+	Ed25519,
+	Ecdsa,
+}
+
+impl FromStr for KeyType {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"sr25519" => Ok(KeyType::Sr25519),
+			"ed25519" => Ok(KeyType::Ed25519),
+			"ecdsa" | "ethereum" => Ok(KeyType::Ecdsa),
+			_ => Err(Error::Custom("Invalid key type".to_string())),
+		}
+	}
+}
+
+/// Signs payloads on behalf of the relayer account, without exposing the key itself.
+pub trait KeySource: Send + Sync {
+	/// Public key of the relayer account.
+	fn public_key(&self) -> MultiSigner;
+
+	/// Signs the payload with the relayer key.
+	fn sign(&self, payload: &[u8]) -> Result<MultiSignature, Error>;
+}
+
+/// A key pair held in memory.
+#[derive(Clone)]
+pub enum LocalKey {
+	Sr25519(sr25519::Pair),
+	Ed25519(ed25519::Pair),
+	Ecdsa(ecdsa::Pair),
+}
+
+impl LocalKey {
+	/// Loads the key from a secret uri, e.g a mnemonic, a hex encoded seed or a dev account like
+	/// `//Alice`.
+	pub fn from_suri(key_type: KeyType, suri: &str) -> Result<Self, Error> {
+		let invalid_key = |_| Error::Custom("invalid key".to_owned());
+		Ok(match key_type {
+			KeyType::Sr25519 =>
+				Self::Sr25519(sr25519::Pair::from_string(suri, None).map_err(invalid_key)?),
+			KeyType::Ed25519 =>
+				Self::Ed25519(ed25519::Pair::from_string(suri, None).map_err(invalid_key)?),
+			KeyType::Ecdsa =>
+				Self::Ecdsa(ecdsa::Pair::from_string(suri, None).map_err(invalid_key)?),
+		})
+	}
+
+	/// Decrypts a json keystore exported by polkadot-js.
+	pub fn from_keystore_json(json: &str, password: &str) -> Result<Self, Error> {
+		let keystore: EncryptedKeystore = serde_json::from_str(json)
+			.map_err(|e| Error::Custom(format!("Invalid keystore: {e}")))?;
+		let encoded = base64::decode(&keystore.encoded)
+			.map_err(|e| Error::Custom(format!("Invalid keystore encoding: {e}")))?;
+		let encoding = |kind: &str| keystore.encoding.kind.iter().any(|k| k == kind);
+		let decoded = if encoding("xsalsa20-poly1305") {
+			let (key, encrypted) = if encoding("scrypt") {
+				keystore_scrypt_key(&encoded, password)?
+			} else {
+				// legacy keystores use the password itself as the key, zero padded.
+				let mut key = [0u8; 32];
+				let length = password.len().min(32);
+				key[..length].copy_from_slice(&password.as_bytes()[..length]);
+				(key, &encoded[..])
+			};
+			if encrypted.len() < NONCE_LENGTH {
+				Err(Error::Custom("Keystore is too short".to_owned()))?
+			}
+			let (nonce, cipher_text) = encrypted.split_at(NONCE_LENGTH);
+			XSalsa20Poly1305::new(Key::from_slice(&key))
+				.decrypt(Nonce::from_slice(nonce), cipher_text)
+				.map_err(|_| Error::Custom("Invalid keystore password".to_owned()))?
+		} else if encoding("none") {
+			encoded
+		} else {
+			Err(Error::Custom(format!(
+				"Unsupported keystore encoding: {:?}",
+				keystore.encoding.kind
+			)))?
+		};
+
+		let key_type = keystore
+			.encoding
+			.content
+			.get(1)
+			.ok_or_else(|| Error::Custom("Keystore doesn't specify a key type".to_owned()))?
+			.parse::<KeyType>()?;
+		let secret_length = match key_type {
+			KeyType::Sr25519 | KeyType::Ed25519 => 64,
+			KeyType::Ecdsa => 32,
+		};
+		let secret_end = PKCS8_HEADER.len() + secret_length;
+		if !decoded.starts_with(&PKCS8_HEADER) ||
+			decoded.get(secret_end..secret_end + PKCS8_DIVIDER.len()) != Some(&PKCS8_DIVIDER[..])
+		{
+			Err(Error::Custom("Invalid pkcs8 key in keystore".to_owned()))?
+		}
+		let secret = &decoded[PKCS8_HEADER.len()..secret_end];
+		let invalid_key = |_| Error::Custom("invalid key".to_owned());
+		Ok(match key_type {
+			KeyType::Sr25519 => Self::Sr25519(
+				schnorrkel::SecretKey::from_ed25519_bytes(secret)
+					.map_err(|e| Error::Custom(format!("invalid key: {e}")))?
+					.into(),
+			),
+			KeyType::Ed25519 =>
+				Self::Ed25519(ed25519::Pair::from_seed_slice(&secret[..32]).map_err(invalid_key)?),
+			KeyType::Ecdsa =>
+				Self::Ecdsa(ecdsa::Pair::from_seed_slice(secret).map_err(invalid_key)?),
+		})
+	}
+}
+
+impl KeySource for LocalKey {
+	fn public_key(&self) -> MultiSigner {
+		match self {
+			Self::Sr25519(pair) => pair.public().into(),
+			Self::Ed25519(pair) => pair.public().into(),
+			Self::Ecdsa(pair) => pair.public().into(),
+		}
+	}
+
+	fn sign(&self, payload: &[u8]) -> Result<MultiSignature, Error> {
+		Ok(match self {
+			Self::Sr25519(pair) => pair.sign(payload).into(),
+			Self::Ed25519(pair) => pair.sign(payload).into(),
+			Self::Ecdsa(pair) => pair.sign(payload).into(),
+		})
+	}
+}
+
+#[derive(Deserialize)]
+struct EncryptedKeystore {
+	encoded: String,
+	encoding: KeystoreEncoding,
+}
+
+#[derive(Deserialize)]
+struct KeystoreEncoding {
+	/// Key format and type, e.g `["pkcs8", "sr25519"]`
+	content: Vec<String>,
+	/// Key derivation and encryption, e.g `["scrypt", "xsalsa20-poly1305"]`
+	#[serde(rename = "type")]
+	kind: Vec<String>,
+}
+
+/// Derives the encryption key of a keystore from the scrypt parameters it's prefixed with,
+/// returning it along with the remaining encrypted data.
+fn keystore_scrypt_key<'a>(
+	encoded: &'a [u8],
+	password: &str,
+) -> Result<([u8; 32], &'a [u8]), Error> {
+	if encoded.len() < SCRYPT_PARAMS_LENGTH {
+		Err(Error::Custom("Keystore is too short".to_owned()))?
+	}
+	let param = |offset: usize| {
+		u32::from_le_bytes(encoded[offset..offset + 4].try_into().expect("length checked above"))
+	};
+	let (salt, n, p, r) = (&encoded[..32], param(32), param(36), param(40));
+	if !n.is_power_of_two() {
+		Err(Error::Custom(format!("Invalid scrypt parameter N: {n}")))?
+	}
+	let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p)
+		.map_err(|e| Error::Custom(format!("Invalid scrypt parameters: {e}")))?;
+	let mut key = [0u8; 32];
+	scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+		.map_err(|e| Error::Custom(format!("Failed to derive keystore key: {e}")))?;
+	Ok((key, &encoded[SCRYPT_PARAMS_LENGTH..]))
+}
+
+/// Json rpc interface of a remote signer, keys and signatures are scale encoded.
+#[rpc(client, server)]
+pub trait RemoteSignerApi {
+	/// Returns the scale encoded [`MultiSigner`] of the key held by the signer.
+	#[method(name = "signer_publicKey")]
+	async fn public_key(&self) -> RpcResult<Bytes>;
+
+	/// Signs the payload, returning the scale encoded [`MultiSignature`].
+	#[method(name = "signer_sign")]
+	async fn sign(&self, payload: Bytes) -> RpcResult<Bytes>;
+}
+
+/// Signs through a remote signer over json rpc, so the key never enters the relayer process.
+///
+/// Signing blocks the calling thread until the signer responds, it must be called from a
+/// multi-threaded tokio runtime.
+pub struct RemoteSigner {
+	client: HttpClient,
+	public_key: MultiSigner,
+}
+
+impl RemoteSigner {
+	/// Connects to the signer at the given http url and fetches its public key.
+	pub async fn connect(url: String) -> Result<Self, Error> {
+		let client = HttpClientBuilder::default()
+			.build(&url)
+			.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		let public_key = RemoteSignerApiClient::public_key(&client)
+			.await
+			.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		let public_key = MultiSigner::decode(&mut &*public_key)?;
+		Ok(Self { client, public_key })
+	}
+}
+
+impl KeySource for RemoteSigner {
+	fn public_key(&self) -> MultiSigner {
+		self.public_key.clone()
+	}
+
+	fn sign(&self, payload: &[u8]) -> Result<MultiSignature, Error> {
+		// extrinsics are signed synchronously, the worker thread is handed over to the other
+		// tasks of the runtime while the request is in flight.
+		let runtime = tokio::runtime::Handle::try_current()
+			.map_err(|e| Error::Custom(format!("Remote signer called outside a runtime: {e}")))?;
+		let payload = Bytes(payload.to_vec());
+		let signature = tokio::task::block_in_place(|| {
+			runtime.block_on(RemoteSignerApiClient::sign(&self.client, payload))
+		})
+		.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		Ok(MultiSignature::decode(&mut &*signature)?)
+	}
+}
+
+/// Remote signer serving a local key, for tests.
+#[cfg(feature = "testing")]
+pub struct StubSigner(pub LocalKey);
+
+#[cfg(feature = "testing")]
+#[async_trait::async_trait]
+impl RemoteSignerApiServer for StubSigner {
+	async fn public_key(&self) -> RpcResult<Bytes> {
+		Ok(KeySource::public_key(&self.0).encode().into())
+	}
+
+	async fn sign(&self, payload: Bytes) -> RpcResult<Bytes> {
+		let signature = KeySource::sign(&self.0, &payload)
+			.map_err(|e| jsonrpsee::core::Error::Custom(e.to_string()))?;
+		Ok(signature.encode().into())
+	}
+}
+
+/// Starts a [`StubSigner`] on a local port, returning its url along with the server handle.
+#[cfg(feature = "testing")]
+pub async fn serve_stub_signer(key: LocalKey) -> Result<(String, HttpServerHandle), Error> {
+	let server = HttpServerBuilder::default()
+		.build("127.0.0.1:0")
+		.await
+		.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+	let url = format!(
+		"http://{}",
+		server.local_addr().map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?
+	);
+	let handle = server
+		.start(StubSigner(key).into_rpc())
+		.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+	Ok((url, handle))
+}
+
+/// Where the relayer key is loaded from, e.g:
+/// ```toml
+/// key_source = { type = "keystore", path = "relayer.json", password_env = "RELAYER_PASSWORD" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeySourceConfig {
+	/// Json keystore exported by polkadot-js, decrypted with the password read from an
+	/// environment variable
+	Keystore { path: PathBuf, password_env: String },
+	/// Secret uri read from an environment variable
+	Env { var: String },
+	/// Secret uri read from a file
+	File { path: PathBuf },
+	/// Remote signer listening on the given http url
+	Remote { url: String },
+}
+
+impl KeySourceConfig {
+	/// Loads the key, `key_type` is only used for secret uris since keystores and remote signers
+	/// carry the type of their key.
+	pub async fn load(self, key_type: KeyType) -> Result<Arc<dyn KeySource>, Error> {
+		Ok(match self {
+			Self::Keystore { path, password_env } => {
+				let json = read_secret_file(&path)?;
+				let password = read_secret_env(&password_env)?;
+				Arc::new(LocalKey::from_keystore_json(&json, &password)?)
+			},
+			Self::Env { var } =>
+				Arc::new(LocalKey::from_suri(key_type, read_secret_env(&var)?.trim())?),
+			Self::File { path } =>
+				Arc::new(LocalKey::from_suri(key_type, read_secret_file(&path)?.trim())?),
+			Self::Remote { url } => Arc::new(RemoteSigner::connect(url).await?),
+		})
+	}
+}
+
+fn read_secret_env(var: &str) -> Result<String, Error> {
+	std::env::var(var).map_err(|e| Error::Custom(format!("Failed to read {var}: {e}")))
+}
+
+fn read_secret_file(path: &Path) -> Result<String, Error> {
+	std::fs::read_to_string(path)
+		.map_err(|e| Error::Custom(format!("Failed to read {}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::traits::{IdentifyAccount, Verify};
+
+	/// Password of the keystores in `mock/keystore`, exported in the polkadot-js format.
+	const PASSWORD: &str = "hyperspace";
+
+	fn assert_signs(key: &dyn KeySource) {
+		let payload = b"hyperspace";
+		let signature = key.sign(payload).unwrap();
+		assert!(signature.verify(&payload[..], &key.public_key().into_account()));
+	}
+
+	#[test]
+	fn decrypts_sr25519_keystore() {
+		let key =
+			LocalKey::from_keystore_json(include_str!("mock/keystore/sr25519.json"), PASSWORD)
+				.unwrap();
+		let public = hex!("94809651ad298a58a7bd591d3373b28d45e5d5c6465a7b694477cdcc933e0c2c");
+		assert!(matches!(key, LocalKey::Sr25519(_)));
+		assert_eq!(key.public_key(), MultiSigner::Sr25519(sr25519::Public::from_raw(public)));
+		assert_signs(&key);
+	}
+
+	#[test]
+	fn decrypts_ed25519_keystore() {
+		let key =
+			LocalKey::from_keystore_json(include_str!("mock/keystore/ed25519.json"), PASSWORD)
+				.unwrap();
+		let public = hex!("447faafd55511d5df26e3b6ed66c53e7de6a0f5e2139a36b16f5f76021135a10");
+		assert!(matches!(key, LocalKey::Ed25519(_)));
+		assert_eq!(key.public_key(), MultiSigner::Ed25519(ed25519::Public::from_raw(public)));
+		assert_signs(&key);
+	}
+
+	#[test]
+	fn decrypts_ecdsa_keystore() {
+		let key = LocalKey::from_keystore_json(include_str!("mock/keystore/ecdsa.json"), PASSWORD)
+			.unwrap();
+		let public = hex!("038894ec08242fb8b9bbe98038eb05aeb53726538bb299ef479495c4a7f1fb09dc");
+		assert!(matches!(key, LocalKey::Ecdsa(_)));
+		assert_eq!(key.public_key(), MultiSigner::Ecdsa(ecdsa::Public::from_raw(public)));
+		assert_signs(&key);
+	}
+
+	#[test]
+	fn rejects_wrong_keystore_password() {
+		let result =
+			LocalKey::from_keystore_json(include_str!("mock/keystore/ed25519.json"), "wrong");
+		assert!(matches!(result, Err(Error::Custom(message)) if message.contains("password")));
+	}
+
+	#[test]
+	fn rejects_invalid_keystore() {
+		assert!(LocalKey::from_keystore_json("{}", PASSWORD).is_err());
+		let json =
+			r#"{"encoded":"AAAA","encoding":{"content":["pkcs8","sr25519"],"type":["none"]}}"#;
+		assert!(LocalKey::from_keystore_json(json, PASSWORD).is_err());
+	}
+
+	#[tokio::test(flavor = "multi_thread")]
+	async fn unreachable_remote_signer_returns_an_error() {
+		let key = LocalKey::from_suri(KeyType::Sr25519, "//Alice").unwrap();
+		let signer = RemoteSigner {
+			client: HttpClientBuilder::default().build("http://127.0.0.1:1").unwrap(),
+			public_key: key.public_key(),
+		};
+		assert!(signer.sign(b"hyperspace").is_err());
+	}
+
+	#[cfg(feature = "testing")]
+	#[tokio::test(flavor = "multi_thread")]
+	async fn remote_signer_signs_with_the_served_key() {
+		let key = LocalKey::from_suri(KeyType::Ed25519, "//Alice").unwrap();
+		let (url, _handle) = serve_stub_signer(key.clone()).await.unwrap();
+		let signer = RemoteSigner::connect(url).await.unwrap();
+		assert_eq!(KeySource::public_key(&signer), key.public_key());
+		// the same client is reused for every signature
+		for _ in 0..3 {
+			assert_signs(&signer);
+		}
+	}
+}
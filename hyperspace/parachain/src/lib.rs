@@ -20,9 +20,14 @@ pub mod chain;
 pub mod config;
 pub mod error;
 pub mod key_provider;
+pub mod key_source;
+pub mod misbehaviour;
+pub mod nonce;
 pub mod parachain;
 pub mod polkadot;
 pub mod provider;
+pub mod reconnect;
+pub mod sampling;
 pub mod signer;
 pub mod utils;
 
@@ -39,11 +44,10 @@ use beefy_prover::Prover;
 use ibc::core::ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId};
 use ics11_beefy::client_message::ParachainHeader;
 use pallet_mmr_primitives::BatchProof;
-use sp_core::{ecdsa, ed25519, sr25519, Bytes, Pair, H256};
-use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_core::{Bytes, H256};
 use sp_runtime::{
 	traits::{IdentifyAccount, Verify},
-	KeyTypeId, MultiSignature,
+	MultiSignature,
 };
 use ss58_registry::Ss58AddressFormat;
 use subxt::ext::sp_runtime::{traits::Header as HeaderT, MultiSigner};
@@ -58,16 +62,21 @@ use ics11_beefy::{
 	client_state::ClientState as BeefyClientState,
 	consensus_state::ConsensusState as BeefyConsensusState,
 };
-use primitives::KeyProvider;
+use primitives::{error::ErrorKind, filter::PacketFilter, KeyProvider, LowBalanceState};
 
-use crate::{finality_protocol::FinalityProtocol, signer::ExtrinsicSigner};
+use crate::{
+	finality_protocol::FinalityProtocol,
+	key_source::{KeySource, KeySourceConfig, KeyType, LocalKey},
+	nonce::SignerPool,
+	reconnect::{ReconnectConfig, Reconnecting, RpcConnection},
+	sampling::FinalitySampling,
+	signer::ExtrinsicSigner,
+};
 use grandpa_light_client_primitives::{FinalityProof, ParachainHeaderProofs};
 use grandpa_prover::GrandpaProver;
 use ibc::timestamp::Timestamp;
 use ics10_grandpa::client_state::ClientState as GrandpaClientState;
-use jsonrpsee_ws_client::WsClientBuilder;
 use pallet_ibc::light_clients::{AnyClientState, AnyConsensusState, HostFunctionsManager};
-use sp_keystore::testing::KeyStore;
 use sp_runtime::traits::One;
 use subxt::tx::TxPayload;
 
@@ -80,14 +89,14 @@ use subxt::tx::TxPayload;
 pub struct ParachainClient<T: config::Config> {
 	/// Chain name
 	pub name: String,
-	/// Relay chain rpc client
-	pub relay_client: subxt::OnlineClient<T>,
-	/// Parachain rpc client
-	pub para_client: subxt::OnlineClient<T>,
-	/// Relay chain ws client
-	pub relay_ws_client: Arc<jsonrpsee_ws_client::WsClient>,
-	/// Parachain ws client
-	pub para_ws_client: Arc<jsonrpsee_ws_client::WsClient>,
+	/// Relay chain rpc connection, re-established whenever it drops
+	pub relay_rpc: Arc<Reconnecting<RpcConnection<T>>>,
+	/// Parachain rpc connection, re-established whenever it drops
+	pub para_rpc: Arc<Reconnecting<RpcConnection<T>>>,
+	/// Relay chain rpc urls, in order of preference
+	pub relay_chain_rpc_urls: Vec<String>,
+	/// Backoff for reconnecting to the rpc nodes
+	pub reconnect: ReconnectConfig,
 	/// Parachain Id
 	pub para_id: u32,
 	/// Beefy activation block
@@ -100,47 +109,25 @@ pub struct ParachainClient<T: config::Config> {
 	pub commitment_prefix: Vec<u8>,
 	/// Public key for relayer on chain
 	pub public_key: MultiSigner,
-	/// Reference to keystore
-	pub key_store: SyncCryptoStorePtr,
-	/// Key type Id
-	pub key_type_id: KeyTypeId,
+	/// Source of the relayer key
+	pub key_source: Arc<dyn KeySource>,
+	/// Accounts that extrinsics are signed with, starting with the relayer key
+	pub signer_pool: Arc<SignerPool<T>>,
 	/// used for encoding relayer address.
 	pub ss58_version: Ss58AddressFormat,
 	/// the maximum extrinsic weight allowed by this client
 	pub max_extrinsic_weight: u64,
 	/// Channels cleared for packet relay
 	pub channel_whitelist: Vec<(ChannelId, PortId)>,
+	/// Filter for the packets sent from this chain
+	pub packet_filter: PacketFilter,
 	/// Finality protocol to use, eg Beefy, Grandpa
 	pub finality_protocol: FinalityProtocol,
-}
-
-enum KeyType {
-	Sr25519,
-	Ed25519,
-	Ecdsa,
-}
-
-impl KeyType {
-	pub fn to_key_type_id(&self) -> KeyTypeId {
-		match self {
-			KeyType::Sr25519 => KeyTypeId(sr25519::CRYPTO_ID.0),
-			KeyType::Ed25519 => KeyTypeId(ed25519::CRYPTO_ID.0),
-			KeyType::Ecdsa => KeyTypeId(ecdsa::CRYPTO_ID.0),
-		}
-	}
-}
-
-impl FromStr for KeyType {
-	type Err = Error;
-
-	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		match s {
-			"sr25519" => Ok(KeyType::Sr25519),
-			"ed25519" => Ok(KeyType::Ed25519),
-			"ecdsa" => Ok(KeyType::Ecdsa),
-			_ => Err(Error::Custom("Invalid key type".to_string())),
-		}
-	}
+	/// Policy for picking the finality notifications to relay, defaults to the one of the
+	/// finality protocol
+	pub finality_sampling: Option<FinalitySampling>,
+	/// Set while the balance of a relayer account is below one of the configured thresholds
+	pub low_balance: LowBalanceState,
 }
 
 /// config options for [`ParachainClient`]
@@ -154,6 +141,15 @@ pub struct ParachainClientConfig {
 	pub parachain_rpc_url: String,
 	/// rpc url for relay chain
 	pub relay_chain_rpc_url: String,
+	/// rpc urls for the parachain, tried in order when `parachain_rpc_url` is unreachable
+	#[serde(default)]
+	pub parachain_rpc_fallback_urls: Vec<String>,
+	/// rpc urls for the relay chain, tried in order when `relay_chain_rpc_url` is unreachable
+	#[serde(default)]
+	pub relay_chain_rpc_fallback_urls: Vec<String>,
+	/// Backoff for reconnecting to the rpc nodes
+	#[serde(default)]
+	pub reconnect: ReconnectConfig,
 	/// Light client id on counterparty chain
 	pub client_id: Option<ClientId>,
 	/// Connection Id
@@ -162,14 +158,28 @@ pub struct ParachainClientConfig {
 	pub beefy_activation_block: Option<u32>,
 	/// Commitment prefix
 	pub commitment_prefix: Bytes,
-	/// Raw private key for signing transactions
-	pub private_key: String,
+	/// Raw private key for signing transactions, `key_source` should be preferred
+	#[serde(default)]
+	pub private_key: Option<String>,
+	/// Source of the key for signing transactions
+	#[serde(default)]
+	pub key_source: Option<KeySourceConfig>,
+	/// Additional accounts for signing transactions, extrinsics are distributed across them and
+	/// the relayer key so that several can be submitted at once
+	#[serde(default)]
+	pub signer_pool: Vec<KeySourceConfig>,
 	/// used for encoding relayer address.
 	pub ss58_version: u8,
 	/// Channels cleared for packet relay
 	pub channel_whitelist: Vec<(ChannelId, PortId)>,
+	/// Filter for the packets sent from this chain
+	#[serde(default)]
+	pub packet_filter: PacketFilter,
 	/// Finality protocol
 	pub finality_protocol: FinalityProtocol,
+	/// Policy for picking the finality notifications to relay
+	#[serde(default)]
+	pub finality_sampling: Option<FinalitySampling>,
 	/// Digital signature scheme
 	pub key_type: String,
 }
@@ -177,86 +187,103 @@ pub struct ParachainClientConfig {
 impl<T> ParachainClient<T>
 where
 	T: config::Config,
+	<T::Signature as Verify>::Signer: From<MultiSigner> + IdentifyAccount<AccountId = T::AccountId>,
 {
 	/// Initializes a [`ParachainClient`] given a [`ParachainConfig`]
 	pub async fn new(config: ParachainClientConfig) -> Result<Self, Error> {
-		let relay_ws_client = Arc::new(
-			WsClientBuilder::default()
-				.build(&config.relay_chain_rpc_url)
-				.await
-				.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?,
-		);
-		let para_ws_client = Arc::new(
-			WsClientBuilder::default()
-				.build(&config.parachain_rpc_url)
-				.await
-				.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?,
-		);
-
-		let para_client = subxt::OnlineClient::from_rpc_client(unsafe {
-			unsafe_cast_to_jsonrpsee_client(&para_ws_client)
-		})
-		.await?;
-
-		let relay_client = subxt::OnlineClient::from_rpc_client(unsafe {
-			unsafe_cast_to_jsonrpsee_client(&relay_ws_client)
-		})
-		.await?;
+		let relay_chain_rpc_urls = std::iter::once(config.relay_chain_rpc_url)
+			.chain(config.relay_chain_rpc_fallback_urls)
+			.collect::<Vec<_>>();
+		let parachain_rpc_urls = std::iter::once(config.parachain_rpc_url)
+			.chain(config.parachain_rpc_fallback_urls)
+			.collect::<Vec<_>>();
+		let relay_rpc =
+			Reconnecting::connect(relay_chain_rpc_urls.clone(), config.reconnect.clone()).await?;
+		let para_rpc = Reconnecting::connect(parachain_rpc_urls, config.reconnect.clone()).await?;
+
+		let max_extrinsic_weight = fetch_max_extrinsic_weight(&para_rpc.current().client).await?;
 
-		let max_extrinsic_weight = fetch_max_extrinsic_weight(&para_client).await?;
-
-		let key_store: SyncCryptoStorePtr = Arc::new(KeyStore::new());
 		let key_type = KeyType::from_str(&config.key_type)?;
-		let key_type_id = key_type.to_key_type_id();
-
-		let public_key: MultiSigner = match key_type {
-			KeyType::Sr25519 => sr25519::Pair::from_string_with_seed(&config.private_key, None)
-				.map_err(|_| Error::Custom("invalid key".to_owned()))?
-				.0
-				.public()
-				.into(),
-			KeyType::Ed25519 => ed25519::Pair::from_string_with_seed(&config.private_key, None)
-				.map_err(|_| Error::Custom("invalid key".to_owned()))?
-				.0
-				.public()
-				.into(),
-			KeyType::Ecdsa => ecdsa::Pair::from_string_with_seed(&config.private_key, None)
-				.map_err(|_| Error::Custom("invalid key".to_owned()))?
-				.0
-				.public()
-				.into(),
+		let key_source: Arc<dyn KeySource> = match (config.key_source, config.private_key) {
+			(Some(key_source), _) => key_source.load(key_type).await?,
+			(None, Some(private_key)) => Arc::new(LocalKey::from_suri(key_type, &private_key)?),
+			(None, None) => Err(Error::Custom(
+				"Either key_source or private_key must be configured".to_owned(),
+			))?,
 		};
-
-		SyncCryptoStore::insert_unknown(
-			&*key_store,
-			key_type_id,
-			&*config.private_key,
-			public_key.as_ref(),
-		)
-		.unwrap();
+		let public_key = key_source.public_key();
+		let mut pool_keys = vec![key_source.clone()];
+		for key in config.signer_pool {
+			pool_keys.push(key.load(key_type).await?);
+		}
+		let signer_pool = Arc::new(SignerPool::new(pool_keys)?);
 
 		Ok(Self {
 			name: config.name,
-			para_client,
-			relay_client,
+			para_rpc,
+			relay_rpc,
 			para_id: config.para_id,
 			client_id: config.client_id,
 			commitment_prefix: config.commitment_prefix.0,
 			connection_id: config.connection_id,
 			beefy_activation_block: config.beefy_activation_block,
 			public_key,
-			key_store,
-			key_type_id,
+			key_source,
+			signer_pool,
 			max_extrinsic_weight,
-			para_ws_client,
-			relay_ws_client,
+			relay_chain_rpc_urls,
+			reconnect: config.reconnect,
 			ss58_version: Ss58AddressFormat::from(config.ss58_version),
 			channel_whitelist: config.channel_whitelist,
+			packet_filter: config.packet_filter,
 			finality_protocol: config.finality_protocol,
+			finality_sampling: config.finality_sampling,
+			low_balance: LowBalanceState::default(),
 		})
 	}
 }
 
+/// Returns a grandpa proving client over the current rpc connections.
+pub(crate) fn grandpa_prover<T: config::Config>(
+	relay_rpc: &Reconnecting<RpcConnection<T>>,
+	para_rpc: &Reconnecting<RpcConnection<T>>,
+	para_id: u32,
+) -> GrandpaProver<T> {
+	let relay = relay_rpc.current();
+	let para = para_rpc.current();
+	GrandpaProver {
+		relay_client: relay.client.clone(),
+		relay_ws_client: unsafe { unsafe_cast_to_jsonrpsee_client(&relay.ws_client) },
+		para_client: para.client.clone(),
+		para_ws_client: unsafe { unsafe_cast_to_jsonrpsee_client(&para.ws_client) },
+		para_id,
+	}
+}
+
+impl<T: config::Config> ParachainClient<T> {
+	/// Relay chain rpc client, the connection is replaced when it drops so this shouldn't be held
+	/// onto
+	pub fn relay_client(&self) -> subxt::OnlineClient<T> {
+		self.relay_rpc.current().client.clone()
+	}
+
+	/// Parachain rpc client, the connection is replaced when it drops so this shouldn't be held
+	/// onto
+	pub fn para_client(&self) -> subxt::OnlineClient<T> {
+		self.para_rpc.current().client.clone()
+	}
+
+	/// Relay chain ws client
+	pub fn relay_ws_client(&self) -> Arc<jsonrpsee_ws_client::WsClient> {
+		self.relay_rpc.current().ws_client.clone()
+	}
+
+	/// Parachain ws client
+	pub fn para_ws_client(&self) -> Arc<jsonrpsee_ws_client::WsClient> {
+		self.para_rpc.current().ws_client.clone()
+	}
+}
+
 impl<T: config::Config + Send + Sync> ParachainClient<T>
 where
 	u32: From<<<T as subxt::Config>::Header as HeaderT>::Number>,
@@ -270,15 +297,7 @@ where
 {
 	/// Returns a grandpa proving client.
 	pub fn grandpa_prover(&self) -> GrandpaProver<T> {
-		let relay_ws_client = unsafe { unsafe_cast_to_jsonrpsee_client(&self.relay_ws_client) };
-		let para_ws_client = unsafe { unsafe_cast_to_jsonrpsee_client(&self.para_ws_client) };
-		GrandpaProver {
-			relay_client: self.relay_client.clone(),
-			relay_ws_client,
-			para_client: self.para_client.clone(),
-			para_ws_client,
-			para_id: self.para_id,
-		}
+		grandpa_prover(&self.relay_rpc, &self.para_rpc, self.para_id)
 	}
 
 	/// Queries parachain headers that have been finalized by BEEFY in between the given relay chain
@@ -293,8 +312,8 @@ where
 		T::BlockNumber: From<u32>,
 	{
 		let client_wrapper = Prover {
-			relay_client: self.relay_client.clone(),
-			para_client: self.para_client.clone(),
+			relay_client: self.relay_client(),
+			para_client: self.para_client(),
 			beefy_activation_block: client_state.beefy_activation_block,
 			para_id: self.para_id,
 		};
@@ -324,8 +343,8 @@ where
 		T::BlockNumber: Ord + sp_runtime::traits::Zero,
 	{
 		let client_wrapper = Prover {
-			relay_client: self.relay_client.clone(),
-			para_client: self.para_client.clone(),
+			relay_client: self.relay_client(),
+			para_client: self.para_client(),
 			beefy_activation_block: client_state.beefy_activation_block,
 			para_id: self.para_id,
 		};
@@ -369,8 +388,8 @@ where
 		client_state: &ClientState,
 	) -> Result<MmrUpdateProof, Error> {
 		let prover = Prover {
-			relay_client: self.relay_client.clone(),
-			para_client: self.para_client.clone(),
+			relay_client: self.relay_client(),
+			para_client: self.para_client(),
 			beefy_activation_block: client_state.beefy_activation_block,
 			para_id: self.para_id,
 		};
@@ -385,14 +404,12 @@ where
 	/// Submits the given transaction to the parachain node, waits for it to be included in a block
 	/// and asserts that it was successfully dispatched on-chain.
 	///
-	/// We retry sending the transaction up to 5 times in the case where the transaction pool might
-	/// reject the transaction because of conflicting nonces.
+	/// The transaction is signed by the next account of the signer pool with a locally tracked
+	/// nonce, so that concurrent submissions don't conflict. Should the transaction pool reject it,
+	/// e.g. because of a nonce gap or a conflicting transaction, the nonce is re-synced with the
+	/// chain and the transaction is resubmitted, up to 5 times.
 	pub async fn submit_call<C: TxPayload>(&self, call: C) -> Result<(T::Hash, T::Hash), Error> {
-		let signer = ExtrinsicSigner::<T, Self>::new(
-			self.key_store.clone(),
-			self.key_type_id.clone(),
-			self.public_key.clone(),
-		);
+		let account = self.signer_pool.next_account();
 
 		// Try extrinsic submission five times in case of failures
 		let mut count = 0;
@@ -401,23 +418,54 @@ where
 				Err(Error::Custom("Failed to submit extrinsic after 5 tries".to_string()))?
 			}
 
-			let other_params = T::custom_extrinsic_params(&self.para_client).await?;
-
-			let res = self
-				.para_client
-				.tx()
-				.sign_and_submit_then_watch(&call, &signer, other_params)
-				.await;
+			let nonce = account
+				.nonce
+				.next(|| async {
+					self.para_client()
+						.rpc()
+						.system_account_next_index(&account.account_id)
+						.await
+						.map_err(Error::from)
+				})
+				.await?;
+			let signer =
+				ExtrinsicSigner::<T, Self>::new(account.key_source.clone()).with_nonce(nonce);
+			let other_params = T::custom_extrinsic_params(&self.para_client()).await?;
+
+			let res = async {
+				let extrinsic =
+					self.para_client().tx().create_signed(&call, &signer, other_params).await?;
+				signer.check()?;
+				extrinsic.submit_and_watch().await.map_err(Error::from)
+			}
+			.await;
 			match res {
 				Ok(progress) => break progress,
 				Err(e) => {
-					log::warn!("Failed to submit extrinsic: {:?}. Retrying...", e);
+					// the nonce never made it into the pool, so the local one is now ahead of the
+					// chain.
+					account.nonce.reset().await;
+					match e.kind() {
+						ErrorKind::Nonce => log::warn!(
+							"Nonce of {:?} out of sync: {:?}. Resubmitting...",
+							account.account_id,
+							e
+						),
+						_ => log::warn!("Failed to submit extrinsic: {:?}. Retrying...", e),
+					}
 					count += 1;
 				},
 			}
 		};
 
-		let tx_in_block = progress.wait_for_in_block().await?;
+		let tx_in_block = match progress.wait_for_in_block().await {
+			Ok(tx_in_block) => tx_in_block,
+			Err(e) => {
+				// the transaction was dropped from the pool, along with its nonce
+				account.nonce.reset().await;
+				Err(e)?
+			},
+		};
 		tx_in_block.wait_for_success().await?;
 		Ok((tx_in_block.extrinsic_hash(), tx_in_block.block_hash()))
 	}
@@ -460,11 +508,11 @@ where
 		use ibc::core::ics24_host::identifier::ChainId;
 		let beefy_activation_block =
 			self.beefy_activation_block.expect("beefy_activation_block was not defined");
-		let api = self.relay_client.storage();
-		let para_client_api = self.para_client.storage();
+		let api = self.relay_client().storage();
+		let para_client_api = self.para_client().storage();
 		let client_wrapper = Prover {
-			relay_client: self.relay_client.clone(),
-			para_client: self.para_client.clone(),
+			relay_client: self.relay_client(),
+			para_client: self.para_client(),
 			beefy_activation_block,
 			para_id: self.para_id,
 		};
@@ -478,7 +526,7 @@ where
 
 			let subxt_block_number: subxt::rpc::BlockNumber =
 				beefy_state.latest_beefy_height.into();
-			let block_hash = self.relay_client.rpc().block_hash(Some(subxt_block_number)).await?;
+			let block_hash = self.relay_client().rpc().block_hash(Some(subxt_block_number)).await?;
 			let heads_addr = polkadot::api::storage().paras().heads(
 				&polkadot::api::runtime_types::polkadot_parachain::primitives::Id(self.para_id),
 			);
@@ -512,7 +560,7 @@ where
 			}
 			let subxt_block_number: subxt::rpc::BlockNumber = block_number.into();
 			let block_hash =
-				self.para_client.rpc().block_hash(Some(subxt_block_number)).await.unwrap();
+				self.para_client().rpc().block_hash(Some(subxt_block_number)).await.unwrap();
 			let timestamp_addr = api::storage().timestamp().now();
 			let unix_timestamp_millis = para_client_api
 				.fetch(&timestamp_addr, block_hash)
@@ -543,17 +591,17 @@ where
 		<T as subxt::Config>::Address: From<<T as subxt::Config>::AccountId>,
 		u32: From<<T as subxt::Config>::BlockNumber>,
 	{
-		let relay_ws_client = unsafe { unsafe_cast_to_jsonrpsee_client(&self.relay_ws_client) };
-		let para_ws_client = unsafe { unsafe_cast_to_jsonrpsee_client(&self.para_ws_client) };
+		let relay_ws_client = unsafe { unsafe_cast_to_jsonrpsee_client(&self.relay_ws_client()) };
+		let para_ws_client = unsafe { unsafe_cast_to_jsonrpsee_client(&self.para_ws_client()) };
 		let prover = GrandpaProver {
-			relay_client: self.relay_client.clone(),
+			relay_client: self.relay_client(),
 			relay_ws_client,
-			para_client: self.para_client.clone(),
+			para_client: self.para_client(),
 			para_ws_client,
 			para_id: self.para_id,
 		};
-		let api = self.relay_client.storage();
-		let para_client_api = self.para_client.storage();
+		let api = self.relay_client().storage();
+		let para_client_api = self.para_client().storage();
 		loop {
 			let light_client_state = prover
 				.initialize_client_state()
@@ -595,7 +643,7 @@ where
 
 			let subxt_block_number: subxt::rpc::BlockNumber = block_number.into();
 			let block_hash =
-				self.para_client.rpc().block_hash(Some(subxt_block_number)).await.unwrap();
+				self.para_client().rpc().block_hash(Some(subxt_block_number)).await.unwrap();
 			let timestamp_addr = api::storage().timestamp().now();
 			let unix_timestamp_millis = para_client_api
 				.fetch(&timestamp_addr, block_hash)
@@ -0,0 +1,82 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of GRANDPA equivocations in the finality proofs submitted to a counterparty.
+
+use anyhow::anyhow;
+use grandpa_light_client_primitives::justification::{
+	check_equivocation_proof, GrandpaJustification,
+};
+use ibc::core::ics24_host::identifier::ClientId;
+use ics10_grandpa::client_message::RelayChainHeader;
+use pallet_ibc::light_clients::{AnyClientState, HostFunctionsManager};
+use primitives::Chain;
+use sp_core::H256;
+use sp_finality_grandpa::Equivocation;
+
+/// Returns the precommit equivocations between two justifications of the same round, i.e the
+/// authorities that voted for different targets in both. Only equivocations whose signatures are
+/// valid for the given authority set are returned.
+pub fn find_equivocations(
+	set_id: u64,
+	first: &GrandpaJustification<RelayChainHeader>,
+	second: &GrandpaJustification<RelayChainHeader>,
+) -> Vec<Equivocation<H256, u32>> {
+	if first.round != second.round {
+		return vec![]
+	}
+
+	let mut equivocations = vec![];
+	for first_precommit in &first.commit.precommits {
+		let second_precommit = second.commit.precommits.iter().find(|second_precommit| {
+			second_precommit.id == first_precommit.id &&
+				second_precommit.precommit.target_hash != first_precommit.precommit.target_hash
+		});
+		let second_precommit = match second_precommit {
+			Some(precommit) => precommit,
+			None => continue,
+		};
+		let equivocation = Equivocation::Precommit(finality_grandpa::Equivocation {
+			round_number: first.round,
+			identity: first_precommit.id.clone(),
+			first: (first_precommit.precommit.clone(), first_precommit.signature.clone()),
+			second: (second_precommit.precommit.clone(), second_precommit.signature.clone()),
+		});
+		match check_equivocation_proof::<HostFunctionsManager, _, _>(set_id, equivocation.clone()) {
+			Ok(()) => equivocations.push(equivocation),
+			Err(err) =>
+				log::debug!("Ignoring invalid equivocation by {:?}: {err:?}", first_precommit.id),
+		}
+	}
+	equivocations
+}
+
+/// Queries the id of the authority set currently tracked by the GRANDPA light client on the
+/// counterparty.
+pub async fn query_grandpa_set_id(
+	counterparty: &impl Chain,
+	client_id: ClientId,
+) -> Result<u64, anyhow::Error> {
+	let (height, _) = counterparty.latest_height_and_timestamp().await?;
+	let response = counterparty.query_client_state(height, client_id.clone()).await?;
+	let client_state = response
+		.client_state
+		.ok_or_else(|| anyhow!("Client state for {client_id} not found"))?;
+	match AnyClientState::try_from(client_state)
+		.map_err(|e| anyhow!("Failed to decode client state: {e:?}"))?
+	{
+		AnyClientState::Grandpa(client_state) => Ok(client_state.current_set_id),
+		_ => Err(anyhow!("Client {client_id} is not a GRANDPA client")),
+	}
+}
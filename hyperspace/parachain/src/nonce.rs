@@ -0,0 +1,207 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pool of signer accounts with local nonce tracking, so that several extrinsics can be in the
+//! transaction pool at once.
+
+use std::{
+	future::Future,
+	ops::Add,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+};
+
+use sp_runtime::{
+	traits::{IdentifyAccount, Verify},
+	MultiSigner,
+};
+use tokio::sync::Mutex;
+
+use crate::{config, error::Error, key_source::KeySource};
+
+/// Tracks the next nonce of a single account locally, instead of querying it for every
+/// extrinsic.
+pub struct NonceTracker<Index> {
+	next: Mutex<Option<Index>>,
+}
+
+impl<Index> Default for NonceTracker<Index> {
+	fn default() -> Self {
+		Self { next: Mutex::new(None) }
+	}
+}
+
+impl<Index> NonceTracker<Index>
+where
+	Index: Copy + Add<Output = Index> + From<u8>,
+{
+	/// Reserves the next nonce of the account. `fetch` is only called to query the nonce from the
+	/// chain on first use and after a [`NonceTracker::reset`].
+	pub async fn next<F, Fut>(&self, fetch: F) -> Result<Index, Error>
+	where
+		F: FnOnce() -> Fut,
+		Fut: Future<Output = Result<Index, Error>>,
+	{
+		let mut next = self.next.lock().await;
+		let nonce = match *next {
+			Some(nonce) => nonce,
+			None => fetch().await?,
+		};
+		*next = Some(nonce + Index::from(1));
+		Ok(nonce)
+	}
+
+	/// Forgets the local nonce, the next one is queried from the chain. Should be called whenever
+	/// a reserved nonce didn't make it into the transaction pool, since the local nonce is then
+	/// ahead of the chain.
+	pub async fn reset(&self) {
+		*self.next.lock().await = None;
+	}
+}
+
+/// A signer account of the pool.
+pub struct PoolAccount<T: config::Config> {
+	/// Account id derived from the key
+	pub account_id: T::AccountId,
+	/// Source of the account key
+	pub key_source: Arc<dyn KeySource>,
+	/// Next nonce of the account
+	pub nonce: NonceTracker<T::Index>,
+}
+
+/// Signer accounts that extrinsics are distributed across in a round-robin fashion.
+pub struct SignerPool<T: config::Config> {
+	accounts: Vec<PoolAccount<T>>,
+	next: AtomicUsize,
+}
+
+impl<T> SignerPool<T>
+where
+	T: config::Config,
+	<T::Signature as Verify>::Signer: From<MultiSigner> + IdentifyAccount<AccountId = T::AccountId>,
+{
+	/// Creates a pool of the given keys, the first one is the relayer's main account.
+	pub fn new(key_sources: Vec<Arc<dyn KeySource>>) -> Result<Self, Error> {
+		if key_sources.is_empty() {
+			Err(Error::Custom("Signer pool must contain at least one key".to_owned()))?
+		}
+		let accounts = key_sources
+			.into_iter()
+			.map(|key_source| PoolAccount {
+				account_id: <T::Signature as Verify>::Signer::from(key_source.public_key())
+					.into_account(),
+				key_source,
+				nonce: NonceTracker::default(),
+			})
+			.collect();
+		Ok(Self { accounts, next: AtomicUsize::new(0) })
+	}
+
+	/// Returns the account to sign the next extrinsic with.
+	pub fn next_account(&self) -> &PoolAccount<T> {
+		let index = self.next.fetch_add(1, Ordering::Relaxed);
+		&self.accounts[index % self.accounts.len()]
+	}
+
+	/// Returns all the accounts of the pool.
+	pub fn accounts(&self) -> &[PoolAccount<T>] {
+		&self.accounts
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::key_source::{KeyType, LocalKey};
+	use subxt::{
+		tx::{SubstrateExtrinsicParams, SubstrateExtrinsicParamsBuilder},
+		OnlineClient,
+	};
+
+	enum TestConfig {}
+
+	impl subxt::Config for TestConfig {
+		type Index = u32;
+		type BlockNumber = u32;
+		type Hash = sp_core::H256;
+		type Hashing = sp_runtime::traits::BlakeTwo256;
+		type AccountId = sp_runtime::AccountId32;
+		type Address = sp_runtime::MultiAddress<Self::AccountId, u32>;
+		type Header = sp_runtime::generic::Header<Self::BlockNumber, Self::Hashing>;
+		type Signature = sp_runtime::MultiSignature;
+		type Extrinsic = sp_runtime::OpaqueExtrinsic;
+		type ExtrinsicParams = SubstrateExtrinsicParams<Self>;
+	}
+
+	#[async_trait::async_trait]
+	impl config::Config for TestConfig {
+		type AssetId = u128;
+
+		async fn custom_extrinsic_params(
+			_client: &OnlineClient<Self>,
+		) -> Result<SubstrateExtrinsicParamsBuilder<Self>, subxt::Error> {
+			Ok(Default::default())
+		}
+	}
+
+	fn key(suri: &str) -> Arc<dyn KeySource> {
+		Arc::new(LocalKey::from_suri(KeyType::Sr25519, suri).unwrap())
+	}
+
+	async fn not_fetched() -> Result<u32, Error> {
+		Err(Error::Custom("Nonce should be tracked locally".to_owned()))
+	}
+
+	#[tokio::test]
+	async fn nonce_tracker_tracks_nonces_locally() {
+		let tracker = NonceTracker::<u32>::default();
+		assert_eq!(tracker.next(|| async { Ok(7) }).await.unwrap(), 7);
+		// subsequent nonces don't depend on the previous extrinsics being included
+		assert_eq!(tracker.next(not_fetched).await.unwrap(), 8);
+		assert_eq!(tracker.next(not_fetched).await.unwrap(), 9);
+
+		// the reserved nonces were never used, the nonce is re-synced with the chain
+		tracker.reset().await;
+		assert_eq!(tracker.next(|| async { Ok(8) }).await.unwrap(), 8);
+		assert_eq!(tracker.next(not_fetched).await.unwrap(), 9);
+	}
+
+	#[tokio::test]
+	async fn nonce_tracker_refetches_after_failed_fetch() {
+		let tracker = NonceTracker::<u32>::default();
+		assert!(tracker.next(not_fetched).await.is_err());
+		assert_eq!(tracker.next(|| async { Ok(3) }).await.unwrap(), 3);
+	}
+
+	#[test]
+	fn signer_pool_rotates_accounts() {
+		let keys = vec![key("//Alice"), key("//Bob"), key("//Charlie")];
+		let account_ids =
+			keys.iter().map(|key| key.public_key().into_account()).collect::<Vec<_>>();
+		let pool = SignerPool::<TestConfig>::new(keys).unwrap();
+
+		// the relayer's main account comes first
+		assert_eq!(pool.accounts()[0].account_id, account_ids[0]);
+		let picked = (0..7).map(|_| pool.next_account().account_id.clone()).collect::<Vec<_>>();
+		let expected = account_ids.iter().cycle().take(7).cloned().collect::<Vec<_>>();
+		assert_eq!(picked, expected);
+	}
+
+	#[test]
+	fn signer_pool_requires_a_key() {
+		assert!(SignerPool::<TestConfig>::new(vec![]).is_err());
+	}
+}
@@ -35,16 +35,19 @@ use ibc::{
 };
 use ibc_proto::{
 	google::protobuf::Any,
-	ibc::core::{
-		channel::v1::{
-			QueryChannelResponse, QueryChannelsResponse, QueryNextSequenceReceiveResponse,
-			QueryPacketAcknowledgementResponse, QueryPacketCommitmentResponse,
-			QueryPacketReceiptResponse,
-		},
-		client::v1::{
-			IdentifiedClientState, QueryClientStateResponse, QueryConsensusStateResponse,
+	ibc::{
+		applications::transfer::v1::DenomTrace,
+		core::{
+			channel::v1::{
+				QueryChannelResponse, QueryChannelsResponse, QueryNextSequenceReceiveResponse,
+				QueryPacketAcknowledgementResponse, QueryPacketCommitmentResponse,
+				QueryPacketReceiptResponse, QueryUpgradeErrorResponse, QueryUpgradeResponse,
+			},
+			client::v1::{
+				IdentifiedClientState, QueryClientStateResponse, QueryConsensusStateResponse,
+			},
+			connection::v1::{IdentifiedConnection, QueryConnectionResponse},
 		},
-		connection::v1::{IdentifiedConnection, QueryConnectionResponse},
 	},
 };
 use ibc_rpc::{IbcApiClient, PacketInfo};
@@ -54,7 +57,9 @@ use pallet_ibc::{
 	light_clients::{AnyClientState, AnyConsensusState, HostFunctionsManager},
 	HostConsensusProof,
 };
-use primitives::{Chain, IbcProvider, KeyProvider, UpdateType};
+use primitives::{
+	filter::PacketFilter, AccountBalance, Chain, IbcProvider, KeyProvider, UpdateType,
+};
 use sp_core::H256;
 use sp_runtime::{
 	traits::{Header as HeaderT, IdentifyAccount, One, Verify},
@@ -68,6 +73,9 @@ use subxt::tx::{BaseExtrinsicParamsBuilder, ExtrinsicParams};
 #[cfg(not(feature = "dali"))]
 use subxt::tx::PlainTip as Tip;
 
+/// Number of denom traces fetched per rpc request.
+const DENOM_TRACES_PAGE_SIZE: u64 = 100;
+
 pub struct TransactionId<Hash> {
 	pub ext_hash: Hash,
 	pub block_hash: Hash,
@@ -117,7 +125,7 @@ where
 		use pallet_ibc::events::IbcEvent as RawIbcEvent;
 
 		let stream = self
-			.para_client
+			.para_client()
 			.events()
 			.subscribe()
 			.await
@@ -164,7 +172,7 @@ where
 		consensus_height: Height,
 	) -> Result<QueryConsensusStateResponse, Self::Error> {
 		let res = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_client_consensus_state(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			Some(at.revision_height as u32),
 			client_id.to_string(),
 			consensus_height.revision_height,
@@ -183,7 +191,7 @@ where
 	) -> Result<QueryClientStateResponse, Self::Error> {
 		let response =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_client_state(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				at.revision_height as u32,
 				client_id.to_string(),
 			)
@@ -192,13 +200,62 @@ where
 		Ok(response)
 	}
 
+	async fn query_upgraded_client_state(
+		&self,
+		at: Height,
+	) -> Result<QueryClientStateResponse, Self::Error> {
+		let response =
+			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_upgraded_client(
+				&*self.para_ws_client(),
+				at.revision_height as u32,
+			)
+			.await
+			.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		Ok(response)
+	}
+
+	async fn query_upgraded_consensus_state(
+		&self,
+		at: Height,
+	) -> Result<QueryConsensusStateResponse, Self::Error> {
+		let response =
+			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_upgraded_cons_state(
+				&*self.para_ws_client(),
+				at.revision_height as u32,
+			)
+			.await
+			.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		Ok(response)
+	}
+
+	async fn client_upgrades(&self) -> Pin<Box<dyn Stream<Item = ()> + Send + 'static>> {
+		use futures::StreamExt;
+
+		let stream = self
+			.para_client()
+			.events()
+			.subscribe()
+			.await
+			.expect("Failed to subscribe to events")
+			.filter_events::<(parachain::api::ibc::events::ClientUpgradeSet,)>()
+			.filter_map(|result| match result {
+				Ok(_) => futures::future::ready(Some(())),
+				Err(err) => {
+					log::error!("Error in ClientUpgradeSet stream: {err:?}");
+					futures::future::ready(None)
+				},
+			});
+
+		Box::pin(stream)
+	}
+
 	async fn query_connection_end(
 		&self,
 		at: Height,
 		connection_id: ConnectionId,
 	) -> Result<QueryConnectionResponse, Self::Error> {
 		let response = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_connection(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			at.revision_height as u32,
 			connection_id.to_string(),
 		)
@@ -214,7 +271,7 @@ where
 		port_id: PortId,
 	) -> Result<QueryChannelResponse, Self::Error> {
 		let response = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_channel(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			at.revision_height as u32,
 			channel_id.to_string(),
 			port_id.to_string(),
@@ -224,9 +281,45 @@ where
 		Ok(response)
 	}
 
+	async fn query_channel_upgrade(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryUpgradeResponse, Self::Error> {
+		let response =
+			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_channel_upgrade(
+				&*self.para_ws_client(),
+				at.revision_height as u32,
+				channel_id.to_string(),
+				port_id.to_string(),
+			)
+			.await
+			.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		Ok(response)
+	}
+
+	async fn query_channel_upgrade_error(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryUpgradeErrorResponse, Self::Error> {
+		let response =
+			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_channel_upgrade_error(
+				&*self.para_ws_client(),
+				at.revision_height as u32,
+				channel_id.to_string(),
+				port_id.to_string(),
+			)
+			.await
+			.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+		Ok(response)
+	}
+
 	async fn query_proof(&self, at: Height, keys: Vec<Vec<u8>>) -> Result<Vec<u8>, Self::Error> {
 		let proof = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_proof(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			at.revision_height as u32,
 			keys,
 		)
@@ -245,7 +338,7 @@ where
 	) -> Result<QueryPacketCommitmentResponse, Self::Error> {
 		let res =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_packet_commitment(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				at.revision_height as u32,
 				channel_id.to_string(),
 				port_id.to_string(),
@@ -264,7 +357,7 @@ where
 		seq: u64,
 	) -> Result<QueryPacketAcknowledgementResponse, Self::Error> {
 		let res = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_packet_acknowledgement(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			at.revision_height as u32,
 			channel_id.to_string(),
 			port_id.to_string(),
@@ -282,7 +375,7 @@ where
 		channel_id: &ChannelId,
 	) -> Result<QueryNextSequenceReceiveResponse, Self::Error> {
 		let res = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_next_seq_recv(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			at.revision_height as u32,
 			channel_id.to_string(),
 			port_id.to_string(),
@@ -300,7 +393,7 @@ where
 		seq: u64,
 	) -> Result<QueryPacketReceiptResponse, Self::Error> {
 		let res = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_packet_receipt(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			at.revision_height as u32,
 			channel_id.to_string(),
 			port_id.to_string(),
@@ -313,7 +406,7 @@ where
 
 	async fn latest_height_and_timestamp(&self) -> Result<(Height, Timestamp), Self::Error> {
 		let finalized_header = self
-			.para_client
+			.para_client()
 			.rpc()
 			.header(None)
 			.await?
@@ -322,10 +415,11 @@ where
 		let height = Height::new(self.para_id.into(), latest_height.into());
 
 		let subxt_block_number: subxt::rpc::BlockNumber = latest_height.into();
-		let block_hash = self.para_client.rpc().block_hash(Some(subxt_block_number)).await.unwrap();
+		let block_hash =
+			self.para_client().rpc().block_hash(Some(subxt_block_number)).await.unwrap();
 		let timestamp_addr = parachain::api::storage().timestamp().now();
 		let unix_timestamp_millis = self
-			.para_client
+			.para_client()
 			.storage()
 			.fetch(&timestamp_addr, block_hash)
 			.await?
@@ -343,7 +437,7 @@ where
 	) -> Result<Vec<u64>, Self::Error> {
 		let res =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_packet_commitments(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				at.revision_height as u32,
 				channel_id.to_string(),
 				port_id.to_string(),
@@ -360,7 +454,7 @@ where
 		port_id: PortId,
 	) -> Result<Vec<u64>, Self::Error> {
 		let res = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_packet_acknowledgements(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			at.revision_height as u32,
 			channel_id.to_string(),
 			port_id.to_string(),
@@ -383,7 +477,7 @@ where
 	) -> Result<Vec<u64>, Self::Error> {
 		let res =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_unreceived_packets(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				at.revision_height as u32,
 				channel_id.to_string(),
 				port_id.to_string(),
@@ -402,7 +496,7 @@ where
 		seqs: Vec<u64>,
 	) -> Result<Vec<u64>, Self::Error> {
 		let res = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_unreceived_acknowledgements(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			at.revision_height as u32,
 			channel_id.to_string(),
 			port_id.to_string(),
@@ -417,6 +511,10 @@ where
 		self.channel_whitelist.clone()
 	}
 
+	fn packet_filter(&self) -> PacketFilter {
+		self.packet_filter.clone()
+	}
+
 	async fn query_connection_channels(
 		&self,
 		at: Height,
@@ -424,7 +522,7 @@ where
 	) -> Result<QueryChannelsResponse, Self::Error> {
 		let response =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_connection_channels(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				at.revision_height as u32,
 				connection_id.to_string(),
 			)
@@ -441,7 +539,7 @@ where
 	) -> Result<Vec<PacketInfo>, Self::Error> {
 		let response =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_send_packets(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				channel_id.to_string(),
 				port_id.to_string(),
 				seqs,
@@ -459,7 +557,7 @@ where
 	) -> Result<Vec<PacketInfo>, Self::Error> {
 		let response =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_recv_packets(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				channel_id.to_string(),
 				port_id.to_string(),
 				seqs,
@@ -480,7 +578,7 @@ where
 		client_height: Height,
 	) -> Result<(Height, Timestamp), Self::Error> {
 		let response = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_client_update_time_and_height(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			client_id.to_string(),
 			client_height.revision_number,
 			client_height.revision_height,
@@ -498,15 +596,15 @@ where
 		&self,
 		height: Height,
 	) -> Result<Option<Vec<u8>>, Self::Error> {
-		let hash = self.para_client.rpc().block_hash(Some(height.revision_height.into())).await?;
+		let hash = self.para_client().rpc().block_hash(Some(height.revision_height.into())).await?;
 		let header = self
-			.para_client
+			.para_client()
 			.rpc()
 			.header(hash)
 			.await?
 			.ok_or_else(|| Error::Custom("Latest height query returned None".to_string()))?;
 		let extrinsic_with_proof =
-			fetch_timestamp_extrinsic_with_proof(&self.para_client, Some(header.hash()))
+			fetch_timestamp_extrinsic_with_proof(&self.para_client(), Some(header.hash()))
 				.await
 				.map_err(Error::BeefyProver)?;
 
@@ -522,11 +620,11 @@ where
 		let account = self.public_key.clone().into_account();
 		let account_addr = parachain::api::storage().system().account(&account);
 		let balance = self
-			.para_client
+			.para_client()
 			.storage()
 			.fetch(&account_addr, None)
 			.await?
-			.expect("Account data should exist");
+			.ok_or_else(|| Error::from(format!("Account data not found for {account}")))?;
 
 		// todo: how should we handle assets?
 		Ok(vec![PrefixedCoin {
@@ -535,6 +633,71 @@ where
 		}])
 	}
 
+	async fn query_relayer_balances(&self) -> Result<Vec<AccountBalance>, Self::Error> {
+		let para_client = self.para_client();
+		// asset ids of the ibc denoms known to the chain
+		let mut ibc_assets = vec![];
+		for trace in self.query_denom_traces().await? {
+			let denom = PrefixedDenom::try_from(trace)
+				.map_err(|e| Error::from(format!("Invalid denom trace: {e:?}")))?;
+			let key = parachain::api::storage().ibc().ibc_denoms(denom.to_string().as_bytes());
+			if let Some(asset_id) = para_client.storage().fetch(&key, None).await? {
+				ibc_assets.push((denom, asset_id));
+			}
+		}
+
+		let mut balances = vec![];
+		for pool_account in self.signer_pool.accounts() {
+			let account = pool_account.key_source.public_key().into_account();
+			// accounts that don't exist yet have a zero balance
+			let native = para_client
+				.storage()
+				.fetch_or_default(&parachain::api::storage().system().account(&account), None)
+				.await?;
+			let mut coins = vec![PrefixedCoin {
+				denom: PrefixedDenom::from_str("UNIT")?,
+				amount: Amount::from_str(&native.data.free.to_string())?,
+			}];
+			for (denom, asset_id) in &ibc_assets {
+				let key = parachain::api::storage().assets().account(asset_id, &account);
+				let amount = para_client
+					.storage()
+					.fetch(&key, None)
+					.await?
+					.map(|asset_account| asset_account.balance)
+					.unwrap_or_default();
+				coins.push(PrefixedCoin {
+					denom: denom.clone(),
+					amount: Amount::from_str(&amount.to_string())?,
+				});
+			}
+			balances.push(AccountBalance { account: account.to_string(), balances: coins });
+		}
+		Ok(balances)
+	}
+
+	async fn query_denom_traces(&self) -> Result<Vec<DenomTrace>, Self::Error> {
+		let mut denom_traces = vec![];
+		loop {
+			let response =
+				IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_denom_traces(
+					&*self.para_ws_client(),
+					None,
+					Some(denom_traces.len() as u32),
+					Some(DENOM_TRACES_PAGE_SIZE),
+					false,
+				)
+				.await
+				.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+			let page_size = response.denom_traces.len() as u64;
+			denom_traces.extend(response.denom_traces);
+			if page_size < DENOM_TRACES_PAGE_SIZE {
+				break
+			}
+		}
+		Ok(denom_traces)
+	}
+
 	fn connection_prefix(&self) -> CommitmentPrefix {
 		CommitmentPrefix::try_from(self.commitment_prefix.clone()).expect("Should not fail")
 	}
@@ -556,10 +719,11 @@ where
 
 	async fn query_timestamp_at(&self, block_number: u64) -> Result<u64, Self::Error> {
 		let subxt_block_number: subxt::rpc::BlockNumber = block_number.into();
-		let block_hash = self.para_client.rpc().block_hash(Some(subxt_block_number)).await.unwrap();
+		let block_hash =
+			self.para_client().rpc().block_hash(Some(subxt_block_number)).await.unwrap();
 		let timestamp_addr = parachain::api::storage().timestamp().now();
 		let unix_timestamp_millis = self
-			.para_client
+			.para_client()
 			.storage()
 			.fetch(&timestamp_addr, block_hash)
 			.await?
@@ -572,7 +736,7 @@ where
 	async fn query_clients(&self) -> Result<Vec<ClientId>, Self::Error> {
 		let response: Vec<IdentifiedClientState> =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_clients(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 			)
 			.await
 			.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
@@ -587,7 +751,7 @@ where
 
 	async fn query_channels(&self) -> Result<Vec<(ChannelId, PortId)>, Self::Error> {
 		let response = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_channels(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 		)
 		.await
 		.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
@@ -611,7 +775,7 @@ where
 		client_id: String,
 	) -> Result<Vec<IdentifiedConnection>, Self::Error> {
 		let response = IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_connection_using_client(
-			&*self.para_ws_client,
+			&*self.para_ws_client(),
 			height,
 			client_id,
 		)
@@ -653,7 +817,7 @@ where
 		let TransactionId { ext_hash, block_hash } = tx_id;
 		let identified_client_state =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_newly_created_client(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				block_hash.into(),
 				ext_hash.into(),
 			)
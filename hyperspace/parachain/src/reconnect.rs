@@ -0,0 +1,412 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Websocket connections and justification subscriptions that survive node restarts and network
+//! failures.
+
+use crate::{
+	chain::{BeefyJustification, GrandpaJustification, JustificationNotification},
+	error::Error,
+	finality_protocol::FinalityProtocol,
+	utils::unsafe_cast_to_jsonrpsee_client,
+};
+use beefy_gadget_rpc::BeefyApiClient;
+use codec::Decode;
+use finality_grandpa_rpc::GrandpaApiClient;
+use futures::{stream, Stream, StreamExt};
+use grandpa_light_client_primitives::FinalityProof;
+use ics10_grandpa::client_message::RelayChainHeader;
+use jsonrpsee_ws_client::{WsClient, WsClientBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+	pin::Pin,
+	sync::{Arc, RwLock, Weak},
+	time::Duration,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Number of justifications buffered before the subscription waits for them to be consumed.
+const JUSTIFICATION_CHANNEL_CAPACITY: usize = 32;
+/// Interval at which connections are checked for having dropped.
+const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Backoff between the attempts to reconnect to an rpc node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconnectConfig {
+	/// Delay in milliseconds before the first attempt
+	#[serde(default = "default_initial_backoff")]
+	pub initial_backoff_ms: u64,
+	/// Maximum delay in milliseconds between two attempts, the delay doubles after every attempt
+	#[serde(default = "default_max_backoff")]
+	pub max_backoff_ms: u64,
+	/// Attempts after which the connection is given up, retries forever if not set
+	#[serde(default)]
+	pub max_attempts: Option<u32>,
+}
+
+fn default_initial_backoff() -> u64 {
+	1_000
+}
+
+fn default_max_backoff() -> u64 {
+	60_000
+}
+
+impl Default for ReconnectConfig {
+	fn default() -> Self {
+		Self {
+			initial_backoff_ms: default_initial_backoff(),
+			max_backoff_ms: default_max_backoff(),
+			max_attempts: None,
+		}
+	}
+}
+
+/// Connects to the first reachable endpoint, cycling through all of them with the configured
+/// backoff.
+pub async fn connect(urls: &[String], config: &ReconnectConfig) -> Result<WsClient, Error> {
+	if urls.is_empty() {
+		Err(Error::Custom("No rpc endpoint configured".to_owned()))?
+	}
+	let mut backoff = Duration::from_millis(config.initial_backoff_ms);
+	let mut attempts = 0;
+	loop {
+		for url in urls {
+			match WsClientBuilder::default().build(url).await {
+				Ok(client) => return Ok(client),
+				Err(err) => log::warn!("Failed to connect to {url}: {err:?}"),
+			}
+		}
+		attempts += 1;
+		if config.max_attempts.map(|max| attempts >= max).unwrap_or(false) {
+			Err(Error::from(format!("Rpc Error: failed to connect to any of {urls:?}")))?
+		}
+		log::info!("Reconnecting to {urls:?} in {backoff:?}");
+		tokio::time::sleep(backoff).await;
+		backoff = (backoff * 2).min(Duration::from_millis(config.max_backoff_ms));
+	}
+}
+
+/// A connection to a node that can be re-established.
+#[async_trait::async_trait]
+pub trait Connect: Sized + Send + Sync + 'static {
+	/// Connects to the first reachable endpoint, see [`connect`].
+	async fn connect(urls: &[String], config: &ReconnectConfig) -> Result<Self, Error>;
+
+	/// Returns false once the connection has dropped.
+	fn is_connected(&self) -> bool;
+}
+
+#[async_trait::async_trait]
+impl Connect for WsClient {
+	async fn connect(urls: &[String], config: &ReconnectConfig) -> Result<Self, Error> {
+		connect(urls, config).await
+	}
+
+	fn is_connected(&self) -> bool {
+		WsClient::is_connected(self)
+	}
+}
+
+/// A websocket connection along with the subxt client built on top of it.
+pub struct RpcConnection<T: subxt::Config> {
+	pub ws_client: Arc<WsClient>,
+	pub client: subxt::OnlineClient<T>,
+}
+
+#[async_trait::async_trait]
+impl<T: subxt::Config + Send + Sync> Connect for RpcConnection<T> {
+	async fn connect(urls: &[String], config: &ReconnectConfig) -> Result<Self, Error> {
+		let ws_client = Arc::new(connect(urls, config).await?);
+		let client = subxt::OnlineClient::from_rpc_client(unsafe {
+			unsafe_cast_to_jsonrpsee_client(&ws_client)
+		})
+		.await?;
+		Ok(Self { ws_client, client })
+	}
+
+	fn is_connected(&self) -> bool {
+		self.ws_client.is_connected()
+	}
+}
+
+/// A connection that is replaced by a new one, cycling through the endpoints, whenever it drops.
+/// Requests made while reconnecting fail, the next ones go through the new connection.
+pub struct Reconnecting<C> {
+	current: RwLock<Arc<C>>,
+	urls: Vec<String>,
+	config: ReconnectConfig,
+}
+
+impl<C: Connect> Reconnecting<C> {
+	/// Connects to the first reachable endpoint and watches the connection until the returned
+	/// handle is dropped.
+	pub async fn connect(urls: Vec<String>, config: ReconnectConfig) -> Result<Arc<Self>, Error> {
+		let connection = C::connect(&urls, &config).await?;
+		let this = Arc::new(Self { current: RwLock::new(Arc::new(connection)), urls, config });
+		tokio::spawn(reconnect_on_disconnect(Arc::downgrade(&this)));
+		Ok(this)
+	}
+
+	/// Returns the current connection.
+	pub fn current(&self) -> Arc<C> {
+		self.current.read().unwrap().clone()
+	}
+}
+
+async fn reconnect_on_disconnect<C: Connect>(connection: Weak<Reconnecting<C>>) {
+	loop {
+		tokio::time::sleep(CONNECTION_CHECK_INTERVAL).await;
+		let connection = match connection.upgrade() {
+			Some(connection) => connection,
+			// every handle was dropped
+			None => return,
+		};
+		if connection.current().is_connected() {
+			continue
+		}
+		log::warn!("Connection to {:?} dropped, reconnecting", connection.urls);
+		match C::connect(&connection.urls, &connection.config).await {
+			Ok(new) => {
+				*connection.current.write().unwrap() = Arc::new(new);
+				log::info!("Reconnected to {:?}", connection.urls);
+			},
+			Err(err) => log::error!("Failed to reconnect to {:?}: {err:?}", connection.urls),
+		}
+	}
+}
+
+type EncodedJustifications = Pin<Box<dyn Stream<Item = Result<Vec<u8>, String>> + Send>>;
+
+struct SubscriptionState {
+	client: Arc<WsClient>,
+	subscription: Option<EncodedJustifications>,
+	/// Block number of the last justification that was yielded
+	last_finalized: Option<u32>,
+}
+
+/// Returns the encoded justifications of the relay chain for the given finality protocol. The
+/// subscription is re-established over a new connection whenever it ends, and the stream only ends
+/// once reconnecting is given up.
+///
+/// Justifications sent while disconnected are lost, so after re-subscribing to GRANDPA
+/// justifications, a justification for the first block after the last finalized one is fetched,
+/// which covers the missed blocks.
+pub fn justifications(
+	client: Arc<WsClient>,
+	urls: Vec<String>,
+	config: ReconnectConfig,
+	protocol: FinalityProtocol,
+) -> impl Stream<Item = Vec<u8>> + Send + Sync {
+	// the subscription is driven from its own task, so the returned stream doesn't have to hold
+	// the rpc futures.
+	let (sender, receiver) = mpsc::channel(JUSTIFICATION_CHANNEL_CAPACITY);
+	tokio::spawn(async move {
+		let mut justifications =
+			Box::pin(resubscribing_justifications(client, urls, config, protocol));
+		while let Some(justification) = justifications.next().await {
+			if sender.send(justification).await.is_err() {
+				// the stream was dropped
+				break
+			}
+		}
+	});
+	ReceiverStream::new(receiver)
+}
+
+fn resubscribing_justifications(
+	client: Arc<WsClient>,
+	urls: Vec<String>,
+	config: ReconnectConfig,
+	protocol: FinalityProtocol,
+) -> impl Stream<Item = Vec<u8>> + Send {
+	let state = SubscriptionState { client, subscription: None, last_finalized: None };
+	stream::unfold(state, move |mut state| {
+		let (urls, config, protocol) = (urls.clone(), config.clone(), protocol.clone());
+		async move {
+			loop {
+				if state.subscription.is_none() {
+					match subscribe(&state.client, &protocol).await {
+						Ok(subscription) => state.subscription = Some(subscription),
+						Err(err) => {
+							log::error!(
+								"Failed to subscribe to {protocol:?} justifications: {err:?}"
+							);
+							state.client = Arc::new(reconnect(&urls, &config).await?);
+							continue
+						},
+					}
+				}
+				let subscription = state.subscription.as_mut().expect("subscribed above; qed");
+
+				match subscription.next().await {
+					Some(Ok(justification)) => {
+						let block_number = block_number(&protocol, &justification);
+						if block_number.is_some() {
+							state.last_finalized = block_number;
+						}
+						return Some((justification, state))
+					},
+					Some(Err(err)) if state.client.is_connected() => {
+						log::error!("Failed to fetch {protocol:?} justification: {err}");
+						continue
+					},
+					_ => {},
+				}
+
+				log::warn!(
+					"{protocol:?} justification subscription ended after block {:?}, reconnecting",
+					state.last_finalized
+				);
+				state.subscription = None;
+				state.client = Arc::new(reconnect(&urls, &config).await?);
+				let last_finalized = match state.last_finalized {
+					Some(last_finalized) => last_finalized,
+					None => continue,
+				};
+				match catch_up(&state.client, &protocol, last_finalized).await {
+					Ok(Some((block_number, justification))) => {
+						log::info!(
+							"Resuming {protocol:?} justifications from block {last_finalized} with a justification for block {block_number}"
+						);
+						state.last_finalized = Some(block_number);
+						return Some((justification, state))
+					},
+					Ok(None) => log::info!(
+						"Resuming {protocol:?} justifications after block {last_finalized}, the next justification covers the blocks finalized while disconnected"
+					),
+					Err(err) => log::error!(
+						"Failed to fetch the justifications missed after block {last_finalized}: {err:?}, the next justification covers them"
+					),
+				}
+			}
+		}
+	})
+}
+
+/// Connects to one of the endpoints, logging the error if reconnecting is given up.
+async fn reconnect(urls: &[String], config: &ReconnectConfig) -> Option<WsClient> {
+	connect(urls, config)
+		.await
+		.map_err(|err| log::error!("Giving up on reconnecting: {err:?}"))
+		.ok()
+}
+
+async fn subscribe(
+	client: &WsClient,
+	protocol: &FinalityProtocol,
+) -> Result<EncodedJustifications, Error> {
+	let subscription = match protocol {
+		FinalityProtocol::Grandpa => GrandpaApiClient::<
+			JustificationNotification,
+			sp_core::H256,
+			u32,
+		>::subscribe_justifications(client)
+		.await,
+		FinalityProtocol::Beefy =>
+			BeefyApiClient::<JustificationNotification, sp_core::H256>::subscribe_justifications(
+				client,
+			)
+			.await,
+	}
+	.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+	Ok(Box::pin(subscription.map(|notification| {
+		notification
+			.map(|JustificationNotification(sp_core::Bytes(justification))| justification)
+			.map_err(|e| e.to_string())
+	})))
+}
+
+/// Returns the block number finalized by the encoded justification.
+fn block_number(protocol: &FinalityProtocol, justification: &[u8]) -> Option<u32> {
+	match protocol {
+		FinalityProtocol::Grandpa => GrandpaJustification::decode(&mut &*justification)
+			.ok()
+			.map(|justification| justification.commit.target_number),
+		FinalityProtocol::Beefy => BeefyJustification::decode(&mut &*justification)
+			.ok()
+			.map(|signed_commitment| signed_commitment.commitment.block_number),
+	}
+}
+
+/// Fetches a justification for the first block after `last_finalized`, if it has been finalized.
+/// Only GRANDPA supports proving the finality of past blocks.
+async fn catch_up(
+	client: &WsClient,
+	protocol: &FinalityProtocol,
+	last_finalized: u32,
+) -> Result<Option<(u32, Vec<u8>)>, Error> {
+	if !matches!(protocol, FinalityProtocol::Grandpa) {
+		return Ok(None)
+	}
+	let encoded =
+		GrandpaApiClient::<JustificationNotification, sp_core::H256, u32>::prove_finality(
+			client,
+			last_finalized + 1,
+		)
+		.await
+		.map_err(|e| Error::from(format!("Rpc Error {:?}", e)))?;
+	let encoded = match encoded {
+		Some(encoded) => encoded.0,
+		None => return Ok(None),
+	};
+	let finality_proof = FinalityProof::<RelayChainHeader>::decode(&mut &*encoded)?;
+	let justification = finality_proof.justification;
+	Ok(block_number(protocol, &justification)
+		.filter(|block_number| *block_number > last_finalized)
+		.map(|block_number| (block_number, justification)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpsee::{
+		ws_server::{WsServerBuilder, WsServerHandle},
+		RpcModule,
+	};
+
+	async fn serve() -> (String, WsServerHandle) {
+		let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+		let url = format!("ws://{}", server.local_addr().unwrap());
+		let handle = server.start(RpcModule::new(())).unwrap();
+		(url, handle)
+	}
+
+	#[tokio::test]
+	async fn reconnects_to_fallback_when_socket_drops() {
+		let (primary_url, primary) = serve().await;
+		let (fallback_url, _fallback) = serve().await;
+		let config =
+			ReconnectConfig { initial_backoff_ms: 10, max_backoff_ms: 10, max_attempts: None };
+		let connection = Reconnecting::<WsClient>::connect(vec![primary_url, fallback_url], config)
+			.await
+			.unwrap();
+		let dropped = connection.current();
+		assert!(dropped.is_connected());
+
+		// drops the socket, the primary endpoint can't be reached anymore
+		let _ = primary.stop();
+		tokio::time::timeout(Duration::from_secs(10), async {
+			while Arc::ptr_eq(&connection.current(), &dropped) {
+				tokio::time::sleep(Duration::from_millis(50)).await;
+			}
+		})
+		.await
+		.expect("should reconnect after the socket drops");
+
+		assert!(!dropped.is_connected());
+		assert!(connection.current().is_connected());
+	}
+}
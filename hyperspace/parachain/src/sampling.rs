@@ -0,0 +1,317 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sampling of the finality notifications that the relayer acts upon.
+
+use crate::{
+	config,
+	finality_protocol::{FinalityEvent, FinalityProtocol},
+	grandpa_prover,
+	reconnect::{Reconnecting, RpcConnection},
+};
+use anyhow::anyhow;
+use futures::{Stream, StreamExt};
+use grandpa_prover::{polkadot, GrandpaProver};
+use ibc::events::IbcEvent;
+use ibc_rpc::{BlockNumberOrHash, IbcApiClient};
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::traits::{Header as HeaderT, Zero};
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Number of sampled finality events buffered before sampling waits for them to be consumed.
+const SAMPLED_CHANNEL_CAPACITY: usize = 16;
+
+/// Policy for picking the finality notifications the relayer acts upon, e.g:
+/// ```toml
+/// finality_sampling = { policy = "on_demand", max_interval_secs = 600 }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum FinalitySampling {
+	/// Every notification, for latency sensitive channels
+	All,
+	/// The last of every `n` notifications
+	EveryN { n: usize },
+	/// At most one notification every `interval_secs` seconds
+	Interval { interval_secs: u64 },
+	/// Only the notifications that finalize parachain blocks with ibc events or an authority set
+	/// change, or that are at least `max_interval_secs` seconds apart if set, so the client on the
+	/// counterparty keeps advancing for timeouts. Unlike the other policies, this queries the
+	/// chains for every notification.
+	OnDemand { max_interval_secs: Option<u64> },
+}
+
+impl FinalitySampling {
+	/// Sampling used when none is configured: the last of every 6 GRANDPA justifications, or
+	/// every BEEFY commitment.
+	pub fn default_for(protocol: &FinalityProtocol) -> Self {
+		match protocol {
+			FinalityProtocol::Grandpa => Self::EveryN { n: 6 },
+			FinalityProtocol::Beefy => Self::All,
+		}
+	}
+}
+
+/// Notifications seen by a [`FinalitySampling`] policy.
+#[derive(Debug, Default)]
+struct SamplingState {
+	/// Notifications received since the last sampled one
+	received: usize,
+	last_sampled_at: Option<Instant>,
+}
+
+impl SamplingState {
+	/// Records a notification received at `now`, returning true if it's sampled. `required` is
+	/// whether it must be relayed for the [`FinalitySampling::OnDemand`] policy, it's ignored by
+	/// the others.
+	fn sample(&mut self, policy: &FinalitySampling, now: Instant, required: bool) -> bool {
+		self.received += 1;
+		let interval_elapsed = |interval_secs: u64| {
+			self.last_sampled_at
+				.map(|at| now.saturating_duration_since(at) >= Duration::from_secs(interval_secs))
+				.unwrap_or(true)
+		};
+		let sampled = match *policy {
+			FinalitySampling::All => true,
+			FinalitySampling::EveryN { n } => self.received >= n,
+			FinalitySampling::Interval { interval_secs } => interval_elapsed(interval_secs),
+			FinalitySampling::OnDemand { max_interval_secs } =>
+				required || max_interval_secs.map(interval_elapsed).unwrap_or(false),
+		};
+		if sampled {
+			self.received = 0;
+			self.last_sampled_at = Some(now);
+		}
+		sampled
+	}
+}
+
+/// Applies a [`FinalitySampling`] policy to the finality events of a parachain.
+pub struct FinalitySampler<T: config::Config> {
+	policy: FinalitySampling,
+	relay_rpc: Arc<Reconnecting<RpcConnection<T>>>,
+	para_rpc: Arc<Reconnecting<RpcConnection<T>>>,
+	para_id: u32,
+	state: SamplingState,
+	/// Authority set id finalized by the last notification
+	last_set_id: Option<u64>,
+	/// Last parachain block checked for ibc events
+	last_checked_para_height: Option<u32>,
+}
+
+impl<T> FinalitySampler<T>
+where
+	T: config::Config + Send + Sync,
+	T::BlockNumber: Ord + Zero,
+	u32: From<T::BlockNumber> + From<<T::Header as HeaderT>::Number>,
+{
+	pub fn new(
+		policy: FinalitySampling,
+		relay_rpc: Arc<Reconnecting<RpcConnection<T>>>,
+		para_rpc: Arc<Reconnecting<RpcConnection<T>>>,
+		para_id: u32,
+	) -> Self {
+		Self {
+			policy,
+			relay_rpc,
+			para_rpc,
+			para_id,
+			state: SamplingState::default(),
+			last_set_id: None,
+			last_checked_para_height: None,
+		}
+	}
+
+	/// Returns true if the relayer should act upon the finality event. Only the
+	/// [`FinalitySampling::OnDemand`] policy queries the chains, checks that fail are logged and
+	/// the event is sampled, so that no update is missed.
+	pub async fn sample(&mut self, event: &FinalityEvent) -> bool {
+		let required = match self.policy {
+			FinalitySampling::OnDemand { .. } => {
+				let authority_set_changed =
+					self.authority_set_changed(event).await.unwrap_or_else(|err| {
+						log::warn!("Failed to check for an authority set change: {err:?}");
+						true
+					});
+				let has_ibc_events = self.has_ibc_events(event).await.unwrap_or_else(|err| {
+					log::warn!("Failed to check for new ibc events: {err:?}");
+					true
+				});
+				authority_set_changed || has_ibc_events
+			},
+			_ => false,
+		};
+		self.state.sample(&self.policy, Instant::now(), required)
+	}
+
+	/// Returns a prover over the current rpc connections, which are replaced when they drop.
+	fn prover(&self) -> GrandpaProver<T> {
+		grandpa_prover(&self.relay_rpc, &self.para_rpc, self.para_id)
+	}
+
+	/// Returns true if the event finalizes a different authority set than the previous one.
+	async fn authority_set_changed(&mut self, event: &FinalityEvent) -> anyhow::Result<bool> {
+		let set_id = match event {
+			FinalityEvent::Grandpa(justification) => {
+				let relay_client = self.relay_rpc.current().client.clone();
+				let hash = relay_client
+					.rpc()
+					.block_hash(Some(justification.commit.target_number.into()))
+					.await?
+					.ok_or_else(|| {
+						anyhow!(
+							"Block hash not found for number: {}",
+							justification.commit.target_number
+						)
+					})?;
+				let key = polkadot::api::storage().grandpa().current_set_id();
+				relay_client
+					.storage()
+					.fetch(&key, Some(hash))
+					.await?
+					.ok_or_else(|| anyhow!("Authority set id not found at {hash:?}"))?
+			},
+			FinalityEvent::Beefy(signed_commitment) =>
+				signed_commitment.commitment.validator_set_id,
+		};
+		let changed = self.last_set_id.map(|last_set_id| last_set_id != set_id).unwrap_or(false);
+		self.last_set_id = Some(set_id);
+		Ok(changed)
+	}
+
+	/// Returns true if the parachain blocks finalized since the last check contain ibc events
+	/// other than client updates.
+	async fn has_ibc_events(&mut self, event: &FinalityEvent) -> anyhow::Result<bool> {
+		let relay_height = match event {
+			FinalityEvent::Grandpa(justification) => justification.commit.target_number,
+			FinalityEvent::Beefy(signed_commitment) => signed_commitment.commitment.block_number,
+		};
+		let finalized_para_height = u32::from(
+			*self
+				.prover()
+				.query_latest_finalized_parachain_header(relay_height)
+				.await?
+				.number(),
+		);
+		let last_checked = match self.last_checked_para_height.replace(finalized_para_height) {
+			Some(last_checked) => last_checked,
+			// events left over from a previous run may be pending
+			None => return Ok(true),
+		};
+		if finalized_para_height <= last_checked {
+			return Ok(false)
+		}
+
+		let block_numbers = ((last_checked + 1)..=finalized_para_height)
+			.map(BlockNumberOrHash::Number)
+			.collect::<Vec<_>>();
+		let events: HashMap<String, Vec<IbcEvent>> =
+			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_events(
+				&*self.para_rpc.current().ws_client,
+				block_numbers,
+			)
+			.await?;
+		Ok(events
+			.values()
+			.flatten()
+			.any(|event| !matches!(event, IbcEvent::UpdateClient(_) | IbcEvent::NewBlock(_))))
+	}
+}
+
+/// Returns the finality events picked by the sampler. Sampling queries the chain, so it's driven
+/// from its own task.
+pub fn sample<T>(
+	events: impl Stream<Item = FinalityEvent> + Send + 'static,
+	mut sampler: FinalitySampler<T>,
+) -> impl Stream<Item = FinalityEvent> + Send + Sync
+where
+	T: config::Config + Send + Sync + 'static,
+	T::BlockNumber: Ord + Zero,
+	u32: From<T::BlockNumber> + From<<T::Header as HeaderT>::Number>,
+{
+	let (sender, receiver) = mpsc::channel(SAMPLED_CHANNEL_CAPACITY);
+	tokio::spawn(async move {
+		let mut events = Box::pin(events);
+		while let Some(event) = events.next().await {
+			if !sampler.sample(&event).await {
+				continue
+			}
+			if sender.send(event).await.is_err() {
+				// the stream was dropped
+				break
+			}
+		}
+	});
+	ReceiverStream::new(receiver)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Returns the indices of the sampled notifications, received `spacing` apart.
+	fn sampled(
+		policy: FinalitySampling,
+		spacing: Duration,
+		required: impl Fn(usize) -> bool,
+	) -> Vec<usize> {
+		let mut state = SamplingState::default();
+		let start = Instant::now();
+		(0..12)
+			.filter(|i| state.sample(&policy, start + spacing * *i as u32, required(*i)))
+			.collect()
+	}
+
+	#[test]
+	fn all_samples_every_notification() {
+		let sampled = sampled(FinalitySampling::All, Duration::from_secs(1), |_| false);
+		assert_eq!(sampled, (0..12).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn every_n_samples_the_last_of_every_n_notifications() {
+		let sampled = sampled(FinalitySampling::EveryN { n: 4 }, Duration::from_secs(1), |_| true);
+		assert_eq!(sampled, vec![3, 7, 11]);
+	}
+
+	#[test]
+	fn interval_samples_at_most_once_per_interval() {
+		let policy = FinalitySampling::Interval { interval_secs: 5 };
+		let sampled = sampled(policy, Duration::from_secs(2), |_| false);
+		// the first notification is always sampled
+		assert_eq!(sampled, vec![0, 3, 6, 9]);
+	}
+
+	#[test]
+	fn on_demand_samples_required_notifications() {
+		let policy = FinalitySampling::OnDemand { max_interval_secs: None };
+		let sampled = sampled(policy, Duration::from_secs(60), |i| i % 5 == 2);
+		assert_eq!(sampled, vec![2, 7]);
+	}
+
+	#[test]
+	fn on_demand_samples_after_max_interval() {
+		let policy = FinalitySampling::OnDemand { max_interval_secs: Some(10) };
+		let sampled = sampled(policy, Duration::from_secs(3), |i| i == 5);
+		// sampled after at least 10 seconds, or when required
+		assert_eq!(sampled, vec![0, 4, 5, 9]);
+	}
+}
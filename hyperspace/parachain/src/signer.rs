@@ -12,26 +12,28 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_core::sr25519;
 use sp_runtime::{
-	app_crypto::CryptoTypePublicPair,
 	traits::{IdentifyAccount, Verify},
-	KeyTypeId, MultiSignature, MultiSigner,
+	MultiSignature, MultiSigner,
 };
+use std::sync::{Arc, Mutex};
 use subxt::tx::Signer;
 
-use crate::config;
-use codec::Decode;
+use crate::{config, error::Error, key_source::KeySource};
 use primitives::KeyProvider;
 
 /// A [`Signer`] implementation.
+///
+/// Signing through a [`KeySource`] may fail, e.g when a remote signer is unreachable, but
+/// [`Signer::sign`] can't return an error. The error is kept instead, and must be checked with
+/// [`ExtrinsicSigner::check`] before the signed extrinsic is submitted.
 #[derive(Clone)]
 pub struct ExtrinsicSigner<T: config::Config, Provider: KeyProvider> {
 	account_id: T::AccountId,
 	nonce: Option<T::Index>,
-	signer: MultiSigner,
-	key_store: SyncCryptoStorePtr,
-	key_type_id: KeyTypeId,
+	key_source: Arc<dyn KeySource>,
+	error: Arc<Mutex<Option<Error>>>,
 	_phantom: std::marker::PhantomData<Provider>,
 }
 
@@ -40,24 +42,34 @@ where
 	T: config::Config,
 	<T::Signature as Verify>::Signer: From<MultiSigner> + IdentifyAccount<AccountId = T::AccountId>,
 	P: KeyProvider,
-	MultiSigner: From<MultiSigner>,
 {
-	/// Creates a new [`Signer`] from a key store reference and key type
-	pub fn new(
-		key_store: SyncCryptoStorePtr,
-		key_type_id: KeyTypeId,
-		public_key: MultiSigner,
-	) -> Self {
-		let account_id = <T::Signature as Verify>::Signer::from(public_key.clone()).into_account();
+	/// Creates a new [`Signer`] that signs through the given key source
+	pub fn new(key_source: Arc<dyn KeySource>) -> Self {
+		let account_id =
+			<T::Signature as Verify>::Signer::from(key_source.public_key()).into_account();
 		Self {
 			account_id,
 			nonce: None,
-			key_store,
-			key_type_id,
-			signer: MultiSigner::from(public_key),
+			key_source,
+			error: Default::default(),
 			_phantom: Default::default(),
 		}
 	}
+
+	/// Signs with the given nonce instead of querying it from the chain
+	pub fn with_nonce(mut self, nonce: T::Index) -> Self {
+		self.nonce = Some(nonce);
+		self
+	}
+
+	/// Returns the error the last payload failed to be signed with, if any. Extrinsics signed
+	/// since that failure carry an invalid signature.
+	pub fn check(&self) -> Result<(), Error> {
+		match self.error.lock().unwrap().take() {
+			Some(error) => Err(error),
+			None => Ok(()),
+		}
+	}
 }
 
 impl<T, P> Signer<T> for ExtrinsicSigner<T, P>
@@ -80,28 +92,14 @@ where
 	}
 
 	fn sign(&self, signer_payload: &[u8]) -> T::Signature {
-		let (crypto_type_id, public_key) = match &self.signer {
-			MultiSigner::Ed25519(key) => (sp_core::ed25519::CRYPTO_ID, key.0.to_vec()),
-			MultiSigner::Sr25519(key) => (sp_core::sr25519::CRYPTO_ID, key.0.to_vec()),
-			MultiSigner::Ecdsa(key) => (sp_core::ecdsa::CRYPTO_ID, key.0.to_vec()),
-		};
-		let key = CryptoTypePublicPair(crypto_type_id, public_key);
-		let encoded_sig =
-			SyncCryptoStore::sign_with(&*self.key_store, self.key_type_id, &key, signer_payload)
-				.ok()
-				.flatten()
-				.expect("Signing should not fail");
-		let signature: MultiSignature = match self.signer {
-			MultiSigner::Ed25519(_) => sp_core::ed25519::Signature::decode(&mut &encoded_sig[..])
-				.expect("Should decode same signature type as public key; qed")
-				.into(),
-			MultiSigner::Sr25519(_) => sp_core::sr25519::Signature::decode(&mut &encoded_sig[..])
-				.expect("Should decode same signature type as public key; qed")
-				.into(),
-			MultiSigner::Ecdsa(_) => sp_core::ecdsa::Signature::decode(&mut &encoded_sig[..])
-				.expect("Should decode same signature type as public key; qed")
-				.into(),
-		};
-		signature.into()
+		match self.key_source.sign(signer_payload) {
+			Ok(signature) => signature.into(),
+			Err(error) => {
+				log::error!("Failed to sign extrinsic: {error}");
+				*self.error.lock().unwrap() = Some(error);
+				// the signer api is infallible, the error is reported through `check`
+				MultiSignature::Sr25519(sr25519::Signature::from_raw([0; 64])).into()
+			},
+		}
 	}
 }
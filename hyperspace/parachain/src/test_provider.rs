@@ -81,7 +81,7 @@ where
 		// Query newly created client Id
 		let identified_client_state =
 			IbcApiClient::<u32, H256, <T as config::Config>::AssetId>::query_newly_created_client(
-				&*self.para_ws_client,
+				&*self.para_ws_client(),
 				block_hash.into(),
 				ext_hash.into(),
 			)
@@ -115,6 +115,7 @@ where
 				Timeout::Absolute { timestamp, height } =>
 					api::runtime_types::ibc_primitives::Timeout::Absolute { timestamp, height },
 			},
+			memo: params.memo,
 		};
 
 		#[cfg(feature = "dali")]
@@ -129,21 +130,17 @@ where
 	}
 
 	pub async fn submit_sudo_call(&self, call: Call) -> Result<(), Error> {
-		let signer = ExtrinsicSigner::<T, Self>::new(
-			self.key_store.clone(),
-			self.key_type_id.clone(),
-			self.public_key.clone(),
-		);
+		let signer = ExtrinsicSigner::<T, Self>::new(self.key_source.clone());
 
 		let ext = api::tx().sudo().sudo(call);
 		// Submit extrinsic to parachain node
 
-		let other_params = T::custom_extrinsic_params(&self.para_client).await?;
+		let other_params = T::custom_extrinsic_params(&self.para_client()).await?;
 
-		let _progress = self
-			.para_client
-			.tx()
-			.sign_and_submit_then_watch(&ext, &signer, other_params)
+		let extrinsic = self.para_client().tx().create_signed(&ext, &signer, other_params).await?;
+		signer.check()?;
+		let _progress = extrinsic
+			.submit_and_watch()
 			.await?
 			.wait_for_in_block()
 			.await?
@@ -198,6 +195,7 @@ where
 				timestamp: Some(transfer.timeout_timestamp.nanoseconds()),
 				height: Some(transfer.timeout_height.revision_height),
 			},
+			memo: if transfer.memo.is_empty() { None } else { Some(transfer.memo.into_bytes()) },
 		};
 		let amount = str::parse::<u128>(&transfer.token.amount.to_string()).expect("Infallible!");
 		dbg!(&amount);
@@ -229,7 +227,7 @@ where
 	}
 
 	async fn subscribe_blocks(&self) -> Pin<Box<dyn Stream<Item = u64> + Send + Sync>> {
-		let para_client = unsafe { unsafe_cast_to_jsonrpsee_client(&self.para_ws_client) };
+		let para_client = unsafe { unsafe_cast_to_jsonrpsee_client(&self.para_ws_client()) };
 		let stream = para_client
 			.subscribe::<T::Header>("chain_subscribeNewHeads", None, "chain_unsubscribeNewHeads")
 			.await
@@ -348,6 +348,25 @@ impl From<MetadataIbcEventWrapper> for RawIbcEvent {
 			},
 			MetadataIbcEvent::AppModule { kind, module_id } =>
 				RawIbcEvent::AppModule { kind, module_id },
+			MetadataIbcEvent::ChannelUpgrade {
+				kind,
+				revision_height,
+				revision_number,
+				port_id,
+				channel_id,
+				counterparty_port_id,
+				counterparty_channel_id,
+				upgrade_sequence,
+			} => RawIbcEvent::ChannelUpgrade {
+				kind,
+				revision_height,
+				revision_number,
+				port_id,
+				channel_id,
+				counterparty_port_id,
+				counterparty_channel_id,
+				upgrade_sequence,
+			},
 			MetadataIbcEvent::Empty => RawIbcEvent::Empty,
 			MetadataIbcEvent::ChainError => RawIbcEvent::ChainError,
 		}
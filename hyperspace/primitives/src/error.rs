@@ -50,3 +50,34 @@ impl From<String> for Error {
 		Self::Custom(error)
 	}
 }
+
+/// A finality event at or below the latest height of the counterparty client, its ibc events
+/// were already relayed along with a later client update.
+#[derive(Error, Debug)]
+#[error("skipping outdated finality event: {height}, with latest client height: {latest_height}")]
+pub struct OutdatedFinalityEvent {
+	/// Height finalized by the event
+	pub height: u64,
+	/// Latest height of the counterparty client
+	pub latest_height: u64,
+}
+
+/// Classification of the errors encountered while relaying, used by the relay loop to decide how
+/// to recover from them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+	/// Rpc failures and queries against state that isn't available (yet), retried with an
+	/// exponential backoff.
+	Transient,
+	/// The transaction was signed with a stale account nonce, retried once the pending
+	/// transactions are included.
+	Nonce,
+	/// The transaction exceeded the gas or weight limits, retrying it unchanged won't help.
+	OutOfGas,
+	/// The chain rejected the messages themselves, e.g an invalid proof or a packet that was
+	/// already received. Such messages are isolated and dropped, retrying them won't help.
+	Rejected,
+	/// Unrecoverable errors, e.g a misconfigured chain or outdated metadata. These stop the
+	/// relayer.
+	Fatal,
+}
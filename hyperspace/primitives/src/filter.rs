@@ -0,0 +1,299 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Policies for selecting which packets get relayed.
+
+use ibc::{
+	applications::transfer::packet::PacketData,
+	core::{
+		ics04_channel::packet::Packet,
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashSet,
+	sync::{Arc, RwLock},
+};
+
+/// Filter applied to the packets sent from a chain, before they're relayed. Rules that are left
+/// empty allow every packet. The ICS-20 rules only apply to packets carrying
+/// [`PacketData`], other packets are only subject to the channel rules.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PacketFilter {
+	/// Only relay packets sent over channels matching one of these patterns
+	#[serde(default)]
+	pub allow: Vec<ChannelPattern>,
+	/// Never relay packets sent over channels matching one of these patterns
+	#[serde(default)]
+	pub deny: Vec<ChannelPattern>,
+	/// Only relay ICS-20 transfers of denominations matching one of these patterns, e.g.
+	/// `transfer/channel-0/*`
+	#[serde(default)]
+	pub denoms: Vec<String>,
+	/// Minimum amount of an ICS-20 transfer
+	pub min_amount: Option<u128>,
+	/// Maximum amount of an ICS-20 transfer
+	pub max_amount: Option<u128>,
+	/// Rules for the sender of an ICS-20 transfer
+	#[serde(default)]
+	pub senders: AddressRule,
+	/// Rules for the receiver of an ICS-20 transfer
+	#[serde(default)]
+	pub receivers: AddressRule,
+	/// Channels paused at runtime, e.g through the admin api
+	#[serde(skip)]
+	pub paused: PausedChannels,
+}
+
+/// A channel whose outgoing packets are not relayed until it's resumed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PausedChannel {
+	/// Name of the chain the packets are sent from
+	pub chain: String,
+	pub port_id: PortId,
+	pub channel_id: ChannelId,
+}
+
+/// Set of paused channels, shared by all the clones of the filters it's attached to.
+#[derive(Debug, Clone, Default)]
+pub struct PausedChannels(Arc<RwLock<HashSet<PausedChannel>>>);
+
+impl PartialEq for PausedChannels {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.0, &other.0) || *self.0.read().unwrap() == *other.0.read().unwrap()
+	}
+}
+
+impl PausedChannels {
+	/// Returns true if relaying the packets sent from `chain` over the given channel is paused.
+	pub fn contains(&self, chain: &str, port_id: &PortId, channel_id: &ChannelId) -> bool {
+		self.0.read().unwrap().contains(&PausedChannel {
+			chain: chain.to_string(),
+			port_id: port_id.clone(),
+			channel_id: *channel_id,
+		})
+	}
+
+	/// Pauses relaying over a channel, returns false if it was already paused.
+	pub fn pause(&self, channel: PausedChannel) -> bool {
+		self.0.write().unwrap().insert(channel)
+	}
+
+	/// Resumes relaying over a channel, returns false if it wasn't paused.
+	pub fn resume(&self, channel: &PausedChannel) -> bool {
+		self.0.write().unwrap().remove(channel)
+	}
+
+	/// Returns all the paused channels.
+	pub fn list(&self) -> Vec<PausedChannel> {
+		self.0.read().unwrap().iter().cloned().collect()
+	}
+}
+
+/// Glob patterns matched against a port and channel id, `*` matches any sequence of characters
+/// and `?` a single character.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelPattern {
+	pub port_id: String,
+	pub channel_id: String,
+}
+
+/// Allow and deny lists of address patterns.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AddressRule {
+	/// Only allow addresses matching one of these patterns
+	#[serde(default)]
+	pub allow: Vec<String>,
+	/// Deny addresses matching any of these patterns
+	#[serde(default)]
+	pub deny: Vec<String>,
+}
+
+impl PacketFilter {
+	/// Returns true if the packet should be relayed. The packet is matched on its source port
+	/// and channel, the filter is expected to belong to the chain that sent it.
+	pub fn allows(&self, packet: &Packet) -> bool {
+		self.allows_channel(&packet.source_port, &packet.source_channel) &&
+			self.allows_transfer(packet)
+	}
+
+	/// Returns true if packets sent over the given channel may be relayed.
+	pub fn allows_channel(&self, port_id: &PortId, channel_id: &ChannelId) -> bool {
+		let (port_id, channel_id) = (port_id.to_string(), channel_id.to_string());
+		let matches = |pattern: &ChannelPattern| {
+			glob_match(&pattern.port_id, &port_id) && glob_match(&pattern.channel_id, &channel_id)
+		};
+		(self.allow.is_empty() || self.allow.iter().any(matches)) && !self.deny.iter().any(matches)
+	}
+
+	fn has_transfer_rules(&self) -> bool {
+		!self.denoms.is_empty() ||
+			self.min_amount.is_some() ||
+			self.max_amount.is_some() ||
+			!self.senders.is_empty() ||
+			!self.receivers.is_empty()
+	}
+
+	fn allows_transfer(&self, packet: &Packet) -> bool {
+		if !self.has_transfer_rules() {
+			return true
+		}
+		let data = match serde_json::from_slice::<PacketData>(&packet.data) {
+			Ok(data) => data,
+			// not an ICS-20 packet
+			Err(_) => return true,
+		};
+		let denom = data.token.denom.to_string();
+		let amount = data.token.amount.as_u256();
+		(self.denoms.is_empty() || self.denoms.iter().any(|pattern| glob_match(pattern, &denom))) &&
+			self.min_amount.map(|min| amount >= min.into()).unwrap_or(true) &&
+			self.max_amount.map(|max| amount <= max.into()).unwrap_or(true) &&
+			self.senders.allows(data.sender.as_ref()) &&
+			self.receivers.allows(data.receiver.as_ref())
+	}
+}
+
+impl AddressRule {
+	fn is_empty(&self) -> bool {
+		self.allow.is_empty() && self.deny.is_empty()
+	}
+
+	/// Returns true if the address is allowed by this rule.
+	pub fn allows(&self, address: &str) -> bool {
+		(self.allow.is_empty() || self.allow.iter().any(|pattern| glob_match(pattern, address))) &&
+			!self.deny.iter().any(|pattern| glob_match(pattern, address))
+	}
+}
+
+/// Matches `text` against a glob `pattern` supporting the `*` and `?` wildcards.
+fn glob_match(pattern: &str, text: &str) -> bool {
+	let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+	let (mut p, mut t) = (0, 0);
+	// position of the last `*` in the pattern, and of the text it was matched at
+	let mut backtrack = None;
+	while t < text.len() {
+		match pattern.get(p) {
+			Some(b'*') => {
+				backtrack = Some((p, t));
+				p += 1;
+			},
+			Some(&c) if c == b'?' || c == text[t] => {
+				p += 1;
+				t += 1;
+			},
+			_ => match backtrack {
+				// let the last `*` consume one more character
+				Some((star, matched)) => {
+					p = star + 1;
+					t = matched + 1;
+					backtrack = Some((star, matched + 1));
+				},
+				None => return false,
+			},
+		}
+	}
+	pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ibc::applications::transfer::{Amount, PrefixedCoin, PrefixedDenom};
+	use std::str::FromStr;
+
+	fn pattern(port_id: &str, channel_id: &str) -> ChannelPattern {
+		ChannelPattern { port_id: port_id.to_owned(), channel_id: channel_id.to_owned() }
+	}
+
+	fn packet(port_id: &str, channel_id: &str, data: Vec<u8>) -> Packet {
+		Packet {
+			source_port: PortId::from_str(port_id).unwrap(),
+			source_channel: ChannelId::from_str(channel_id).unwrap(),
+			data,
+			..Default::default()
+		}
+	}
+
+	fn transfer(denom: &str, amount: u64, sender: &str) -> Packet {
+		let data = PacketData {
+			token: PrefixedCoin {
+				denom: PrefixedDenom::from_str(denom).unwrap(),
+				amount: Amount::from(amount),
+			},
+			sender: sender.parse().unwrap(),
+			receiver: "receiver".parse().unwrap(),
+			memo: String::new(),
+		};
+		packet("transfer", "channel-0", serde_json::to_vec(&data).unwrap())
+	}
+
+	#[test]
+	fn glob_matches_wildcards() {
+		assert!(glob_match("*", ""));
+		assert!(glob_match("*", "channel-0"));
+		assert!(glob_match("channel-*", "channel-0"));
+		assert!(glob_match("channel-*", "channel-"));
+		assert!(!glob_match("channel-*", "chan-0"));
+		assert!(glob_match("*-0", "channel-0"));
+		assert!(glob_match("ch*el-?", "channel-7"));
+		assert!(!glob_match("channel-?", "channel-10"));
+		assert!(glob_match("transfer/*/uatom", "transfer/channel-0/uatom"));
+		assert!(!glob_match("transfer/*/uatom", "transfer/channel-0/uosmo"));
+		assert!(glob_match("channel-0", "channel-0"));
+		assert!(!glob_match("channel-0", "channel-01"));
+	}
+
+	#[test]
+	fn empty_filter_allows_everything() {
+		let filter = PacketFilter::default();
+		assert!(filter.allows(&packet("transfer", "channel-0", vec![])));
+		assert!(filter.allows(&transfer("uatom", 1, "sender")));
+	}
+
+	#[test]
+	fn channel_rules_match_ports_and_channels() {
+		let filter = PacketFilter {
+			allow: vec![pattern("transfer", "channel-*")],
+			deny: vec![pattern("*", "channel-1")],
+			..Default::default()
+		};
+		assert!(filter.allows(&packet("transfer", "channel-0", vec![])));
+		assert!(!filter.allows(&packet("transfer", "channel-1", vec![])));
+		assert!(!filter.allows(&packet("ica", "channel-0", vec![])));
+
+		let filter = PacketFilter { deny: vec![pattern("ica*", "*")], ..Default::default() };
+		assert!(filter.allows(&packet("transfer", "channel-1", vec![])));
+		assert!(!filter.allows(&packet("icahost", "channel-0", vec![])));
+	}
+
+	#[test]
+	fn transfer_rules_match_denoms_amounts_and_addresses() {
+		let filter = PacketFilter {
+			denoms: vec!["transfer/channel-*/uatom".to_owned()],
+			min_amount: Some(10),
+			max_amount: Some(1000),
+			senders: AddressRule { allow: vec![], deny: vec!["spam*".to_owned()] },
+			..Default::default()
+		};
+		assert!(filter.allows(&transfer("transfer/channel-0/uatom", 10, "sender")));
+		assert!(filter.allows(&transfer("transfer/channel-0/uatom", 1000, "sender")));
+		assert!(!filter.allows(&transfer("uatom", 100, "sender")));
+		assert!(!filter.allows(&transfer("transfer/channel-0/uatom", 9, "sender")));
+		assert!(!filter.allows(&transfer("transfer/channel-0/uatom", 1001, "sender")));
+		assert!(!filter.allows(&transfer("transfer/channel-0/uatom", 100, "spammer")));
+		// packets that don't carry ics-20 data are only subject to the channel rules
+		assert!(filter.allows(&packet("transfer", "channel-0", b"not json".to_vec())));
+	}
+}
@@ -14,23 +14,38 @@
 
 #![allow(clippy::all)]
 
-use std::{pin::Pin, str::FromStr, time::Duration};
+use std::{
+	pin::Pin,
+	str::FromStr,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
 
 use futures::Stream;
 use ibc_proto::{
 	google::protobuf::Any,
-	ibc::core::{
-		channel::v1::{
-			QueryChannelResponse, QueryNextSequenceReceiveResponse,
-			QueryPacketAcknowledgementResponse, QueryPacketCommitmentResponse,
-			QueryPacketReceiptResponse,
+	ibc::{
+		applications::transfer::v1::DenomTrace,
+		core::{
+			channel::v1::{
+				QueryChannelResponse, QueryNextSequenceReceiveResponse,
+				QueryPacketAcknowledgementResponse, QueryPacketCommitmentResponse,
+				QueryPacketReceiptResponse, QueryUpgradeErrorResponse, QueryUpgradeResponse,
+			},
+			client::v1::{QueryClientStateResponse, QueryConsensusStateResponse},
+			connection::v1::QueryConnectionResponse,
 		},
-		client::v1::{QueryClientStateResponse, QueryConsensusStateResponse},
-		connection::v1::QueryConnectionResponse,
 	},
 };
 
-use crate::error::Error;
+use crate::{
+	error::{Error, ErrorKind},
+	filter::PacketFilter,
+	misbehaviour::MisbehaviourAlert,
+};
 #[cfg(feature = "testing")]
 use ibc::applications::transfer::msgs::transfer::MsgTransfer;
 use ibc::{
@@ -61,6 +76,8 @@ use ibc_rpc::PacketInfo;
 use pallet_ibc::light_clients::{AnyClientMessage, AnyClientState, AnyConsensusState};
 
 pub mod error;
+pub mod filter;
+pub mod misbehaviour;
 pub mod mock;
 pub mod utils;
 
@@ -85,6 +102,58 @@ impl UpdateType {
 	}
 }
 
+/// Outcome of simulating the execution of a batch of messages without submitting them.
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+	/// Estimated weight or gas consumed by the messages.
+	pub weight: u64,
+	/// Estimated transaction fee, if the chain reports it.
+	pub fee: Option<u128>,
+	/// The error the messages would fail with, `None` if they'd execute successfully.
+	pub error: Option<String>,
+}
+
+/// Status of a submitted transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+	/// The transaction isn't part of a canonical block, either because it hasn't been included
+	/// yet or because its block was retracted.
+	Pending,
+	/// The transaction was included in a block that isn't finalized yet.
+	Included,
+	/// The transaction was included in a finalized block, along with the errors of the messages
+	/// that failed to be executed.
+	Finalized { failed_messages: Vec<String> },
+	/// The transaction was included in a finalized block but failed as a whole.
+	Failed(String),
+}
+
+/// Native and ibc balances of an account the relayer signs transactions with.
+#[derive(Debug, Clone)]
+pub struct AccountBalance {
+	/// Address of the account
+	pub account: String,
+	pub balances: Vec<PrefixedCoin>,
+}
+
+/// Whether the balance of a relayer account on a chain is below one of the configured
+/// thresholds. Clones share the same state, which is set by the balance monitor of the chain.
+/// Nothing is submitted to the chain while it's set.
+#[derive(Debug, Clone, Default)]
+pub struct LowBalanceState(Arc<AtomicBool>);
+
+impl LowBalanceState {
+	/// Returns true if a balance is below its threshold.
+	pub fn is_low(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+
+	/// Records whether a balance is below its threshold, returning the previous state.
+	pub fn set_low(&self, low: bool) -> bool {
+		self.0.swap(low, Ordering::Relaxed)
+	}
+}
+
 pub fn apply_prefix(mut commitment_prefix: Vec<u8>, path: String) -> Vec<u8> {
 	let path = path.as_bytes().to_vec();
 	commitment_prefix.extend_from_slice(&path);
@@ -99,7 +168,7 @@ pub trait IbcProvider {
 	type FinalityEvent;
 
 	/// A representation of the transaction id for the chain
-	type TransactionId;
+	type TransactionId: Send + Sync;
 
 	/// Error type, just needs to implement standard error trait.
 	type Error: std::error::Error + From<String> + Send + Sync + 'static;
@@ -134,6 +203,21 @@ pub trait IbcProvider {
 		client_id: ClientId,
 	) -> Result<QueryClientStateResponse, Self::Error>;
 
+	/// Query the client state this chain scheduled for its next upgrade, with a proof at `at`
+	async fn query_upgraded_client_state(
+		&self,
+		at: Height,
+	) -> Result<QueryClientStateResponse, Self::Error>;
+
+	/// Query the consensus state this chain scheduled for its next upgrade, with a proof at `at`
+	async fn query_upgraded_consensus_state(
+		&self,
+		at: Height,
+	) -> Result<QueryConsensusStateResponse, Self::Error>;
+
+	/// Return a stream that yields whenever this chain schedules a client upgrade
+	async fn client_upgrades(&self) -> Pin<Box<dyn Stream<Item = ()> + Send + 'static>>;
+
 	/// Query connection end with proof
 	async fn query_connection_end(
 		&self,
@@ -149,6 +233,22 @@ pub trait IbcProvider {
 		port_id: PortId,
 	) -> Result<QueryChannelResponse, Self::Error>;
 
+	/// Query the upgrade this chain proposed for a channel, with proof
+	async fn query_channel_upgrade(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryUpgradeResponse, Self::Error>;
+
+	/// Query the error receipt of the last aborted upgrade of a channel, with proof
+	async fn query_channel_upgrade_error(
+		&self,
+		at: Height,
+		channel_id: ChannelId,
+		port_id: PortId,
+	) -> Result<QueryUpgradeErrorResponse, Self::Error>;
+
 	/// Query proof for provided key path
 	async fn query_proof(&self, at: Height, keys: Vec<Vec<u8>>) -> Result<Vec<u8>, Self::Error>;
 
@@ -235,6 +335,9 @@ pub trait IbcProvider {
 	/// Channel whitelist
 	fn channel_whitelist(&self) -> Vec<(ChannelId, PortId)>;
 
+	/// Filter for the packets sent from this chain
+	fn packet_filter(&self) -> PacketFilter;
+
 	/// Query all channels for a connection
 	async fn query_connection_channels(
 		&self,
@@ -282,6 +385,13 @@ pub trait IbcProvider {
 	/// Should return the list of ibc denoms available to this account to spend.
 	async fn query_ibc_balance(&self) -> Result<Vec<PrefixedCoin>, Self::Error>;
 
+	/// Should return the native and ibc balances of every account the relayer signs
+	/// transactions with.
+	async fn query_relayer_balances(&self) -> Result<Vec<AccountBalance>, Self::Error>;
+
+	/// Should return the traces of all the ibc denoms known to this chain.
+	async fn query_denom_traces(&self) -> Result<Vec<DenomTrace>, Self::Error>;
+
 	/// Return the chain connection prefix
 	fn connection_prefix(&self) -> CommitmentPrefix;
 
@@ -362,12 +472,14 @@ pub trait KeyProvider {
 /// Provides an interface for managing IBC misbehaviour.
 #[async_trait::async_trait]
 pub trait MisbehaviourHandler {
-	/// Check the client message for misbehaviour and submit it to the chain if any.
+	/// Check the client message for misbehaviour and submit it to the chain if any. Returns the
+	/// alert describing the misbehaviour that was found, or an
+	/// [`misbehaviour::UnreportedMisbehaviour`] carrying it if the report couldn't be submitted.
 	async fn check_for_misbehaviour<C: Chain>(
 		&self,
 		counterparty: &C,
 		client_message: AnyClientMessage,
-	) -> Result<(), anyhow::Error>;
+	) -> Result<Option<MisbehaviourAlert>, anyhow::Error>;
 }
 
 /// Provides an interface for the chain to the relayer core for submitting IbcEvents as well as
@@ -383,6 +495,9 @@ pub trait Chain: IbcProvider + MisbehaviourHandler + KeyProvider + Send + Sync {
 	/// Should return an estimate of the weight of a batch of messages.
 	async fn estimate_weight(&self, msg: Vec<Any>) -> Result<u64, Self::Error>;
 
+	/// Simulates the execution of a batch of messages on this chain without submitting them.
+	async fn simulate(&self, messages: Vec<Any>) -> Result<SimulationResult, Self::Error>;
+
 	/// Return a stream that yields when new [`IbcEvents`] are ready to be queried.
 	async fn finality_notifications(
 		&self,
@@ -393,11 +508,24 @@ pub trait Chain: IbcProvider + MisbehaviourHandler + KeyProvider + Send + Sync {
 	/// Should return the transaction id
 	async fn submit(&self, messages: Vec<Any>) -> Result<Self::TransactionId, Self::Error>;
 
+	/// Returns the balance state of the relayer accounts, checked before submitting to this
+	/// chain.
+	fn low_balance(&self) -> &LowBalanceState;
+
+	/// Returns the status of a transaction previously returned by [`Chain::submit`].
+	async fn query_tx_status(&self, tx_id: &Self::TransactionId) -> Result<TxStatus, Self::Error>;
+
 	/// Returns an [`AnyClientMessage`] for an [`UpdateClient`] event
 	async fn query_client_message(
 		&self,
 		update: UpdateClient,
 	) -> Result<AnyClientMessage, Self::Error>;
+
+	/// Classifies an error encountered while relaying to or from this chain. Should return `None`
+	/// for errors that didn't originate from this chain.
+	fn error_kind(&self, _error: &anyhow::Error) -> Option<ErrorKind> {
+		None
+	}
 }
 
 /// Returns undelivered packet sequences that have been sent out from
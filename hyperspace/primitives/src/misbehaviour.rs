@@ -0,0 +1,56 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reporting of the misbehaviour found in client updates.
+
+use serde::Serialize;
+
+/// Alert emitted when a client update that conflicts with the chain tracked by the client is
+/// found.
+#[derive(Debug, Clone, Serialize)]
+pub struct MisbehaviourAlert {
+	/// Name of the chain whose light client received the conflicting update
+	pub counterparty: String,
+	/// Id of the light client on the counterparty
+	pub client_id: String,
+	/// Height of the first conflicting header, on the chain that finalizes it
+	pub height: u64,
+	/// Hash of the header that was submitted to the counterparty
+	pub submitted_hash: String,
+	/// Hash of the canonical header at the same height
+	pub canonical_hash: String,
+	/// Authorities that signed for both conflicting headers, if they could be identified
+	pub equivocators: Vec<String>,
+}
+
+/// Misbehaviour that was found but couldn't be reported to the chain, carrying the alert so it's
+/// not lost with the error.
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to submit misbehaviour report: {error:?}")]
+pub struct UnreportedMisbehaviour {
+	/// Alert describing the misbehaviour
+	pub alert: MisbehaviourAlert,
+	/// Error the report failed with
+	pub error: anyhow::Error,
+}
+
+impl MisbehaviourAlert {
+	/// Logs the alert as a single json line, so it can be picked up by log based alerting.
+	pub fn emit(&self) {
+		match serde_json::to_string(self) {
+			Ok(alert) => log::error!(target: "hyperspace::alert", "{alert}"),
+			Err(_) => log::error!(target: "hyperspace::alert", "{self:?}"),
+		}
+	}
+}
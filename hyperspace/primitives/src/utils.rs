@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{mock::LocalClientTypes, Chain};
+use crate::{mock::LocalClientTypes, Chain, SimulationResult};
 use futures::{future, StreamExt};
 use ibc::{
 	core::{
@@ -41,10 +41,12 @@ pub async fn timeout_future<T: Future>(future: T, secs: u64, reason: String) ->
 	}
 }
 
-pub async fn create_clients(
+/// Builds the messages that create a light client for each chain on its counterparty. Returns
+/// the message to be submitted to `chain_a` followed by the one for `chain_b`.
+pub async fn create_client_messages(
 	chain_a: &impl Chain,
 	chain_b: &impl Chain,
-) -> Result<(ClientId, ClientId), anyhow::Error> {
+) -> Result<(Any, Any), anyhow::Error> {
 	let (client_state_a, cs_state_a) = chain_a.initialize_client_state().await?;
 	let (client_state_b, cs_state_b) = chain_b.initialize_client_state().await?;
 
@@ -54,10 +56,7 @@ pub async fn create_clients(
 		signer: chain_a.account_id(),
 	};
 
-	let msg = Any { type_url: msg.type_url(), value: msg.encode_vec() };
-
-	let tx_id = chain_a.submit(vec![msg]).await?;
-	let client_id_b_on_a = chain_a.query_client_id_from_tx_hash(tx_id).await?;
+	let msg_a = Any { type_url: msg.type_url(), value: msg.encode_vec() };
 
 	let msg = MsgCreateAnyClient::<LocalClientTypes> {
 		client_state: client_state_a,
@@ -65,21 +64,32 @@ pub async fn create_clients(
 		signer: chain_b.account_id(),
 	};
 
-	let msg = Any { type_url: msg.type_url(), value: msg.encode_vec() };
+	let msg_b = Any { type_url: msg.type_url(), value: msg.encode_vec() };
+
+	Ok((msg_a, msg_b))
+}
+
+pub async fn create_clients(
+	chain_a: &impl Chain,
+	chain_b: &impl Chain,
+) -> Result<(ClientId, ClientId), anyhow::Error> {
+	let (msg_a, msg_b) = create_client_messages(chain_a, chain_b).await?;
+
+	let tx_id = chain_a.submit(vec![msg_a]).await?;
+	let client_id_b_on_a = chain_a.query_client_id_from_tx_hash(tx_id).await?;
 
-	let tx_id = chain_b.submit(vec![msg]).await?;
+	let tx_id = chain_b.submit(vec![msg_b]).await?;
 	let client_id_a_on_b = chain_b.query_client_id_from_tx_hash(tx_id).await?;
 
 	Ok((client_id_a_on_b, client_id_b_on_a))
 }
 
-/// Completes the connection handshake process
-/// The relayer process must be running before this function is executed
-pub async fn create_connection(
+/// Builds the message that initiates the connection handshake on `chain_a`.
+pub fn connection_open_init_message(
 	chain_a: &impl Chain,
 	chain_b: &impl Chain,
 	delay_period: Duration,
-) -> Result<(ConnectionId, ConnectionId), anyhow::Error> {
+) -> Any {
 	let msg = MsgConnectionOpenInit {
 		client_id: chain_a.client_id(),
 		counterparty: Counterparty::new(chain_b.client_id(), None, chain_b.connection_prefix()),
@@ -88,7 +98,17 @@ pub async fn create_connection(
 		signer: chain_a.account_id(),
 	};
 
-	let msg = Any { type_url: msg.type_url(), value: msg.encode_vec() };
+	Any { type_url: msg.type_url(), value: msg.encode_vec() }
+}
+
+/// Completes the connection handshake process
+/// The relayer process must be running before this function is executed
+pub async fn create_connection(
+	chain_a: &impl Chain,
+	chain_b: &impl Chain,
+	delay_period: Duration,
+) -> Result<(ConnectionId, ConnectionId), anyhow::Error> {
+	let msg = connection_open_init_message(chain_a, chain_b, delay_period);
 
 	chain_a.submit(vec![msg]).await?;
 
@@ -120,16 +140,14 @@ pub async fn create_connection(
 	Ok((connection_id_a, connection_id_b))
 }
 
-/// Completes the chanel handshake process
-/// The relayer process must be running before this function is executed
-pub async fn create_channel(
+/// Builds the message that initiates the channel handshake on `chain_a`.
+pub fn channel_open_init_message(
 	chain_a: &impl Chain,
-	chain_b: &impl Chain,
 	connection_id: ConnectionId,
 	port_id: PortId,
 	version: String,
 	order: Order,
-) -> Result<(ChannelId, ChannelId), anyhow::Error> {
+) -> Any {
 	let channel = ChannelEnd::new(
 		State::Init,
 		order,
@@ -140,7 +158,20 @@ pub async fn create_channel(
 
 	let msg = MsgChannelOpenInit::new(port_id, channel, chain_a.account_id());
 
-	let msg = Any { type_url: msg.type_url(), value: msg.encode_vec() };
+	Any { type_url: msg.type_url(), value: msg.encode_vec() }
+}
+
+/// Completes the chanel handshake process
+/// The relayer process must be running before this function is executed
+pub async fn create_channel(
+	chain_a: &impl Chain,
+	chain_b: &impl Chain,
+	connection_id: ConnectionId,
+	port_id: PortId,
+	version: String,
+	order: Order,
+) -> Result<(ChannelId, ChannelId), anyhow::Error> {
+	let msg = channel_open_init_message(chain_a, connection_id, port_id, version, order);
 
 	chain_a.submit(vec![msg]).await?;
 
@@ -168,3 +199,31 @@ pub async fn create_channel(
 
 	Ok((channel_id_a, channel_id_b))
 }
+
+/// Logs the given messages along with the result of simulating them on `chain`, without
+/// submitting anything.
+pub async fn dry_run(
+	chain: &impl Chain,
+	messages: Vec<Any>,
+) -> Result<SimulationResult, anyhow::Error> {
+	for msg in &messages {
+		log::info!(
+			target: "hyperspace",
+			"[dry-run] Message for {}: {} 0x{}",
+			chain.name(),
+			msg.type_url,
+			hex::encode(&msg.value)
+		);
+	}
+	let result = chain.simulate(messages).await?;
+	log::info!(
+		target: "hyperspace",
+		"[dry-run] Simulated on {}: weight {} (block max weight {}), fee {:?}, error {:?}",
+		chain.name(),
+		result.weight,
+		chain.block_max_weight(),
+		result.fee,
+		result.error
+	);
+	Ok(result)
+}
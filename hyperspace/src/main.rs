@@ -12,9 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::path::PathBuf;
-
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use clap::Parser;
 use hyperspace_core::{
 	command::{Cli, Subcommand},
@@ -30,25 +28,28 @@ async fn main() -> Result<()> {
 		Subcommand::Relay(cmd) => cmd.run().await,
 		Subcommand::CreateClients(cmd) => {
 			let new_config = cmd.create_clients().await?;
-			let config = cmd.new_config.as_ref().cloned().unwrap_or_else(|| cmd.config.clone());
-			tokio::fs::write(config.parse::<PathBuf>()?, toml::to_string(&new_config)?)
-				.await
-				.map_err(|e| anyhow!(e))
+			cmd.save_config(&new_config).await
 		},
 		Subcommand::CreateConnection(cmd) => {
 			let new_config = cmd.create_connection().await?;
-			let config = cmd.new_config.as_ref().cloned().unwrap_or_else(|| cmd.config.clone());
-			tokio::fs::write(config.parse::<PathBuf>()?, toml::to_string(&new_config)?)
-				.await
-				.map_err(|e| anyhow!(e))
+			cmd.save_config(&new_config).await
 		},
 		Subcommand::CreateChannel(cmd) => {
 			let new_config = cmd.create_channel().await?;
-			let config = cmd.new_config.as_ref().cloned().unwrap_or_else(|| cmd.config.clone());
-			tokio::fs::write(config.parse::<PathBuf>()?, toml::to_string(&new_config)?)
-				.await
-				.map_err(|e| anyhow!(e))
+			cmd.save_config(&new_config).await
+		},
+		Subcommand::CompleteConnection(cmd) => {
+			let new_config = cmd.complete_connection().await?;
+			cmd.save_config(&new_config).await
+		},
+		Subcommand::CompleteChannel(cmd) => {
+			let new_config = cmd.complete_channel().await?;
+			cmd.save_config(&new_config).await
 		},
 		Subcommand::Fish(cmd) => cmd.fish().await,
+		Subcommand::ClearPackets(cmd) => cmd.clear_packets().await,
+		Subcommand::UpgradeClients(cmd) => cmd.upgrade_clients().await,
+		Subcommand::RegisterPayees(cmd) => cmd.register_payees().await,
+		Subcommand::Query(cmd) => cmd.run().await,
 	}
 }
@@ -123,8 +123,6 @@ where
 	}
 	let header = headers.last().unwrap().clone();
 	let header_hash = header.hash();
-	let precommit = Precommit { target_hash: header_hash, target_number: header.number };
-	let message = finality_grandpa::Message::Precommit(precommit.clone());
 
 	let (update_client_msg, _, _) = chain_b
 		.query_latest_ibc_events(finality_event, chain_a)
@@ -145,27 +143,52 @@ where
 		_ => panic!("unexpected client message"),
 	};
 
-	// sign pre-commits by the authorities to vote for the highest block in the chain
-	let precommits = relaychain_authorities
-		.iter()
-		.map(|id| {
-			let key = id.pair();
-			let encoded = sp_finality_grandpa::localized_payload(round, set_id, &message);
-			let signature = AuthoritySignature::from(key.sign(&encoded));
-			SignedPrecommit {
-				precommit: precommit.clone(),
-				signature,
-				id: AuthorityId::from(key.public()),
-			}
-		})
-		.collect();
-	let commit = Commit::<RelayChainHeader> {
-		target_hash: header_hash,
-		target_number: header.number,
-		precommits,
+	// sign pre-commits by the authorities to vote for the given block
+	let sign_commit = |target: &RelayChainHeader| {
+		let precommit = Precommit { target_hash: target.hash(), target_number: target.number };
+		let message = finality_grandpa::Message::Precommit(precommit.clone());
+		let precommits = relaychain_authorities
+			.iter()
+			.map(|id| {
+				let key = id.pair();
+				let encoded = sp_finality_grandpa::localized_payload(round, set_id, &message);
+				let signature = AuthoritySignature::from(key.sign(&encoded));
+				SignedPrecommit {
+					precommit: precommit.clone(),
+					signature,
+					id: AuthorityId::from(key.public()),
+				}
+			})
+			.collect();
+		Commit::<RelayChainHeader> {
+			target_hash: target.hash(),
+			target_number: target.number,
+			precommits,
+		}
 	};
+	// vote for the highest block in the chain
+	let commit = sign_commit(&header);
 	let justification =
 		GrandpaJustification::<RelayChainHeader> { round, commit, votes_ancestries: vec![] };
+
+	// every authority voting for another block in the same round is an equivocation
+	let conflicting_justification = GrandpaJustification::<RelayChainHeader> {
+		round,
+		commit: sign_commit(&headers[0]),
+		votes_ancestries: vec![],
+	};
+	let equivocations = hyperspace_parachain::misbehaviour::find_equivocations(
+		set_id,
+		&justification,
+		&conflicting_justification,
+	);
+	assert_eq!(equivocations.len(), relaychain_authorities.len());
+	assert!(hyperspace_parachain::misbehaviour::find_equivocations(
+		set_id,
+		&justification,
+		&justification
+	)
+	.is_empty());
 	let finality_proof = FinalityProof {
 		block: header_hash,
 		justification: justification.encode(),
@@ -217,5 +240,14 @@ where
 		.expect("timeout")
 		.expect("failed to receive misbehaviour event");
 
+	// the misbehaviour submitted by the fisherman should have frozen the client
+	let client_id = chain_b.client_id();
+	let latest_height = chain_a.latest_height_and_timestamp().await.unwrap().0;
+	let response = chain_a.query_client_state(latest_height, client_id).await.unwrap();
+	match AnyClientState::try_from(response.client_state.unwrap()).unwrap() {
+		AnyClientState::Grandpa(client_state) => assert!(client_state.frozen_height.is_some()),
+		_ => panic!("unexpected client state"),
+	}
+
 	handle.abort()
 }
@@ -0,0 +1,64 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use hyperspace_parachain::key_source::{
+	serve_stub_signer, KeySource, KeySourceConfig, KeyType, LocalKey, RemoteSigner,
+};
+use sp_keyring::AccountKeyring;
+use sp_runtime::{traits::Verify, MultiSigner};
+
+const PAYLOAD: &[u8] = b"hyperspace";
+
+fn assert_signs_for(key_source: &dyn KeySource, keyring: AccountKeyring) {
+	assert_eq!(key_source.public_key(), MultiSigner::from(keyring.public()));
+	let signature = key_source.sign(PAYLOAD).unwrap();
+	assert!(signature.verify(PAYLOAD, &keyring.to_account_id()));
+}
+
+// the remote signer blocks the calling thread while waiting for the stub server
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_signer_signs_with_the_stub_key() {
+	let key = LocalKey::from_suri(KeyType::Sr25519, "//Alice").unwrap();
+	let (url, _handle) = serve_stub_signer(key).await.unwrap();
+
+	let signer = RemoteSigner::connect(url.clone()).await.unwrap();
+	assert_signs_for(&signer, AccountKeyring::Alice);
+
+	let signer = KeySourceConfig::Remote { url }.load(KeyType::Sr25519).await.unwrap();
+	assert_signs_for(&*signer, AccountKeyring::Alice);
+}
+
+#[tokio::test]
+async fn secret_uris_are_loaded_from_env_and_files() {
+	std::env::set_var("HYPERSPACE_TEST_RELAYER_KEY", "//Bob");
+	let key_source = KeySourceConfig::Env { var: "HYPERSPACE_TEST_RELAYER_KEY".to_string() }
+		.load(KeyType::Sr25519)
+		.await
+		.unwrap();
+	assert_signs_for(&*key_source, AccountKeyring::Bob);
+
+	let path = std::env::temp_dir().join("hyperspace-test-relayer-key");
+	std::fs::write(&path, "//Charlie\n").unwrap();
+	let key_source = KeySourceConfig::File { path: path.clone() }
+		.load(KeyType::Sr25519)
+		.await
+		.unwrap();
+	std::fs::remove_file(path).unwrap();
+	assert_signs_for(&*key_source, AccountKeyring::Charlie);
+
+	let missing = KeySourceConfig::Env { var: "HYPERSPACE_TEST_MISSING_KEY".to_string() }
+		.load(KeyType::Sr25519)
+		.await;
+	assert!(missing.is_err());
+}
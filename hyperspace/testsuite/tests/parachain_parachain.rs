@@ -98,14 +98,21 @@ async fn setup_clients() -> (ParachainClient<DefaultConfig>, ParachainClient<Def
 		para_id: args.para_id_a,
 		parachain_rpc_url: args.chain_a,
 		relay_chain_rpc_url: args.relay_chain.clone(),
+		parachain_rpc_fallback_urls: vec![],
+		relay_chain_rpc_fallback_urls: vec![],
+		reconnect: Default::default(),
 		client_id: None,
 		beefy_activation_block: None,
 		connection_id: None,
 		commitment_prefix: args.connection_prefix_b.as_bytes().to_vec().into(),
 		ss58_version: 42,
 		channel_whitelist: vec![],
+		packet_filter: Default::default(),
 		finality_protocol: FinalityProtocol::Grandpa,
-		private_key: "//Alice".to_string(),
+		finality_sampling: None,
+		private_key: Some("//Alice".to_string()),
+		key_source: None,
+		signer_pool: vec![],
 		key_type: "sr25519".to_string(),
 	};
 	let config_b = ParachainClientConfig {
@@ -113,14 +120,21 @@ async fn setup_clients() -> (ParachainClient<DefaultConfig>, ParachainClient<Def
 		para_id: args.para_id_b,
 		parachain_rpc_url: args.chain_b,
 		relay_chain_rpc_url: args.relay_chain,
+		parachain_rpc_fallback_urls: vec![],
+		relay_chain_rpc_fallback_urls: vec![],
+		reconnect: Default::default(),
 		client_id: None,
 		beefy_activation_block: None,
 		connection_id: None,
 		commitment_prefix: args.connection_prefix_b.as_bytes().to_vec().into(),
-		private_key: "//Alice".to_string(),
+		private_key: Some("//Alice".to_string()),
+		key_source: None,
+		signer_pool: vec![],
 		ss58_version: 42,
 		channel_whitelist: vec![],
+		packet_filter: Default::default(),
 		finality_protocol: FinalityProtocol::Grandpa,
+		finality_sampling: None,
 		key_type: "sr25519".to_string(),
 	};
 
@@ -131,7 +145,7 @@ async fn setup_clients() -> (ParachainClient<DefaultConfig>, ParachainClient<Def
 	log::info!(target: "hyperspace", "Waiting for  block production from parachains");
 	let session_length = chain_a.grandpa_prover().session_length().await.unwrap();
 	let _ = chain_a
-		.relay_client
+		.relay_client()
 		.rpc()
 		.subscribe_blocks()
 		.await
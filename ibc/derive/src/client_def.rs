@@ -149,6 +149,71 @@ impl State {
 		}
 	}
 
+	fn impl_fn_updates_state_on_proofs_verified(&self) -> proc_macro2::TokenStream {
+		let trait_ = &self.current_impl_trait;
+		let cases = self.clients.iter().map(|client| {
+			let inner_ty_path = &client.inner_ty_path;
+			let attrs = &client.attrs;
+			quote! {
+				#(#attrs)*
+				if <#inner_ty_path as #trait_>::updates_state_on_proofs_verified(client_type) {
+					return true
+				}
+			}
+		});
+
+		quote! {
+			fn updates_state_on_proofs_verified(client_type: &str) -> bool {
+				#(#cases)*
+				false
+			}
+		}
+	}
+
+	fn impl_fn_update_state_on_proofs_verified(&self) -> proc_macro2::TokenStream {
+		let crate_ = &self.crate_ident;
+		let trait_ = &self.current_impl_trait;
+		let error = &self.current_impl_error;
+		let client_state_trait = &self.client_state_trait;
+		let cases = self.clients.iter().map(|client| {
+			let variant_ident = &client.variant_ident;
+			let attrs = &client.attrs;
+			quote! {
+				#(#attrs)*
+				Self::#variant_ident(client) => {
+					let client_type = #client_state_trait::client_type(&client_state).to_owned();
+					let client_state = #crate_::downcast!(
+						client_state => Self::ClientState::#variant_ident
+					)
+					.ok_or_else(|| #error::client_args_type_mismatch(client_type))?;
+
+					let update = #trait_::update_state_on_proofs_verified(
+						client,
+						ctx,
+						client_id,
+						client_state,
+					)?;
+					Ok(update.map(|(new_state, new_consensus)| {
+						(Self::ClientState::#variant_ident(new_state), new_consensus)
+					}))
+				}
+			}
+		});
+
+		quote! {
+			fn update_state_on_proofs_verified<Ctx: #crate_::core::ics26_routing::context::ReaderContext>(
+				&self,
+				ctx: &Ctx,
+				client_id: #crate_::core::ics24_host::identifier::ClientId,
+				client_state: Self::ClientState,
+			) -> ::core::result::Result<::core::option::Option<(Self::ClientState, #crate_::core::ics02_client::client_def::ConsensusUpdateResult<Ctx>)>, #error> {
+				match self {
+					#(#cases)*
+				}
+			}
+		}
+	}
+
 	fn impl_fn_check_for_misbehaviour(&self) -> proc_macro2::TokenStream {
 		let crate_ = &self.crate_ident;
 		let error = &self.current_impl_error;
@@ -404,6 +469,112 @@ impl State {
 		}
 	}
 
+	fn impl_fn_verify_channel_upgrade(&self) -> proc_macro2::TokenStream {
+		let crate_ = &self.crate_ident;
+		let trait_ = &self.current_impl_trait;
+		let error = &self.current_impl_error;
+		let client_state_trait = &self.client_state_trait;
+		let cases = self.clients.iter().map(|client| {
+			let variant_ident = &client.variant_ident;
+			let attrs = &client.attrs;
+			quote! {
+				#(#attrs)*
+				Self::#variant_ident(client) => {
+					let client_type = #client_state_trait::client_type(client_state).to_owned();
+					let client_state = #crate_::downcast!(client_state => Self::ClientState::#variant_ident)
+						.ok_or_else(|| #error::client_args_type_mismatch(client_type))?;
+
+					#trait_::verify_channel_upgrade(
+						client,
+						ctx,
+						client_id,
+						client_state,
+						height,
+						prefix,
+						proof,
+						root,
+						port_id,
+						channel_id,
+						expected_upgrade,
+					)
+				}
+			}
+		});
+
+		quote! {
+			fn verify_channel_upgrade<Ctx: #crate_::core::ics26_routing::context::ReaderContext>(
+				&self,
+				ctx: &Ctx,
+				client_id: &#crate_::core::ics24_host::identifier::ClientId,
+				client_state: &Self::ClientState,
+				height: #crate_::core::ics02_client::height::Height,
+				prefix: &#crate_::core::ics23_commitment::commitment::CommitmentPrefix,
+				proof: &#crate_::core::ics23_commitment::commitment::CommitmentProofBytes,
+				root: &#crate_::core::ics23_commitment::commitment::CommitmentRoot,
+				port_id: &#crate_::core::ics24_host::identifier::PortId,
+				channel_id: &#crate_::core::ics24_host::identifier::ChannelId,
+				expected_upgrade: &#crate_::core::ics04_channel::upgrade::Upgrade,
+			) -> ::core::result::Result<(), #error> {
+				match self {
+					#(#cases)*
+				}
+			}
+		}
+	}
+
+	fn impl_fn_verify_channel_upgrade_error(&self) -> proc_macro2::TokenStream {
+		let crate_ = &self.crate_ident;
+		let trait_ = &self.current_impl_trait;
+		let error = &self.current_impl_error;
+		let client_state_trait = &self.client_state_trait;
+		let cases = self.clients.iter().map(|client| {
+			let variant_ident = &client.variant_ident;
+			let attrs = &client.attrs;
+			quote! {
+				#(#attrs)*
+				Self::#variant_ident(client) => {
+					let client_type = #client_state_trait::client_type(client_state).to_owned();
+					let client_state = #crate_::downcast!(client_state => Self::ClientState::#variant_ident)
+						.ok_or_else(|| #error::client_args_type_mismatch(client_type))?;
+
+					#trait_::verify_channel_upgrade_error(
+						client,
+						ctx,
+						client_id,
+						client_state,
+						height,
+						prefix,
+						proof,
+						root,
+						port_id,
+						channel_id,
+						expected_error_receipt,
+					)
+				}
+			}
+		});
+
+		quote! {
+			fn verify_channel_upgrade_error<Ctx: #crate_::core::ics26_routing::context::ReaderContext>(
+				&self,
+				ctx: &Ctx,
+				client_id: &#crate_::core::ics24_host::identifier::ClientId,
+				client_state: &Self::ClientState,
+				height: #crate_::core::ics02_client::height::Height,
+				prefix: &#crate_::core::ics23_commitment::commitment::CommitmentPrefix,
+				proof: &#crate_::core::ics23_commitment::commitment::CommitmentProofBytes,
+				root: &#crate_::core::ics23_commitment::commitment::CommitmentRoot,
+				port_id: &#crate_::core::ics24_host::identifier::PortId,
+				channel_id: &#crate_::core::ics24_host::identifier::ChannelId,
+				expected_error_receipt: &#crate_::core::ics04_channel::upgrade::ErrorReceipt,
+			) -> ::core::result::Result<(), #error> {
+				match self {
+					#(#cases)*
+				}
+			}
+		}
+	}
+
 	fn impl_fn_verify_client_full_state(&self) -> proc_macro2::TokenStream {
 		let crate_ = &self.crate_ident;
 		let trait_ = &self.current_impl_trait;
@@ -699,9 +870,13 @@ impl State {
 		let fn_update_state_on_misbehaviour = self.impl_fn_update_state_on_misbehaviour();
 		let fn_check_for_misbehaviour = self.impl_fn_check_for_misbehaviour();
 		let fn_verify_upgrade_and_update_state = self.impl_fn_verify_upgrade_and_update_state();
+		let fn_updates_state_on_proofs_verified = self.impl_fn_updates_state_on_proofs_verified();
+		let fn_update_state_on_proofs_verified = self.impl_fn_update_state_on_proofs_verified();
 		let fn_verify_client_consensus_state = self.impl_fn_verify_client_consensus_state();
 		let fn_verify_connection_state = self.impl_fn_verify_connection_state();
 		let fn_verify_channel_state = self.impl_fn_verify_channel_state();
+		let fn_verify_channel_upgrade = self.impl_fn_verify_channel_upgrade();
+		let fn_verify_channel_upgrade_error = self.impl_fn_verify_channel_upgrade_error();
 		let fn_verify_client_full_state = self.impl_fn_verify_client_full_state();
 		let fn_verify_packet_data = self.impl_fn_verify_packet_data();
 		let fn_verify_packet_acknowledgement = self.impl_fn_verify_packet_acknowledgement();
@@ -719,9 +894,13 @@ impl State {
 				#fn_update_state_on_misbehaviour
 				#fn_check_for_misbehaviour
 				#fn_verify_upgrade_and_update_state
+				#fn_updates_state_on_proofs_verified
+				#fn_update_state_on_proofs_verified
 				#fn_verify_client_consensus_state
 				#fn_verify_connection_state
 				#fn_verify_channel_state
+				#fn_verify_channel_upgrade
+				#fn_verify_channel_upgrade_error
 				#fn_verify_client_full_state
 				#fn_verify_packet_data
 				#fn_verify_packet_acknowledgement
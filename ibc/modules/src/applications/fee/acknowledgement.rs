@@ -0,0 +1,113 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The acknowledgement written for packets received on fee enabled channels, which wraps the
+//! acknowledgement of the underlying application.
+
+use crate::prelude::*;
+
+use ibc_proto::ibc::applications::fee::v1::IncentivizedAcknowledgement as RawIncentivizedAcknowledgement;
+use serde::{Deserialize, Serialize};
+use subtle_encoding::base64;
+
+use super::error::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IncentivizedAcknowledgement {
+	/// Acknowledgement written by the underlying application.
+	pub app_acknowledgement: Vec<u8>,
+	/// Counterparty payee of the relayer that delivered the packet, which is paid the receive
+	/// fee on the sending chain. Empty if the relayer didn't register one.
+	pub forward_relayer_address: String,
+	pub underlying_app_success: bool,
+}
+
+/// The proto JSON encoding of the acknowledgement, in which bytes are base64 encoded.
+#[derive(Serialize, Deserialize)]
+struct JsonIncentivizedAcknowledgement {
+	app_acknowledgement: String,
+	forward_relayer_address: String,
+	underlying_app_success: bool,
+}
+
+impl IncentivizedAcknowledgement {
+	/// Encodes the acknowledgement the same way as ibc-go, so that it can be unwrapped by any
+	/// fee enabled counterparty.
+	pub fn to_json_bytes(&self) -> Vec<u8> {
+		let json = JsonIncentivizedAcknowledgement {
+			app_acknowledgement: String::from_utf8(base64::encode(&self.app_acknowledgement))
+				.expect("base64 encoding is valid UTF8; qed"),
+			forward_relayer_address: self.forward_relayer_address.clone(),
+			underlying_app_success: self.underlying_app_success,
+		};
+		serde_json::to_vec(&json).expect("acknowledgement is always serializable; qed")
+	}
+
+	pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, Error> {
+		let json: JsonIncentivizedAcknowledgement =
+			serde_json::from_slice(bytes).map_err(|_| Error::ack_deserialization())?;
+		Ok(Self {
+			app_acknowledgement: base64::decode(json.app_acknowledgement)
+				.map_err(|_| Error::ack_deserialization())?,
+			forward_relayer_address: json.forward_relayer_address,
+			underlying_app_success: json.underlying_app_success,
+		})
+	}
+}
+
+impl From<RawIncentivizedAcknowledgement> for IncentivizedAcknowledgement {
+	fn from(raw: RawIncentivizedAcknowledgement) -> Self {
+		Self {
+			app_acknowledgement: raw.app_acknowledgement,
+			forward_relayer_address: raw.forward_relayer_address,
+			underlying_app_success: raw.underlying_app_success,
+		}
+	}
+}
+
+impl From<IncentivizedAcknowledgement> for RawIncentivizedAcknowledgement {
+	fn from(ack: IncentivizedAcknowledgement) -> Self {
+		Self {
+			app_acknowledgement: ack.app_acknowledgement,
+			forward_relayer_address: ack.forward_relayer_address,
+			underlying_app_success: ack.underlying_app_success,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn incentivized_acknowledgement_json_round_trip() {
+		let ack = IncentivizedAcknowledgement {
+			app_acknowledgement: b"AQ==".to_vec(),
+			forward_relayer_address: "cosmos1relayer".to_string(),
+			underlying_app_success: true,
+		};
+		let bytes = ack.to_json_bytes();
+		assert_eq!(
+			bytes,
+			br#"{"app_acknowledgement":"QVE9PQ==","forward_relayer_address":"cosmos1relayer","underlying_app_success":true}"#
+				.to_vec()
+		);
+		assert_eq!(IncentivizedAcknowledgement::from_json_bytes(&bytes).unwrap(), ack);
+	}
+
+	#[test]
+	fn plain_acknowledgements_are_rejected() {
+		assert!(IncentivizedAcknowledgement::from_json_bytes(b"AQ==").is_err());
+	}
+}
@@ -0,0 +1,92 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::prelude::*;
+
+use super::{
+	error::Error,
+	fee::{PacketFee, PacketId},
+};
+use crate::{
+	applications::transfer::context::BankKeeper,
+	core::{
+		ics04_channel::context::ChannelReader,
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	signer::Signer,
+};
+
+pub trait FeeReader: ChannelReader {
+	type AccountId: TryFrom<Signer>;
+
+	/// Returns the account holding the escrowed fees until they're paid out or refunded.
+	fn get_fee_escrow_address(&self) -> Result<<Self as FeeReader>::AccountId, Error>;
+
+	/// Returns true iff the fee version was negotiated for the channel.
+	fn is_fee_enabled(&self, port_id: &PortId, channel_id: &ChannelId) -> bool;
+
+	/// Returns the address the fees earned by `relayer` on the channel should be paid to.
+	fn get_payee(&self, relayer: &Signer, channel_id: &ChannelId) -> Option<Signer>;
+
+	/// Returns the address on the counterparty chain the receive fees earned by `relayer` on
+	/// the channel should be paid to.
+	fn get_counterparty_payee(&self, relayer: &Signer, channel_id: &ChannelId) -> Option<String>;
+
+	/// Returns the fees escrowed for a packet sent by this chain.
+	fn get_fees_in_escrow(&self, packet_id: &PacketId) -> Vec<PacketFee>;
+
+	/// Returns the fees escrowed for all the packets sent on a channel.
+	fn get_channel_fees_in_escrow(
+		&self,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	) -> Vec<(PacketId, Vec<PacketFee>)>;
+
+	/// Returns the forward relayer recorded for a packet received by this chain, until its
+	/// acknowledgement is written.
+	fn get_forward_relayer_address(&self, packet_id: &PacketId) -> Option<String>;
+}
+
+pub trait FeeKeeper: BankKeeper<AccountId = <Self as FeeKeeper>::AccountId> {
+	type AccountId;
+
+	fn set_fee_enabled(&mut self, port_id: &PortId, channel_id: &ChannelId);
+
+	/// Called when a channel is upgraded to a version without fees.
+	fn delete_fee_enabled(&mut self, port_id: &PortId, channel_id: &ChannelId);
+
+	fn set_payee(&mut self, relayer: &Signer, channel_id: &ChannelId, payee: &Signer);
+
+	fn set_counterparty_payee(
+		&mut self,
+		relayer: &Signer,
+		channel_id: &ChannelId,
+		counterparty_payee: &str,
+	);
+
+	fn store_fees_in_escrow(&mut self, packet_id: &PacketId, fees: Vec<PacketFee>);
+
+	fn delete_fees_in_escrow(&mut self, packet_id: &PacketId);
+
+	fn set_forward_relayer_address(&mut self, packet_id: &PacketId, address: &str);
+
+	fn delete_forward_relayer_address(&mut self, packet_id: &PacketId);
+}
+
+pub trait FeeContext:
+	FeeKeeper<AccountId = <Self as FeeContext>::AccountId>
+	+ FeeReader<AccountId = <Self as FeeContext>::AccountId>
+{
+	type AccountId: TryFrom<Signer>;
+}
@@ -0,0 +1,106 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use flex_error::{define_error, TraceError};
+use tendermint_proto::Error as TendermintProtoError;
+
+use crate::{
+	applications::transfer::error::Error as Ics20Error,
+	core::{
+		ics04_channel::error as channel_error,
+		ics24_host::{
+			error::ValidationError,
+			identifier::{ChannelId, PortId},
+		},
+	},
+	prelude::*,
+	signer::SignerError,
+};
+
+define_error! {
+	#[derive(Debug, PartialEq, Eq)]
+	Error {
+		Ics04Channel
+			[ channel_error::Error ]
+			|_ | { "Ics04 channel error" },
+
+		Transfer
+			[ Ics20Error ]
+			|_ | { "failed to move fee tokens" },
+
+		InvalidPortId
+			{ context: String }
+			[ ValidationError ]
+			| _ | { "invalid port identifier" },
+
+		InvalidChannelId
+			{ context: String }
+			[ ValidationError ]
+			| _ | { "invalid channel identifier" },
+
+		InvalidFeeVersion
+			{ version: String }
+			| e | { format_args!("expected fee version '{0}', got '{1}'", super::VERSION, e.version) },
+
+		MissingFeeVersion
+			{ port_id: PortId, channel_id: ChannelId }
+			| e | { format_args!("the counterparty of fee enabled channel {0}/{1} didn't negotiate a fee version", e.port_id, e.channel_id) },
+
+		FeeNotEnabled
+			{ port_id: PortId, channel_id: ChannelId }
+			| e | { format_args!("fees are not enabled on channel {0}/{1}", e.port_id, e.channel_id) },
+
+		MissingFee
+			| _ | { "missing fee" },
+
+		InvalidCoin
+			[ Ics20Error ]
+			| _ | { "invalid fee coin" },
+
+		RelayersNotSupported
+			| _ | { "restricting the fee to a set of relayers is not supported" },
+
+		MissingPacketId
+			| _ | { "missing packet id" },
+
+		PacketNotSent
+			{ port_id: PortId, channel_id: ChannelId, sequence: u64 }
+			| e | { format_args!("packet {0}/{1}/{2} has no commitment, it was never sent or was already acknowledged", e.port_id, e.channel_id, e.sequence) },
+
+		Signer
+			[ SignerError ]
+			| _ | { "failed to parse signer" },
+
+		EmptyCounterpartyPayee
+			| _ | { "counterparty payee can't be empty" },
+
+		ParseAccountFailure
+			| _ | { "failed to parse as AccountId" },
+
+		AckDeserialization
+			| _ | { "failed to deserialize incentivized acknowledgement" },
+
+		DecodeRawMsg
+			[ TraceError<TendermintProtoError> ]
+			| _ | { "error decoding raw msg" },
+
+		UnknownMsgType
+			{ msg_type: String }
+			| e | { format_args!("unknown msg type: {0}", e.msg_type) },
+
+		ImplementationSpecific
+			{ reason: String }
+			| e | { format_args!("implementation specific error: {}", e.reason) },
+	}
+}
@@ -0,0 +1,51 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	applications::{fee::MODULE_ID_STR, transfer::PrefixedCoin},
+	events::ModuleEvent,
+	prelude::*,
+	signer::Signer,
+};
+
+use super::fee::PacketId;
+
+const EVENT_TYPE_REFUND_FAILED: &str = "fee_refund_failed";
+
+/// Emitted when a fee could neither be paid out nor refunded, the coins are left in the fee
+/// escrow account.
+pub struct RefundFailedEvent {
+	pub packet_id: PacketId,
+	pub refund_address: Signer,
+	pub fee: PrefixedCoin,
+	pub reason: String,
+}
+
+impl From<RefundFailedEvent> for ModuleEvent {
+	fn from(ev: RefundFailedEvent) -> Self {
+		let RefundFailedEvent { packet_id, refund_address, fee, reason } = ev;
+		Self {
+			kind: EVENT_TYPE_REFUND_FAILED.to_string(),
+			module_name: MODULE_ID_STR.parse().expect("invalid ModuleId"),
+			attributes: vec![
+				("port_id", packet_id.port_id).into(),
+				("channel_id", packet_id.channel_id).into(),
+				("sequence", packet_id.sequence).into(),
+				("refund_address", refund_address).into(),
+				("fee", fee).into(),
+				("reason", reason).into(),
+			],
+		}
+	}
+}
@@ -0,0 +1,192 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Domain types of the fees paid for relaying a packet.
+
+use crate::prelude::*;
+
+use ibc_proto::{
+	cosmos::base::v1beta1::Coin as RawCoin,
+	ibc::{
+		applications::fee::v1::{
+			Fee as RawFee, PacketFee as RawPacketFee, PacketFees as RawPacketFees,
+		},
+		core::channel::v1::PacketId as RawPacketId,
+	},
+};
+use tendermint_proto::Protobuf;
+
+use super::error::Error;
+use crate::{
+	applications::transfer::PrefixedCoin,
+	core::{
+		ics04_channel::packet::{Packet, Sequence},
+		ics24_host::identifier::{ChannelId, PortId},
+	},
+	signer::Signer,
+};
+
+/// Identifies a packet by its source port, channel and sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PacketId {
+	pub port_id: PortId,
+	pub channel_id: ChannelId,
+	pub sequence: Sequence,
+}
+
+impl PacketId {
+	pub fn new(port_id: PortId, channel_id: ChannelId, sequence: Sequence) -> Self {
+		Self { port_id, channel_id, sequence }
+	}
+
+	/// Id of the packet on the chain that sent it.
+	pub fn source(packet: &Packet) -> Self {
+		Self::new(packet.source_port.clone(), packet.source_channel, packet.sequence)
+	}
+
+	/// Id of the packet on the chain that received it.
+	pub fn destination(packet: &Packet) -> Self {
+		Self::new(packet.destination_port.clone(), packet.destination_channel, packet.sequence)
+	}
+}
+
+impl TryFrom<RawPacketId> for PacketId {
+	type Error = Error;
+
+	fn try_from(raw: RawPacketId) -> Result<Self, Self::Error> {
+		Ok(Self {
+			port_id: raw.port_id.parse().map_err(|e| Error::invalid_port_id(raw.port_id, e))?,
+			channel_id: raw
+				.channel_id
+				.parse()
+				.map_err(|e| Error::invalid_channel_id(raw.channel_id, e))?,
+			sequence: raw.sequence.into(),
+		})
+	}
+}
+
+impl From<PacketId> for RawPacketId {
+	fn from(packet_id: PacketId) -> Self {
+		Self {
+			port_id: packet_id.port_id.to_string(),
+			channel_id: packet_id.channel_id.to_string(),
+			sequence: packet_id.sequence.into(),
+		}
+	}
+}
+
+/// The fees paid to the relayers of a packet: `recv_fee` goes to the relayer of the packet,
+/// `ack_fee` to the relayer of its acknowledgement and `timeout_fee` to the relayer of its
+/// timeout.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fee {
+	pub recv_fee: Vec<PrefixedCoin>,
+	pub ack_fee: Vec<PrefixedCoin>,
+	pub timeout_fee: Vec<PrefixedCoin>,
+}
+
+impl Fee {
+	/// All the coins escrowed to cover the fee, the part that isn't paid out is refunded.
+	pub fn total(&self) -> impl Iterator<Item = &PrefixedCoin> {
+		self.recv_fee.iter().chain(self.ack_fee.iter()).chain(self.timeout_fee.iter())
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.total().all(|coin| coin.amount.as_u256().is_zero())
+	}
+}
+
+fn coins_from_raw(coins: Vec<RawCoin>) -> Result<Vec<PrefixedCoin>, Error> {
+	coins
+		.into_iter()
+		.map(|coin| coin.try_into().map_err(Error::invalid_coin))
+		.collect()
+}
+
+impl TryFrom<RawFee> for Fee {
+	type Error = Error;
+
+	fn try_from(raw: RawFee) -> Result<Self, Self::Error> {
+		Ok(Self {
+			recv_fee: coins_from_raw(raw.recv_fee)?,
+			ack_fee: coins_from_raw(raw.ack_fee)?,
+			timeout_fee: coins_from_raw(raw.timeout_fee)?,
+		})
+	}
+}
+
+impl From<Fee> for RawFee {
+	fn from(fee: Fee) -> Self {
+		Self {
+			recv_fee: fee.recv_fee.into_iter().map(Into::into).collect(),
+			ack_fee: fee.ack_fee.into_iter().map(Into::into).collect(),
+			timeout_fee: fee.timeout_fee.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// A fee escrowed for a packet, along with the account the unused part is refunded to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PacketFee {
+	pub fee: Fee,
+	pub refund_address: Signer,
+	/// Relayers allowed to claim the fee, currently required to be empty.
+	pub relayers: Vec<Signer>,
+}
+
+impl TryFrom<RawPacketFee> for PacketFee {
+	type Error = Error;
+
+	fn try_from(raw: RawPacketFee) -> Result<Self, Self::Error> {
+		Ok(Self {
+			fee: raw.fee.ok_or_else(Error::missing_fee)?.try_into()?,
+			refund_address: raw.refund_address.parse().map_err(Error::signer)?,
+			relayers: raw
+				.relayers
+				.into_iter()
+				.map(|relayer| relayer.parse().map_err(Error::signer))
+				.collect::<Result<_, _>>()?,
+		})
+	}
+}
+
+impl From<PacketFee> for RawPacketFee {
+	fn from(packet_fee: PacketFee) -> Self {
+		Self {
+			fee: Some(packet_fee.fee.into()),
+			refund_address: packet_fee.refund_address.to_string(),
+			relayers: packet_fee.relayers.into_iter().map(|relayer| relayer.to_string()).collect(),
+		}
+	}
+}
+
+/// All the fees escrowed for a packet.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PacketFees(pub Vec<PacketFee>);
+
+impl TryFrom<RawPacketFees> for PacketFees {
+	type Error = Error;
+
+	fn try_from(raw: RawPacketFees) -> Result<Self, Self::Error> {
+		Ok(Self(raw.packet_fees.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?))
+	}
+}
+
+impl From<PacketFees> for RawPacketFees {
+	fn from(packet_fees: PacketFees) -> Self {
+		Self { packet_fees: packet_fees.0.into_iter().map(Into::into).collect() }
+	}
+}
+
+impl Protobuf<RawPacketFees> for PacketFees {}
@@ -0,0 +1,90 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Channel version negotiated by the fee middleware, which wraps the version of the underlying
+//! application.
+
+use crate::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use super::{error::Error, VERSION};
+use crate::core::ics04_channel::Version;
+
+/// The JSON encoded version of a fee enabled channel, e.g.
+/// `{"fee_version":"ics29-1","app_version":"ics20-1"}`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+	pub fee_version: String,
+	pub app_version: String,
+}
+
+impl Metadata {
+	/// Wraps the version of the underlying application with the current fee version.
+	pub fn new(app_version: &Version) -> Self {
+		Self { fee_version: VERSION.to_string(), app_version: app_version.to_string() }
+	}
+
+	/// Parses a channel version as fee metadata, returns `None` if the version isn't fee
+	/// metadata, i.e. the channel isn't fee enabled.
+	pub fn from_version(version: &Version) -> Option<Self> {
+		serde_json::from_str(&version.to_string()).ok()
+	}
+
+	/// Fails if the metadata was negotiated with an unsupported fee version.
+	pub fn validate(&self) -> Result<(), Error> {
+		if self.fee_version != VERSION {
+			return Err(Error::invalid_fee_version(self.fee_version.clone()))
+		}
+		Ok(())
+	}
+
+	pub fn app_version(&self) -> Version {
+		Version::new(self.app_version.clone())
+	}
+
+	pub fn to_version(&self) -> Version {
+		Version::new(serde_json::to_string(self).expect("metadata is always serializable; qed"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn metadata_round_trips_through_version() {
+		let version = Metadata::new(&Version::ics20()).to_version();
+		assert_eq!(version.to_string(), r#"{"fee_version":"ics29-1","app_version":"ics20-1"}"#);
+
+		let metadata = Metadata::from_version(&version).unwrap();
+		assert!(metadata.validate().is_ok());
+		assert_eq!(metadata.app_version(), Version::ics20());
+	}
+
+	#[test]
+	fn plain_versions_are_not_metadata() {
+		assert_eq!(Metadata::from_version(&Version::ics20()), None);
+		assert_eq!(Metadata::from_version(&Version::empty()), None);
+	}
+
+	#[test]
+	fn unknown_fee_versions_are_rejected() {
+		let metadata = Metadata::from_version(&Version::new(
+			r#"{"fee_version":"ics29-2","app_version":"ics20-1"}"#.to_string(),
+		))
+		.unwrap();
+		assert!(metadata.validate().is_err());
+	}
+}
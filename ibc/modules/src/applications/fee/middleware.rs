@@ -0,0 +1,489 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The fee middleware, which adds ICS29 fees to the channels of the module it wraps.
+
+use crate::prelude::*;
+
+use core::{fmt, marker::PhantomData};
+
+use super::{
+	acknowledgement::IncentivizedAcknowledgement,
+	context::FeeContext,
+	error::Error,
+	fee::PacketId,
+	metadata::Metadata,
+	relay::{
+		distribute_fees_on_acknowledgement, distribute_fees_on_timeout,
+		refund_fees_on_channel_closure,
+	},
+};
+use crate::{
+	core::{
+		ics04_channel::{
+			channel::{Counterparty, Order},
+			error::Error as Ics04Error,
+			msgs::acknowledgement::Acknowledgement as GenericAcknowledgement,
+			packet::Packet,
+			Version,
+		},
+		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+		ics26_routing::context::{Module, ModuleCallbackContext, ModuleOutputBuilder},
+	},
+	signer::Signer,
+};
+
+/// Wraps a [`Module`] to negotiate the fee version during the channel handshake, and to escrow
+/// and pay out the fees of the packets sent on the channels where it was negotiated. Channels
+/// whose version isn't fee [`Metadata`] are passed through to the wrapped module untouched.
+///
+/// The module callbacks only get read access to the host, so a fresh `C` is created to write the
+/// fee state, the same way the host's own modules do.
+///
+/// Modules write their own acknowledgements, so the host must pass them through
+/// [`wrap_acknowledgement`](super::relay::wrap_acknowledgement) before committing them.
+pub struct FeeMiddleware<M, C> {
+	inner: M,
+	_ctx: PhantomData<fn() -> C>,
+}
+
+// implemented by hand so that `C` isn't required to implement them: the host context usually
+// contains the router, and so the middleware itself.
+impl<M: Clone, C> Clone for FeeMiddleware<M, C> {
+	fn clone(&self) -> Self {
+		Self::new(self.inner.clone())
+	}
+}
+
+impl<M: fmt::Debug, C> fmt::Debug for FeeMiddleware<M, C> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("FeeMiddleware").field("inner", &self.inner).finish()
+	}
+}
+
+impl<M: PartialEq, C> PartialEq for FeeMiddleware<M, C> {
+	fn eq(&self, other: &Self) -> bool {
+		self.inner == other.inner
+	}
+}
+
+impl<M: Eq, C> Eq for FeeMiddleware<M, C> {}
+
+impl<M, C> FeeMiddleware<M, C> {
+	pub fn new(inner: M) -> Self {
+		Self { inner, _ctx: PhantomData }
+	}
+
+	pub fn inner(&self) -> &M {
+		&self.inner
+	}
+}
+
+impl<M: Default, C> Default for FeeMiddleware<M, C> {
+	fn default() -> Self {
+		Self::new(M::default())
+	}
+}
+
+fn app_error(e: Error) -> Ics04Error {
+	Ics04Error::app_module(e.to_string())
+}
+
+impl<M, C> Module for FeeMiddleware<M, C>
+where
+	M: Module,
+	C: FeeContext + Default + 'static,
+{
+	fn on_chan_open_init(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let metadata = match Metadata::from_version(version) {
+			Some(metadata) => metadata,
+			None =>
+				return self.inner.on_chan_open_init(
+					ctx,
+					output,
+					order,
+					connection_hops,
+					port_id,
+					channel_id,
+					counterparty,
+					version,
+					relayer,
+				),
+		};
+		metadata.validate().map_err(app_error)?;
+		self.inner.on_chan_open_init(
+			ctx,
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			&metadata.app_version(),
+			relayer,
+		)
+	}
+
+	fn on_chan_open_try(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty: &Counterparty,
+		version: &Version,
+		counterparty_version: &Version,
+		relayer: &Signer,
+	) -> Result<Version, Ics04Error> {
+		let counterparty_metadata = match Metadata::from_version(counterparty_version) {
+			Some(metadata) => metadata,
+			None =>
+				return self.inner.on_chan_open_try(
+					ctx,
+					output,
+					order,
+					connection_hops,
+					port_id,
+					channel_id,
+					counterparty,
+					version,
+					counterparty_version,
+					relayer,
+				),
+		};
+		counterparty_metadata.validate().map_err(app_error)?;
+		let app_version = Metadata::from_version(version)
+			.map(|metadata| metadata.app_version())
+			.unwrap_or_else(|| version.clone());
+		let app_version = self.inner.on_chan_open_try(
+			ctx,
+			output,
+			order,
+			connection_hops,
+			port_id,
+			channel_id,
+			counterparty,
+			&app_version,
+			&counterparty_metadata.app_version(),
+			relayer,
+		)?;
+		// fees are only enabled once the handshake is confirmed, so that a failed handshake
+		// leaves no fee state behind.
+		Ok(Metadata::new(&app_version).to_version())
+	}
+
+	fn on_chan_open_ack(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		// the channel end still holds the version proposed on init.
+		let channel_end = ctx.channel_end(&(port_id.clone(), *channel_id))?;
+		if Metadata::from_version(channel_end.version()).is_none() {
+			return self.inner.on_chan_open_ack(
+				ctx,
+				output,
+				port_id,
+				channel_id,
+				counterparty_version,
+				relayer,
+			)
+		}
+		// a handshake started with fees must complete with fees
+		let metadata = Metadata::from_version(counterparty_version)
+			.ok_or_else(|| Error::missing_fee_version(port_id.clone(), *channel_id))
+			.map_err(app_error)?;
+		metadata.validate().map_err(app_error)?;
+		self.inner.on_chan_open_ack(
+			ctx,
+			output,
+			port_id,
+			channel_id,
+			&metadata.app_version(),
+			relayer,
+		)?;
+		C::default().set_fee_enabled(port_id, channel_id);
+		Ok(())
+	}
+
+	fn on_chan_open_confirm(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_open_confirm(ctx, output, port_id, channel_id, relayer)?;
+		// the channel end holds the version negotiated on try.
+		let channel_end = ctx.channel_end(&(port_id.clone(), *channel_id))?;
+		if Metadata::from_version(channel_end.version()).is_some() {
+			C::default().set_fee_enabled(port_id, channel_id);
+		}
+		Ok(())
+	}
+
+	fn on_chan_close_init(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_init(ctx, output, port_id, channel_id, relayer)?;
+		refund_fees_on_channel_closure(&mut C::default(), output, port_id, channel_id)
+			.map_err(app_error)
+	}
+
+	fn on_chan_close_confirm(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		self.inner.on_chan_close_confirm(ctx, output, port_id, channel_id, relayer)?;
+		refund_fees_on_channel_closure(&mut C::default(), output, port_id, channel_id)
+			.map_err(app_error)
+	}
+
+	fn on_chan_upgrade_init(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		proposed_order: Order,
+		proposed_connection_hops: &[ConnectionId],
+		proposed_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		let metadata = match Metadata::from_version(proposed_version) {
+			Some(metadata) => metadata,
+			None =>
+				return self.inner.on_chan_upgrade_init(
+					ctx,
+					output,
+					port_id,
+					channel_id,
+					proposed_order,
+					proposed_connection_hops,
+					proposed_version,
+				),
+		};
+		metadata.validate().map_err(app_error)?;
+		let app_version = self.inner.on_chan_upgrade_init(
+			ctx,
+			output,
+			port_id,
+			channel_id,
+			proposed_order,
+			proposed_connection_hops,
+			&metadata.app_version(),
+		)?;
+		Ok(Metadata::new(&app_version).to_version())
+	}
+
+	fn on_chan_upgrade_try(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		proposed_order: Order,
+		proposed_connection_hops: &[ConnectionId],
+		proposed_version: &Version,
+	) -> Result<Version, Ics04Error> {
+		let metadata = match Metadata::from_version(proposed_version) {
+			Some(metadata) => metadata,
+			None =>
+				return self.inner.on_chan_upgrade_try(
+					ctx,
+					output,
+					port_id,
+					channel_id,
+					proposed_order,
+					proposed_connection_hops,
+					proposed_version,
+				),
+		};
+		metadata.validate().map_err(app_error)?;
+		let app_version = self.inner.on_chan_upgrade_try(
+			ctx,
+			output,
+			port_id,
+			channel_id,
+			proposed_order,
+			proposed_connection_hops,
+			&metadata.app_version(),
+		)?;
+		Ok(Metadata::new(&app_version).to_version())
+	}
+
+	fn on_chan_upgrade_ack(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		counterparty_version: &Version,
+	) -> Result<(), Ics04Error> {
+		let metadata = match Metadata::from_version(counterparty_version) {
+			Some(metadata) => metadata,
+			None =>
+				return self.inner.on_chan_upgrade_ack(
+					ctx,
+					output,
+					port_id,
+					channel_id,
+					counterparty_version,
+				),
+		};
+		metadata.validate().map_err(app_error)?;
+		self.inner
+			.on_chan_upgrade_ack(ctx, output, port_id, channel_id, &metadata.app_version())
+	}
+
+	fn on_chan_upgrade_open(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		order: Order,
+		connection_hops: &[ConnectionId],
+		version: &Version,
+	) -> Result<(), Ics04Error> {
+		let mut fee_ctx = C::default();
+		let app_version = match Metadata::from_version(version) {
+			Some(metadata) => {
+				fee_ctx.set_fee_enabled(port_id, channel_id);
+				metadata.app_version()
+			},
+			None => {
+				// the upgrade removed fees from the channel, the fees still in escrow can no
+				// longer be paid out
+				if fee_ctx.is_fee_enabled(port_id, channel_id) {
+					refund_fees_on_channel_closure(&mut fee_ctx, output, port_id, channel_id)
+						.map_err(app_error)?;
+					fee_ctx.delete_fee_enabled(port_id, channel_id);
+				}
+				version.clone()
+			},
+		};
+		self.inner.on_chan_upgrade_open(
+			ctx,
+			output,
+			port_id,
+			channel_id,
+			order,
+			connection_hops,
+			&app_version,
+		)
+	}
+
+	fn on_recv_packet(
+		&self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let mut fee_ctx = C::default();
+		if !fee_ctx.is_fee_enabled(&packet.destination_port, &packet.destination_channel) {
+			return self.inner.on_recv_packet(ctx, output, packet, relayer)
+		}
+		// recorded for `wrap_acknowledgement`, which is called once the module writes its
+		// acknowledgement.
+		let packet_id = PacketId::destination(packet);
+		let forward_relayer = fee_ctx
+			.get_counterparty_payee(relayer, &packet.destination_channel)
+			.unwrap_or_default();
+		fee_ctx.set_forward_relayer_address(&packet_id, &forward_relayer);
+		self.inner.on_recv_packet(ctx, output, packet, relayer).map_err(|e| {
+			fee_ctx.delete_forward_relayer_address(&packet_id);
+			e
+		})
+	}
+
+	fn on_acknowledgement_packet(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		acknowledgement: &GenericAcknowledgement,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let mut fee_ctx = C::default();
+		if !fee_ctx.is_fee_enabled(&packet.source_port, &packet.source_channel) {
+			return self.inner.on_acknowledgement_packet(
+				ctx,
+				output,
+				packet,
+				acknowledgement,
+				relayer,
+			)
+		}
+		let ack = IncentivizedAcknowledgement::from_json_bytes(acknowledgement.as_ref())
+			.map_err(app_error)?;
+		distribute_fees_on_acknowledgement(
+			&mut fee_ctx,
+			output,
+			&PacketId::source(packet),
+			&ack.forward_relayer_address,
+			relayer,
+		)
+		.map_err(app_error)?;
+		self.inner.on_acknowledgement_packet(
+			ctx,
+			output,
+			packet,
+			&GenericAcknowledgement::from_bytes(ack.app_acknowledgement),
+			relayer,
+		)
+	}
+
+	fn on_timeout_packet(
+		&mut self,
+		ctx: &dyn ModuleCallbackContext,
+		output: &mut ModuleOutputBuilder,
+		packet: &Packet,
+		relayer: &Signer,
+	) -> Result<(), Ics04Error> {
+		let mut fee_ctx = C::default();
+		if fee_ctx.is_fee_enabled(&packet.source_port, &packet.source_channel) {
+			distribute_fees_on_timeout(&mut fee_ctx, output, &PacketId::source(packet), relayer)
+				.map_err(app_error)?;
+		}
+		self.inner.on_timeout_packet(ctx, output, packet, relayer)
+	}
+}
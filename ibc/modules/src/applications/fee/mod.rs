@@ -0,0 +1,32 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ICS 29: Fee middleware incentivizes relayers by paying them fees, escrowed by the packet
+//! senders, for delivering packets, acknowledgements and timeouts. It wraps any application
+//! module and is negotiated per channel as part of the channel version.
+pub mod acknowledgement;
+pub mod context;
+pub mod error;
+pub mod events;
+pub mod fee;
+pub mod metadata;
+pub mod middleware;
+pub mod msgs;
+pub mod relay;
+
+/// Module identifier for the ICS29 middleware.
+pub const MODULE_ID_STR: &str = "feeibc";
+
+/// ICS29 middleware current version.
+pub const VERSION: &str = "ics29-1";
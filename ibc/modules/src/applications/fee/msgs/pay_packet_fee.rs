@@ -0,0 +1,180 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Messages escrowing the fees of a packet.
+
+use crate::prelude::*;
+
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::applications::fee::v1::{
+		MsgPayPacketFee as RawMsgPayPacketFee, MsgPayPacketFeeAsync as RawMsgPayPacketFeeAsync,
+	},
+};
+use tendermint_proto::Protobuf;
+
+use crate::{
+	applications::fee::{
+		error::Error,
+		fee::{Fee, PacketFee, PacketId},
+	},
+	core::ics24_host::identifier::{ChannelId, PortId},
+	signer::Signer,
+	tx_msg::Msg,
+};
+
+pub const PAY_PACKET_FEE_TYPE_URL: &str = "/ibc.applications.fee.v1.MsgPayPacketFee";
+pub const PAY_PACKET_FEE_ASYNC_TYPE_URL: &str = "/ibc.applications.fee.v1.MsgPayPacketFeeAsync";
+
+/// Escrows a fee for the next packet sent on a channel, it's meant to be submitted in the same
+/// transaction as the message sending the packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgPayPacketFee {
+	pub fee: Fee,
+	pub source_port_id: PortId,
+	pub source_channel_id: ChannelId,
+	/// The account paying the fee, which is refunded the unused part.
+	pub signer: Signer,
+	/// Relayers allowed to claim the fee, currently required to be empty.
+	pub relayers: Vec<Signer>,
+}
+
+impl Msg for MsgPayPacketFee {
+	type ValidationError = Error;
+	type Raw = RawMsgPayPacketFee;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		PAY_PACKET_FEE_TYPE_URL.to_string()
+	}
+}
+
+impl TryFrom<RawMsgPayPacketFee> for MsgPayPacketFee {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgPayPacketFee) -> Result<Self, Self::Error> {
+		Ok(MsgPayPacketFee {
+			fee: raw_msg.fee.ok_or_else(Error::missing_fee)?.try_into()?,
+			source_port_id: raw_msg
+				.source_port_id
+				.parse()
+				.map_err(|e| Error::invalid_port_id(raw_msg.source_port_id.clone(), e))?,
+			source_channel_id: raw_msg
+				.source_channel_id
+				.parse()
+				.map_err(|e| Error::invalid_channel_id(raw_msg.source_channel_id.clone(), e))?,
+			signer: raw_msg.signer.parse().map_err(Error::signer)?,
+			relayers: raw_msg
+				.relayers
+				.into_iter()
+				.map(|relayer| relayer.parse().map_err(Error::signer))
+				.collect::<Result<_, _>>()?,
+		})
+	}
+}
+
+impl From<MsgPayPacketFee> for RawMsgPayPacketFee {
+	fn from(domain_msg: MsgPayPacketFee) -> Self {
+		RawMsgPayPacketFee {
+			fee: Some(domain_msg.fee.into()),
+			source_port_id: domain_msg.source_port_id.to_string(),
+			source_channel_id: domain_msg.source_channel_id.to_string(),
+			signer: domain_msg.signer.to_string(),
+			relayers: domain_msg.relayers.into_iter().map(|relayer| relayer.to_string()).collect(),
+		}
+	}
+}
+
+impl Protobuf<RawMsgPayPacketFee> for MsgPayPacketFee {}
+
+impl TryFrom<Any> for MsgPayPacketFee {
+	type Error = Error;
+
+	fn try_from(raw: Any) -> Result<Self, Self::Error> {
+		match raw.type_url.as_str() {
+			PAY_PACKET_FEE_TYPE_URL =>
+				MsgPayPacketFee::decode_vec(&raw.value).map_err(Error::decode_raw_msg),
+			_ => Err(Error::unknown_msg_type(raw.type_url)),
+		}
+	}
+}
+
+impl From<MsgPayPacketFee> for Any {
+	fn from(msg: MsgPayPacketFee) -> Self {
+		Self { type_url: PAY_PACKET_FEE_TYPE_URL.to_string(), value: msg.encode_vec() }
+	}
+}
+
+/// Escrows a fee for a packet that was already sent and isn't acknowledged yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgPayPacketFeeAsync {
+	pub packet_id: PacketId,
+	pub packet_fee: PacketFee,
+}
+
+impl Msg for MsgPayPacketFeeAsync {
+	type ValidationError = Error;
+	type Raw = RawMsgPayPacketFeeAsync;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		PAY_PACKET_FEE_ASYNC_TYPE_URL.to_string()
+	}
+}
+
+impl TryFrom<RawMsgPayPacketFeeAsync> for MsgPayPacketFeeAsync {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgPayPacketFeeAsync) -> Result<Self, Self::Error> {
+		Ok(MsgPayPacketFeeAsync {
+			packet_id: raw_msg.packet_id.ok_or_else(Error::missing_packet_id)?.try_into()?,
+			packet_fee: raw_msg.packet_fee.ok_or_else(Error::missing_fee)?.try_into()?,
+		})
+	}
+}
+
+impl From<MsgPayPacketFeeAsync> for RawMsgPayPacketFeeAsync {
+	fn from(domain_msg: MsgPayPacketFeeAsync) -> Self {
+		RawMsgPayPacketFeeAsync {
+			packet_id: Some(domain_msg.packet_id.into()),
+			packet_fee: Some(domain_msg.packet_fee.into()),
+		}
+	}
+}
+
+impl Protobuf<RawMsgPayPacketFeeAsync> for MsgPayPacketFeeAsync {}
+
+impl TryFrom<Any> for MsgPayPacketFeeAsync {
+	type Error = Error;
+
+	fn try_from(raw: Any) -> Result<Self, Self::Error> {
+		match raw.type_url.as_str() {
+			PAY_PACKET_FEE_ASYNC_TYPE_URL =>
+				MsgPayPacketFeeAsync::decode_vec(&raw.value).map_err(Error::decode_raw_msg),
+			_ => Err(Error::unknown_msg_type(raw.type_url)),
+		}
+	}
+}
+
+impl From<MsgPayPacketFeeAsync> for Any {
+	fn from(msg: MsgPayPacketFeeAsync) -> Self {
+		Self { type_url: PAY_PACKET_FEE_ASYNC_TYPE_URL.to_string(), value: msg.encode_vec() }
+	}
+}
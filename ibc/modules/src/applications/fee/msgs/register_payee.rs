@@ -0,0 +1,187 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Messages registering where the fees earned by a relayer should be paid.
+
+use crate::prelude::*;
+
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::applications::fee::v1::{
+		MsgRegisterCounterpartyPayee as RawMsgRegisterCounterpartyPayee,
+		MsgRegisterPayee as RawMsgRegisterPayee,
+	},
+};
+use tendermint_proto::Protobuf;
+
+use crate::{
+	applications::fee::error::Error,
+	core::ics24_host::identifier::{ChannelId, PortId},
+	signer::Signer,
+	tx_msg::Msg,
+};
+
+pub const REGISTER_PAYEE_TYPE_URL: &str = "/ibc.applications.fee.v1.MsgRegisterPayee";
+pub const REGISTER_COUNTERPARTY_PAYEE_TYPE_URL: &str =
+	"/ibc.applications.fee.v1.MsgRegisterCounterpartyPayee";
+
+/// Registers the account the acknowledgement and timeout fees earned by `relayer` on a channel
+/// are paid to, instead of the relayer itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgRegisterPayee {
+	pub port_id: PortId,
+	pub channel_id: ChannelId,
+	pub relayer: Signer,
+	pub payee: Signer,
+}
+
+impl Msg for MsgRegisterPayee {
+	type ValidationError = Error;
+	type Raw = RawMsgRegisterPayee;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		REGISTER_PAYEE_TYPE_URL.to_string()
+	}
+}
+
+impl TryFrom<RawMsgRegisterPayee> for MsgRegisterPayee {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgRegisterPayee) -> Result<Self, Self::Error> {
+		Ok(MsgRegisterPayee {
+			port_id: raw_msg
+				.port_id
+				.parse()
+				.map_err(|e| Error::invalid_port_id(raw_msg.port_id.clone(), e))?,
+			channel_id: raw_msg
+				.channel_id
+				.parse()
+				.map_err(|e| Error::invalid_channel_id(raw_msg.channel_id.clone(), e))?,
+			relayer: raw_msg.relayer.parse().map_err(Error::signer)?,
+			payee: raw_msg.payee.parse().map_err(Error::signer)?,
+		})
+	}
+}
+
+impl From<MsgRegisterPayee> for RawMsgRegisterPayee {
+	fn from(domain_msg: MsgRegisterPayee) -> Self {
+		RawMsgRegisterPayee {
+			port_id: domain_msg.port_id.to_string(),
+			channel_id: domain_msg.channel_id.to_string(),
+			relayer: domain_msg.relayer.to_string(),
+			payee: domain_msg.payee.to_string(),
+		}
+	}
+}
+
+impl Protobuf<RawMsgRegisterPayee> for MsgRegisterPayee {}
+
+impl TryFrom<Any> for MsgRegisterPayee {
+	type Error = Error;
+
+	fn try_from(raw: Any) -> Result<Self, Self::Error> {
+		match raw.type_url.as_str() {
+			REGISTER_PAYEE_TYPE_URL =>
+				MsgRegisterPayee::decode_vec(&raw.value).map_err(Error::decode_raw_msg),
+			_ => Err(Error::unknown_msg_type(raw.type_url)),
+		}
+	}
+}
+
+impl From<MsgRegisterPayee> for Any {
+	fn from(msg: MsgRegisterPayee) -> Self {
+		Self { type_url: REGISTER_PAYEE_TYPE_URL.to_string(), value: msg.encode_vec() }
+	}
+}
+
+/// Registers the address on the counterparty chain the receive fees earned by `relayer` on a
+/// channel are paid to. The address is forwarded to the counterparty in the acknowledgement, so
+/// it isn't validated by this chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgRegisterCounterpartyPayee {
+	pub port_id: PortId,
+	pub channel_id: ChannelId,
+	pub relayer: Signer,
+	pub counterparty_payee: String,
+}
+
+impl Msg for MsgRegisterCounterpartyPayee {
+	type ValidationError = Error;
+	type Raw = RawMsgRegisterCounterpartyPayee;
+
+	fn route(&self) -> String {
+		crate::keys::ROUTER_KEY.to_string()
+	}
+
+	fn type_url(&self) -> String {
+		REGISTER_COUNTERPARTY_PAYEE_TYPE_URL.to_string()
+	}
+}
+
+impl TryFrom<RawMsgRegisterCounterpartyPayee> for MsgRegisterCounterpartyPayee {
+	type Error = Error;
+
+	fn try_from(raw_msg: RawMsgRegisterCounterpartyPayee) -> Result<Self, Self::Error> {
+		if raw_msg.counterparty_payee.trim().is_empty() {
+			return Err(Error::empty_counterparty_payee())
+		}
+		Ok(MsgRegisterCounterpartyPayee {
+			port_id: raw_msg
+				.port_id
+				.parse()
+				.map_err(|e| Error::invalid_port_id(raw_msg.port_id.clone(), e))?,
+			channel_id: raw_msg
+				.channel_id
+				.parse()
+				.map_err(|e| Error::invalid_channel_id(raw_msg.channel_id.clone(), e))?,
+			relayer: raw_msg.relayer.parse().map_err(Error::signer)?,
+			counterparty_payee: raw_msg.counterparty_payee,
+		})
+	}
+}
+
+impl From<MsgRegisterCounterpartyPayee> for RawMsgRegisterCounterpartyPayee {
+	fn from(domain_msg: MsgRegisterCounterpartyPayee) -> Self {
+		RawMsgRegisterCounterpartyPayee {
+			port_id: domain_msg.port_id.to_string(),
+			channel_id: domain_msg.channel_id.to_string(),
+			relayer: domain_msg.relayer.to_string(),
+			counterparty_payee: domain_msg.counterparty_payee,
+		}
+	}
+}
+
+impl Protobuf<RawMsgRegisterCounterpartyPayee> for MsgRegisterCounterpartyPayee {}
+
+impl TryFrom<Any> for MsgRegisterCounterpartyPayee {
+	type Error = Error;
+
+	fn try_from(raw: Any) -> Result<Self, Self::Error> {
+		match raw.type_url.as_str() {
+			REGISTER_COUNTERPARTY_PAYEE_TYPE_URL =>
+				MsgRegisterCounterpartyPayee::decode_vec(&raw.value).map_err(Error::decode_raw_msg),
+			_ => Err(Error::unknown_msg_type(raw.type_url)),
+		}
+	}
+}
+
+impl From<MsgRegisterCounterpartyPayee> for Any {
+	fn from(msg: MsgRegisterCounterpartyPayee) -> Self {
+		Self { type_url: REGISTER_COUNTERPARTY_PAYEE_TYPE_URL.to_string(), value: msg.encode_vec() }
+	}
+}
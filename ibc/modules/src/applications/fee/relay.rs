@@ -0,0 +1,709 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Escrow of the fees paid for relaying packets, and their distribution to the relayers.
+
+use crate::prelude::*;
+
+use super::{
+	acknowledgement::IncentivizedAcknowledgement,
+	context::FeeContext,
+	error::Error,
+	events::RefundFailedEvent,
+	fee::{PacketFee, PacketId},
+	msgs::{
+		pay_packet_fee::{MsgPayPacketFee, MsgPayPacketFeeAsync},
+		register_payee::{MsgRegisterCounterpartyPayee, MsgRegisterPayee},
+	},
+};
+use crate::{
+	applications::transfer::PrefixedCoin,
+	core::{
+		ics04_channel::packet::Packet,
+		ics24_host::identifier::{ChannelId, PortId},
+		ics26_routing::context::ModuleOutputBuilder,
+	},
+	signer::Signer,
+};
+
+pub fn register_payee(ctx: &mut impl FeeContext, msg: MsgRegisterPayee) -> Result<(), Error> {
+	if !ctx.is_fee_enabled(&msg.port_id, &msg.channel_id) {
+		return Err(Error::fee_not_enabled(msg.port_id, msg.channel_id))
+	}
+	ctx.set_payee(&msg.relayer, &msg.channel_id, &msg.payee);
+	Ok(())
+}
+
+pub fn register_counterparty_payee(
+	ctx: &mut impl FeeContext,
+	msg: MsgRegisterCounterpartyPayee,
+) -> Result<(), Error> {
+	if !ctx.is_fee_enabled(&msg.port_id, &msg.channel_id) {
+		return Err(Error::fee_not_enabled(msg.port_id, msg.channel_id))
+	}
+	ctx.set_counterparty_payee(&msg.relayer, &msg.channel_id, &msg.counterparty_payee);
+	Ok(())
+}
+
+/// Escrows the fee for the next packet sent on the channel.
+pub fn pay_packet_fee(ctx: &mut impl FeeContext, msg: MsgPayPacketFee) -> Result<(), Error> {
+	if !ctx.is_fee_enabled(&msg.source_port_id, &msg.source_channel_id) {
+		return Err(Error::fee_not_enabled(msg.source_port_id, msg.source_channel_id))
+	}
+	let sequence = ctx
+		.get_next_sequence_send(&(msg.source_port_id.clone(), msg.source_channel_id))
+		.map_err(Error::ics04_channel)?;
+	let packet_id = PacketId::new(msg.source_port_id, msg.source_channel_id, sequence);
+	let packet_fee = PacketFee { fee: msg.fee, refund_address: msg.signer, relayers: msg.relayers };
+	escrow_packet_fee(ctx, &packet_id, packet_fee)
+}
+
+/// Escrows a fee for a packet in flight.
+pub fn pay_packet_fee_async(
+	ctx: &mut impl FeeContext,
+	msg: MsgPayPacketFeeAsync,
+) -> Result<(), Error> {
+	let PacketId { port_id, channel_id, sequence } = msg.packet_id.clone();
+	if !ctx.is_fee_enabled(&port_id, &channel_id) {
+		return Err(Error::fee_not_enabled(port_id, channel_id))
+	}
+	if ctx.get_packet_commitment(&(port_id.clone(), channel_id, sequence)).is_err() {
+		return Err(Error::packet_not_sent(port_id, channel_id, sequence.into()))
+	}
+	escrow_packet_fee(ctx, &msg.packet_id, msg.packet_fee)
+}
+
+/// Moves the total of `packet_fee` from its refund address to the fee escrow account.
+pub fn escrow_packet_fee<Ctx: FeeContext>(
+	ctx: &mut Ctx,
+	packet_id: &PacketId,
+	packet_fee: PacketFee,
+) -> Result<(), Error> {
+	if !packet_fee.relayers.is_empty() {
+		return Err(Error::relayers_not_supported())
+	}
+	let refund_address =
+		<Ctx as FeeContext>::AccountId::try_from(packet_fee.refund_address.clone())
+			.map_err(|_| Error::parse_account_failure())?;
+	let escrow_address = ctx.get_fee_escrow_address()?;
+	for coin in packet_fee.fee.total() {
+		ctx.send_coins(&refund_address, &escrow_address, coin)
+			.map_err(Error::transfer)?;
+	}
+	let mut fees = ctx.get_fees_in_escrow(packet_id);
+	fees.push(packet_fee);
+	ctx.store_fees_in_escrow(packet_id, fees);
+	Ok(())
+}
+
+/// Wraps the acknowledgement written by the underlying application for a packet received on a
+/// fee enabled channel, forwarding the counterparty payee of the relayer that delivered it.
+/// Acknowledgements on channels without fees are returned unchanged.
+pub fn wrap_acknowledgement(
+	ctx: &mut impl FeeContext,
+	packet: &Packet,
+	app_acknowledgement: Vec<u8>,
+	underlying_app_success: bool,
+) -> Vec<u8> {
+	if !ctx.is_fee_enabled(&packet.destination_port, &packet.destination_channel) {
+		return app_acknowledgement
+	}
+	let packet_id = PacketId::destination(packet);
+	let forward_relayer_address = ctx.get_forward_relayer_address(&packet_id).unwrap_or_default();
+	ctx.delete_forward_relayer_address(&packet_id);
+	IncentivizedAcknowledgement {
+		app_acknowledgement,
+		forward_relayer_address,
+		underlying_app_success,
+	}
+	.to_json_bytes()
+}
+
+/// Pays the receive fee to the forward relayer and the acknowledgement fee to the payee of
+/// `relayer`, and refunds the timeout fee. The receive fee is refunded as well if the packet was
+/// delivered by a relayer without a counterparty payee.
+pub fn distribute_fees_on_acknowledgement<Ctx: FeeContext>(
+	ctx: &mut Ctx,
+	output: &mut ModuleOutputBuilder,
+	packet_id: &PacketId,
+	forward_relayer: &str,
+	relayer: &Signer,
+) -> Result<(), Error> {
+	let fees = ctx.get_fees_in_escrow(packet_id);
+	if fees.is_empty() {
+		return Ok(())
+	}
+	let escrow_address = ctx.get_fee_escrow_address()?;
+	let reverse_relayer = payee(ctx, relayer, &packet_id.channel_id);
+	let forward_relayer = forward_relayer.parse::<Signer>().ok();
+	for PacketFee { fee, refund_address, .. } in fees {
+		let recv_receiver = forward_relayer.as_ref().unwrap_or(&refund_address);
+		let mut payout = Payout {
+			ctx: &mut *ctx,
+			output: &mut *output,
+			packet_id,
+			escrow_address: &escrow_address,
+			refund_address: &refund_address,
+		};
+		payout.distribute(recv_receiver, &fee.recv_fee);
+		payout.distribute(&reverse_relayer, &fee.ack_fee);
+		payout.distribute(&refund_address, &fee.timeout_fee);
+	}
+	ctx.delete_fees_in_escrow(packet_id);
+	Ok(())
+}
+
+/// Pays the timeout fee to the payee of `relayer`, and refunds the receive and acknowledgement
+/// fees.
+pub fn distribute_fees_on_timeout<Ctx: FeeContext>(
+	ctx: &mut Ctx,
+	output: &mut ModuleOutputBuilder,
+	packet_id: &PacketId,
+	relayer: &Signer,
+) -> Result<(), Error> {
+	let fees = ctx.get_fees_in_escrow(packet_id);
+	if fees.is_empty() {
+		return Ok(())
+	}
+	let escrow_address = ctx.get_fee_escrow_address()?;
+	let timeout_relayer = payee(ctx, relayer, &packet_id.channel_id);
+	for PacketFee { fee, refund_address, .. } in fees {
+		let mut payout = Payout {
+			ctx: &mut *ctx,
+			output: &mut *output,
+			packet_id,
+			escrow_address: &escrow_address,
+			refund_address: &refund_address,
+		};
+		payout.distribute(&refund_address, &fee.recv_fee);
+		payout.distribute(&refund_address, &fee.ack_fee);
+		payout.distribute(&timeout_relayer, &fee.timeout_fee);
+	}
+	ctx.delete_fees_in_escrow(packet_id);
+	Ok(())
+}
+
+/// Refunds all the fees escrowed for the packets sent on a channel that is being closed.
+pub fn refund_fees_on_channel_closure<Ctx: FeeContext>(
+	ctx: &mut Ctx,
+	output: &mut ModuleOutputBuilder,
+	port_id: &PortId,
+	channel_id: &ChannelId,
+) -> Result<(), Error> {
+	let escrow_address = ctx.get_fee_escrow_address()?;
+	for (packet_id, fees) in ctx.get_channel_fees_in_escrow(port_id, channel_id) {
+		for PacketFee { fee, refund_address, .. } in fees {
+			let coins = fee.total().cloned().collect::<Vec<_>>();
+			let mut payout = Payout {
+				ctx: &mut *ctx,
+				output: &mut *output,
+				packet_id: &packet_id,
+				escrow_address: &escrow_address,
+				refund_address: &refund_address,
+			};
+			payout.distribute(&refund_address, &coins);
+		}
+		ctx.delete_fees_in_escrow(&packet_id);
+	}
+	Ok(())
+}
+
+/// The registered payee of `relayer` on the channel, or the relayer itself.
+fn payee(ctx: &impl FeeContext, relayer: &Signer, channel_id: &ChannelId) -> Signer {
+	ctx.get_payee(relayer, channel_id).unwrap_or_else(|| relayer.clone())
+}
+
+/// Pays out the escrowed fee of a packet.
+struct Payout<'a, Ctx: FeeContext> {
+	ctx: &'a mut Ctx,
+	output: &'a mut ModuleOutputBuilder,
+	packet_id: &'a PacketId,
+	escrow_address: &'a <Ctx as FeeContext>::AccountId,
+	refund_address: &'a Signer,
+}
+
+impl<'a, Ctx: FeeContext> Payout<'a, Ctx> {
+	/// Pays `coins` from escrow to `receiver`, falling back to the refund address if the receiver
+	/// can't be paid. Failed refunds don't fail the packet lifecycle, so that a bad fee can't
+	/// block the acknowledgement or timeout of a packet: the coins are left in escrow and a
+	/// [`RefundFailedEvent`] is emitted instead.
+	fn distribute(&mut self, receiver: &Signer, coins: &[PrefixedCoin]) {
+		let receiver = <Ctx as FeeContext>::AccountId::try_from(receiver.clone()).ok();
+		for coin in coins.iter().filter(|coin| !coin.amount.as_u256().is_zero()) {
+			let paid = receiver
+				.as_ref()
+				.map(|receiver| self.ctx.send_coins(self.escrow_address, receiver, coin).is_ok())
+				.unwrap_or(false);
+			if paid {
+				continue
+			}
+			if let Err(e) = self.refund(coin) {
+				self.output.log(format!(
+					"failed to refund fee {coin} of packet {:?} to {}: {e}",
+					self.packet_id, self.refund_address
+				));
+				self.output.emit(
+					RefundFailedEvent {
+						packet_id: self.packet_id.clone(),
+						refund_address: self.refund_address.clone(),
+						fee: coin.clone(),
+						reason: e.to_string(),
+					}
+					.into(),
+				);
+			}
+		}
+	}
+
+	fn refund(&mut self, coin: &PrefixedCoin) -> Result<(), Error> {
+		let refund_address = <Ctx as FeeContext>::AccountId::try_from(self.refund_address.clone())
+			.map_err(|_| Error::parse_account_failure())?;
+		self.ctx
+			.send_coins(self.escrow_address, &refund_address, coin)
+			.map_err(Error::transfer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		applications::{
+			fee::{
+				context::{FeeKeeper, FeeReader},
+				fee::Fee,
+			},
+			transfer::{context::BankKeeper, error::Error as Ics20Error, Amount, PrefixedDenom},
+		},
+		core::{
+			ics04_channel::{
+				channel::ChannelEnd,
+				commitment::{AcknowledgementCommitment, PacketCommitment},
+				context::ChannelReader,
+				error::Error as Ics04Error,
+				packet::{Receipt, Sequence},
+				upgrade::{ErrorReceipt, Upgrade},
+			},
+			ics24_host::identifier::{ClientId, ConnectionId},
+		},
+		timestamp::Timestamp,
+		Height,
+	};
+	use alloc::collections::{BTreeMap, BTreeSet};
+	use core::{str::FromStr, time::Duration};
+
+	const ESCROW: &str = "escrow";
+	const SENDER: &str = "sender";
+	const FORWARD_RELAYER: &str = "forward-relayer";
+	const REVERSE_RELAYER: &str = "reverse-relayer";
+	const PAYEE: &str = "payee";
+
+	#[derive(Default)]
+	struct MockFeeContext {
+		balances: BTreeMap<(Signer, String), Amount>,
+		/// Accounts that can't receive coins.
+		frozen: BTreeSet<Signer>,
+		next_sequence_send: u64,
+		payees: BTreeMap<Signer, Signer>,
+		escrow: Vec<(PacketId, Vec<PacketFee>)>,
+	}
+
+	fn signer(s: &str) -> Signer {
+		Signer::from_str(s).unwrap()
+	}
+
+	fn coins(amount: u64) -> Vec<PrefixedCoin> {
+		vec![PrefixedCoin {
+			denom: PrefixedDenom::from_str("uatom").unwrap(),
+			amount: amount.into(),
+		}]
+	}
+
+	fn channel() -> (PortId, ChannelId) {
+		(PortId::transfer(), ChannelId::new(0))
+	}
+
+	fn packet_id(sequence: u64) -> PacketId {
+		let (port_id, channel_id) = channel();
+		PacketId::new(port_id, channel_id, sequence.into())
+	}
+
+	impl MockFeeContext {
+		fn new() -> Self {
+			let mut ctx = Self { next_sequence_send: 1, ..Default::default() };
+			ctx.balances.insert((signer(SENDER), "uatom".to_string()), 1000u64.into());
+			ctx
+		}
+
+		fn balance(&self, account: &str) -> u64 {
+			self.balances
+				.get(&(signer(account), "uatom".to_string()))
+				.map(|amount| amount.as_u256().as_u64())
+				.unwrap_or_default()
+		}
+
+		/// Escrows a receive fee of 10, an acknowledgement fee of 20 and a timeout fee of 30 for
+		/// the next packet.
+		fn pay_fee(&mut self) {
+			let msg = MsgPayPacketFee {
+				fee: Fee { recv_fee: coins(10), ack_fee: coins(20), timeout_fee: coins(30) },
+				source_port_id: channel().0,
+				source_channel_id: channel().1,
+				signer: signer(SENDER),
+				relayers: vec![],
+			};
+			pay_packet_fee(self, msg).unwrap();
+			self.next_sequence_send += 1;
+		}
+	}
+
+	impl BankKeeper for MockFeeContext {
+		type AccountId = Signer;
+
+		fn send_coins(
+			&mut self,
+			from: &Signer,
+			to: &Signer,
+			amt: &PrefixedCoin,
+		) -> Result<(), Ics20Error> {
+			if self.frozen.contains(to) {
+				return Err(Ics20Error::implementation_specific("account is frozen".to_string()))
+			}
+			let denom = amt.denom.to_string();
+			let from_balance = self
+				.balances
+				.entry((from.clone(), denom.clone()))
+				.or_insert_with(|| 0u64.into());
+			*from_balance = from_balance.checked_sub(amt.amount).ok_or_else(|| {
+				Ics20Error::implementation_specific("insufficient funds".to_string())
+			})?;
+			let to_balance =
+				self.balances.entry((to.clone(), denom)).or_insert_with(|| 0u64.into());
+			*to_balance = to_balance.checked_add(amt.amount).expect("no overflow in tests");
+			Ok(())
+		}
+
+		fn mint_coins(&mut self, _account: &Signer, _amt: &PrefixedCoin) -> Result<(), Ics20Error> {
+			unimplemented!()
+		}
+
+		fn burn_coins(&mut self, _account: &Signer, _amt: &PrefixedCoin) -> Result<(), Ics20Error> {
+			unimplemented!()
+		}
+	}
+
+	impl FeeReader for MockFeeContext {
+		type AccountId = Signer;
+
+		fn get_fee_escrow_address(&self) -> Result<Signer, Error> {
+			Ok(signer(ESCROW))
+		}
+
+		fn is_fee_enabled(&self, port_id: &PortId, channel_id: &ChannelId) -> bool {
+			(port_id.clone(), *channel_id) == channel()
+		}
+
+		fn get_payee(&self, relayer: &Signer, _channel_id: &ChannelId) -> Option<Signer> {
+			self.payees.get(relayer).cloned()
+		}
+
+		fn get_counterparty_payee(
+			&self,
+			_relayer: &Signer,
+			_channel_id: &ChannelId,
+		) -> Option<String> {
+			None
+		}
+
+		fn get_fees_in_escrow(&self, packet_id: &PacketId) -> Vec<PacketFee> {
+			self.escrow
+				.iter()
+				.find(|(id, _)| id == packet_id)
+				.map(|(_, fees)| fees.clone())
+				.unwrap_or_default()
+		}
+
+		fn get_channel_fees_in_escrow(
+			&self,
+			port_id: &PortId,
+			channel_id: &ChannelId,
+		) -> Vec<(PacketId, Vec<PacketFee>)> {
+			self.escrow
+				.iter()
+				.filter(|(id, _)| &id.port_id == port_id && &id.channel_id == channel_id)
+				.cloned()
+				.collect()
+		}
+
+		fn get_forward_relayer_address(&self, _packet_id: &PacketId) -> Option<String> {
+			None
+		}
+	}
+
+	impl FeeKeeper for MockFeeContext {
+		type AccountId = Signer;
+
+		fn set_fee_enabled(&mut self, _port_id: &PortId, _channel_id: &ChannelId) {}
+
+		fn delete_fee_enabled(&mut self, _port_id: &PortId, _channel_id: &ChannelId) {}
+
+		fn set_payee(&mut self, relayer: &Signer, _channel_id: &ChannelId, payee: &Signer) {
+			self.payees.insert(relayer.clone(), payee.clone());
+		}
+
+		fn set_counterparty_payee(
+			&mut self,
+			_relayer: &Signer,
+			_channel_id: &ChannelId,
+			_counterparty_payee: &str,
+		) {
+		}
+
+		fn store_fees_in_escrow(&mut self, packet_id: &PacketId, fees: Vec<PacketFee>) {
+			self.delete_fees_in_escrow(packet_id);
+			self.escrow.push((packet_id.clone(), fees));
+		}
+
+		fn delete_fees_in_escrow(&mut self, packet_id: &PacketId) {
+			self.escrow.retain(|(id, _)| id != packet_id);
+		}
+
+		fn set_forward_relayer_address(&mut self, _packet_id: &PacketId, _address: &str) {}
+
+		fn delete_forward_relayer_address(&mut self, _packet_id: &PacketId) {}
+	}
+
+	impl FeeContext for MockFeeContext {
+		type AccountId = Signer;
+	}
+
+	impl ChannelReader for MockFeeContext {
+		fn channel_end(
+			&self,
+			_port_channel_id: &(PortId, ChannelId),
+		) -> Result<ChannelEnd, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn connection_channels(
+			&self,
+			_cid: &ConnectionId,
+		) -> Result<Vec<(PortId, ChannelId)>, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn get_next_sequence_send(
+			&self,
+			_port_channel_id: &(PortId, ChannelId),
+		) -> Result<Sequence, Ics04Error> {
+			Ok(self.next_sequence_send.into())
+		}
+
+		fn get_next_sequence_recv(
+			&self,
+			_port_channel_id: &(PortId, ChannelId),
+		) -> Result<Sequence, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn get_next_sequence_ack(
+			&self,
+			_port_channel_id: &(PortId, ChannelId),
+		) -> Result<Sequence, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn get_packet_commitment(
+			&self,
+			_key: &(PortId, ChannelId, Sequence),
+		) -> Result<PacketCommitment, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn get_packet_receipt(
+			&self,
+			_key: &(PortId, ChannelId, Sequence),
+		) -> Result<Receipt, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn get_packet_acknowledgement(
+			&self,
+			_key: &(PortId, ChannelId, Sequence),
+		) -> Result<AcknowledgementCommitment, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn packet_commitment_sequences(
+			&self,
+			_port_channel_id: &(PortId, ChannelId),
+		) -> Result<Vec<Sequence>, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn channel_upgrade(
+			&self,
+			_port_channel_id: &(PortId, ChannelId),
+		) -> Result<Upgrade, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn channel_counterparty_upgrade(
+			&self,
+			_port_channel_id: &(PortId, ChannelId),
+		) -> Result<Upgrade, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn channel_upgrade_error(
+			&self,
+			_port_channel_id: &(PortId, ChannelId),
+		) -> Result<ErrorReceipt, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn hash(&self, _value: Vec<u8>) -> Vec<u8> {
+			unimplemented!()
+		}
+
+		fn client_update_time(
+			&self,
+			_client_id: &ClientId,
+			_height: Height,
+		) -> Result<Timestamp, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn client_update_height(
+			&self,
+			_client_id: &ClientId,
+			_height: Height,
+		) -> Result<Height, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn channel_counter(&self) -> Result<u64, Ics04Error> {
+			unimplemented!()
+		}
+
+		fn max_expected_time_per_block(&self) -> Duration {
+			unimplemented!()
+		}
+	}
+
+	#[test]
+	fn fees_are_escrowed_for_the_next_packet() {
+		let mut ctx = MockFeeContext::new();
+		ctx.pay_fee();
+		assert_eq!(ctx.balance(SENDER), 940);
+		assert_eq!(ctx.balance(ESCROW), 60);
+		assert_eq!(ctx.get_fees_in_escrow(&packet_id(1)).len(), 1);
+		assert!(ctx.get_fees_in_escrow(&packet_id(2)).is_empty());
+	}
+
+	#[test]
+	fn acknowledgement_pays_the_forward_and_reverse_relayers() {
+		let mut ctx = MockFeeContext::new();
+		ctx.pay_fee();
+		ctx.set_payee(&signer(REVERSE_RELAYER), &channel().1, &signer(PAYEE));
+		let mut output = ModuleOutputBuilder::new();
+		distribute_fees_on_acknowledgement(
+			&mut ctx,
+			&mut output,
+			&packet_id(1),
+			FORWARD_RELAYER,
+			&signer(REVERSE_RELAYER),
+		)
+		.unwrap();
+		assert_eq!(ctx.balance(FORWARD_RELAYER), 10);
+		assert_eq!(ctx.balance(PAYEE), 20);
+		assert_eq!(ctx.balance(REVERSE_RELAYER), 0);
+		// the timeout fee is refunded
+		assert_eq!(ctx.balance(SENDER), 970);
+		assert_eq!(ctx.balance(ESCROW), 0);
+		assert!(ctx.get_fees_in_escrow(&packet_id(1)).is_empty());
+	}
+
+	#[test]
+	fn receive_fee_is_refunded_without_a_forward_relayer() {
+		let mut ctx = MockFeeContext::new();
+		ctx.pay_fee();
+		let mut output = ModuleOutputBuilder::new();
+		distribute_fees_on_acknowledgement(
+			&mut ctx,
+			&mut output,
+			&packet_id(1),
+			"",
+			&signer(REVERSE_RELAYER),
+		)
+		.unwrap();
+		assert_eq!(ctx.balance(REVERSE_RELAYER), 20);
+		assert_eq!(ctx.balance(SENDER), 980);
+	}
+
+	#[test]
+	fn timeout_pays_the_timeout_relayer() {
+		let mut ctx = MockFeeContext::new();
+		ctx.pay_fee();
+		let mut output = ModuleOutputBuilder::new();
+		distribute_fees_on_timeout(&mut ctx, &mut output, &packet_id(1), &signer(REVERSE_RELAYER))
+			.unwrap();
+		assert_eq!(ctx.balance(REVERSE_RELAYER), 30);
+		assert_eq!(ctx.balance(SENDER), 970);
+		assert_eq!(ctx.balance(ESCROW), 0);
+		assert!(ctx.get_fees_in_escrow(&packet_id(1)).is_empty());
+	}
+
+	#[test]
+	fn unpayable_relayer_fee_is_refunded() {
+		let mut ctx = MockFeeContext::new();
+		ctx.pay_fee();
+		ctx.frozen.insert(signer(REVERSE_RELAYER));
+		let mut output = ModuleOutputBuilder::new();
+		distribute_fees_on_timeout(&mut ctx, &mut output, &packet_id(1), &signer(REVERSE_RELAYER))
+			.unwrap();
+		assert_eq!(ctx.balance(SENDER), 1000);
+		assert!(output.with_result(()).events.is_empty());
+	}
+
+	#[test]
+	fn failed_refund_is_reported() {
+		let mut ctx = MockFeeContext::new();
+		ctx.pay_fee();
+		ctx.frozen.insert(signer(REVERSE_RELAYER));
+		ctx.frozen.insert(signer(SENDER));
+		let mut output = ModuleOutputBuilder::new();
+		distribute_fees_on_timeout(&mut ctx, &mut output, &packet_id(1), &signer(REVERSE_RELAYER))
+			.unwrap();
+		// the coins stay in escrow and every failed refund is reported
+		assert_eq!(ctx.balance(ESCROW), 60);
+		let events = output.with_result(()).events;
+		assert_eq!(events.len(), 3);
+		assert!(events.iter().all(|event| event.kind == "fee_refund_failed"));
+	}
+
+	#[test]
+	fn fees_are_refunded_on_channel_closure() {
+		let mut ctx = MockFeeContext::new();
+		ctx.pay_fee();
+		ctx.pay_fee();
+		assert_eq!(ctx.balance(SENDER), 880);
+		let mut output = ModuleOutputBuilder::new();
+		let (port_id, channel_id) = channel();
+		refund_fees_on_channel_closure(&mut ctx, &mut output, &port_id, &channel_id).unwrap();
+		assert_eq!(ctx.balance(SENDER), 1000);
+		assert_eq!(ctx.balance(ESCROW), 0);
+		assert!(ctx.escrow.is_empty());
+	}
+}
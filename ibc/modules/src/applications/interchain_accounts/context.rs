@@ -0,0 +1,100 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::prelude::*;
+
+use ibc_proto::google::protobuf::Any;
+
+use super::error::Error;
+use crate::core::{
+	ics03_connection::context::ConnectionReader,
+	ics04_channel::context::ChannelReader,
+	ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+};
+
+pub trait Ics27Reader: ChannelReader + ConnectionReader {
+	/// Returns true iff the host accepts channels and executes the transactions they carry.
+	fn is_host_enabled(&self) -> bool;
+
+	/// Returns true iff local accounts can register and use interchain accounts.
+	fn is_controller_enabled(&self) -> bool;
+
+	/// Returns true iff the host executes messages with the given type url.
+	fn is_message_allowed(&self, type_url: &str) -> bool;
+
+	/// Returns the channel the interchain account of a controller port uses on a connection.
+	fn get_active_channel(
+		&self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+	) -> Option<ChannelId>;
+
+	/// Returns the address of the interchain account of a controller port on a connection.
+	fn get_interchain_account(
+		&self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+	) -> Option<String>;
+
+	/// Returns the deterministic address of the interchain account the host creates for a
+	/// controller port on a connection.
+	fn generate_address(
+		&self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+	) -> Result<String, Error>;
+}
+
+pub trait Ics27Keeper {
+	fn set_active_channel(
+		&mut self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+	);
+
+	fn register_interchain_account(
+		&mut self,
+		connection_id: &ConnectionId,
+		port_id: &PortId,
+		address: &str,
+	);
+
+	/// Executes the messages of a transaction with the interchain account, atomically, and
+	/// returns the result written in the acknowledgement.
+	fn execute_tx(&mut self, address: &str, messages: Vec<Any>) -> Result<Vec<u8>, Error>;
+}
+
+pub trait Ics27Context: Ics27Keeper + Ics27Reader {}
+
+/// Interchain account channels have a single connection hop.
+pub(crate) fn connection_hop(connection_hops: &[ConnectionId]) -> Result<&ConnectionId, Error> {
+	match connection_hops {
+		[connection_id] => Ok(connection_id),
+		_ => Err(Error::invalid_connection_hops(connection_hops.len())),
+	}
+}
+
+/// Returns the counterparty of a connection.
+pub(crate) fn counterparty_connection_id(
+	ctx: &impl Ics27Reader,
+	connection_id: &ConnectionId,
+) -> Result<ConnectionId, Error> {
+	let connection_end = ctx.connection_end(connection_id).map_err(Error::ics03_connection)?;
+	connection_end
+		.counterparty()
+		.connection_id()
+		.cloned()
+		.ok_or_else(|| Error::missing_counterparty_connection(connection_id.clone()))
+}
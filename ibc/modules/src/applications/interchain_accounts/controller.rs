@@ -0,0 +1,177 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Callbacks of the ICS27 controller, which registers interchain accounts for local accounts and
+//! sends the transactions they execute with them.
+
+use crate::prelude::*;
+
+use super::{
+	context::{connection_hop, counterparty_connection_id, Ics27Context},
+	controller_port_id,
+	error::Error,
+	host_port_id, is_controller_port,
+	metadata::Metadata,
+	packet::{CosmosTx, InterchainAccountPacketData},
+};
+use crate::core::{
+	ics04_channel::{
+		channel::{ChannelEnd, Counterparty, Order, State},
+		Version,
+	},
+	ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+};
+
+/// Returns the controller port of `owner` and the channel to open on it to register an
+/// interchain account on the host of the connection. A channel closed by a packet timeout is
+/// replaced by a new channel to the same account.
+pub fn register_interchain_account(
+	ctx: &impl Ics27Context,
+	connection_id: &ConnectionId,
+	owner: &str,
+) -> Result<(PortId, ChannelEnd), Error> {
+	if !ctx.is_controller_enabled() {
+		return Err(Error::controller_disabled())
+	}
+	let port_id = controller_port_id(owner)?;
+	let metadata = match ctx.get_active_channel(connection_id, &port_id) {
+		Some(channel_id) => {
+			let channel_end =
+				ctx.channel_end(&(port_id.clone(), channel_id)).map_err(Error::ics04_channel)?;
+			if channel_end.state != State::Closed {
+				return Err(Error::active_channel_exists(port_id, channel_id))
+			}
+			Metadata::from_version(channel_end.version())?
+		},
+		None => {
+			let host_connection_id = counterparty_connection_id(ctx, connection_id)?;
+			Metadata::new(connection_id, &host_connection_id)
+		},
+	};
+	let channel_end = ChannelEnd::new(
+		State::Init,
+		Order::Ordered,
+		Counterparty::new(host_port_id(), None),
+		vec![connection_id.clone()],
+		metadata.to_version(),
+	);
+	Ok((port_id, channel_end))
+}
+
+/// Returns the active channel of the interchain account of `owner` on the connection, and the
+/// packet data executing the transaction with it on the host.
+pub fn send_tx(
+	ctx: &impl Ics27Context,
+	connection_id: &ConnectionId,
+	owner: &str,
+	tx: CosmosTx,
+	memo: String,
+) -> Result<(PortId, ChannelId, InterchainAccountPacketData), Error> {
+	if !ctx.is_controller_enabled() {
+		return Err(Error::controller_disabled())
+	}
+	if tx.messages.is_empty() {
+		return Err(Error::empty_tx())
+	}
+	let port_id = controller_port_id(owner)?;
+	let channel_id = ctx
+		.get_active_channel(connection_id, &port_id)
+		.ok_or_else(|| Error::active_channel_not_found(connection_id.clone(), port_id.clone()))?;
+	let channel_end =
+		ctx.channel_end(&(port_id.clone(), channel_id)).map_err(Error::ics04_channel)?;
+	if !channel_end.is_open() {
+		return Err(Error::channel_not_open(port_id, channel_id))
+	}
+	Ok((port_id, channel_id, InterchainAccountPacketData::new(tx, memo)))
+}
+
+pub fn on_chan_open_init(
+	ctx: &mut impl Ics27Context,
+	order: Order,
+	connection_hops: &[ConnectionId],
+	port_id: &PortId,
+	counterparty: &Counterparty,
+	version: &Version,
+) -> Result<(), Error> {
+	if !ctx.is_controller_enabled() {
+		return Err(Error::controller_disabled())
+	}
+	if order != Order::Ordered {
+		return Err(Error::invalid_ordering(order))
+	}
+	if !is_controller_port(port_id) {
+		return Err(Error::invalid_controller_port(port_id.clone()))
+	}
+	if counterparty.port_id() != &host_port_id() {
+		return Err(Error::invalid_host_port(counterparty.port_id().clone()))
+	}
+	let connection_id = connection_hop(connection_hops)?;
+	let host_connection_id = counterparty_connection_id(ctx, connection_id)?;
+	let metadata = Metadata::from_version(version)?;
+	metadata.validate(connection_id, &host_connection_id)?;
+
+	if let Some(channel_id) = ctx.get_active_channel(connection_id, port_id) {
+		let channel_end =
+			ctx.channel_end(&(port_id.clone(), channel_id)).map_err(Error::ics04_channel)?;
+		if channel_end.state != State::Closed {
+			return Err(Error::active_channel_exists(port_id.clone(), channel_id))
+		}
+		let previous_metadata = Metadata::from_version(channel_end.version())?;
+		if !previous_metadata.is_previous_version_equal(&metadata) {
+			return Err(Error::metadata_mismatch())
+		}
+	}
+	Ok(())
+}
+
+/// Interchain account channels are only opened by the controller.
+pub fn on_chan_open_try() -> Result<Version, Error> {
+	Err(Error::open_try_not_allowed())
+}
+
+/// Records the address of the interchain account set by the host, and makes the channel the
+/// active channel of the account.
+pub fn on_chan_open_ack(
+	ctx: &mut impl Ics27Context,
+	port_id: &PortId,
+	channel_id: &ChannelId,
+	counterparty_version: &Version,
+) -> Result<(), Error> {
+	if !is_controller_port(port_id) {
+		return Err(Error::invalid_controller_port(port_id.clone()))
+	}
+	let metadata = Metadata::from_version(counterparty_version)?;
+	if metadata.address.is_empty() {
+		return Err(Error::empty_address())
+	}
+	let channel_end =
+		ctx.channel_end(&(port_id.clone(), *channel_id)).map_err(Error::ics04_channel)?;
+	let connection_id = connection_hop(channel_end.connection_hops())?;
+	let host_connection_id = counterparty_connection_id(ctx, connection_id)?;
+	metadata.validate(connection_id, &host_connection_id)?;
+
+	ctx.set_active_channel(connection_id, port_id, channel_id);
+	ctx.register_interchain_account(connection_id, port_id, &metadata.address);
+	Ok(())
+}
+
+/// Interchain account channels are only closed by a packet timeout.
+pub fn on_chan_close_init() -> Result<(), Error> {
+	Err(Error::channel_close_not_allowed())
+}
+
+/// The host never sends packets.
+pub fn on_recv_packet() -> Result<(), Error> {
+	Err(Error::receive_not_supported())
+}
@@ -0,0 +1,151 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use flex_error::{define_error, TraceError};
+use tendermint_proto::Error as TendermintProtoError;
+
+use crate::{
+	core::{
+		ics03_connection::error as connection_error,
+		ics04_channel::{channel::Order, error as channel_error},
+		ics24_host::{
+			error::ValidationError,
+			identifier::{ChannelId, ConnectionId, PortId},
+		},
+	},
+	prelude::*,
+};
+
+define_error! {
+	#[derive(Debug, PartialEq, Eq)]
+	Error {
+		Ics04Channel
+			[ channel_error::Error ]
+			|_ | { "Ics04 channel error" },
+
+		Ics03Connection
+			[ connection_error::Error ]
+			|_ | { "Ics03 connection error" },
+
+		MissingCounterpartyConnection
+			{ connection_id: ConnectionId }
+			| e | { format_args!("connection {0} has no counterparty connection", e.connection_id) },
+
+		InvalidPortId
+			{ context: String }
+			[ ValidationError ]
+			| _ | { "invalid port identifier" },
+
+		EmptyOwner
+			| _ | { "the owner of an interchain account can't be empty" },
+
+		InvalidControllerPort
+			{ port_id: PortId }
+			| e | { format_args!("expected a port prefixed with '{0}', got '{1}'", super::CONTROLLER_PORT_PREFIX, e.port_id) },
+
+		InvalidHostPort
+			{ port_id: PortId }
+			| e | { format_args!("expected port '{0}', got '{1}'", super::HOST_PORT_ID_STR, e.port_id) },
+
+		InvalidOrdering
+			{ order: Order }
+			| e | { format_args!("interchain account channels must be ordered, got {0}", e.order) },
+
+		InvalidConnectionHops
+			{ length: usize }
+			| e | { format_args!("expected a single connection hop, got {0}", e.length) },
+
+		InvalidVersion
+			{ version: String }
+			| e | { format_args!("expected version '{0}', got '{1}'", super::VERSION, e.version) },
+
+		InvalidMetadata
+			{ version: String }
+			| e | { format_args!("channel version '{0}' isn't interchain accounts metadata", e.version) },
+
+		UnsupportedEncoding
+			{ encoding: String }
+			| e | { format_args!("unsupported encoding '{0}'", e.encoding) },
+
+		UnsupportedTxType
+			{ tx_type: String }
+			| e | { format_args!("unsupported transaction type '{0}'", e.tx_type) },
+
+		ConnectionMismatch
+			{ expected: ConnectionId, got: String }
+			| e | { format_args!("expected connection {0} in the channel metadata, got '{1}'", e.expected, e.got) },
+
+		MetadataMismatch
+			| _ | { "the metadata of a reopened channel must match the metadata of the previous channel" },
+
+		ActiveChannelExists
+			{ port_id: PortId, channel_id: ChannelId }
+			| e | { format_args!("channel {0}/{1} of the interchain account is still active", e.port_id, e.channel_id) },
+
+		ChannelNotOpen
+			{ port_id: PortId, channel_id: ChannelId }
+			| e | { format_args!("channel {0}/{1} of the interchain account is not open", e.port_id, e.channel_id) },
+
+		ActiveChannelNotFound
+			{ connection_id: ConnectionId, port_id: PortId }
+			| e | { format_args!("no active channel for port {0} on connection {1}", e.port_id, e.connection_id) },
+
+		AccountNotFound
+			{ connection_id: ConnectionId, port_id: PortId }
+			| e | { format_args!("no interchain account registered for port {0} on connection {1}", e.port_id, e.connection_id) },
+
+		EmptyAddress
+			| _ | { "the counterparty didn't set the address of the interchain account" },
+
+		HostDisabled
+			| _ | { "interchain accounts host is disabled" },
+
+		ControllerDisabled
+			| _ | { "interchain accounts controller is disabled" },
+
+		MessageNotAllowed
+			{ type_url: String }
+			| e | { format_args!("message '{0}' is not allowed on the host", e.type_url) },
+
+		EmptyTx
+			| _ | { "interchain account transactions need at least one message" },
+
+		UnsupportedPacketType
+			{ packet_type: i32 }
+			| e | { format_args!("unsupported packet type {0}", e.packet_type) },
+
+		PacketDataDeserialization
+			| _ | { "failed to deserialize packet data" },
+
+		AckDeserialization
+			| _ | { "failed to deserialize acknowledgement" },
+
+		OpenTryNotAllowed
+			| _ | { "interchain account channels can only be opened by the controller" },
+
+		ChannelCloseNotAllowed
+			| _ | { "interchain account channels can only be closed by a packet timeout" },
+
+		ReceiveNotSupported
+			| _ | { "the controller doesn't receive packets" },
+
+		DecodeRawMsg
+			[ TraceError<TendermintProtoError> ]
+			| _ | { "error decoding raw msg" },
+
+		ImplementationSpecific
+			{ reason: String }
+			| e | { format_args!("implementation specific error: {}", e.reason) },
+	}
+}
@@ -0,0 +1,138 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Callbacks of the ICS27 host, which creates the interchain accounts and executes the
+//! transactions sent by their controllers.
+
+use crate::prelude::*;
+
+use super::{
+	context::{connection_hop, counterparty_connection_id, Ics27Context},
+	error::Error,
+	host_port_id, is_controller_port,
+	metadata::Metadata,
+	packet::{Acknowledgement, InterchainAccountPacketData},
+};
+use crate::core::{
+	ics04_channel::{
+		channel::{Counterparty, Order, State},
+		packet::Packet,
+		Version,
+	},
+	ics24_host::identifier::{ChannelId, ConnectionId, PortId},
+};
+
+/// Accepts a channel for the interchain account of the counterparty port, creating the account
+/// on the first channel, and returns the version with the address of the account.
+pub fn on_chan_open_try(
+	ctx: &mut impl Ics27Context,
+	order: Order,
+	connection_hops: &[ConnectionId],
+	port_id: &PortId,
+	counterparty: &Counterparty,
+	counterparty_version: &Version,
+) -> Result<Version, Error> {
+	if !ctx.is_host_enabled() {
+		return Err(Error::host_disabled())
+	}
+	if order != Order::Ordered {
+		return Err(Error::invalid_ordering(order))
+	}
+	if port_id != &host_port_id() {
+		return Err(Error::invalid_host_port(port_id.clone()))
+	}
+	let controller_port_id = counterparty.port_id();
+	if !is_controller_port(controller_port_id) {
+		return Err(Error::invalid_controller_port(controller_port_id.clone()))
+	}
+	let connection_id = connection_hop(connection_hops)?;
+	let controller_connection_id = counterparty_connection_id(ctx, connection_id)?;
+	let mut metadata = Metadata::from_version(counterparty_version)?;
+	metadata.validate(&controller_connection_id, connection_id)?;
+
+	// a closed channel can be replaced by a new one with the same metadata
+	if let Some(channel_id) = ctx.get_active_channel(connection_id, controller_port_id) {
+		let channel_end =
+			ctx.channel_end(&(port_id.clone(), channel_id)).map_err(Error::ics04_channel)?;
+		if channel_end.state != State::Closed {
+			return Err(Error::active_channel_exists(port_id.clone(), channel_id))
+		}
+		let previous_metadata = Metadata::from_version(channel_end.version())?;
+		if !previous_metadata.is_previous_version_equal(&metadata) {
+			return Err(Error::metadata_mismatch())
+		}
+	}
+
+	let address = match ctx.get_interchain_account(connection_id, controller_port_id) {
+		Some(address) => address,
+		None => {
+			let address = ctx.generate_address(connection_id, controller_port_id)?;
+			ctx.register_interchain_account(connection_id, controller_port_id, &address);
+			address
+		},
+	};
+	metadata.address = address;
+	Ok(metadata.to_version())
+}
+
+/// Makes the channel the active channel of the interchain account.
+pub fn on_chan_open_confirm(
+	ctx: &mut impl Ics27Context,
+	port_id: &PortId,
+	channel_id: &ChannelId,
+) -> Result<(), Error> {
+	let channel_end =
+		ctx.channel_end(&(port_id.clone(), *channel_id)).map_err(Error::ics04_channel)?;
+	let connection_id = connection_hop(channel_end.connection_hops())?;
+	ctx.set_active_channel(connection_id, channel_end.counterparty().port_id(), channel_id);
+	Ok(())
+}
+
+/// Interchain account channels are only closed by a packet timeout.
+pub fn on_chan_close_init() -> Result<(), Error> {
+	Err(Error::channel_close_not_allowed())
+}
+
+/// Executes the transaction carried by the packet with the interchain account of its source
+/// port, and returns the acknowledgement to write for it.
+pub fn on_recv_packet(ctx: &mut impl Ics27Context, packet: &Packet) -> Acknowledgement {
+	match execute_tx(ctx, packet) {
+		Ok(result) => Acknowledgement::success(&result),
+		Err(e) => Acknowledgement::from_error(e),
+	}
+}
+
+fn execute_tx(ctx: &mut impl Ics27Context, packet: &Packet) -> Result<Vec<u8>, Error> {
+	if !ctx.is_host_enabled() {
+		return Err(Error::host_disabled())
+	}
+	let packet_data = InterchainAccountPacketData::from_json_bytes(&packet.data)?;
+	let tx = packet_data.tx()?;
+	if tx.messages.is_empty() {
+		return Err(Error::empty_tx())
+	}
+	if let Some(message) = tx.messages.iter().find(|msg| !ctx.is_message_allowed(&msg.type_url)) {
+		return Err(Error::message_not_allowed(message.type_url.clone()))
+	}
+
+	let channel_end = ctx
+		.channel_end(&(packet.destination_port.clone(), packet.destination_channel))
+		.map_err(Error::ics04_channel)?;
+	let connection_id = connection_hop(channel_end.connection_hops())?;
+	let address =
+		ctx.get_interchain_account(connection_id, &packet.source_port).ok_or_else(|| {
+			Error::account_not_found(connection_id.clone(), packet.source_port.clone())
+		})?;
+	ctx.execute_tx(&address, tx.messages)
+}
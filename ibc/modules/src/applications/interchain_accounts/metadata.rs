@@ -0,0 +1,127 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Channel version of interchain account channels, which records the connections and the
+//! address of the account on the host.
+
+use crate::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use super::{error::Error, ENCODING_PROTO3, TX_TYPE_SDK_MULTI_MSG, VERSION};
+use crate::core::{ics04_channel::Version, ics24_host::identifier::ConnectionId};
+
+/// The JSON encoded version of an interchain account channel. The address is left empty by
+/// the controller and set by the host when it accepts the channel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+	pub version: String,
+	pub controller_connection_id: String,
+	pub host_connection_id: String,
+	#[serde(default)]
+	pub address: String,
+	pub encoding: String,
+	pub tx_type: String,
+}
+
+impl Metadata {
+	/// The metadata proposed by the controller for a new channel on the given connections.
+	pub fn new(controller_connection_id: &ConnectionId, host_connection_id: &ConnectionId) -> Self {
+		Self {
+			version: VERSION.to_string(),
+			controller_connection_id: controller_connection_id.to_string(),
+			host_connection_id: host_connection_id.to_string(),
+			address: String::new(),
+			encoding: ENCODING_PROTO3.to_string(),
+			tx_type: TX_TYPE_SDK_MULTI_MSG.to_string(),
+		}
+	}
+
+	pub fn from_version(version: &Version) -> Result<Self, Error> {
+		serde_json::from_str(&version.to_string())
+			.map_err(|_| Error::invalid_metadata(version.to_string()))
+	}
+
+	/// Fails if the metadata uses an unsupported version, encoding or transaction type, or
+	/// isn't for the given connections.
+	pub fn validate(
+		&self,
+		controller_connection_id: &ConnectionId,
+		host_connection_id: &ConnectionId,
+	) -> Result<(), Error> {
+		if self.version != VERSION {
+			return Err(Error::invalid_version(self.version.clone()))
+		}
+		if self.encoding != ENCODING_PROTO3 {
+			return Err(Error::unsupported_encoding(self.encoding.clone()))
+		}
+		if self.tx_type != TX_TYPE_SDK_MULTI_MSG {
+			return Err(Error::unsupported_tx_type(self.tx_type.clone()))
+		}
+		if self.controller_connection_id != controller_connection_id.as_str() {
+			return Err(Error::connection_mismatch(
+				controller_connection_id.clone(),
+				self.controller_connection_id.clone(),
+			))
+		}
+		if self.host_connection_id != host_connection_id.as_str() {
+			return Err(Error::connection_mismatch(
+				host_connection_id.clone(),
+				self.host_connection_id.clone(),
+			))
+		}
+		Ok(())
+	}
+
+	/// Returns true if both metadata describe the same channel, ignoring the address of the
+	/// account.
+	pub fn is_previous_version_equal(&self, other: &Self) -> bool {
+		Self { address: String::new(), ..self.clone() } ==
+			Self { address: String::new(), ..other.clone() }
+	}
+
+	pub fn to_version(&self) -> Version {
+		Version::new(serde_json::to_string(self).expect("metadata is always serializable; qed"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn metadata_round_trips_through_version() {
+		let controller = ConnectionId::new(0);
+		let host = ConnectionId::new(3);
+		let version = Metadata::new(&controller, &host).to_version();
+		assert_eq!(
+			version.to_string(),
+			r#"{"version":"ics27-1","controller_connection_id":"connection-0","host_connection_id":"connection-3","address":"","encoding":"proto3","tx_type":"sdk_multi_msg"}"#
+		);
+
+		let metadata = Metadata::from_version(&version).unwrap();
+		assert!(metadata.validate(&controller, &host).is_ok());
+		assert!(metadata.validate(&host, &controller).is_err());
+	}
+
+	#[test]
+	fn unsupported_metadata_is_rejected() {
+		let controller = ConnectionId::new(0);
+		let host = ConnectionId::new(0);
+		let metadata =
+			Metadata { encoding: "json".to_string(), ..Metadata::new(&controller, &host) };
+		assert!(metadata.validate(&controller, &host).is_err());
+		assert!(Metadata::from_version(&Version::new(VERSION.to_string())).is_err());
+	}
+}
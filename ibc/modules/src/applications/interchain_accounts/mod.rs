@@ -0,0 +1,68 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ICS 27: Interchain Accounts lets a controller chain register accounts on a host chain and
+//! execute transactions with them over an ordered channel. Each account is bound to a
+//! controller port, derived from the address of its owner, and to a connection.
+pub mod context;
+pub mod controller;
+pub mod error;
+pub mod host;
+pub mod metadata;
+pub mod packet;
+
+use crate::{core::ics24_host::identifier::PortId, prelude::*};
+use error::Error;
+
+/// Module identifier for the ICS27 controller.
+pub const CONTROLLER_MODULE_ID_STR: &str = "icacontroller";
+
+/// Module identifier for the ICS27 host.
+pub const HOST_MODULE_ID_STR: &str = "icahost";
+
+/// Port bound by the ICS27 host.
+pub const HOST_PORT_ID_STR: &str = "icahost";
+
+/// Prefix of the ports bound by the ICS27 controller, followed by the address of the owner of
+/// the interchain account.
+pub const CONTROLLER_PORT_PREFIX: &str = "icacontroller-";
+
+/// ICS27 current version.
+pub const VERSION: &str = "ics27-1";
+
+/// The only encoding supported for the transactions sent to the host, a protobuf `CosmosTx`.
+pub const ENCODING_PROTO3: &str = "proto3";
+
+/// The only transaction type supported by the host, a list of messages executed atomically.
+pub const TX_TYPE_SDK_MULTI_MSG: &str = "sdk_multi_msg";
+
+/// Returns the controller port of the interchain accounts owned by `owner`.
+pub fn controller_port_id(owner: &str) -> Result<PortId, Error> {
+	if owner.trim().is_empty() {
+		return Err(Error::empty_owner())
+	}
+	format!("{}{}", CONTROLLER_PORT_PREFIX, owner)
+		.parse()
+		.map_err(|e| Error::invalid_port_id(owner.to_string(), e))
+}
+
+/// Returns true if the port was bound by the ICS27 controller.
+pub fn is_controller_port(port_id: &PortId) -> bool {
+	port_id.as_str().starts_with(CONTROLLER_PORT_PREFIX)
+}
+
+/// Returns the port of the ICS27 host.
+pub fn host_port_id() -> PortId {
+	HOST_PORT_ID_STR.parse().expect("host port id is valid; qed")
+}
@@ -0,0 +1,186 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Packets sent by the controller to execute transactions with interchain accounts, and the
+//! acknowledgements written by the host.
+
+use crate::prelude::*;
+
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::applications::interchain_accounts::v1::{
+		CosmosTx as RawCosmosTx, InterchainAccountPacketData as RawInterchainAccountPacketData,
+		Type,
+	},
+};
+use serde::{Deserialize, Serialize};
+use subtle_encoding::base64;
+use tendermint_proto::Protobuf;
+
+use super::error::Error;
+
+/// A transaction to execute with an interchain account, the only packet type of ICS27.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterchainAccountPacketData {
+	/// Protobuf encoded `CosmosTx`
+	pub data: Vec<u8>,
+	pub memo: String,
+}
+
+/// The proto JSON encoding of the packet data, in which bytes are base64 encoded.
+#[derive(Serialize, Deserialize)]
+struct JsonInterchainAccountPacketData {
+	#[serde(rename = "type")]
+	packet_type: String,
+	data: String,
+	#[serde(default)]
+	memo: String,
+}
+
+impl InterchainAccountPacketData {
+	pub fn new(tx: CosmosTx, memo: String) -> Self {
+		Self { data: tx.encode_vec(), memo }
+	}
+
+	/// Decodes the transaction carried by the packet.
+	pub fn tx(&self) -> Result<CosmosTx, Error> {
+		CosmosTx::decode_vec(&self.data).map_err(Error::decode_raw_msg)
+	}
+
+	/// Encodes the packet data the same way as ibc-go, so that it can be executed by any host.
+	pub fn to_json_bytes(&self) -> Vec<u8> {
+		let json = JsonInterchainAccountPacketData {
+			packet_type: Type::ExecuteTx.as_str_name().to_string(),
+			data: String::from_utf8(base64::encode(&self.data))
+				.expect("base64 encoding is valid UTF8; qed"),
+			memo: self.memo.clone(),
+		};
+		serde_json::to_vec(&json).expect("packet data is always serializable; qed")
+	}
+
+	pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, Error> {
+		let json: JsonInterchainAccountPacketData =
+			serde_json::from_slice(bytes).map_err(|_| Error::packet_data_deserialization())?;
+		if json.packet_type != Type::ExecuteTx.as_str_name() {
+			return Err(Error::packet_data_deserialization())
+		}
+		Ok(Self {
+			data: base64::decode(json.data).map_err(|_| Error::packet_data_deserialization())?,
+			memo: json.memo,
+		})
+	}
+}
+
+impl TryFrom<RawInterchainAccountPacketData> for InterchainAccountPacketData {
+	type Error = Error;
+
+	fn try_from(raw: RawInterchainAccountPacketData) -> Result<Self, Self::Error> {
+		if raw.r#type != Type::ExecuteTx as i32 {
+			return Err(Error::unsupported_packet_type(raw.r#type))
+		}
+		Ok(Self { data: raw.data, memo: raw.memo })
+	}
+}
+
+impl From<InterchainAccountPacketData> for RawInterchainAccountPacketData {
+	fn from(packet_data: InterchainAccountPacketData) -> Self {
+		Self { r#type: Type::ExecuteTx as i32, data: packet_data.data, memo: packet_data.memo }
+	}
+}
+
+/// The messages of a transaction, executed atomically by the host.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CosmosTx {
+	pub messages: Vec<Any>,
+}
+
+impl From<RawCosmosTx> for CosmosTx {
+	fn from(raw: RawCosmosTx) -> Self {
+		Self { messages: raw.messages }
+	}
+}
+
+impl From<CosmosTx> for RawCosmosTx {
+	fn from(tx: CosmosTx) -> Self {
+		Self { messages: tx.messages }
+	}
+}
+
+impl Protobuf<RawCosmosTx> for CosmosTx {}
+
+/// The JSON encoded acknowledgement written by the host, e.g. `{"result":"AQ=="}` or
+/// `{"error":"..."}`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Acknowledgement {
+	/// Base64 encoded result of the transaction
+	Result(String),
+	Error(String),
+}
+
+impl Acknowledgement {
+	pub fn success(result: &[u8]) -> Self {
+		Self::Result(
+			String::from_utf8(base64::encode(result)).expect("base64 encoding is valid UTF8; qed"),
+		)
+	}
+
+	pub fn from_error(err: Error) -> Self {
+		Self::Error(err.to_string())
+	}
+
+	pub fn is_successful(&self) -> bool {
+		matches!(self, Self::Result(_))
+	}
+
+	pub fn to_json_bytes(&self) -> Vec<u8> {
+		serde_json::to_vec(self).expect("acknowledgement is always serializable; qed")
+	}
+
+	pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, Error> {
+		serde_json::from_slice(bytes).map_err(|_| Error::ack_deserialization())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn packet_data_json_round_trip() {
+		let tx = CosmosTx {
+			messages: vec![Any {
+				type_url: "/pallet_balances.transfer".to_string(),
+				value: vec![1],
+			}],
+		};
+		let packet_data = InterchainAccountPacketData::new(tx.clone(), "memo".to_string());
+		let bytes = packet_data.to_json_bytes();
+		assert!(bytes.starts_with(br#"{"type":"TYPE_EXECUTE_TX","data":""#));
+
+		let decoded = InterchainAccountPacketData::from_json_bytes(&bytes).unwrap();
+		assert_eq!(decoded, packet_data);
+		assert_eq!(decoded.tx().unwrap(), tx);
+	}
+
+	#[test]
+	fn acknowledgement_json_round_trip() {
+		let ack = Acknowledgement::success(&[1]);
+		assert_eq!(ack.to_json_bytes(), br#"{"result":"AQ=="}"#.to_vec());
+		assert_eq!(Acknowledgement::from_json_bytes(&ack.to_json_bytes()).unwrap(), ack);
+
+		let ack = Acknowledgement::from_json_bytes(br#"{"error":"failed"}"#).unwrap();
+		assert!(!ack.is_successful());
+	}
+}
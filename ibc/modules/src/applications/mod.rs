@@ -14,4 +14,6 @@
 
 //! Various packet encoding semantics which underpin the various types of transactions.
 
+pub mod fee;
+pub mod interchain_accounts;
 pub mod transfer;
@@ -223,6 +223,46 @@ pub fn on_chan_open_confirm(
 	Ok(())
 }
 
+/// Transfer channels can be upgraded as long as they stay unordered and keep the ics20 version,
+/// e.g. to wrap them in a middleware.
+#[allow(clippy::too_many_arguments)]
+pub fn on_chan_upgrade_init(
+	ctx: &mut impl Ics20Context,
+	_output: &mut ModuleOutputBuilder,
+	port_id: &PortId,
+	channel_id: &ChannelId,
+	proposed_order: Order,
+	_proposed_connection_hops: &[ConnectionId],
+	proposed_version: &Version,
+) -> Result<Version, Ics20Error> {
+	validate_transfer_channel_params(ctx, proposed_order, port_id, channel_id, proposed_version)?;
+	Ok(Version::ics20())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn on_chan_upgrade_try(
+	ctx: &mut impl Ics20Context,
+	_output: &mut ModuleOutputBuilder,
+	port_id: &PortId,
+	channel_id: &ChannelId,
+	proposed_order: Order,
+	_proposed_connection_hops: &[ConnectionId],
+	proposed_version: &Version,
+) -> Result<Version, Ics20Error> {
+	validate_transfer_channel_params(ctx, proposed_order, port_id, channel_id, proposed_version)?;
+	Ok(Version::ics20())
+}
+
+pub fn on_chan_upgrade_ack(
+	_ctx: &mut impl Ics20Context,
+	_output: &mut ModuleOutputBuilder,
+	_port_id: &PortId,
+	_channel_id: &ChannelId,
+	counterparty_version: &Version,
+) -> Result<(), Ics20Error> {
+	validate_counterparty_version(counterparty_version)
+}
+
 pub fn on_chan_close_init(
 	_ctx: &mut impl Ics20Context,
 	_output: &mut ModuleOutputBuilder,
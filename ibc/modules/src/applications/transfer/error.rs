@@ -155,6 +155,10 @@ define_error! {
 			{ msg_type: String }
 			| e | { format_args!("unknown msg type: {0}", e.msg_type) },
 
+		InvalidForwardMetadata
+			{ reason: String }
+			| e | { format_args!("invalid forward metadata in packet memo: {}", e.reason) },
+
 		ImplementationSpecific
 			{ reason: String }
 			| e | { format_args!("implementation specific error: {}", e.reason) },
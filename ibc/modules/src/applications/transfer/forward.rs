@@ -0,0 +1,139 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Forward instructions of the packet-forward middleware, carried in the memo of ICS20 packets
+//! sent to an intermediate chain which passes the tokens on to their destination.
+
+use crate::prelude::*;
+
+use core::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::error::Error;
+use crate::core::ics24_host::identifier::{ChannelId, PortId};
+
+/// Key of the forward instruction in a packet memo.
+pub const FORWARD_MEMO_KEY: &str = "forward";
+
+/// Timeout of forwarded packets, relative to the counterparty's latest timestamp, when the memo
+/// doesn't set one.
+pub const DEFAULT_FORWARD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Instruction to send the tokens of a received packet on to another chain, given in the memo as
+/// `{"forward":{"receiver":"...","port":"transfer","channel":"channel-1"}}`.
+///
+/// `next` becomes the memo of the forwarded packet, so that it can be forwarded again by the next
+/// chain.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ForwardMetadata {
+	/// the recipient address on the next chain
+	pub receiver: String,
+	/// the port on which the tokens are forwarded
+	pub port: String,
+	/// the channel by which the tokens are forwarded
+	pub channel: String,
+	/// Timeout of the forwarded packet in nanoseconds.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub timeout: Option<u64>,
+	/// Memo of the forwarded packet, either a string or a JSON object.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub next: Option<Value>,
+}
+
+impl ForwardMetadata {
+	/// Parses the forward instruction of a packet memo. Memos that aren't JSON objects with a
+	/// `forward` key are meant for someone else and yield `None`.
+	pub fn from_memo(memo: &str) -> Result<Option<Self>, Error> {
+		let forward = match serde_json::from_str(memo) {
+			Ok(Value::Object(mut memo)) => match memo.remove(FORWARD_MEMO_KEY) {
+				Some(forward) => forward,
+				None => return Ok(None),
+			},
+			_ => return Ok(None),
+		};
+		let metadata: Self = serde_json::from_value(forward)
+			.map_err(|e| Error::invalid_forward_metadata(e.to_string()))?;
+		if metadata.receiver.is_empty() {
+			return Err(Error::invalid_forward_metadata("empty receiver".to_string()))
+		}
+		Ok(Some(metadata))
+	}
+
+	pub fn port_id(&self) -> Result<PortId, Error> {
+		self.port.parse().map_err(|e| Error::invalid_port_id(self.port.clone(), e))
+	}
+
+	pub fn channel_id(&self) -> Result<ChannelId, Error> {
+		self.channel
+			.parse()
+			.map_err(|e| Error::invalid_channel_id(self.channel.clone(), e))
+	}
+
+	pub fn timeout(&self) -> Duration {
+		self.timeout.map(Duration::from_nanos).unwrap_or(DEFAULT_FORWARD_TIMEOUT)
+	}
+
+	/// The memo of the forwarded packet.
+	pub fn next_memo(&self) -> String {
+		match &self.next {
+			None => String::new(),
+			Some(Value::String(memo)) => memo.clone(),
+			Some(next) => next.to_string(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn forward_memo_is_parsed() {
+		let memo = r#"{"forward":{"receiver":"0xdead","port":"transfer","channel":"channel-3","timeout":1000000000,"next":{"forward":{"receiver":"cosmos1","port":"transfer","channel":"channel-0"}}}}"#;
+		let metadata = ForwardMetadata::from_memo(memo).unwrap().unwrap();
+		assert_eq!(metadata.receiver, "0xdead");
+		assert_eq!(metadata.port_id().unwrap(), PortId::transfer());
+		assert_eq!(metadata.channel_id().unwrap(), ChannelId::new(3));
+		assert_eq!(metadata.timeout(), Duration::from_secs(1));
+
+		let next = ForwardMetadata::from_memo(&metadata.next_memo()).unwrap().unwrap();
+		assert_eq!(next.receiver, "cosmos1");
+		assert_eq!(next.timeout(), DEFAULT_FORWARD_TIMEOUT);
+		assert_eq!(next.next_memo(), "");
+	}
+
+	#[test]
+	fn other_memos_are_ignored() {
+		assert_eq!(ForwardMetadata::from_memo("").unwrap(), None);
+		assert_eq!(ForwardMetadata::from_memo("hello").unwrap(), None);
+		assert_eq!(ForwardMetadata::from_memo(r#"{"wasm":{}}"#).unwrap(), None);
+	}
+
+	#[test]
+	fn invalid_forward_metadata_is_rejected() {
+		assert!(ForwardMetadata::from_memo(r#"{"forward":{"port":"transfer"}}"#).is_err());
+		assert!(ForwardMetadata::from_memo(
+			r#"{"forward":{"receiver":"","port":"transfer","channel":"channel-0"}}"#
+		)
+		.is_err());
+		let metadata = ForwardMetadata::from_memo(
+			r#"{"forward":{"receiver":"0xdead","port":"transfer","channel":"3"}}"#,
+		)
+		.unwrap()
+		.unwrap();
+		assert!(metadata.channel_id().is_err());
+	}
+}
@@ -12,7 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloc::string::ToString;
+use alloc::string::{String, ToString};
 use core::{convert::TryFrom, str::FromStr};
 
 use ibc_proto::ibc::applications::transfer::v2::FungibleTokenPacketData as RawPacketData;
@@ -26,6 +26,9 @@ pub struct PacketData {
 	pub token: PrefixedCoin,
 	pub sender: Signer,
 	pub receiver: Signer,
+	/// Optional memo, omitted from the json encoding when empty.
+	#[serde(default, skip_serializing_if = "String::is_empty")]
+	pub memo: String,
 }
 
 impl TryFrom<RawPacketData> for PacketData {
@@ -39,6 +42,7 @@ impl TryFrom<RawPacketData> for PacketData {
 			token: PrefixedCoin { denom, amount },
 			sender: raw_pkt_data.sender.parse().map_err(Error::signer)?,
 			receiver: raw_pkt_data.receiver.parse().map_err(Error::signer)?,
+			memo: raw_pkt_data.memo,
 		})
 	}
 }
@@ -50,6 +54,7 @@ impl From<PacketData> for RawPacketData {
 			amount: pkt_data.token.amount.to_string(),
 			sender: pkt_data.sender.to_string(),
 			receiver: pkt_data.receiver.to_string(),
+			memo: pkt_data.memo,
 		}
 	}
 }
@@ -22,6 +22,7 @@ use crate::{
 			channel::ChannelEnd,
 			commitment::{AcknowledgementCommitment, PacketCommitment},
 			packet::Sequence,
+			upgrade::{ErrorReceipt, Upgrade},
 		},
 		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
 		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
@@ -97,6 +98,26 @@ pub trait ClientDef: Clone {
 		proof_upgrade_consensus_state: Vec<u8>,
 	) -> Result<(Self::ClientState, ConsensusUpdateResult<Ctx>), Error>;
 
+	/// Whether clients of `client_type` opt in to [`Self::update_state_on_proofs_verified`]. The
+	/// hook costs a client state read per message, so it's only called for the client types this
+	/// returns `true` for.
+	fn updates_state_on_proofs_verified(_client_type: &str) -> bool {
+		false
+	}
+
+	/// Called once a connection, channel or packet message whose proofs were verified by this
+	/// client was processed, if the client opted in through
+	/// [`Self::updates_state_on_proofs_verified`]. Clients that must not accept the same proof
+	/// twice return their new state, which is stored like the result of a client update.
+	fn update_state_on_proofs_verified<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: ClientId,
+		_client_state: Self::ClientState,
+	) -> Result<Option<(Self::ClientState, ConsensusUpdateResult<Ctx>)>, Error> {
+		Ok(None)
+	}
+
 	/// Verification functions as specified in:
 	/// <https://github.com/cosmos/ibc/tree/master/spec/core/ics-002-client-semantics>
 	///
@@ -149,6 +170,39 @@ pub trait ClientDef: Clone {
 		expected_channel_end: &ChannelEnd,
 	) -> Result<(), Error>;
 
+	/// Verify a `proof` that the upgrade in progress for a channel matches the input `upgrade`.
+	#[allow(clippy::too_many_arguments)]
+	fn verify_channel_upgrade<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_upgrade: &Upgrade,
+	) -> Result<(), Error>;
+
+	/// Verify a `proof` that the counterparty aborted a channel upgrade with the input
+	/// `error_receipt`.
+	#[allow(clippy::too_many_arguments)]
+	fn verify_channel_upgrade_error<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_error_receipt: &ErrorReceipt,
+	) -> Result<(), Error>;
+
 	/// Verify the client state for this chain that it is stored on the counterparty chain.
 	#[allow(clippy::too_many_arguments)]
 	fn verify_client_full_state<Ctx: ReaderContext>(
@@ -58,6 +58,7 @@ impl TryFrom<RawIdentifiedChannel> for IdentifiedChannelEnd {
 			counterparty: value.counterparty,
 			connection_hops: value.connection_hops,
 			version: value.version,
+			upgrade_sequence: value.upgrade_sequence,
 		};
 
 		Ok(IdentifiedChannelEnd {
@@ -83,6 +84,7 @@ impl From<IdentifiedChannelEnd> for RawIdentifiedChannel {
 			version: value.channel_end.version.to_string(),
 			port_id: value.port_id.to_string(),
 			channel_id: value.channel_id.to_string(),
+			upgrade_sequence: value.channel_end.upgrade_sequence,
 		}
 	}
 }
@@ -94,6 +96,9 @@ pub struct ChannelEnd {
 	pub remote: Counterparty,
 	pub connection_hops: Vec<ConnectionId>,
 	pub version: Version,
+	/// Latest upgrade attempt performed by this channel, 0 if it has never been upgraded.
+	#[serde(default)]
+	pub upgrade_sequence: u64,
 }
 
 impl Default for ChannelEnd {
@@ -104,6 +109,7 @@ impl Default for ChannelEnd {
 			remote: Counterparty::default(),
 			connection_hops: Vec::new(),
 			version: Version::default(),
+			upgrade_sequence: 0,
 		}
 	}
 }
@@ -131,7 +137,8 @@ impl TryFrom<RawChannel> for ChannelEnd {
 
 		let version = value.version.into();
 
-		Ok(ChannelEnd::new(chan_state, chan_ordering, remote, connection_hops, version))
+		Ok(ChannelEnd::new(chan_state, chan_ordering, remote, connection_hops, version)
+			.with_upgrade_sequence(value.upgrade_sequence))
 	}
 }
 
@@ -143,6 +150,7 @@ impl From<ChannelEnd> for RawChannel {
 			counterparty: Some(value.counterparty().clone().into()),
 			connection_hops: value.connection_hops.iter().map(|v| v.as_str().to_string()).collect(),
 			version: value.version.to_string(),
+			upgrade_sequence: value.upgrade_sequence,
 		}
 	}
 }
@@ -156,7 +164,12 @@ impl ChannelEnd {
 		connection_hops: Vec<ConnectionId>,
 		version: Version,
 	) -> Self {
-		Self { state, ordering, remote, connection_hops, version }
+		Self { state, ordering, remote, connection_hops, version, upgrade_sequence: 0 }
+	}
+
+	/// Sets the upgrade sequence of this ChannelEnd.
+	pub fn with_upgrade_sequence(self, upgrade_sequence: u64) -> Self {
+		Self { upgrade_sequence, ..self }
 	}
 
 	/// Updates the ChannelEnd to assume a new State 's'.
@@ -172,6 +185,18 @@ impl ChannelEnd {
 		self.remote.channel_id = Some(c);
 	}
 
+	pub fn set_ordering(&mut self, o: Order) {
+		self.ordering = o;
+	}
+
+	pub fn set_connection_hops(&mut self, hops: Vec<ConnectionId>) {
+		self.connection_hops = hops;
+	}
+
+	pub fn set_upgrade_sequence(&mut self, sequence: u64) {
+		self.upgrade_sequence = sequence;
+	}
+
 	/// Returns `true` if this `ChannelEnd` is in state [`State::Open`].
 	pub fn is_open(&self) -> bool {
 		self.state_matches(&State::Open)
@@ -197,6 +222,10 @@ impl ChannelEnd {
 		&self.version
 	}
 
+	pub fn upgrade_sequence(&self) -> u64 {
+		self.upgrade_sequence
+	}
+
 	pub fn validate_basic(&self) -> Result<(), Error> {
 		if self.connection_hops.len() != 1 {
 			return Err(Error::invalid_connection_hops_length(1, self.connection_hops.len()))
@@ -331,6 +360,8 @@ pub enum State {
 	TryOpen = 2,
 	Open = 3,
 	Closed = 4,
+	Flushing = 5,
+	FlushComplete = 6,
 }
 
 impl State {
@@ -341,6 +372,8 @@ impl State {
 			Self::TryOpen => "TRYOPEN",
 			Self::Open => "OPEN",
 			Self::Closed => "CLOSED",
+			Self::Flushing => "FLUSHING",
+			Self::FlushComplete => "FLUSHCOMPLETE",
 		}
 	}
 
@@ -351,6 +384,8 @@ impl State {
 			2 => Ok(Self::TryOpen),
 			3 => Ok(Self::Open),
 			4 => Ok(Self::Closed),
+			5 => Ok(Self::Flushing),
+			6 => Ok(Self::FlushComplete),
 			_ => Err(Error::unknown_state(s)),
 		}
 	}
@@ -360,6 +395,12 @@ impl State {
 		self == State::Open
 	}
 
+	/// Returns whether or not the channel is in the middle of an upgrade,
+	/// i.e. is either `Flushing` or `FlushComplete`.
+	pub fn is_upgrading(self) -> bool {
+		matches!(self, State::Flushing | State::FlushComplete)
+	}
+
 	/// Returns whether or not the channel with this state
 	/// has progressed less or the same than the argument.
 	///
@@ -421,6 +462,7 @@ pub mod test_util {
 			counterparty: Some(get_dummy_raw_counterparty()),
 			connection_hops: vec![ConnectionId::default().to_string()],
 			version: "ics20".to_string(), // The version is not validated.
+			upgrade_sequence: 0,
 		}
 	}
 }
@@ -23,9 +23,12 @@ use crate::{
 			channel::ChannelEnd,
 			commitment::{AcknowledgementCommitment, PacketCommitment},
 			error::Error,
-			handler::{recv_packet::RecvPacketResult, ChannelIdState, ChannelResult},
+			handler::{
+				recv_packet::RecvPacketResult, ChannelIdState, ChannelResult, UpgradeResult,
+			},
 			msgs::acknowledgement::Acknowledgement,
 			packet::Receipt,
+			upgrade::{ErrorReceipt, Upgrade},
 		},
 		ics24_host::identifier::{ChannelId, ClientId, ConnectionId, PortId},
 	},
@@ -70,6 +73,28 @@ pub trait ChannelReader {
 		key: &(PortId, ChannelId, Sequence),
 	) -> Result<AcknowledgementCommitment, Error>;
 
+	/// Returns the sequences of all packets sent on the channel for which a packet commitment is
+	/// still stored, i.e. the packets that are still in flight.
+	fn packet_commitment_sequences(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Vec<Sequence>, Error>;
+
+	/// Returns the upgrade proposed for the given channel by this chain.
+	fn channel_upgrade(&self, port_channel_id: &(PortId, ChannelId)) -> Result<Upgrade, Error>;
+
+	/// Returns the upgrade proposed for the given channel by the counterparty chain.
+	fn channel_counterparty_upgrade(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Upgrade, Error>;
+
+	/// Returns the error receipt of the last aborted upgrade of the given channel.
+	fn channel_upgrade_error(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<ErrorReceipt, Error>;
+
 	fn packet_commitment(
 		&self,
 		packet_data: Vec<u8>,
@@ -137,7 +162,37 @@ pub trait ChannelKeeper {
 			// Initialize send, recv, and ack sequence numbers.
 			self.store_next_sequence_send((result.port_id.clone(), result.channel_id), 1.into())?;
 			self.store_next_sequence_recv((result.port_id.clone(), result.channel_id), 1.into())?;
-			self.store_next_sequence_ack((result.port_id, result.channel_id), 1.into())?;
+			self.store_next_sequence_ack((result.port_id.clone(), result.channel_id), 1.into())?;
+		}
+
+		let port_channel_id = (result.port_id, result.channel_id);
+		match result.upgrade {
+			Some(UpgradeResult::Progress { upgrade, counterparty_upgrade }) => {
+				self.store_channel_upgrade(port_channel_id.clone(), upgrade)?;
+				if let Some(counterparty_upgrade) = counterparty_upgrade {
+					self.store_channel_counterparty_upgrade(port_channel_id, counterparty_upgrade)?;
+				}
+			},
+			Some(UpgradeResult::Complete { next_sequence_recv, next_sequence_ack }) => {
+				self.delete_channel_upgrade(port_channel_id.clone())?;
+				self.delete_channel_counterparty_upgrade(port_channel_id.clone())?;
+				if let Some(next_sequence_recv) = next_sequence_recv {
+					self.store_next_sequence_recv(port_channel_id.clone(), next_sequence_recv)?;
+				}
+				if let Some(next_sequence_ack) = next_sequence_ack {
+					self.store_next_sequence_ack(port_channel_id, next_sequence_ack)?;
+				}
+			},
+			Some(UpgradeResult::Cancelled) => {
+				self.delete_channel_upgrade(port_channel_id.clone())?;
+				self.delete_channel_counterparty_upgrade(port_channel_id)?;
+			},
+			Some(UpgradeResult::Aborted(error_receipt)) => {
+				self.delete_channel_upgrade(port_channel_id.clone())?;
+				self.delete_channel_counterparty_upgrade(port_channel_id.clone())?;
+				self.store_channel_upgrade_error(port_channel_id, error_receipt)?;
+			},
+			None => {},
 		}
 
 		Ok(())
@@ -182,6 +237,11 @@ pub trait ChannelKeeper {
 					self.store_next_sequence_ack((res.port_id.clone(), res.channel_id), s)?;
 				}
 
+				if let Some(c) = res.channel {
+					// Upgrading channel done flushing
+					self.store_channel((res.port_id.clone(), res.channel_id), &c)?;
+				}
+
 				// Delete packet commitment since packet has been aknowledged
 				self.delete_packet_commitment((res.port_id.clone(), res.channel_id, res.seq))?;
 			},
@@ -267,6 +327,32 @@ pub trait ChannelKeeper {
 		seq: Sequence,
 	) -> Result<(), Error>;
 
+	fn store_channel_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		upgrade: Upgrade,
+	) -> Result<(), Error>;
+
+	fn delete_channel_upgrade(&mut self, port_channel_id: (PortId, ChannelId))
+		-> Result<(), Error>;
+
+	fn store_channel_counterparty_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		upgrade: Upgrade,
+	) -> Result<(), Error>;
+
+	fn delete_channel_counterparty_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+	) -> Result<(), Error>;
+
+	fn store_channel_upgrade_error(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		error_receipt: ErrorReceipt,
+	) -> Result<(), Error>;
+
 	/// Called upon channel identifier creation (Init or Try message processing).
 	/// Increases the counter which keeps track of how many channels have been created.
 	/// Should never fail.
@@ -374,6 +374,56 @@ define_error! {
 		RouteNotFound
 			| _ | { "route not found" },
 
+		MissingUpgradeFields
+			| _ | { "missing channel upgrade fields" },
+
+		MissingUpgrade
+			| _ | { "missing counterparty channel upgrade" },
+
+		MissingErrorReceipt
+			| _ | { "missing channel upgrade error receipt" },
+
+		MissingUpgradeProof
+			| _ | { "missing channel upgrade proof" },
+
+		InvalidUpgradeTimeout
+			| _ | { "channel upgrade timeout height and timestamp cannot both be 0" },
+
+		InvalidUpgrade
+			{ reason: String }
+			| e | { format_args!("invalid channel upgrade: {}", e.reason) },
+
+		UpgradeNotFound
+			{ port_id: PortId, channel_id: ChannelId }
+			| e | {
+				format_args!(
+					"no upgrade in progress for channel end ({0}, {1})",
+					e.port_id, e.channel_id)
+			},
+
+		InvalidUpgradeSequence
+			{ expected: u64, actual: u64 }
+			| e | {
+				format_args!(
+					"invalid channel upgrade sequence: expected {0}; actual {1}",
+					e.expected, e.actual)
+			},
+
+		PacketSentAfterFlush
+			{ sequence: Sequence, next_sequence_send: Sequence }
+			| e | {
+				format_args!(
+					"packet sequence {0} was sent after the counterparty started flushing at sequence {1}",
+					e.sequence, e.next_sequence_send)
+			},
+
+		UpgradeTimeoutNotReached
+			| _ | { "channel upgrade timeout has not been reached on the counterparty" },
+
+		UpgradeVerificationFailed
+			[ client_error::Error ]
+			| _ | { "Error verifying channel upgrade" },
+
 		ImplementationSpecific
 			{ reason: String }
 			| e | { format_args!("implementation specific error: {}", e.reason) },
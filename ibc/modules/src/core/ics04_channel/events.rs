@@ -20,7 +20,7 @@ use tendermint::abci::{Event as AbciEvent, EventAttribute};
 use crate::{
 	core::{
 		ics02_client::height::Height,
-		ics04_channel::{error::Error, packet::Packet},
+		ics04_channel::{channel::ChannelEnd, error::Error, packet::Packet},
 		ics24_host::identifier::{ChannelId, ConnectionId, PortId},
 	},
 	events::{
@@ -37,6 +37,7 @@ const CHANNEL_ID_ATTRIBUTE_KEY: &str = "channel_id";
 const PORT_ID_ATTRIBUTE_KEY: &str = "port_id";
 const COUNTERPARTY_CHANNEL_ID_ATTRIBUTE_KEY: &str = "counterparty_channel_id";
 const COUNTERPARTY_PORT_ID_ATTRIBUTE_KEY: &str = "counterparty_port_id";
+const UPGRADE_SEQUENCE_ATTRIBUTE_KEY: &str = "upgrade_sequence";
 
 /// Packet event attribute keys
 const PKT_SEQ_ATTRIBUTE_KEY: &str = "packet_sequence";
@@ -81,6 +82,19 @@ pub fn try_from_tx(event: &tendermint::abci::Event) -> Option<IbcEvent> {
 			.map(|res| res.ok().map(IbcEvent::CloseConfirmChannel))
 			.ok()
 			.flatten(),
+		Ok(
+			kind @ (IbcEventType::UpgradeInitChannel |
+			IbcEventType::UpgradeTryChannel |
+			IbcEventType::UpgradeAckChannel |
+			IbcEventType::UpgradeConfirmChannel |
+			IbcEventType::UpgradeOpenChannel |
+			IbcEventType::UpgradeTimeoutChannel |
+			IbcEventType::UpgradeCancelChannel |
+			IbcEventType::UpgradeErrorChannel |
+			IbcEventType::FlushCompleteChannel),
+		) => extract_upgrade_attributes_from_tx(event)
+			.ok()
+			.and_then(|attrs| attrs.into_ibc_event(kind)),
 		Ok(IbcEventType::SendPacket) => {
 			extract_packet_and_write_ack_from_tx(event)
 				.map(|(packet, write_ack)| {
@@ -151,6 +165,36 @@ fn extract_attributes_from_tx(event: &tendermint::abci::Event) -> Result<Attribu
 	Ok(attr)
 }
 
+fn extract_upgrade_attributes_from_tx(
+	event: &tendermint::abci::Event,
+) -> Result<UpgradeAttributes, Error> {
+	let mut attr = UpgradeAttributes::default();
+
+	for tag in &event.attributes {
+		let key = tag.key.as_str();
+		let value = tag.value.as_str();
+		match key {
+			PORT_ID_ATTRIBUTE_KEY => attr.port_id = value.parse().map_err(Error::identifier)?,
+			CHANNEL_ID_ATTRIBUTE_KEY =>
+				attr.channel_id = value.parse().map_err(Error::identifier)?,
+			COUNTERPARTY_PORT_ID_ATTRIBUTE_KEY => {
+				attr.counterparty_port_id = value.parse().map_err(Error::identifier)?;
+			},
+			COUNTERPARTY_CHANNEL_ID_ATTRIBUTE_KEY => {
+				attr.counterparty_channel_id = value.parse().ok();
+			},
+			UPGRADE_SEQUENCE_ATTRIBUTE_KEY => {
+				attr.upgrade_sequence = value.parse().map_err(|_| {
+					Error::implementation_specific("parse upgrade_sequence error".to_string())
+				})?;
+			},
+			_ => {},
+		}
+	}
+
+	Ok(attr)
+}
+
 fn extract_packet_and_write_ack_from_tx(
 	event: &tendermint::abci::Event,
 ) -> Result<(Packet, Vec<u8>), Error> {
@@ -750,6 +794,108 @@ macro_rules! impl_try_from_raw_obj_for_event {
 
 impl_try_from_raw_obj_for_event!(OpenInit, OpenTry, OpenAck, OpenConfirm, CloseInit, CloseConfirm);
 
+/// Attributes of the events emitted by every step of the channel upgrade handshake, and when an
+/// upgrading channel end has flushed all of its in-flight packets.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct UpgradeAttributes {
+	pub height: Height,
+	pub port_id: PortId,
+	pub channel_id: ChannelId,
+	pub counterparty_port_id: PortId,
+	pub counterparty_channel_id: Option<ChannelId>,
+	pub upgrade_sequence: u64,
+}
+
+impl UpgradeAttributes {
+	pub fn new(
+		height: Height,
+		port_id: PortId,
+		channel_id: ChannelId,
+		channel_end: &ChannelEnd,
+	) -> Self {
+		Self {
+			height,
+			port_id,
+			channel_id,
+			counterparty_port_id: channel_end.counterparty().port_id().clone(),
+			counterparty_channel_id: channel_end.counterparty().channel_id().cloned(),
+			upgrade_sequence: channel_end.upgrade_sequence(),
+		}
+	}
+
+	pub fn port_id(&self) -> &PortId {
+		&self.port_id
+	}
+
+	pub fn channel_id(&self) -> &ChannelId {
+		&self.channel_id
+	}
+
+	pub fn height(&self) -> Height {
+		self.height
+	}
+
+	pub fn set_height(&mut self, height: Height) {
+		self.height = height;
+	}
+
+	/// Wraps the attributes in the `IbcEvent` of the given channel upgrade event type.
+	pub fn into_ibc_event(self, kind: IbcEventType) -> Option<IbcEvent> {
+		let event = match kind {
+			IbcEventType::UpgradeInitChannel => IbcEvent::UpgradeInitChannel(self),
+			IbcEventType::UpgradeTryChannel => IbcEvent::UpgradeTryChannel(self),
+			IbcEventType::UpgradeAckChannel => IbcEvent::UpgradeAckChannel(self),
+			IbcEventType::UpgradeConfirmChannel => IbcEvent::UpgradeConfirmChannel(self),
+			IbcEventType::UpgradeOpenChannel => IbcEvent::UpgradeOpenChannel(self),
+			IbcEventType::UpgradeTimeoutChannel => IbcEvent::UpgradeTimeoutChannel(self),
+			IbcEventType::UpgradeCancelChannel => IbcEvent::UpgradeCancelChannel(self),
+			IbcEventType::UpgradeErrorChannel => IbcEvent::UpgradeErrorChannel(self),
+			IbcEventType::FlushCompleteChannel => IbcEvent::FlushCompleteChannel(self),
+			_ => return None,
+		};
+		Some(event)
+	}
+
+	/// Converts the attributes to an ABCI event of the given kind.
+	pub fn into_abci_event(self, kind: IbcEventType) -> AbciEvent {
+		let mut attributes = vec![
+			EventAttribute {
+				key: HEIGHT_ATTRIBUTE_KEY.to_string(),
+				value: self.height.to_string(),
+				index: false,
+			},
+			EventAttribute {
+				key: PORT_ID_ATTRIBUTE_KEY.to_string(),
+				value: self.port_id.to_string(),
+				index: false,
+			},
+			EventAttribute {
+				key: CHANNEL_ID_ATTRIBUTE_KEY.to_string(),
+				value: self.channel_id.to_string(),
+				index: false,
+			},
+			EventAttribute {
+				key: COUNTERPARTY_PORT_ID_ATTRIBUTE_KEY.to_string(),
+				value: self.counterparty_port_id.to_string(),
+				index: false,
+			},
+		];
+		if let Some(channel_id) = self.counterparty_channel_id {
+			attributes.push(EventAttribute {
+				key: COUNTERPARTY_CHANNEL_ID_ATTRIBUTE_KEY.to_string(),
+				value: channel_id.to_string(),
+				index: false,
+			});
+		}
+		attributes.push(EventAttribute {
+			key: UPGRADE_SEQUENCE_ATTRIBUTE_KEY.to_string(),
+			value: self.upgrade_sequence.to_string(),
+			index: false,
+		});
+		AbciEvent { kind: kind.as_str().to_string(), attributes }
+	}
+}
+
 #[derive(Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub struct SendPacket {
 	pub height: Height,
@@ -1163,6 +1309,24 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn upgrade_event_to_abci_event() {
+		let attributes = UpgradeAttributes {
+			height: Height::default(),
+			port_id: "test_port".parse().unwrap(),
+			channel_id: "channel-0".parse().unwrap(),
+			counterparty_port_id: "counterparty_test_port".parse().unwrap(),
+			counterparty_channel_id: "channel-1".parse().ok(),
+			upgrade_sequence: 2,
+		};
+		let abci_event = attributes.clone().into_abci_event(IbcEventType::UpgradeTryChannel);
+
+		match try_from_tx(&abci_event) {
+			Some(IbcEvent::UpgradeTryChannel(e)) => assert_eq!(e, attributes),
+			_ => panic!("converted event was wrong"),
+		}
+	}
+
 	#[test]
 	fn packet_event_to_abci_event() {
 		let packet = Packet {
@@ -17,10 +17,11 @@
 use crate::{
 	core::{
 		ics04_channel::{
-			channel::ChannelEnd,
+			channel::{ChannelEnd, Order, State},
 			error::Error,
 			msgs::{ChannelMsg, PacketMsg},
-			packet::PacketResult,
+			packet::{PacketResult, Sequence},
+			upgrade::{ErrorReceipt, Upgrade},
 		},
 		ics24_host::identifier::{ChannelId, PortId},
 		ics26_routing::context::{
@@ -39,6 +40,13 @@ pub mod chan_open_ack;
 pub mod chan_open_confirm;
 pub mod chan_open_init;
 pub mod chan_open_try;
+pub mod chan_upgrade_ack;
+pub mod chan_upgrade_cancel;
+pub mod chan_upgrade_confirm;
+pub mod chan_upgrade_init;
+pub mod chan_upgrade_open;
+pub mod chan_upgrade_timeout;
+pub mod chan_upgrade_try;
 pub mod recv_packet;
 pub mod send_packet;
 pub mod timeout;
@@ -63,6 +71,82 @@ pub struct ChannelResult {
 	pub channel_id: ChannelId,
 	pub channel_id_state: ChannelIdState,
 	pub channel_end: ChannelEnd,
+	/// Set by the channel upgrade handlers, describes how the stored upgrade must be modified.
+	pub upgrade: Option<UpgradeResult>,
+}
+
+/// Outcome of processing one of the channel upgrade handshake messages.
+#[derive(Clone, Debug)]
+pub enum UpgradeResult {
+	/// The handshake moved forward, the (counterparty) upgrade should be stored.
+	Progress { upgrade: Upgrade, counterparty_upgrade: Option<Upgrade> },
+	/// The upgrade was applied to the channel end, all upgrade state should be pruned. When the
+	/// channel became `ORDERED` the receive and acknowledgement sequences must be reset.
+	Complete { next_sequence_recv: Option<Sequence>, next_sequence_ack: Option<Sequence> },
+	/// The upgrade was aborted, all upgrade state should be pruned and the error receipt stored
+	/// so that the counterparty can cancel its side of the upgrade.
+	Aborted(ErrorReceipt),
+	/// The upgrade was cancelled after the counterparty aborted it, all upgrade state should be
+	/// pruned.
+	Cancelled,
+}
+
+impl ChannelResult {
+	/// Applies the upgrade to the channel end and moves it back to `OPEN`.
+	///
+	/// Packets are not in flight anymore once both ends are done flushing, so an `UNORDERED`
+	/// channel becoming `ORDERED` expects the next packet at the sequence the counterparty will
+	/// send next, and the next acknowledgement for the sequence the host will send next. An
+	/// `ORDERED` channel becoming `UNORDERED` keeps its sequences, received packets are tracked
+	/// by receipts from then on.
+	pub fn complete_upgrade(&mut self, upgrade: Upgrade, counterparty_upgrade: &Upgrade) {
+		let (next_sequence_recv, next_sequence_ack) =
+			if self.channel_end.order_matches(&Order::Unordered) &&
+				upgrade.fields.ordering == Order::Ordered
+			{
+				(Some(counterparty_upgrade.next_sequence_send), Some(upgrade.next_sequence_send))
+			} else {
+				(None, None)
+			};
+
+		self.channel_end.set_ordering(upgrade.fields.ordering);
+		self.channel_end.set_connection_hops(upgrade.fields.connection_hops);
+		self.channel_end.set_version(upgrade.fields.version);
+		self.channel_end.set_state(State::Open);
+		self.upgrade = Some(UpgradeResult::Complete { next_sequence_recv, next_sequence_ack });
+	}
+
+	/// Turns this result into an aborted upgrade, the channel end is restored to `OPEN` with its
+	/// pre-upgrade parameters and an error receipt is written for the current upgrade sequence.
+	pub fn abort_upgrade(&mut self, message: impl ToString) {
+		self.channel_end.set_state(State::Open);
+		self.upgrade = Some(UpgradeResult::Aborted(ErrorReceipt::new(
+			self.channel_end.upgrade_sequence(),
+			message,
+		)));
+	}
+}
+
+/// Returns the channel end moved to `FLUSHCOMPLETE` when the packet with the given sequence was
+/// the last one in flight on a channel that is flushing its packets for an upgrade.
+pub(crate) fn flush_complete_channel<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	port_channel_id: &(PortId, ChannelId),
+	channel_end: &ChannelEnd,
+	sequence: Sequence,
+) -> Result<Option<ChannelEnd>, Error> {
+	if !channel_end.state_matches(&State::Flushing) {
+		return Ok(None)
+	}
+
+	let in_flight = ctx.packet_commitment_sequences(port_channel_id)?;
+	if in_flight.iter().any(|seq| *seq != sequence) {
+		return Ok(None)
+	}
+
+	let mut channel_end = channel_end.clone();
+	channel_end.set_state(State::FlushComplete);
+	Ok(Some(channel_end))
 }
 
 pub fn channel_validate<Ctx>(ctx: &Ctx, msg: &ChannelMsg) -> Result<ModuleId, Error>
@@ -77,8 +161,8 @@ where
 	}
 }
 
-/// General entry point for processing any type of message related to the ICS4 channel open and
-/// channel close handshake protocols.
+/// General entry point for processing any type of message related to the ICS4 channel open,
+/// channel close and channel upgrade handshake protocols.
 pub fn channel_dispatch<Ctx>(
 	ctx: &Ctx,
 	msg: &ChannelMsg,
@@ -93,6 +177,13 @@ where
 		ChannelMsg::ChannelOpenConfirm(msg) => chan_open_confirm::process::<_>(ctx, msg),
 		ChannelMsg::ChannelCloseInit(msg) => chan_close_init::process(ctx, msg),
 		ChannelMsg::ChannelCloseConfirm(msg) => chan_close_confirm::process::<_>(ctx, msg),
+		ChannelMsg::ChannelUpgradeInit(msg) => chan_upgrade_init::process(ctx, msg),
+		ChannelMsg::ChannelUpgradeTry(msg) => chan_upgrade_try::process::<_>(ctx, msg),
+		ChannelMsg::ChannelUpgradeAck(msg) => chan_upgrade_ack::process::<_>(ctx, msg),
+		ChannelMsg::ChannelUpgradeConfirm(msg) => chan_upgrade_confirm::process::<_>(ctx, msg),
+		ChannelMsg::ChannelUpgradeOpen(msg) => chan_upgrade_open::process::<_>(ctx, msg),
+		ChannelMsg::ChannelUpgradeTimeout(msg) => chan_upgrade_timeout::process::<_>(ctx, msg),
+		ChannelMsg::ChannelUpgradeCancel(msg) => chan_upgrade_cancel::process::<_>(ctx, msg),
 	}?;
 	let HandlerOutput { result, log, events } = output;
 	let builder = HandlerOutput::builder().with_log(log).with_events(events);
@@ -169,6 +260,64 @@ where
 			&result.channel_id,
 			&msg.signer,
 		)?,
+		ChannelMsg::ChannelUpgradeInit(msg) => {
+			if let Some(UpgradeResult::Progress { upgrade, .. }) = result.upgrade.as_mut() {
+				upgrade.fields.version = cb.on_chan_upgrade_init(
+					&ctx_clone,
+					module_output,
+					&msg.port_id,
+					&result.channel_id,
+					upgrade.fields.ordering,
+					&upgrade.fields.connection_hops,
+					&upgrade.fields.version,
+				)?;
+			}
+		},
+		// A failing application callback aborts the upgrade instead of failing the message, so
+		// that the counterparty learns about it through the error receipt.
+		ChannelMsg::ChannelUpgradeTry(msg) => {
+			if let Some(UpgradeResult::Progress { upgrade, .. }) = result.upgrade.as_mut() {
+				match cb.on_chan_upgrade_try(
+					&ctx_clone,
+					module_output,
+					&msg.port_id,
+					&result.channel_id,
+					upgrade.fields.ordering,
+					&upgrade.fields.connection_hops,
+					&upgrade.fields.version,
+				) {
+					Ok(version) => upgrade.fields.version = version,
+					Err(e) => result.abort_upgrade(e),
+				}
+			}
+		},
+		ChannelMsg::ChannelUpgradeAck(msg) => {
+			if let Some(UpgradeResult::Progress { .. }) = result.upgrade {
+				if let Err(e) = cb.on_chan_upgrade_ack(
+					&ctx_clone,
+					module_output,
+					&msg.port_id,
+					&result.channel_id,
+					&msg.counterparty_upgrade.fields.version,
+				) {
+					result.abort_upgrade(e);
+				}
+			}
+		},
+		ChannelMsg::ChannelUpgradeConfirm(_) | ChannelMsg::ChannelUpgradeOpen(_) => {
+			if let Some(UpgradeResult::Complete { .. }) = result.upgrade {
+				cb.on_chan_upgrade_open(
+					&ctx_clone,
+					module_output,
+					&result.port_id,
+					&result.channel_id,
+					*result.channel_end.ordering(),
+					result.channel_end.connection_hops(),
+					result.channel_end.version(),
+				)?;
+			}
+		},
+		ChannelMsg::ChannelUpgradeTimeout(_) | ChannelMsg::ChannelUpgradeCancel(_) => {},
 	}
 	Ok(result)
 }
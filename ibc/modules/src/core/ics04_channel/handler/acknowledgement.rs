@@ -16,10 +16,10 @@ use crate::{
 	core::{
 		ics03_connection::connection::State as ConnectionState,
 		ics04_channel::{
-			channel::{Counterparty, Order, State},
+			channel::{ChannelEnd, Counterparty, Order, State},
 			error::Error,
-			events::AcknowledgePacket,
-			handler::verify::verify_packet_acknowledgement_proofs,
+			events::{AcknowledgePacket, UpgradeAttributes},
+			handler::{flush_complete_channel, verify::verify_packet_acknowledgement_proofs},
 			msgs::acknowledgement::MsgAcknowledgement,
 			packet::{PacketResult, Sequence},
 		},
@@ -38,6 +38,9 @@ pub struct AckPacketResult {
 	pub channel_id: ChannelId,
 	pub seq: Sequence,
 	pub seq_number: Option<Sequence>,
+	/// The channel end moved to `FLUSHCOMPLETE` once the last in-flight packet of an upgrading
+	/// channel was acknowledged.
+	pub channel: Option<ChannelEnd>,
 }
 
 pub fn process<Ctx: ReaderContext>(
@@ -51,7 +54,9 @@ pub fn process<Ctx: ReaderContext>(
 	let source_channel_end =
 		ctx.channel_end(&(packet.source_port.clone(), packet.source_channel))?;
 
-	if !source_channel_end.state_matches(&State::Open) {
+	if !source_channel_end.state_matches(&State::Open) &&
+		!source_channel_end.state_matches(&State::Flushing)
+	{
 		return Err(Error::channel_closed(packet.source_channel))
 	}
 
@@ -99,6 +104,10 @@ pub fn process<Ctx: ReaderContext>(
 		&msg.proofs,
 	)?;
 
+	let port_channel_id = (packet.source_port.clone(), packet.source_channel);
+	let flushed_channel =
+		flush_complete_channel(ctx, &port_channel_id, &source_channel_end, packet.sequence)?;
+
 	let result = if source_channel_end.order_matches(&Order::Ordered) {
 		let next_seq_ack =
 			ctx.get_next_sequence_ack(&(packet.source_port.clone(), packet.source_channel))?;
@@ -112,6 +121,7 @@ pub fn process<Ctx: ReaderContext>(
 			channel_id: packet.source_channel,
 			seq: packet.sequence,
 			seq_number: Some(next_seq_ack.increment()),
+			channel: flushed_channel.clone(),
 		})
 	} else {
 		PacketResult::Ack(AckPacketResult {
@@ -119,6 +129,7 @@ pub fn process<Ctx: ReaderContext>(
 			channel_id: packet.source_channel,
 			seq: packet.sequence,
 			seq_number: None,
+			channel: flushed_channel.clone(),
 		})
 	};
 
@@ -129,6 +140,15 @@ pub fn process<Ctx: ReaderContext>(
 		packet: packet.clone(),
 	}));
 
+	if let Some(channel_end) = flushed_channel {
+		output.emit(IbcEvent::FlushCompleteChannel(UpgradeAttributes::new(
+			ctx.host_height(),
+			packet.source_port.clone(),
+			packet.source_channel,
+			&channel_end,
+		)));
+	}
+
 	Ok(output.with_result(result))
 }
 
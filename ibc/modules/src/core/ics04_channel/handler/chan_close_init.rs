@@ -76,6 +76,7 @@ pub(crate) fn process<Ctx: ReaderContext>(
 		channel_id: msg.channel_id,
 		channel_id_state: ChannelIdState::Reused,
 		channel_end,
+		upgrade: None,
 	};
 
 	output.emit(IbcEvent::CloseInitChannel(
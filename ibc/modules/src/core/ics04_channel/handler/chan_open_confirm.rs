@@ -110,6 +110,7 @@ where
 		channel_id: msg.channel_id,
 		channel_id_state: ChannelIdState::Reused,
 		channel_end,
+		upgrade: None,
 	};
 
 	output.emit(IbcEvent::OpenConfirmChannel(
@@ -86,6 +86,7 @@ pub(crate) fn process<Ctx: ReaderContext>(
 		channel_id: chan_id,
 		channel_end: new_channel_end,
 		channel_id_state: ChannelIdState::Generated,
+		upgrade: None,
 	};
 
 	output.emit(IbcEvent::OpenInitChannel(
@@ -132,6 +132,7 @@ where
 		channel_id_state: ChannelIdState::Generated,
 		channel_id,
 		channel_end: new_channel_end,
+		upgrade: None,
 	};
 
 	output.emit(IbcEvent::OpenTryChannel(
@@ -0,0 +1,337 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol logic specific to ICS4 messages of type `MsgChannelUpgradeAck`.
+
+use crate::{
+	core::{
+		ics03_connection::connection::State as ConnectionState,
+		ics04_channel::{
+			channel::{ChannelEnd, Counterparty, State},
+			error::Error,
+			events::UpgradeAttributes,
+			handler::{
+				verify::{verify_channel_proofs, verify_channel_upgrade_proofs},
+				ChannelIdState, ChannelResult, UpgradeResult,
+			},
+			msgs::chan_upgrade_ack::MsgChannelUpgradeAck,
+			upgrade::{Upgrade, UpgradeTimeout, DEFAULT_UPGRADE_TIMEOUT},
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	events::IbcEvent,
+	handler::{HandlerOutput, HandlerResult},
+	prelude::*,
+	Height,
+};
+
+pub(crate) fn process<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	msg: &MsgChannelUpgradeAck,
+) -> HandlerResult<ChannelResult, Error> {
+	let mut output = HandlerOutput::builder();
+
+	let port_channel_id = (msg.port_id.clone(), msg.channel_id);
+	let channel_end = ctx.channel_end(&port_channel_id)?;
+
+	// The channel end is already FLUSHING in case of crossing hellos.
+	if !channel_end.state_matches(&State::Open) && !channel_end.state_matches(&State::Flushing) {
+		return Err(Error::invalid_channel_state(msg.channel_id, channel_end.state))
+	}
+
+	let mut upgrade = ctx
+		.channel_upgrade(&port_channel_id)
+		.map_err(|_| Error::upgrade_not_found(msg.port_id.clone(), msg.channel_id))?;
+
+	if channel_end.connection_hops().len() != 1 {
+		return Err(Error::invalid_connection_hops_length(1, channel_end.connection_hops().len()))
+	}
+
+	let conn = ctx
+		.connection_end(&channel_end.connection_hops()[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(channel_end.connection_hops()[0].clone()))
+	}
+
+	let ccid = conn.counterparty().connection_id().ok_or_else(|| {
+		Error::undefined_connection_counterparty(channel_end.connection_hops()[0].clone())
+	})?;
+
+	// The counterparty must have accepted the upgrade and started flushing.
+	let expected_channel_end = ChannelEnd::new(
+		State::Flushing,
+		*channel_end.ordering(),
+		Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+		vec![ccid.clone()],
+		channel_end.version().clone(),
+	)
+	.with_upgrade_sequence(channel_end.upgrade_sequence());
+
+	verify_channel_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		&expected_channel_end,
+		msg.proofs.object_proof(),
+	)?;
+
+	verify_channel_upgrade_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		&msg.counterparty_upgrade,
+		msg.proofs.other_proof().as_ref().ok_or_else(Error::missing_upgrade_proof)?,
+	)?;
+
+	let mut result = ChannelResult {
+		port_id: msg.port_id.clone(),
+		channel_id: msg.channel_id,
+		channel_id_state: ChannelIdState::Reused,
+		channel_end: channel_end.clone(),
+		upgrade: None,
+	};
+
+	if let Err(e) = check_upgrade_compatibility(ctx, &upgrade, &msg.counterparty_upgrade) {
+		output.log("failure: channel upgrade ack, incompatible upgrade fields");
+		result.abort_upgrade(e);
+		return Ok(output.with_result(result))
+	}
+
+	if msg
+		.counterparty_upgrade
+		.timeout
+		.has_passed(ctx.host_height(), ctx.host_timestamp())
+	{
+		output.log("failure: channel upgrade ack, counterparty upgrade timed out");
+		result.abort_upgrade("counterparty upgrade timeout has passed");
+		return Ok(output.with_result(result))
+	}
+
+	// The version chosen by the counterparty is confirmed by the module callback.
+	upgrade.fields.version = msg.counterparty_upgrade.fields.version.clone();
+
+	if channel_end.state_matches(&State::Open) {
+		upgrade.timeout = UpgradeTimeout::new(
+			Height::zero(),
+			(ctx.host_timestamp() + DEFAULT_UPGRADE_TIMEOUT)
+				.map_err(|_| Error::invalid_upgrade_timeout())?,
+		)?;
+		upgrade.next_sequence_send = ctx.get_next_sequence_send(&port_channel_id)?;
+	}
+
+	if ctx.packet_commitment_sequences(&port_channel_id)?.is_empty() {
+		result.channel_end.set_state(State::FlushComplete);
+	} else {
+		result.channel_end.set_state(State::Flushing);
+	}
+
+	output.log("success: channel upgrade ack ");
+
+	result.upgrade = Some(UpgradeResult::Progress {
+		upgrade,
+		counterparty_upgrade: Some(msg.counterparty_upgrade.clone()),
+	});
+
+	output.emit(IbcEvent::UpgradeAckChannel(UpgradeAttributes::new(
+		ctx.host_height(),
+		msg.port_id.clone(),
+		msg.channel_id,
+		&result.channel_end,
+	)));
+
+	Ok(output.with_result(result))
+}
+
+/// Checks that the upgrade accepted by the counterparty matches the upgrade proposed by the host.
+fn check_upgrade_compatibility<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	upgrade: &Upgrade,
+	counterparty_upgrade: &Upgrade,
+) -> Result<(), Error> {
+	if upgrade.fields.ordering != counterparty_upgrade.fields.ordering {
+		return Err(Error::invalid_upgrade(
+			"counterparty upgrade ordering does not match the proposed ordering".to_string(),
+		))
+	}
+
+	let proposed_conn = ctx
+		.connection_end(&upgrade.fields.connection_hops[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !proposed_conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(upgrade.fields.connection_hops[0].clone()))
+	}
+
+	if proposed_conn.counterparty().connection_id() !=
+		Some(&counterparty_upgrade.fields.connection_hops[0])
+	{
+		return Err(Error::invalid_upgrade(
+			"proposed connection does not match the counterparty connection".to_string(),
+		))
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		core::{
+			ics02_client::context::ClientReader,
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				msgs::test_util::get_dummy_raw_counterparty,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				commitment::PacketCommitment,
+				handler::{channel_dispatch, UpgradeResult},
+				msgs::{
+					chan_upgrade_ack::{
+						test_util::get_dummy_raw_msg_chan_upgrade_ack, MsgChannelUpgradeAck,
+					},
+					ChannelMsg,
+				},
+				upgrade::{test_util::get_dummy_raw_upgrade, Upgrade},
+				Version,
+			},
+			ics24_host::identifier::{ClientId, ConnectionId},
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::MockClientState,
+			context::{MockClientTypes, MockContext},
+		},
+		prelude::*,
+		timestamp::ZERO_DURATION,
+	};
+
+	#[test]
+	fn chan_upgrade_ack_msg_processing() {
+		let client_id = ClientId::new(&MockClientState::client_type(), 24).unwrap();
+		let conn_id = ConnectionId::default();
+
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+
+		let context = MockContext::<MockClientTypes>::default();
+		let proof_height = context.host_height();
+		let timeout_height = proof_height.revision_height + 100;
+		let context = context
+			.with_client(&client_id, proof_height)
+			.with_connection(conn_id.clone(), conn_end);
+
+		let msg = MsgChannelUpgradeAck::try_from(get_dummy_raw_msg_chan_upgrade_ack(
+			"ics20-2",
+			timeout_height,
+			proof_height.revision_height,
+		))
+		.unwrap();
+		let mut upgrade =
+			Upgrade::try_from(get_dummy_raw_upgrade("ics20-2", timeout_height)).unwrap();
+		upgrade.timeout = Default::default();
+
+		let chan_end = ChannelEnd::new(
+			State::Open,
+			Order::Unordered,
+			Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+			vec![conn_id],
+			Version::new("ics20-1".to_string()),
+		)
+		.with_upgrade_sequence(1);
+
+		let context = context.with_channel(msg.port_id.clone(), msg.channel_id, chan_end);
+
+		let mut timed_out_msg = msg.clone();
+		timed_out_msg.counterparty_upgrade.timeout.height = proof_height;
+
+		let tests = vec![
+			(
+				"Processing fails because no upgrade was proposed",
+				context.clone(),
+				msg.clone(),
+				None,
+			),
+			(
+				"Upgrade is aborted because the counterparty upgrade timed out",
+				context.clone().with_channel_upgrade(
+					msg.port_id.clone(),
+					msg.channel_id,
+					upgrade.clone(),
+				),
+				timed_out_msg,
+				Some(State::Open),
+			),
+			(
+				"Good parameters, packets still in flight",
+				context
+					.clone()
+					.with_channel_upgrade(msg.port_id.clone(), msg.channel_id, upgrade.clone())
+					.with_packet_commitment(
+						msg.port_id.clone(),
+						msg.channel_id,
+						1.into(),
+						PacketCommitment::from(vec![0]),
+					),
+				msg.clone(),
+				Some(State::Flushing),
+			),
+			(
+				"Good parameters, nothing to flush",
+				context.with_channel_upgrade(msg.port_id.clone(), msg.channel_id, upgrade),
+				msg,
+				Some(State::FlushComplete),
+			),
+		];
+
+		for (name, ctx, msg, want_state) in tests {
+			let res = channel_dispatch(&ctx, &ChannelMsg::ChannelUpgradeAck(msg.clone()));
+			match (res, want_state) {
+				(Ok((output, res)), Some(state)) => {
+					assert_eq!(res.channel_end.state, state, "chan_upgrade_ack: {}", name);
+					let output = output.with_result(());
+					match res.upgrade {
+						Some(UpgradeResult::Progress { upgrade, counterparty_upgrade }) => {
+							assert!(upgrade.timeout.is_valid());
+							assert_eq!(counterparty_upgrade, Some(msg.counterparty_upgrade));
+							assert!(matches!(output.events[0], IbcEvent::UpgradeAckChannel(_)));
+						},
+						Some(UpgradeResult::Aborted(receipt)) => {
+							assert_eq!(receipt.sequence, 1);
+							assert!(output.events.is_empty());
+						},
+						other => panic!("chan_upgrade_ack: unexpected result {:?}", other),
+					}
+				},
+				(Ok(_), None) =>
+					panic!("chan_upgrade_ack: test passed but was supposed to fail: {}", name),
+				(Err(e), Some(_)) =>
+					panic!("chan_upgrade_ack: did not pass test: {}, error: {:?}", name, e),
+				(Err(_), None) => {},
+			}
+		}
+	}
+}
@@ -0,0 +1,222 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol logic specific to ICS4 messages of type `MsgChannelUpgradeCancel`.
+
+use crate::{
+	core::{
+		ics03_connection::connection::State as ConnectionState,
+		ics04_channel::{
+			channel::State,
+			error::Error,
+			events::UpgradeAttributes,
+			handler::{
+				verify::verify_channel_upgrade_error_proofs, ChannelIdState, ChannelResult,
+				UpgradeResult,
+			},
+			msgs::chan_upgrade_cancel::MsgChannelUpgradeCancel,
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	events::IbcEvent,
+	handler::{HandlerOutput, HandlerResult},
+	prelude::*,
+};
+
+pub(crate) fn process<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	msg: &MsgChannelUpgradeCancel,
+) -> HandlerResult<ChannelResult, Error> {
+	let mut output = HandlerOutput::builder();
+
+	let port_channel_id = (msg.port_id.clone(), msg.channel_id);
+	let mut channel_end = ctx.channel_end(&port_channel_id)?;
+
+	// Only an upgrade in progress can be cancelled.
+	ctx.channel_upgrade(&port_channel_id)
+		.map_err(|_| Error::upgrade_not_found(msg.port_id.clone(), msg.channel_id))?;
+
+	// Once flushing completed, only an error receipt for the current upgrade can cancel it,
+	// otherwise any receipt that is not older than the current upgrade can.
+	let sequence = msg.error_receipt.sequence;
+	let upgrade_sequence = channel_end.upgrade_sequence();
+	let valid_sequence = if channel_end.state_matches(&State::FlushComplete) {
+		sequence == upgrade_sequence
+	} else {
+		sequence >= upgrade_sequence
+	};
+	if !valid_sequence {
+		return Err(Error::invalid_upgrade_sequence(upgrade_sequence, sequence))
+	}
+
+	if channel_end.connection_hops().len() != 1 {
+		return Err(Error::invalid_connection_hops_length(1, channel_end.connection_hops().len()))
+	}
+
+	let conn = ctx
+		.connection_end(&channel_end.connection_hops()[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(channel_end.connection_hops()[0].clone()))
+	}
+
+	verify_channel_upgrade_error_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		&msg.error_receipt,
+		msg.proofs.object_proof(),
+	)?;
+
+	output.log("success: channel upgrade cancel ");
+
+	// Restore the channel end and catch up with the sequence of the counterparty.
+	channel_end.set_state(State::Open);
+	channel_end.set_upgrade_sequence(sequence);
+
+	output.emit(IbcEvent::UpgradeCancelChannel(UpgradeAttributes::new(
+		ctx.host_height(),
+		msg.port_id.clone(),
+		msg.channel_id,
+		&channel_end,
+	)));
+
+	let result = ChannelResult {
+		port_id: msg.port_id.clone(),
+		channel_id: msg.channel_id,
+		channel_id_state: ChannelIdState::Reused,
+		channel_end,
+		upgrade: Some(UpgradeResult::Cancelled),
+	};
+
+	Ok(output.with_result(result))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		core::{
+			ics02_client::context::ClientReader,
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				msgs::test_util::get_dummy_raw_counterparty,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				handler::{channel_dispatch, UpgradeResult},
+				msgs::{
+					chan_upgrade_cancel::{
+						test_util::get_dummy_raw_msg_chan_upgrade_cancel, MsgChannelUpgradeCancel,
+					},
+					ChannelMsg,
+				},
+				upgrade::{test_util::get_dummy_raw_upgrade, Upgrade},
+				Version,
+			},
+			ics24_host::identifier::{ClientId, ConnectionId},
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::MockClientState,
+			context::{MockClientTypes, MockContext},
+		},
+		prelude::*,
+		timestamp::ZERO_DURATION,
+	};
+
+	#[test]
+	fn chan_upgrade_cancel_msg_processing() {
+		let client_id = ClientId::new(&MockClientState::client_type(), 24).unwrap();
+		let conn_id = ConnectionId::default();
+
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+
+		let context = MockContext::<MockClientTypes>::default();
+		let proof_height = context.host_height();
+		let context = context
+			.with_client(&client_id, proof_height)
+			.with_connection(conn_id.clone(), conn_end);
+
+		let msg = MsgChannelUpgradeCancel::try_from(get_dummy_raw_msg_chan_upgrade_cancel(
+			3,
+			proof_height.revision_height,
+		))
+		.unwrap();
+
+		let mut chan_end = ChannelEnd::new(
+			State::Flushing,
+			Order::Unordered,
+			Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+			vec![conn_id],
+			Version::new("ics20-1".to_string()),
+		)
+		.with_upgrade_sequence(2);
+
+		let upgrade = Upgrade::try_from(get_dummy_raw_upgrade("ics20-2", 100)).unwrap();
+		let context_without_upgrade =
+			context.with_channel(msg.port_id.clone(), msg.channel_id, chan_end.clone());
+		let context = context_without_upgrade.clone().with_channel_upgrade(
+			msg.port_id.clone(),
+			msg.channel_id,
+			upgrade,
+		);
+		chan_end.set_state(State::FlushComplete);
+		let flushed_context =
+			context.clone().with_channel(msg.port_id.clone(), msg.channel_id, chan_end);
+
+		let tests = vec![
+			("Processing fails because no upgrade is in progress", context_without_upgrade, false),
+			(
+				"Processing fails because flushing completed for another upgrade sequence",
+				flushed_context,
+				false,
+			),
+			("Good parameters", context, true),
+		];
+
+		for (name, ctx, want_pass) in tests {
+			let res = channel_dispatch(&ctx, &ChannelMsg::ChannelUpgradeCancel(msg.clone()));
+			match res {
+				Ok((output, res)) => {
+					assert!(
+						want_pass,
+						"chan_upgrade_cancel: test passed but was supposed to fail: {}",
+						name
+					);
+					assert_eq!(res.channel_end.state, State::Open);
+					assert_eq!(res.channel_end.upgrade_sequence(), 3);
+					assert!(matches!(res.upgrade, Some(UpgradeResult::Cancelled)));
+					let output = output.with_result(());
+					assert!(matches!(output.events[0], IbcEvent::UpgradeCancelChannel(_)));
+				},
+				Err(e) => assert!(
+					!want_pass,
+					"chan_upgrade_cancel: did not pass test: {}, error: {:?}",
+					name, e
+				),
+			}
+		}
+	}
+}
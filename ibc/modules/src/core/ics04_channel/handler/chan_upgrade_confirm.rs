@@ -0,0 +1,291 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol logic specific to ICS4 messages of type `MsgChannelUpgradeConfirm`.
+
+use crate::{
+	core::{
+		ics03_connection::connection::State as ConnectionState,
+		ics04_channel::{
+			channel::{ChannelEnd, Counterparty, State},
+			error::Error,
+			events::UpgradeAttributes,
+			handler::{
+				verify::{verify_channel_proofs, verify_channel_upgrade_proofs},
+				ChannelIdState, ChannelResult, UpgradeResult,
+			},
+			msgs::chan_upgrade_confirm::MsgChannelUpgradeConfirm,
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	events::IbcEvent,
+	handler::{HandlerOutput, HandlerResult},
+	prelude::*,
+};
+
+pub(crate) fn process<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	msg: &MsgChannelUpgradeConfirm,
+) -> HandlerResult<ChannelResult, Error> {
+	let mut output = HandlerOutput::builder();
+
+	let port_channel_id = (msg.port_id.clone(), msg.channel_id);
+	let channel_end = ctx.channel_end(&port_channel_id)?;
+
+	if !channel_end.state_matches(&State::Flushing) &&
+		!channel_end.state_matches(&State::FlushComplete)
+	{
+		return Err(Error::invalid_channel_state(msg.channel_id, channel_end.state))
+	}
+
+	let upgrade = ctx
+		.channel_upgrade(&port_channel_id)
+		.map_err(|_| Error::upgrade_not_found(msg.port_id.clone(), msg.channel_id))?;
+
+	if channel_end.connection_hops().len() != 1 {
+		return Err(Error::invalid_connection_hops_length(1, channel_end.connection_hops().len()))
+	}
+
+	let conn = ctx
+		.connection_end(&channel_end.connection_hops()[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(channel_end.connection_hops()[0].clone()))
+	}
+
+	let ccid = conn.counterparty().connection_id().ok_or_else(|| {
+		Error::undefined_connection_counterparty(channel_end.connection_hops()[0].clone())
+	})?;
+
+	let expected_channel_end = ChannelEnd::new(
+		msg.counterparty_channel_state,
+		*channel_end.ordering(),
+		Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+		vec![ccid.clone()],
+		channel_end.version().clone(),
+	)
+	.with_upgrade_sequence(channel_end.upgrade_sequence());
+
+	verify_channel_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		&expected_channel_end,
+		msg.proofs.object_proof(),
+	)?;
+
+	verify_channel_upgrade_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		&msg.counterparty_upgrade,
+		msg.proofs.other_proof().as_ref().ok_or_else(Error::missing_upgrade_proof)?,
+	)?;
+
+	let mut result = ChannelResult {
+		port_id: msg.port_id.clone(),
+		channel_id: msg.channel_id,
+		channel_id_state: ChannelIdState::Reused,
+		channel_end: channel_end.clone(),
+		upgrade: None,
+	};
+
+	if msg
+		.counterparty_upgrade
+		.timeout
+		.has_passed(ctx.host_height(), ctx.host_timestamp())
+	{
+		output.log("failure: channel upgrade confirm, counterparty upgrade timed out");
+		result.abort_upgrade("counterparty upgrade timeout has passed");
+		return Ok(output.with_result(result))
+	}
+
+	if channel_end.state_matches(&State::Flushing) &&
+		ctx.packet_commitment_sequences(&port_channel_id)?.is_empty()
+	{
+		result.channel_end.set_state(State::FlushComplete);
+	}
+
+	output.log("success: channel upgrade confirm ");
+
+	output.emit(IbcEvent::UpgradeConfirmChannel(UpgradeAttributes::new(
+		ctx.host_height(),
+		msg.port_id.clone(),
+		msg.channel_id,
+		&result.channel_end,
+	)));
+
+	// Both ends are done flushing, the upgrade can be applied right away.
+	if result.channel_end.state_matches(&State::FlushComplete) &&
+		msg.counterparty_channel_state == State::FlushComplete
+	{
+		result.complete_upgrade(upgrade, &msg.counterparty_upgrade);
+
+		output.emit(IbcEvent::UpgradeOpenChannel(UpgradeAttributes::new(
+			ctx.host_height(),
+			msg.port_id.clone(),
+			msg.channel_id,
+			&result.channel_end,
+		)));
+	} else {
+		result.upgrade = Some(UpgradeResult::Progress {
+			upgrade,
+			counterparty_upgrade: Some(msg.counterparty_upgrade.clone()),
+		});
+	}
+
+	Ok(output.with_result(result))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		core::{
+			ics02_client::context::ClientReader,
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				msgs::test_util::get_dummy_raw_counterparty,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				handler::{channel_dispatch, UpgradeResult},
+				msgs::{
+					chan_upgrade_confirm::{
+						test_util::get_dummy_raw_msg_chan_upgrade_confirm, MsgChannelUpgradeConfirm,
+					},
+					ChannelMsg,
+				},
+				upgrade::{test_util::get_dummy_raw_upgrade, Upgrade},
+				Version,
+			},
+			ics24_host::identifier::{ClientId, ConnectionId},
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::MockClientState,
+			context::{MockClientTypes, MockContext},
+		},
+		prelude::*,
+		timestamp::ZERO_DURATION,
+	};
+
+	#[test]
+	fn chan_upgrade_confirm_msg_processing() {
+		let client_id = ClientId::new(&MockClientState::client_type(), 24).unwrap();
+		let conn_id = ConnectionId::default();
+
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+
+		let context = MockContext::<MockClientTypes>::default();
+		let proof_height = context.host_height();
+		let context = context
+			.with_client(&client_id, proof_height)
+			.with_connection(conn_id.clone(), conn_end);
+
+		let msg = MsgChannelUpgradeConfirm::try_from(get_dummy_raw_msg_chan_upgrade_confirm(
+			State::FlushComplete as i32,
+			"ics20-2",
+			proof_height.revision_height,
+		))
+		.unwrap();
+		let mut flushing_msg = msg.clone();
+		flushing_msg.counterparty_channel_state = State::Flushing;
+
+		let upgrade = Upgrade::try_from(get_dummy_raw_upgrade("ics20-2", 100)).unwrap();
+
+		let mut chan_end = ChannelEnd::new(
+			State::Flushing,
+			Order::Unordered,
+			Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+			vec![conn_id],
+			Version::new("ics20-1".to_string()),
+		)
+		.with_upgrade_sequence(1);
+
+		let context_without_upgrade =
+			context
+				.clone()
+				.with_channel(msg.port_id.clone(), msg.channel_id, chan_end.clone());
+		let context = context_without_upgrade.clone().with_channel_upgrade(
+			msg.port_id.clone(),
+			msg.channel_id,
+			upgrade,
+		);
+		chan_end.set_state(State::Open);
+		let open_context =
+			context.clone().with_channel(msg.port_id.clone(), msg.channel_id, chan_end);
+
+		let tests = vec![
+			(
+				"Processing fails because no upgrade is in progress",
+				context_without_upgrade,
+				msg.clone(),
+				None,
+			),
+			(
+				"Processing fails because the channel is not upgrading",
+				open_context,
+				msg.clone(),
+				None,
+			),
+			(
+				"Counterparty still flushing",
+				context.clone(),
+				flushing_msg,
+				Some(State::FlushComplete),
+			),
+			("Both ends flushed, upgrade completes", context, msg, Some(State::Open)),
+		];
+
+		for (name, ctx, msg, want_state) in tests {
+			let res = channel_dispatch(&ctx, &ChannelMsg::ChannelUpgradeConfirm(msg.clone()));
+			match (res, want_state) {
+				(Ok((output, res)), Some(state)) => {
+					assert_eq!(res.channel_end.state, state, "chan_upgrade_confirm: {}", name);
+					let output = output.with_result(());
+					assert!(matches!(output.events[0], IbcEvent::UpgradeConfirmChannel(_)));
+					match res.upgrade {
+						Some(UpgradeResult::Complete { .. }) => {
+							assert_eq!(
+								res.channel_end.version,
+								Version::new("ics20-2".to_string())
+							);
+							assert!(matches!(output.events[1], IbcEvent::UpgradeOpenChannel(_)));
+						},
+						Some(UpgradeResult::Progress { counterparty_upgrade, .. }) =>
+							assert_eq!(counterparty_upgrade, Some(msg.counterparty_upgrade)),
+						other => panic!("chan_upgrade_confirm: unexpected result {:?}", other),
+					}
+				},
+				(Ok(_), None) =>
+					panic!("chan_upgrade_confirm: test passed but was supposed to fail: {}", name),
+				(Err(e), Some(_)) =>
+					panic!("chan_upgrade_confirm: did not pass test: {}, error: {:?}", name, e),
+				(Err(_), None) => {},
+			}
+		}
+	}
+}
@@ -0,0 +1,223 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol logic specific to ICS4 messages of type `MsgChannelUpgradeInit`.
+
+use crate::{
+	core::{
+		ics03_connection::connection::State as ConnectionState,
+		ics04_channel::{
+			channel::State,
+			error::Error,
+			events::UpgradeAttributes,
+			handler::{ChannelIdState, ChannelResult, UpgradeResult},
+			msgs::chan_upgrade_init::MsgChannelUpgradeInit,
+			upgrade::{Upgrade, UpgradeTimeout},
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	events::IbcEvent,
+	handler::{HandlerOutput, HandlerResult},
+	prelude::*,
+};
+
+pub(crate) fn process<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	msg: &MsgChannelUpgradeInit,
+) -> HandlerResult<ChannelResult, Error> {
+	let mut output = HandlerOutput::builder();
+
+	let mut channel_end = ctx.channel_end(&(msg.port_id.clone(), msg.channel_id))?;
+
+	// Upgrades can only be proposed for open channels.
+	if !channel_end.state_matches(&State::Open) {
+		return Err(Error::invalid_channel_state(msg.channel_id, channel_end.state))
+	}
+
+	if msg.fields.matches_channel(&channel_end) {
+		return Err(Error::invalid_upgrade(
+			"proposed upgrade fields are identical to the current channel parameters".to_string(),
+		))
+	}
+
+	// The proposed connection must be OPEN on the host chain.
+	let conn = ctx
+		.connection_end(&msg.fields.connection_hops[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(msg.fields.connection_hops[0].clone()))
+	}
+
+	// A previously proposed upgrade that did not make it past `INIT` is simply superseded, the
+	// counterparty can no longer prove it against the bumped upgrade sequence.
+	channel_end.set_upgrade_sequence(channel_end.upgrade_sequence() + 1);
+
+	let upgrade = Upgrade::new(msg.fields.clone(), UpgradeTimeout::default(), 0.into());
+
+	output.log("success: channel upgrade init ");
+
+	let event_attributes = UpgradeAttributes::new(
+		ctx.host_height(),
+		msg.port_id.clone(),
+		msg.channel_id,
+		&channel_end,
+	);
+
+	let result = ChannelResult {
+		port_id: msg.port_id.clone(),
+		channel_id: msg.channel_id,
+		channel_id_state: ChannelIdState::Reused,
+		channel_end,
+		upgrade: Some(UpgradeResult::Progress { upgrade, counterparty_upgrade: None }),
+	};
+
+	output.emit(IbcEvent::UpgradeInitChannel(event_attributes));
+
+	Ok(output.with_result(result))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		core::{
+			ics02_client::context::ClientReader,
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				msgs::test_util::get_dummy_raw_counterparty,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				handler::{channel_dispatch, UpgradeResult},
+				msgs::{
+					chan_upgrade_init::{
+						test_util::get_dummy_raw_msg_chan_upgrade_init, MsgChannelUpgradeInit,
+					},
+					ChannelMsg,
+				},
+				Version,
+			},
+			ics24_host::identifier::{ClientId, ConnectionId},
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::MockClientState,
+			context::{MockClientTypes, MockContext},
+		},
+		prelude::*,
+		timestamp::ZERO_DURATION,
+	};
+
+	#[test]
+	fn chan_upgrade_init_msg_processing() {
+		let client_id = ClientId::new(&MockClientState::client_type(), 24).unwrap();
+		let conn_id = ConnectionId::default();
+
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+
+		let msg = MsgChannelUpgradeInit::try_from(get_dummy_raw_msg_chan_upgrade_init("ics20-2"))
+			.unwrap();
+
+		let chan_end = ChannelEnd::new(
+			State::Open,
+			Order::Unordered,
+			Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+			vec![conn_id.clone()],
+			Version::new("ics20-1".to_string()),
+		);
+
+		let context = {
+			let default_context = MockContext::<MockClientTypes>::default();
+			let client_consensus_state_height = default_context.host_height();
+			default_context
+				.with_client(&client_id, client_consensus_state_height)
+				.with_connection(conn_id, conn_end)
+		};
+
+		let mut chan_end_init = chan_end.clone();
+		chan_end_init.set_state(State::Init);
+		let mut chan_end_upgraded = chan_end.clone();
+		chan_end_upgraded.set_version(Version::new("ics20-2".to_string()));
+		let mut chan_end_ordered = chan_end_upgraded.clone();
+		chan_end_ordered.set_ordering(Order::Ordered);
+
+		let tests = vec![
+			("Processing fails because no channel exists in the context", context.clone(), false),
+			(
+				"Processing fails because the channel is not open",
+				context.clone().with_channel(msg.port_id.clone(), msg.channel_id, chan_end_init),
+				false,
+			),
+			(
+				"Processing fails because nothing would be upgraded",
+				context.clone().with_channel(
+					msg.port_id.clone(),
+					msg.channel_id,
+					chan_end_upgraded,
+				),
+				false,
+			),
+			(
+				"Only the channel ordering is upgraded",
+				context
+					.clone()
+					.with_channel(msg.port_id.clone(), msg.channel_id, chan_end_ordered),
+				true,
+			),
+			(
+				"Good parameters",
+				context.with_channel(msg.port_id.clone(), msg.channel_id, chan_end),
+				true,
+			),
+		];
+
+		for (name, ctx, want_pass) in tests {
+			let res = channel_dispatch(&ctx, &ChannelMsg::ChannelUpgradeInit(msg.clone()));
+			match res {
+				Ok((output, res)) => {
+					assert!(
+						want_pass,
+						"chan_upgrade_init: test passed but was supposed to fail: {}",
+						name
+					);
+					assert_eq!(res.channel_end.state, State::Open);
+					assert_eq!(res.channel_end.upgrade_sequence(), 1);
+					match res.upgrade {
+						Some(UpgradeResult::Progress { upgrade, counterparty_upgrade: None }) =>
+							assert_eq!(upgrade.fields, msg.fields),
+						other => panic!("unexpected upgrade result {:?}", other),
+					}
+
+					let output = output.with_result(());
+					assert_eq!(output.events.len(), 1);
+					assert!(matches!(output.events[0], IbcEvent::UpgradeInitChannel(_)));
+				},
+				Err(e) => assert!(
+					!want_pass,
+					"chan_upgrade_init: did not pass test: {}, error: {:?}",
+					name, e
+				),
+			}
+		}
+	}
+}
@@ -0,0 +1,321 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol logic specific to ICS4 messages of type `MsgChannelUpgradeOpen`.
+
+use crate::{
+	core::{
+		ics03_connection::connection::State as ConnectionState,
+		ics04_channel::{
+			channel::{ChannelEnd, Counterparty, State},
+			error::Error,
+			events::UpgradeAttributes,
+			handler::{verify::verify_channel_proofs, ChannelIdState, ChannelResult},
+			msgs::chan_upgrade_open::MsgChannelUpgradeOpen,
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	events::IbcEvent,
+	handler::{HandlerOutput, HandlerResult},
+	prelude::*,
+};
+
+pub(crate) fn process<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	msg: &MsgChannelUpgradeOpen,
+) -> HandlerResult<ChannelResult, Error> {
+	let mut output = HandlerOutput::builder();
+
+	let port_channel_id = (msg.port_id.clone(), msg.channel_id);
+	let channel_end = ctx.channel_end(&port_channel_id)?;
+
+	if !channel_end.state_matches(&State::FlushComplete) {
+		return Err(Error::invalid_channel_state(msg.channel_id, channel_end.state))
+	}
+
+	let upgrade = ctx
+		.channel_upgrade(&port_channel_id)
+		.map_err(|_| Error::upgrade_not_found(msg.port_id.clone(), msg.channel_id))?;
+
+	if channel_end.connection_hops().len() != 1 {
+		return Err(Error::invalid_connection_hops_length(1, channel_end.connection_hops().len()))
+	}
+
+	let conn = ctx
+		.connection_end(&channel_end.connection_hops()[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(channel_end.connection_hops()[0].clone()))
+	}
+
+	let ccid = conn.counterparty().connection_id().ok_or_else(|| {
+		Error::undefined_connection_counterparty(channel_end.connection_hops()[0].clone())
+	})?;
+
+	let counterparty_upgrade = ctx
+		.channel_counterparty_upgrade(&port_channel_id)
+		.map_err(|_| Error::upgrade_not_found(msg.port_id.clone(), msg.channel_id))?;
+
+	let counterparty = Counterparty::new(msg.port_id.clone(), Some(msg.channel_id));
+	let expected_channel_end = match msg.counterparty_channel_state {
+		// The counterparty is done flushing but did not apply the upgrade yet.
+		State::FlushComplete => ChannelEnd::new(
+			State::FlushComplete,
+			*channel_end.ordering(),
+			counterparty,
+			vec![ccid.clone()],
+			channel_end.version().clone(),
+		)
+		.with_upgrade_sequence(channel_end.upgrade_sequence()),
+		// The counterparty already applied the upgrade it agreed to.
+		_ => ChannelEnd::new(
+			State::Open,
+			counterparty_upgrade.fields.ordering,
+			counterparty,
+			counterparty_upgrade.fields.connection_hops.clone(),
+			counterparty_upgrade.fields.version.clone(),
+		)
+		.with_upgrade_sequence(msg.counterparty_upgrade_sequence),
+	};
+
+	verify_channel_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		&expected_channel_end,
+		msg.proofs.object_proof(),
+	)?;
+
+	output.log("success: channel upgrade open ");
+
+	let mut result = ChannelResult {
+		port_id: msg.port_id.clone(),
+		channel_id: msg.channel_id,
+		channel_id_state: ChannelIdState::Reused,
+		channel_end,
+		upgrade: None,
+	};
+	result.complete_upgrade(upgrade, &counterparty_upgrade);
+
+	output.emit(IbcEvent::UpgradeOpenChannel(UpgradeAttributes::new(
+		ctx.host_height(),
+		msg.port_id.clone(),
+		msg.channel_id,
+		&result.channel_end,
+	)));
+
+	Ok(output.with_result(result))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		core::{
+			ics02_client::context::ClientReader,
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				msgs::test_util::get_dummy_raw_counterparty,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				handler::{channel_dispatch, UpgradeResult},
+				msgs::{
+					chan_upgrade_open::{
+						test_util::get_dummy_raw_msg_chan_upgrade_open, MsgChannelUpgradeOpen,
+					},
+					ChannelMsg,
+				},
+				upgrade::{test_util::get_dummy_raw_upgrade, Upgrade},
+				Version,
+			},
+			ics24_host::identifier::{ClientId, ConnectionId},
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::MockClientState,
+			context::{MockClientTypes, MockContext},
+		},
+		prelude::*,
+		timestamp::ZERO_DURATION,
+	};
+
+	#[test]
+	fn chan_upgrade_open_msg_processing() {
+		let client_id = ClientId::new(&MockClientState::client_type(), 24).unwrap();
+		let conn_id = ConnectionId::default();
+
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+
+		let context = MockContext::<MockClientTypes>::default();
+		let proof_height = context.host_height();
+		let context = context
+			.with_client(&client_id, proof_height)
+			.with_connection(conn_id.clone(), conn_end);
+
+		let msg = MsgChannelUpgradeOpen::try_from(get_dummy_raw_msg_chan_upgrade_open(
+			State::FlushComplete as i32,
+			1,
+			proof_height.revision_height,
+		))
+		.unwrap();
+		let mut counterparty_open_msg = msg.clone();
+		counterparty_open_msg.counterparty_channel_state = State::Open;
+
+		let upgrade = Upgrade::try_from(get_dummy_raw_upgrade("ics20-2", 100)).unwrap();
+
+		let mut chan_end = ChannelEnd::new(
+			State::FlushComplete,
+			Order::Unordered,
+			Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+			vec![conn_id],
+			Version::new("ics20-1".to_string()),
+		)
+		.with_upgrade_sequence(1);
+
+		let context = context
+			.with_channel(msg.port_id.clone(), msg.channel_id, chan_end.clone())
+			.with_channel_upgrade(msg.port_id.clone(), msg.channel_id, upgrade.clone());
+		chan_end.set_state(State::Flushing);
+		let flushing_context =
+			context.clone().with_channel(msg.port_id.clone(), msg.channel_id, chan_end);
+
+		let upgraded_context = context.clone().with_channel_counterparty_upgrade(
+			msg.port_id.clone(),
+			msg.channel_id,
+			upgrade,
+		);
+
+		let tests = vec![
+			(
+				"Processing fails because the channel is still flushing",
+				flushing_context,
+				msg.clone(),
+				false,
+			),
+			(
+				"Processing fails because the counterparty upgrade is unknown",
+				context,
+				counterparty_open_msg.clone(),
+				false,
+			),
+			(
+				"Counterparty already upgraded",
+				upgraded_context.clone(),
+				counterparty_open_msg,
+				true,
+			),
+			("Counterparty flush complete", upgraded_context, msg, true),
+		];
+
+		for (name, ctx, msg, want_pass) in tests {
+			let res = channel_dispatch(&ctx, &ChannelMsg::ChannelUpgradeOpen(msg.clone()));
+			match res {
+				Ok((output, res)) => {
+					assert!(
+						want_pass,
+						"chan_upgrade_open: test passed but was supposed to fail: {}",
+						name
+					);
+					assert_eq!(res.channel_end.state, State::Open);
+					assert_eq!(res.channel_end.version, Version::new("ics20-2".to_string()));
+					assert!(matches!(
+						res.upgrade,
+						Some(UpgradeResult::Complete {
+							next_sequence_recv: None,
+							next_sequence_ack: None
+						})
+					));
+					let output = output.with_result(());
+					assert!(matches!(output.events[0], IbcEvent::UpgradeOpenChannel(_)));
+				},
+				Err(e) => assert!(
+					!want_pass,
+					"chan_upgrade_open: did not pass test: {}, error: {:?}",
+					name, e
+				),
+			}
+		}
+	}
+
+	#[test]
+	fn chan_upgrade_open_resets_sequences_when_channel_becomes_ordered() {
+		let client_id = ClientId::new(&MockClientState::client_type(), 24).unwrap();
+		let conn_id = ConnectionId::default();
+
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+
+		let context = MockContext::<MockClientTypes>::default();
+		let proof_height = context.host_height();
+
+		let msg = MsgChannelUpgradeOpen::try_from(get_dummy_raw_msg_chan_upgrade_open(
+			State::FlushComplete as i32,
+			1,
+			proof_height.revision_height,
+		))
+		.unwrap();
+
+		let mut upgrade = Upgrade::try_from(get_dummy_raw_upgrade("ics20-2", 100)).unwrap();
+		upgrade.fields.ordering = Order::Ordered;
+		upgrade.next_sequence_send = 5.into();
+		let mut counterparty_upgrade = upgrade.clone();
+		counterparty_upgrade.next_sequence_send = 7.into();
+
+		let chan_end = ChannelEnd::new(
+			State::FlushComplete,
+			Order::Unordered,
+			Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+			vec![conn_id.clone()],
+			Version::new("ics20-1".to_string()),
+		)
+		.with_upgrade_sequence(1);
+
+		let context = context
+			.with_client(&client_id, proof_height)
+			.with_connection(conn_id, conn_end)
+			.with_channel(msg.port_id.clone(), msg.channel_id, chan_end)
+			.with_channel_upgrade(msg.port_id.clone(), msg.channel_id, upgrade)
+			.with_channel_counterparty_upgrade(
+				msg.port_id.clone(),
+				msg.channel_id,
+				counterparty_upgrade,
+			);
+
+		let (_, res) = channel_dispatch(&context, &ChannelMsg::ChannelUpgradeOpen(msg)).unwrap();
+		assert_eq!(res.channel_end.ordering, Order::Ordered);
+		match res.upgrade {
+			Some(UpgradeResult::Complete { next_sequence_recv, next_sequence_ack }) => {
+				assert_eq!(next_sequence_recv, Some(7.into()));
+				assert_eq!(next_sequence_ack, Some(5.into()));
+			},
+			_ => panic!("expected the upgrade to complete"),
+		}
+	}
+}
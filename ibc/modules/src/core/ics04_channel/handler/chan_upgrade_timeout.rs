@@ -0,0 +1,252 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol logic specific to ICS4 messages of type `MsgChannelUpgradeTimeout`.
+
+use crate::{
+	core::{
+		ics02_client::client_consensus::ConsensusState,
+		ics03_connection::connection::State as ConnectionState,
+		ics04_channel::{
+			channel::State,
+			error::Error,
+			events::UpgradeAttributes,
+			handler::{verify::verify_channel_proofs, ChannelIdState, ChannelResult},
+			msgs::chan_upgrade_timeout::MsgChannelUpgradeTimeout,
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	events::IbcEvent,
+	handler::{HandlerOutput, HandlerResult},
+	prelude::*,
+};
+
+pub(crate) fn process<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	msg: &MsgChannelUpgradeTimeout,
+) -> HandlerResult<ChannelResult, Error> {
+	let mut output = HandlerOutput::builder();
+
+	let port_channel_id = (msg.port_id.clone(), msg.channel_id);
+	let channel_end = ctx.channel_end(&port_channel_id)?;
+
+	if !channel_end.state_matches(&State::Flushing) &&
+		!channel_end.state_matches(&State::FlushComplete)
+	{
+		return Err(Error::invalid_channel_state(msg.channel_id, channel_end.state))
+	}
+
+	// The timeout is enforced on the counterparty, it is part of the upgrade it accepted.
+	let counterparty_upgrade = ctx
+		.channel_counterparty_upgrade(&port_channel_id)
+		.map_err(|_| Error::upgrade_not_found(msg.port_id.clone(), msg.channel_id))?;
+
+	if channel_end.connection_hops().len() != 1 {
+		return Err(Error::invalid_connection_hops_length(1, channel_end.connection_hops().len()))
+	}
+
+	let conn = ctx
+		.connection_end(&channel_end.connection_hops()[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(channel_end.connection_hops()[0].clone()))
+	}
+
+	let consensus_state = ctx
+		.consensus_state(conn.client_id(), msg.proofs.height())
+		.map_err(|_| Error::error_invalid_consensus_state())?;
+
+	if !counterparty_upgrade
+		.timeout
+		.has_passed(msg.proofs.height(), consensus_state.timestamp())
+	{
+		return Err(Error::upgrade_timeout_not_reached())
+	}
+
+	// A counterparty that finished flushing, or already applied the upgrade, cannot be timed out.
+	let counterparty_channel = &msg.counterparty_channel;
+	if counterparty_channel.state_matches(&State::FlushComplete) ||
+		(counterparty_channel.state_matches(&State::Open) &&
+			counterparty_channel.upgrade_sequence() == channel_end.upgrade_sequence())
+	{
+		return Err(Error::invalid_channel_state(msg.channel_id, counterparty_channel.state))
+	}
+
+	verify_channel_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		counterparty_channel,
+		msg.proofs.object_proof(),
+	)?;
+
+	output.log("success: channel upgrade timeout ");
+
+	let mut result = ChannelResult {
+		port_id: msg.port_id.clone(),
+		channel_id: msg.channel_id,
+		channel_id_state: ChannelIdState::Reused,
+		channel_end,
+		upgrade: None,
+	};
+	result.abort_upgrade("counterparty did not complete the upgrade before the timeout");
+
+	output.emit(IbcEvent::UpgradeTimeoutChannel(UpgradeAttributes::new(
+		ctx.host_height(),
+		msg.port_id.clone(),
+		msg.channel_id,
+		&result.channel_end,
+	)));
+
+	Ok(output.with_result(result))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		core::{
+			ics02_client::context::ClientReader,
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				msgs::test_util::get_dummy_raw_counterparty,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				handler::{channel_dispatch, UpgradeResult},
+				msgs::{
+					chan_upgrade_timeout::{
+						test_util::get_dummy_raw_msg_chan_upgrade_timeout, MsgChannelUpgradeTimeout,
+					},
+					ChannelMsg,
+				},
+				upgrade::{test_util::get_dummy_raw_upgrade, Upgrade},
+				Version,
+			},
+			ics24_host::identifier::{ClientId, ConnectionId},
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::MockClientState,
+			context::{MockClientTypes, MockContext},
+		},
+		prelude::*,
+		timestamp::ZERO_DURATION,
+	};
+
+	#[test]
+	fn chan_upgrade_timeout_msg_processing() {
+		let client_id = ClientId::new(&MockClientState::client_type(), 24).unwrap();
+		let conn_id = ConnectionId::default();
+
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+
+		let context = MockContext::<MockClientTypes>::default();
+		let proof_height = context.host_height();
+		let context = context
+			.with_client(&client_id, proof_height)
+			.with_connection(conn_id.clone(), conn_end);
+
+		let mut msg = MsgChannelUpgradeTimeout::try_from(get_dummy_raw_msg_chan_upgrade_timeout(
+			proof_height.revision_height,
+		))
+		.unwrap();
+		msg.counterparty_channel.set_state(State::Flushing);
+		let mut flushed_msg = msg.clone();
+		flushed_msg.counterparty_channel.set_state(State::FlushComplete);
+
+		let chan_end = ChannelEnd::new(
+			State::Flushing,
+			Order::Unordered,
+			Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+			vec![conn_id],
+			Version::new("ics20-1".to_string()),
+		)
+		.with_upgrade_sequence(1);
+
+		let context = context.with_channel(msg.port_id.clone(), msg.channel_id, chan_end);
+		let pending_upgrade =
+			Upgrade::try_from(get_dummy_raw_upgrade("ics20-2", proof_height.revision_height + 1))
+				.unwrap();
+		let expired_upgrade =
+			Upgrade::try_from(get_dummy_raw_upgrade("ics20-2", proof_height.revision_height))
+				.unwrap();
+
+		let tests = vec![
+			(
+				"Processing fails because the timeout has not been reached",
+				context.clone().with_channel_counterparty_upgrade(
+					msg.port_id.clone(),
+					msg.channel_id,
+					pending_upgrade,
+				),
+				msg.clone(),
+				false,
+			),
+			(
+				"Processing fails because the counterparty is done flushing",
+				context.clone().with_channel_counterparty_upgrade(
+					msg.port_id.clone(),
+					msg.channel_id,
+					expired_upgrade.clone(),
+				),
+				flushed_msg,
+				false,
+			),
+			(
+				"Good parameters",
+				context.with_channel_counterparty_upgrade(
+					msg.port_id.clone(),
+					msg.channel_id,
+					expired_upgrade,
+				),
+				msg,
+				true,
+			),
+		];
+
+		for (name, ctx, msg, want_pass) in tests {
+			let res = channel_dispatch(&ctx, &ChannelMsg::ChannelUpgradeTimeout(msg.clone()));
+			match res {
+				Ok((output, res)) => {
+					assert!(
+						want_pass,
+						"chan_upgrade_timeout: test passed but was supposed to fail: {}",
+						name
+					);
+					assert_eq!(res.channel_end.state, State::Open);
+					assert_eq!(res.channel_end.version, Version::new("ics20-1".to_string()));
+					assert!(matches!(res.upgrade, Some(UpgradeResult::Aborted(_))));
+					let output = output.with_result(());
+					assert!(matches!(output.events[0], IbcEvent::UpgradeTimeoutChannel(_)));
+				},
+				Err(e) => assert!(
+					!want_pass,
+					"chan_upgrade_timeout: did not pass test: {}, error: {:?}",
+					name, e
+				),
+			}
+		}
+	}
+}
@@ -0,0 +1,333 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protocol logic specific to ICS4 messages of type `MsgChannelUpgradeTry`.
+
+use crate::{
+	core::{
+		ics03_connection::connection::State as ConnectionState,
+		ics04_channel::{
+			channel::{ChannelEnd, Counterparty, State},
+			error::Error,
+			events::UpgradeAttributes,
+			handler::{
+				verify::{verify_channel_proofs, verify_channel_upgrade_proofs},
+				ChannelIdState, ChannelResult, UpgradeResult,
+			},
+			msgs::chan_upgrade_try::MsgChannelUpgradeTry,
+			upgrade::{Upgrade, UpgradeFields, UpgradeTimeout, DEFAULT_UPGRADE_TIMEOUT},
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	events::IbcEvent,
+	handler::{HandlerOutput, HandlerResult},
+	prelude::*,
+	Height,
+};
+
+pub(crate) fn process<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	msg: &MsgChannelUpgradeTry,
+) -> HandlerResult<ChannelResult, Error> {
+	let mut output = HandlerOutput::builder();
+
+	let port_channel_id = (msg.port_id.clone(), msg.channel_id);
+	let mut channel_end = ctx.channel_end(&port_channel_id)?;
+
+	if !channel_end.state_matches(&State::Open) {
+		return Err(Error::invalid_channel_state(msg.channel_id, channel_end.state))
+	}
+
+	// The connection the channel currently runs on is used to verify the proofs.
+	if channel_end.connection_hops().len() != 1 {
+		return Err(Error::invalid_connection_hops_length(1, channel_end.connection_hops().len()))
+	}
+
+	let conn = ctx
+		.connection_end(&channel_end.connection_hops()[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(channel_end.connection_hops()[0].clone()))
+	}
+
+	let ccid = conn.counterparty().connection_id().ok_or_else(|| {
+		Error::undefined_connection_counterparty(channel_end.connection_hops()[0].clone())
+	})?;
+
+	// The counterparty must have proposed the upgrade with the given sequence while its channel
+	// end is still OPEN.
+	let expected_channel_end = ChannelEnd::new(
+		State::Open,
+		*channel_end.ordering(),
+		Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+		vec![ccid.clone()],
+		channel_end.version().clone(),
+	)
+	.with_upgrade_sequence(msg.counterparty_upgrade_sequence);
+
+	verify_channel_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		&expected_channel_end,
+		msg.proofs.object_proof(),
+	)?;
+
+	let counterparty_upgrade =
+		Upgrade::new(msg.counterparty_upgrade_fields.clone(), UpgradeTimeout::default(), 0.into());
+
+	verify_channel_upgrade_proofs::<Ctx>(
+		ctx,
+		msg.proofs.height(),
+		&channel_end,
+		&conn,
+		&counterparty_upgrade,
+		msg.proofs.other_proof().as_ref().ok_or_else(Error::missing_upgrade_proof)?,
+	)?;
+
+	let mut result = ChannelResult {
+		port_id: msg.port_id.clone(),
+		channel_id: msg.channel_id,
+		channel_id_state: ChannelIdState::Reused,
+		channel_end: channel_end.clone(),
+		upgrade: None,
+	};
+
+	// The counterparty is behind, abort with the current sequence so that it can cancel its
+	// upgrade and catch up.
+	if msg.counterparty_upgrade_sequence < channel_end.upgrade_sequence() {
+		output.log("failure: channel upgrade try, counterparty upgrade sequence is outdated");
+		result.abort_upgrade(Error::invalid_upgrade_sequence(
+			channel_end.upgrade_sequence(),
+			msg.counterparty_upgrade_sequence,
+		));
+		return Ok(output.with_result(result))
+	}
+
+	result.channel_end.set_upgrade_sequence(msg.counterparty_upgrade_sequence);
+
+	if let Err(e) = check_upgrade_compatibility(ctx, &channel_end, msg) {
+		output.log("failure: channel upgrade try, incompatible upgrade fields");
+		result.abort_upgrade(e);
+		return Ok(output.with_result(result))
+	}
+
+	// Start flushing the in-flight packets, the counterparty has until the upgrade timeout to
+	// move its channel end to FLUSHCOMPLETE.
+	let timeout = UpgradeTimeout::new(
+		Height::zero(),
+		(ctx.host_timestamp() + DEFAULT_UPGRADE_TIMEOUT)
+			.map_err(|_| Error::invalid_upgrade_timeout())?,
+	)?;
+	let upgrade = Upgrade::new(
+		UpgradeFields::new(
+			msg.counterparty_upgrade_fields.ordering,
+			msg.proposed_upgrade_connection_hops.clone(),
+			msg.counterparty_upgrade_fields.version.clone(),
+		),
+		timeout,
+		ctx.get_next_sequence_send(&port_channel_id)?,
+	);
+
+	output.log("success: channel upgrade try ");
+
+	result.channel_end.set_state(State::Flushing);
+	result.upgrade = Some(UpgradeResult::Progress { upgrade, counterparty_upgrade: None });
+
+	output.emit(IbcEvent::UpgradeTryChannel(UpgradeAttributes::new(
+		ctx.host_height(),
+		msg.port_id.clone(),
+		msg.channel_id,
+		&result.channel_end,
+	)));
+
+	Ok(output.with_result(result))
+}
+
+/// Checks that the upgrade proposed by the counterparty can be accepted by the host chain.
+fn check_upgrade_compatibility<Ctx: ReaderContext>(
+	ctx: &Ctx,
+	channel_end: &ChannelEnd,
+	msg: &MsgChannelUpgradeTry,
+) -> Result<(), Error> {
+	// The proposed connection must be OPEN and connect to the one proposed by the counterparty.
+	let proposed_conn = ctx
+		.connection_end(&msg.proposed_upgrade_connection_hops[0])
+		.map_err(Error::ics03_connection)?;
+
+	if !proposed_conn.state_matches(&ConnectionState::Open) {
+		return Err(Error::connection_not_open(msg.proposed_upgrade_connection_hops[0].clone()))
+	}
+
+	if proposed_conn.counterparty().connection_id() !=
+		Some(&msg.counterparty_upgrade_fields.connection_hops[0])
+	{
+		return Err(Error::invalid_upgrade(
+			"proposed connection does not match the counterparty connection".to_string(),
+		))
+	}
+
+	// In case of crossing hellos the host chain proposed an upgrade as well, both must agree.
+	if let Ok(upgrade) = ctx.channel_upgrade(&(msg.port_id.clone(), msg.channel_id)) {
+		if upgrade.fields.ordering != msg.counterparty_upgrade_fields.ordering ||
+			upgrade.fields.connection_hops != msg.proposed_upgrade_connection_hops ||
+			upgrade.fields.version != msg.counterparty_upgrade_fields.version
+		{
+			return Err(Error::invalid_upgrade(
+				"counterparty upgrade does not match the upgrade proposed by the host".to_string(),
+			))
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		core::{
+			ics02_client::context::ClientReader,
+			ics03_connection::{
+				connection::{
+					ConnectionEnd, Counterparty as ConnectionCounterparty, State as ConnectionState,
+				},
+				msgs::test_util::get_dummy_raw_counterparty,
+				version::get_compatible_versions,
+			},
+			ics04_channel::{
+				channel::{ChannelEnd, Counterparty, Order, State},
+				handler::{channel_dispatch, UpgradeResult},
+				msgs::{
+					chan_upgrade_try::{
+						test_util::get_dummy_raw_msg_chan_upgrade_try, MsgChannelUpgradeTry,
+					},
+					ChannelMsg,
+				},
+				Version,
+			},
+			ics24_host::identifier::{ClientId, ConnectionId},
+		},
+		events::IbcEvent,
+		mock::{
+			client_state::MockClientState,
+			context::{MockClientTypes, MockContext},
+		},
+		prelude::*,
+		timestamp::ZERO_DURATION,
+	};
+
+	#[test]
+	fn chan_upgrade_try_msg_processing() {
+		let client_id = ClientId::new(&MockClientState::client_type(), 24).unwrap();
+		let conn_id = ConnectionId::default();
+
+		let conn_end = ConnectionEnd::new(
+			ConnectionState::Open,
+			client_id.clone(),
+			ConnectionCounterparty::try_from(get_dummy_raw_counterparty()).unwrap(),
+			get_compatible_versions(),
+			ZERO_DURATION,
+		);
+
+		let context = MockContext::<MockClientTypes>::default();
+		let proof_height = context.host_height();
+		let context = context
+			.with_client(&client_id, proof_height)
+			.with_connection(conn_id.clone(), conn_end);
+
+		let msg = MsgChannelUpgradeTry::try_from(get_dummy_raw_msg_chan_upgrade_try(
+			"ics20-2",
+			1,
+			proof_height.revision_height,
+		))
+		.unwrap();
+
+		let chan_end = ChannelEnd::new(
+			State::Open,
+			Order::Unordered,
+			Counterparty::new(msg.port_id.clone(), Some(msg.channel_id)),
+			vec![conn_id],
+			Version::new("ics20-1".to_string()),
+		);
+		let mut flushing_chan_end = chan_end.clone();
+		flushing_chan_end.set_state(State::Flushing);
+		let mut ordered_chan_end = chan_end.clone();
+		ordered_chan_end.set_ordering(Order::Ordered);
+
+		let tests = vec![
+			("Processing fails because no channel exists in the context", context.clone(), None),
+			(
+				"Processing fails because the channel is already upgrading",
+				context.clone().with_channel(
+					msg.port_id.clone(),
+					msg.channel_id,
+					flushing_chan_end,
+				),
+				None,
+			),
+			(
+				"Upgrade is aborted because the counterparty sequence is outdated",
+				context.clone().with_channel(
+					msg.port_id.clone(),
+					msg.channel_id,
+					chan_end.clone().with_upgrade_sequence(2),
+				),
+				Some(State::Open),
+			),
+			(
+				"The counterparty changes the channel ordering",
+				context
+					.clone()
+					.with_channel(msg.port_id.clone(), msg.channel_id, ordered_chan_end),
+				Some(State::Flushing),
+			),
+			(
+				"Good parameters",
+				context.with_channel(msg.port_id.clone(), msg.channel_id, chan_end),
+				Some(State::Flushing),
+			),
+		];
+
+		for (name, ctx, want_state) in tests {
+			let res = channel_dispatch(&ctx, &ChannelMsg::ChannelUpgradeTry(msg.clone()));
+			match (res, want_state) {
+				(Ok((output, res)), Some(state)) => {
+					assert_eq!(res.channel_end.state, state, "chan_upgrade_try: {}", name);
+					let output = output.with_result(());
+					match res.upgrade {
+						Some(UpgradeResult::Progress { upgrade, counterparty_upgrade: None }) => {
+							assert_eq!(res.channel_end.upgrade_sequence(), 1);
+							assert_eq!(upgrade.fields.version, Version::new("ics20-2".to_string()));
+							assert_eq!(upgrade.fields.ordering, Order::Unordered);
+							assert!(upgrade.timeout.is_valid());
+							assert!(matches!(output.events[0], IbcEvent::UpgradeTryChannel(_)));
+						},
+						Some(UpgradeResult::Aborted(receipt)) => {
+							assert_eq!(receipt.sequence, 2);
+							assert!(output.events.is_empty());
+						},
+						other => panic!("chan_upgrade_try: unexpected result {:?}", other),
+					}
+				},
+				(Ok(_), None) =>
+					panic!("chan_upgrade_try: test passed but was supposed to fail: {}", name),
+				(Err(e), Some(_)) =>
+					panic!("chan_upgrade_try: did not pass test: {}, error: {:?}", name, e),
+				(Err(_), None) => {},
+			}
+		}
+	}
+}
@@ -63,10 +63,26 @@ pub fn process<Ctx: ReaderContext>(
 	let dest_channel_end =
 		ctx.channel_end(&(packet.destination_port.clone(), packet.destination_channel))?;
 
-	if !dest_channel_end.state_matches(&State::Open) {
+	if !dest_channel_end.state_matches(&State::Open) && !dest_channel_end.state.is_upgrading() {
 		return Err(Error::invalid_channel_state(packet.source_channel, dest_channel_end.state))
 	}
 
+	// Once the counterparty started flushing, it can no longer have sent packets past the
+	// sequence recorded in its upgrade.
+	if dest_channel_end.state.is_upgrading() {
+		if let Ok(counterparty_upgrade) = ctx.channel_counterparty_upgrade(&(
+			packet.destination_port.clone(),
+			packet.destination_channel,
+		)) {
+			if packet.sequence >= counterparty_upgrade.next_sequence_send {
+				return Err(Error::packet_sent_after_flush(
+					packet.sequence,
+					counterparty_upgrade.next_sequence_send,
+				))
+			}
+		}
+	}
+
 	let counterparty = Counterparty::new(packet.source_port.clone(), Some(packet.source_channel));
 
 	if !dest_channel_end.counterparty_matches(&counterparty) {
@@ -18,8 +18,11 @@ use crate::{
 		ics04_channel::{
 			channel::{ChannelEnd, Counterparty, Order, State},
 			error::Error,
-			events::TimeoutPacket,
-			handler::verify::{verify_next_sequence_recv, verify_packet_receipt_absence},
+			events::{TimeoutPacket, UpgradeAttributes},
+			handler::{
+				flush_complete_channel,
+				verify::{verify_next_sequence_recv, verify_packet_receipt_absence},
+			},
 			msgs::timeout::MsgTimeout,
 			packet::{PacketResult, Sequence},
 		},
@@ -51,7 +54,9 @@ where
 	let mut source_channel_end =
 		ctx.channel_end(&(packet.source_port.clone(), packet.source_channel))?;
 
-	if !source_channel_end.state_matches(&State::Open) {
+	if !source_channel_end.state_matches(&State::Open) &&
+		!source_channel_end.state_matches(&State::Flushing)
+	{
 		return Err(Error::channel_closed(packet.source_channel))
 	}
 
@@ -102,6 +107,7 @@ where
 		return Err(Error::incorrect_packet_commitment(packet.sequence))
 	}
 
+	let mut flushed_channel = None;
 	let result = if source_channel_end.order_matches(&Order::Ordered) {
 		if packet.sequence < msg.next_sequence_recv {
 			return Err(Error::invalid_packet_sequence(packet.sequence, msg.next_sequence_recv))
@@ -131,11 +137,18 @@ where
 			&msg.proofs,
 		)?;
 
+		flushed_channel = flush_complete_channel(
+			ctx,
+			&(packet.source_port.clone(), packet.source_channel),
+			&source_channel_end,
+			packet.sequence,
+		)?;
+
 		PacketResult::Timeout(TimeoutPacketResult {
 			port_id: packet.source_port.clone(),
 			channel_id: packet.source_channel,
 			seq: packet.sequence,
-			channel: None,
+			channel: flushed_channel.clone(),
 		})
 	};
 
@@ -146,6 +159,15 @@ where
 		packet: packet.clone(),
 	}));
 
+	if let Some(channel_end) = flushed_channel {
+		output.emit(IbcEvent::FlushCompleteChannel(UpgradeAttributes::new(
+			ctx.host_height(),
+			packet.source_port.clone(),
+			packet.source_channel,
+			&channel_end,
+		)));
+	}
+
 	Ok(output.with_result(result))
 }
 
@@ -23,6 +23,7 @@ use crate::{
 			error::Error,
 			msgs::acknowledgement::Acknowledgement,
 			packet::{Packet, Sequence},
+			upgrade::{ErrorReceipt, Upgrade},
 		},
 		ics23_commitment::commitment::CommitmentProofBytes,
 		ics26_routing::context::ReaderContext,
@@ -81,6 +82,99 @@ where
 		.map_err(Error::verify_channel_failed)
 }
 
+/// Verifies that the counterparty of `channel_end` has stored the `expected_upgrade`.
+pub fn verify_channel_upgrade_proofs<Ctx>(
+	ctx: &Ctx,
+	height: Height,
+	channel_end: &ChannelEnd,
+	connection_end: &ConnectionEnd,
+	expected_upgrade: &Upgrade,
+	proof: &CommitmentProofBytes,
+) -> Result<(), Error>
+where
+	Ctx: ReaderContext,
+{
+	let client_id = connection_end.client_id().clone();
+
+	let client_state = ctx.client_state(&client_id).map_err(Error::ics02_client)?;
+
+	// The client must not be frozen.
+	if client_state.is_frozen() {
+		return Err(Error::frozen_client(client_id))
+	}
+
+	let consensus_state = ctx
+		.consensus_state(&client_id, height)
+		.map_err(|_| Error::error_invalid_consensus_state())?;
+
+	let client_def = client_state.client_def();
+
+	client_def
+		.verify_channel_upgrade(
+			ctx,
+			&client_id,
+			&client_state,
+			height,
+			connection_end.counterparty().prefix(),
+			proof,
+			consensus_state.root(),
+			channel_end.counterparty().port_id(),
+			channel_end
+				.counterparty()
+				.channel_id()
+				.ok_or_else(|| Error::missing_channel_id())?,
+			expected_upgrade,
+		)
+		.map_err(Error::upgrade_verification_failed)
+}
+
+/// Verifies that the counterparty of `channel_end` has aborted its upgrade with the
+/// `expected_error_receipt`.
+pub fn verify_channel_upgrade_error_proofs<Ctx>(
+	ctx: &Ctx,
+	height: Height,
+	channel_end: &ChannelEnd,
+	connection_end: &ConnectionEnd,
+	expected_error_receipt: &ErrorReceipt,
+	proof: &CommitmentProofBytes,
+) -> Result<(), Error>
+where
+	Ctx: ReaderContext,
+{
+	let client_id = connection_end.client_id().clone();
+
+	let client_state = ctx.client_state(&client_id).map_err(Error::ics02_client)?;
+
+	// The client must not be frozen.
+	if client_state.is_frozen() {
+		return Err(Error::frozen_client(client_id))
+	}
+
+	let consensus_state = ctx
+		.consensus_state(&client_id, height)
+		.map_err(|_| Error::error_invalid_consensus_state())?;
+
+	let client_def = client_state.client_def();
+
+	client_def
+		.verify_channel_upgrade_error(
+			ctx,
+			&client_id,
+			&client_state,
+			height,
+			connection_end.counterparty().prefix(),
+			proof,
+			consensus_state.root(),
+			channel_end.counterparty().port_id(),
+			channel_end
+				.counterparty()
+				.channel_id()
+				.ok_or_else(|| Error::missing_channel_id())?,
+			expected_error_receipt,
+		)
+		.map_err(Error::upgrade_verification_failed)
+}
+
 /// Entry point for verifying all proofs bundled in a ICS4 packet recv. message.
 pub fn verify_packet_recv_proofs<Ctx: ReaderContext>(
 	ctx: &Ctx,
@@ -47,7 +47,7 @@ pub fn process<Ctx: ReaderContext>(
 	let dest_channel_end =
 		ctx.channel_end(&(packet.destination_port.clone(), packet.destination_channel))?;
 
-	if !dest_channel_end.state_matches(&State::Open) {
+	if !dest_channel_end.state_matches(&State::Open) && !dest_channel_end.state.is_upgrading() {
 		return Err(Error::invalid_channel_state(packet.source_channel, dest_channel_end.state))
 	}
 
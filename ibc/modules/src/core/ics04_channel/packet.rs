@@ -19,6 +19,7 @@ use core::str::FromStr;
 use serde_derive::{Deserialize, Serialize};
 
 use ibc_proto::ibc::core::channel::v1::Packet as RawPacket;
+use tendermint_proto::Protobuf;
 
 use crate::{
 	core::{
@@ -323,6 +324,8 @@ impl From<Packet> for RawPacket {
 	}
 }
 
+impl Protobuf<RawPacket> for Packet {}
+
 #[cfg(test)]
 pub mod test_utils {
 	use crate::prelude::*;
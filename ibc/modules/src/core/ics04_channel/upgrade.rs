@@ -0,0 +1,282 @@
+// Copyright 2022 ComposableFi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Types used by the channel upgrade handshake, which allows two channel ends to renegotiate
+//! their ordering, connection hops and version without closing the channel.
+
+use crate::prelude::*;
+
+use core::{str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tendermint_proto::Protobuf;
+
+use ibc_proto::ibc::core::channel::v1::{
+	ErrorReceipt as RawErrorReceipt, Timeout as RawTimeout, Upgrade as RawUpgrade,
+	UpgradeFields as RawUpgradeFields,
+};
+
+use crate::{
+	core::{
+		ics04_channel::{
+			channel::{ChannelEnd, Order},
+			error::Error,
+			packet::Sequence,
+			Version,
+		},
+		ics24_host::identifier::ConnectionId,
+	},
+	timestamp::{Expiry, Timestamp},
+	Height,
+};
+
+/// Upgrade timeout applied by a chain to its own upgrade, relative to the latest timestamp of
+/// the counterparty chain known to its light client.
+pub const DEFAULT_UPGRADE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// The fields of a channel end that may be changed by an upgrade.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpgradeFields {
+	pub ordering: Order,
+	pub connection_hops: Vec<ConnectionId>,
+	pub version: Version,
+}
+
+impl UpgradeFields {
+	pub fn new(ordering: Order, connection_hops: Vec<ConnectionId>, version: Version) -> Self {
+		Self { ordering, connection_hops, version }
+	}
+
+	pub fn validate_basic(&self) -> Result<(), Error> {
+		if self.connection_hops.len() != 1 {
+			return Err(Error::invalid_connection_hops_length(1, self.connection_hops.len()))
+		}
+		if self.version.to_string().trim().is_empty() {
+			return Err(Error::invalid_upgrade("proposed version cannot be empty".to_string()))
+		}
+		Ok(())
+	}
+
+	/// Returns `true` if applying these fields to the channel end would not change it.
+	pub fn matches_channel(&self, channel_end: &ChannelEnd) -> bool {
+		channel_end.order_matches(&self.ordering) &&
+			channel_end.connection_hops_matches(&self.connection_hops) &&
+			channel_end.version_matches(&self.version)
+	}
+}
+
+impl Protobuf<RawUpgradeFields> for UpgradeFields {}
+
+impl TryFrom<RawUpgradeFields> for UpgradeFields {
+	type Error = Error;
+
+	fn try_from(value: RawUpgradeFields) -> Result<Self, Self::Error> {
+		let connection_hops = value
+			.connection_hops
+			.into_iter()
+			.map(|conn_id| ConnectionId::from_str(conn_id.as_str()))
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(Error::identifier)?;
+
+		Ok(UpgradeFields {
+			ordering: Order::from_i32(value.ordering)?,
+			connection_hops,
+			version: value.version.into(),
+		})
+	}
+}
+
+impl From<UpgradeFields> for RawUpgradeFields {
+	fn from(value: UpgradeFields) -> Self {
+		RawUpgradeFields {
+			ordering: value.ordering as i32,
+			connection_hops: value.connection_hops.iter().map(|v| v.as_str().to_string()).collect(),
+			version: value.version.to_string(),
+		}
+	}
+}
+
+/// Deadline on the counterparty chain after which an upgrade attempt is aborted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpgradeTimeout {
+	pub height: Height,
+	pub timestamp: Timestamp,
+}
+
+impl UpgradeTimeout {
+	pub fn new(height: Height, timestamp: Timestamp) -> Result<Self, Error> {
+		let timeout = Self { height, timestamp };
+		if !timeout.is_valid() {
+			return Err(Error::invalid_upgrade_timeout())
+		}
+		Ok(timeout)
+	}
+
+	/// A timeout must define at least one of a height or a timestamp.
+	pub fn is_valid(&self) -> bool {
+		!self.height.is_zero() || self.timestamp != Timestamp::none()
+	}
+
+	/// Returns `true` if the timeout has elapsed at the given height and timestamp.
+	pub fn has_passed(&self, height: Height, timestamp: Timestamp) -> bool {
+		(!self.height.is_zero() && self.height <= height) ||
+			(self.timestamp != Timestamp::none() &&
+				timestamp.check_expiry(&self.timestamp) == Expiry::Expired)
+	}
+}
+
+impl Protobuf<RawTimeout> for UpgradeTimeout {}
+
+impl TryFrom<RawTimeout> for UpgradeTimeout {
+	type Error = Error;
+
+	fn try_from(value: RawTimeout) -> Result<Self, Self::Error> {
+		Ok(UpgradeTimeout {
+			height: value.height.map(Into::into).unwrap_or_else(Height::zero),
+			timestamp: Timestamp::from_nanoseconds(value.timestamp)
+				.map_err(Error::invalid_packet_timestamp)?,
+		})
+	}
+}
+
+impl From<UpgradeTimeout> for RawTimeout {
+	fn from(value: UpgradeTimeout) -> Self {
+		RawTimeout { height: Some(value.height.into()), timestamp: value.timestamp.nanoseconds() }
+	}
+}
+
+/// An upgrade attempt: the proposed fields, the deadline on the counterparty and the next
+/// sequence the channel end will send, which bounds the packets that must be flushed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Upgrade {
+	pub fields: UpgradeFields,
+	pub timeout: UpgradeTimeout,
+	pub next_sequence_send: Sequence,
+}
+
+impl Upgrade {
+	pub fn new(
+		fields: UpgradeFields,
+		timeout: UpgradeTimeout,
+		next_sequence_send: Sequence,
+	) -> Self {
+		Self { fields, timeout, next_sequence_send }
+	}
+}
+
+impl Protobuf<RawUpgrade> for Upgrade {}
+
+impl TryFrom<RawUpgrade> for Upgrade {
+	type Error = Error;
+
+	fn try_from(value: RawUpgrade) -> Result<Self, Self::Error> {
+		Ok(Upgrade {
+			fields: value.fields.ok_or_else(Error::missing_upgrade_fields)?.try_into()?,
+			timeout: value.timeout.map(TryInto::try_into).transpose()?.unwrap_or_default(),
+			next_sequence_send: value.next_sequence_send.into(),
+		})
+	}
+}
+
+impl From<Upgrade> for RawUpgrade {
+	fn from(value: Upgrade) -> Self {
+		RawUpgrade {
+			fields: Some(value.fields.into()),
+			timeout: Some(value.timeout.into()),
+			next_sequence_send: value.next_sequence_send.into(),
+		}
+	}
+}
+
+/// Written by a chain that aborted the upgrade attempt with the given sequence, so that the
+/// counterparty can prove it and cancel its own attempt.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReceipt {
+	pub sequence: u64,
+	pub message: String,
+}
+
+impl ErrorReceipt {
+	pub fn new(sequence: u64, message: impl ToString) -> Self {
+		Self { sequence, message: message.to_string() }
+	}
+}
+
+impl Protobuf<RawErrorReceipt> for ErrorReceipt {}
+
+impl TryFrom<RawErrorReceipt> for ErrorReceipt {
+	type Error = Error;
+
+	fn try_from(value: RawErrorReceipt) -> Result<Self, Self::Error> {
+		Ok(ErrorReceipt { sequence: value.sequence, message: value.message })
+	}
+}
+
+impl From<ErrorReceipt> for RawErrorReceipt {
+	fn from(value: ErrorReceipt) -> Self {
+		RawErrorReceipt { sequence: value.sequence, message: value.message }
+	}
+}
+
+#[cfg(test)]
+pub mod test_util {
+	use crate::{core::ics24_host::identifier::ConnectionId, prelude::*};
+	use ibc_proto::ibc::core::{
+		channel::v1::{
+			Timeout as RawTimeout, Upgrade as RawUpgrade, UpgradeFields as RawUpgradeFields,
+		},
+		client::v1::Height as RawHeight,
+	};
+
+	/// Returns dummy `RawUpgradeFields` proposing to move the channel to the given version.
+	pub fn get_dummy_raw_upgrade_fields(version: &str) -> RawUpgradeFields {
+		RawUpgradeFields {
+			ordering: 1,
+			connection_hops: vec![ConnectionId::default().to_string()],
+			version: version.to_string(),
+		}
+	}
+
+	/// Returns a dummy `RawUpgrade` that times out at the given height.
+	pub fn get_dummy_raw_upgrade(version: &str, timeout_height: u64) -> RawUpgrade {
+		RawUpgrade {
+			fields: Some(get_dummy_raw_upgrade_fields(version)),
+			timeout: Some(RawTimeout {
+				height: Some(RawHeight { revision_number: 0, revision_height: timeout_height }),
+				timestamp: 0,
+			}),
+			next_sequence_send: 1,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{test_util::get_dummy_raw_upgrade, *};
+
+	#[test]
+	fn upgrade_raw_roundtrip() {
+		let raw = get_dummy_raw_upgrade("ics20-1", 10);
+		let upgrade = Upgrade::try_from(raw.clone()).unwrap();
+		assert_eq!(RawUpgrade::from(upgrade), raw);
+	}
+
+	#[test]
+	fn upgrade_timeout_has_passed() {
+		let timeout = UpgradeTimeout::new(Height::new(0, 10), Timestamp::none()).unwrap();
+		assert!(!timeout.has_passed(Height::new(0, 9), Timestamp::now()));
+		assert!(timeout.has_passed(Height::new(0, 10), Timestamp::now()));
+		assert!(UpgradeTimeout::new(Height::zero(), Timestamp::none()).is_err());
+	}
+}
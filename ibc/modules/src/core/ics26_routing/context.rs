@@ -180,6 +180,66 @@ pub trait Module: Send + Sync + AsAnyMut {
 		Ok(())
 	}
 
+	/// Called when a channel upgrade is proposed on this chain, returns the version the module
+	/// wants to upgrade to. Modules have to opt into channel upgrades by overriding this callback.
+	#[allow(clippy::too_many_arguments)]
+	fn on_chan_upgrade_init(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_proposed_order: Order,
+		_proposed_connection_hops: &[ConnectionId],
+		_proposed_version: &Version,
+	) -> Result<Version, Error> {
+		Err(Error::invalid_upgrade("module does not support channel upgrades".to_string()))
+	}
+
+	/// Called when the counterparty proposed a channel upgrade, returns the version the module
+	/// agrees to upgrade to. An error aborts the upgrade.
+	#[allow(clippy::too_many_arguments)]
+	fn on_chan_upgrade_try(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_proposed_order: Order,
+		_proposed_connection_hops: &[ConnectionId],
+		_proposed_version: &Version,
+	) -> Result<Version, Error> {
+		Err(Error::invalid_upgrade("module does not support channel upgrades".to_string()))
+	}
+
+	/// Called when the counterparty accepted the upgrade proposed on this chain. An error aborts
+	/// the upgrade.
+	fn on_chan_upgrade_ack(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_counterparty_version: &Version,
+	) -> Result<(), Error> {
+		Err(Error::invalid_upgrade("module does not support channel upgrades".to_string()))
+	}
+
+	/// Called once the upgraded parameters have been applied to the channel end.
+	#[allow(clippy::too_many_arguments)]
+	fn on_chan_upgrade_open(
+		&mut self,
+		_ctx: &dyn ModuleCallbackContext,
+		_output: &mut ModuleOutputBuilder,
+		_port_id: &PortId,
+		_channel_id: &ChannelId,
+		_order: Order,
+		_connection_hops: &[ConnectionId],
+		_version: &Version,
+	) -> Result<(), Error> {
+		Ok(())
+	}
+
 	/// Modules should write acknowledgement to storage in this callback
 	fn on_recv_packet(
 		&self,
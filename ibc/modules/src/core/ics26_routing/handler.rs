@@ -15,19 +15,25 @@
 use crate::{
 	core::{
 		ics02_client::{
-			context::{ClientKeeper, ClientTypes},
-			handler::dispatch as ics2_msg_dispatcher,
+			client_def::ClientDef,
+			client_state::ClientState,
+			context::{ClientKeeper, ClientReader, ClientTypes},
+			handler::{dispatch as ics2_msg_dispatcher, update_client, ClientResult},
 		},
-		ics03_connection::handler::dispatch as ics3_msg_dispatcher,
+		ics03_connection::{handler::dispatch as ics3_msg_dispatcher, msgs::ConnectionMsg},
 		ics04_channel::{
+			events::UpgradeAttributes,
 			handler::{
 				channel_callback as ics4_callback, channel_dispatch as ics4_msg_dispatcher,
 				channel_validate as ics4_validate, get_module_for_packet_msg,
 				packet_callback as ics4_packet_callback,
 				packet_dispatch as ics4_packet_msg_dispatcher, recv_packet::RecvPacketResult,
+				UpgradeResult,
 			},
+			msgs::{ChannelMsg, PacketMsg},
 			packet::PacketResult,
 		},
+		ics24_host::identifier::{ChannelId, ClientId, PortId},
 		ics26_routing::{
 			context::{Ics26Context, ModuleOutputBuilder, ReaderContext},
 			error::Error,
@@ -85,6 +91,9 @@ pub fn dispatch<Ctx>(ctx: &mut Ctx, msg: Ics26Envelope<Ctx>) -> Result<HandlerOu
 where
 	Ctx: Ics26Context + ClientKeeper,
 {
+	let proof_client_id = proof_client_id(ctx, &msg)
+		.filter(|client_id| updates_state_on_proofs_verified::<Ctx>(client_id));
+
 	let output = match msg {
 		Ics2Msg(msg) => {
 			let handler_output =
@@ -124,6 +133,17 @@ where
 			handler_builder.merge(module_output);
 			let channel_result = cb_result.map_err(Error::ics04_channel)?;
 
+			// An aborted channel upgrade is not a failure of the message, relayers learn about it
+			// through the error event and cancel the upgrade on the counterparty.
+			if let Some(UpgradeResult::Aborted(_)) = channel_result.upgrade {
+				handler_builder.emit(IbcEvent::UpgradeErrorChannel(UpgradeAttributes::new(
+					ctx.host_height(),
+					channel_result.port_id.clone(),
+					channel_result.channel_id,
+					&channel_result.channel_end,
+				)));
+			}
+
 			// Apply any results to the host chain store.
 			ctx.store_channel_result(channel_result).map_err(Error::ics04_channel)?;
 
@@ -151,9 +171,99 @@ where
 		},
 	};
 
+	if let Some(client_id) = proof_client_id {
+		update_client_on_proofs_verified(ctx, client_id)?;
+	}
+
 	Ok(output)
 }
 
+/// Returns the client verifying the proofs carried by `msg`, looked up before the message changes
+/// any connection or channel. Messages that carry no proofs return `None`.
+fn proof_client_id<Ctx>(ctx: &Ctx, msg: &Ics26Envelope<Ctx>) -> Option<ClientId>
+where
+	Ctx: Ics26Context,
+{
+	let port_channel_id: (PortId, ChannelId) = match msg {
+		Ics2Msg(_) => return None,
+		Ics3Msg(msg) => {
+			let connection_id = match msg {
+				ConnectionMsg::ConnectionOpenInit(_) => return None,
+				ConnectionMsg::ConnectionOpenTry(msg) => return Some(msg.client_id.clone()),
+				ConnectionMsg::ConnectionOpenAck(msg) => &msg.connection_id,
+				ConnectionMsg::ConnectionOpenConfirm(msg) => &msg.connection_id,
+			};
+			return ctx.connection_end(connection_id).ok().map(|conn| conn.client_id().clone())
+		},
+		Ics4ChannelMsg(msg) => match msg {
+			ChannelMsg::ChannelOpenInit(_) |
+			ChannelMsg::ChannelCloseInit(_) |
+			ChannelMsg::ChannelUpgradeInit(_) => return None,
+			ChannelMsg::ChannelOpenTry(msg) => {
+				let connection_id = msg.channel.connection_hops().first()?;
+				return ctx.connection_end(connection_id).ok().map(|conn| conn.client_id().clone())
+			},
+			ChannelMsg::ChannelOpenAck(msg) => (msg.port_id.clone(), msg.channel_id),
+			ChannelMsg::ChannelOpenConfirm(msg) => (msg.port_id.clone(), msg.channel_id),
+			ChannelMsg::ChannelCloseConfirm(msg) => (msg.port_id.clone(), msg.channel_id),
+			ChannelMsg::ChannelUpgradeTry(msg) => (msg.port_id.clone(), msg.channel_id),
+			ChannelMsg::ChannelUpgradeAck(msg) => (msg.port_id.clone(), msg.channel_id),
+			ChannelMsg::ChannelUpgradeConfirm(msg) => (msg.port_id.clone(), msg.channel_id),
+			ChannelMsg::ChannelUpgradeOpen(msg) => (msg.port_id.clone(), msg.channel_id),
+			ChannelMsg::ChannelUpgradeTimeout(msg) => (msg.port_id.clone(), msg.channel_id),
+			ChannelMsg::ChannelUpgradeCancel(msg) => (msg.port_id.clone(), msg.channel_id),
+		},
+		Ics4PacketMsg(msg) => match msg {
+			PacketMsg::RecvPacket(msg) =>
+				(msg.packet.destination_port.clone(), msg.packet.destination_channel),
+			PacketMsg::AckPacket(msg) =>
+				(msg.packet.source_port.clone(), msg.packet.source_channel),
+			PacketMsg::ToPacket(msg) => (msg.packet.source_port.clone(), msg.packet.source_channel),
+			PacketMsg::ToClosePacket(msg) =>
+				(msg.packet.source_port.clone(), msg.packet.source_channel),
+		},
+	};
+
+	let channel_end = ctx.channel_end(&port_channel_id).ok()?;
+	let connection_id = channel_end.connection_hops().first()?;
+	ctx.connection_end(connection_id).ok().map(|conn| conn.client_id().clone())
+}
+
+/// Whether the client `client_id` opted in to [`ClientDef::update_state_on_proofs_verified`].
+/// Client identifiers are prefixed with their client type, so this needs no client state read.
+fn updates_state_on_proofs_verified<Ctx: ClientTypes>(client_id: &ClientId) -> bool {
+	client_id.as_str().rsplit_once('-').map_or(false, |(client_type, _)| {
+		<Ctx::ClientDef as ClientDef>::updates_state_on_proofs_verified(client_type)
+	})
+}
+
+/// Lets the client that verified the proofs of a processed message advance its state, see
+/// [`ClientDef::update_state_on_proofs_verified`].
+fn update_client_on_proofs_verified<Ctx>(ctx: &mut Ctx, client_id: ClientId) -> Result<(), Error>
+where
+	Ctx: Ics26Context + ClientKeeper,
+{
+	let client_state = ctx.client_state(&client_id).map_err(Error::ics02_client)?;
+	let update = client_state
+		.client_def()
+		.update_state_on_proofs_verified(&*ctx, client_id.clone(), client_state)
+		.map_err(Error::ics02_client)?;
+
+	if let Some((client_state, consensus_state)) = update {
+		let result = update_client::Result {
+			client_id,
+			client_state,
+			consensus_state: Some(consensus_state),
+			processed_time: ctx.host_timestamp(),
+			processed_height: ctx.host_height(),
+		};
+		ctx.store_client_result(ClientResult::Update(result))
+			.map_err(Error::ics02_client)?;
+	}
+
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::prelude::*;
@@ -349,6 +459,7 @@ mod tests {
 				token: PrefixedCoin { denom, amount: msg_transfer_two.token.amount },
 				sender: msg_transfer_two.sender.clone(),
 				receiver: msg_transfer_two.receiver.clone(),
+				memo: msg_transfer_two.memo.clone(),
 			};
 			serde_json::to_vec(&data).expect("PacketData's infallible Serialize impl failed")
 		};
@@ -590,4 +701,16 @@ mod tests {
 			);
 		}
 	}
+
+	#[test]
+	fn proofs_verified_hook_is_opt_in() {
+		use super::updates_state_on_proofs_verified;
+		use crate::core::ics24_host::identifier::ClientId;
+
+		type Ctx = MockContext<MockClientTypes>;
+		for client_type in ["9999-mock", "07-tendermint"] {
+			let client_id = ClientId::new(client_type, 0).unwrap();
+			assert!(!updates_state_on_proofs_verified::<Ctx>(&client_id), "{client_type}");
+		}
+	}
 }
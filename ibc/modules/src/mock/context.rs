@@ -36,6 +36,10 @@ use crate::{
 			client_state::{ClientState, ClientType},
 			context::{ClientKeeper, ClientReader, ClientTypes},
 			error::Error as Ics02Error,
+			handler::{dispatch as ics02_dispatch, ClientResult},
+			msgs::{
+				create_client::MsgCreateAnyClient, update_client::MsgUpdateAnyClient, ClientMsg,
+			},
 		},
 		ics03_connection::{
 			connection::ConnectionEnd,
@@ -48,6 +52,7 @@ use crate::{
 			context::{ChannelKeeper, ChannelReader},
 			error::Error as Ics04Error,
 			packet::{Receipt, Sequence},
+			upgrade::{ErrorReceipt, Upgrade},
 		},
 		ics05_port::{
 			context::PortReader,
@@ -74,6 +79,7 @@ use crate::{
 		header::{AnyClientMessage, MockHeader},
 		host::{HostBlock, MockHostBlock},
 	},
+	test_utils::get_dummy_account_id,
 	timestamp::Timestamp,
 	Height,
 };
@@ -243,6 +249,36 @@ where
 		self
 	}
 
+	/// Associates an upgrade proposed by this chain to a channel of this context.
+	pub fn with_channel_upgrade(
+		self,
+		port_id: PortId,
+		chan_id: ChannelId,
+		upgrade: Upgrade,
+	) -> Self {
+		self.ibc_store
+			.lock()
+			.unwrap()
+			.channel_upgrades
+			.insert((port_id, chan_id), upgrade);
+		self
+	}
+
+	/// Associates an upgrade proposed by the counterparty to a channel of this context.
+	pub fn with_channel_counterparty_upgrade(
+		self,
+		port_id: PortId,
+		chan_id: ChannelId,
+		upgrade: Upgrade,
+	) -> Self {
+		self.ibc_store
+			.lock()
+			.unwrap()
+			.channel_counterparty_upgrades
+			.insert((port_id, chan_id), upgrade);
+		self
+	}
+
 	pub fn with_send_sequence(
 		self,
 		port_id: PortId,
@@ -362,6 +398,47 @@ where
 		Ok(())
 	}
 
+	/// Creates a client from `client_state` and `consensus_state` at the current host height and
+	/// returns its id.
+	pub fn create_client(
+		&mut self,
+		client_state: C::AnyClientState,
+		consensus_state: C::AnyConsensusState,
+	) -> Result<ClientId, Ics02Error> {
+		let msg =
+			MsgCreateAnyClient { client_state, consensus_state, signer: get_dummy_account_id() };
+		let output = ics02_dispatch(&*self, ClientMsg::CreateClient(msg))?;
+		let client_id = match &output.result {
+			ClientResult::Create(result) => result.client_id.clone(),
+			_ => unreachable!("creating a client always yields a create result"),
+		};
+		self.store_client_result(output.result)?;
+		Ok(client_id)
+	}
+
+	/// Advances the host chain and updates the client `client_id` with `client_message`.
+	pub fn update_client(
+		&mut self,
+		client_id: &ClientId,
+		client_message: C::AnyClientMessage,
+	) -> Result<(), Ics02Error> {
+		let msg = MsgUpdateAnyClient {
+			client_id: client_id.clone(),
+			client_message,
+			signer: get_dummy_account_id(),
+		};
+		self.advance_host_chain_height();
+		let output = ics02_dispatch(&*self, ClientMsg::UpdateClient(msg))?;
+		self.store_client_result(output.result)
+	}
+
+	/// Returns the client state of `client_id`, which must be a `T`.
+	pub fn client_state_as<T: Clone + 'static>(&self, client_id: &ClientId) -> T {
+		self.latest_client_states(client_id)
+			.downcast::<T>()
+			.expect("client state has an unexpected type")
+	}
+
 	/// Validates this context. Should be called after the context is mutated by a test.
 	pub fn validate(&self) -> Result<(), String> {
 		// Check that the number of entries is not higher than window size.
@@ -541,6 +618,15 @@ pub struct MockIbcStore<C: HostBlockType> {
 
 	// Used by unordered channel
 	pub packet_receipt: BTreeMap<(PortId, ChannelId, Sequence), Receipt>,
+
+	/// Upgrades proposed by this chain for channels that are being upgraded.
+	pub channel_upgrades: BTreeMap<(PortId, ChannelId), Upgrade>,
+
+	/// Upgrades proposed by the counterparty for channels that are being upgraded.
+	pub channel_counterparty_upgrades: BTreeMap<(PortId, ChannelId), Upgrade>,
+
+	/// Error receipts of the last aborted upgrade of each channel.
+	pub channel_upgrade_errors: BTreeMap<(PortId, ChannelId), ErrorReceipt>,
 }
 
 #[derive(Default)]
@@ -703,6 +789,61 @@ impl<C: HostBlockType> ChannelReader for MockContext<C> {
 		}
 	}
 
+	fn packet_commitment_sequences(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Vec<Sequence>, Ics04Error> {
+		Ok(self
+			.ibc_store
+			.lock()
+			.unwrap()
+			.packet_commitment
+			.keys()
+			.filter(|(port_id, channel_id, _)| {
+				port_id == &port_channel_id.0 && channel_id == &port_channel_id.1
+			})
+			.map(|(_, _, sequence)| *sequence)
+			.collect())
+	}
+
+	fn channel_upgrade(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Upgrade, Ics04Error> {
+		match self.ibc_store.lock().unwrap().channel_upgrades.get(port_channel_id) {
+			Some(upgrade) => Ok(upgrade.clone()),
+			None =>
+				Err(Ics04Error::upgrade_not_found(port_channel_id.0.clone(), port_channel_id.1)),
+		}
+	}
+
+	fn channel_counterparty_upgrade(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<Upgrade, Ics04Error> {
+		match self
+			.ibc_store
+			.lock()
+			.unwrap()
+			.channel_counterparty_upgrades
+			.get(port_channel_id)
+		{
+			Some(upgrade) => Ok(upgrade.clone()),
+			None =>
+				Err(Ics04Error::upgrade_not_found(port_channel_id.0.clone(), port_channel_id.1)),
+		}
+	}
+
+	fn channel_upgrade_error(
+		&self,
+		port_channel_id: &(PortId, ChannelId),
+	) -> Result<ErrorReceipt, Ics04Error> {
+		match self.ibc_store.lock().unwrap().channel_upgrade_errors.get(port_channel_id) {
+			Some(error_receipt) => Ok(error_receipt.clone()),
+			None => Err(Ics04Error::missing_error_receipt()),
+		}
+	}
+
 	fn hash(&self, value: Vec<u8>) -> Vec<u8> {
 		sha2::Sha256::digest(value).to_vec()
 	}
@@ -836,6 +977,61 @@ impl<C: HostBlockType> ChannelKeeper for MockContext<C> {
 		Ok(())
 	}
 
+	fn store_channel_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		upgrade: Upgrade,
+	) -> Result<(), Ics04Error> {
+		self.ibc_store.lock().unwrap().channel_upgrades.insert(port_channel_id, upgrade);
+		Ok(())
+	}
+
+	fn delete_channel_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+	) -> Result<(), Ics04Error> {
+		self.ibc_store.lock().unwrap().channel_upgrades.remove(&port_channel_id);
+		Ok(())
+	}
+
+	fn store_channel_counterparty_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		upgrade: Upgrade,
+	) -> Result<(), Ics04Error> {
+		self.ibc_store
+			.lock()
+			.unwrap()
+			.channel_counterparty_upgrades
+			.insert(port_channel_id, upgrade);
+		Ok(())
+	}
+
+	fn delete_channel_counterparty_upgrade(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+	) -> Result<(), Ics04Error> {
+		self.ibc_store
+			.lock()
+			.unwrap()
+			.channel_counterparty_upgrades
+			.remove(&port_channel_id);
+		Ok(())
+	}
+
+	fn store_channel_upgrade_error(
+		&mut self,
+		port_channel_id: (PortId, ChannelId),
+		error_receipt: ErrorReceipt,
+	) -> Result<(), Ics04Error> {
+		self.ibc_store
+			.lock()
+			.unwrap()
+			.channel_upgrade_errors
+			.insert(port_channel_id, error_receipt);
+		Ok(())
+	}
+
 	fn increase_channel_counter(&mut self) {
 		self.ibc_store.lock().unwrap().channel_ids_counter += 1;
 	}
@@ -38,6 +38,7 @@ use crate::{
 			context::{ChannelKeeper, ChannelReader},
 			error::Error,
 			packet::{Receipt, Sequence},
+			upgrade::{ErrorReceipt, Upgrade},
 			Version,
 		},
 		ics05_port::{context::PortReader, error::Error as PortError},
@@ -347,6 +348,44 @@ impl<C: HostBlockType> ChannelKeeper for DummyTransferModule<C> {
 		unimplemented!()
 	}
 
+	fn store_channel_upgrade(
+		&mut self,
+		_port_channel_id: (PortId, ChannelId),
+		_upgrade: Upgrade,
+	) -> Result<(), Error> {
+		unimplemented!()
+	}
+
+	fn delete_channel_upgrade(
+		&mut self,
+		_port_channel_id: (PortId, ChannelId),
+	) -> Result<(), Error> {
+		unimplemented!()
+	}
+
+	fn store_channel_counterparty_upgrade(
+		&mut self,
+		_port_channel_id: (PortId, ChannelId),
+		_upgrade: Upgrade,
+	) -> Result<(), Error> {
+		unimplemented!()
+	}
+
+	fn delete_channel_counterparty_upgrade(
+		&mut self,
+		_port_channel_id: (PortId, ChannelId),
+	) -> Result<(), Error> {
+		unimplemented!()
+	}
+
+	fn store_channel_upgrade_error(
+		&mut self,
+		_port_channel_id: (PortId, ChannelId),
+		_error_receipt: ErrorReceipt,
+	) -> Result<(), Error> {
+		unimplemented!()
+	}
+
 	fn increase_channel_counter(&mut self) {
 		unimplemented!()
 	}
@@ -568,6 +607,31 @@ impl<C: HostBlockType> ChannelReader for DummyTransferModule<C> {
 		unimplemented!()
 	}
 
+	fn packet_commitment_sequences(
+		&self,
+		_port_channel_id: &(PortId, ChannelId),
+	) -> Result<Vec<Sequence>, Error> {
+		unimplemented!()
+	}
+
+	fn channel_upgrade(&self, _port_channel_id: &(PortId, ChannelId)) -> Result<Upgrade, Error> {
+		unimplemented!()
+	}
+
+	fn channel_counterparty_upgrade(
+		&self,
+		_port_channel_id: &(PortId, ChannelId),
+	) -> Result<Upgrade, Error> {
+		unimplemented!()
+	}
+
+	fn channel_upgrade_error(
+		&self,
+		_port_channel_id: &(PortId, ChannelId),
+	) -> Result<ErrorReceipt, Error> {
+		unimplemented!()
+	}
+
 	fn hash(&self, value: Vec<u8>) -> Vec<u8> {
 		use sha2::Digest;
 
@@ -152,6 +152,11 @@ pub mod ibc {
 				include_proto!("ibc.applications.transfer.v2.rs");
 			}
 		}
+		pub mod fee {
+			pub mod v1 {
+				include_proto!("ibc.applications.fee.v1.rs");
+			}
+		}
 		pub mod interchain_accounts {
 			pub mod v1 {
 				include_proto!("ibc.applications.interchain_accounts.v1.rs");
@@ -200,6 +205,9 @@ pub mod ibc {
 			pub mod v1 {
 				include_proto!("ibc.lightclients.solomachine.v1.rs");
 			}
+			pub mod v2 {
+				include_proto!("ibc.lightclients.solomachine.v2.rs");
+			}
 		}
 		pub mod tendermint {
 			pub mod v1 {
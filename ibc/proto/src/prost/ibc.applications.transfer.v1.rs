@@ -27,6 +27,9 @@ pub struct MsgTransfer {
     /// The timeout is disabled when set to 0.
     #[prost(uint64, tag="7")]
     pub timeout_timestamp: u64,
+    /// optional memo
+    #[prost(string, tag="8")]
+    pub memo: ::prost::alloc::string::String,
 }
 /// MsgTransferResponse defines the Msg/Transfer response type.
 #[derive(::serde::Serialize, ::serde::Deserialize)]
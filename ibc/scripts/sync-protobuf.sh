@@ -8,7 +8,7 @@ set -eou pipefail
 # proto/src/prost/COSMOS_SDK_COMMIT and
 # proto/src/prost/IBC_GO_COMMIT. If you want to sync
 # the protobuf files to a newer version, modify the
-# relevant files with the new commit IDs.
+# relevant files with the new commit IDs or tags.
 
 # This script should be run from the root directory of ibc-rs
 
@@ -120,3 +120,27 @@ cargo +nightly run -- compile \
 
 rm -rf "$COSMOS_SDK_DIR"
 rm -rf "$IBC_GO_DIR"
+
+# Fail instead of silently dropping definitions the modules depend on, which happens
+# when IBC_GO_COMMIT points at an ibc-go release that doesn't define them.
+
+require_definition() {
+	if ! grep -q "$2" "../proto/src/prost/$1"
+	then
+		echo "$1 doesn't define \`$2\`, IBC_GO_COMMIT ($IBC_GO_COMMIT) is too old"
+		exit 1
+	fi
+}
+
+# ICS-20 memo
+require_definition ibc.applications.transfer.v1.rs "pub memo:"
+require_definition ibc.applications.transfer.v2.rs "pub memo:"
+
+# ICS-04 channel upgrades
+require_definition ibc.core.channel.v1.rs "pub struct Upgrade {"
+require_definition ibc.core.channel.v1.rs "pub struct UpgradeFields {"
+require_definition ibc.core.channel.v1.rs "pub struct ErrorReceipt {"
+require_definition ibc.core.channel.v1.rs "pub struct Timeout {"
+require_definition ibc.core.channel.v1.rs "pub upgrade_sequence:"
+require_definition ibc.core.channel.v1.rs "Flushing ="
+require_definition ibc.core.channel.v1.rs "pub struct MsgChannelUpgradeInit {"
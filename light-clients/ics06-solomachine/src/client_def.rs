@@ -0,0 +1,483 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	client_message::{sign_bytes, ClientMessage},
+	client_state::ClientState,
+	consensus_state::ConsensusState,
+	error::Error,
+	proto::{
+		ChannelStateData, ChannelUpgradeData, ChannelUpgradeErrorData, ClientStateData,
+		ConnectionStateData, ConsensusStateData, DataType, NextSequenceRecvData,
+		PacketAcknowledgementData, PacketCommitmentData, PacketReceiptAbsenceData,
+		TimestampedSignatureData, DATA_TYPE_CHANNEL_UPGRADE, DATA_TYPE_CHANNEL_UPGRADE_ERROR,
+	},
+	HostFunctions,
+};
+use alloc::{format, string::ToString, vec, vec::Vec};
+use core::{fmt::Display, marker::PhantomData};
+use ibc::{
+	core::{
+		ics02_client::{
+			client_consensus::ConsensusState as _,
+			client_def::{ClientDef, ConsensusUpdateResult},
+			client_state::ClientState as _,
+			error::Error as Ics02Error,
+		},
+		ics03_connection::connection::ConnectionEnd,
+		ics04_channel::{
+			channel::ChannelEnd,
+			commitment::{AcknowledgementCommitment, PacketCommitment},
+			packet::Sequence,
+			upgrade::{ErrorReceipt, Upgrade},
+		},
+		ics23_commitment::{
+			commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
+			merkle::apply_prefix,
+		},
+		ics24_host::{
+			identifier::{ChannelId, ClientId, ConnectionId, PortId},
+			path::{
+				AcksPath, ChannelEndsPath, ChannelUpgradeErrorsPath, ChannelUpgradesPath,
+				ClientConsensusStatePath, ClientStatePath, CommitmentsPath, ConnectionsPath,
+				ReceiptsPath, SeqRecvsPath,
+			},
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	Height,
+};
+use ibc_proto::google::protobuf::Any;
+use prost::Message;
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct SoloMachineClient<T>(PhantomData<T>);
+
+impl<H> ClientDef for SoloMachineClient<H>
+where
+	H: HostFunctions,
+{
+	type ClientMessage = ClientMessage;
+	type ClientState = ClientState<H>;
+	type ConsensusState = ConsensusState;
+
+	fn verify_client_message<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: ClientId,
+		client_state: Self::ClientState,
+		client_message: Self::ClientMessage,
+	) -> Result<(), Ics02Error> {
+		let consensus_state = &client_state.consensus_state;
+		match client_message {
+			ClientMessage::Header(header) => {
+				if header.sequence != client_state.sequence {
+					Err(Error::Custom(format!(
+						"Header sequence {} doesn't match the client sequence {}",
+						header.sequence, client_state.sequence
+					)))?
+				}
+				if header.timestamp.nanoseconds() < consensus_state.timestamp.nanoseconds() {
+					Err(Error::Custom(format!(
+						"Header timestamp {} is older than the consensus state timestamp {}",
+						header.timestamp.nanoseconds(),
+						consensus_state.timestamp.nanoseconds()
+					)))?
+				}
+
+				let sign_bytes = header.sign_bytes(consensus_state.diversifier.clone());
+				consensus_state
+					.public_key
+					.verify_signature::<H>(&sign_bytes, &header.signature)?;
+			},
+			ClientMessage::Misbehaviour(misbehaviour) => {
+				let (one, two) = (&misbehaviour.signature_one, &misbehaviour.signature_two);
+				if one.signature == two.signature {
+					Err(Error::Custom("Misbehaviour signatures cannot be equal".to_string()))?
+				}
+				if one.data_type == two.data_type && one.data == two.data {
+					Err(Error::Custom("Misbehaviour data signed cannot be equal".to_string()))?
+				}
+
+				// both signatures must be valid for the same sequence, which means the solo machine
+				// signed two different things where it should have signed only one.
+				for signature in [one, two] {
+					let sign_bytes = signature
+						.sign_bytes(misbehaviour.sequence, consensus_state.diversifier.clone());
+					consensus_state
+						.public_key
+						.verify_signature::<H>(&sign_bytes, &signature.signature)?;
+				}
+			},
+		}
+
+		Ok(())
+	}
+
+	fn update_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: ClientId,
+		mut client_state: Self::ClientState,
+		client_message: Self::ClientMessage,
+	) -> Result<(Self::ClientState, ConsensusUpdateResult<Ctx>), Ics02Error> {
+		let header = match client_message {
+			ClientMessage::Header(header) => header,
+			_ => unreachable!(
+				"02-client will check for misbehaviour before calling update_state; qed"
+			),
+		};
+
+		let consensus_state =
+			ConsensusState::new(header.new_public_key, header.new_diversifier, header.timestamp);
+		client_state.sequence += 1;
+		client_state.consensus_state = consensus_state.clone();
+
+		let wrapped = Ctx::AnyConsensusState::wrap(&consensus_state)
+			.expect("AnyConsenusState is type checked; qed");
+		Ok((client_state, ConsensusUpdateResult::Single(wrapped)))
+	}
+
+	fn update_state_on_misbehaviour(
+		&self,
+		mut client_state: Self::ClientState,
+		_client_message: Self::ClientMessage,
+	) -> Result<Self::ClientState, Ics02Error> {
+		client_state.is_frozen = true;
+		Ok(client_state)
+	}
+
+	fn check_for_misbehaviour<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: ClientId,
+		_client_state: Self::ClientState,
+		client_message: Self::ClientMessage,
+	) -> Result<bool, Ics02Error> {
+		Ok(matches!(client_message, ClientMessage::Misbehaviour(_)))
+	}
+
+	fn verify_upgrade_and_update_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: ClientId,
+		_old_client_state: &Self::ClientState,
+		_upgrade_client_state: &Self::ClientState,
+		_upgrade_consensus_state: &Self::ConsensusState,
+		_proof_upgrade_client: Vec<u8>,
+		_proof_upgrade_consensus_state: Vec<u8>,
+	) -> Result<(Self::ClientState, ConsensusUpdateResult<Ctx>), Ics02Error> {
+		Err(Error::Custom(
+			"Solo machine clients can't be upgraded, submit a header to rotate the public key"
+				.to_string(),
+		)
+		.into())
+	}
+
+	fn updates_state_on_proofs_verified(client_type: &str) -> bool {
+		client_type == ClientState::<H>::client_type()
+	}
+
+	/// Every proof is a signature at the current sequence, which would stay valid until the next
+	/// header. The sequence is bumped once the proofs of a message were accepted so that they
+	/// can't be replayed, the consensus state carries over to the new sequence.
+	///
+	/// **This is not ibc-go compatible.** ICS-06 and ibc-go bump the sequence after every verified
+	/// proof, while this client bumps it once per message: all proofs of a message must be signed
+	/// at the same sequence. Messages carrying several proofs, like `ConnOpenTry`, `ConnOpenAck`
+	/// or `TimeoutOnClose`, signed by an ibc-go solo machine at consecutive sequences are rejected.
+	fn update_state_on_proofs_verified<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: ClientId,
+		mut client_state: Self::ClientState,
+	) -> Result<Option<(Self::ClientState, ConsensusUpdateResult<Ctx>)>, Ics02Error> {
+		client_state.sequence += 1;
+
+		let wrapped = Ctx::AnyConsensusState::wrap(&client_state.consensus_state)
+			.expect("AnyConsenusState is type checked; qed");
+		Ok(Some((client_state, ConsensusUpdateResult::Single(wrapped))))
+	}
+
+	fn verify_client_consensus_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		client_id: &ClientId,
+		consensus_height: Height,
+		expected_consensus_state: &Ctx::AnyConsensusState,
+	) -> Result<(), Ics02Error> {
+		let path = ClientConsensusStatePath {
+			client_id: client_id.clone(),
+			epoch: consensus_height.revision_number,
+			height: consensus_height.revision_height,
+		};
+		let data = ConsensusStateData {
+			path: path_bytes(prefix, path),
+			consensus_state: Some(
+				Any::decode(&*expected_consensus_state.encode_to_vec()).map_err(Error::from)?,
+			),
+		};
+		verify_signature(client_state, height, proof, DataType::ConsensusState as i32, data)?;
+		Ok(())
+	}
+
+	fn verify_connection_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		connection_id: &ConnectionId,
+		expected_connection_end: &ConnectionEnd,
+	) -> Result<(), Ics02Error> {
+		let path = ConnectionsPath(connection_id.clone());
+		let data = ConnectionStateData {
+			path: path_bytes(prefix, path),
+			connection: Some(expected_connection_end.clone().into()),
+		};
+		verify_signature(client_state, height, proof, DataType::ConnectionState as i32, data)?;
+		Ok(())
+	}
+
+	fn verify_channel_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_channel_end: &ChannelEnd,
+	) -> Result<(), Ics02Error> {
+		let path = ChannelEndsPath(port_id.clone(), *channel_id);
+		let data = ChannelStateData {
+			path: path_bytes(prefix, path),
+			channel: Some(expected_channel_end.clone().into()),
+		};
+		verify_signature(client_state, height, proof, DataType::ChannelState as i32, data)?;
+		Ok(())
+	}
+
+	fn verify_channel_upgrade<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_upgrade: &Upgrade,
+	) -> Result<(), Ics02Error> {
+		let path = ChannelUpgradesPath(port_id.clone(), *channel_id);
+		let data = ChannelUpgradeData {
+			path: path_bytes(prefix, path),
+			upgrade: Some(expected_upgrade.clone().into()),
+		};
+		verify_signature(client_state, height, proof, DATA_TYPE_CHANNEL_UPGRADE, data)?;
+		Ok(())
+	}
+
+	fn verify_channel_upgrade_error<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_error_receipt: &ErrorReceipt,
+	) -> Result<(), Ics02Error> {
+		let path = ChannelUpgradeErrorsPath(port_id.clone(), *channel_id);
+		let data = ChannelUpgradeErrorData {
+			path: path_bytes(prefix, path),
+			error_receipt: Some(expected_error_receipt.clone().into()),
+		};
+		verify_signature(client_state, height, proof, DATA_TYPE_CHANNEL_UPGRADE_ERROR, data)?;
+		Ok(())
+	}
+
+	fn verify_client_full_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		client_id: &ClientId,
+		expected_client_state: &Ctx::AnyClientState,
+	) -> Result<(), Ics02Error> {
+		let path = ClientStatePath(client_id.clone());
+		let data = ClientStateData {
+			path: path_bytes(prefix, path),
+			client_state: Some(
+				Any::decode(&*expected_client_state.encode_to_vec()).map_err(Error::from)?,
+			),
+		};
+		verify_signature(client_state, height, proof, DataType::ClientState as i32, data)?;
+		Ok(())
+	}
+
+	fn verify_packet_data<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		commitment: PacketCommitment,
+	) -> Result<(), Ics02Error> {
+		let path = CommitmentsPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		let data = PacketCommitmentData {
+			path: path_bytes(connection_end.counterparty().prefix(), path),
+			commitment: commitment.into_vec(),
+		};
+		verify_signature(client_state, height, proof, DataType::PacketCommitment as i32, data)?;
+		Ok(())
+	}
+
+	fn verify_packet_acknowledgement<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		ack: AcknowledgementCommitment,
+	) -> Result<(), Ics02Error> {
+		let path = AcksPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		let data = PacketAcknowledgementData {
+			path: path_bytes(connection_end.counterparty().prefix(), path),
+			acknowledgement: ack.into_vec(),
+		};
+		verify_signature(
+			client_state,
+			height,
+			proof,
+			DataType::PacketAcknowledgement as i32,
+			data,
+		)?;
+		Ok(())
+	}
+
+	fn verify_next_sequence_recv<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+	) -> Result<(), Ics02Error> {
+		let path = SeqRecvsPath(port_id.clone(), *channel_id);
+		let data = NextSequenceRecvData {
+			path: path_bytes(connection_end.counterparty().prefix(), path),
+			next_seq_recv: sequence.into(),
+		};
+		verify_signature(client_state, height, proof, DataType::NextSequenceRecv as i32, data)?;
+		Ok(())
+	}
+
+	fn verify_packet_receipt_absence<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		_root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+	) -> Result<(), Ics02Error> {
+		let path = ReceiptsPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		let data = PacketReceiptAbsenceData {
+			path: path_bytes(connection_end.counterparty().prefix(), path),
+		};
+		verify_signature(client_state, height, proof, DataType::PacketReceiptAbsence as i32, data)?;
+		Ok(())
+	}
+}
+
+/// Returns the encoded merkle path of `path` under `prefix`, which the solo machine signs along
+/// with the value stored at it.
+pub fn path_bytes(prefix: &CommitmentPrefix, path: impl Display) -> Vec<u8> {
+	apply_prefix(prefix, vec![path.to_string()]).encode_to_vec()
+}
+
+/// Verify that `proof`, an encoded [`TimestampedSignatureData`], is a signature of the solo
+/// machine over `data` at the current sequence. Solo machines don't commit to their state, so
+/// the signature takes the place of a membership proof.
+fn verify_signature<H: HostFunctions>(
+	client_state: &ClientState<H>,
+	height: Height,
+	proof: &CommitmentProofBytes,
+	data_type: i32,
+	data: impl Message,
+) -> Result<(), Error> {
+	client_state.verify_height(height)?;
+	let proof = TimestampedSignatureData::decode(proof.as_bytes())?;
+	let consensus_state = &client_state.consensus_state;
+	if proof.timestamp < consensus_state.timestamp.nanoseconds() {
+		Err(Error::Custom(format!(
+			"Proof timestamp {} is older than the consensus state timestamp {}",
+			proof.timestamp,
+			consensus_state.timestamp.nanoseconds()
+		)))?
+	}
+
+	let sign_bytes = sign_bytes(
+		client_state.sequence,
+		proof.timestamp,
+		consensus_state.diversifier.clone(),
+		data_type,
+		data.encode_to_vec(),
+	);
+	consensus_state
+		.public_key
+		.verify_signature::<H>(&sign_bytes, &proof.signature_data)
+}
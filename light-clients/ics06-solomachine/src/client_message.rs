@@ -0,0 +1,264 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	error::Error,
+	proto::{
+		DataType, Header as RawHeader, HeaderData, Misbehaviour as RawMisbehaviour, SignBytes,
+		SignatureAndData as RawSignatureAndData,
+	},
+	public_key::PublicKey,
+};
+use alloc::{
+	format,
+	string::{String, ToString},
+	vec::Vec,
+};
+use ibc::{
+	core::{ics02_client, ics24_host::identifier::ClientId},
+	timestamp::Timestamp,
+};
+use ibc_proto::google::protobuf::Any;
+use prost::Message;
+use tendermint_proto::Protobuf;
+
+/// Protobuf type url for the solo machine Header
+pub const SOLOMACHINE_HEADER_TYPE_URL: &str = "/ibc.lightclients.solomachine.v2.Header";
+/// Protobuf type url for the solo machine Misbehaviour
+pub const SOLOMACHINE_MISBEHAVIOUR_TYPE_URL: &str = "/ibc.lightclients.solomachine.v2.Misbehaviour";
+/// Protobuf type url for the solo machine ClientMessage
+pub const SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL: &str =
+	"/ibc.lightclients.solomachine.v2.ClientMessage";
+
+/// Returns the bytes a solo machine signs for `data` at `sequence`.
+pub fn sign_bytes(
+	sequence: u64,
+	timestamp: u64,
+	diversifier: String,
+	data_type: i32,
+	data: Vec<u8>,
+) -> Vec<u8> {
+	SignBytes { sequence, timestamp, diversifier, data_type, data }.encode_to_vec()
+}
+
+/// Header of a solo machine, which bumps the sequence of its client and may rotate its public
+/// key and diversifier.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+	/// Sequence the header is signed at, the current sequence of the client
+	pub sequence: u64,
+	/// Time of the header
+	pub timestamp: Timestamp,
+	/// Signature of the current public key over the header
+	pub signature: Vec<u8>,
+	/// Public key used from the next sequence
+	pub new_public_key: PublicKey,
+	/// Diversifier used from the next sequence
+	pub new_diversifier: String,
+}
+
+impl Header {
+	/// Returns the bytes the header is signed over, `diversifier` is the current one.
+	pub fn sign_bytes(&self, diversifier: String) -> Vec<u8> {
+		let data = HeaderData {
+			new_pub_key: Some(self.new_public_key.clone().into()),
+			new_diversifier: self.new_diversifier.clone(),
+		};
+		sign_bytes(
+			self.sequence,
+			self.timestamp.nanoseconds(),
+			diversifier,
+			DataType::Header as i32,
+			data.encode_to_vec(),
+		)
+	}
+}
+
+/// A signature of the solo machine over some data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureAndData {
+	pub signature: Vec<u8>,
+	pub data_type: i32,
+	pub data: Vec<u8>,
+	pub timestamp: u64,
+}
+
+impl SignatureAndData {
+	/// Returns the bytes the data is signed over.
+	pub fn sign_bytes(&self, sequence: u64, diversifier: String) -> Vec<u8> {
+		sign_bytes(sequence, self.timestamp, diversifier, self.data_type, self.data.clone())
+	}
+}
+
+/// Misbehaviour of a solo machine: two different signatures at the same sequence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Misbehaviour {
+	pub client_id: ClientId,
+	pub sequence: u64,
+	pub signature_one: SignatureAndData,
+	pub signature_two: SignatureAndData,
+}
+
+/// [`ClientMessage`] for Ics06-SoloMachine
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClientMessage {
+	/// This is the variant for header updates
+	Header(Header),
+	/// This is for submitting misbehaviors.
+	Misbehaviour(Misbehaviour),
+}
+
+impl ics02_client::client_message::ClientMessage for ClientMessage {
+	fn encode_to_vec(&self) -> Vec<u8> {
+		self.encode_vec()
+	}
+}
+
+impl Protobuf<Any> for ClientMessage {}
+
+impl TryFrom<Any> for ClientMessage {
+	type Error = Error;
+
+	fn try_from(any: Any) -> Result<Self, Self::Error> {
+		let msg = match &*any.type_url {
+			SOLOMACHINE_HEADER_TYPE_URL => Self::Header(Header::decode_vec(&*any.value)?),
+			SOLOMACHINE_MISBEHAVIOUR_TYPE_URL =>
+				Self::Misbehaviour(Misbehaviour::decode_vec(&*any.value)?),
+			_ => Err(Error::Custom(format!("Unknown type: {}", any.type_url)))?,
+		};
+
+		Ok(msg)
+	}
+}
+
+impl From<ClientMessage> for Any {
+	fn from(msg: ClientMessage) -> Self {
+		match msg {
+			ClientMessage::Header(header) => Any {
+				value: header.encode_vec(),
+				type_url: SOLOMACHINE_HEADER_TYPE_URL.to_string(),
+			},
+			ClientMessage::Misbehaviour(misbehaviour) => Any {
+				value: misbehaviour.encode_vec(),
+				type_url: SOLOMACHINE_MISBEHAVIOUR_TYPE_URL.to_string(),
+			},
+		}
+	}
+}
+
+impl Protobuf<RawHeader> for Header {}
+
+impl TryFrom<RawHeader> for Header {
+	type Error = Error;
+
+	fn try_from(raw: RawHeader) -> Result<Self, Self::Error> {
+		if raw.timestamp == 0 {
+			Err(Error::Custom("Invalid header: timestamp cannot be zero".to_string()))?
+		}
+		if raw.signature.is_empty() {
+			Err(Error::Custom("Invalid header: signature cannot be empty".to_string()))?
+		}
+		let new_public_key = raw
+			.new_public_key
+			.ok_or_else(|| Error::Custom("Invalid header: missing new public key".to_string()))?
+			.try_into()?;
+
+		Ok(Self {
+			sequence: raw.sequence,
+			timestamp: Timestamp::from_nanoseconds(raw.timestamp)?,
+			signature: raw.signature,
+			new_public_key,
+			new_diversifier: raw.new_diversifier,
+		})
+	}
+}
+
+impl From<Header> for RawHeader {
+	fn from(header: Header) -> Self {
+		RawHeader {
+			sequence: header.sequence,
+			timestamp: header.timestamp.nanoseconds(),
+			signature: header.signature,
+			new_public_key: Some(header.new_public_key.into()),
+			new_diversifier: header.new_diversifier,
+		}
+	}
+}
+
+impl TryFrom<RawSignatureAndData> for SignatureAndData {
+	type Error = Error;
+
+	fn try_from(raw: RawSignatureAndData) -> Result<Self, Self::Error> {
+		if raw.signature.is_empty() {
+			Err(Error::Custom("Invalid misbehaviour: signature cannot be empty".to_string()))?
+		}
+		if raw.data.is_empty() {
+			Err(Error::Custom("Invalid misbehaviour: data cannot be empty".to_string()))?
+		}
+
+		Ok(Self {
+			signature: raw.signature,
+			data_type: raw.data_type,
+			data: raw.data,
+			timestamp: raw.timestamp,
+		})
+	}
+}
+
+impl From<SignatureAndData> for RawSignatureAndData {
+	fn from(value: SignatureAndData) -> Self {
+		RawSignatureAndData {
+			signature: value.signature,
+			data_type: value.data_type,
+			data: value.data,
+			timestamp: value.timestamp,
+		}
+	}
+}
+
+impl Protobuf<RawMisbehaviour> for Misbehaviour {}
+
+impl TryFrom<RawMisbehaviour> for Misbehaviour {
+	type Error = Error;
+
+	fn try_from(raw: RawMisbehaviour) -> Result<Self, Self::Error> {
+		let signature_one = raw
+			.signature_one
+			.ok_or_else(|| Error::Custom("Invalid misbehaviour: missing signature one".into()))?
+			.try_into()?;
+		let signature_two = raw
+			.signature_two
+			.ok_or_else(|| Error::Custom("Invalid misbehaviour: missing signature two".into()))?
+			.try_into()?;
+
+		Ok(Self {
+			client_id: raw.client_id.parse()?,
+			sequence: raw.sequence,
+			signature_one,
+			signature_two,
+		})
+	}
+}
+
+impl From<Misbehaviour> for RawMisbehaviour {
+	fn from(value: Misbehaviour) -> Self {
+		RawMisbehaviour {
+			client_id: value.client_id.to_string(),
+			sequence: value.sequence,
+			signature_one: Some(value.signature_one.into()),
+			signature_two: Some(value.signature_two.into()),
+		}
+	}
+}
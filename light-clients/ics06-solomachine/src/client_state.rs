@@ -0,0 +1,179 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	client_def::SoloMachineClient, consensus_state::ConsensusState, error::Error,
+	proto::ClientState as RawClientState, HostFunctions,
+};
+use alloc::{format, string::ToString, vec::Vec};
+use core::{marker::PhantomData, time::Duration};
+use ibc::{
+	core::{ics02_client::client_state::ClientType, ics24_host::identifier::ChainId},
+	Height,
+};
+use tendermint_proto::Protobuf;
+
+/// Protobuf type url for the solo machine ClientState
+pub const SOLOMACHINE_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.solomachine.v2.ClientState";
+
+#[derive(PartialEq, Clone, Debug, Eq)]
+pub struct ClientState<H> {
+	/// Sequence the next header and proofs are signed at, also the latest height of the client.
+	/// Bumped once per message rather than per proof, unlike ibc-go, see the crate docs.
+	pub sequence: u64,
+	/// Whether the client was frozen due to a misbehaviour
+	pub is_frozen: bool,
+	/// Current consensus state of the solo machine
+	pub consensus_state: ConsensusState,
+	/// Whether a governance proposal may update the client after it's frozen
+	pub allow_update_after_proposal: bool,
+	/// phantom type.
+	pub _phantom: PhantomData<H>,
+}
+
+impl<H: Clone> Protobuf<RawClientState> for ClientState<H> {}
+
+impl<H> ClientState<H> {
+	pub fn new(sequence: u64, consensus_state: ConsensusState) -> Self {
+		Self {
+			sequence,
+			is_frozen: false,
+			consensus_state,
+			allow_update_after_proposal: false,
+			_phantom: PhantomData,
+		}
+	}
+
+	/// Verify that the client is unfrozen and `height` is its current sequence, the only height
+	/// a solo machine proof can be verified at.
+	pub fn verify_height(&self, height: Height) -> Result<(), Error> {
+		if self.is_frozen {
+			return Err(Error::Custom("Client is frozen".to_string()))
+		}
+
+		let latest_height = self.latest_height();
+		if height != latest_height {
+			return Err(Error::Custom(format!(
+				"Proofs must be signed at the current sequence, known height: {latest_height}, given height: {height}"
+			)))
+		}
+
+		Ok(())
+	}
+
+	pub fn latest_height(&self) -> Height {
+		Height::new(0, self.sequence)
+	}
+
+	/// Solo machines don't have a chain id, the diversifier identifies them instead.
+	pub fn chain_id(&self) -> ChainId {
+		ChainId::from_string(&self.consensus_state.diversifier)
+	}
+
+	pub fn client_type() -> ClientType {
+		"06-solomachine".to_string()
+	}
+
+	pub fn frozen_height(&self) -> Option<Height> {
+		self.is_frozen.then(|| self.latest_height())
+	}
+
+	pub fn with_frozen_height(self, h: Height) -> Result<Self, Error> {
+		if h == Height::zero() {
+			return Err(Error::Custom(
+				"ClientState frozen height must be greater than zero".to_string(),
+			))
+		}
+		Ok(Self { is_frozen: true, ..self })
+	}
+}
+
+impl<H> ibc::core::ics02_client::client_state::ClientState for ClientState<H>
+where
+	H: HostFunctions,
+{
+	type UpgradeOptions = ();
+	type ClientDef = SoloMachineClient<H>;
+
+	fn chain_id(&self) -> ChainId {
+		self.chain_id()
+	}
+
+	fn client_def(&self) -> Self::ClientDef {
+		SoloMachineClient::default()
+	}
+
+	fn client_type(&self) -> ClientType {
+		Self::client_type()
+	}
+
+	fn latest_height(&self) -> Height {
+		self.latest_height()
+	}
+
+	fn frozen_height(&self) -> Option<Height> {
+		self.frozen_height()
+	}
+
+	/// Solo machines have no upgrade path, see
+	/// [`SoloMachineClient::verify_upgrade_and_update_state`].
+	fn upgrade(self, _upgrade_height: Height, _upgrade_options: (), _chain_id: ChainId) -> Self {
+		self
+	}
+
+	/// Solo machines don't have a trusting period, their clients never expire.
+	fn expired(&self, _elapsed: Duration) -> bool {
+		false
+	}
+
+	fn encode_to_vec(&self) -> Vec<u8> {
+		self.encode_vec()
+	}
+}
+
+impl<H> TryFrom<RawClientState> for ClientState<H> {
+	type Error = Error;
+
+	fn try_from(raw: RawClientState) -> Result<Self, Self::Error> {
+		if raw.sequence == 0 {
+			Err(Error::Custom("Invalid client state: sequence cannot be zero".to_string()))?
+		}
+		let consensus_state = raw
+			.consensus_state
+			.ok_or_else(|| {
+				Error::Custom("Invalid client state: missing consensus state".to_string())
+			})?
+			.try_into()?;
+
+		Ok(Self {
+			sequence: raw.sequence,
+			is_frozen: raw.is_frozen,
+			consensus_state,
+			allow_update_after_proposal: raw.allow_update_after_proposal,
+			_phantom: PhantomData,
+		})
+	}
+}
+
+impl<H> From<ClientState<H>> for RawClientState {
+	fn from(client_state: ClientState<H>) -> Self {
+		RawClientState {
+			sequence: client_state.sequence,
+			is_frozen: client_state.is_frozen,
+			consensus_state: Some(client_state.consensus_state.into()),
+			allow_update_after_proposal: client_state.allow_update_after_proposal,
+		}
+	}
+}
@@ -0,0 +1,62 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::all)]
+
+//! ICS-06: Solo machine IBC light client protocol implementation.
+//!
+//! A solo machine is a standalone process, like a single signer or a multisig custodian, that
+//! proves its state by signing it. The client tracks the public key and diversifier of the
+//! machine, and a sequence which is bumped by every header the machine submits. The sequence is
+//! also bumped once a message whose proofs the client verified was processed, so all proofs of a
+//! message are signed at the same sequence and can't be replayed afterwards.
+//!
+//! # Compatibility
+//!
+//! **This client is not compatible with ibc-go solo machines.** ICS-06 and ibc-go bump the
+//! sequence after every verified proof, so a message carrying several proofs, like `ConnOpenTry`,
+//! `ConnOpenAck` or `TimeoutOnClose`, has them signed at consecutive sequences. This client bumps
+//! the sequence once per message and rejects every proof that isn't signed at the current
+//! sequence, so such messages only verify if the solo machine signs all their proofs at the same
+//! sequence. Messages carrying a single proof behave the same in both.
+
+extern crate alloc;
+
+use core::fmt::Debug;
+
+pub mod client_def;
+pub mod client_message;
+pub mod client_state;
+pub mod consensus_state;
+pub mod error;
+pub mod proto;
+pub mod public_key;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Host functions used to verify the signatures of a solo machine.
+pub trait HostFunctions: Debug + Clone + Send + Sync + Default + Eq {
+	/// Verify an ed25519 `signature` over `msg`.
+	fn ed25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool;
+
+	/// Verify a 64 byte secp256k1 `signature` over the sha256 hash of `msg`, as produced by
+	/// cosmos-sdk keys. `public_key` is the compressed public key.
+	fn secp256k1_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool;
+}
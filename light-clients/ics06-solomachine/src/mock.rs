@@ -0,0 +1,182 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(unreachable_code)]
+
+use crate::{
+	client_def::SoloMachineClient,
+	client_message::{ClientMessage, SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL},
+	client_state::{ClientState, SOLOMACHINE_CLIENT_STATE_TYPE_URL},
+	consensus_state::{ConsensusState, SOLOMACHINE_CONSENSUS_STATE_TYPE_URL},
+	HostFunctions,
+};
+use ibc::{
+	core::{
+		ics02_client,
+		ics02_client::{
+			client_consensus::ConsensusState as _, client_state::ClientState as _,
+			context::ClientTypes,
+		},
+	},
+	mock::{
+		client_def::MockClient,
+		client_state::{MockClientState, MockConsensusState},
+		context::HostBlockType,
+		header::MockClientMessage,
+		host::MockHostBlock,
+	},
+	prelude::*,
+};
+use ibc_derive::{ClientDef, ClientMessage, ClientState, ConsensusState, Protobuf};
+use ibc_proto::google::protobuf::Any;
+use serde::{Deserialize, Serialize};
+use sp_core::{ecdsa, ed25519, hashing::sha2_256, Pair};
+use tendermint_proto::Protobuf;
+
+pub const MOCK_CLIENT_STATE_TYPE_URL: &str = "/ibc.mock.ClientState";
+pub const MOCK_CLIENT_MESSAGE_TYPE_URL: &str = "/ibc.mock.ClientMessage";
+pub const MOCK_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.mock.ConsensusState";
+
+#[derive(Clone, Default, PartialEq, Debug, Eq)]
+pub struct HostFunctionsManager;
+
+impl HostFunctions for HostFunctionsManager {
+	fn ed25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		match (ed25519::Signature::from_slice(signature), ed25519::Public::try_from(public_key)) {
+			(Some(signature), Ok(public_key)) =>
+				ed25519::Pair::verify(&signature, msg, &public_key),
+			_ => false,
+		}
+	}
+
+	fn secp256k1_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		if signature.len() != 64 {
+			return false
+		}
+		let message = sha2_256(msg);
+		(0u8..2).any(|recovery_id| {
+			let mut bytes = [0u8; 65];
+			bytes[..64].copy_from_slice(signature);
+			bytes[64] = recovery_id;
+			ecdsa::Signature::from_raw(bytes)
+				.recover_prehashed(&message)
+				.map(|recovered| recovered.as_ref() == public_key)
+				.unwrap_or(false)
+		})
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, ClientDef)]
+pub enum AnyClient {
+	Mock(MockClient),
+	SoloMachine(SoloMachineClient<HostFunctionsManager>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AnyUpgradeOptions {
+	Mock(()),
+	SoloMachine(()),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ClientState, Protobuf)]
+#[serde(tag = "type")]
+pub enum AnyClientState {
+	#[ibc(proto_url = "MOCK_CLIENT_STATE_TYPE_URL")]
+	Mock(MockClientState),
+	#[serde(skip)]
+	#[ibc(proto_url = "SOLOMACHINE_CLIENT_STATE_TYPE_URL")]
+	SoloMachine(ClientState<HostFunctionsManager>),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ClientMessage)]
+#[allow(clippy::large_enum_variant)]
+pub enum AnyClientMessage {
+	#[ibc(proto_url = "MOCK_CLIENT_MESSAGE_TYPE_URL")]
+	Mock(MockClientMessage),
+	#[serde(skip)]
+	#[ibc(proto_url = "SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL")]
+	SoloMachine(ClientMessage),
+}
+
+impl Protobuf<Any> for AnyClientMessage {}
+
+impl TryFrom<Any> for AnyClientMessage {
+	type Error = ics02_client::error::Error;
+
+	fn try_from(value: Any) -> Result<Self, Self::Error> {
+		match value.type_url.as_str() {
+			MOCK_CLIENT_MESSAGE_TYPE_URL =>
+				Ok(Self::Mock(panic!("MockClientMessage doesn't implement Protobuf"))),
+			SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL => Ok(Self::SoloMachine(
+				ClientMessage::decode_vec(&value.value)
+					.map_err(ics02_client::error::Error::decode_raw_header)?,
+			)),
+			_ => Err(ics02_client::error::Error::unknown_consensus_state_type(value.type_url)),
+		}
+	}
+}
+
+impl From<AnyClientMessage> for Any {
+	fn from(client_msg: AnyClientMessage) -> Self {
+		match client_msg {
+			AnyClientMessage::Mock(_mock) => {
+				panic!("MockClientMessage doesn't implement Protobuf");
+			},
+			AnyClientMessage::SoloMachine(msg) => Any {
+				type_url: SOLOMACHINE_CLIENT_MESSAGE_TYPE_URL.to_string(),
+				value: msg.encode_vec(),
+			},
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, ConsensusState, Protobuf)]
+#[serde(tag = "type")]
+pub enum AnyConsensusState {
+	#[serde(skip)]
+	#[ibc(proto_url = "SOLOMACHINE_CONSENSUS_STATE_TYPE_URL")]
+	SoloMachine(ConsensusState),
+	#[ibc(proto_url = "MOCK_CONSENSUS_STATE_TYPE_URL")]
+	Mock(MockConsensusState),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct MockClientTypes;
+
+impl ClientTypes for MockClientTypes {
+	type AnyClientMessage = AnyClientMessage;
+	type AnyClientState = AnyClientState;
+	type AnyConsensusState = AnyConsensusState;
+	type ClientDef = AnyClient;
+}
+
+impl HostBlockType for MockClientTypes {
+	type HostBlock = MockHostBlock;
+}
+
+impl From<MockHostBlock> for AnyClientMessage {
+	fn from(block: MockHostBlock) -> Self {
+		let MockHostBlock::Mock(header) = block;
+		AnyClientMessage::Mock(MockClientMessage::Header(header))
+	}
+}
+
+impl From<MockHostBlock> for AnyConsensusState {
+	fn from(block: MockHostBlock) -> Self {
+		let MockHostBlock::Mock(header) = block;
+		AnyConsensusState::Mock(MockConsensusState::new(header))
+	}
+}
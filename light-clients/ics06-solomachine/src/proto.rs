@@ -0,0 +1,74 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protobuf types of the solo machine client. The client, consensus state and message types are
+//! the `ibc.lightclients.solomachine.v2` ones, the public key types mirror `cosmos.crypto`.
+
+use alloc::vec::Vec;
+use ibc_proto::{
+	google::protobuf::Any,
+	ibc::core::channel::v1::{ErrorReceipt as RawErrorReceipt, Upgrade as RawUpgrade},
+};
+
+pub use ibc_proto::ibc::lightclients::solomachine::v2::*;
+
+/// Protobuf type url for an ed25519 public key
+pub const ED25519_PUB_KEY_TYPE_URL: &str = "/cosmos.crypto.ed25519.PubKey";
+/// Protobuf type url for a secp256k1 public key
+pub const SECP256K1_PUB_KEY_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+/// Protobuf type url for a multisig public key
+pub const MULTISIG_PUB_KEY_TYPE_URL: &str = "/cosmos.crypto.multisig.LegacyAminoPubKey";
+
+/// Data type for channel upgrade verification, which the v2 [`DataType`] predates.
+pub const DATA_TYPE_CHANNEL_UPGRADE: i32 = 10;
+/// Data type for channel upgrade error receipt verification.
+pub const DATA_TYPE_CHANNEL_UPGRADE_ERROR: i32 = 11;
+
+/// Either a `cosmos.crypto.ed25519.PubKey` or a `cosmos.crypto.secp256k1.PubKey`, which are
+/// encoded the same way.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PubKey {
+	#[prost(bytes = "vec", tag = "1")]
+	pub key: Vec<u8>,
+}
+
+/// A `cosmos.crypto.multisig.LegacyAminoPubKey`, satisfied by `threshold` signatures of the
+/// `public_keys`.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LegacyAminoPubKey {
+	#[prost(uint32, tag = "1")]
+	pub threshold: u32,
+	#[prost(message, repeated, tag = "2")]
+	pub public_keys: Vec<Any>,
+}
+
+/// ChannelUpgradeData returns the SignBytes data for channel upgrade verification.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChannelUpgradeData {
+	#[prost(bytes = "vec", tag = "1")]
+	pub path: Vec<u8>,
+	#[prost(message, optional, tag = "2")]
+	pub upgrade: Option<RawUpgrade>,
+}
+
+/// ChannelUpgradeErrorData returns the SignBytes data for channel upgrade error receipt
+/// verification.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChannelUpgradeErrorData {
+	#[prost(bytes = "vec", tag = "1")]
+	pub path: Vec<u8>,
+	#[prost(message, optional, tag = "2")]
+	pub error_receipt: Option<RawErrorReceipt>,
+}
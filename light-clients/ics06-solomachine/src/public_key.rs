@@ -0,0 +1,207 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	error::Error,
+	proto::{
+		LegacyAminoPubKey, PubKey, ED25519_PUB_KEY_TYPE_URL, MULTISIG_PUB_KEY_TYPE_URL,
+		SECP256K1_PUB_KEY_TYPE_URL,
+	},
+	HostFunctions,
+};
+use alloc::{format, string::ToString, vec::Vec};
+use ibc_proto::{
+	cosmos::{
+		crypto::multisig::v1beta1::CompactBitArray,
+		tx::signing::v1beta1::signature_descriptor::{
+			data::{Multi, Single, Sum},
+			Data as SignatureData,
+		},
+	},
+	google::protobuf::Any,
+};
+use prost::Message;
+
+/// Public key of a solo machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublicKey {
+	/// A 32 byte ed25519 public key.
+	Ed25519(Vec<u8>),
+	/// A 33 byte compressed secp256k1 public key.
+	Secp256k1(Vec<u8>),
+	/// A multisig, satisfied by `threshold` signatures of the `public_keys`.
+	Multisig { threshold: u32, public_keys: Vec<PublicKey> },
+}
+
+impl PublicKey {
+	/// Verify `signature`, an encoded `cosmos.tx.signing.v1beta1.SignatureDescriptor.Data`,
+	/// over `sign_bytes`.
+	pub fn verify_signature<H: HostFunctions>(
+		&self,
+		sign_bytes: &[u8],
+		signature: &[u8],
+	) -> Result<(), Error> {
+		let signature = SignatureData::decode(signature)?;
+		self.verify::<H>(sign_bytes, signature)
+	}
+
+	fn verify<H: HostFunctions>(
+		&self,
+		sign_bytes: &[u8],
+		signature: SignatureData,
+	) -> Result<(), Error> {
+		let signature = signature
+			.sum
+			.ok_or_else(|| Error::Custom("Missing signature data".to_string()))?;
+		let valid = match (self, signature) {
+			(PublicKey::Ed25519(key), Sum::Single(Single { signature, .. })) =>
+				H::ed25519_verify(&signature, sign_bytes, key),
+			(PublicKey::Secp256k1(key), Sum::Single(Single { signature, .. })) =>
+				H::secp256k1_verify(&signature, sign_bytes, key),
+			(
+				PublicKey::Multisig { threshold, public_keys },
+				Sum::Multi(Multi { bitarray, signatures }),
+			) => {
+				let bitarray = bitarray
+					.ok_or_else(|| Error::Custom("Missing multisig bit array".to_string()))?;
+				if bit_array_size(&bitarray) != public_keys.len() {
+					Err(Error::Custom(format!(
+						"Multisig bit array size doesn't match the {} public keys",
+						public_keys.len()
+					)))?
+				}
+
+				let signers = public_keys
+					.iter()
+					.enumerate()
+					.filter(|(index, _)| bit_array_get(&bitarray, *index))
+					.map(|(_, key)| key)
+					.collect::<Vec<_>>();
+				if signers.len() != signatures.len() {
+					Err(Error::Custom(format!(
+						"Expected {} multisig signatures, found {}",
+						signers.len(),
+						signatures.len()
+					)))?
+				}
+				if signers.len() < *threshold as usize {
+					Err(Error::Custom(format!(
+						"Multisig requires {threshold} signatures, found {}",
+						signers.len()
+					)))?
+				}
+
+				for (key, signature) in signers.into_iter().zip(signatures) {
+					key.verify::<H>(sign_bytes, signature)?;
+				}
+				true
+			},
+			_ =>
+				Err(Error::Custom("Signature data doesn't match the public key type".to_string()))?,
+		};
+
+		if !valid {
+			Err(Error::Custom("Invalid solo machine signature".to_string()))?
+		}
+
+		Ok(())
+	}
+}
+
+/// Number of bits in a [`CompactBitArray`].
+fn bit_array_size(bitarray: &CompactBitArray) -> usize {
+	match (bitarray.elems.len(), bitarray.extra_bits_stored) {
+		(0, _) => 0,
+		(len, 0) => len * 8,
+		(len, extra) => (len - 1) * 8 + extra as usize,
+	}
+}
+
+/// Whether the bit at `index` is set, bits are stored most significant first.
+fn bit_array_get(bitarray: &CompactBitArray, index: usize) -> bool {
+	bitarray
+		.elems
+		.get(index >> 3)
+		.map(|elem| elem & (1 << (7 - (index % 8))) != 0)
+		.unwrap_or(false)
+}
+
+impl TryFrom<Any> for PublicKey {
+	type Error = Error;
+
+	fn try_from(any: Any) -> Result<Self, Self::Error> {
+		let public_key = match &*any.type_url {
+			ED25519_PUB_KEY_TYPE_URL => {
+				let key = PubKey::decode(&*any.value)?.key;
+				if key.len() != 32 {
+					Err(Error::Custom(format!("Invalid ed25519 public key length: {}", key.len())))?
+				}
+				PublicKey::Ed25519(key)
+			},
+			SECP256K1_PUB_KEY_TYPE_URL => {
+				let key = PubKey::decode(&*any.value)?.key;
+				if key.len() != 33 {
+					Err(Error::Custom(format!(
+						"Invalid secp256k1 public key length: {}",
+						key.len()
+					)))?
+				}
+				PublicKey::Secp256k1(key)
+			},
+			MULTISIG_PUB_KEY_TYPE_URL => {
+				let raw = LegacyAminoPubKey::decode(&*any.value)?;
+				if raw.threshold == 0 || raw.threshold as usize > raw.public_keys.len() {
+					Err(Error::Custom(format!(
+						"Invalid multisig threshold {} for {} public keys",
+						raw.threshold,
+						raw.public_keys.len()
+					)))?
+				}
+				let public_keys = raw
+					.public_keys
+					.into_iter()
+					.map(PublicKey::try_from)
+					.collect::<Result<_, _>>()?;
+				PublicKey::Multisig { threshold: raw.threshold, public_keys }
+			},
+			_ => Err(Error::Custom(format!("Unsupported public key type: {}", any.type_url)))?,
+		};
+
+		Ok(public_key)
+	}
+}
+
+impl From<PublicKey> for Any {
+	fn from(public_key: PublicKey) -> Self {
+		match public_key {
+			PublicKey::Ed25519(key) => Any {
+				type_url: ED25519_PUB_KEY_TYPE_URL.to_string(),
+				value: PubKey { key }.encode_to_vec(),
+			},
+			PublicKey::Secp256k1(key) => Any {
+				type_url: SECP256K1_PUB_KEY_TYPE_URL.to_string(),
+				value: PubKey { key }.encode_to_vec(),
+			},
+			PublicKey::Multisig { threshold, public_keys } => Any {
+				type_url: MULTISIG_PUB_KEY_TYPE_URL.to_string(),
+				value: LegacyAminoPubKey {
+					threshold,
+					public_keys: public_keys.into_iter().map(Into::into).collect(),
+				}
+				.encode_to_vec(),
+			},
+		}
+	}
+}
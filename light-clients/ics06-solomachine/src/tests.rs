@@ -0,0 +1,424 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	client_def::{path_bytes, SoloMachineClient},
+	client_message::{sign_bytes, ClientMessage, Header, Misbehaviour, SignatureAndData},
+	client_state::ClientState,
+	consensus_state::ConsensusState,
+	mock::{
+		AnyClient, AnyClientMessage, AnyClientState, AnyConsensusState, HostFunctionsManager,
+		MockClientTypes,
+	},
+	proto::{ConnectionStateData, DataType, TimestampedSignatureData},
+	public_key::PublicKey,
+};
+use ibc::{
+	core::{
+		ics02_client::{
+			client_consensus::ConsensusState as _, client_def::ClientDef,
+			client_state::ClientState as _, context::ClientReader,
+		},
+		ics03_connection::{
+			connection::{ConnectionEnd, Counterparty, State},
+			context::ConnectionReader,
+			msgs::{conn_open_confirm::MsgConnectionOpenConfirm, ConnectionMsg},
+			version::get_compatible_versions,
+		},
+		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes},
+		ics24_host::{
+			identifier::{ClientId, ConnectionId},
+			path::ConnectionsPath,
+		},
+		ics26_routing::{handler::dispatch as ics26_dispatch, msgs::Ics26Envelope},
+	},
+	mock::context::MockContext,
+	proofs::Proofs,
+	test_utils::get_dummy_account_id,
+	timestamp::Timestamp,
+	Height,
+};
+use ibc_proto::cosmos::{
+	crypto::multisig::v1beta1::CompactBitArray,
+	tx::signing::v1beta1::signature_descriptor::{
+		data::{Multi, Single, Sum},
+		Data as SignatureData,
+	},
+};
+use prost::Message;
+use sp_core::{ed25519, Pair};
+
+const DIVERSIFIER: &str = "solo-machine";
+
+fn pair(seed: u8) -> ed25519::Pair {
+	ed25519::Pair::from_seed(&[seed; 32])
+}
+
+fn public_key(pair: &ed25519::Pair) -> PublicKey {
+	PublicKey::Ed25519(pair.public().0.to_vec())
+}
+
+fn single_signature(pair: &ed25519::Pair, sign_bytes: &[u8]) -> SignatureData {
+	let signature = pair.sign(sign_bytes).0.to_vec();
+	SignatureData { sum: Some(Sum::Single(Single { mode: 0, signature })) }
+}
+
+fn sign(pair: &ed25519::Pair, sign_bytes: &[u8]) -> Vec<u8> {
+	single_signature(pair, sign_bytes).encode_to_vec()
+}
+
+fn timestamp(nanos: u64) -> Timestamp {
+	Timestamp::from_nanoseconds(nanos).unwrap()
+}
+
+fn header(
+	signer: &ed25519::Pair,
+	sequence: u64,
+	new_public_key: PublicKey,
+	diversifier: &str,
+) -> Header {
+	let mut header = Header {
+		sequence,
+		timestamp: timestamp(sequence * 1_000),
+		signature: vec![],
+		new_public_key,
+		new_diversifier: DIVERSIFIER.to_string(),
+	};
+	header.signature = sign(signer, &header.sign_bytes(diversifier.to_string()));
+	header
+}
+
+/// Creates a solo machine client at sequence 1 signing with `public_key`.
+fn create_client(public_key: PublicKey) -> (MockContext<MockClientTypes>, ClientId) {
+	let mut ctx = MockContext::<MockClientTypes>::default();
+	let consensus_state = ConsensusState::new(public_key, DIVERSIFIER.to_string(), timestamp(1));
+	let client_state = ClientState::<HostFunctionsManager>::new(1, consensus_state.clone());
+	let client_id = ctx
+		.create_client(
+			AnyClientState::SoloMachine(client_state),
+			AnyConsensusState::SoloMachine(consensus_state),
+		)
+		.unwrap();
+	(ctx, client_id)
+}
+
+#[test]
+fn test_update_solo_machine_client_rotates_public_key() {
+	let (old, new) = (pair(1), pair(2));
+	let (mut ctx, client_id) = create_client(public_key(&old));
+
+	ctx.update_client(
+		&client_id,
+		AnyClientMessage::SoloMachine(ClientMessage::Header(header(
+			&old,
+			1,
+			public_key(&new),
+			DIVERSIFIER,
+		))),
+	)
+	.unwrap();
+
+	let client_state = ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id);
+	assert_eq!(client_state.sequence, 2);
+	assert_eq!(client_state.latest_height(), Height::new(0, 2));
+	assert_eq!(client_state.consensus_state.public_key, public_key(&new));
+	assert!(ctx.consensus_state(&client_id, Height::new(0, 2)).is_ok());
+
+	// the old key can no longer sign headers
+	let res = ctx.update_client(
+		&client_id,
+		AnyClientMessage::SoloMachine(ClientMessage::Header(header(
+			&old,
+			2,
+			public_key(&old),
+			DIVERSIFIER,
+		))),
+	);
+	assert!(res.is_err());
+
+	// headers can't be replayed
+	let res = ctx.update_client(
+		&client_id,
+		AnyClientMessage::SoloMachine(ClientMessage::Header(header(
+			&old,
+			1,
+			public_key(&old),
+			DIVERSIFIER,
+		))),
+	);
+	assert!(res.is_err());
+}
+
+#[test]
+fn test_update_solo_machine_client_rejects_wrong_diversifier() {
+	let signer = pair(1);
+	let (mut ctx, client_id) = create_client(public_key(&signer));
+
+	let res = ctx.update_client(
+		&client_id,
+		AnyClientMessage::SoloMachine(ClientMessage::Header(header(
+			&signer,
+			1,
+			public_key(&signer),
+			"other-machine",
+		))),
+	);
+	assert!(res.is_err());
+	assert_eq!(ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id).sequence, 1);
+}
+
+#[test]
+fn test_update_solo_machine_client_with_multisig() {
+	let signers = [pair(1), pair(2), pair(3)];
+	let multisig =
+		PublicKey::Multisig { threshold: 2, public_keys: signers.iter().map(public_key).collect() };
+	let (mut ctx, client_id) = create_client(multisig.clone());
+
+	let multisig_header = |signed_by: &[usize]| {
+		let mut header = Header {
+			sequence: 1,
+			timestamp: timestamp(1_000),
+			signature: vec![],
+			new_public_key: multisig.clone(),
+			new_diversifier: DIVERSIFIER.to_string(),
+		};
+		let sign_bytes = header.sign_bytes(DIVERSIFIER.to_string());
+		let bits = signed_by.iter().fold(0u8, |bits, index| bits | (1 << (7 - index)));
+		header.signature = SignatureData {
+			sum: Some(Sum::Multi(Multi {
+				bitarray: Some(CompactBitArray { extra_bits_stored: 3, elems: vec![bits] }),
+				signatures: signed_by
+					.iter()
+					.map(|index| single_signature(&signers[*index], &sign_bytes))
+					.collect(),
+			})),
+		}
+		.encode_to_vec();
+		ClientMessage::Header(header)
+	};
+
+	// below the threshold
+	assert!(ctx
+		.update_client(&client_id, AnyClientMessage::SoloMachine(multisig_header(&[1])))
+		.is_err());
+
+	ctx.update_client(&client_id, AnyClientMessage::SoloMachine(multisig_header(&[0, 2])))
+		.unwrap();
+	assert_eq!(ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id).sequence, 2);
+}
+
+#[test]
+fn test_solo_machine_misbehaviour_freezes_client() {
+	let signer = pair(1);
+	let (mut ctx, client_id) = create_client(public_key(&signer));
+
+	let signature_and_data = |data: &[u8]| {
+		let sign_bytes = sign_bytes(
+			1,
+			1_000,
+			DIVERSIFIER.to_string(),
+			DataType::ConnectionState as i32,
+			data.to_vec(),
+		);
+		SignatureAndData {
+			signature: sign(&signer, &sign_bytes),
+			data_type: DataType::ConnectionState as i32,
+			data: data.to_vec(),
+			timestamp: 1_000,
+		}
+	};
+
+	// the same data signed twice isn't misbehaviour
+	let misbehaviour = Misbehaviour {
+		client_id: client_id.clone(),
+		sequence: 1,
+		signature_one: signature_and_data(b"connection"),
+		signature_two: signature_and_data(b"connection"),
+	};
+	assert!(ctx
+		.update_client(
+			&client_id,
+			AnyClientMessage::SoloMachine(ClientMessage::Misbehaviour(misbehaviour))
+		)
+		.is_err());
+
+	let misbehaviour = Misbehaviour {
+		client_id: client_id.clone(),
+		sequence: 1,
+		signature_one: signature_and_data(b"connection"),
+		signature_two: signature_and_data(b"another connection"),
+	};
+	ctx.update_client(
+		&client_id,
+		AnyClientMessage::SoloMachine(ClientMessage::Misbehaviour(misbehaviour)),
+	)
+	.unwrap();
+
+	let client_state = ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id);
+	assert!(client_state.is_frozen());
+
+	// frozen clients can't be updated
+	let res = ctx.update_client(
+		&client_id,
+		AnyClientMessage::SoloMachine(ClientMessage::Header(header(
+			&signer,
+			1,
+			public_key(&signer),
+			DIVERSIFIER,
+		))),
+	);
+	assert!(res.is_err());
+}
+
+#[test]
+fn test_verify_solo_machine_connection_state() {
+	let signer = pair(1);
+	let (ctx, client_id) = create_client(public_key(&signer));
+	let client_state = ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id);
+
+	let prefix = CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap();
+	let connection_id = ConnectionId::new(0);
+	let connection_end = ConnectionEnd::default();
+	let proof = |sequence: u64, connection_end: &ConnectionEnd| {
+		let data = ConnectionStateData {
+			path: path_bytes(&prefix, ConnectionsPath(connection_id.clone())),
+			connection: Some(connection_end.clone().into()),
+		};
+		let sign_bytes = sign_bytes(
+			sequence,
+			1_000,
+			DIVERSIFIER.to_string(),
+			DataType::ConnectionState as i32,
+			data.encode_to_vec(),
+		);
+		let proof = TimestampedSignatureData {
+			signature_data: sign(&signer, &sign_bytes),
+			timestamp: 1_000,
+		};
+		CommitmentProofBytes::try_from(proof.encode_to_vec()).unwrap()
+	};
+	let verify = |height: Height, proof: &CommitmentProofBytes| {
+		SoloMachineClient::<HostFunctionsManager>::default().verify_connection_state(
+			&ctx,
+			&client_id,
+			&client_state,
+			height,
+			&prefix,
+			proof,
+			client_state.consensus_state.root(),
+			&connection_id,
+			&connection_end,
+		)
+	};
+
+	verify(Height::new(0, 1), &proof(1, &connection_end)).unwrap();
+
+	// proofs are only valid at the current sequence
+	assert!(verify(Height::new(0, 2), &proof(2, &connection_end)).is_err());
+	assert!(verify(Height::new(0, 1), &proof(2, &connection_end)).is_err());
+
+	// the signature must be over the expected connection end
+	let mut other_connection_end = ConnectionEnd::default();
+	other_connection_end.set_state(State::Open);
+	assert!(verify(Height::new(0, 1), &proof(1, &other_connection_end)).is_err());
+}
+
+#[test]
+fn test_solo_machine_proofs_cant_be_replayed() {
+	let signer = pair(1);
+	let (ctx, client_id) = create_client(public_key(&signer));
+
+	let prefix = CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap();
+	let connection_id = ConnectionId::new(0);
+	let counterparty_connection_id = ConnectionId::new(1);
+	let counterparty_client_id = ClientId::new("07-tendermint", 0).unwrap();
+	let connection_end = ConnectionEnd::new(
+		State::TryOpen,
+		client_id.clone(),
+		Counterparty::new(
+			counterparty_client_id.clone(),
+			Some(counterparty_connection_id.clone()),
+			prefix.clone(),
+		),
+		get_compatible_versions(),
+		Default::default(),
+	);
+	let mut ctx = ctx.with_connection(connection_id.clone(), connection_end);
+
+	// the connection end the solo machine signs for MsgConnectionOpenConfirm
+	let expected_connection_end = ConnectionEnd::new(
+		State::Open,
+		counterparty_client_id,
+		Counterparty::new(client_id.clone(), Some(connection_id.clone()), ctx.commitment_prefix()),
+		get_compatible_versions(),
+		Default::default(),
+	);
+	let proof = |sequence: u64| {
+		let data = ConnectionStateData {
+			path: path_bytes(&prefix, ConnectionsPath(counterparty_connection_id.clone())),
+			connection: Some(expected_connection_end.clone().into()),
+		};
+		let sign_bytes = sign_bytes(
+			sequence,
+			1_000,
+			DIVERSIFIER.to_string(),
+			DataType::ConnectionState as i32,
+			data.encode_to_vec(),
+		);
+		let proof = TimestampedSignatureData {
+			signature_data: sign(&signer, &sign_bytes),
+			timestamp: 1_000,
+		};
+		CommitmentProofBytes::try_from(proof.encode_to_vec()).unwrap()
+	};
+
+	let msg = MsgConnectionOpenConfirm {
+		connection_id: connection_id.clone(),
+		proofs: Proofs::new(proof(1), None, None, None, Height::new(0, 1)).unwrap(),
+		signer: get_dummy_account_id(),
+	};
+	ics26_dispatch(&mut ctx, Ics26Envelope::Ics3Msg(ConnectionMsg::ConnectionOpenConfirm(msg)))
+		.unwrap();
+
+	// the accepted proof bumped the sequence, the consensus state carries over
+	let client_state = ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id);
+	assert_eq!(client_state.sequence, 2);
+	assert!(ctx.consensus_state(&client_id, Height::new(0, 2)).is_ok());
+
+	let verify = |height: Height, proof: &CommitmentProofBytes| {
+		SoloMachineClient::<HostFunctionsManager>::default().verify_connection_state(
+			&ctx,
+			&client_id,
+			&client_state,
+			height,
+			&prefix,
+			proof,
+			client_state.consensus_state.root(),
+			&counterparty_connection_id,
+			&expected_connection_end,
+		)
+	};
+
+	// the signature at the previous sequence is rejected at either height
+	assert!(verify(Height::new(0, 1), &proof(1)).is_err());
+	assert!(verify(Height::new(0, 2), &proof(1)).is_err());
+	verify(Height::new(0, 2), &proof(2)).unwrap();
+}
+
+#[test]
+fn test_only_solo_machine_clients_update_state_on_proofs_verified() {
+	assert!(AnyClient::updates_state_on_proofs_verified("06-solomachine"));
+	assert!(!AnyClient::updates_state_on_proofs_verified("07-tendermint"));
+}
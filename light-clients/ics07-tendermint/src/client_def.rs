@@ -26,6 +26,7 @@ use ibc::core::{
 		channel::ChannelEnd,
 		commitment::{AcknowledgementCommitment, PacketCommitment},
 		packet::Sequence,
+		upgrade::{ErrorReceipt, Upgrade},
 	},
 	ics23_commitment::{
 		commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
@@ -34,8 +35,9 @@ use ibc::core::{
 	ics24_host::{
 		identifier::{ChannelId, ClientId, ConnectionId, PortId},
 		path::{
-			AcksPath, ChannelEndsPath, ClientConsensusStatePath, ClientStatePath, CommitmentsPath,
-			ConnectionsPath, ReceiptsPath, SeqRecvsPath,
+			AcksPath, ChannelEndsPath, ChannelUpgradeErrorsPath, ChannelUpgradesPath,
+			ClientConsensusStatePath, ClientStatePath, CommitmentsPath, ConnectionsPath,
+			ReceiptsPath, SeqRecvsPath,
 		},
 		Path,
 	},
@@ -394,6 +396,46 @@ where
 		verify_membership::<H, _>(client_state, prefix, proof, root, path, value)
 	}
 
+	fn verify_channel_upgrade<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_upgrade: &Upgrade,
+	) -> Result<(), Ics02Error> {
+		client_state.verify_height(height)?;
+
+		let path = ChannelUpgradesPath(port_id.clone(), *channel_id);
+		let value = expected_upgrade.encode_vec();
+		verify_membership::<H, _>(client_state, prefix, proof, root, path, value)
+	}
+
+	fn verify_channel_upgrade_error<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_error_receipt: &ErrorReceipt,
+	) -> Result<(), Ics02Error> {
+		client_state.verify_height(height)?;
+
+		let path = ChannelUpgradeErrorsPath(port_id.clone(), *channel_id);
+		let value = expected_error_receipt.encode_vec();
+		verify_membership::<H, _>(client_state, prefix, proof, root, path, value)
+	}
+
 	fn verify_client_full_state<Ctx: ReaderContext>(
 		&self,
 		_ctx: &Ctx,
@@ -50,6 +50,17 @@ impl TryFrom<IbcEvent> for AbciEvent {
             IbcEvent::TimeoutPacket(event) => event.try_into().map_err(Error::channel)?,
             IbcEvent::TimeoutOnClosePacket(event) => event.try_into().map_err(Error::channel)?,
             IbcEvent::AppModule(event) => event.try_into()?,
+            IbcEvent::UpgradeInitChannel(ref attributes)
+            | IbcEvent::UpgradeTryChannel(ref attributes)
+            | IbcEvent::UpgradeAckChannel(ref attributes)
+            | IbcEvent::UpgradeConfirmChannel(ref attributes)
+            | IbcEvent::UpgradeOpenChannel(ref attributes)
+            | IbcEvent::UpgradeTimeoutChannel(ref attributes)
+            | IbcEvent::UpgradeCancelChannel(ref attributes)
+            | IbcEvent::UpgradeErrorChannel(ref attributes)
+            | IbcEvent::FlushCompleteChannel(ref attributes) => {
+                attributes.clone().into_abci_event(event.event_type())
+            }
             IbcEvent::NewBlock(_) | IbcEvent::Empty(_) | IbcEvent::ChainError(_) => {
                 return Err(Error::incorrect_event_type(event.to_string()))
             }
@@ -0,0 +1,129 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interface between the host and a wasm light client contract.
+//!
+//! A contract is a wasm module which exports its `memory` and two functions, it may only import
+//! the host functions listed in [`crate::executor`]:
+//!
+//! - `alloc(len: i32) -> i32`, returning a pointer to `len` bytes of memory the host writes the
+//!   SCALE encoded [`Request`] to.
+//! - `call(ptr: i32, len: i32) -> i64`, which handles the request at `ptr` and returns the pointer
+//!   to its SCALE encoded `Result<Response, String>` in the upper 32 bits and its length in the
+//!   lower 32 bits.
+//!
+//! Client states, consensus states and client messages are passed as the opaque bytes the
+//! contract defined them as.
+
+use alloc::{string::String, vec::Vec};
+use codec::{Decode, Encode};
+
+/// Height of a client, as passed to and from contracts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct Height {
+	pub revision_number: u64,
+	pub revision_height: u64,
+}
+
+impl From<ibc::Height> for Height {
+	fn from(height: ibc::Height) -> Self {
+		Self { revision_number: height.revision_number, revision_height: height.revision_height }
+	}
+}
+
+impl From<Height> for ibc::Height {
+	fn from(height: Height) -> Self {
+		ibc::Height::new(height.revision_number, height.revision_height)
+	}
+}
+
+/// A consensus state produced by a contract.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct ConsensusUpdate {
+	/// Height the consensus state is stored at
+	pub height: Height,
+	/// Consensus state of the contract
+	pub data: Vec<u8>,
+	/// Timestamp of the consensus state, in nanoseconds
+	pub timestamp: u64,
+}
+
+/// A request to a contract, mirroring the calls of
+/// [`ClientDef`](ibc::core::ics02_client::client_def::ClientDef) that depend on the client
+/// type.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum Request {
+	/// Verify a header or misbehaviour, the contract should respond with [`Response::Ok`].
+	VerifyClientMessage {
+		client_state: Vec<u8>,
+		/// Consensus state at the latest height of the client
+		consensus_state: Vec<u8>,
+		client_message: Vec<u8>,
+		/// Timestamp of the host, in nanoseconds
+		host_timestamp: u64,
+	},
+	/// Check a verified client message for misbehaviour, the contract should respond with
+	/// [`Response::Misbehaviour`].
+	CheckForMisbehaviour {
+		client_state: Vec<u8>,
+		consensus_state: Vec<u8>,
+		client_message: Vec<u8>,
+	},
+	/// Apply a verified header, the contract should respond with [`Response::UpdateState`].
+	UpdateState { client_state: Vec<u8>, consensus_state: Vec<u8>, client_message: Vec<u8> },
+	/// Freeze the client for a verified misbehaviour, the contract should respond with
+	/// [`Response::Frozen`].
+	UpdateStateOnMisbehaviour { client_state: Vec<u8>, client_message: Vec<u8> },
+	/// Verify that `value` is stored at `path` under `prefix`, the contract should respond
+	/// with [`Response::Ok`].
+	VerifyMembership {
+		client_state: Vec<u8>,
+		/// Consensus state at `height`
+		consensus_state: Vec<u8>,
+		height: Height,
+		proof: Vec<u8>,
+		prefix: Vec<u8>,
+		path: String,
+		value: Vec<u8>,
+	},
+	/// Verify that nothing is stored at `path` under `prefix`, the contract should respond
+	/// with [`Response::Ok`].
+	VerifyNonMembership {
+		client_state: Vec<u8>,
+		/// Consensus state at `height`
+		consensus_state: Vec<u8>,
+		height: Height,
+		proof: Vec<u8>,
+		prefix: Vec<u8>,
+		path: String,
+	},
+}
+
+/// A response of a contract to a [`Request`].
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub enum Response {
+	/// The request was verified.
+	Ok,
+	/// Whether the client message is a misbehaviour.
+	Misbehaviour(bool),
+	/// The client state and consensus states after applying a header.
+	UpdateState {
+		client_state: Vec<u8>,
+		latest_height: Height,
+		consensus_states: Vec<ConsensusUpdate>,
+	},
+	/// The client state after freezing the client at `frozen_height`.
+	Frozen { client_state: Vec<u8>, frozen_height: Height },
+}
@@ -0,0 +1,473 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	abi::{Request, Response},
+	client_message::ClientMessage,
+	client_state::ClientState,
+	consensus_state::ConsensusState,
+	error::Error,
+	executor::execute,
+	HostFunctions,
+};
+use alloc::{format, string::ToString, vec::Vec};
+use core::{fmt::Display, marker::PhantomData};
+use ibc::{
+	core::{
+		ics02_client::{
+			client_consensus::ConsensusState as _,
+			client_def::{ClientDef, ConsensusUpdateResult},
+			client_state::ClientState as _,
+			error::Error as Ics02Error,
+		},
+		ics03_connection::connection::ConnectionEnd,
+		ics04_channel::{
+			channel::ChannelEnd,
+			commitment::{AcknowledgementCommitment, PacketCommitment},
+			packet::Sequence,
+			upgrade::{ErrorReceipt, Upgrade},
+		},
+		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
+		ics24_host::{
+			identifier::{ChannelId, ClientId, ConnectionId, PortId},
+			path::{
+				AcksPath, ChannelEndsPath, ChannelUpgradeErrorsPath, ChannelUpgradesPath,
+				ClientConsensusStatePath, ClientStatePath, CommitmentsPath, ConnectionsPath,
+				ReceiptsPath, SeqRecvsPath,
+			},
+		},
+		ics26_routing::context::ReaderContext,
+	},
+	timestamp::Timestamp,
+	Height,
+};
+use light_client_common::verify_delay_passed;
+use prost::Message;
+use tendermint_proto::Protobuf;
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct WasmClient<T>(PhantomData<T>);
+
+impl<H> ClientDef for WasmClient<H>
+where
+	H: HostFunctions,
+{
+	type ClientMessage = ClientMessage;
+	type ClientState = ClientState<H>;
+	type ConsensusState = ConsensusState;
+
+	fn verify_client_message<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		client_id: ClientId,
+		client_state: Self::ClientState,
+		client_message: Self::ClientMessage,
+	) -> Result<(), Ics02Error> {
+		let consensus_state = latest_consensus_state(ctx, &client_id, &client_state)?;
+		let request = Request::VerifyClientMessage {
+			client_state: client_state.data,
+			consensus_state: consensus_state.data,
+			client_message: client_message.data,
+			host_timestamp: ctx.host_timestamp().nanoseconds(),
+		};
+		match execute::<H>(&client_state.code_hash, request)? {
+			Response::Ok => Ok(()),
+			response => Err(unexpected_response(response).into()),
+		}
+	}
+
+	fn update_state<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		client_id: ClientId,
+		client_state: Self::ClientState,
+		client_message: Self::ClientMessage,
+	) -> Result<(Self::ClientState, ConsensusUpdateResult<Ctx>), Ics02Error> {
+		let consensus_state = latest_consensus_state(ctx, &client_id, &client_state)?;
+		let request = Request::UpdateState {
+			client_state: client_state.data.clone(),
+			consensus_state: consensus_state.data,
+			client_message: client_message.data,
+		};
+		let (data, latest_height, consensus_states) =
+			match execute::<H>(&client_state.code_hash, request)? {
+				Response::UpdateState { client_state, latest_height, consensus_states } =>
+					(client_state, latest_height, consensus_states),
+				response => Err(unexpected_response(response))?,
+			};
+
+		let consensus_states = consensus_states
+			.into_iter()
+			.map(|update| {
+				let timestamp =
+					Timestamp::from_nanoseconds(update.timestamp).map_err(Error::from)?;
+				let consensus_state = ConsensusState::new(update.data, timestamp);
+				let wrapped = Ctx::AnyConsensusState::wrap(&consensus_state)
+					.expect("AnyConsenusState is type checked; qed");
+				Ok((update.height.into(), wrapped))
+			})
+			.collect::<Result<Vec<_>, Ics02Error>>()?;
+
+		let client_state = ClientState {
+			data,
+			latest_height: client_state.latest_height.max(latest_height.into()),
+			..client_state
+		};
+		Ok((client_state, ConsensusUpdateResult::Batch(consensus_states)))
+	}
+
+	fn update_state_on_misbehaviour(
+		&self,
+		client_state: Self::ClientState,
+		client_message: Self::ClientMessage,
+	) -> Result<Self::ClientState, Ics02Error> {
+		let request = Request::UpdateStateOnMisbehaviour {
+			client_state: client_state.data.clone(),
+			client_message: client_message.data,
+		};
+		match execute::<H>(&client_state.code_hash, request)? {
+			Response::Frozen { client_state: data, frozen_height } =>
+				Ok(ClientState { data, ..client_state }.with_frozen_height(frozen_height.into())?),
+			response => Err(unexpected_response(response).into()),
+		}
+	}
+
+	fn check_for_misbehaviour<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		client_id: ClientId,
+		client_state: Self::ClientState,
+		client_message: Self::ClientMessage,
+	) -> Result<bool, Ics02Error> {
+		let consensus_state = latest_consensus_state(ctx, &client_id, &client_state)?;
+		let request = Request::CheckForMisbehaviour {
+			client_state: client_state.data,
+			consensus_state: consensus_state.data,
+			client_message: client_message.data,
+		};
+		match execute::<H>(&client_state.code_hash, request)? {
+			Response::Misbehaviour(misbehaviour) => Ok(misbehaviour),
+			response => Err(unexpected_response(response).into()),
+		}
+	}
+
+	fn verify_upgrade_and_update_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: ClientId,
+		_old_client_state: &Self::ClientState,
+		_upgrade_client_state: &Self::ClientState,
+		_upgrade_consensus_state: &Self::ConsensusState,
+		_proof_upgrade_client: Vec<u8>,
+		_proof_upgrade_consensus_state: Vec<u8>,
+	) -> Result<(Self::ClientState, ConsensusUpdateResult<Ctx>), Ics02Error> {
+		Err(Error::Custom(
+			"Wasm clients can't be upgraded through ICS-02, store new code and recreate the client"
+				.to_string(),
+		)
+		.into())
+	}
+
+	fn verify_client_consensus_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		client_id: &ClientId,
+		consensus_height: Height,
+		expected_consensus_state: &Ctx::AnyConsensusState,
+	) -> Result<(), Ics02Error> {
+		let path = ClientConsensusStatePath {
+			client_id: client_id.clone(),
+			epoch: consensus_height.revision_number,
+			height: consensus_height.revision_height,
+		};
+		let value = expected_consensus_state.encode_to_vec();
+		verify_membership(client_state, height, prefix, proof, root, path, Some(value))?;
+		Ok(())
+	}
+
+	fn verify_connection_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		connection_id: &ConnectionId,
+		expected_connection_end: &ConnectionEnd,
+	) -> Result<(), Ics02Error> {
+		let path = ConnectionsPath(connection_id.clone());
+		let value = expected_connection_end.encode_vec();
+		verify_membership(client_state, height, prefix, proof, root, path, Some(value))?;
+		Ok(())
+	}
+
+	fn verify_channel_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_channel_end: &ChannelEnd,
+	) -> Result<(), Ics02Error> {
+		let path = ChannelEndsPath(port_id.clone(), *channel_id);
+		let value = expected_channel_end.encode_vec();
+		verify_membership(client_state, height, prefix, proof, root, path, Some(value))?;
+		Ok(())
+	}
+
+	fn verify_channel_upgrade<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_upgrade: &Upgrade,
+	) -> Result<(), Ics02Error> {
+		let path = ChannelUpgradesPath(port_id.clone(), *channel_id);
+		let value = expected_upgrade.encode_vec();
+		verify_membership(client_state, height, prefix, proof, root, path, Some(value))?;
+		Ok(())
+	}
+
+	fn verify_channel_upgrade_error<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_error_receipt: &ErrorReceipt,
+	) -> Result<(), Ics02Error> {
+		let path = ChannelUpgradeErrorsPath(port_id.clone(), *channel_id);
+		let value = expected_error_receipt.encode_vec();
+		verify_membership(client_state, height, prefix, proof, root, path, Some(value))?;
+		Ok(())
+	}
+
+	fn verify_client_full_state<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		client_id: &ClientId,
+		expected_client_state: &Ctx::AnyClientState,
+	) -> Result<(), Ics02Error> {
+		let path = ClientStatePath(client_id.clone());
+		let value = expected_client_state.encode_to_vec();
+		verify_membership(client_state, height, prefix, proof, root, path, Some(value))?;
+		Ok(())
+	}
+
+	fn verify_packet_data<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		commitment: PacketCommitment,
+	) -> Result<(), Ics02Error> {
+		verify_delay_passed::<H, _>(ctx, height, connection_end).map_err(Error::Anyhow)?;
+
+		let path = CommitmentsPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		verify_membership(
+			client_state,
+			height,
+			connection_end.counterparty().prefix(),
+			proof,
+			root,
+			path,
+			Some(commitment.into_vec()),
+		)?;
+		Ok(())
+	}
+
+	fn verify_packet_acknowledgement<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+		ack: AcknowledgementCommitment,
+	) -> Result<(), Ics02Error> {
+		verify_delay_passed::<H, _>(ctx, height, connection_end).map_err(Error::Anyhow)?;
+
+		let path = AcksPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		verify_membership(
+			client_state,
+			height,
+			connection_end.counterparty().prefix(),
+			proof,
+			root,
+			path,
+			Some(ack.into_vec()),
+		)?;
+		Ok(())
+	}
+
+	fn verify_next_sequence_recv<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+	) -> Result<(), Ics02Error> {
+		verify_delay_passed::<H, _>(ctx, height, connection_end).map_err(Error::Anyhow)?;
+
+		let path = SeqRecvsPath(port_id.clone(), *channel_id);
+		verify_membership(
+			client_state,
+			height,
+			connection_end.counterparty().prefix(),
+			proof,
+			root,
+			path,
+			Some(u64::from(sequence).encode_to_vec()),
+		)?;
+		Ok(())
+	}
+
+	fn verify_packet_receipt_absence<Ctx: ReaderContext>(
+		&self,
+		ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		connection_end: &ConnectionEnd,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		sequence: Sequence,
+	) -> Result<(), Ics02Error> {
+		verify_delay_passed::<H, _>(ctx, height, connection_end).map_err(Error::Anyhow)?;
+
+		let path = ReceiptsPath { port_id: port_id.clone(), channel_id: *channel_id, sequence };
+		verify_membership(
+			client_state,
+			height,
+			connection_end.counterparty().prefix(),
+			proof,
+			root,
+			path,
+			None,
+		)?;
+		Ok(())
+	}
+}
+
+/// Returns the consensus state at the latest height of the client, which the contract verifies
+/// client messages against.
+fn latest_consensus_state<Ctx: ReaderContext, H>(
+	ctx: &Ctx,
+	client_id: &ClientId,
+	client_state: &ClientState<H>,
+) -> Result<ConsensusState, Ics02Error> {
+	ctx.consensus_state(client_id, client_state.latest_height)?
+		.downcast()
+		.ok_or_else(|| Ics02Error::client_args_type_mismatch(ClientState::<H>::client_type()))
+}
+
+fn unexpected_response(response: Response) -> Error {
+	Error::Contract(format!("Unexpected response: {response:?}"))
+}
+
+/// Asks the contract to verify that `value` is stored at `path` under `prefix`, or that nothing
+/// is stored there if `value` is `None`. `root` holds the consensus state at `height`, see
+/// [`ConsensusState`].
+fn verify_membership<H: HostFunctions>(
+	client_state: &ClientState<H>,
+	height: Height,
+	prefix: &CommitmentPrefix,
+	proof: &CommitmentProofBytes,
+	root: &CommitmentRoot,
+	path: impl Display,
+	value: Option<Vec<u8>>,
+) -> Result<(), Error> {
+	client_state.verify_height(height)?;
+
+	let (client_state_data, consensus_state, height, proof, prefix, path) = (
+		client_state.data.clone(),
+		root.as_bytes().to_vec(),
+		height.into(),
+		proof.as_bytes().to_vec(),
+		prefix.as_bytes().to_vec(),
+		path.to_string(),
+	);
+	let request = match value {
+		Some(value) => Request::VerifyMembership {
+			client_state: client_state_data,
+			consensus_state,
+			height,
+			proof,
+			prefix,
+			path,
+			value,
+		},
+		None => Request::VerifyNonMembership {
+			client_state: client_state_data,
+			consensus_state,
+			height,
+			proof,
+			prefix,
+			path,
+		},
+	};
+	match execute::<H>(&client_state.code_hash, request)? {
+		Response::Ok => Ok(()),
+		response => Err(unexpected_response(response)),
+	}
+}
@@ -0,0 +1,50 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{error::Error, proto::ClientMessage as RawClientMessage};
+use alloc::vec::Vec;
+use ibc::core::ics02_client;
+use tendermint_proto::Protobuf;
+
+/// Protobuf type url for the wasm ClientMessage
+pub const WASM_CLIENT_MESSAGE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ClientMessage";
+
+/// A header or misbehaviour of a wasm client, only the contract knows which.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientMessage {
+	pub data: Vec<u8>,
+}
+
+impl ics02_client::client_message::ClientMessage for ClientMessage {
+	fn encode_to_vec(&self) -> Vec<u8> {
+		self.encode_vec()
+	}
+}
+
+impl Protobuf<RawClientMessage> for ClientMessage {}
+
+impl TryFrom<RawClientMessage> for ClientMessage {
+	type Error = Error;
+
+	fn try_from(raw: RawClientMessage) -> Result<Self, Self::Error> {
+		Ok(Self { data: raw.data })
+	}
+}
+
+impl From<ClientMessage> for RawClientMessage {
+	fn from(value: ClientMessage) -> Self {
+		RawClientMessage { data: value.data }
+	}
+}
@@ -0,0 +1,167 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	client_def::WasmClient, error::Error, proto::ClientState as RawClientState, HostFunctions,
+};
+use alloc::{format, string::ToString, vec::Vec};
+use core::{marker::PhantomData, time::Duration};
+use ibc::{
+	core::{ics02_client::client_state::ClientType, ics24_host::identifier::ChainId},
+	Height,
+};
+use tendermint_proto::Protobuf;
+
+/// Protobuf type url for the wasm ClientState
+pub const WASM_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ClientState";
+
+#[derive(PartialEq, Clone, Debug, Eq)]
+pub struct ClientState<H> {
+	/// Client state of the contract
+	pub data: Vec<u8>,
+	/// Hash of the contract code verifying the client
+	pub code_hash: Vec<u8>,
+	/// Latest height of the client, as reported by the contract
+	pub latest_height: Height,
+	/// Height at which the client was frozen due to a misbehaviour
+	pub frozen_height: Option<Height>,
+	/// phantom type.
+	pub _phantom: PhantomData<H>,
+}
+
+impl<H: Clone> Protobuf<RawClientState> for ClientState<H> {}
+
+impl<H> ClientState<H> {
+	pub fn new(data: Vec<u8>, code_hash: Vec<u8>, latest_height: Height) -> Self {
+		Self { data, code_hash, latest_height, frozen_height: None, _phantom: PhantomData }
+	}
+
+	/// Verify that the client is at a sufficient height and unfrozen at the given height
+	pub fn verify_height(&self, height: Height) -> Result<(), Error> {
+		if self.latest_height < height {
+			return Err(Error::Custom(format!(
+				"Insufficient height, known height: {}, given height: {height}",
+				self.latest_height
+			)))
+		}
+
+		match self.frozen_height {
+			Some(frozen_height) if frozen_height <= height =>
+				Err(Error::Custom(format!("Client has been frozen at height {frozen_height}"))),
+			_ => Ok(()),
+		}
+	}
+
+	pub fn latest_height(&self) -> Height {
+		self.latest_height
+	}
+
+	/// The host doesn't know the chain a contract tracks, so wasm clients are only told apart by
+	/// their revision.
+	pub fn chain_id(&self) -> ChainId {
+		ChainId::new(Self::client_type(), self.latest_height.revision_number)
+	}
+
+	pub fn client_type() -> ClientType {
+		"08-wasm".to_string()
+	}
+
+	pub fn with_frozen_height(self, h: Height) -> Result<Self, Error> {
+		if h == Height::zero() {
+			return Err(Error::Custom(
+				"ClientState frozen height must be greater than zero".to_string(),
+			))
+		}
+		Ok(Self { frozen_height: Some(h), ..self })
+	}
+}
+
+impl<H> ibc::core::ics02_client::client_state::ClientState for ClientState<H>
+where
+	H: HostFunctions,
+{
+	type UpgradeOptions = ();
+	type ClientDef = WasmClient<H>;
+
+	fn chain_id(&self) -> ChainId {
+		self.chain_id()
+	}
+
+	fn client_def(&self) -> Self::ClientDef {
+		WasmClient::default()
+	}
+
+	fn client_type(&self) -> ClientType {
+		Self::client_type()
+	}
+
+	fn latest_height(&self) -> Height {
+		self.latest_height
+	}
+
+	fn frozen_height(&self) -> Option<Height> {
+		self.frozen_height
+	}
+
+	/// Wasm clients are upgraded by pointing them at new code, see
+	/// [`WasmClient::verify_upgrade_and_update_state`].
+	fn upgrade(self, _upgrade_height: Height, _upgrade_options: (), _chain_id: ChainId) -> Self {
+		self
+	}
+
+	/// Expiry is up to the contract, which rejects headers once its trusting period passed.
+	fn expired(&self, _elapsed: Duration) -> bool {
+		false
+	}
+
+	fn encode_to_vec(&self) -> Vec<u8> {
+		self.encode_vec()
+	}
+}
+
+impl<H> TryFrom<RawClientState> for ClientState<H> {
+	type Error = Error;
+
+	fn try_from(raw: RawClientState) -> Result<Self, Self::Error> {
+		if raw.code_hash.is_empty() {
+			Err(Error::Custom("Invalid client state: code hash cannot be empty".to_string()))?
+		}
+		let latest_height = raw
+			.latest_height
+			.ok_or_else(|| {
+				Error::Custom("Invalid client state: missing latest height".to_string())
+			})?
+			.into();
+
+		Ok(Self {
+			data: raw.data,
+			code_hash: raw.code_hash,
+			latest_height,
+			frozen_height: raw.frozen_height.map(Into::into),
+			_phantom: PhantomData,
+		})
+	}
+}
+
+impl<H> From<ClientState<H>> for RawClientState {
+	fn from(client_state: ClientState<H>) -> Self {
+		RawClientState {
+			data: client_state.data,
+			code_hash: client_state.code_hash,
+			latest_height: Some(client_state.latest_height.into()),
+			frozen_height: client_state.frozen_height.map(Into::into),
+		}
+	}
+}
@@ -0,0 +1,78 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{error::Error, proto::ConsensusState as RawConsensusState};
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use ibc::{core::ics23_commitment::commitment::CommitmentRoot, timestamp::Timestamp};
+use tendermint_proto::Protobuf;
+
+/// Protobuf type url for the wasm ConsensusState
+pub const WASM_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ConsensusState";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsensusState {
+	/// Consensus state of the contract
+	pub data: Vec<u8>,
+	/// Time of the consensus state
+	pub timestamp: Timestamp,
+	/// The host can't tell where the contract keeps its state root, so the whole consensus state
+	/// takes its place and is handed to the contract for verification.
+	root: CommitmentRoot,
+}
+
+impl ConsensusState {
+	pub fn new(data: Vec<u8>, timestamp: Timestamp) -> Self {
+		let root = data.clone().into();
+		Self { data, timestamp, root }
+	}
+}
+
+impl ibc::core::ics02_client::client_consensus::ConsensusState for ConsensusState {
+	type Error = Infallible;
+
+	fn root(&self) -> &CommitmentRoot {
+		&self.root
+	}
+
+	fn timestamp(&self) -> Timestamp {
+		self.timestamp
+	}
+
+	fn encode_to_vec(&self) -> Vec<u8> {
+		self.encode_vec()
+	}
+}
+
+impl Protobuf<RawConsensusState> for ConsensusState {}
+
+impl TryFrom<RawConsensusState> for ConsensusState {
+	type Error = Error;
+
+	fn try_from(raw: RawConsensusState) -> Result<Self, Self::Error> {
+		if raw.timestamp == 0 {
+			Err(Error::Custom("Invalid consensus state: timestamp cannot be zero".into()))?
+		}
+		let timestamp = Timestamp::from_nanoseconds(raw.timestamp)?;
+
+		Ok(Self::new(raw.data, timestamp))
+	}
+}
+
+impl From<ConsensusState> for RawConsensusState {
+	fn from(value: ConsensusState) -> Self {
+		RawConsensusState { data: value.data, timestamp: value.timestamp.nanoseconds() }
+	}
+}
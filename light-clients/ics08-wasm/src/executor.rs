@@ -0,0 +1,323 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sandboxed execution of wasm light client contracts.
+//!
+//! Contracts can only import the host functions below from the `env` module. Each call into the
+//! host is charged the fuel given by the [`Schedule`] of the host, on top of the fuel of the
+//! instructions the contract runs. Pointers and lengths are `i32`s into the contract's memory.
+//!
+//! - `ed25519_verify(sig_ptr, sig_len, msg_ptr, msg_len, key_ptr, key_len) -> i32`
+//! - `sr25519_verify(sig_ptr, sig_len, msg_ptr, msg_len, key_ptr, key_len) -> i32`
+//! - `secp256k1_verify(sig_ptr, sig_len, msg_ptr, msg_len, key_ptr, key_len) -> i32`, for a 64 byte
+//!   signature of the sha256 hash of the message by a compressed public key
+//!
+//!   The verify functions return `1` for a valid signature and `0` otherwise.
+//! - `sha2_256(data_ptr, data_len, out_ptr)`
+//! - `keccak_256(data_ptr, data_len, out_ptr)`
+//! - `blake2b_256(data_ptr, data_len, out_ptr)`
+//!
+//!   The hash functions write the 32 byte hash of the data to `out_ptr`.
+
+use crate::{
+	abi::{Request, Response},
+	error::Error,
+	HostFunctions,
+};
+use alloc::{format, string::String, vec::Vec};
+use codec::{Decode, Encode};
+use core::fmt::Display;
+use wasmi::{
+	core::Trap, Caller, Config, Engine, Extern, Func, Linker, Memory, Module, Store, TypedFunc,
+};
+
+#[cfg(feature = "std")]
+use {
+	alloc::{collections::BTreeMap, sync::Arc},
+	once_cell::sync::Lazy,
+	std::sync::Mutex,
+};
+
+/// Engine shared by every compiled contract, modules can only be instantiated in stores of the
+/// engine that compiled them.
+#[cfg(feature = "std")]
+static ENGINE: Lazy<Engine> = Lazy::new(new_engine);
+
+/// Contracts compiled so far, by code hash. Stored code is immutable, so entries never go stale.
+#[cfg(feature = "std")]
+static MODULES: Lazy<Mutex<BTreeMap<Vec<u8>, Arc<Module>>>> = Lazy::new(Default::default);
+
+/// Fuel limit of a contract call and fuel charged for the host functions it calls. Hosts derive
+/// it from the benchmarked weight of a unit of fuel and of each host function, so that a call
+/// never costs more than the weight charged for its fuel limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Encode, Decode)]
+pub struct Schedule {
+	/// Fuel a single contract call may consume before it's aborted
+	pub fuel_limit: u64,
+	/// Fuel charged for verifying an ed25519 signature
+	pub ed25519_verify: u64,
+	/// Fuel charged for verifying an sr25519 signature
+	pub sr25519_verify: u64,
+	/// Fuel charged for verifying a secp256k1 signature
+	pub secp256k1_verify: u64,
+	/// Fuel charged for every call to a hash function
+	pub hash: u64,
+	/// Fuel charged per byte hashed, or per byte of a verified message
+	pub hash_per_byte: u64,
+}
+
+/// An instantiated contract, ready to handle a single request.
+struct Contract {
+	store: Store<()>,
+	memory: Memory,
+	alloc: TypedFunc<i32, i32>,
+	call: TypedFunc<(i32, i32), i64>,
+}
+
+fn wasm_error(e: impl Display) -> Error {
+	Error::Wasm(format!("{e}"))
+}
+
+fn new_engine() -> Engine {
+	let mut config = Config::default();
+	config.consume_fuel(true);
+	Engine::new(&config)
+}
+
+#[cfg(feature = "std")]
+fn compile(code: &[u8]) -> Result<Module, Error> {
+	Module::new(&ENGINE, code).map_err(wasm_error)
+}
+
+#[cfg(not(feature = "std"))]
+fn compile(code: &[u8]) -> Result<Module, Error> {
+	Module::new(&new_engine(), code).map_err(wasm_error)
+}
+
+/// Compiles the contract stored under `code_hash`, or reuses the module compiled by a previous
+/// call.
+#[cfg(feature = "std")]
+fn load<H: HostFunctions>(code_hash: &[u8]) -> Result<Arc<Module>, Error> {
+	if let Some(module) = MODULES.lock().expect("Module cache poisoned").get(code_hash) {
+		return Ok(module.clone())
+	}
+	let module = Arc::new(compile(&stored_code::<H>(code_hash)?)?);
+	MODULES
+		.lock()
+		.expect("Module cache poisoned")
+		.insert(code_hash.to_vec(), module.clone());
+	Ok(module)
+}
+
+/// Compiles the contract stored under `code_hash`. Without `std` there's nowhere to keep the
+/// compiled module between calls.
+#[cfg(not(feature = "std"))]
+fn load<H: HostFunctions>(code_hash: &[u8]) -> Result<Module, Error> {
+	compile(&stored_code::<H>(code_hash)?)
+}
+
+fn stored_code<H: HostFunctions>(code_hash: &[u8]) -> Result<Vec<u8>, Error> {
+	H::code(code_hash)
+		.ok_or_else(|| Error::Custom(format!("No contract stored for code hash {code_hash:?}")))
+}
+
+/// Charges `fuel` for a call into the host, trapping once the contract runs out of fuel.
+fn charge(caller: &mut Caller<'_, ()>, fuel: u64) -> Result<(), Trap> {
+	caller
+		.consume_fuel(fuel)
+		.map(|_| ())
+		.map_err(|_| Trap::new("Contract ran out of fuel"))
+}
+
+fn caller_memory(caller: &Caller<'_, ()>) -> Result<Memory, Trap> {
+	caller
+		.get_export("memory")
+		.and_then(Extern::into_memory)
+		.ok_or_else(|| Trap::new("Contract doesn't export `memory`"))
+}
+
+/// Copies `len` bytes at `ptr` out of the contract's memory.
+fn read(caller: &Caller<'_, ()>, ptr: i32, len: i32) -> Result<Vec<u8>, Trap> {
+	caller_memory(caller)?
+		.data(caller)
+		.get(ptr as u32 as usize..)
+		.and_then(|memory| memory.get(..len as u32 as usize))
+		.map(|bytes| bytes.to_vec())
+		.ok_or_else(|| Trap::new("Host function argument out of bounds"))
+}
+
+/// Copies `data` to `ptr` in the contract's memory.
+fn write(caller: &mut Caller<'_, ()>, ptr: i32, data: &[u8]) -> Result<(), Trap> {
+	caller_memory(caller)?
+		.data_mut(caller)
+		.get_mut(ptr as u32 as usize..)
+		.and_then(|memory| memory.get_mut(..data.len()))
+		.ok_or_else(|| Trap::new("Host function output out of bounds"))?
+		.copy_from_slice(data);
+	Ok(())
+}
+
+/// Wraps a signature verification host function, charged `fuel` and the fuel for hashing the
+/// message.
+fn verify_func(
+	store: &mut Store<()>,
+	verify: fn(&[u8], &[u8], &[u8]) -> bool,
+	fuel: u64,
+	fuel_per_byte: u64,
+) -> Func {
+	Func::wrap(
+		store,
+		move |mut caller: Caller<'_, ()>,
+		      sig_ptr: i32,
+		      sig_len: i32,
+		      msg_ptr: i32,
+		      msg_len: i32,
+		      key_ptr: i32,
+		      key_len: i32|
+		      -> Result<i32, Trap> {
+			let msg_fuel = fuel_per_byte.saturating_mul(msg_len as u32 as u64);
+			charge(&mut caller, fuel.saturating_add(msg_fuel))?;
+			let signature = read(&caller, sig_ptr, sig_len)?;
+			let msg = read(&caller, msg_ptr, msg_len)?;
+			let public_key = read(&caller, key_ptr, key_len)?;
+			Ok(verify(&signature, &msg, &public_key) as i32)
+		},
+	)
+}
+
+/// Wraps a hash host function, charged `fuel` and `fuel_per_byte` for every byte hashed.
+fn hash_func(
+	store: &mut Store<()>,
+	hash: fn(&[u8]) -> [u8; 32],
+	fuel: u64,
+	fuel_per_byte: u64,
+) -> Func {
+	Func::wrap(
+		store,
+		move |mut caller: Caller<'_, ()>,
+		      data_ptr: i32,
+		      data_len: i32,
+		      out_ptr: i32|
+		      -> Result<(), Trap> {
+			let data_fuel = fuel_per_byte.saturating_mul(data_len as u32 as u64);
+			charge(&mut caller, fuel.saturating_add(data_fuel))?;
+			let data = read(&caller, data_ptr, data_len)?;
+			write(&mut caller, out_ptr, &hash(&data))
+		},
+	)
+}
+
+/// Links the host functions contracts may import, metered according to `schedule`.
+fn linker<H: HostFunctions>(
+	store: &mut Store<()>,
+	schedule: &Schedule,
+) -> Result<Linker<()>, Error> {
+	let per_byte = schedule.hash_per_byte;
+	let functions = [
+		(
+			"ed25519_verify",
+			verify_func(store, H::ed25519_verify, schedule.ed25519_verify, per_byte),
+		),
+		(
+			"sr25519_verify",
+			verify_func(store, H::sr25519_verify, schedule.sr25519_verify, per_byte),
+		),
+		(
+			"secp256k1_verify",
+			verify_func(store, H::secp256k1_verify, schedule.secp256k1_verify, per_byte),
+		),
+		("sha2_256", hash_func(store, H::sha2_256, schedule.hash, per_byte)),
+		("keccak_256", hash_func(store, H::keccak_256, schedule.hash, per_byte)),
+		("blake2b_256", hash_func(store, H::blake2b_256, schedule.hash, per_byte)),
+	];
+	let mut linker = Linker::new();
+	for (name, func) in functions {
+		linker.define("env", name, func).map_err(wasm_error)?;
+	}
+	Ok(linker)
+}
+
+impl Contract {
+	fn instantiate<H: HostFunctions>(module: &Module, schedule: &Schedule) -> Result<Self, Error> {
+		let mut store = Store::new(module.engine(), ());
+		store.add_fuel(schedule.fuel_limit).map_err(wasm_error)?;
+
+		// only the metered host functions are linked, so contracts that import anything else
+		// fail to instantiate.
+		let instance = linker::<H>(&mut store, schedule)?
+			.instantiate(&mut store, module)
+			.map_err(wasm_error)?
+			.start(&mut store)
+			.map_err(wasm_error)?;
+
+		let export = |name: &str| {
+			instance
+				.get_export(&store, name)
+				.ok_or_else(|| Error::Wasm(format!("Contract doesn't export `{name}`")))
+		};
+		let memory = export("memory")?
+			.into_memory()
+			.ok_or_else(|| Error::Wasm("Contract export `memory` isn't a memory".into()))?;
+		let alloc = export("alloc")?
+			.into_func()
+			.ok_or_else(|| Error::Wasm("Contract export `alloc` isn't a function".into()))?
+			.typed::<i32, i32>(&store)
+			.map_err(wasm_error)?;
+		let call = export("call")?
+			.into_func()
+			.ok_or_else(|| Error::Wasm("Contract export `call` isn't a function".into()))?
+			.typed::<(i32, i32), i64>(&store)
+			.map_err(wasm_error)?;
+
+		Ok(Self { store, memory, alloc, call })
+	}
+
+	fn call(mut self, request: Request) -> Result<Response, Error> {
+		let input = request.encode();
+		let len = i32::try_from(input.len())
+			.map_err(|_| Error::Wasm("Request exceeds the contract memory".into()))?;
+		let ptr = self.alloc.call(&mut self.store, len).map_err(wasm_error)?;
+		self.memory
+			.data_mut(&mut self.store)
+			.get_mut(ptr as u32 as usize..)
+			.and_then(|memory| memory.get_mut(..input.len()))
+			.ok_or_else(|| Error::Wasm("Contract allocated out of bounds".into()))?
+			.copy_from_slice(&input);
+
+		let output = self.call.call(&mut self.store, (ptr, len)).map_err(wasm_error)? as u64;
+		let (ptr, len) = ((output >> 32) as usize, (output as u32) as usize);
+		let mut output = self
+			.memory
+			.data(&self.store)
+			.get(ptr..)
+			.and_then(|memory| memory.get(..len))
+			.ok_or_else(|| Error::Wasm("Contract returned out of bounds".into()))?;
+
+		Result::<Response, String>::decode(&mut output)?.map_err(Error::Contract)
+	}
+}
+
+/// Checks that `code` is a contract which can be executed by the host `H`, see [`crate::abi`].
+pub fn validate<H: HostFunctions>(code: &[u8]) -> Result<(), Error> {
+	Contract::instantiate::<H>(&compile(code)?, &H::schedule()).map(|_| ())
+}
+
+/// Executes `request` with the contract stored under `code_hash`, metered by
+/// [`HostFunctions::schedule`]. With `std` the contract is compiled once per code hash and every
+/// call after that only instantiates it.
+pub fn execute<H: HostFunctions>(code_hash: &[u8], request: Request) -> Result<Response, Error> {
+	let module = load::<H>(code_hash)?;
+	Contract::instantiate::<H>(&module, &H::schedule())?.call(request)
+}
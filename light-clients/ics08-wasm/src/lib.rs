@@ -0,0 +1,74 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::all)]
+
+//! ICS-08: Wasm IBC light client protocol implementation.
+//!
+//! The verification logic of a wasm client lives in a contract, which is uploaded once and then
+//! referenced by the hash of its code. Client and consensus states are opaque to the host, it
+//! only tracks their heights and timestamps and hands the bytes to the contract, which runs in a
+//! sandboxed [`wasmi`] instance with a bounded amount of fuel. Contracts can only import the
+//! metered crypto host functions of the [`executor`]. See [`abi`] for the interface contracts
+//! have to implement.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+pub mod abi;
+pub mod client_def;
+pub mod client_message;
+pub mod client_state;
+pub mod consensus_state;
+pub mod error;
+pub mod executor;
+pub mod proto;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// Host functions used to look up and execute wasm light client contracts.
+pub trait HostFunctions: Debug + Clone + Send + Sync + Default + Eq {
+	/// Returns the code of the contract stored under `code_hash`, if any.
+	fn code(code_hash: &[u8]) -> Option<Vec<u8>>;
+
+	/// Fuel limit of a contract call and fuel charged for the host functions it calls.
+	fn schedule() -> executor::Schedule;
+
+	/// Verifies an ed25519 `signature` of `msg` by `public_key`.
+	fn ed25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool;
+
+	/// Verifies an sr25519 `signature` of `msg` by `public_key`.
+	fn sr25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool;
+
+	/// Verifies a 64 byte secp256k1 `signature` of the sha256 hash of `msg` by the compressed
+	/// `public_key`.
+	fn secp256k1_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool;
+
+	/// Returns the sha2-256 hash of `data`.
+	fn sha2_256(data: &[u8]) -> [u8; 32];
+
+	/// Returns the keccak-256 hash of `data`.
+	fn keccak_256(data: &[u8]) -> [u8; 32];
+
+	/// Returns the blake2b-256 hash of `data`.
+	fn blake2b_256(data: &[u8]) -> [u8; 32];
+}
@@ -0,0 +1,232 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(unreachable_code)]
+
+use crate::{
+	client_def::WasmClient,
+	client_message::{ClientMessage, WASM_CLIENT_MESSAGE_TYPE_URL},
+	client_state::{ClientState, WASM_CLIENT_STATE_TYPE_URL},
+	consensus_state::{ConsensusState, WASM_CONSENSUS_STATE_TYPE_URL},
+	executor::Schedule,
+	HostFunctions,
+};
+use ibc::{
+	core::{
+		ics02_client,
+		ics02_client::{
+			client_consensus::ConsensusState as _, client_state::ClientState as _,
+			context::ClientTypes,
+		},
+	},
+	mock::{
+		client_def::MockClient,
+		client_state::{MockClientState, MockConsensusState},
+		context::HostBlockType,
+		header::MockClientMessage,
+		host::MockHostBlock,
+	},
+	prelude::*,
+};
+use ibc_derive::{ClientDef, ClientMessage, ClientState, ConsensusState, Protobuf};
+use ibc_proto::google::protobuf::Any;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+	ecdsa, ed25519,
+	hashing::{blake2_256, keccak_256, sha2_256},
+	sr25519, Pair,
+};
+use std::{cell::RefCell, collections::BTreeMap};
+use tendermint_proto::Protobuf;
+
+pub const MOCK_CLIENT_STATE_TYPE_URL: &str = "/ibc.mock.ClientState";
+pub const MOCK_CLIENT_MESSAGE_TYPE_URL: &str = "/ibc.mock.ClientMessage";
+pub const MOCK_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.mock.ConsensusState";
+
+thread_local! {
+	static CODE: RefCell<BTreeMap<Vec<u8>, Vec<u8>>> = RefCell::new(BTreeMap::new());
+}
+
+/// Stores `code` for the current test and returns its hash.
+pub fn store_code(code: Vec<u8>) -> Vec<u8> {
+	let code_hash = sha2_256(&code).to_vec();
+	CODE.with(|store| store.borrow_mut().insert(code_hash.clone(), code));
+	code_hash
+}
+
+#[derive(Clone, Default, PartialEq, Debug, Eq)]
+pub struct HostFunctionsManager;
+
+impl HostFunctions for HostFunctionsManager {
+	fn code(code_hash: &[u8]) -> Option<Vec<u8>> {
+		CODE.with(|store| store.borrow().get(code_hash).cloned())
+	}
+
+	fn schedule() -> Schedule {
+		Schedule {
+			fuel_limit: 1_000_000,
+			ed25519_verify: 10_000,
+			sr25519_verify: 10_000,
+			secp256k1_verify: 10_000,
+			hash: 100,
+			hash_per_byte: 1,
+		}
+	}
+
+	fn ed25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		match (ed25519::Signature::from_slice(signature), ed25519::Public::try_from(public_key)) {
+			(Some(signature), Ok(public_key)) =>
+				ed25519::Pair::verify(&signature, msg, &public_key),
+			_ => false,
+		}
+	}
+
+	fn sr25519_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		match (sr25519::Signature::from_slice(signature), sr25519::Public::try_from(public_key)) {
+			(Some(signature), Ok(public_key)) =>
+				sr25519::Pair::verify(&signature, msg, &public_key),
+			_ => false,
+		}
+	}
+
+	fn secp256k1_verify(signature: &[u8], msg: &[u8], public_key: &[u8]) -> bool {
+		if signature.len() != 64 {
+			return false
+		}
+		let message = sha2_256(msg);
+		(0u8..2).any(|recovery_id| {
+			let mut signature_with_id = [0u8; 65];
+			signature_with_id[..64].copy_from_slice(signature);
+			signature_with_id[64] = recovery_id;
+			ecdsa::Signature::from_raw(signature_with_id)
+				.recover_prehashed(&message)
+				.map(|recovered| recovered.0[..] == *public_key)
+				.unwrap_or(false)
+		})
+	}
+
+	fn sha2_256(data: &[u8]) -> [u8; 32] {
+		sha2_256(data)
+	}
+
+	fn keccak_256(data: &[u8]) -> [u8; 32] {
+		keccak_256(data)
+	}
+
+	fn blake2b_256(data: &[u8]) -> [u8; 32] {
+		blake2_256(data)
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, ClientDef)]
+pub enum AnyClient {
+	Mock(MockClient),
+	Wasm(WasmClient<HostFunctionsManager>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AnyUpgradeOptions {
+	Mock(()),
+	Wasm(()),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ClientState, Protobuf)]
+#[serde(tag = "type")]
+pub enum AnyClientState {
+	#[ibc(proto_url = "MOCK_CLIENT_STATE_TYPE_URL")]
+	Mock(MockClientState),
+	#[serde(skip)]
+	#[ibc(proto_url = "WASM_CLIENT_STATE_TYPE_URL")]
+	Wasm(ClientState<HostFunctionsManager>),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ClientMessage)]
+#[allow(clippy::large_enum_variant)]
+pub enum AnyClientMessage {
+	#[ibc(proto_url = "MOCK_CLIENT_MESSAGE_TYPE_URL")]
+	Mock(MockClientMessage),
+	#[serde(skip)]
+	#[ibc(proto_url = "WASM_CLIENT_MESSAGE_TYPE_URL")]
+	Wasm(ClientMessage),
+}
+
+impl Protobuf<Any> for AnyClientMessage {}
+
+impl TryFrom<Any> for AnyClientMessage {
+	type Error = ics02_client::error::Error;
+
+	fn try_from(value: Any) -> Result<Self, Self::Error> {
+		match value.type_url.as_str() {
+			MOCK_CLIENT_MESSAGE_TYPE_URL =>
+				Ok(Self::Mock(panic!("MockClientMessage doesn't implement Protobuf"))),
+			WASM_CLIENT_MESSAGE_TYPE_URL => Ok(Self::Wasm(
+				ClientMessage::decode_vec(&value.value)
+					.map_err(ics02_client::error::Error::decode_raw_header)?,
+			)),
+			_ => Err(ics02_client::error::Error::unknown_consensus_state_type(value.type_url)),
+		}
+	}
+}
+
+impl From<AnyClientMessage> for Any {
+	fn from(client_msg: AnyClientMessage) -> Self {
+		match client_msg {
+			AnyClientMessage::Mock(_mock) => {
+				panic!("MockClientMessage doesn't implement Protobuf");
+			},
+			AnyClientMessage::Wasm(msg) =>
+				Any { type_url: WASM_CLIENT_MESSAGE_TYPE_URL.to_string(), value: msg.encode_vec() },
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, ConsensusState, Protobuf)]
+#[serde(tag = "type")]
+pub enum AnyConsensusState {
+	#[serde(skip)]
+	#[ibc(proto_url = "WASM_CONSENSUS_STATE_TYPE_URL")]
+	Wasm(ConsensusState),
+	#[ibc(proto_url = "MOCK_CONSENSUS_STATE_TYPE_URL")]
+	Mock(MockConsensusState),
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct MockClientTypes;
+
+impl ClientTypes for MockClientTypes {
+	type AnyClientMessage = AnyClientMessage;
+	type AnyClientState = AnyClientState;
+	type AnyConsensusState = AnyConsensusState;
+	type ClientDef = AnyClient;
+}
+
+impl HostBlockType for MockClientTypes {
+	type HostBlock = MockHostBlock;
+}
+
+impl From<MockHostBlock> for AnyClientMessage {
+	fn from(block: MockHostBlock) -> Self {
+		let MockHostBlock::Mock(header) = block;
+		AnyClientMessage::Mock(MockClientMessage::Header(header))
+	}
+}
+
+impl From<MockHostBlock> for AnyConsensusState {
+	fn from(block: MockHostBlock) -> Self {
+		let MockHostBlock::Mock(header) = block;
+		AnyConsensusState::Mock(MockConsensusState::new(header))
+	}
+}
@@ -0,0 +1,52 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protobuf types of the wasm client, mirroring the `ibc.lightclients.wasm.v1` package.
+
+use alloc::vec::Vec;
+use ibc_proto::ibc::core::client::v1::Height;
+
+/// Wraps the opaque client state of a wasm contract.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClientState {
+	/// Client state of the contract
+	#[prost(bytes = "vec", tag = "1")]
+	pub data: Vec<u8>,
+	/// Hash of the contract code
+	#[prost(bytes = "vec", tag = "2")]
+	pub code_hash: Vec<u8>,
+	#[prost(message, optional, tag = "3")]
+	pub latest_height: Option<Height>,
+	#[prost(message, optional, tag = "4")]
+	pub frozen_height: Option<Height>,
+}
+
+/// Wraps the opaque consensus state of a wasm contract.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsensusState {
+	/// Consensus state of the contract
+	#[prost(bytes = "vec", tag = "1")]
+	pub data: Vec<u8>,
+	/// Timestamp of the consensus state, in nanoseconds
+	#[prost(uint64, tag = "2")]
+	pub timestamp: u64,
+}
+
+/// Wraps an opaque header or misbehaviour of a wasm contract.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClientMessage {
+	#[prost(bytes = "vec", tag = "1")]
+	pub data: Vec<u8>,
+}
@@ -0,0 +1,369 @@
+// Copyright (C) 2022 ComposableFi.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	abi::{ConsensusUpdate, Request, Response},
+	client_def::WasmClient,
+	client_message::ClientMessage,
+	client_state::ClientState,
+	consensus_state::ConsensusState,
+	error::Error,
+	executor::{execute, validate},
+	mock::{
+		store_code, AnyClientMessage, AnyClientState, AnyConsensusState, HostFunctionsManager,
+		MockClientTypes,
+	},
+};
+use codec::Encode;
+use ibc::{
+	core::{
+		ics02_client::{
+			client_consensus::ConsensusState as _, client_def::ClientDef,
+			client_state::ClientState as _, context::ClientReader,
+		},
+		ics03_connection::connection::ConnectionEnd,
+		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes},
+		ics24_host::identifier::{ClientId, ConnectionId},
+	},
+	mock::context::MockContext,
+	timestamp::Timestamp,
+	Height,
+};
+use sp_core::{ed25519, hashing::sha2_256, Pair};
+
+/// Offset of the table holding the packed pointer and length of the response to each kind of
+/// request, indexed by the SCALE variant index of the request.
+const RESPONSE_TABLE: u64 = 60_000;
+
+/// Returns a contract which answers every kind of request with the response `respond` gives for
+/// its variant index, see [`Request`].
+fn contract(respond: impl Fn(u8) -> Result<Response, String>) -> Vec<u8> {
+	let mut data = String::new();
+	let mut table = Vec::new();
+	for index in 0u8..6 {
+		let offset = index as u64 * 4096;
+		let response = respond(index).encode();
+		data.push_str(&format!("(data (i32.const {offset}) \"{}\")\n", escape(&response)));
+		table.extend_from_slice(&((offset << 32) | response.len() as u64).to_le_bytes());
+	}
+	let wat = format!(
+		r#"(module
+	(memory (export "memory") 1)
+	{data}
+	(data (i32.const {RESPONSE_TABLE}) "{table}")
+	(func (export "alloc") (param i32) (result i32) i32.const 32768)
+	(func (export "call") (param $ptr i32) (param $len i32) (result i64)
+		(i64.load
+			(i32.add
+				(i32.const {RESPONSE_TABLE})
+				(i32.mul (i32.load8_u (local.get $ptr)) (i32.const 8)))))
+)"#,
+		table = escape(&table),
+	);
+	wat::parse_str(wat).unwrap()
+}
+
+fn escape(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("\\{byte:02x}")).collect()
+}
+
+fn height(revision_height: u64) -> crate::abi::Height {
+	Height::new(0, revision_height).into()
+}
+
+/// A contract which accepts every header and proof and moves the client to height 2.
+fn light_client(index: u8) -> Result<Response, String> {
+	match index {
+		1 => Ok(Response::Misbehaviour(false)),
+		2 => Ok(Response::UpdateState {
+			client_state: b"client-state-2".to_vec(),
+			latest_height: height(2),
+			consensus_states: vec![ConsensusUpdate {
+				height: height(2),
+				data: b"consensus-state-2".to_vec(),
+				timestamp: 2_000,
+			}],
+		}),
+		3 => Ok(Response::Frozen {
+			client_state: b"client-state-frozen".to_vec(),
+			frozen_height: height(1),
+		}),
+		_ => Ok(Response::Ok),
+	}
+}
+
+/// Creates a wasm client at height 1 verified by `code`.
+fn create_client(code: Vec<u8>) -> (MockContext<MockClientTypes>, ClientId) {
+	let mut ctx = MockContext::<MockClientTypes>::default();
+	let code_hash = store_code(code);
+	let consensus_state = ConsensusState::new(
+		b"consensus-state-1".to_vec(),
+		Timestamp::from_nanoseconds(1_000).unwrap(),
+	);
+	let client_state = ClientState::<HostFunctionsManager>::new(
+		b"client-state-1".to_vec(),
+		code_hash,
+		Height::new(0, 1),
+	);
+	let client_id = ctx
+		.create_client(AnyClientState::Wasm(client_state), AnyConsensusState::Wasm(consensus_state))
+		.unwrap();
+	(ctx, client_id)
+}
+
+/// A header, which the contracts under test don't look at.
+fn header() -> AnyClientMessage {
+	AnyClientMessage::Wasm(ClientMessage { data: b"header".to_vec() })
+}
+
+#[test]
+fn test_update_wasm_client() {
+	let (mut ctx, client_id) = create_client(contract(light_client));
+
+	ctx.update_client(&client_id, header()).unwrap();
+
+	let client_state = ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id);
+	assert_eq!(client_state.data, b"client-state-2".to_vec());
+	assert_eq!(client_state.latest_height(), Height::new(0, 2));
+	assert_eq!(client_state.frozen_height(), None);
+	match ctx.consensus_state(&client_id, Height::new(0, 2)).unwrap() {
+		AnyConsensusState::Wasm(consensus_state) => {
+			assert_eq!(consensus_state.data, b"consensus-state-2".to_vec());
+			assert_eq!(consensus_state.timestamp().nanoseconds(), 2_000);
+		},
+		_ => panic!("unexpected consensus state"),
+	}
+}
+
+#[test]
+fn test_update_wasm_client_rejected_by_contract() {
+	let code = contract(|index| match index {
+		0 => Err("invalid header".to_string()),
+		index => light_client(index),
+	});
+	let (mut ctx, client_id) = create_client(code);
+
+	assert!(ctx.update_client(&client_id, header()).is_err());
+	assert_eq!(
+		ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id).data,
+		b"client-state-1".to_vec()
+	);
+}
+
+#[test]
+fn test_wasm_misbehaviour_freezes_client() {
+	let code = contract(|index| match index {
+		1 => Ok(Response::Misbehaviour(true)),
+		index => light_client(index),
+	});
+	let (mut ctx, client_id) = create_client(code);
+
+	ctx.update_client(&client_id, header()).unwrap();
+
+	let client_state = ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id);
+	assert_eq!(client_state.data, b"client-state-frozen".to_vec());
+	assert_eq!(client_state.frozen_height(), Some(Height::new(0, 1)));
+	assert!(ctx.update_client(&client_id, header()).is_err());
+}
+
+#[test]
+fn test_verify_wasm_connection_state() {
+	let verify = |code: Vec<u8>, height: Height| {
+		let (ctx, client_id) = create_client(code);
+		let client_state = ctx.client_state_as::<ClientState<HostFunctionsManager>>(&client_id);
+		let consensus_state = match ctx.consensus_state(&client_id, Height::new(0, 1)).unwrap() {
+			AnyConsensusState::Wasm(consensus_state) => consensus_state,
+			_ => panic!("unexpected consensus state"),
+		};
+		WasmClient::<HostFunctionsManager>::default().verify_connection_state(
+			&ctx,
+			&client_id,
+			&client_state,
+			height,
+			&CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap(),
+			&CommitmentProofBytes::try_from(b"proof".to_vec()).unwrap(),
+			consensus_state.root(),
+			&ConnectionId::new(0),
+			&ConnectionEnd::default(),
+		)
+	};
+
+	verify(contract(light_client), Height::new(0, 1)).unwrap();
+
+	// proofs can't be verified above the latest height of the client
+	assert!(verify(contract(light_client), Height::new(0, 2)).is_err());
+
+	let code = contract(|index| match index {
+		4 => Err("invalid proof".to_string()),
+		index => light_client(index),
+	});
+	assert!(verify(code, Height::new(0, 1)).is_err());
+}
+
+#[test]
+fn test_wasm_contract_is_metered() {
+	let code = wat::parse_str(
+		r#"(module
+	(memory (export "memory") 1)
+	(func (export "alloc") (param i32) (result i32) i32.const 0)
+	(func (export "call") (param i32 i32) (result i64)
+		(loop $spin (br $spin))
+		i64.const 0)
+)"#,
+	)
+	.unwrap();
+	let code_hash = store_code(code);
+	let request =
+		Request::UpdateStateOnMisbehaviour { client_state: vec![], client_message: vec![] };
+
+	match execute::<HostFunctionsManager>(&code_hash, request) {
+		Err(Error::Wasm(_)) => {},
+		res => panic!("expected the contract to run out of fuel, got {res:?}"),
+	}
+}
+
+#[test]
+fn test_wasm_contract_is_sandboxed() {
+	let code = wat::parse_str(
+		r#"(module
+	(import "env" "storage_read" (func (param i32 i32) (result i64)))
+	(memory (export "memory") 1)
+	(func (export "alloc") (param i32) (result i32) i32.const 0)
+	(func (export "call") (param i32 i32) (result i64) i64.const 0)
+)"#,
+	)
+	.unwrap();
+	assert!(validate::<HostFunctionsManager>(&code).is_err());
+
+	// contracts must implement the whole interface
+	let code = wat::parse_str(r#"(module (memory (export "memory") 1))"#).unwrap();
+	assert!(validate::<HostFunctionsManager>(&code).is_err());
+	assert!(validate::<HostFunctionsManager>(&contract(light_client)).is_ok());
+}
+
+/// Packs the pointer and length of a contract's response.
+fn packed(ptr: u64, len: u64) -> u64 {
+	(ptr << 32) | len
+}
+
+#[test]
+fn test_wasm_contract_calls_hash_host_function() {
+	// responds with `Frozen { client_state: sha2_256(request), frozen_height: 0-0 }`
+	let code = wat::parse_str(format!(
+		r#"(module
+	(import "env" "sha2_256" (func $sha2_256 (param i32 i32 i32)))
+	(memory (export "memory") 1)
+	(data (i32.const 61) "\00\03\80")
+	(func (export "alloc") (param i32) (result i32) i32.const 1024)
+	(func (export "call") (param $ptr i32) (param $len i32) (result i64)
+		(call $sha2_256 (local.get $ptr) (local.get $len) (i32.const 64))
+		i64.const {})
+)"#,
+		packed(61, 3 + 32 + 16)
+	))
+	.unwrap();
+	let code_hash = store_code(code);
+	let request =
+		Request::UpdateStateOnMisbehaviour { client_state: vec![1], client_message: vec![2] };
+
+	let response = execute::<HostFunctionsManager>(&code_hash, request.clone()).unwrap();
+	assert_eq!(
+		response,
+		Response::Frozen {
+			client_state: sha2_256(&request.encode()).to_vec(),
+			frozen_height: height(0)
+		}
+	);
+}
+
+/// A contract which responds with `Misbehaviour(valid)`, whether `signature` of `msg` by
+/// `public_key` is a valid ed25519 signature.
+fn verifying_contract(signature: &[u8], public_key: &[u8], msg: &[u8]) -> Vec<u8> {
+	wat::parse_str(format!(
+		r#"(module
+	(import "env" "ed25519_verify" (func $verify (param i32 i32 i32 i32 i32 i32) (result i32)))
+	(memory (export "memory") 1)
+	(data (i32.const 0) "{}")
+	(data (i32.const 64) "{}")
+	(data (i32.const 96) "{}")
+	(data (i32.const 200) "\00\01")
+	(func (export "alloc") (param i32) (result i32) i32.const 1024)
+	(func (export "call") (param i32 i32) (result i64)
+		(i32.store8
+			(i32.const 202)
+			(call $verify
+				(i32.const 0) (i32.const 64)
+				(i32.const 96) (i32.const {})
+				(i32.const 64) (i32.const 32)))
+		i64.const {})
+)"#,
+		escape(signature),
+		escape(public_key),
+		escape(msg),
+		msg.len(),
+		packed(200, 3),
+	))
+	.unwrap()
+}
+
+#[test]
+fn test_wasm_contract_calls_signature_host_function() {
+	let pair = ed25519::Pair::from_seed(&[1; 32]);
+	let msg = b"header";
+	let signature = pair.sign(msg);
+	let request =
+		Request::UpdateStateOnMisbehaviour { client_state: vec![], client_message: vec![] };
+
+	let valid = store_code(verifying_contract(&signature.0, &pair.public().0, msg));
+	assert_eq!(
+		execute::<HostFunctionsManager>(&valid, request.clone()).unwrap(),
+		Response::Misbehaviour(true)
+	);
+	let forged = store_code(verifying_contract(&signature.0, &pair.public().0, b"forged"));
+	assert_eq!(
+		execute::<HostFunctionsManager>(&forged, request).unwrap(),
+		Response::Misbehaviour(false)
+	);
+}
+
+#[test]
+fn test_wasm_host_functions_are_metered() {
+	// hashes `len` bytes once and responds with `Ok`, the instructions alone cost little fuel
+	let hashing_contract = |len: u32| {
+		wat::parse_str(format!(
+			r#"(module
+	(import "env" "sha2_256" (func $sha2_256 (param i32 i32 i32)))
+	(memory (export "memory") 16)
+	(func (export "alloc") (param i32) (result i32) i32.const 1024)
+	(func (export "call") (param i32 i32) (result i64)
+		(call $sha2_256 (i32.const 0) (i32.const {len}) (i32.const 64))
+		i64.const {})
+)"#,
+			packed(0, 2)
+		))
+		.unwrap()
+	};
+	let request =
+		Request::UpdateStateOnMisbehaviour { client_state: vec![], client_message: vec![] };
+
+	let cheap = store_code(hashing_contract(1_000));
+	assert_eq!(execute::<HostFunctionsManager>(&cheap, request.clone()).unwrap(), Response::Ok);
+	// the fuel charged for hashing a megabyte exceeds the fuel limit of the mock host
+	let expensive = store_code(hashing_contract(1 << 20));
+	match execute::<HostFunctionsManager>(&expensive, request) {
+		Err(Error::Wasm(_)) => {},
+		res => panic!("expected the contract to run out of fuel, got {res:?}"),
+	}
+}
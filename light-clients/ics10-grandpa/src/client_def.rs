@@ -38,13 +38,15 @@ use ibc::{
 			channel::ChannelEnd,
 			commitment::{AcknowledgementCommitment, PacketCommitment},
 			packet::Sequence,
+			upgrade::{ErrorReceipt, Upgrade},
 		},
 		ics23_commitment::commitment::{CommitmentPrefix, CommitmentProofBytes, CommitmentRoot},
 		ics24_host::{
 			identifier::{ChannelId, ClientId, ConnectionId, PortId},
 			path::{
-				AcksPath, ChannelEndsPath, ClientConsensusStatePath, ClientStatePath,
-				CommitmentsPath, ConnectionsPath, ReceiptsPath, SeqRecvsPath,
+				AcksPath, ChannelEndsPath, ChannelUpgradeErrorsPath, ChannelUpgradesPath,
+				ClientConsensusStatePath, ClientStatePath, CommitmentsPath, ConnectionsPath,
+				ReceiptsPath, SeqRecvsPath,
 			},
 		},
 		ics26_routing::context::ReaderContext,
@@ -59,8 +61,8 @@ use sp_runtime::traits::Header;
 use sp_trie::StorageProof;
 use tendermint_proto::Protobuf;
 
-const CLIENT_STATE_UPGRADE_PATH: &[u8] = b"client-state-upgrade-path";
-const CONSENSUS_STATE_UPGRADE_PATH: &[u8] = b"consensus-state-upgrade-path";
+pub(crate) const CLIENT_STATE_UPGRADE_PATH: &[u8] = b"client-state-upgrade-path";
+pub(crate) const CONSENSUS_STATE_UPGRADE_PATH: &[u8] = b"consensus-state-upgrade-path";
 
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct GrandpaClient<T>(PhantomData<T>);
@@ -400,7 +402,7 @@ where
 				StorageProof::new(nodes)
 			};
 
-			let encoded = Ctx::AnyConsensusState::wrap(upgrade_client_state)
+			let encoded = Ctx::AnyConsensusState::wrap(upgrade_consensus_state)
 				.expect("AnyConsensusState is type-checked; qed")
 				.encode_to_vec();
 
@@ -412,10 +414,10 @@ where
 			.map_err(|err| Error::Custom(format!("{err}")))?
 			.remove(CONSENSUS_STATE_UPGRADE_PATH)
 			.flatten()
-			.ok_or_else(|| Error::Custom(format!("Invalid proof for client state upgrade")))?;
+			.ok_or_else(|| Error::Custom(format!("Invalid proof for consensus state upgrade")))?;
 
 			if value != encoded {
-				Err(Error::Custom(format!("Invalid proof for client state upgrade")))?
+				Err(Error::Custom(format!("Invalid proof for consensus state upgrade")))?
 			}
 		}
 
@@ -493,6 +495,48 @@ where
 		Ok(())
 	}
 
+	fn verify_channel_upgrade<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_upgrade: &Upgrade,
+	) -> Result<(), Ics02Error> {
+		client_state.verify_height(height)?;
+		let path = ChannelUpgradesPath(port_id.clone(), *channel_id);
+		let value = expected_upgrade.encode_vec();
+		verify_membership::<H::BlakeTwo256, _>(prefix, proof, root, path, value)
+			.map_err(Error::Anyhow)?;
+		Ok(())
+	}
+
+	fn verify_channel_upgrade_error<Ctx: ReaderContext>(
+		&self,
+		_ctx: &Ctx,
+		_client_id: &ClientId,
+		client_state: &Self::ClientState,
+		height: Height,
+		prefix: &CommitmentPrefix,
+		proof: &CommitmentProofBytes,
+		root: &CommitmentRoot,
+		port_id: &PortId,
+		channel_id: &ChannelId,
+		expected_error_receipt: &ErrorReceipt,
+	) -> Result<(), Ics02Error> {
+		client_state.verify_height(height)?;
+		let path = ChannelUpgradeErrorsPath(port_id.clone(), *channel_id);
+		let value = expected_error_receipt.encode_vec();
+		verify_membership::<H::BlakeTwo256, _>(prefix, proof, root, path, value)
+			.map_err(Error::Anyhow)?;
+		Ok(())
+	}
+
 	fn verify_client_full_state<Ctx: ReaderContext>(
 		&self,
 		_ctx: &Ctx,
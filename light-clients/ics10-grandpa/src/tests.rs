@@ -14,6 +14,7 @@
 // limitations under the License.
 
 use crate::{
+	client_def::{GrandpaClient, CLIENT_STATE_UPGRADE_PATH, CONSENSUS_STATE_UPGRADE_PATH},
 	client_message::{ClientMessage, Header, RelayChainHeader},
 	client_state::ClientState,
 	consensus_state::ConsensusState,
@@ -24,7 +25,7 @@ use crate::{
 use beefy_prover::helpers::{
 	fetch_timestamp_extrinsic_with_proof, unsafe_arc_cast, TimeStampExtWithProof,
 };
-use codec::Decode;
+use codec::{Decode, Encode};
 use finality_grandpa_rpc::GrandpaApiClient;
 use futures::stream::StreamExt;
 use grandpa_client_primitives::{
@@ -34,6 +35,8 @@ use grandpa_prover::{polkadot, GrandpaProver, JustificationNotification};
 use ibc::{
 	core::{
 		ics02_client::{
+			client_consensus::ConsensusState as _,
+			client_def::{ClientDef, ConsensusUpdateResult},
 			client_state::ClientState as _,
 			context::{ClientKeeper, ClientReader},
 			handler::{dispatch, ClientResult::Update},
@@ -50,6 +53,9 @@ use ibc::{
 	Height,
 };
 use primitive_types::H256;
+use sp_runtime::traits::BlakeTwo256;
+use sp_state_machine::{prove_read_on_trie_backend, TrieBackend};
+use sp_trie::{LayoutV0, MemoryDB, TrieDBMut, TrieMut};
 use std::{mem::size_of_val, time::Duration};
 use subxt::{ext::sp_core::hexdisplay::AsBytesRef, PolkadotConfig};
 
@@ -294,3 +300,72 @@ async fn test_continuous_update_of_grandpa_client() {
 		}
 	}
 }
+
+#[test]
+fn upgrade_verifies_the_upgraded_consensus_state() {
+	let client_id = ClientId::new(&ClientState::<HostFunctionsManager>::client_type(), 0).unwrap();
+	let mut ctx = MockContext::<MockClientTypes>::new(
+		ChainId::new("mockgaiaA".to_string(), 1),
+		MockHostType::Mock,
+		5,
+		Height::new(1, 11),
+	);
+	let timestamp = tendermint::time::Time::from_unix_timestamp(1, 0).unwrap();
+	let client_state = ClientState::<HostFunctionsManager> {
+		para_id: 2000,
+		latest_para_height: 10,
+		..Default::default()
+	};
+	let upgraded_client_state = ClientState { latest_para_height: 20, ..client_state.clone() };
+	let upgraded_consensus_state = ConsensusState::new(vec![1; 32], timestamp);
+
+	// the upgraded states scheduled by the counterparty, proven against the consensus state the
+	// client is at
+	let mut db = MemoryDB::<BlakeTwo256>::default();
+	let mut root = Default::default();
+	{
+		let mut trie = TrieDBMut::<LayoutV0<BlakeTwo256>>::new(&mut db, &mut root);
+		trie.insert(
+			CLIENT_STATE_UPGRADE_PATH,
+			&AnyClientState::Grandpa(upgraded_client_state.clone()).encode_to_vec(),
+		)
+		.unwrap();
+		trie.insert(
+			CONSENSUS_STATE_UPGRADE_PATH,
+			&AnyConsensusState::Grandpa(upgraded_consensus_state.clone()).encode_to_vec(),
+		)
+		.unwrap();
+	}
+	let prove = |key: &[u8]| {
+		prove_read_on_trie_backend(&TrieBackend::new(db.clone(), root), &[key])
+			.unwrap()
+			.into_nodes()
+			.into_iter()
+			.collect::<Vec<_>>()
+			.encode()
+	};
+	ctx.store_consensus_state(
+		client_id.clone(),
+		Height::new(2000, 10),
+		AnyConsensusState::Grandpa(ConsensusState::new(root.as_bytes().to_vec(), timestamp)),
+	)
+	.unwrap();
+
+	let (new_client_state, consensus_update) = GrandpaClient::<HostFunctionsManager>::default()
+		.verify_upgrade_and_update_state(
+			&ctx,
+			client_id,
+			&client_state,
+			&upgraded_client_state,
+			&upgraded_consensus_state,
+			prove(CLIENT_STATE_UPGRADE_PATH),
+			prove(CONSENSUS_STATE_UPGRADE_PATH),
+		)
+		.unwrap();
+	assert_eq!(new_client_state, upgraded_client_state);
+	assert!(matches!(
+		consensus_update,
+		ConsensusUpdateResult::Single(AnyConsensusState::Grandpa(consensus_state))
+			if consensus_state == upgraded_consensus_state
+	));
+}
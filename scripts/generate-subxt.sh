@@ -9,5 +9,5 @@ then
   echo "Generated subxt types are up to date"
 else
   echo "Subxt types are outdated, please generate subxt types for the new runtime."
-#  exit 1
+  exit 1
 fi
\ No newline at end of file
@@ -693,6 +693,7 @@ impl pallet_ibc::Config for Runtime {
 	type AdminOrigin = EnsureRoot<AccountId>;
 	type SentryOrigin = EnsureRoot<AccountId>;
 	type SpamProtectionDeposit = SpamProtectionDeposit;
+	type Call = Call;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -891,6 +892,14 @@ impl_runtime_apis! {
 			Ibc::consensus_state(client_id, revision_number, revision_height, latest_cs).ok()
 		}
 
+		fn upgraded_client_state() -> Option<ibc_primitives::QueryClientStateResponse> {
+			Ibc::upgraded_client_state()
+		}
+
+		fn upgraded_consensus_state() -> Option<ibc_primitives::QueryConsensusStateResponse> {
+			Ibc::upgraded_consensus_state()
+		}
+
 		fn clients() -> Option<Vec<(Vec<u8>, Vec<u8>)>> {
 			Some(Ibc::clients())
 		}
@@ -915,6 +924,14 @@ impl_runtime_apis! {
 			Ibc::channel(channel_id, port_id).ok()
 		}
 
+		fn channel_upgrade(channel_id: Vec<u8>, port_id: Vec<u8>) -> Option<ibc_primitives::QueryChannelUpgradeResponse> {
+			Ibc::channel_upgrade(channel_id, port_id).ok()
+		}
+
+		fn channel_upgrade_error(channel_id: Vec<u8>, port_id: Vec<u8>) -> Option<ibc_primitives::QueryChannelUpgradeErrorResponse> {
+			Ibc::channel_upgrade_error(channel_id, port_id).ok()
+		}
+
 		fn channel_client(channel_id: Vec<u8>, port_id: Vec<u8>) -> Option<ibc_primitives::IdentifiedClientState> {
 			Ibc::channel_client(channel_id, port_id).ok()
 		}
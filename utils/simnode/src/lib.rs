@@ -71,25 +71,32 @@ impl ChainInfo for ParachainRuntimeChainInfo {
 	fn signed_extras(
 		from: <Self::Runtime as frame_system::pallet::Config>::AccountId,
 	) -> Self::SignedExtras {
-		let nonce = frame_system::Pallet::<Self::Runtime>::account_nonce(from);
-		(
-			frame_system::CheckNonZeroSender::<Self::Runtime>::new(),
-			frame_system::CheckSpecVersion::<Self::Runtime>::new(),
-			frame_system::CheckTxVersion::<Self::Runtime>::new(),
-			frame_system::CheckGenesis::<Self::Runtime>::new(),
-			frame_system::CheckEra::<Self::Runtime>::from(Era::Immortal),
-			frame_system::CheckNonce::<Self::Runtime>::from(nonce),
-			frame_system::CheckWeight::<Self::Runtime>::new(),
-			pallet_transaction_payment::ChargeTransactionPayment::<Self::Runtime>::from(0),
-		)
+		signed_extras(frame_system::Pallet::<Self::Runtime>::account_nonce(from))
 	}
 }
 
+/// Signed extensions of an extrinsic signed at the given nonce.
+fn signed_extras(nonce: parachain_runtime::Index) -> parachain_runtime::SignedExtra {
+	type Runtime = parachain_runtime::Runtime;
+	(
+		frame_system::CheckNonZeroSender::<Runtime>::new(),
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+	)
+}
+
 #[cfg(test)]
 mod tests {
-	use crate::ParachainRuntimeChainInfo;
+	use crate::{signed_extras, ParachainRuntimeChainInfo};
 	use frame_benchmarking::frame_support::codec::Encode;
+	use futures::future::join_all;
 	use grandpa_client_primitives::{justification::GrandpaJustification, Commit, FinalityProof};
+	use hyperspace_parachain::nonce::NonceTracker;
 	use ibc::{
 		core::{
 			ics02_client::{
@@ -108,7 +115,10 @@ mod tests {
 		light_clients::{AnyClient, AnyClientMessage, AnyClientState, AnyConsensusState},
 		Any,
 	};
-	use sp_runtime::{testing::H256, SaturatedConversion};
+	use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+	use sp_runtime::{
+		generic::BlockId, testing::H256, MultiAddress, MultiSignature, SaturatedConversion,
+	};
 	use std::str::FromStr;
 	use substrate_simnode::ChainInfo;
 
@@ -228,4 +238,61 @@ mod tests {
 		})
 		.unwrap();
 	}
+
+	#[test]
+	fn concurrent_submissions_get_distinct_nonces() {
+		type Runtime = <ParachainRuntimeChainInfo as ChainInfo>::Runtime;
+		const EXTRINSICS: u8 = 10;
+
+		substrate_simnode::parachain_node::<ParachainRuntimeChainInfo, _, _>(|node| async move {
+			let signer = node.with_state(None, sudo::Pallet::<Runtime>::key).unwrap();
+			let account_nonce = || {
+				node.with_state(None, || frame_system::Pallet::<Runtime>::account_nonce(&signer))
+			};
+			let initial_nonce = account_nonce();
+			let at = BlockId::Hash(node.client().chain_info().best_hash);
+			let pool = node.pool();
+			let tracker = NonceTracker::<parachain_runtime::Index>::default();
+
+			// every submission reserves its nonce concurrently, while the previous extrinsics
+			// are still in the pool and the on-chain nonce hasn't moved
+			let submissions = (0..EXTRINSICS).map(|i| {
+				let (pool, tracker, signer, at) = (&pool, &tracker, signer.clone(), &at);
+				async move {
+					let nonce = tracker
+						.next(|| async { Ok(account_nonce()) })
+						.await
+						.map_err(|err| format!("Failed to reserve a nonce: {err:?}"))?;
+					let call = parachain_runtime::Call::System(frame_system::Call::remark {
+						remark: vec![i],
+					});
+					// signatures aren't verified by the simnode, see
+					// `SignatureVerificationOverride`
+					let extrinsic = parachain_runtime::UncheckedExtrinsic::new_signed(
+						call,
+						MultiAddress::Id(signer),
+						MultiSignature::Sr25519(sp_core::sr25519::Signature::from_raw([0; 64])),
+						signed_extras(nonce),
+					);
+					pool.submit_one(at, TransactionSource::External, extrinsic.into())
+						.await
+						.map_err(|err| {
+							format!("Extrinsic with nonce {nonce} was rejected: {err:?}")
+						})?;
+					Ok::<_, String>(nonce)
+				}
+			});
+			let mut nonces =
+				join_all(submissions).await.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+			nonces.sort();
+			let expected = (0..EXTRINSICS as u32).map(|i| initial_nonce + i).collect::<Vec<_>>();
+			assert_eq!(nonces, expected);
+
+			// none of them clashed, they're all included
+			node.seal_blocks(1).await;
+			assert_eq!(account_nonce(), initial_nonce + EXTRINSICS as u32);
+			Ok(())
+		})
+		.unwrap();
+	}
 }
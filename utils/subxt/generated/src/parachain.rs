@@ -11329,6 +11329,18 @@ pub mod api {
 						kind: ::std::vec::Vec<::core::primitive::u8>,
 						module_id: ::std::vec::Vec<::core::primitive::u8>,
 					},
+					#[codec(index = 24)]
+					ChannelUpgrade {
+						kind: ::std::vec::Vec<::core::primitive::u8>,
+						revision_height: ::core::primitive::u64,
+						revision_number: ::core::primitive::u64,
+						port_id: ::std::vec::Vec<::core::primitive::u8>,
+						channel_id: ::std::vec::Vec<::core::primitive::u8>,
+						counterparty_port_id: ::std::vec::Vec<::core::primitive::u8>,
+						counterparty_channel_id:
+							::core::option::Option<::std::vec::Vec<::core::primitive::u8>>,
+						upgrade_sequence: ::core::primitive::u64,
+					},
 				}
 			}
 			pub mod pallet {
@@ -11584,6 +11596,7 @@ pub mod api {
 				pub to: runtime_types::pallet_ibc::MultiAddress<_0>,
 				pub source_channel: ::core::primitive::u64,
 				pub timeout: runtime_types::ibc_primitives::Timeout,
+				pub memo: ::core::option::Option<::std::vec::Vec<::core::primitive::u8>>,
 			}
 			#[derive(
 				:: subxt :: ext :: codec :: Decode, :: subxt :: ext :: codec :: Encode, Debug,